@@ -0,0 +1,616 @@
+//! What key presses mean, separated from carrying them out.
+//!
+//! [`key_action`] is the keymap: it turns a key event into an [`Action`]
+//! for the current mode. `App::apply` makes the state change and returns the
+//! [`Effect`]s that need the API clients or the filesystem, which `main`
+//! then runs.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+use crate::app::{App, AppMode, BulkRemoval, CacheKind, RightPanel};
+use crate::flight::FlightStatus;
+use crate::import::FlightQuery;
+
+/// Something the user asked for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    Quit,
+
+    // Typing into the input box, in any of the input modes
+    InsertChar(char),
+    Backspace,
+    ClearInput,
+    /// Enter in an input mode: search, set the alert, or import.
+    Submit,
+    /// Esc in an input mode.
+    CancelInput,
+    HistoryNext,
+    HistoryPrevious,
+    /// Tab in the command palette.
+    CompleteCommand,
+
+    // Browsing tracked flights
+    BeginInput,
+    BeginRetrack,
+    /// Open the input with a guess at the selected flight's return leg.
+    BeginReturnInput,
+    /// Tab while that guess is in the input: try the other one.
+    NextReturnGuess,
+    /// Search a flight that found nothing again from scratch.
+    RetrySearch,
+    RepeatLastSearch,
+    SelectPrevious,
+    SelectNext,
+    /// Remove the selected flight, or drop the landed restored ones.
+    Delete,
+    /// Ask before removing several flights at once.
+    RequestRemoval(BulkRemoval),
+    AnswerRemoval(bool),
+    /// Put back the flights removed last.
+    Undo,
+    CycleFilter,
+    /// Open the prompt that narrows the list by free text.
+    BeginListSearch,
+    ToggleArrived,
+    /// List every leg of the selected flight's trip.
+    StepIntoTrip,
+    /// Close the selected flight's trip down to its current leg.
+    StepOutOfTrip,
+    BeginAlertInput,
+    BeginImportInput,
+    /// Open the map tab, or go back to the flight's last tab.
+    ToggleMap,
+    /// Lock the details and map onto the selected flight, or let go.
+    ToggleFollow,
+    ToggleProfile,
+    /// Open the selected flight's raw payloads tab, or go back.
+    ToggleRawView,
+    /// Switch the right-hand panel to this tab.
+    ShowPanel(RightPanel),
+    /// Tab and Shift+Tab: the next tab along, or the one before.
+    CyclePanel {
+        backward: bool,
+    },
+    /// Scroll the active tab by this many lines.
+    ScrollPanel(i16),
+    ZoomMap {
+        zoom_in: bool,
+    },
+    ToggleTimeFormat,
+    /// Switch between the full layout and the one-line-per-flight ticker.
+    ToggleLayout,
+    ToggleRecurring,
+    /// Mute the selected flight's notifications for longer, or unmute it.
+    CycleMute,
+    ExportReport,
+    /// Refresh every flight, or search the held restored ones.
+    Refresh,
+    /// Open the `:` command palette.
+    BeginCommand,
+    /// Empty a provider's cache without asking, from the palette.
+    ClearCache(CacheKind),
+    /// Raise or lower the shortest refresh interval for this session.
+    SetMinRefresh(u64),
+    /// Choose the report's columns for this session, by id.
+    SetColumns(Vec<String>),
+    /// Group flights under a named trip, tracking any that aren't yet.
+    AddTrip {
+        name: String,
+        legs: Vec<FlightQuery>,
+    },
+    /// Ungroup the trip of this name.
+    RemoveTrip(String),
+
+    // Settings overlay
+    OpenSettings,
+    /// Move the settings cursor by this many rows.
+    MoveSettingsCursor(i16),
+    /// Cycle the setting under the cursor to its next value, or back.
+    ChangeSetting {
+        backward: bool,
+    },
+    /// Leave the overlay, saving what changed.
+    CloseSettings,
+
+    // Diagnostics overlay
+    ToggleDiagnostics,
+    CloseDiagnostics,
+    RequestCacheClear(CacheKind),
+    AnswerCacheClear(bool),
+
+    // First-run walkthrough
+    CreateStarterFiles,
+    FinishOnboarding {
+        try_suggestion: bool,
+    },
+}
+
+/// Follow-up work an action needs from outside `App`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Effect {
+    /// Send out the queued searches.
+    StartSearches,
+    /// Fetch positions for the flights at these indices.
+    StartRefresh(Vec<usize>),
+    /// Snapshot the providers into the diagnostics overlay.
+    OpenDiagnostics,
+    /// Empty a provider's cache, then update the overlay.
+    ClearCache(CacheKind),
+    /// Write the Markdown report and copy it to the clipboard.
+    ExportReport,
+}
+
+/// Lines PageUp and PageDown move a tab by.
+const PAGE: i16 = 10;
+
+/// The action bound to `key` in the app's current mode, if any.
+pub fn key_action(app: &App, key: KeyEvent) -> Option<Action> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    // Any key but y cancels, Ctrl+C included
+    if app.mode == AppMode::Viewing && app.pending_cache_clear.is_some() {
+        return Some(Action::AnswerCacheClear(key.code == KeyCode::Char('y')));
+    }
+    if app.mode == AppMode::Viewing && app.pending_removal.is_some() {
+        return Some(Action::AnswerRemoval(key.code == KeyCode::Char('y')));
+    }
+    if ctrl && key.code == KeyCode::Char('c') {
+        return Some(Action::Quit);
+    }
+    let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+
+    let action = match app.mode {
+        AppMode::Input
+        | AppMode::AlertInput
+        | AppMode::ImportInput
+        | AppMode::ListSearch
+        | AppMode::Command => match key.code {
+            KeyCode::Enter => Action::Submit,
+            KeyCode::Char('u') if ctrl && app.mode == AppMode::Input => Action::ClearInput,
+            // Flight numbers are upper case and alert rules lower case
+            KeyCode::Char(c) => Action::InsertChar(match app.mode {
+                AppMode::Input => c.to_ascii_uppercase(),
+                AppMode::AlertInput => c.to_ascii_lowercase(),
+                _ => c,
+            }),
+            KeyCode::Backspace => Action::Backspace,
+            KeyCode::Up if app.mode == AppMode::Input => Action::HistoryNext,
+            KeyCode::Down if app.mode == AppMode::Input => Action::HistoryPrevious,
+            KeyCode::Tab if app.mode == AppMode::Command => Action::CompleteCommand,
+            KeyCode::Tab if app.mode == AppMode::Input && app.return_prompt.is_some() => {
+                Action::NextReturnGuess
+            }
+            KeyCode::Esc => Action::CancelInput,
+            _ => return None,
+        },
+        AppMode::Settings => match key.code {
+            KeyCode::Up | KeyCode::Char('k') => Action::MoveSettingsCursor(-1),
+            KeyCode::Down | KeyCode::Char('j') => Action::MoveSettingsCursor(1),
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char(' ') | KeyCode::Char('l') => {
+                Action::ChangeSetting { backward: false }
+            }
+            KeyCode::Left | KeyCode::Char('h') => Action::ChangeSetting { backward: true },
+            KeyCode::Esc | KeyCode::Char(',') | KeyCode::Char('q') => Action::CloseSettings,
+            _ => return None,
+        },
+        AppMode::Onboarding => match key.code {
+            KeyCode::Char('q') => Action::Quit,
+            KeyCode::Char('c') => Action::CreateStarterFiles,
+            KeyCode::Enter => Action::FinishOnboarding {
+                try_suggestion: true,
+            },
+            KeyCode::Esc | KeyCode::Char('s') => Action::FinishOnboarding {
+                try_suggestion: false,
+            },
+            _ => return None,
+        },
+        AppMode::Viewing => match key.code {
+            KeyCode::Char('q') => Action::Quit,
+            KeyCode::Char('/') | KeyCode::Char('a') => Action::BeginInput,
+            // A flight that wasn't found has nothing to refresh or roll over
+            KeyCode::Enter | KeyCode::Char('R') if selected_not_found(app) => Action::RetrySearch,
+            KeyCode::Enter => Action::BeginRetrack,
+            KeyCode::Char('.') => Action::RepeatLastSearch,
+            KeyCode::Char('o') => Action::BeginReturnInput,
+            KeyCode::Up | KeyCode::Char('k') => Action::SelectPrevious,
+            KeyCode::Down | KeyCode::Char('j') => Action::SelectNext,
+            // Only terminals that report Shift with Ctrl tell this from Ctrl+D
+            KeyCode::Char('d') | KeyCode::Char('D') if ctrl && shift => {
+                Action::RequestRemoval(BulkRemoval::All)
+            }
+            KeyCode::Char('d') if ctrl => Action::ToggleRawView,
+            // Keys for one tab only reach it while it is showing
+            KeyCode::PageDown if app.right_panel.scrolls() => Action::ScrollPanel(PAGE),
+            KeyCode::PageUp if app.right_panel.scrolls() => Action::ScrollPanel(-PAGE),
+            KeyCode::Char('+') | KeyCode::Char('=') if app.right_panel == RightPanel::Map => {
+                Action::ZoomMap { zoom_in: true }
+            }
+            KeyCode::Char('-') if app.right_panel == RightPanel::Map => {
+                Action::ZoomMap { zoom_in: false }
+            }
+            KeyCode::Char(c @ '1'..='5') => {
+                Action::ShowPanel(RightPanel::numbered(c.to_digit(10)?)?)
+            }
+            KeyCode::Tab => Action::CyclePanel { backward: false },
+            KeyCode::BackTab => Action::CyclePanel { backward: true },
+            KeyCode::Char('d') => Action::Delete,
+            KeyCode::Char('X') => Action::RequestRemoval(BulkRemoval::Finished),
+            KeyCode::Char('u') => Action::Undo,
+            KeyCode::Char('f') if ctrl => Action::BeginListSearch,
+            KeyCode::Char('f') => Action::CycleFilter,
+            KeyCode::Char('A') => Action::ToggleArrived,
+            KeyCode::Right | KeyCode::Char('l') => Action::StepIntoTrip,
+            KeyCode::Left | KeyCode::Char('h') => Action::StepOutOfTrip,
+            KeyCode::Char('w') => Action::BeginAlertInput,
+            KeyCode::Char('i') => Action::BeginImportInput,
+            KeyCode::Char('M') => Action::ToggleMap,
+            KeyCode::Char('F') => Action::ToggleFollow,
+            KeyCode::Char('P') => Action::ToggleProfile,
+            KeyCode::Char('t') if ctrl => Action::ToggleLayout,
+            KeyCode::Char('t') => Action::ToggleTimeFormat,
+            KeyCode::Char('R') => Action::ToggleRecurring,
+            KeyCode::Char('z') => Action::CycleMute,
+            KeyCode::Char('E') => Action::ExportReport,
+            KeyCode::Char('D') => Action::ToggleDiagnostics,
+            KeyCode::Esc => Action::CloseDiagnostics,
+            KeyCode::Char('p') => Action::RequestCacheClear(CacheKind::Position),
+            KeyCode::Char('s') => Action::RequestCacheClear(CacheKind::Schedule),
+            KeyCode::Char('r') => Action::Refresh,
+            KeyCode::Char(':') => Action::BeginCommand,
+            KeyCode::Char(',') => Action::OpenSettings,
+            _ => return None,
+        },
+    };
+    Some(action)
+}
+
+fn selected_not_found(app: &App) -> bool {
+    app.selected_flight()
+        .is_some_and(|flight| flight.status == FlightStatus::NotFound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::ReturnPrompt;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::from(code)
+    }
+
+    fn ctrl(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    fn in_mode(mode: AppMode) -> App {
+        let mut app = App::default();
+        app.mode = mode;
+        app
+    }
+
+    #[test]
+    fn test_input_modes() {
+        let input = in_mode(AppMode::Input);
+        assert_eq!(
+            key_action(&input, key(KeyCode::Char('u'))),
+            Some(Action::InsertChar('U'))
+        );
+        assert_eq!(key_action(&input, ctrl('u')), Some(Action::ClearInput));
+        assert_eq!(
+            key_action(&input, key(KeyCode::Up)),
+            Some(Action::HistoryNext)
+        );
+        assert_eq!(
+            key_action(&input, key(KeyCode::Down)),
+            Some(Action::HistoryPrevious)
+        );
+        assert_eq!(
+            key_action(&input, key(KeyCode::Enter)),
+            Some(Action::Submit)
+        );
+        assert_eq!(
+            key_action(&input, key(KeyCode::Esc)),
+            Some(Action::CancelInput)
+        );
+        // q is a letter while typing
+        assert_eq!(
+            key_action(&input, key(KeyCode::Char('q'))),
+            Some(Action::InsertChar('Q'))
+        );
+        assert_eq!(key_action(&input, key(KeyCode::Tab)), None);
+        let mut input = input;
+        input.return_prompt = Some(ReturnPrompt {
+            of: "LH400".to_string(),
+            from: "JFK".to_string(),
+            to: "FRA".to_string(),
+            guesses: vec!["LH401".to_string(), "LH399".to_string()],
+        });
+        assert_eq!(
+            key_action(&input, key(KeyCode::Tab)),
+            Some(Action::NextReturnGuess)
+        );
+
+        let alert = in_mode(AppMode::AlertInput);
+        assert_eq!(
+            key_action(&alert, key(KeyCode::Char('L'))),
+            Some(Action::InsertChar('l'))
+        );
+        assert_eq!(key_action(&alert, key(KeyCode::Up)), None);
+        // Ctrl+U only clears the flight number input, as before
+        assert_eq!(key_action(&alert, ctrl('u')), Some(Action::InsertChar('u')));
+
+        let import = in_mode(AppMode::ImportInput);
+        assert_eq!(
+            key_action(&import, key(KeyCode::Char('F'))),
+            Some(Action::InsertChar('F'))
+        );
+        assert_eq!(
+            key_action(&import, key(KeyCode::Backspace)),
+            Some(Action::Backspace)
+        );
+
+        let search = in_mode(AppMode::ListSearch);
+        assert_eq!(
+            key_action(&search, key(KeyCode::Char('q'))),
+            Some(Action::InsertChar('q'))
+        );
+        assert_eq!(
+            key_action(&search, key(KeyCode::Enter)),
+            Some(Action::Submit)
+        );
+        assert_eq!(
+            key_action(&search, key(KeyCode::Esc)),
+            Some(Action::CancelInput)
+        );
+        assert_eq!(key_action(&search, key(KeyCode::Up)), None);
+
+        let command = in_mode(AppMode::Command);
+        assert_eq!(
+            key_action(&command, key(KeyCode::Tab)),
+            Some(Action::CompleteCommand)
+        );
+        assert_eq!(
+            key_action(&command, key(KeyCode::Char('Q'))),
+            Some(Action::InsertChar('Q'))
+        );
+        assert_eq!(key_action(&search, key(KeyCode::Tab)), None);
+    }
+
+    #[test]
+    fn test_ctrl_c_quits_everywhere() {
+        for mode in [
+            AppMode::Input,
+            AppMode::Viewing,
+            AppMode::AlertInput,
+            AppMode::ImportInput,
+            AppMode::Onboarding,
+            AppMode::ListSearch,
+            AppMode::Command,
+            AppMode::Settings,
+        ] {
+            assert_eq!(
+                key_action(&in_mode(mode), ctrl('c')),
+                Some(Action::Quit),
+                "{mode:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_viewing_keys() {
+        let app = in_mode(AppMode::Viewing);
+        let table = [
+            (KeyCode::Char('q'), Action::Quit),
+            (KeyCode::Char('/'), Action::BeginInput),
+            (KeyCode::Char('a'), Action::BeginInput),
+            (KeyCode::Enter, Action::BeginRetrack),
+            (KeyCode::Char('.'), Action::RepeatLastSearch),
+            (KeyCode::Char('o'), Action::BeginReturnInput),
+            (KeyCode::Char('k'), Action::SelectPrevious),
+            (KeyCode::Down, Action::SelectNext),
+            (KeyCode::Char('d'), Action::Delete),
+            (KeyCode::Char('f'), Action::CycleFilter),
+            (KeyCode::Char('A'), Action::ToggleArrived),
+            (KeyCode::Right, Action::StepIntoTrip),
+            (KeyCode::Char('h'), Action::StepOutOfTrip),
+            (KeyCode::Char('P'), Action::ToggleProfile),
+            (KeyCode::Char('F'), Action::ToggleFollow),
+            (KeyCode::Char('E'), Action::ExportReport),
+            (KeyCode::Char('D'), Action::ToggleDiagnostics),
+            (
+                KeyCode::Char('s'),
+                Action::RequestCacheClear(CacheKind::Schedule),
+            ),
+            (KeyCode::Char('r'), Action::Refresh),
+            (KeyCode::Char(':'), Action::BeginCommand),
+            (KeyCode::Char(','), Action::OpenSettings),
+            (
+                KeyCode::Char('X'),
+                Action::RequestRemoval(BulkRemoval::Finished),
+            ),
+            (KeyCode::Char('u'), Action::Undo),
+        ];
+        for (code, action) in table {
+            assert_eq!(key_action(&app, key(code)), Some(action), "{code:?}");
+        }
+        assert_eq!(
+            key_action(&app, key(KeyCode::Char('z'))),
+            Some(Action::CycleMute)
+        );
+        assert_eq!(key_action(&app, key(KeyCode::Char('Z'))), None);
+        assert_eq!(key_action(&app, ctrl('f')), Some(Action::BeginListSearch));
+        assert_eq!(key_action(&app, ctrl('d')), Some(Action::ToggleRawView));
+        assert_eq!(key_action(&app, ctrl('t')), Some(Action::ToggleLayout));
+        assert_eq!(
+            key_action(&app, key(KeyCode::Char('t'))),
+            Some(Action::ToggleTimeFormat)
+        );
+        assert_eq!(key_action(&app, key(KeyCode::PageDown)), None);
+
+        // On a flight that wasn't found, Enter and R search it again
+        let mut app = app;
+        app.add_flight("UA9000".to_string(), None, None);
+        assert_eq!(
+            key_action(&app, key(KeyCode::Enter)),
+            Some(Action::RetrySearch)
+        );
+        assert_eq!(
+            key_action(&app, key(KeyCode::Char('R'))),
+            Some(Action::RetrySearch)
+        );
+        app.tracked_flights[0].status = FlightStatus::EnRoute;
+        assert_eq!(
+            key_action(&app, key(KeyCode::Enter)),
+            Some(Action::BeginRetrack)
+        );
+        assert_eq!(
+            key_action(&app, key(KeyCode::Char('R'))),
+            Some(Action::ToggleRecurring)
+        );
+
+        let mut app = app;
+        app.pending_cache_clear = Some(CacheKind::Position);
+        assert_eq!(
+            key_action(&app, key(KeyCode::Char('y'))),
+            Some(Action::AnswerCacheClear(true))
+        );
+        assert_eq!(
+            key_action(&app, key(KeyCode::Char('q'))),
+            Some(Action::AnswerCacheClear(false))
+        );
+        assert_eq!(
+            key_action(&app, ctrl('c')),
+            Some(Action::AnswerCacheClear(false))
+        );
+    }
+
+    #[test]
+    fn test_panel_keys() {
+        let mut app = in_mode(AppMode::Viewing);
+        let numbered = [
+            ('1', RightPanel::Details),
+            ('2', RightPanel::Map),
+            ('3', RightPanel::Log),
+            ('4', RightPanel::Weather),
+            ('5', RightPanel::Raw),
+        ];
+        for (c, panel) in numbered {
+            assert_eq!(
+                key_action(&app, key(KeyCode::Char(c))),
+                Some(Action::ShowPanel(panel))
+            );
+        }
+        assert_eq!(key_action(&app, key(KeyCode::Char('6'))), None);
+        assert_eq!(key_action(&app, key(KeyCode::Char('0'))), None);
+        assert_eq!(
+            key_action(&app, key(KeyCode::Tab)),
+            Some(Action::CyclePanel { backward: false })
+        );
+        assert_eq!(
+            key_action(&app, key(KeyCode::BackTab)),
+            Some(Action::CyclePanel { backward: true })
+        );
+
+        // Paging and zoom reach only the tabs they are for
+        for panel in RightPanel::ALL {
+            app.right_panel = panel;
+            let (scroll, zoom) = match panel {
+                RightPanel::Log | RightPanel::Raw => (true, false),
+                RightPanel::Map => (false, true),
+                RightPanel::Details | RightPanel::Weather => (false, false),
+            };
+            let page_down = key_action(&app, key(KeyCode::PageDown));
+            let page_up = key_action(&app, key(KeyCode::PageUp));
+            assert_eq!(
+                page_down,
+                scroll.then_some(Action::ScrollPanel(10)),
+                "{panel:?}"
+            );
+            assert_eq!(
+                page_up,
+                scroll.then_some(Action::ScrollPanel(-10)),
+                "{panel:?}"
+            );
+            for (c, zoom_in) in [('+', true), ('=', true), ('-', false)] {
+                let action = key_action(&app, key(KeyCode::Char(c)));
+                assert_eq!(
+                    action,
+                    zoom.then_some(Action::ZoomMap { zoom_in }),
+                    "{panel:?} {c}"
+                );
+            }
+        }
+
+        // Typing a flight number still takes digits
+        let input = in_mode(AppMode::Input);
+        assert_eq!(
+            key_action(&input, key(KeyCode::Char('2'))),
+            Some(Action::InsertChar('2'))
+        );
+    }
+
+    #[test]
+    fn test_bulk_removal_keys() {
+        let mut app = in_mode(AppMode::Viewing);
+        let ctrl_shift = KeyEvent::new(
+            KeyCode::Char('D'),
+            KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        );
+        assert_eq!(
+            key_action(&app, ctrl_shift),
+            Some(Action::RequestRemoval(BulkRemoval::All))
+        );
+
+        // Any key but y cancels
+        app.pending_removal = Some(BulkRemoval::All);
+        assert_eq!(
+            key_action(&app, key(KeyCode::Char('y'))),
+            Some(Action::AnswerRemoval(true))
+        );
+        assert_eq!(
+            key_action(&app, key(KeyCode::Char('d'))),
+            Some(Action::AnswerRemoval(false))
+        );
+        assert_eq!(
+            key_action(&app, ctrl('c')),
+            Some(Action::AnswerRemoval(false))
+        );
+    }
+
+    #[test]
+    fn test_settings_keys() {
+        let app = in_mode(AppMode::Settings);
+        let forward = Action::ChangeSetting { backward: false };
+        let table = [
+            (KeyCode::Up, Action::MoveSettingsCursor(-1)),
+            (KeyCode::Char('j'), Action::MoveSettingsCursor(1)),
+            (KeyCode::Enter, forward.clone()),
+            (KeyCode::Right, forward.clone()),
+            (KeyCode::Char(' '), forward),
+            (KeyCode::Left, Action::ChangeSetting { backward: true }),
+            (KeyCode::Esc, Action::CloseSettings),
+            (KeyCode::Char(','), Action::CloseSettings),
+            (KeyCode::Char('q'), Action::CloseSettings),
+        ];
+        for (code, action) in table {
+            assert_eq!(key_action(&app, key(code)), Some(action), "{code:?}");
+        }
+        assert_eq!(key_action(&app, key(KeyCode::Char('d'))), None);
+    }
+
+    #[test]
+    fn test_onboarding_keys() {
+        let app = in_mode(AppMode::Onboarding);
+        assert_eq!(
+            key_action(&app, key(KeyCode::Char('c'))),
+            Some(Action::CreateStarterFiles)
+        );
+        assert_eq!(
+            key_action(&app, key(KeyCode::Esc)),
+            Some(Action::FinishOnboarding {
+                try_suggestion: false
+            })
+        );
+        assert_eq!(key_action(&app, key(KeyCode::Char('x'))), None);
+    }
+}
@@ -0,0 +1,206 @@
+//! Friendly names for aircraft type codes.
+//!
+//! Providers report types as ICAO designators (`B789`) or IATA codes
+//! (`789`). The built-in table covers common airliners; entries in
+//! `aircraft_types.toml` in the data directory add to it or replace its
+//! names, one `CODE = "Name"` line per type.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::paths::data_dir;
+
+const TYPES_FILE: &str = "aircraft_types.toml";
+
+/// (ICAO designator, IATA code, name)
+const TYPES: &[(&str, &str, &str)] = &[
+    // Airbus
+    ("BCS1", "221", "Airbus A220-100"),
+    ("BCS3", "223", "Airbus A220-300"),
+    ("A318", "318", "Airbus A318"),
+    ("A319", "319", "Airbus A319"),
+    ("A320", "320", "Airbus A320"),
+    ("A321", "321", "Airbus A321"),
+    ("A19N", "31N", "Airbus A319neo"),
+    ("A20N", "32N", "Airbus A320neo"),
+    ("A21N", "32Q", "Airbus A321neo"),
+    ("A332", "332", "Airbus A330-200"),
+    ("A333", "333", "Airbus A330-300"),
+    ("A339", "339", "Airbus A330-900neo"),
+    ("A343", "343", "Airbus A340-300"),
+    ("A346", "346", "Airbus A340-600"),
+    ("A359", "359", "Airbus A350-900"),
+    ("A35K", "351", "Airbus A350-1000"),
+    ("A388", "388", "Airbus A380-800"),
+    // Boeing
+    ("B712", "717", "Boeing 717-200"),
+    ("B733", "733", "Boeing 737-300"),
+    ("B734", "734", "Boeing 737-400"),
+    ("B735", "735", "Boeing 737-500"),
+    ("B737", "73G", "Boeing 737-700"),
+    ("B738", "738", "Boeing 737-800"),
+    ("B739", "739", "Boeing 737-900"),
+    ("B38M", "7M8", "Boeing 737 MAX 8"),
+    ("B39M", "7M9", "Boeing 737 MAX 9"),
+    ("B744", "744", "Boeing 747-400"),
+    ("B748", "748", "Boeing 747-8"),
+    ("B752", "752", "Boeing 757-200"),
+    ("B753", "753", "Boeing 757-300"),
+    ("B762", "762", "Boeing 767-200"),
+    ("B763", "763", "Boeing 767-300"),
+    ("B764", "764", "Boeing 767-400"),
+    ("B772", "772", "Boeing 777-200"),
+    ("B77L", "77L", "Boeing 777-200LR"),
+    ("B773", "773", "Boeing 777-300"),
+    ("B77W", "77W", "Boeing 777-300ER"),
+    ("B788", "788", "Boeing 787-8 Dreamliner"),
+    ("B789", "789", "Boeing 787-9 Dreamliner"),
+    ("B78X", "781", "Boeing 787-10 Dreamliner"),
+    // Regional
+    ("E170", "E70", "Embraer 170"),
+    ("E75L", "E75", "Embraer 175"),
+    ("E190", "E90", "Embraer 190"),
+    ("E195", "E95", "Embraer 195"),
+    ("E290", "290", "Embraer E190-E2"),
+    ("E295", "295", "Embraer E195-E2"),
+    ("CRJ2", "CR2", "Bombardier CRJ200"),
+    ("CRJ7", "CR7", "Bombardier CRJ700"),
+    ("CRJ9", "CR9", "Bombardier CRJ900"),
+    ("AT45", "AT5", "ATR 42-500"),
+    ("AT72", "AT7", "ATR 72"),
+    ("DH8D", "DH4", "De Havilland Dash 8-400"),
+];
+
+/// Name of a type in the built-in table, by ICAO designator or IATA code.
+///
+/// ```
+/// use flight_tracker_tui::aircraft;
+///
+/// assert_eq!(aircraft::builtin_name("B789"), Some("Boeing 787-9 Dreamliner"));
+/// assert_eq!(aircraft::builtin_name("32n"), Some("Airbus A320neo"));
+/// assert_eq!(aircraft::builtin_name("ZZZZ"), None);
+/// ```
+pub fn builtin_name(code: &str) -> Option<&'static str> {
+    let code = code.trim().to_uppercase();
+    TYPES
+        .iter()
+        .find(|(icao, iata, _)| *icao == code || *iata == code)
+        .map(|(_, _, name)| *name)
+}
+
+/// The built-in type names, with the user's `aircraft_types.toml` on top.
+#[derive(Debug, Clone, Default)]
+pub struct AircraftTypes {
+    /// Names keyed by upper-case code.
+    overrides: HashMap<String, String>,
+}
+
+impl AircraftTypes {
+    /// Read overrides from TOML, a flat table of `CODE = "Name"` entries.
+    pub fn parse(contents: &str) -> Result<Self, toml::de::Error> {
+        let entries: HashMap<String, String> = toml::from_str(contents)?;
+        Ok(Self {
+            overrides: entries
+                .into_iter()
+                .map(|(code, name)| (code.trim().to_uppercase(), name))
+                .collect(),
+        })
+    }
+
+    /// Load the overrides file, or just the built-in table if it is missing
+    /// or invalid.
+    pub fn load() -> Self {
+        let Some(path) = Self::file_path() else {
+            return Self::default();
+        };
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        Self::parse(&contents).unwrap_or_else(|e| {
+            tracing::warn!(
+                path = %path.display(),
+                error = %e,
+                "Ignoring invalid aircraft types file"
+            );
+            Self::default()
+        })
+    }
+
+    pub fn file_path() -> Option<PathBuf> {
+        data_dir().map(|p| p.join(TYPES_FILE))
+    }
+
+    /// Name for a type code, preferring the user's entries.
+    pub fn name(&self, code: &str) -> Option<&str> {
+        let key = code.trim().to_uppercase();
+        self.overrides
+            .get(&key)
+            .map(String::as_str)
+            .or_else(|| builtin_name(&key))
+    }
+
+    /// "Boeing 787-9 Dreamliner (B789)", or just the code when the type is
+    /// unknown.
+    ///
+    /// ```
+    /// use flight_tracker_tui::aircraft::AircraftTypes;
+    ///
+    /// let types = AircraftTypes::default();
+    /// assert_eq!(types.describe("A35K"), "Airbus A350-1000 (A35K)");
+    /// assert_eq!(types.describe("C172"), "C172");
+    /// ```
+    pub fn describe(&self, code: &str) -> String {
+        match self.name(code) {
+            Some(name) => format!("{} ({})", name, code.trim()),
+            None => code.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_common_types() {
+        let types = AircraftTypes::default();
+        assert_eq!(types.name("B738"), Some("Boeing 737-800"));
+        assert_eq!(types.name("738"), Some("Boeing 737-800"));
+        assert_eq!(types.name(" a20n "), Some("Airbus A320neo"));
+        assert_eq!(types.name("77W"), Some("Boeing 777-300ER"));
+        assert_eq!(types.describe("E75L"), "Embraer 175 (E75L)");
+    }
+
+    #[test]
+    fn test_unknown_types() {
+        let types = AircraftTypes::default();
+        assert_eq!(types.name(""), None);
+        assert_eq!(types.name("B7899"), None);
+        assert_eq!(types.describe("GLF6"), "GLF6");
+    }
+
+    #[test]
+    fn test_overrides_add_and_replace() {
+        let types =
+            AircraftTypes::parse("B789 = \"Boeing 787-9\"\nglf6 = \"Gulfstream G650\"\n").unwrap();
+        assert_eq!(types.describe("B789"), "Boeing 787-9 (B789)");
+        // The IATA code for the same type still has the built-in name
+        assert_eq!(types.name("789"), Some("Boeing 787-9 Dreamliner"));
+        assert_eq!(types.describe("GLF6"), "Gulfstream G650 (GLF6)");
+        assert_eq!(types.name("B738"), Some("Boeing 737-800"));
+
+        assert!(AircraftTypes::parse("B789 = 789").is_err());
+    }
+
+    #[test]
+    fn test_codes_are_unique() {
+        let codes: Vec<&str> = TYPES
+            .iter()
+            .flat_map(|(icao, iata, _)| [*icao, *iata])
+            .collect();
+        for (i, code) in codes.iter().enumerate() {
+            assert!(!codes[i + 1..].contains(code), "duplicate {code}");
+        }
+    }
+}
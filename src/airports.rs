@@ -0,0 +1,376 @@
+//! Built-in coordinates for major airports.
+//!
+//! AviationStack only gives us airport codes, so distance-based features
+//! look up positions here. The table covers large hubs and the usual
+//! oceanic diversion fields; unknown codes simply yield `None`. A table
+//! downloaded with `--update-data` is searched first when there is one.
+//! [`nearest`] goes the other way, from a position to an airport, and
+//! [`timezone`] gives the zone its local times are in.
+
+use std::sync::OnceLock;
+
+use chrono_tz::Tz;
+
+use crate::geo;
+
+/// Location of an airport.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AirportLocation {
+    pub iata: &'static str,
+    pub icao: &'static str,
+    pub name: &'static str,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+const fn airport(
+    iata: &'static str,
+    icao: &'static str,
+    name: &'static str,
+    latitude: f64,
+    longitude: f64,
+) -> AirportLocation {
+    AirportLocation {
+        iata,
+        icao,
+        name,
+        latitude,
+        longitude,
+    }
+}
+
+/// Kilometres in a degree of latitude, everywhere.
+const KM_PER_DEG_LAT: f64 = 111.2;
+
+/// Airports from a downloaded table, searched before [`AIRPORTS`].
+static DOWNLOADED: OnceLock<Vec<AirportLocation>> = OnceLock::new();
+
+const AIRPORTS: &[AirportLocation] = &[
+    // North America
+    airport(
+        "ATL",
+        "KATL",
+        "Hartsfield-Jackson Atlanta Intl",
+        33.6407,
+        -84.4277,
+    ),
+    airport("BOS", "KBOS", "Boston Logan Intl", 42.3656, -71.0096),
+    airport("CLT", "KCLT", "Charlotte Douglas Intl", 35.2144, -80.9473),
+    airport("DCA", "KDCA", "Washington National", 38.8512, -77.0402),
+    airport("DEN", "KDEN", "Denver Intl", 39.8561, -104.6737),
+    airport("DFW", "KDFW", "Dallas/Fort Worth Intl", 32.8998, -97.0403),
+    airport("DTW", "KDTW", "Detroit Metropolitan", 42.2162, -83.3554),
+    airport("EWR", "KEWR", "Newark Liberty Intl", 40.6895, -74.1745),
+    airport("HNL", "PHNL", "Honolulu Intl", 21.3187, -157.9225),
+    airport("IAD", "KIAD", "Washington Dulles Intl", 38.9531, -77.4565),
+    airport("IAH", "KIAH", "Houston Intercontinental", 29.9902, -95.3368),
+    airport("JFK", "KJFK", "New York JFK Intl", 40.6413, -73.7781),
+    airport(
+        "LAS",
+        "KLAS",
+        "Las Vegas Harry Reid Intl",
+        36.0840,
+        -115.1537,
+    ),
+    airport("LAX", "KLAX", "Los Angeles Intl", 33.9416, -118.4085),
+    airport("LGA", "KLGA", "New York LaGuardia", 40.7769, -73.8740),
+    airport("MCO", "KMCO", "Orlando Intl", 28.4312, -81.3081),
+    airport("MEX", "MMMX", "Mexico City Intl", 19.4361, -99.0719),
+    airport("MIA", "KMIA", "Miami Intl", 25.7959, -80.2870),
+    airport("MSP", "KMSP", "Minneapolis-St Paul Intl", 44.8848, -93.2223),
+    airport("ORD", "KORD", "Chicago O'Hare Intl", 41.9742, -87.9073),
+    airport("PHL", "KPHL", "Philadelphia Intl", 39.8744, -75.2424),
+    airport("PHX", "KPHX", "Phoenix Sky Harbor Intl", 33.4342, -112.0116),
+    airport("SAN", "KSAN", "San Diego Intl", 32.7338, -117.1933),
+    airport("SEA", "KSEA", "Seattle-Tacoma Intl", 47.4502, -122.3088),
+    airport("SFO", "KSFO", "San Francisco Intl", 37.6213, -122.3790),
+    airport("YUL", "CYUL", "Montreal Trudeau Intl", 45.4706, -73.7408),
+    airport("YVR", "CYVR", "Vancouver Intl", 49.1967, -123.1815),
+    airport("YYZ", "CYYZ", "Toronto Pearson Intl", 43.6777, -79.6248),
+    // Europe
+    airport("AMS", "EHAM", "Amsterdam Schiphol", 52.3105, 4.7683),
+    airport("ARN", "ESSA", "Stockholm Arlanda", 59.6498, 17.9238),
+    airport("BCN", "LEBL", "Barcelona El Prat", 41.2974, 2.0833),
+    airport("CDG", "LFPG", "Paris Charles de Gaulle", 49.0097, 2.5479),
+    airport("CPH", "EKCH", "Copenhagen Kastrup", 55.6180, 12.6508),
+    airport("DUB", "EIDW", "Dublin", 53.4264, -6.2499),
+    airport("FCO", "LIRF", "Rome Fiumicino", 41.8003, 12.2389),
+    airport("FRA", "EDDF", "Frankfurt", 50.0379, 8.5622),
+    airport("HEL", "EFHK", "Helsinki-Vantaa", 60.3172, 24.9633),
+    airport("IST", "LTFM", "Istanbul", 41.2753, 28.7519),
+    airport("LGW", "EGKK", "London Gatwick", 51.1537, -0.1821),
+    airport("LHR", "EGLL", "London Heathrow", 51.4700, -0.4543),
+    airport("LIS", "LPPT", "Lisbon Humberto Delgado", 38.7742, -9.1342),
+    airport("MAD", "LEMD", "Madrid Barajas", 40.4983, -3.5676),
+    airport("MUC", "EDDM", "Munich", 48.3537, 11.7750),
+    airport("OSL", "ENGM", "Oslo Gardermoen", 60.1976, 11.1004),
+    airport("VIE", "LOWW", "Vienna", 48.1103, 16.5697),
+    airport("ZRH", "LSZH", "Zurich", 47.4582, 8.5555),
+    // Middle East & Africa
+    airport("AUH", "OMAA", "Abu Dhabi Zayed Intl", 24.4330, 54.6511),
+    airport("DOH", "OTHH", "Doha Hamad Intl", 25.2731, 51.6081),
+    airport("DXB", "OMDB", "Dubai Intl", 25.2532, 55.3657),
+    airport(
+        "JNB",
+        "FAOR",
+        "Johannesburg O.R. Tambo Intl",
+        -26.1392,
+        28.2460,
+    ),
+    // Asia & Oceania
+    airport("AKL", "NZAA", "Auckland", -37.0082, 174.7850),
+    airport("BKK", "VTBS", "Bangkok Suvarnabhumi", 13.6900, 100.7501),
+    airport(
+        "BOM",
+        "VABB",
+        "Mumbai Chhatrapati Shivaji Intl",
+        19.0896,
+        72.8656,
+    ),
+    airport("DEL", "VIDP", "Delhi Indira Gandhi Intl", 28.5562, 77.1000),
+    airport("HKG", "VHHH", "Hong Kong Intl", 22.3080, 113.9185),
+    airport("HND", "RJTT", "Tokyo Haneda", 35.5494, 139.7798),
+    airport("ICN", "RKSI", "Seoul Incheon Intl", 37.4602, 126.4407),
+    airport("MEL", "YMML", "Melbourne", -37.6690, 144.8410),
+    airport("NRT", "RJAA", "Tokyo Narita", 35.7720, 140.3929),
+    airport("PEK", "ZBAA", "Beijing Capital Intl", 40.0799, 116.6031),
+    airport("PVG", "ZSPD", "Shanghai Pudong Intl", 31.1443, 121.8083),
+    airport("SIN", "WSSS", "Singapore Changi", 1.3644, 103.9915),
+    airport("SYD", "YSSY", "Sydney Kingsford Smith", -33.9399, 151.1753),
+    // South America
+    airport("EZE", "SAEZ", "Buenos Aires Ezeiza", -34.8222, -58.5358),
+    airport("GRU", "SBGR", "Sao Paulo Guarulhos", -23.4356, -46.4731),
+    // Transatlantic and transpacific diversion fields
+    airport(
+        "ANC",
+        "PANC",
+        "Anchorage Ted Stevens Intl",
+        61.1743,
+        -149.9983,
+    ),
+    airport("KEF", "BIKF", "Keflavik Intl", 63.9850, -22.6056),
+    airport("SFJ", "BGSF", "Kangerlussuaq", 67.0122, -50.7116),
+    airport("SNN", "EINN", "Shannon", 52.7020, -8.9248),
+    airport("TER", "LPLA", "Lajes", 38.7618, -27.0908),
+    airport("YHZ", "CYHZ", "Halifax Stanfield Intl", 44.8808, -63.5086),
+    airport("YQX", "CYQX", "Gander Intl", 48.9369, -54.5681),
+    airport("YYR", "CYYR", "Goose Bay", 53.3192, -60.4258),
+];
+
+/// Time zones of the built-in airports, by ICAO code. Downloaded tables
+/// don't carry one, so an airport found only there has no known zone.
+const TIMEZONES: &[(Tz, &[&str])] = &[
+    (
+        Tz::America__New_York,
+        &[
+            "KATL", "KBOS", "KCLT", "KDCA", "KEWR", "KIAD", "KJFK", "KLGA", "KMCO", "KMIA", "KPHL",
+        ],
+    ),
+    (Tz::America__Detroit, &["KDTW"]),
+    (Tz::America__Chicago, &["KDFW", "KIAH", "KMSP", "KORD"]),
+    (Tz::America__Denver, &["KDEN"]),
+    (Tz::America__Phoenix, &["KPHX"]),
+    (
+        Tz::America__Los_Angeles,
+        &["KLAS", "KLAX", "KSAN", "KSEA", "KSFO"],
+    ),
+    (Tz::America__Anchorage, &["PANC"]),
+    (Tz::Pacific__Honolulu, &["PHNL"]),
+    (Tz::America__Mexico_City, &["MMMX"]),
+    (Tz::America__Toronto, &["CYUL", "CYYZ"]),
+    (Tz::America__Vancouver, &["CYVR"]),
+    (Tz::America__Halifax, &["CYHZ"]),
+    (Tz::America__St_Johns, &["CYQX"]),
+    (Tz::America__Goose_Bay, &["CYYR"]),
+    (Tz::America__Nuuk, &["BGSF"]),
+    (Tz::Atlantic__Reykjavik, &["BIKF"]),
+    (Tz::Atlantic__Azores, &["LPLA"]),
+    (Tz::Europe__Dublin, &["EIDW", "EINN"]),
+    (Tz::Europe__London, &["EGKK", "EGLL"]),
+    (Tz::Europe__Lisbon, &["LPPT"]),
+    (Tz::Europe__Madrid, &["LEBL", "LEMD"]),
+    (Tz::Europe__Paris, &["LFPG"]),
+    (Tz::Europe__Amsterdam, &["EHAM"]),
+    (Tz::Europe__Berlin, &["EDDF", "EDDM"]),
+    (Tz::Europe__Zurich, &["LSZH"]),
+    (Tz::Europe__Rome, &["LIRF"]),
+    (Tz::Europe__Vienna, &["LOWW"]),
+    (Tz::Europe__Copenhagen, &["EKCH"]),
+    (Tz::Europe__Oslo, &["ENGM"]),
+    (Tz::Europe__Stockholm, &["ESSA"]),
+    (Tz::Europe__Helsinki, &["EFHK"]),
+    (Tz::Europe__Istanbul, &["LTFM"]),
+    (Tz::Asia__Dubai, &["OMAA", "OMDB"]),
+    (Tz::Asia__Qatar, &["OTHH"]),
+    (Tz::Africa__Johannesburg, &["FAOR"]),
+    (Tz::Asia__Kolkata, &["VABB", "VIDP"]),
+    (Tz::Asia__Bangkok, &["VTBS"]),
+    (Tz::Asia__Singapore, &["WSSS"]),
+    (Tz::Asia__Hong_Kong, &["VHHH"]),
+    (Tz::Asia__Shanghai, &["ZBAA", "ZSPD"]),
+    (Tz::Asia__Seoul, &["RKSI"]),
+    (Tz::Asia__Tokyo, &["RJAA", "RJTT"]),
+    (Tz::Australia__Melbourne, &["YMML"]),
+    (Tz::Australia__Sydney, &["YSSY"]),
+    (Tz::Pacific__Auckland, &["NZAA"]),
+    (Tz::America__Argentina__Buenos_Aires, &["SAEZ"]),
+    (Tz::America__Sao_Paulo, &["SBGR"]),
+];
+
+/// Look up an airport by IATA or ICAO code (case-insensitive).
+///
+/// ```
+/// use flight_tracker_tui::airports;
+///
+/// let lhr = airports::lookup("egll").unwrap();
+/// assert_eq!(lhr.iata, "LHR");
+/// assert!(airports::lookup("XXX").is_none());
+/// ```
+pub fn lookup(code: &str) -> Option<&'static AirportLocation> {
+    let code = code.trim().to_uppercase();
+    all().find(|a| a.iata == code || a.icao == code)
+}
+
+/// The time zone of the airport with IATA or ICAO code `code`, when known.
+///
+/// ```
+/// use flight_tracker_tui::airports;
+///
+/// assert_eq!(airports::timezone("JFK"), Some(chrono_tz::America::New_York));
+/// assert_eq!(airports::timezone("XXX"), None);
+/// ```
+pub fn timezone(code: &str) -> Option<Tz> {
+    let icao = lookup(code)?.icao;
+    TIMEZONES
+        .iter()
+        .find(|(_, codes)| codes.contains(&icao))
+        .map(|(zone, _)| *zone)
+}
+
+/// Look airports up in `airports` before the built-in table from now on.
+/// Call it before the first lookup: [`nearest`] indexes the tables once.
+/// Only the first call has any effect; returns whether this one did.
+pub fn use_downloaded(airports: Vec<AirportLocation>) -> bool {
+    DOWNLOADED.set(airports).is_ok()
+}
+
+/// Every airport known, downloaded ones first.
+fn all() -> impl Iterator<Item = &'static AirportLocation> {
+    DOWNLOADED.get().into_iter().flatten().chain(AIRPORTS)
+}
+
+/// The tables sorted by latitude, so a search only measures the airports
+/// in the band of latitudes it could reach.
+fn by_latitude() -> &'static [AirportLocation] {
+    static SORTED: OnceLock<Vec<AirportLocation>> = OnceLock::new();
+    SORTED.get_or_init(|| {
+        let mut sorted: Vec<_> = all().copied().collect();
+        sorted.sort_by(|a, b| a.latitude.total_cmp(&b.latitude));
+        sorted
+    })
+}
+
+/// The closest airport within `max_km` of a position, with its distance.
+///
+/// ```
+/// use flight_tracker_tui::airports;
+///
+/// // On the apron at Gander
+/// let (gander, km) = airports::nearest(48.94, -54.57, 5.0).unwrap();
+/// assert_eq!(gander.icao, "CYQX");
+/// assert!(km < 1.0);
+/// assert!(airports::nearest(45.0, -40.0, 5.0).is_none()); // mid-Atlantic
+/// ```
+pub fn nearest(latitude: f64, longitude: f64, max_km: f64) -> Option<(AirportLocation, f64)> {
+    let sorted = by_latitude();
+    let band = max_km / KM_PER_DEG_LAT;
+    let start = sorted.partition_point(|a| a.latitude < latitude - band);
+    sorted[start..]
+        .iter()
+        .take_while(|a| a.latitude <= latitude + band)
+        .map(|a| {
+            (
+                *a,
+                geo::haversine_km(latitude, longitude, a.latitude, a.longitude),
+            )
+        })
+        .filter(|(_, km)| *km <= max_km)
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_by_iata_and_icao() {
+        assert_eq!(lookup("SFO").map(|a| a.icao), Some("KSFO"));
+        assert_eq!(lookup("KSFO").map(|a| a.iata), Some("SFO"));
+        assert_eq!(lookup(" jfk ").map(|a| a.icao), Some("KJFK"));
+    }
+
+    #[test]
+    fn test_lookup_unknown() {
+        assert!(lookup("").is_none());
+        assert!(lookup("ZZZ").is_none());
+    }
+
+    #[test]
+    fn test_nearest_matches_a_full_scan() {
+        // Airports a few km apart, and points along every runway-sized offset
+        for a in AIRPORTS {
+            for (dlat, dlon) in [(0.0, 0.0), (0.02, -0.03), (-0.04, 0.01)] {
+                let (lat, lon) = (a.latitude + dlat, a.longitude + dlon);
+                let scan = AIRPORTS
+                    .iter()
+                    .map(|b| (b.icao, geo::haversine_km(lat, lon, b.latitude, b.longitude)))
+                    .filter(|(_, km)| *km <= 5.0)
+                    .min_by(|x, y| x.1.total_cmp(&y.1))
+                    .map(|(icao, _)| icao);
+                assert_eq!(
+                    nearest(lat, lon, 5.0).map(|(b, _)| b.icao),
+                    scan,
+                    "{}",
+                    a.icao
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_nearest_respects_the_radius() {
+        // Heathrow's threshold is within 5 km, Windsor isn't
+        assert_eq!(
+            nearest(51.4775, -0.4614, 5.0).map(|(a, _)| a.iata),
+            Some("LHR")
+        );
+        assert_eq!(nearest(51.4839, -0.6044, 5.0), None);
+        assert_eq!(
+            nearest(51.4839, -0.6044, 15.0).map(|(a, _)| a.iata),
+            Some("LHR")
+        );
+        // Between JFK and LaGuardia, the closer one wins
+        assert_eq!(
+            nearest(40.70, -73.80, 20.0).map(|(a, _)| a.iata),
+            Some("JFK")
+        );
+    }
+
+    #[test]
+    fn test_every_airport_has_a_timezone() {
+        for a in AIRPORTS {
+            assert!(timezone(a.icao).is_some(), "{}", a.icao);
+        }
+        assert_eq!(timezone("lhr"), Some(Tz::Europe__London));
+    }
+
+    #[test]
+    fn test_codes_are_unique() {
+        for (i, a) in AIRPORTS.iter().enumerate() {
+            for b in &AIRPORTS[i + 1..] {
+                assert_ne!(a.iata, b.iata);
+                assert_ne!(a.icao, b.icao);
+            }
+        }
+    }
+}
@@ -0,0 +1,324 @@
+//! Per-flight alert rules evaluated on every position update, and muting
+//! of a flight's notifications.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::flight::{Flight, FlightStatus};
+
+/// On-ground distance from the destination at which a flight counts as arrived
+/// when the schedule provider has not reported it as landed yet.
+const LANDED_RADIUS_KM: f64 = 20.0;
+
+/// Condition that triggers an alert.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AlertCondition {
+    /// Aircraft is within this many kilometres of its destination.
+    DistanceBelowKm(f64),
+    /// Aircraft is airborne, not climbing, and below this altitude in feet.
+    AltitudeBelowFt(f64),
+    /// Flight has landed.
+    Landed,
+}
+
+impl AlertCondition {
+    /// Parse a prompt entry such as `150km`, `3000ft`, or `landed`.
+    ///
+    /// ```
+    /// use flight_tracker_tui::alert::AlertCondition;
+    ///
+    /// assert_eq!(AlertCondition::parse("150 km"), Some(AlertCondition::DistanceBelowKm(150.0)));
+    /// assert_eq!(AlertCondition::parse("LANDED"), Some(AlertCondition::Landed));
+    /// assert_eq!(AlertCondition::parse("soon"), None);
+    /// ```
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim().to_lowercase();
+        if input == "landed" || input == "land" {
+            return Some(Self::Landed);
+        }
+
+        let threshold = |suffix: &str| -> Option<f64> {
+            let value: f64 = input.strip_suffix(suffix)?.trim().parse().ok()?;
+            (value.is_finite() && value > 0.0).then_some(value)
+        };
+
+        if let Some(km) = threshold("km") {
+            Some(Self::DistanceBelowKm(km))
+        } else {
+            threshold("ft").map(Self::AltitudeBelowFt)
+        }
+    }
+
+    /// Whether the flight currently satisfies this condition.
+    pub fn is_met(&self, flight: &Flight) -> bool {
+        match self {
+            Self::DistanceBelowKm(km) => flight
+                .distance_to_destination_km()
+                .is_some_and(|d| d <= *km),
+            Self::AltitudeBelowFt(ft) => {
+                !flight.on_ground
+                    && flight.vertical_rate.is_none_or(|vr| vr <= 0.0)
+                    && flight.altitude_ft.is_some_and(|alt| alt <= *ft)
+            }
+            Self::Landed => {
+                flight.status == FlightStatus::Landed
+                    || (flight.on_ground
+                        && flight
+                            .distance_to_destination_km()
+                            .is_some_and(|d| d <= LANDED_RADIUS_KM))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for AlertCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DistanceBelowKm(km) => write!(f, "within {:.0} km of destination", km),
+            Self::AltitudeBelowFt(ft) => write!(f, "below {:.0} ft", ft),
+            Self::Landed => write!(f, "landed"),
+        }
+    }
+}
+
+/// An alert condition that fires once and then disarms itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub condition: AlertCondition,
+    /// Cleared once the rule has fired.
+    pub armed: bool,
+}
+
+impl AlertRule {
+    pub fn new(condition: AlertCondition) -> Self {
+        Self {
+            condition,
+            armed: true,
+        }
+    }
+}
+
+/// Check every armed rule on `flight`, disarming the ones that fire.
+///
+/// Returns one message per fired rule.
+pub fn evaluate(flight: &mut Flight) -> Vec<String> {
+    let mut fired = Vec::new();
+
+    for i in 0..flight.alerts.len() {
+        let rule = &flight.alerts[i];
+        if rule.armed && rule.condition.is_met(flight) {
+            fired.push(format!("{} is {}", flight.flight_number, rule.condition));
+            flight.alerts[i].armed = false;
+        }
+    }
+
+    fired
+}
+
+/// How long a flight's notifications are held back, chosen with `z`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Mute {
+    /// Until `until`, `minutes` after the mute was chosen.
+    For { minutes: i64, until: DateTime<Utc> },
+    /// Until the flight lands or is cancelled.
+    UntilLanded,
+}
+
+impl Mute {
+    /// The setting after `current` in the `z` cycle: 15 minutes, an hour,
+    /// until landed, then off. A mute that has run out counts as off.
+    ///
+    /// ```
+    /// use chrono::Utc;
+    /// use flight_tracker_tui::alert::Mute;
+    ///
+    /// let now = Utc::now();
+    /// let first = Mute::cycle(None, now);
+    /// assert!(matches!(first, Some(Mute::For { minutes: 15, .. })));
+    /// assert_eq!(Mute::cycle(Some(Mute::UntilLanded), now), None);
+    /// ```
+    pub fn cycle(current: Option<Mute>, now: DateTime<Utc>) -> Option<Mute> {
+        let muted_for = |minutes| Mute::For {
+            minutes,
+            until: now + Duration::minutes(minutes),
+        };
+        match current {
+            Some(Mute::For { until, .. }) if until <= now => Some(muted_for(15)),
+            None => Some(muted_for(15)),
+            Some(Mute::For { minutes: 15, .. }) => Some(muted_for(60)),
+            Some(Mute::For { .. }) => Some(Mute::UntilLanded),
+            Some(Mute::UntilLanded) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Mute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::For { minutes: 60, .. } => write!(f, "muted for 1h"),
+            Self::For { minutes, .. } => write!(f, "muted for {}m", minutes),
+            Self::UntilLanded => write!(f, "muted until landed"),
+        }
+    }
+}
+
+/// Whether a flight's bell, desktop and webhook notifications are held back
+/// at `now`, given its mute and status.
+pub fn is_muted(mute: Option<Mute>, status: &FlightStatus, now: DateTime<Utc>) -> bool {
+    match mute {
+        None => false,
+        Some(Mute::For { until, .. }) => now < until,
+        Some(Mute::UntilLanded) => {
+            !matches!(status, FlightStatus::Landed | FlightStatus::Cancelled)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::Airport;
+
+    fn inbound_to_lhr(lat: f64, lon: f64, alt: f64) -> Flight {
+        Flight {
+            flight_number: "UA100".to_string(),
+            status: FlightStatus::EnRoute,
+            latitude: Some(lat),
+            longitude: Some(lon),
+            altitude_ft: Some(alt),
+            vertical_rate: Some(-800.0),
+            destination: Some(Airport {
+                iata: Some("LHR".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_conditions() {
+        assert_eq!(
+            AlertCondition::parse("150km"),
+            Some(AlertCondition::DistanceBelowKm(150.0))
+        );
+        assert_eq!(
+            AlertCondition::parse(" 3000FT "),
+            Some(AlertCondition::AltitudeBelowFt(3000.0))
+        );
+        assert_eq!(
+            AlertCondition::parse("landed"),
+            Some(AlertCondition::Landed)
+        );
+        assert_eq!(AlertCondition::parse("0km"), None);
+        assert_eq!(AlertCondition::parse("-5ft"), None);
+        assert_eq!(AlertCondition::parse("150"), None);
+        assert_eq!(AlertCondition::parse(""), None);
+    }
+
+    #[test]
+    fn test_distance_rule_fires_once() {
+        // Roughly 110 km west of Heathrow
+        let mut flight = inbound_to_lhr(51.47, -2.05, 12000.0);
+        flight
+            .alerts
+            .push(AlertRule::new(AlertCondition::DistanceBelowKm(150.0)));
+
+        let fired = evaluate(&mut flight);
+        assert_eq!(fired.len(), 1);
+        assert!(fired[0].starts_with("UA100"));
+        assert!(!flight.alerts[0].armed);
+
+        assert!(evaluate(&mut flight).is_empty());
+    }
+
+    #[test]
+    fn test_distance_rule_not_met_far_away() {
+        // Over the Atlantic
+        let mut flight = inbound_to_lhr(52.0, -30.0, 38000.0);
+        flight
+            .alerts
+            .push(AlertRule::new(AlertCondition::DistanceBelowKm(150.0)));
+
+        assert!(evaluate(&mut flight).is_empty());
+        assert!(flight.alerts[0].armed);
+    }
+
+    #[test]
+    fn test_distance_rule_unknown_destination() {
+        let mut flight = inbound_to_lhr(51.47, -0.5, 1000.0);
+        flight.destination = None;
+
+        assert!(!AlertCondition::DistanceBelowKm(500.0).is_met(&flight));
+    }
+
+    #[test]
+    fn test_altitude_rule_ignores_climb() {
+        let mut flight = inbound_to_lhr(51.47, -1.0, 2500.0);
+        assert!(AlertCondition::AltitudeBelowFt(3000.0).is_met(&flight));
+
+        flight.vertical_rate = Some(2000.0);
+        assert!(!AlertCondition::AltitudeBelowFt(3000.0).is_met(&flight));
+
+        flight.on_ground = true;
+        flight.vertical_rate = None;
+        assert!(!AlertCondition::AltitudeBelowFt(3000.0).is_met(&flight));
+    }
+
+    #[test]
+    fn test_landed_rule() {
+        let mut flight = inbound_to_lhr(51.47, -0.45, 0.0);
+        assert!(!AlertCondition::Landed.is_met(&flight));
+
+        flight.on_ground = true;
+        assert!(AlertCondition::Landed.is_met(&flight));
+
+        let mut scheduled = Flight {
+            status: FlightStatus::Landed,
+            ..Default::default()
+        };
+        assert!(AlertCondition::Landed.is_met(&scheduled));
+        scheduled.status = FlightStatus::Scheduled;
+        assert!(!AlertCondition::Landed.is_met(&scheduled));
+    }
+
+    #[test]
+    fn test_mute_cycle() {
+        let now = DateTime::from_timestamp(1_721_000_000, 0).unwrap();
+        let fifteen = Mute::cycle(None, now).unwrap();
+        assert_eq!(
+            fifteen,
+            Mute::For {
+                minutes: 15,
+                until: now + Duration::minutes(15)
+            }
+        );
+        let hour = Mute::cycle(Some(fifteen), now).unwrap();
+        assert_eq!(hour.to_string(), "muted for 1h");
+        assert_eq!(Mute::cycle(Some(hour), now), Some(Mute::UntilLanded));
+        assert_eq!(Mute::cycle(Some(Mute::UntilLanded), now), None);
+
+        // Pressed again after a mute ran out, it starts over
+        let later = now + Duration::hours(2);
+        assert_eq!(Mute::cycle(Some(hour), later), Mute::cycle(None, later));
+    }
+
+    #[test]
+    fn test_is_muted() {
+        let now = DateTime::from_timestamp(1_721_000_000, 0).unwrap();
+        let en_route = FlightStatus::EnRoute;
+        assert!(!is_muted(None, &en_route, now));
+
+        let fifteen = Mute::cycle(None, now);
+        assert!(is_muted(fifteen, &en_route, now));
+        assert!(is_muted(fifteen, &en_route, now + Duration::minutes(14)));
+        assert!(!is_muted(fifteen, &en_route, now + Duration::minutes(15)));
+        // A timed mute outlasts the landing
+        assert!(is_muted(fifteen, &FlightStatus::Landed, now));
+
+        let landed = Some(Mute::UntilLanded);
+        assert!(is_muted(landed, &en_route, now + Duration::days(1)));
+        assert!(is_muted(landed, &FlightStatus::Delayed, now));
+        assert!(!is_muted(landed, &FlightStatus::Landed, now));
+        assert!(!is_muted(landed, &FlightStatus::Cancelled, now));
+    }
+}
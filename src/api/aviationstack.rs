@@ -3,34 +3,135 @@
 //! Provides route information, departure/arrival times, and delay data.
 //! Uses persistent disk cache to minimize API calls (free tier: 100/month).
 
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
+use chrono::{DateTime, NaiveDate, Utc};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
 
+use super::breaker::CircuitBreaker;
+use super::http_client;
+use super::telemetry::{ProviderDiagnostics, Telemetry};
+use crate::airports;
 use crate::cache::PersistentCache;
 use crate::error::AppError;
+use crate::geo;
 
-const AVIATIONSTACK_BASE_URL: &str = "http://api.aviationstack.com/v1";
+const AVIATIONSTACK_HTTPS_URL: &str = "https://api.aviationstack.com/v1";
+/// The free plan only allows plain HTTP.
+const AVIATIONSTACK_HTTP_URL: &str = "http://api.aviationstack.com/v1";
+/// Error code for an HTTPS request on a plan without HTTPS access.
+const HTTPS_RESTRICTED_CODE: &str = "https_access_restricted";
 const CACHE_TTL_SECS: u64 = 86400; // 24 hours - schedule data rarely changes
+/// Longest a request may take before it counts as failed.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
 const CACHE_FILE: &str = "schedule_cache.json";
+const USAGE_FILE: &str = "aviationstack_usage.json";
+/// Usage counters only need to outlive the month they count.
+const USAGE_TTL_SECS: u64 = 32 * 86400;
+/// Most pages requested for one lookup. Each one counts against the
+/// monthly quota.
+const MAX_PAGES: u32 = 3;
 
 /// Client for the AviationStack API.
 #[derive(Clone)]
 pub struct AviationStackClient {
     client: Client,
+    https_url: String,
+    http_url: String,
     api_key: Option<String>,
+    https: HttpsMode,
+    /// Set once the plan turns out not to include HTTPS. Shared between
+    /// clones so the fallback happens once per session.
+    https_restricted: Arc<AtomicBool>,
     cache: PersistentCache<Option<FlightData>>,
+    /// Network requests made per calendar month, keyed by `YYYY-MM`.
+    usage: PersistentCache<u32>,
+    telemetry: Telemetry,
+    /// Skips requests while the API keeps failing. Shared between clones.
+    breaker: CircuitBreaker,
 }
 
+/// Whether requests use HTTPS, from `AVIATIONSTACK_HTTPS`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HttpsMode {
+    /// Unset: try HTTPS, falling back to HTTP if the plan lacks it.
+    Preferred,
+    /// `1`: HTTPS only. A plan without it is an error.
+    Required,
+    /// `0`: plain HTTP only.
+    Disabled,
+}
+
+impl HttpsMode {
+    fn from_env(value: Option<&str>) -> Self {
+        match value.map(|v| v.trim().to_ascii_lowercase()).as_deref() {
+            Some("1" | "true" | "yes") => HttpsMode::Required,
+            Some("0" | "false" | "no") => HttpsMode::Disabled,
+            _ => HttpsMode::Preferred,
+        }
+    }
+}
+
+/// Envelope returned by the AviationStack `/flights` endpoint.
 #[derive(Debug, Deserialize)]
 pub struct AviationStackResponse {
     pub data: Option<Vec<FlightData>>,
+    /// Where `data` sits among all the matching rows.
+    #[serde(default)]
+    pub pagination: Option<Pagination>,
+    /// Present instead of `data` when the request was refused.
+    #[serde(default)]
+    pub error: Option<ApiError>,
+}
+
+/// The `pagination` block of a response: `count` rows from `offset` of
+/// `total`, at most `limit` per page.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Pagination {
+    pub limit: u32,
+    pub offset: u32,
+    pub count: u32,
+    pub total: u32,
+}
+
+impl Pagination {
+    /// Offset of the page after this one, if there are rows left.
+    pub fn next_offset(&self) -> Option<u32> {
+        let next = self.offset + self.count;
+        (self.count > 0 && next < self.total).then_some(next)
+    }
+}
+
+/// Error body returned for refused requests, e.g. an invalid key or a
+/// feature outside the subscription plan.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiError {
+    pub code: Option<String>,
+    pub message: Option<String>,
+}
+
+impl From<ApiError> for AppError {
+    fn from(error: ApiError) -> Self {
+        match error.code.as_deref() {
+            Some(HTTPS_RESTRICTED_CODE) => AppError::HttpsRestricted,
+            Some("usage_limit_reached" | "rate_limit_reached") => AppError::RateLimited,
+            code => AppError::Api(
+                error
+                    .message
+                    .or_else(|| code.map(str::to_string))
+                    .unwrap_or_else(|| "unknown error".to_string()),
+            ),
+        }
+    }
 }
 
 /// Flight data from AviationStack API.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[allow(dead_code)]
 pub struct FlightData {
     pub flight_status: Option<String>,
     pub departure: Option<AirportInfo>,
@@ -38,6 +139,10 @@ pub struct FlightData {
     pub airline: Option<AirlineInfo>,
     pub flight: Option<FlightInfo>,
     pub aircraft: Option<AircraftInfo>,
+    /// Latest position, for flights in the air. Older responses and cache
+    /// entries have no such field.
+    #[serde(default)]
+    pub live: Option<LiveInfo>,
 }
 
 /// Airport information including schedule times.
@@ -54,7 +159,6 @@ pub struct AirportInfo {
 
 /// Airline information.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[allow(dead_code)]
 pub struct AirlineInfo {
     pub name: Option<String>,
     pub iata: Option<String>,
@@ -62,11 +166,21 @@ pub struct AirlineInfo {
 
 /// Flight number information.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[allow(dead_code)]
 pub struct FlightInfo {
     pub iata: Option<String>,
     pub icao: Option<String>,
     pub number: Option<String>,
+    /// Present when this row is a codeshare of another airline's flight.
+    #[serde(default)]
+    pub codeshared: Option<CodeshareInfo>,
+}
+
+/// The operating flight behind a codeshare row.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeshareInfo {
+    pub airline_name: Option<String>,
+    pub airline_iata: Option<String>,
+    pub flight_iata: Option<String>,
 }
 
 /// Aircraft information.
@@ -77,20 +191,184 @@ pub struct AircraftInfo {
     pub icao: Option<String>,
 }
 
+/// Position report in an active flight's `live` block. Per the API docs,
+/// altitude is in meters and both speeds in km/h. It is refreshed every few
+/// minutes at best, so it only stands in when OpenSky has no state vector.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiveInfo {
+    pub updated: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub altitude: Option<f64>,
+    pub direction: Option<f64>,
+    pub speed_horizontal: Option<f64>,
+    pub speed_vertical: Option<f64>,
+    #[serde(default)]
+    pub is_ground: bool,
+}
+
+impl LiveInfo {
+    const METERS_TO_FEET: f64 = 3.28084;
+    const KMH_TO_KNOTS: f64 = 1.0 / 1.852;
+
+    /// When the position was reported.
+    pub fn updated_at(&self) -> Option<DateTime<Utc>> {
+        let updated = self.updated.as_deref()?;
+        DateTime::parse_from_rfc3339(updated)
+            .ok()
+            .map(|t| t.with_timezone(&Utc))
+    }
+
+    pub fn altitude_ft(&self) -> Option<f64> {
+        self.altitude.map(|m| m * Self::METERS_TO_FEET)
+    }
+
+    pub fn ground_speed_kts(&self) -> Option<f64> {
+        self.speed_horizontal.map(|kmh| kmh * Self::KMH_TO_KNOTS)
+    }
+
+    /// Climb rate in ft/min.
+    pub fn vertical_rate_fpm(&self) -> Option<f64> {
+        self.speed_vertical
+            .map(|kmh| kmh * 1000.0 / 60.0 * Self::METERS_TO_FEET)
+    }
+}
+
+impl FlightData {
+    /// Great-circle midpoint between the departure and arrival airports,
+    /// when both are in the built-in airport table.
+    pub fn route_midpoint(&self) -> Option<(f64, f64)> {
+        geo::route_midpoint(
+            self.departure.as_ref().and_then(AirportInfo::coordinates),
+            self.arrival.as_ref().and_then(AirportInfo::coordinates),
+        )
+    }
+}
+
+impl AirportInfo {
+    fn coordinates(&self) -> Option<(f64, f64)> {
+        self.iata
+            .as_deref()
+            .and_then(airports::lookup)
+            .or_else(|| self.icao.as_deref().and_then(airports::lookup))
+            .map(|a| (a.latitude, a.longitude))
+    }
+}
+
+impl Default for AviationStackClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl AviationStackClient {
+    /// Create a client, reading the API key from `AVIATIONSTACK_API_KEY` and
+    /// the HTTPS setting from `AVIATIONSTACK_HTTPS`.
     pub fn new() -> Self {
         Self {
-            client: Client::new(),
+            client: http_client(REQUEST_TIMEOUT),
+            https_url: AVIATIONSTACK_HTTPS_URL.to_string(),
+            http_url: AVIATIONSTACK_HTTP_URL.to_string(),
             api_key: std::env::var("AVIATIONSTACK_API_KEY").ok(),
-            cache: PersistentCache::new(Duration::from_secs(CACHE_TTL_SECS), CACHE_FILE),
+            https: HttpsMode::from_env(std::env::var("AVIATIONSTACK_HTTPS").ok().as_deref()),
+            https_restricted: Arc::new(AtomicBool::new(false)),
+            cache: schedule_cache(),
+            usage: PersistentCache::new(Duration::from_secs(USAGE_TTL_SECS), USAGE_FILE),
+            telemetry: Telemetry::default(),
+            breaker: CircuitBreaker::default(),
+        }
+    }
+
+    /// Give up on requests that take longer than `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = http_client(timeout);
+        self
+    }
+
+    /// API key state, transport, request counters and cache usage.
+    pub fn diagnostics(&self) -> ProviderDiagnostics {
+        let auth = if !self.has_api_key() {
+            "no API key"
+        } else if self.uses_https() {
+            "API key loaded, HTTPS"
+        } else if self.https_restricted.load(Ordering::Relaxed) {
+            "API key loaded, HTTP (plan has no HTTPS)"
+        } else {
+            "API key loaded, HTTP"
+        };
+        ProviderDiagnostics {
+            name: "AviationStack",
+            auth: auth.to_string(),
+            stats: self.telemetry.stats(),
+            cache: self.cache.stats(),
+            breaker: self.breaker.state(),
+            resolution: None,
         }
     }
 
+    /// Forget every cached schedule, including flights remembered as not
+    /// found, so the next lookup of each goes to the API.
+    pub fn clear_cache(&self) {
+        info!(
+            entries = self.cache.len(),
+            "Clearing the AviationStack schedule cache"
+        );
+        self.cache.clear();
+    }
+
+    /// Whether an API key is configured. Without one, lookups return `None`.
     pub fn has_api_key(&self) -> bool {
         self.api_key.is_some()
     }
 
-    pub async fn get_flight(&self, flight_number: &str) -> Result<Option<FlightData>, AppError> {
+    /// Whether the next request goes over HTTPS.
+    fn uses_https(&self) -> bool {
+        match self.https {
+            HttpsMode::Required => true,
+            HttpsMode::Preferred => !self.https_restricted.load(Ordering::Relaxed),
+            HttpsMode::Disabled => false,
+        }
+    }
+
+    /// Number of API requests made so far this calendar month (UTC).
+    pub fn requests_this_month(&self) -> u32 {
+        self.usage.get(&month_key(Utc::now())).unwrap_or(0)
+    }
+
+    fn record_request(&self) {
+        let key = month_key(Utc::now());
+        let count = self.usage.get(&key).unwrap_or(0);
+        self.usage.set(key, count + 1);
+    }
+
+    /// Look up the schedule for a flight by IATA flight number, optionally
+    /// on a specific departure date.
+    pub async fn get_flight(
+        &self,
+        flight_number: &str,
+        date: Option<NaiveDate>,
+    ) -> Result<Option<FlightData>, AppError> {
+        self.fetch_flight(flight_number, date, Duration::from_secs(CACHE_TTL_SECS))
+            .await
+    }
+
+    /// Like [`get_flight`](Self::get_flight), but only reuses a cached
+    /// result younger than `max_age`.
+    pub async fn refresh_flight(
+        &self,
+        flight_number: &str,
+        date: Option<NaiveDate>,
+        max_age: Duration,
+    ) -> Result<Option<FlightData>, AppError> {
+        self.fetch_flight(flight_number, date, max_age).await
+    }
+
+    async fn fetch_flight(
+        &self,
+        flight_number: &str,
+        date: Option<NaiveDate>,
+        max_age: Duration,
+    ) -> Result<Option<FlightData>, AppError> {
         let api_key = match &self.api_key {
             Some(key) => key,
             None => return Ok(None),
@@ -98,33 +376,866 @@ impl AviationStackClient {
 
         // Clean flight number (remove spaces, uppercase)
         let flight_iata = flight_number.trim().to_uppercase().replace(' ', "");
+        let cache_key = cache_key(&flight_iata, date, Utc::now());
+        // Flights not found are cached too, to avoid repeated lookups
+        self.cache
+            .get_or_fetch_fresh(&cache_key, max_age, || {
+                self.request_flight(api_key, &flight_iata, date)
+            })
+            .await
+    }
+
+    async fn request_flight(
+        &self,
+        api_key: &str,
+        flight_iata: &str,
+        date: Option<NaiveDate>,
+    ) -> Result<Option<FlightData>, AppError> {
+        self.breaker.check("AviationStack")?;
+        let mut query = format!(
+            "/flights?access_key={}&flight_iata={}",
+            api_key, flight_iata
+        );
+        if let Some(date) = date {
+            query.push_str(&format!("&flight_date={}", date.format("%Y-%m-%d")));
+        }
 
-        // Check cache first
-        if let Some(cached) = self.cache.get(&flight_iata) {
-            return Ok(cached);
+        // Frequent shuttles can have more rows than fit on a page, with
+        // the one in the air on a later page
+        let mut flights = Vec::new();
+        let mut offset = 0;
+        for page in 1..=MAX_PAGES {
+            let page_query = match offset {
+                0 => query.clone(),
+                offset => format!("{}&offset={}", query, offset),
+            };
+            let data = match self.send_with_fallback(&page_query).await {
+                Ok(data) => data,
+                // Earlier pages still count
+                Err(e) if page > 1 => {
+                    warn!(page, error = %e, "AviationStack page failed, using the rows so far");
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
+            flights.extend(data.data.unwrap_or_default());
+            match next_page(&flights, data.pagination, date) {
+                Some(next) if page < MAX_PAGES => offset = next,
+                _ => break,
+            }
         }
 
-        let url = format!(
-            "{}/flights?access_key={}&flight_iata={}",
-            AVIATIONSTACK_BASE_URL, api_key, flight_iata
+        let rows = flights.len();
+        let result = select_flight(flights, date, Utc::now());
+        debug!(
+            rows,
+            found = result.is_some(),
+            "AviationStack flights parsed"
         );
+        Ok(result)
+    }
+
+    /// [`send`](Self::send), retrying over HTTP once if the plan turns out
+    /// not to include HTTPS.
+    async fn send_with_fallback(&self, query: &str) -> Result<AviationStackResponse, AppError> {
+        let response = self.send(query).await;
+        if matches!(response, Err(AppError::HttpsRestricted)) && self.https == HttpsMode::Preferred
+        {
+            warn!("AviationStack plan does not include HTTPS, using HTTP for this session");
+            self.https_restricted.store(true, Ordering::Relaxed);
+            return self.send(query).await;
+        }
+        response
+    }
+
+    /// Request `query` over the current scheme, counting it against the
+    /// monthly usage.
+    async fn send(&self, query: &str) -> Result<AviationStackResponse, AppError> {
+        let base_url = if self.uses_https() {
+            &self.https_url
+        } else {
+            &self.http_url
+        };
+        let url = format!("{}{}", base_url, query);
+        log_request(&url);
+        self.record_request();
+        let response = self.request_flights(&url).await;
+        self.telemetry.record(&response);
+        self.breaker.record(&response);
+        response
+    }
 
-        let response = self.client.get(&url).send().await?;
+    async fn request_flights(&self, url: &str) -> Result<AviationStackResponse, AppError> {
+        // reqwest errors carry the request URL, which includes the API key
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| AppError::Network(e.without_url()))?;
+        debug!(status = %response.status(), "AviationStack response");
 
         if response.status() == 429 {
             return Err(AppError::RateLimited);
         }
 
-        let data: AviationStackResponse = response
+        let body: AviationStackResponse = response
             .json()
             .await
-            .map_err(|e| AppError::Parse(e.to_string()))?;
+            .map_err(|e| AppError::parse(e.without_url().to_string()))?;
+        match body.error {
+            Some(error) => Err(error.into()),
+            None => Ok(body),
+        }
+    }
+}
 
-        let result = data.data.and_then(|flights| flights.into_iter().next());
+fn log_request(url: &str) {
+    debug!(url = %redact_api_key(url), "AviationStack request");
+}
 
-        // Cache the result (even if None, to avoid repeated lookups)
-        self.cache.set(flight_iata, result.clone());
+/// Replace the value of every `access_key` query parameter so URLs, or
+/// text quoting them, can be logged or shown.
+pub(super) fn redact_api_key(text: &str) -> String {
+    const PARAM: &str = "access_key=";
 
-        Ok(result)
+    let mut redacted = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(PARAM).map(|i| i + PARAM.len()) {
+        let end = rest[start..]
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .map_or(rest.len(), |i| start + i);
+        redacted.push_str(&rest[..start]);
+        redacted.push_str("REDACTED");
+        rest = &rest[end..];
+    }
+    redacted.push_str(rest);
+    redacted
+}
+
+/// Usage counter key for the month containing `now`.
+fn month_key(now: DateTime<Utc>) -> String {
+    now.format("%Y-%m").to_string()
+}
+
+/// The schedule cache on disk, without entries from before keys carried a
+/// date.
+fn schedule_cache() -> PersistentCache<Option<FlightData>> {
+    let cache = PersistentCache::new(Duration::from_secs(CACHE_TTL_SECS), CACHE_FILE);
+    cache.retain(|key| key.contains(['@', '#']));
+    cache
+}
+
+/// Persistent cache key for a flight. A dated lookup is keyed by its date
+/// (`UA123@2024-07-14`); an undated one by the UTC date it was made on
+/// (`UA123#2024-07-14`), so after midnight a daily flight's lookup finds
+/// the new day's instance instead of yesterday's.
+fn cache_key(flight_iata: &str, date: Option<NaiveDate>, now: DateTime<Utc>) -> String {
+    match date {
+        Some(date) => format!("{}@{}", flight_iata, date.format("%Y-%m-%d")),
+        None => format!("{}#{}", flight_iata, now.format("%Y-%m-%d")),
+    }
+}
+
+/// Offset of the next page to request, or `None` once `flights` (every row
+/// so far) holds one worth stopping at: an active row, or with a requested
+/// `date`, a row departing that day. Also `None` on the last page, or if
+/// the response had no pagination block.
+fn next_page(
+    flights: &[FlightData],
+    pagination: Option<Pagination>,
+    date: Option<NaiveDate>,
+) -> Option<u32> {
+    let satisfied = flights.iter().any(|f| match date {
+        Some(date) => departs_on(f, date),
+        None => is_active(f),
+    });
+    if satisfied {
+        return None;
+    }
+    pagination?.next_offset()
+}
+
+/// Pick the record to use from a multi-row response.
+///
+/// The API often returns yesterday's completed leg, today's active one and
+/// codeshare rows for the same flight. If a date was requested, rows
+/// departing on that date are considered first. Among the candidates we
+/// prefer, in order: an `active` flight, the soonest future scheduled
+/// departure, and the operating (non-codeshare) row, falling back to the
+/// first row returned.
+fn select_flight(
+    flights: Vec<FlightData>,
+    date: Option<NaiveDate>,
+    now: DateTime<Utc>,
+) -> Option<FlightData> {
+    let date = date.filter(|&d| flights.iter().any(|f| departs_on(f, d)));
+
+    let best = flights
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| date.is_none_or(|d| departs_on(f, d)))
+        .min_by_key(|(i, f)| {
+            let active = is_active(f);
+            let until_departure = scheduled_departure(f)
+                .map(|dep| (dep - now).num_seconds())
+                .filter(|secs| *secs >= 0)
+                .unwrap_or(i64::MAX);
+            let codeshare = f
+                .flight
+                .as_ref()
+                .is_some_and(|info| info.codeshared.is_some());
+
+            (!active, until_departure, codeshare, *i)
+        })
+        .map(|(i, _)| i)?;
+
+    flights.into_iter().nth(best)
+}
+
+fn is_active(flight: &FlightData) -> bool {
+    flight
+        .flight_status
+        .as_deref()
+        .is_some_and(|s| s.eq_ignore_ascii_case("active"))
+}
+
+fn departs_on(flight: &FlightData, date: NaiveDate) -> bool {
+    scheduled_departure(flight).is_some_and(|dep| dep.date_naive() == date)
+}
+
+fn scheduled_departure(flight: &FlightData) -> Option<DateTime<Utc>> {
+    let scheduled = flight.departure.as_ref()?.scheduled.as_deref()?;
+    DateTime::parse_from_rfc3339(scheduled)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{mock, BreakerState};
+
+    /// Trimmed-down capture of a `/flights?flight_iata=UA900` response:
+    /// yesterday's landed leg, today's active leg and a Lufthansa codeshare.
+    const UA900_ACTIVE: &str = r#"{"data": [
+        {"flight_date": "2024-07-13", "flight_status": "landed",
+         "departure": {"airport": "San Francisco International", "iata": "SFO", "icao": "KSFO",
+                       "scheduled": "2024-07-13T20:05:00+00:00", "estimated": null, "actual": "2024-07-13T20:17:00+00:00", "delay": 12},
+         "arrival": {"airport": "Frankfurt International Airport", "iata": "FRA", "icao": "EDDF",
+                     "scheduled": "2024-07-14T16:15:00+00:00", "estimated": null, "actual": null, "delay": null},
+         "airline": {"name": "United Airlines", "iata": "UA"},
+         "flight": {"number": "900", "iata": "UA900", "icao": "UAL900", "codeshared": null},
+         "aircraft": null},
+        {"flight_date": "2024-07-14", "flight_status": "scheduled",
+         "departure": {"airport": "San Francisco International", "iata": "SFO", "icao": "KSFO",
+                       "scheduled": "2024-07-14T20:05:00+00:00", "estimated": null, "actual": null, "delay": null},
+         "arrival": {"airport": "Frankfurt International Airport", "iata": "FRA", "icao": "EDDF",
+                     "scheduled": "2024-07-15T16:15:00+00:00", "estimated": null, "actual": null, "delay": null},
+         "airline": {"name": "Lufthansa", "iata": "LH"},
+         "flight": {"number": "7607", "iata": "LH7607", "icao": "DLH7607",
+                    "codeshared": {"airline_name": "united airlines", "airline_iata": "ua", "flight_iata": "ua900"}},
+         "aircraft": null},
+        {"flight_date": "2024-07-14", "flight_status": "active",
+         "departure": {"airport": "San Francisco International", "iata": "SFO", "icao": "KSFO",
+                       "scheduled": "2024-07-14T20:05:00+00:00", "estimated": null, "actual": "2024-07-14T20:11:00+00:00", "delay": 6},
+         "arrival": {"airport": "Frankfurt International Airport", "iata": "FRA", "icao": "EDDF",
+                     "scheduled": "2024-07-15T16:15:00+00:00", "estimated": null, "actual": null, "delay": null},
+         "airline": {"name": "United Airlines", "iata": "UA"},
+         "flight": {"number": "900", "iata": "UA900", "icao": "UAL900", "codeshared": null},
+         "aircraft": {"registration": "N2748U", "iata": "B77W", "icao": "B77W"},
+         "live": {"updated": "2024-07-14T23:42:00+00:00", "latitude": 58.7364, "longitude": -41.2841,
+                  "altitude": 10972.8, "direction": 62, "speed_horizontal": 907.4,
+                  "speed_vertical": 0, "is_ground": false}}
+    ]}"#;
+
+    /// Capture of a `/flights?flight_iata=BA285` response before departure:
+    /// yesterday's leg, today's and tomorrow's scheduled legs, and a codeshare
+    /// of today's leg listed first.
+    const BA285_SCHEDULED: &str = r#"{"data": [
+        {"flight_status": "scheduled",
+         "departure": {"airport": "Heathrow", "iata": "LHR", "icao": "EGLL",
+                       "scheduled": "2024-07-14T15:20:00+00:00", "estimated": null, "actual": null, "delay": null},
+         "arrival": null, "airline": {"name": "American Airlines", "iata": "AA"},
+         "flight": {"number": "6981", "iata": "AA6981", "icao": "AAL6981",
+                    "codeshared": {"airline_name": "british airways", "airline_iata": "ba", "flight_iata": "ba285"}},
+         "aircraft": null},
+        {"flight_status": "landed",
+         "departure": {"airport": "Heathrow", "iata": "LHR", "icao": "EGLL",
+                       "scheduled": "2024-07-13T15:20:00+00:00", "estimated": null, "actual": null, "delay": null},
+         "arrival": null, "airline": {"name": "British Airways", "iata": "BA"},
+         "flight": {"number": "285", "iata": "BA285", "icao": "BAW285", "codeshared": null},
+         "aircraft": null},
+        {"flight_status": "scheduled",
+         "departure": {"airport": "Heathrow", "iata": "LHR", "icao": "EGLL",
+                       "scheduled": "2024-07-15T15:20:00+00:00", "estimated": null, "actual": null, "delay": null},
+         "arrival": null, "airline": {"name": "British Airways", "iata": "BA"},
+         "flight": {"number": "285", "iata": "BA285", "icao": "BAW285", "codeshared": null},
+         "aircraft": null},
+        {"flight_status": "scheduled",
+         "departure": {"airport": "Heathrow", "iata": "LHR", "icao": "EGLL",
+                       "scheduled": "2024-07-14T15:20:00+00:00", "estimated": null, "actual": null, "delay": null},
+         "arrival": null, "airline": {"name": "British Airways", "iata": "BA"},
+         "flight": {"number": "285", "iata": "BA285", "icao": "BAW285", "codeshared": null},
+         "aircraft": null}
+    ]}"#;
+
+    fn rows(json: &str) -> Vec<FlightData> {
+        serde_json::from_str::<AviationStackResponse>(json)
+            .unwrap()
+            .data
+            .unwrap()
+    }
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn describe(f: &FlightData) -> String {
+        format!(
+            "{} {}",
+            f.flight
+                .as_ref()
+                .and_then(|i| i.iata.as_deref())
+                .unwrap_or("?"),
+            f.departure
+                .as_ref()
+                .and_then(|d| d.scheduled.as_deref())
+                .map(|s| &s[..10])
+                .unwrap_or("?")
+        )
+    }
+
+    #[test]
+    fn test_route_midpoint() {
+        let (lat, lon) = rows(UA900_ACTIVE)[2].route_midpoint().unwrap();
+        // SFO-FRA passes over Hudson Bay
+        assert!((55.0..70.0).contains(&lat), "lat {lat}");
+        assert!((-100.0..-60.0).contains(&lon), "lon {lon}");
+
+        // BA285 rows have no arrival
+        assert!(rows(BA285_SCHEDULED)[0].route_midpoint().is_none());
+    }
+
+    #[test]
+    fn test_redact_api_key() {
+        assert_eq!(
+            redact_api_key("http://api/v1/flights?access_key=s3cret&flight_iata=UA1"),
+            "http://api/v1/flights?access_key=REDACTED&flight_iata=UA1"
+        );
+        assert_eq!(
+            redact_api_key("http://api/v1/flights?flight_iata=UA1&access_key=s3cret"),
+            "http://api/v1/flights?flight_iata=UA1&access_key=REDACTED"
+        );
+        assert_eq!(
+            redact_api_key("http://api/v1/flights"),
+            "http://api/v1/flights"
+        );
+        // Quoted in JSON, and more than once
+        assert_eq!(
+            redact_api_key(r#"{"a": "?access_key=abc1", "b": "access_key=def2"}"#),
+            r#"{"a": "?access_key=REDACTED", "b": "access_key=REDACTED"}"#
+        );
+    }
+
+    #[test]
+    fn test_logged_request_hides_api_key() {
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+        impl std::io::Write for Buffer {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+
+        tracing::subscriber::with_default(subscriber, || {
+            log_request(
+                "http://api.aviationstack.com/v1/flights?access_key=s3cret&flight_iata=UA1",
+            );
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("flight_iata=UA1"));
+        assert!(output.contains("access_key=REDACTED"));
+        assert!(!output.contains("s3cret"));
+    }
+
+    #[test]
+    fn test_cache_key() {
+        let late = at("2024-07-14T23:59:59+00:00");
+        let after_midnight = at("2024-07-15T00:30:00+00:00");
+        assert_eq!(cache_key("UA123", None, late), "UA123#2024-07-14");
+        assert_eq!(cache_key("UA123", None, after_midnight), "UA123#2024-07-15");
+        // A requested date wins over the day of the lookup
+        let date = NaiveDate::from_ymd_opt(2024, 7, 14);
+        assert_eq!(cache_key("UA123", date, after_midnight), "UA123@2024-07-14");
+    }
+
+    #[test]
+    fn test_month_key() {
+        assert_eq!(month_key(at("2024-07-31T23:59:59+00:00")), "2024-07");
+        assert_eq!(month_key(at("2024-08-01T00:00:00+00:00")), "2024-08");
+    }
+
+    #[test]
+    fn test_select_flight_table() {
+        let cases: &[(&str, &str, Option<NaiveDate>, &str, &str)] = &[
+            // (name, response, requested date, now, expected)
+            (
+                "active leg beats landed and codeshare",
+                UA900_ACTIVE,
+                None,
+                "2024-07-14T22:00:00+00:00",
+                "UA900 2024-07-14",
+            ),
+            (
+                "requested date narrows to that day's rows",
+                UA900_ACTIVE,
+                NaiveDate::from_ymd_opt(2024, 7, 13),
+                "2024-07-14T22:00:00+00:00",
+                "UA900 2024-07-13",
+            ),
+            (
+                "soonest future departure, operating row over codeshare",
+                BA285_SCHEDULED,
+                None,
+                "2024-07-14T09:00:00+00:00",
+                "BA285 2024-07-14",
+            ),
+            (
+                "after today's departure, tomorrow's leg is next",
+                BA285_SCHEDULED,
+                None,
+                "2024-07-14T18:00:00+00:00",
+                "BA285 2024-07-15",
+            ),
+            (
+                "requested date with no matching row is ignored",
+                BA285_SCHEDULED,
+                NaiveDate::from_ymd_opt(2024, 8, 1),
+                "2024-07-14T09:00:00+00:00",
+                "BA285 2024-07-14",
+            ),
+            (
+                "all departures past picks first operating row",
+                BA285_SCHEDULED,
+                None,
+                "2024-08-01T00:00:00+00:00",
+                "BA285 2024-07-13",
+            ),
+            (
+                "active row wins long after departure",
+                UA900_ACTIVE,
+                None,
+                "2024-08-01T00:00:00+00:00",
+                "UA900 2024-07-14",
+            ),
+        ];
+
+        for (name, response, date, now, expected) in cases {
+            let chosen = select_flight(rows(response), *date, at(now)).unwrap();
+            assert_eq!(describe(&chosen), *expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_select_flight_empty() {
+        assert!(select_flight(Vec::new(), None, Utc::now()).is_none());
+    }
+
+    /// One BA1436 London-Edinburgh shuttle row with `status`, departing at
+    /// `departure`.
+    fn shuttle_row(status: &str, departure: &str) -> String {
+        format!(
+            r#"{{"flight_status": "{}",
+                "departure": {{"iata": "LHR", "scheduled": "{}"}},
+                "arrival": {{"iata": "EDI"}}, "airline": {{"name": "British Airways"}},
+                "flight": {{"iata": "BA1436", "number": "1436"}}, "aircraft": null}}"#,
+            status, departure
+        )
+    }
+
+    /// A response holding `rows`, starting at `offset` of `total`.
+    fn page(rows: &[String], offset: u32, total: u32) -> String {
+        format!(
+            r#"{{"pagination": {{"limit": 2, "offset": {}, "count": {}, "total": {}}},
+                "data": [{}]}}"#,
+            offset,
+            rows.len(),
+            total,
+            rows.join(",")
+        )
+    }
+
+    #[test]
+    fn test_pagination() {
+        let body: AviationStackResponse = serde_json::from_str(&page(&[], 4, 9)).unwrap();
+        let pagination = body.pagination.unwrap();
+        assert_eq!(
+            (pagination.limit, pagination.offset, pagination.total),
+            (2, 4, 9)
+        );
+        assert_eq!(pagination.next_offset(), None);
+        // Responses without the block still parse
+        assert!(serde_json::from_str::<AviationStackResponse>(UA900_ACTIVE)
+            .unwrap()
+            .pagination
+            .is_none());
+
+        let pagination = |offset, count, total| Pagination {
+            limit: 2,
+            offset,
+            count,
+            total,
+        };
+        assert_eq!(pagination(0, 2, 5).next_offset(), Some(2));
+        assert_eq!(pagination(2, 2, 5).next_offset(), Some(4));
+        assert_eq!(pagination(4, 1, 5).next_offset(), None);
+    }
+
+    #[test]
+    fn test_next_page_stops_at_a_usable_row() {
+        let more = Some(Pagination {
+            limit: 100,
+            offset: 0,
+            count: 3,
+            total: 250,
+        });
+        let today = NaiveDate::from_ymd_opt(2024, 7, 14);
+        // No active row yet: keep going
+        assert_eq!(next_page(&rows(BA285_SCHEDULED), more, None), Some(3));
+        // An active row anywhere so far is enough
+        assert_eq!(next_page(&rows(UA900_ACTIVE), more, None), None);
+        // With a date, a row departing that day is enough
+        assert_eq!(next_page(&rows(BA285_SCHEDULED), more, today), None);
+        let elsewhere = NaiveDate::from_ymd_opt(2024, 8, 1);
+        assert_eq!(next_page(&rows(BA285_SCHEDULED), more, elsewhere), Some(3));
+        // Nothing left, or no pagination at all
+        let last = more.map(|p| Pagination { offset: 247, ..p });
+        assert_eq!(next_page(&rows(BA285_SCHEDULED), last, None), None);
+        assert_eq!(next_page(&rows(BA285_SCHEDULED), None, None), None);
+    }
+
+    #[test]
+    fn test_flight_info_without_codeshared_field() {
+        // Entries cached before the field was added must still load
+        let info: FlightInfo =
+            serde_json::from_str(r#"{"iata": "UA1", "icao": null, "number": "1"}"#).unwrap();
+        assert!(info.codeshared.is_none());
+    }
+
+    #[test]
+    fn test_live_block() {
+        let rows = rows(UA900_ACTIVE);
+        // Only the active leg has one
+        assert!(rows[0].live.is_none());
+        let live = rows[2].live.as_ref().unwrap();
+        assert_eq!(live.updated_at(), Some(at("2024-07-14T23:42:00Z")));
+        assert_eq!(
+            (live.latitude, live.longitude),
+            (Some(58.7364), Some(-41.2841))
+        );
+        assert!(!live.is_ground);
+        // 10,972.8 m is FL360, and 907.4 km/h is 490 kts
+        assert!((live.altitude_ft().unwrap() - 36_000.0).abs() < 1.0);
+        assert!((live.ground_speed_kts().unwrap() - 490.0).abs() < 0.1);
+        assert_eq!(live.vertical_rate_fpm(), Some(0.0));
+    }
+
+    #[test]
+    fn test_live_block_units() {
+        let live: LiveInfo = serde_json::from_str(
+            r#"{"updated": "not a time", "latitude": null, "longitude": null,
+                "altitude": null, "direction": null, "speed_horizontal": 1.852,
+                "speed_vertical": -3.6}"#,
+        )
+        .unwrap();
+        assert_eq!(live.updated_at(), None);
+        assert_eq!(live.altitude_ft(), None);
+        assert!((live.ground_speed_kts().unwrap() - 1.0).abs() < 1e-9);
+        // -3.6 km/h is -1 m/s, about -197 ft/min
+        assert!((live.vertical_rate_fpm().unwrap() + 196.85).abs() < 0.01);
+        assert!(!live.is_ground);
+    }
+
+    const RESTRICTED: &str = r#"{"error": {"code": "https_access_restricted",
+        "message": "Access Restricted - Your current Subscription Plan does not support HTTPS Encryption."}}"#;
+
+    /// A free plan: HTTPS requests are refused, HTTP ones answered.
+    fn free_plan(head: &str) -> (&'static str, String) {
+        if head.starts_with("get /https/") {
+            ("200 OK", RESTRICTED.to_string())
+        } else {
+            ("200 OK", UA900_ACTIVE.to_string())
+        }
+    }
+
+    /// A paid plan: every request is answered.
+    fn paid_plan(_head: &str) -> (&'static str, String) {
+        ("200 OK", UA900_ACTIVE.to_string())
+    }
+
+    /// A client talking to the mock server, with `/https` and `/http`
+    /// standing in for the two schemes.
+    fn client_with(base_url: &str, https: HttpsMode) -> AviationStackClient {
+        let ttl = Duration::from_secs(CACHE_TTL_SECS);
+        AviationStackClient {
+            client: Client::new(),
+            https_url: format!("{}/https", base_url),
+            http_url: format!("{}/http", base_url),
+            api_key: Some("key".to_string()),
+            https,
+            https_restricted: Arc::new(AtomicBool::new(false)),
+            cache: PersistentCache::in_memory(ttl),
+            usage: PersistentCache::in_memory(ttl),
+            telemetry: Telemetry::default(),
+            breaker: CircuitBreaker::default(),
+        }
+    }
+
+    fn paths(heads: &[String]) -> Vec<&str> {
+        heads
+            .iter()
+            .map(|h| {
+                h.split_whitespace()
+                    .nth(1)
+                    .unwrap()
+                    .split('?')
+                    .next()
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_https_mode_from_env() {
+        assert_eq!(HttpsMode::from_env(None), HttpsMode::Preferred);
+        assert_eq!(HttpsMode::from_env(Some("1")), HttpsMode::Required);
+        assert_eq!(HttpsMode::from_env(Some(" TRUE ")), HttpsMode::Required);
+        assert_eq!(HttpsMode::from_env(Some("0")), HttpsMode::Disabled);
+        assert_eq!(HttpsMode::from_env(Some("")), HttpsMode::Preferred);
+    }
+
+    #[test]
+    fn test_api_error_body() {
+        let body: AviationStackResponse = serde_json::from_str(RESTRICTED).unwrap();
+        assert!(body.data.is_none());
+        assert!(matches!(
+            body.error.unwrap().into(),
+            AppError::HttpsRestricted
+        ));
+
+        let body: AviationStackResponse = serde_json::from_str(
+            r#"{"error": {"code": "invalid_access_key", "message": "You have not supplied a valid API Access Key."}}"#,
+        )
+        .unwrap();
+        let error: AppError = body.error.unwrap().into();
+        assert_eq!(
+            error.to_string(),
+            "API error: You have not supplied a valid API Access Key."
+        );
+
+        let body: AviationStackResponse =
+            serde_json::from_str(r#"{"error": {"code": "usage_limit_reached"}}"#).unwrap();
+        assert!(matches!(body.error.unwrap().into(), AppError::RateLimited));
+    }
+
+    #[tokio::test]
+    async fn test_free_plan_falls_back_to_http_once() {
+        let (base_url, server) = mock::serve(3, free_plan).await;
+        let client = client_with(&base_url, HttpsMode::Preferred);
+        assert!(client.diagnostics().auth.ends_with("HTTPS"));
+
+        let flight = client.get_flight("UA900", None).await.unwrap();
+        assert!(flight.is_some());
+        // Remembered for the rest of the session, including by clones
+        let refreshed = client
+            .clone()
+            .refresh_flight("UA900", None, Duration::ZERO)
+            .await
+            .unwrap();
+        assert!(refreshed.is_some());
+
+        assert_eq!(
+            paths(&server.await.unwrap()),
+            vec!["/https/flights", "/http/flights", "/http/flights"]
+        );
+        assert_eq!(client.requests_this_month(), 3);
+        assert_eq!(
+            client.diagnostics().auth,
+            "API key loaded, HTTP (plan has no HTTPS)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_paid_plan_stays_on_https() {
+        let (base_url, server) = mock::serve(2, paid_plan).await;
+        let client = client_with(&base_url, HttpsMode::Preferred);
+
+        client.get_flight("UA900", None).await.unwrap();
+        client
+            .refresh_flight("UA900", None, Duration::ZERO)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            paths(&server.await.unwrap()),
+            vec!["/https/flights", "/https/flights"]
+        );
+        assert_eq!(client.diagnostics().auth, "API key loaded, HTTPS");
+    }
+
+    #[tokio::test]
+    async fn test_required_https_never_falls_back() {
+        let (base_url, server) = mock::serve(1, free_plan).await;
+        let client = client_with(&base_url, HttpsMode::Required);
+
+        let result = client.get_flight("UA900", None).await;
+        assert!(matches!(result, Err(AppError::HttpsRestricted)));
+        assert_eq!(paths(&server.await.unwrap()), vec!["/https/flights"]);
+        // The refusal is not cached as "no such flight"
+        assert!(client.cache.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failing_api_is_skipped() {
+        let (base_url, server) =
+            mock::serve(3, |_| ("502 Bad Gateway", "<html>502</html>".to_string())).await;
+        let client = client_with(&base_url, HttpsMode::Disabled);
+
+        for flight in ["UA1", "UA2", "UA3"] {
+            assert!(matches!(
+                client.get_flight(flight, None).await,
+                Err(AppError::Parse(_))
+            ));
+        }
+        // The fourth lookup never reaches the server
+        let result = client.clone().get_flight("UA4", None).await;
+        assert!(matches!(
+            result,
+            Err(AppError::ProviderDegraded("AviationStack"))
+        ));
+        assert_eq!(server.await.unwrap().len(), 3);
+        assert_eq!(client.requests_this_month(), 3);
+        assert_eq!(client.diagnostics().breaker, BreakerState::Open);
+    }
+
+    #[tokio::test]
+    async fn test_lookup_after_midnight_misses_yesterday() {
+        let (base_url, server) = mock::serve(1, paid_plan).await;
+        let client = client_with(&base_url, HttpsMode::Preferred);
+        // Looked up late yesterday, well within the TTL
+        let yesterday = Utc::now() - chrono::Duration::days(1);
+        client.cache.set(cache_key("UA900", None, yesterday), None);
+
+        let found = client.get_flight("UA900", None).await.unwrap();
+        assert!(found.is_some());
+        assert_eq!(server.await.unwrap().len(), 1);
+        assert_eq!(client.cache.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_clear_cache_retries_flights_not_found() {
+        let (base_url, server) =
+            mock::serve(2, |_| ("200 OK", r#"{"data": []}"#.to_string())).await;
+        let client = client_with(&base_url, HttpsMode::Disabled);
+
+        assert!(client.get_flight("XY999", None).await.unwrap().is_none());
+        // The miss is remembered
+        assert!(client.get_flight("XY999", None).await.unwrap().is_none());
+        let cache = client.diagnostics().cache;
+        assert_eq!(
+            (cache.entries, cache.hits, cache.misses, cache.inserts),
+            (1, 1, 1, 1)
+        );
+
+        client.clear_cache();
+        assert!(client.get_flight("XY999", None).await.unwrap().is_none());
+        assert_eq!(server.await.unwrap().len(), 2);
+        assert_eq!(client.requests_this_month(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_active_row_on_a_later_page_is_found() {
+        let (base_url, server) = mock::serve(2, |head| {
+            let body = if head.contains("offset=2") {
+                page(&[shuttle_row("active", "2024-07-14T09:00:00+00:00")], 2, 3)
+            } else {
+                let rows = [
+                    shuttle_row("landed", "2024-07-13T19:00:00+00:00"),
+                    shuttle_row("landed", "2024-07-14T07:00:00+00:00"),
+                ];
+                page(&rows, 0, 3)
+            };
+            ("200 OK", body)
+        })
+        .await;
+        let client = client_with(&base_url, HttpsMode::Disabled);
+
+        let flight = client.get_flight("BA1436", None).await.unwrap().unwrap();
+        assert_eq!(flight.flight_status.as_deref(), Some("active"));
+        let heads = server.await.unwrap();
+        assert!(!heads[0].contains("offset="));
+        assert!(heads[1].contains("&offset=2 "));
+        assert_eq!(client.requests_this_month(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_paging_stops_at_the_cap() {
+        let (base_url, server) = mock::serve(MAX_PAGES as usize, |_| {
+            let rows = [shuttle_row("landed", "2024-07-13T19:00:00+00:00")];
+            ("200 OK", page(&rows, 0, 100))
+        })
+        .await;
+        let client = client_with(&base_url, HttpsMode::Disabled);
+
+        // Every page is someone else's landed leg; the best of them is used
+        let flight = client.get_flight("BA1436", None).await.unwrap().unwrap();
+        assert_eq!(flight.flight_status.as_deref(), Some("landed"));
+        assert_eq!(server.await.unwrap().len(), MAX_PAGES as usize);
+        assert_eq!(client.requests_this_month(), MAX_PAGES);
+    }
+
+    #[tokio::test]
+    async fn test_failed_later_page_keeps_earlier_rows() {
+        let (base_url, server) = mock::serve(2, |head| {
+            if head.contains("offset=") {
+                ("502 Bad Gateway", "<html>502</html>".to_string())
+            } else {
+                let rows = [shuttle_row("scheduled", "2099-07-14T09:00:00+00:00")];
+                ("200 OK", page(&rows, 0, 2))
+            }
+        })
+        .await;
+        let client = client_with(&base_url, HttpsMode::Disabled);
+
+        let flight = client.get_flight("BA1436", None).await.unwrap().unwrap();
+        assert_eq!(flight.flight_status.as_deref(), Some("scheduled"));
+        assert_eq!(server.await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_https_uses_http() {
+        let (base_url, server) = mock::serve(1, free_plan).await;
+        let client = client_with(&base_url, HttpsMode::Disabled);
+
+        assert!(client.get_flight("UA900", None).await.unwrap().is_some());
+        assert_eq!(paths(&server.await.unwrap()), vec!["/http/flights"]);
     }
 }
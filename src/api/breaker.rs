@@ -0,0 +1,264 @@
+//! Circuit breaker that stops calling a provider that keeps failing.
+//!
+//! After [`FAILURE_THRESHOLD`] failed requests in a row the breaker opens
+//! and requests are refused without touching the network for
+//! [`COOL_DOWN`]. The first request after that goes through as a probe:
+//! its success closes the breaker again, its failure opens it for another
+//! cool-down.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::error::AppError;
+
+/// Failed requests in a row that open the breaker.
+pub const FAILURE_THRESHOLD: u32 = 3;
+
+/// How long an open breaker refuses requests before probing.
+pub const COOL_DOWN: Duration = Duration::from_secs(60);
+
+/// Where a breaker is in its cycle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Requests go through.
+    #[default]
+    Closed,
+    /// Requests are refused until the cool-down is over.
+    Open,
+    /// One probe request is out; the rest are refused until it answers.
+    HalfOpen,
+}
+
+impl BreakerState {
+    /// Whether requests are being skipped, or only a probe let through.
+    pub fn is_degraded(self) -> bool {
+        self != BreakerState::Closed
+    }
+
+    /// What the state means for requests, for the diagnostics overlay.
+    pub fn describe(self) -> &'static str {
+        match self {
+            BreakerState::Closed => "closed",
+            BreakerState::Open => "open, skipping requests",
+            BreakerState::HalfOpen => "half-open, probing",
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Inner {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { since: Instant },
+}
+
+/// Thread-safe breaker for one provider. Clones share the same state, like
+/// the clients that own them.
+///
+/// ```
+/// use flight_tracker_tui::api::{BreakerState, CircuitBreaker};
+/// use flight_tracker_tui::AppError;
+///
+/// let breaker = CircuitBreaker::default();
+/// for _ in 0..3 {
+///     breaker.check("AviationStack").unwrap();
+///     breaker.clone().record(&Err::<(), _>(AppError::RateLimited));
+/// }
+/// assert_eq!(breaker.state(), BreakerState::Open);
+/// assert!(breaker.check("AviationStack").is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    inner: Arc<Mutex<Inner>>,
+    threshold: u32,
+    cool_down: Duration,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(FAILURE_THRESHOLD, COOL_DOWN)
+    }
+}
+
+impl CircuitBreaker {
+    /// A closed breaker that opens after `threshold` failures in a row and
+    /// stays open for `cool_down`. A threshold of 0 never opens.
+    pub fn new(threshold: u32, cool_down: Duration) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Inner::Closed { failures: 0 })),
+            threshold,
+            cool_down,
+        }
+    }
+
+    /// Whether a request to `provider` may go out now. Refused requests
+    /// fail with [`AppError::ProviderDegraded`].
+    pub fn check(&self, provider: &'static str) -> Result<(), AppError> {
+        if self.allow_at(Instant::now()) {
+            Ok(())
+        } else {
+            Err(AppError::ProviderDegraded(provider))
+        }
+    }
+
+    /// Note how a request that [`check`](Self::check) let out finished.
+    ///
+    /// Rejected credentials and a plan without HTTPS are not counted: the
+    /// client asks again differently, and that answer is the one that says
+    /// whether the provider is healthy.
+    pub fn record<T>(&self, result: &Result<T, AppError>) {
+        match result {
+            Err(AppError::Unauthorized | AppError::HttpsRestricted) => {}
+            result => self.record_at(result.is_ok(), Instant::now()),
+        }
+    }
+
+    pub fn state(&self) -> BreakerState {
+        match *self.lock() {
+            Inner::Closed { .. } => BreakerState::Closed,
+            Inner::Open { .. } => BreakerState::Open,
+            Inner::HalfOpen { .. } => BreakerState::HalfOpen,
+        }
+    }
+
+    fn allow_at(&self, now: Instant) -> bool {
+        let mut inner = self.lock();
+        let probe = match *inner {
+            Inner::Closed { .. } => return true,
+            Inner::Open { until } => now >= until,
+            // A probe whose task was dropped never answers, so after a
+            // cool-down without word another one goes out
+            Inner::HalfOpen { since } => now >= since + self.cool_down,
+        };
+        if probe {
+            *inner = Inner::HalfOpen { since: now };
+        }
+        probe
+    }
+
+    fn record_at(&self, ok: bool, now: Instant) {
+        let mut inner = self.lock();
+        let failures = match *inner {
+            _ if ok => 0,
+            Inner::Closed { failures } => failures + 1,
+            // The probe failed, or a request from before the breaker
+            // opened came back late
+            Inner::HalfOpen { .. } => self.threshold,
+            Inner::Open { .. } => return,
+        };
+        *inner = if self.threshold > 0 && failures >= self.threshold {
+            Inner::Open {
+                until: now + self.cool_down,
+            }
+        } else {
+            Inner::Closed { failures }
+        };
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        // The state is always whole, so a panic elsewhere can't corrupt it
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    fn breaker() -> CircuitBreaker {
+        CircuitBreaker::new(3, Duration::from_secs(60))
+    }
+
+    #[test]
+    fn test_opens_after_consecutive_failures() {
+        let breaker = breaker();
+        let now = Instant::now();
+
+        // A success in between starts the count again
+        breaker.record_at(false, now);
+        breaker.record_at(false, now);
+        breaker.record_at(true, now);
+        breaker.record_at(false, now);
+        breaker.record_at(false, now);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert!(breaker.allow_at(now));
+
+        breaker.record_at(false, now);
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(!breaker.allow_at(now + 59 * SECOND));
+        assert!(matches!(
+            breaker.check("AviationStack"),
+            Err(AppError::ProviderDegraded("AviationStack"))
+        ));
+    }
+
+    #[test]
+    fn test_half_open_probe_closes_on_success() {
+        let breaker = breaker();
+        let now = Instant::now();
+        for _ in 0..3 {
+            breaker.record_at(false, now);
+        }
+
+        // One probe once the cool-down is over, nothing else meanwhile
+        let later = now + 60 * SECOND;
+        assert!(breaker.allow_at(later));
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        assert!(!breaker.allow_at(later));
+
+        breaker.record_at(true, later + SECOND);
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert!(breaker.allow_at(later + SECOND));
+    }
+
+    #[test]
+    fn test_half_open_probe_reopens_on_failure() {
+        let breaker = breaker();
+        let now = Instant::now();
+        for _ in 0..3 {
+            breaker.record_at(false, now);
+        }
+
+        let later = now + 60 * SECOND;
+        assert!(breaker.allow_at(later));
+        breaker.record_at(false, later);
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(!breaker.allow_at(later + 59 * SECOND));
+        assert!(breaker.allow_at(later + 60 * SECOND));
+    }
+
+    #[test]
+    fn test_lost_probe_is_retried() {
+        let breaker = breaker();
+        let now = Instant::now();
+        for _ in 0..3 {
+            breaker.record_at(false, now);
+        }
+        let later = now + 60 * SECOND;
+        assert!(breaker.allow_at(later));
+
+        // The probe never answers
+        assert!(!breaker.allow_at(later + 59 * SECOND));
+        assert!(breaker.allow_at(later + 60 * SECOND));
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+    }
+
+    #[test]
+    fn test_clones_share_state_and_zero_never_opens() {
+        let breaker = breaker();
+        let clone = breaker.clone();
+        let now = Instant::now();
+        for _ in 0..3 {
+            clone.record_at(false, now);
+        }
+        assert_eq!(breaker.state(), BreakerState::Open);
+
+        let never = CircuitBreaker::new(0, COOL_DOWN);
+        for _ in 0..10 {
+            never.record_at(false, now);
+        }
+        assert_eq!(never.state(), BreakerState::Closed);
+    }
+}
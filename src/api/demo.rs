@@ -0,0 +1,424 @@
+//! Scripted offline provider for demos and tests.
+//!
+//! Serves a fixed set of flights without touching the network. Airborne
+//! flights advance along the great circle between their airports each time
+//! their position is requested, so auto-refresh shows realistic movement.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use futures::future::BoxFuture;
+
+use super::aviationstack::{AircraftInfo, AirlineInfo, AirportInfo, FlightData, FlightInfo};
+use super::breaker::BreakerState;
+use super::provider::{PositionProvider, ScheduleProvider};
+use super::telemetry::{ProviderDiagnostics, ProviderStats};
+use super::types::{AircraftCategory, StateSnapshot, StateVector};
+use crate::airports;
+use crate::cache::CacheStats;
+use crate::error::AppError;
+use crate::geo;
+
+/// Fraction of the route flown per position request.
+const PROGRESS_STEP: f64 = 0.02;
+const CRUISE_ALTITUDE_FT: f64 = 37000.0;
+const CLIMB_DISTANCE_KM: f64 = 220.0;
+const DESCENT_DISTANCE_KM: f64 = 280.0;
+const CRUISE_SPEED_KMH: f64 = 850.0;
+const FEET_PER_METER: f64 = 3.28084;
+
+/// How a scripted flight behaves.
+#[derive(Debug, Clone, Copy)]
+enum Script {
+    /// Airborne, starting at the given fraction of the route.
+    EnRoute(f64),
+    /// Still at the gate with a departure delay in minutes.
+    Delayed(i64),
+    /// Arrived a while ago.
+    Landed,
+}
+
+struct DemoFlight {
+    flight_number: &'static str,
+    callsign: &'static str,
+    icao24: &'static str,
+    airline: &'static str,
+    origin: &'static str,
+    destination: &'static str,
+    aircraft: &'static str,
+    registration: &'static str,
+    script: Script,
+}
+
+const FLIGHTS: &[DemoFlight] = &[
+    DemoFlight {
+        flight_number: "UA900",
+        callsign: "UAL900",
+        icao24: "a35c1e",
+        airline: "United Airlines",
+        origin: "SFO",
+        destination: "FRA",
+        aircraft: "B77W",
+        registration: "N2748U",
+        script: Script::EnRoute(0.35),
+    },
+    DemoFlight {
+        flight_number: "BA178",
+        callsign: "BAW178",
+        icao24: "4007f2",
+        airline: "British Airways",
+        origin: "LHR",
+        destination: "JFK",
+        aircraft: "B772",
+        registration: "G-YMMN",
+        script: Script::EnRoute(0.82),
+    },
+    DemoFlight {
+        flight_number: "AF007",
+        callsign: "AFR007",
+        icao24: "39bda3",
+        airline: "Air France",
+        origin: "CDG",
+        destination: "JFK",
+        aircraft: "A359",
+        registration: "F-HTYA",
+        script: Script::Delayed(45),
+    },
+    DemoFlight {
+        flight_number: "LH400",
+        callsign: "DLH400",
+        icao24: "3c4b26",
+        airline: "Lufthansa",
+        origin: "FRA",
+        destination: "JFK",
+        aircraft: "B748",
+        registration: "D-ABYA",
+        script: Script::Landed,
+    },
+];
+
+/// A flight number the demo provider deliberately knows nothing about.
+const NOT_FOUND_FLIGHT: &str = "ZZ404";
+
+/// Offline provider serving scripted flights.
+///
+/// Clones share progress, so the same instance can back both provider
+/// traits.
+///
+/// ```
+/// use flight_tracker_tui::api::{DemoProvider, PositionProvider};
+///
+/// # futures::executor::block_on(async {
+/// let demo = DemoProvider::new();
+/// let first = demo.search_flight("UA900").await.unwrap().state.unwrap();
+/// let second = demo.get_state(&first.icao24).await.unwrap().state.unwrap();
+/// assert_ne!(first.longitude, second.longitude);
+/// # });
+/// ```
+#[derive(Clone, Default)]
+pub struct DemoProvider {
+    progress: Arc<Mutex<HashMap<&'static str, f64>>>,
+}
+
+impl DemoProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Flight numbers to track when starting in demo mode, including one
+    /// that is never found.
+    pub fn flight_numbers() -> impl Iterator<Item = &'static str> {
+        FLIGHTS
+            .iter()
+            .map(|f| f.flight_number)
+            .chain(std::iter::once(NOT_FOUND_FLIGHT))
+    }
+
+    fn find(&self, matches: impl Fn(&DemoFlight) -> bool) -> Option<&'static DemoFlight> {
+        FLIGHTS.iter().find(|f| matches(f))
+    }
+
+    /// Advance an airborne flight and return its new route fraction.
+    fn advance(&self, flight: &DemoFlight, start: f64) -> f64 {
+        let Ok(mut progress) = self.progress.lock() else {
+            return start;
+        };
+        let fraction = progress
+            .entry(flight.flight_number)
+            .or_insert(start - PROGRESS_STEP);
+        *fraction = (*fraction + PROGRESS_STEP).min(1.0);
+        *fraction
+    }
+
+    fn position(&self, flight: &DemoFlight) -> Option<StateVector> {
+        let Script::EnRoute(start) = flight.script else {
+            return None;
+        };
+        let fraction = self.advance(flight, start);
+        Some(state_vector(flight, fraction, Utc::now()))
+    }
+}
+
+/// Build the state of `flight` at `fraction` of its route.
+fn state_vector(flight: &DemoFlight, fraction: f64, now: DateTime<Utc>) -> StateVector {
+    let (from, to) = route(flight);
+    let total_km = geo::haversine_km(from.0, from.1, to.0, to.1);
+    let (lat, lon) = geo::intermediate_point(from.0, from.1, to.0, to.1, fraction);
+    let (next_lat, next_lon) =
+        geo::intermediate_point(from.0, from.1, to.0, to.1, (fraction + 0.001).min(1.0));
+
+    let flown_km = total_km * fraction;
+    let remaining_km = total_km - flown_km;
+    let on_ground = fraction >= 1.0;
+
+    // Linear climb and descent ramps around a level cruise
+    let (altitude_ft, vertical_fpm) = if on_ground {
+        (0.0, 0.0)
+    } else if flown_km < CLIMB_DISTANCE_KM {
+        (CRUISE_ALTITUDE_FT * flown_km / CLIMB_DISTANCE_KM, 2200.0)
+    } else if remaining_km < DESCENT_DISTANCE_KM {
+        (
+            CRUISE_ALTITUDE_FT * remaining_km / DESCENT_DISTANCE_KM,
+            -1800.0,
+        )
+    } else {
+        (CRUISE_ALTITUDE_FT, 0.0)
+    };
+
+    StateVector {
+        icao24: flight.icao24.to_string(),
+        callsign: Some(flight.callsign.to_string()),
+        origin_country: "Demo".to_string(),
+        time_position: Some(now.timestamp()),
+        last_contact: now.timestamp(),
+        longitude: Some(lon),
+        latitude: Some(lat),
+        baro_altitude: Some(altitude_ft / FEET_PER_METER),
+        on_ground,
+        velocity: Some(if on_ground {
+            8.0
+        } else {
+            CRUISE_SPEED_KMH / 3.6
+        }),
+        true_track: Some(geo::initial_bearing(lat, lon, next_lat, next_lon)),
+        vertical_rate: Some(vertical_fpm / FEET_PER_METER / 60.0),
+        geo_altitude: Some(altitude_ft / FEET_PER_METER),
+        squawk: Some("2000".to_string()),
+        // Every scripted aircraft is a widebody
+        category: Some(AircraftCategory::Heavy),
+    }
+}
+
+fn route(flight: &DemoFlight) -> ((f64, f64), (f64, f64)) {
+    let coords = |code| {
+        airports::lookup(code)
+            .map(|a| (a.latitude, a.longitude))
+            .unwrap_or_default()
+    };
+    (coords(flight.origin), coords(flight.destination))
+}
+
+/// Build a schedule for `flight` consistent with its script at `now`.
+fn schedule(flight: &DemoFlight, fraction: Option<f64>, now: DateTime<Utc>) -> FlightData {
+    let (from, to) = route(flight);
+    let block = Duration::minutes(
+        (geo::haversine_km(from.0, from.1, to.0, to.1) / CRUISE_SPEED_KMH * 60.0) as i64 + 30,
+    );
+
+    let (status, departed, dep_delay) = match flight.script {
+        Script::EnRoute(start) => {
+            let fraction = fraction.unwrap_or(start);
+            let status = if fraction >= 1.0 { "landed" } else { "active" };
+            let elapsed = block.num_seconds() as f64 * fraction;
+            (status, now - Duration::seconds(elapsed as i64), 5)
+        }
+        Script::Delayed(minutes) => ("delayed", now + Duration::minutes(40), minutes),
+        Script::Landed => ("landed", now - block - Duration::minutes(35), 0),
+    };
+    let scheduled_dep = departed - Duration::minutes(dep_delay);
+    let scheduled_arr = scheduled_dep + block;
+    let estimated_arr = departed + block;
+    let has_departed = departed <= now;
+    let has_arrived = estimated_arr <= now;
+
+    let airport = |code: &str, scheduled, estimated, actual: Option<DateTime<Utc>>, delay| {
+        let icao = airports::lookup(code).map(|a| a.icao.to_string());
+        AirportInfo {
+            airport: Some(format!("{} International", code)),
+            iata: Some(code.to_string()),
+            icao,
+            scheduled: Some(timestamp(scheduled)),
+            estimated: Some(timestamp(estimated)),
+            actual: actual.map(timestamp),
+            delay,
+        }
+    };
+
+    FlightData {
+        flight_status: Some(status.to_string()),
+        departure: Some(airport(
+            flight.origin,
+            scheduled_dep,
+            departed,
+            has_departed.then_some(departed),
+            (dep_delay > 0).then_some(dep_delay as i32),
+        )),
+        arrival: Some(airport(
+            flight.destination,
+            scheduled_arr,
+            estimated_arr,
+            has_arrived.then_some(estimated_arr),
+            (dep_delay > 0).then_some(dep_delay as i32),
+        )),
+        airline: Some(AirlineInfo {
+            name: Some(flight.airline.to_string()),
+            iata: Some(flight.flight_number[..2].to_string()),
+        }),
+        flight: Some(FlightInfo {
+            iata: Some(flight.flight_number.to_string()),
+            icao: Some(flight.callsign.to_string()),
+            number: Some(flight.flight_number[2..].to_string()),
+            codeshared: None,
+        }),
+        aircraft: Some(AircraftInfo {
+            registration: Some(flight.registration.to_string()),
+            iata: Some(flight.aircraft.to_string()),
+            icao: Some(flight.aircraft.to_string()),
+        }),
+        // Demo positions come from the position provider
+        live: None,
+    }
+}
+
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%dT%H:%M:%S+00:00").to_string()
+}
+
+/// The demo makes no requests and caches nothing.
+fn diagnostics() -> ProviderDiagnostics {
+    ProviderDiagnostics {
+        name: "Demo",
+        auth: "offline, scripted flights".to_string(),
+        stats: ProviderStats::default(),
+        cache: CacheStats::default(),
+        breaker: BreakerState::Closed,
+        resolution: None,
+    }
+}
+
+/// Demo snapshots are always current.
+fn snapshot(state: Option<StateVector>) -> StateSnapshot {
+    StateSnapshot {
+        time: Utc::now().timestamp(),
+        state,
+        ..Default::default()
+    }
+}
+
+impl PositionProvider for DemoProvider {
+    fn search_flight<'a>(
+        &'a self,
+        flight_number: &'a str,
+    ) -> BoxFuture<'a, Result<StateSnapshot, AppError>> {
+        let flight_number = flight_number.trim().to_uppercase();
+        let state = self
+            .find(|f| f.flight_number == flight_number || f.callsign == flight_number)
+            .and_then(|f| self.position(f));
+        Box::pin(async move { Ok(snapshot(state)) })
+    }
+
+    fn get_state<'a>(&'a self, icao24: &'a str) -> BoxFuture<'a, Result<StateSnapshot, AppError>> {
+        let state = self
+            .find(|f| f.icao24.eq_ignore_ascii_case(icao24))
+            .and_then(|f| self.position(f));
+        Box::pin(async move { Ok(snapshot(state)) })
+    }
+
+    fn diagnostics(&self) -> ProviderDiagnostics {
+        diagnostics()
+    }
+}
+
+impl ScheduleProvider for DemoProvider {
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    fn diagnostics(&self) -> ProviderDiagnostics {
+        diagnostics()
+    }
+
+    fn get_flight<'a>(
+        &'a self,
+        flight_number: &'a str,
+        _date: Option<NaiveDate>,
+    ) -> BoxFuture<'a, Result<Option<FlightData>, AppError>> {
+        let flight_number = flight_number.trim().to_uppercase();
+        let result = self.find(|f| f.flight_number == flight_number).map(|f| {
+            let fraction = self
+                .progress
+                .lock()
+                .ok()
+                .and_then(|p| p.get(f.flight_number).copied());
+            schedule(f, fraction, Utc::now())
+        });
+        Box::pin(async move { Ok(result) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flight(number: &str) -> &'static DemoFlight {
+        FLIGHTS.iter().find(|f| f.flight_number == number).unwrap()
+    }
+
+    #[test]
+    fn test_demo_airports_are_known() {
+        for f in FLIGHTS {
+            assert!(airports::lookup(f.origin).is_some(), "{}", f.origin);
+            assert!(
+                airports::lookup(f.destination).is_some(),
+                "{}",
+                f.destination
+            );
+        }
+    }
+
+    #[test]
+    fn test_state_vector_phases() {
+        let now = Utc::now();
+        let f = flight("UA900");
+
+        let climbing = state_vector(f, 0.01, now);
+        assert!(climbing.vertical_rate.unwrap() > 0.0);
+        assert!(!climbing.on_ground);
+
+        let cruising = state_vector(f, 0.5, now);
+        assert_eq!(cruising.vertical_rate, Some(0.0));
+
+        let descending = state_vector(f, 0.99, now);
+        assert!(descending.vertical_rate.unwrap() < 0.0);
+
+        let arrived = state_vector(f, 1.0, now);
+        assert!(arrived.on_ground);
+        let fra = airports::lookup("FRA").unwrap();
+        assert!((arrived.latitude.unwrap() - fra.latitude).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_schedule_statuses() {
+        let now = Utc::now();
+        let status = |n: &str, fraction| schedule(flight(n), fraction, now).flight_status.unwrap();
+
+        assert_eq!(status("UA900", None), "active");
+        assert_eq!(status("UA900", Some(1.0)), "landed");
+        assert_eq!(status("AF007", None), "delayed");
+        assert_eq!(status("LH400", None), "landed");
+
+        let delayed = schedule(flight("AF007"), None, now);
+        assert_eq!(delayed.departure.unwrap().delay, Some(45));
+    }
+}
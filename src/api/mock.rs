@@ -0,0 +1,45 @@
+//! Local HTTP server standing in for a provider in client tests.
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::task::JoinHandle;
+
+/// Serve `requests` HTTP connections on a local port, answering each
+/// with `respond(request head)`. Returns the base URL and a handle
+/// yielding each request's lowercased head.
+pub async fn serve(
+    requests: usize,
+    respond: fn(&str) -> (&'static str, String),
+) -> (String, JoinHandle<Vec<String>>) {
+    serve_as(requests, "application/json", respond).await
+}
+
+/// Like [`serve`], answering with `content_type` instead of JSON.
+pub async fn serve_as(
+    requests: usize,
+    content_type: &'static str,
+    respond: fn(&str) -> (&'static str, String),
+) -> (String, JoinHandle<Vec<String>>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = tokio::spawn(async move {
+        let mut seen = Vec::new();
+        for _ in 0..requests {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0; 4096];
+            let n = socket.read(&mut buf).await.unwrap();
+            let head = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+            let (status, body) = respond(&head);
+            let response = format!(
+                "HTTP/1.1 {}\r\ncontent-type: {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                status,
+                content_type,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            seen.push(head);
+        }
+        seen
+    });
+    (base_url, handle)
+}
@@ -1,7 +1,39 @@
+//! Clients and response types for the flight data providers.
+
 mod aviationstack;
+mod breaker;
+mod demo;
+#[cfg(test)]
+mod mock;
 mod opensky;
+mod provider;
+mod raw;
+mod telemetry;
 mod types;
+mod weather;
+
+pub use aviationstack::{
+    AircraftInfo, AirlineInfo, AirportInfo, ApiError, AviationStackClient, AviationStackResponse,
+    CodeshareInfo, FlightData, FlightInfo, LiveInfo, Pagination,
+};
+pub use breaker::{BreakerState, CircuitBreaker};
+pub use demo::DemoProvider;
+pub use opensky::{
+    airline_name, callsign_prefix, iata_flight_number, missing_credential, normalize_callsign,
+    operating_airline, use_downloaded_airlines, AirlineCode, OpenSkyClient,
+};
+pub use provider::{PositionProvider, ScheduleProvider};
+pub use raw::{raw_payload, MAX_RAW_PAYLOAD_BYTES};
+pub use telemetry::{AuthMode, ProviderDiagnostics, ProviderStats, Telemetry};
+pub use types::{
+    AircraftCategory, FlightTrack, OpenSkyResponse, StateSnapshot, StateVector, Waypoint,
+};
+pub use weather::{StationWeather, WeatherClient};
 
-pub use aviationstack::{AviationStackClient, FlightData};
-pub use opensky::OpenSkyClient;
-pub use types::StateVector;
+/// An HTTP client that gives up on a request after `timeout`.
+fn http_client(timeout: std::time::Duration) -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .unwrap_or_default()
+}
@@ -1,118 +1,727 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
 
+use chrono::{Local, NaiveDate, Utc};
+use reqwest::header::CONTENT_TYPE;
 use reqwest::Client;
+use serde::de::DeserializeOwned;
+use tokio::sync::OnceCell;
+use tracing::{debug, info, warn};
 
-use super::types::{OpenSkyResponse, StateVector};
-use crate::cache::Cache;
+use super::breaker::CircuitBreaker;
+use super::http_client;
+use super::telemetry::{AuthMode, ProviderDiagnostics, Telemetry};
+use super::types::{FlightTrack, OpenSkyResponse, StateSnapshot, StateVector};
+use crate::cache::{Cache, PersistentCache};
 use crate::error::AppError;
+use crate::geo::BoundingBox;
 
 const OPENSKY_BASE_URL: &str = "https://opensky-network.org/api";
 const CACHE_TTL_SECS: u64 = 10; // 10 seconds - position data changes frequently
+/// Key of the one entry in the snapshot cache.
+const SNAPSHOT_KEY: &str = "all";
+/// Longest a request may take before it counts as failed.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
 
+/// Seconds between the snapshots `/states/all` serves anonymous users, who
+/// can get one up to this old; signed-in users get a finer one.
+const ANONYMOUS_RESOLUTION_SECS: u32 = 10;
+const AUTHENTICATED_RESOLUTION_SECS: u32 = 5;
+
+/// Tracks only serve to fill in the trail before tracking began, so they
+/// can be a few minutes old.
+const TRACK_CACHE_TTL_SECS: u64 = 300;
+const USAGE_FILE: &str = "opensky_usage.json";
+/// Usage counters only need to outlive the day they count.
+const USAGE_TTL_SECS: u64 = 2 * 86400;
+/// Degrees either side of an aircraft's approximate position to search
+/// before falling back to the whole world.
+const SEARCH_MARGIN_DEG: f64 = 3.0;
+
+/// Client for the OpenSky Network live state API.
 #[derive(Clone)]
 pub struct OpenSkyClient {
     client: Client,
+    base_url: String,
     username: Option<String>,
     password: Option<String>,
-    cache: Cache<Option<StateVector>>,
+    /// The credential variable left unset when only the other one is, so
+    /// requests are anonymous.
+    missing_credential: Option<&'static str>,
+    /// Set once the credentials have been rejected; later requests go out
+    /// anonymously for the rest of the session.
+    credentials_rejected: Arc<AtomicBool>,
+    /// Whether the check before the credentials' first use accepted them,
+    /// once it has run. Shared between clones, so it runs once.
+    probe: Arc<OnceCell<bool>>,
+    cache: Cache<StateSnapshot>,
+    /// The latest unbounded `/states/all` response. It holds every aircraft,
+    /// so callsign searches share it while it is fresh, and searches that
+    /// miss it together wait for a single request.
+    snapshot: Cache<Arc<OpenSkyResponse>>,
+    /// Flight tracks by ICAO24, `None` when OpenSky had none to give.
+    tracks: Cache<Option<FlightTrack>>,
+    /// Network requests made per local day, keyed by `YYYY-MM-DD`.
+    usage: PersistentCache<u32>,
+    telemetry: Telemetry,
+    /// Skips requests while the API keeps failing. Shared between clones.
+    breaker: CircuitBreaker,
+}
+
+impl Default for OpenSkyClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl OpenSkyClient {
+    /// Create a client, reading optional credentials from `OPENSKY_USERNAME`
+    /// and `OPENSKY_PASSWORD`.
     pub fn new() -> Self {
+        let var = |name| {
+            std::env::var(name)
+                .ok()
+                .filter(|v: &String| !v.trim().is_empty())
+        };
+        let (username, password) = (var("OPENSKY_USERNAME"), var("OPENSKY_PASSWORD"));
+        let missing = missing_credential(username.as_deref(), password.as_deref());
+        if let Some(missing) = missing {
+            warn!(
+                missing,
+                "Only one OpenSky credential is set; using anonymous access"
+            );
+        }
         Self {
-            client: Client::new(),
-            username: std::env::var("OPENSKY_USERNAME").ok(),
-            password: std::env::var("OPENSKY_PASSWORD").ok(),
+            client: http_client(REQUEST_TIMEOUT),
+            base_url: OPENSKY_BASE_URL.to_string(),
+            username,
+            password,
+            missing_credential: missing,
+            credentials_rejected: Arc::new(AtomicBool::new(false)),
+            probe: Arc::new(OnceCell::new()),
             cache: Cache::new(Duration::from_secs(CACHE_TTL_SECS)),
+            snapshot: Cache::new(Duration::from_secs(CACHE_TTL_SECS)),
+            tracks: Cache::new(Duration::from_secs(TRACK_CACHE_TTL_SECS)),
+            usage: PersistentCache::new(Duration::from_secs(USAGE_TTL_SECS), USAGE_FILE),
+            telemetry: Telemetry::default(),
+            breaker: CircuitBreaker::default(),
         }
     }
 
-    pub async fn search_flight(&self, flight_number: &str) -> Result<Option<StateVector>, AppError> {
-        let callsign = normalize_callsign(flight_number);
+    /// Give up on requests that take longer than `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.client = http_client(timeout);
+        self
+    }
+
+    /// Number of API requests made so far today (local time).
+    pub fn requests_today(&self) -> u32 {
+        self.usage
+            .get(&day_key(Local::now().date_naive()))
+            .unwrap_or(0)
+    }
+
+    fn record_request(&self) {
+        let key = day_key(Local::now().date_naive());
+        let count = self.usage.get(&key).unwrap_or(0);
+        self.usage.set(key, count + 1);
+    }
+
+    /// Forget every cached position, including aircraft not seen, and
+    /// every cached track.
+    pub fn clear_cache(&self) {
+        info!(
+            entries = self.cache.len(),
+            "Clearing the OpenSky position cache"
+        );
+        self.cache.clear();
+        self.snapshot.clear();
+        self.tracks.clear();
+    }
 
-        // Check cache first
-        if let Some(cached) = self.cache.get(&callsign) {
-            return Ok(cached);
+    /// How requests are authenticated right now: basic auth until the
+    /// credentials are rejected, if there are any.
+    pub fn auth_mode(&self) -> AuthMode {
+        match self.credentials() {
+            Some(_) => AuthMode::Basic,
+            None => AuthMode::Anonymous,
         }
+    }
+
+    /// Authentication state, request counters and cache usage.
+    pub fn diagnostics(&self) -> ProviderDiagnostics {
+        let auth = if let Some(missing) = self.missing_credential {
+            format!("anonymous ({} not set)", missing)
+        } else if self.username.is_none() || self.password.is_none() {
+            "anonymous".to_string()
+        } else if self.credentials_rejected.load(Ordering::Relaxed) {
+            "credentials rejected, anonymous".to_string()
+        } else if self.probe.get() == Some(&true) {
+            "basic auth".to_string()
+        } else {
+            "basic auth, not checked yet".to_string()
+        };
+        let resolution = match self.credentials() {
+            Some(_) => format!("{}s (authenticated)", AUTHENTICATED_RESOLUTION_SECS),
+            None => format!("{}s (anonymous)", ANONYMOUS_RESOLUTION_SECS),
+        };
+        ProviderDiagnostics {
+            name: "OpenSky",
+            auth,
+            stats: self.telemetry.stats(),
+            cache: self.cache.stats(),
+            breaker: self.breaker.state(),
+            resolution: Some(resolution),
+        }
+    }
 
-        let url = format!("{}/states/all", OPENSKY_BASE_URL);
+    /// Credentials to send, unless OpenSky has already rejected them.
+    fn credentials(&self) -> Option<(&str, &str)> {
+        if self.credentials_rejected.load(Ordering::Relaxed) {
+            return None;
+        }
+        Some((self.username.as_deref()?, self.password.as_deref()?))
+    }
 
-        let mut request = self.client.get(&url);
+    /// Check the credentials once, before their first use, against
+    /// `/states/own`, which costs no credits and so isn't counted. A
+    /// rejection switches to anonymous access as a rejected request would;
+    /// an answer that settles nothing leaves them to the first request.
+    async fn probe_credentials(&self) {
+        let Some((user, pass)) = self.credentials() else {
+            return;
+        };
+        self.probe
+            .get_or_init(|| async {
+                let url = format!("{}/states/own", self.base_url);
+                let response = self
+                    .client
+                    .get(&url)
+                    .basic_auth(user, Some(pass))
+                    .send()
+                    .await;
+                match response.map(|r| r.status().as_u16()) {
+                    Ok(401 | 403) => {
+                        warn!("OpenSky rejected credentials; continuing anonymously");
+                        self.credentials_rejected.store(true, Ordering::Relaxed);
+                        false
+                    }
+                    Ok(status) => {
+                        debug!(status, "OpenSky credential check");
+                        (200..300).contains(&status)
+                    }
+                    Err(e) => {
+                        debug!(error = %e.without_url(), "OpenSky credential check failed");
+                        false
+                    }
+                }
+            })
+            .await;
+    }
 
-        if let (Some(user), Some(pass)) = (&self.username, &self.password) {
+    /// Area to restrict a query to. Anonymous queries are billed by area, so
+    /// they are narrowed around `near` when it is known; authenticated ones
+    /// are left unbounded.
+    fn search_area(&self, near: Option<(f64, f64)>) -> Option<BoundingBox> {
+        if self.credentials().is_some() {
+            return None;
+        }
+        near.map(|(lat, lon)| BoundingBox::around(lat, lon, SEARCH_MARGIN_DEG))
+    }
+
+    /// GET a `/states/all` URL and decode the response.
+    ///
+    /// A 401/403 is reported as [`AppError::Unauthorized`] and switches the
+    /// client to anonymous access for subsequent requests.
+    async fn fetch_states(&self, url: &str) -> Result<OpenSkyResponse, AppError> {
+        self.probe_credentials().await;
+        self.record_request();
+        let result = self.request_states(url).await;
+        self.telemetry.record(&result);
+        self.breaker.record(&result);
+        result
+    }
+
+    async fn request_states(&self, url: &str) -> Result<OpenSkyResponse, AppError> {
+        let credentials = self.credentials();
+        debug!(%url, authenticated = credentials.is_some(), "OpenSky request");
+
+        let mut request = self.client.get(url);
+        if let Some((user, pass)) = credentials {
             request = request.basic_auth(user, Some(pass));
         }
 
         let response = request.send().await?;
+        debug!(status = %response.status(), "OpenSky response");
 
-        if response.status() == 429 {
-            return Err(AppError::RateLimited);
+        match response.status().as_u16() {
+            401 | 403 => {
+                if credentials.is_some() {
+                    warn!(status = %response.status(), "OpenSky rejected credentials; continuing anonymously");
+                    self.credentials_rejected.store(true, Ordering::Relaxed);
+                }
+                return Err(AppError::Unauthorized);
+            }
+            429 => return Err(AppError::RateLimited),
+            _ => {}
         }
 
-        let data: OpenSkyResponse = response
-            .json()
+        let status = response.status().as_u16();
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body = response.text().await?;
+        parse_states(
+            status,
+            content_type.as_deref(),
+            &body,
+            Utc::now().timestamp(),
+        )
+    }
+
+    /// Find the live state of the aircraft flying `flight_number` by scanning
+    /// all current states for a matching callsign. Searches share one
+    /// snapshot of all states, so adding several flights at once costs one
+    /// request.
+    pub async fn search_flight(&self, flight_number: &str) -> Result<StateSnapshot, AppError> {
+        self.search_flight_near(flight_number, None).await
+    }
+
+    /// Like [`search_flight`](Self::search_flight), but look around `near`
+    /// first and only scan the whole world if the aircraft isn't there.
+    pub async fn search_flight_near(
+        &self,
+        flight_number: &str,
+        near: Option<(f64, f64)>,
+    ) -> Result<StateSnapshot, AppError> {
+        let callsign = normalize_callsign(flight_number);
+        self.cache
+            .get_or_fetch(&callsign, || self.fetch_by_callsign(&callsign, near))
             .await
-            .map_err(|e| AppError::Parse(e.to_string()))?;
+    }
 
-        let flight = data
-            .states
-            .unwrap_or_default()
-            .into_iter()
-            .find(|state| {
-                state
-                    .callsign
-                    .as_ref()
-                    .map(|cs| cs.to_uppercase().starts_with(&callsign.to_uppercase()))
-                    .unwrap_or(false)
-            });
+    async fn fetch_by_callsign(
+        &self,
+        callsign: &str,
+        near: Option<(f64, f64)>,
+    ) -> Result<StateSnapshot, AppError> {
+        self.breaker.check("OpenSky")?;
+        let mut snapshot = StateSnapshot::default();
+        for area in [self.search_area(near), None] {
+            let data = match &area {
+                Some(area) => {
+                    let url = states_url(&self.base_url, None, Some(area), true);
+                    Arc::new(self.fetch_states(&url).await?)
+                }
+                None => self.all_states().await?,
+            };
+
+            let states = data.states.as_ref().map_or(0, Vec::len);
+            let all = data.states.as_deref().unwrap_or_default();
+            let mut candidates = match_callsign(all, callsign);
+            let flight = if candidates.len() == 1 {
+                candidates.pop()
+            } else {
+                None
+            };
+
+            debug!(
+                %callsign,
+                states,
+                bounded = area.is_some(),
+                icao24 = flight.as_ref().map(|sv| sv.icao24.as_str()),
+                candidates = candidates.len(),
+                "OpenSky callsign search"
+            );
+            snapshot = StateSnapshot {
+                time: data.time,
+                state: flight,
+                candidates,
+            };
+            if snapshot.state.is_some() || !snapshot.candidates.is_empty() || area.is_none() {
+                break;
+            }
+        }
+        Ok(snapshot)
+    }
 
-        // Cache by callsign
-        self.cache.set(callsign, flight.clone());
+    /// Every aircraft's state, from the shared snapshot while it is fresh.
+    async fn all_states(&self) -> Result<Arc<OpenSkyResponse>, AppError> {
+        self.snapshot
+            .get_or_fetch(SNAPSHOT_KEY, || async {
+                let url = states_url(&self.base_url, None, None, true);
+                self.fetch_states(&url).await.map(Arc::new)
+            })
+            .await
+    }
 
-        Ok(flight)
+    /// Fetch the current state of a single aircraft by ICAO24 address.
+    pub async fn get_state(&self, icao24: &str) -> Result<StateSnapshot, AppError> {
+        self.get_state_near(icao24, None).await
     }
 
-    pub async fn get_state(&self, icao24: &str) -> Result<Option<StateVector>, AppError> {
+    /// Like [`get_state`](Self::get_state), but look around `near` first and
+    /// only query without bounds if the aircraft isn't there.
+    pub async fn get_state_near(
+        &self,
+        icao24: &str,
+        near: Option<(f64, f64)>,
+    ) -> Result<StateSnapshot, AppError> {
         let icao24_lower = icao24.to_lowercase();
+        self.cache
+            .get_or_fetch(&icao24_lower, || self.fetch_by_icao24(&icao24_lower, near))
+            .await
+    }
+
+    async fn fetch_by_icao24(
+        &self,
+        icao24_lower: &str,
+        near: Option<(f64, f64)>,
+    ) -> Result<StateSnapshot, AppError> {
+        self.breaker.check("OpenSky")?;
+        let mut snapshot = StateSnapshot::default();
+        for area in [self.search_area(near), None] {
+            // The aircraft's category came with the search that found it
+            let url = states_url(&self.base_url, Some(icao24_lower), area.as_ref(), false);
+            let data = self.fetch_states(&url).await?;
 
-        // Check cache first
-        if let Some(cached) = self.cache.get(&icao24_lower) {
-            return Ok(cached);
+            snapshot = StateSnapshot {
+                time: data.time,
+                state: data.states.and_then(|s| s.into_iter().next()),
+                ..Default::default()
+            };
+            if snapshot.state.is_some() || area.is_none() {
+                break;
+            }
+            debug!(icao24 = %icao24_lower, "OpenSky bounded query missed; widening");
         }
+        Ok(snapshot)
+    }
 
-        let url = format!(
-            "{}/states/all?icao24={}",
-            OPENSKY_BASE_URL,
-            icao24_lower
-        );
+    /// Fetch the waypoints of an aircraft's current flight from
+    /// `/tracks/all`, for the part flown before tracking began.
+    ///
+    /// Only registered users may call the endpoint. Anonymous clients get
+    /// `None` without a request, and so does a 401, 403 or 404 answer.
+    pub async fn get_track(&self, icao24: &str) -> Result<Option<FlightTrack>, AppError> {
+        self.probe_credentials().await;
+        let Some((user, pass)) = self.credentials() else {
+            return Ok(None);
+        };
+        let icao24_lower = icao24.to_lowercase();
+        self.tracks
+            .get_or_fetch(&icao24_lower, || async {
+                self.breaker.check("OpenSky")?;
+                self.record_request();
+                let url = format!(
+                    "{}/tracks/all?icao24={}&time=0",
+                    self.base_url, icao24_lower
+                );
+                let result = self.request_track(&url, user, pass).await;
+                self.telemetry.record(&result);
+                self.breaker.record(&result);
+                let track = result?;
+                debug!(
+                    icao24 = %icao24_lower,
+                    waypoints = track.as_ref().map_or(0, |t| t.path.len()),
+                    "OpenSky track"
+                );
+                Ok(track)
+            })
+            .await
+    }
 
-        let mut request = self.client.get(&url);
+    async fn request_track(
+        &self,
+        url: &str,
+        user: &str,
+        pass: &str,
+    ) -> Result<Option<FlightTrack>, AppError> {
+        debug!(%url, "OpenSky track request");
+        let response = self
+            .client
+            .get(url)
+            .basic_auth(user, Some(pass))
+            .send()
+            .await?;
+        debug!(status = %response.status(), "OpenSky track response");
 
-        if let (Some(user), Some(pass)) = (&self.username, &self.password) {
-            request = request.basic_auth(user, Some(pass));
+        let status = response.status().as_u16();
+        match status {
+            401 | 403 | 404 => return Ok(None),
+            429 => return Err(AppError::RateLimited),
+            _ => {}
         }
 
-        let response = request.send().await?;
-
-        if response.status() == 429 {
-            return Err(AppError::RateLimited);
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body = response.text().await?;
+        if body.trim().is_empty() {
+            return Ok(None);
         }
+        decode(status, content_type.as_deref(), &body).map(Some)
+    }
+}
 
-        let data: OpenSkyResponse = response
-            .json()
-            .await
-            .map_err(|e| AppError::Parse(e.to_string()))?;
+/// How a transponder callsign matches the searched one, best first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum CallsignMatch {
+    Exact,
+    /// Equal once the space padding is trimmed
+    Padded,
+    /// The same flight number written with or without leading zeros
+    /// (`BAW028` for `BAW28`)
+    LeadingZeros,
+    /// One letter more, as for a re-dispatch (`DLH400A` for `DLH400`)
+    Suffixed,
+}
 
-        let result = data.states.and_then(|s| s.into_iter().next());
+/// A callsign's airline letters, flight number without leading zeros and
+/// the letters after it. `None` unless it has that shape.
+fn callsign_parts(callsign: &str) -> Option<(&str, &str, &str)> {
+    let number_start = callsign.find(|c: char| c.is_ascii_digit())?;
+    let (airline, rest) = callsign.split_at(number_start);
+    if airline.is_empty() || !airline.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let number_end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let (number, suffix) = rest.split_at(number_end);
+    if !suffix.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let number = match number.trim_start_matches('0') {
+        "" => "0",
+        number => number,
+    };
+    Some((airline, number, suffix))
+}
 
-        // Cache by icao24
-        self.cache.set(icao24_lower, result.clone());
+/// How well the transponder callsign `candidate` matches the searched
+/// `target`, or `None` if it is another flight: `UAL1234` and `UAL12AB`
+/// never match `UAL12`, and a suffix only matches a target without one.
+fn callsign_matches(candidate: &str, target: &str) -> Option<CallsignMatch> {
+    let candidate = candidate.to_uppercase();
+    let target = target.trim().to_uppercase();
+    if candidate == target {
+        return Some(CallsignMatch::Exact);
+    }
+    let trimmed = candidate.trim();
+    if trimmed == target {
+        return Some(CallsignMatch::Padded);
+    }
+    let (airline, number, suffix) = callsign_parts(trimmed)?;
+    let (want_airline, want_number, want_suffix) = callsign_parts(&target)?;
+    if airline != want_airline || number != want_number {
+        return None;
+    }
+    if suffix == want_suffix {
+        Some(CallsignMatch::LeadingZeros)
+    } else if want_suffix.is_empty() && suffix.len() == 1 {
+        Some(CallsignMatch::Suffixed)
+    } else {
+        None
+    }
+}
+
+/// The aircraft among `states` that best match `callsign` (already
+/// normalized): none, the one flying it, or several that can't be told
+/// apart.
+fn match_callsign(states: &[StateVector], callsign: &str) -> Vec<StateVector> {
+    let callsign = callsign.trim().to_uppercase();
+    let ranked: Vec<(CallsignMatch, &StateVector)> = states
+        .iter()
+        .filter_map(|state| {
+            let rank = callsign_matches(state.callsign.as_deref()?, &callsign)?;
+            Some((rank, state))
+        })
+        .collect();
+    let Some(best) = ranked.iter().map(|(rank, _)| *rank).min() else {
+        return Vec::new();
+    };
+    ranked
+        .into_iter()
+        .filter(|(rank, _)| *rank == best)
+        .map(|(_, state)| state.clone())
+        .collect()
+}
+
+/// The OpenSky credential variable that is missing when only the other
+/// one is set, in which case requests go out anonymously.
+///
+/// ```
+/// use flight_tracker_tui::api::missing_credential;
+///
+/// assert_eq!(missing_credential(Some("pilot"), None), Some("OPENSKY_PASSWORD"));
+/// assert_eq!(missing_credential(None, Some("secret")), Some("OPENSKY_USERNAME"));
+/// assert_eq!(missing_credential(Some("pilot"), Some("secret")), None);
+/// assert_eq!(missing_credential(None, None), None);
+/// ```
+pub fn missing_credential(username: Option<&str>, password: Option<&str>) -> Option<&'static str> {
+    match (username, password) {
+        (Some(_), None) => Some("OPENSKY_PASSWORD"),
+        (None, Some(_)) => Some("OPENSKY_USERNAME"),
+        _ => None,
+    }
+}
+
+/// Usage counter key for a local calendar day.
+fn day_key(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
 
-        Ok(result)
+/// Build a `/states/all` URL, optionally filtered to one aircraft and to an
+/// area. With `extended`, states carry their emitter category, which is only
+/// wanted when looking for an aircraft not known yet.
+fn states_url(
+    base_url: &str,
+    icao24: Option<&str>,
+    area: Option<&BoundingBox>,
+    extended: bool,
+) -> String {
+    let mut params = Vec::new();
+    if let Some(icao24) = icao24 {
+        params.push(format!("icao24={}", icao24));
     }
+    if let Some(area) = area {
+        params.push(format!(
+            "lamin={:.4}&lamax={:.4}&lomin={:.4}&lomax={:.4}",
+            area.min_lat, area.max_lat, area.min_lon, area.max_lon
+        ));
+    }
+
+    if extended {
+        params.push("extended=1".to_string());
+    }
+
+    if params.is_empty() {
+        return format!("{}/states/all", base_url);
+    }
+    format!("{}/states/all?{}", base_url, params.join("&"))
+}
+
+/// Interpret a `/states/all` response received at `now` (Unix seconds).
+///
+/// An empty body means no aircraft, like `"states": null`. A body that
+/// isn't JSON, such as a load balancer's HTML error page, or JSON cut off
+/// mid-way is a parse error with a short excerpt rather than the whole body.
+fn parse_states(
+    status: u16,
+    content_type: Option<&str>,
+    body: &str,
+    now: i64,
+) -> Result<OpenSkyResponse, AppError> {
+    if body.trim().is_empty() {
+        debug!(
+            status,
+            "OpenSky returned an empty body; treating as no aircraft"
+        );
+        return Ok(OpenSkyResponse {
+            time: now,
+            states: None,
+        });
+    }
+    decode(status, content_type, body)
+}
+
+/// Decode a JSON response body, reporting anything else as a parse error.
+fn decode<T: DeserializeOwned>(
+    status: u16,
+    content_type: Option<&str>,
+    body: &str,
+) -> Result<T, AppError> {
+    if let Some(content_type) = content_type.filter(|ct| !ct.contains("json")) {
+        return Err(AppError::parse(format!(
+            "expected JSON but got {} (HTTP {}): {}",
+            content_type, status, body
+        )));
+    }
+    serde_json::from_str(body).map_err(|e| AppError::parse(format!("{} (HTTP {})", e, status)))
+}
+
+/// IATA and ICAO designators with the airline's name.
+pub type AirlineCode = (&'static str, &'static str, &'static str);
+
+/// Airlines from a downloaded table, searched before [`AIRLINE_CODES`].
+static DOWNLOADED_AIRLINES: OnceLock<Vec<AirlineCode>> = OnceLock::new();
+
+/// The airlines built in, for when there's no downloaded table or it lacks
+/// one.
+const AIRLINE_CODES: &[AirlineCode] = &[
+    ("UA", "UAL", "United Airlines"),
+    ("AA", "AAL", "American Airlines"),
+    ("DL", "DAL", "Delta Air Lines"),
+    ("BA", "BAW", "British Airways"),
+    ("AF", "AFR", "Air France"),
+    ("LH", "DLH", "Lufthansa"),
+    ("EK", "UAE", "Emirates"),
+    ("QF", "QFA", "Qantas"),
+    ("SQ", "SIA", "Singapore Airlines"),
+    ("CX", "CPA", "Cathay Pacific"),
+    ("JL", "JAL", "Japan Airlines"),
+    ("NH", "ANA", "All Nippon Airways"),
+    ("KL", "KLM", "KLM"),
+    ("IB", "IBE", "Iberia"),
+    ("WN", "SWA", "Southwest Airlines"),
+    ("B6", "JBU", "JetBlue Airways"),
+    ("AS", "ASA", "Alaska Airlines"),
+    ("F9", "FFT", "Frontier Airlines"),
+    ("NK", "NKS", "Spirit Airlines"),
+    ("AC", "ACA", "Air Canada"),
+    ("VS", "VIR", "Virgin Atlantic"),
+    ("TK", "THY", "Turkish Airlines"),
+    ("EY", "ETD", "Etihad Airways"),
+    ("QR", "QTR", "Qatar Airways"),
+    ("EI", "EIN", "Aer Lingus"),
+    ("AY", "FIN", "Finnair"),
+    ("SK", "SAS", "SAS"),
+    ("TP", "TAP", "TAP Air Portugal"),
+    ("LX", "SWR", "Swiss"),
+    ("OS", "AUA", "Austrian Airlines"),
+    // Regional and wet-lease operators flying under the brands above
+    ("U2", "EZY", "easyJet"),
+    ("EC", "EJU", "easyJet Europe"),
+    ("DS", "EZS", "easyJet Switzerland"),
+    ("WK", "EDW", "Edelweiss Air"),
+    ("CL", "CLH", "Lufthansa CityLine"),
+    ("CJ", "CFE", "BA CityFlyer"),
+    ("OO", "SKW", "SkyWest Airlines"),
+    ("YX", "RPA", "Republic Airways"),
+    ("MQ", "ENY", "Envoy Air"),
+];
+
+/// Look airlines up in `codes` before the built-in table from now on. Only
+/// the first call has any effect; returns whether this one did.
+pub fn use_downloaded_airlines(codes: Vec<AirlineCode>) -> bool {
+    DOWNLOADED_AIRLINES.set(codes).is_ok()
+}
+
+/// Every airline known, downloaded ones first.
+fn airline_codes() -> impl Iterator<Item = &'static AirlineCode> {
+    DOWNLOADED_AIRLINES
+        .get()
+        .into_iter()
+        .flatten()
+        .chain(AIRLINE_CODES)
 }
 
-fn normalize_callsign(flight_number: &str) -> String {
+/// Convert an IATA flight number (e.g. `UA123`) into the ICAO callsign used
+/// by ADS-B transponders (e.g. `UAL123`). Unknown airline codes pass through.
+///
+/// ```
+/// use flight_tracker_tui::api::normalize_callsign;
+///
+/// assert_eq!(normalize_callsign("ua123"), "UAL123");
+/// assert_eq!(normalize_callsign("XY123"), "XY123");
+/// ```
+pub fn normalize_callsign(flight_number: &str) -> String {
     let flight_number = flight_number.trim().to_uppercase();
 
     let split_pos = flight_number
@@ -126,46 +735,266 @@ fn normalize_callsign(flight_number: &str) -> String {
 
     let (airline, number) = flight_number.split_at(split_pos);
 
-    let icao_code = match airline {
-        "UA" => "UAL",
-        "AA" => "AAL",
-        "DL" => "DAL",
-        "BA" => "BAW",
-        "AF" => "AFR",
-        "LH" => "DLH",
-        "EK" => "UAE",
-        "QF" => "QFA",
-        "SQ" => "SIA",
-        "CX" => "CPA",
-        "JL" => "JAL",
-        "NH" => "ANA",
-        "KL" => "KLM",
-        "IB" => "IBE",
-        "WN" => "SWA",
-        "B6" => "JBU",
-        "AS" => "ASA",
-        "F9" => "FFT",
-        "NK" => "NKS",
-        "AC" => "ACA",
-        "VS" => "VIR",
-        "TK" => "THY",
-        "EY" => "ETD",
-        "QR" => "QTR",
-        "EI" => "EIN",
-        "AY" => "FIN",
-        "SK" => "SAS",
-        "TP" => "TAP",
-        "LX" => "SWR",
-        "OS" => "AUA",
-        _ => airline,
-    };
+    let icao_code = airline_codes()
+        .find(|(iata, _, _)| *iata == airline)
+        .map_or(airline, |(_, icao, _)| icao);
 
     format!("{}{}", icao_code, number)
 }
 
+/// Convert an ICAO callsign (e.g. `BAW285`) back into the IATA flight number
+/// schedule APIs expect (e.g. `BA285`). Anything that doesn't start with a
+/// known three-letter ICAO airline code passes through.
+///
+/// ```
+/// use flight_tracker_tui::api::iata_flight_number;
+///
+/// assert_eq!(iata_flight_number("baw285"), "BA285");
+/// assert_eq!(iata_flight_number("BA285"), "BA285");
+/// ```
+pub fn iata_flight_number(callsign: &str) -> String {
+    let callsign = callsign.trim().to_uppercase();
+
+    let Some((airline, number)) = callsign.split_at_checked(3) else {
+        return callsign;
+    };
+    if !airline.chars().all(|c| c.is_ascii_alphabetic())
+        || !number.starts_with(|c: char| c.is_ascii_digit())
+    {
+        return callsign;
+    }
+
+    match airline_codes().find(|(_, icao, _)| *icao == airline) {
+        Some((iata, _, _)) => format!("{}{}", iata, number),
+        None => callsign,
+    }
+}
+
+/// The three-letter ICAO airline designator a callsign starts with, if it
+/// looks like an airline callsign rather than a registration.
+///
+/// ```
+/// use flight_tracker_tui::api::callsign_prefix;
+///
+/// assert_eq!(callsign_prefix("eju73ab"), Some("EJU".to_string()));
+/// assert_eq!(callsign_prefix("N12345"), None);
+/// ```
+pub fn callsign_prefix(callsign: &str) -> Option<String> {
+    let callsign = callsign.trim().to_uppercase();
+    let (airline, rest) = callsign.split_at_checked(3)?;
+    if airline.chars().all(|c| c.is_ascii_alphabetic())
+        && rest.starts_with(|c: char| c.is_ascii_digit())
+    {
+        Some(airline.to_string())
+    } else {
+        None
+    }
+}
+
+/// Name of the airline behind an ICAO designator, e.g. `EDW` → Edelweiss Air.
+pub fn airline_name(icao: &str) -> Option<&'static str> {
+    airline_codes()
+        .find(|(_, code, _)| code.eq_ignore_ascii_case(icao))
+        .map(|(_, _, name)| *name)
+}
+
+/// The airline actually flying `callsign` when it isn't the one selling
+/// `flight_number` as `airline`, as with codeshares and wet leases.
+///
+/// The callsign's prefix names the operator. It counts as the marketing
+/// airline when it matches the flight number's designator or when the
+/// airline name already starts with the operator's name.
+///
+/// ```
+/// use flight_tracker_tui::api::operating_airline;
+///
+/// assert_eq!(operating_airline("UA9", "EDW9", "United Airlines"), Some("Edelweiss Air"));
+/// assert_eq!(operating_airline("UA9", "UAL9", "United Airlines"), None);
+/// ```
+pub fn operating_airline(
+    flight_number: &str,
+    callsign: &str,
+    airline: &str,
+) -> Option<&'static str> {
+    let operator = callsign_prefix(callsign)?;
+    if callsign_prefix(&normalize_callsign(flight_number)).as_deref() == Some(operator.as_str()) {
+        return None;
+    }
+    let name = airline_name(&operator)?;
+    let airline = airline.trim().to_lowercase();
+    if airline.starts_with(&name.to_lowercase()) {
+        return None;
+    }
+    Some(name)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::mock;
+
+    /// A state with only the fields callsign matching looks at.
+    fn state(icao24: &str, callsign: &str) -> StateVector {
+        StateVector {
+            icao24: icao24.to_string(),
+            callsign: Some(callsign.to_string()),
+            origin_country: String::new(),
+            time_position: None,
+            last_contact: 0,
+            longitude: None,
+            latitude: None,
+            baro_altitude: None,
+            on_ground: false,
+            velocity: None,
+            true_track: None,
+            vertical_rate: None,
+            geo_altitude: None,
+            squawk: None,
+            category: None,
+        }
+    }
+
+    /// ICAO24 addresses of the best matches for a flight number.
+    fn matches(states: Vec<StateVector>, flight_number: &str) -> Vec<String> {
+        match_callsign(&states, &normalize_callsign(flight_number))
+            .into_iter()
+            .map(|s| s.icao24)
+            .collect()
+    }
+
+    #[test]
+    fn test_callsign_matches() {
+        use CallsignMatch::*;
+        let pairs = [
+            // (transponder callsign, searched callsign, match)
+            ("UAL12", "UAL12", Some(Exact)),
+            ("ual12", "UAL12", Some(Exact)),
+            ("UAL12   ", "UAL12", Some(Padded)),
+            ("DLH400  ", "dlh400 ", Some(Padded)),
+            ("N123AB  ", "N123AB", Some(Padded)),
+            // Leading zeros either way round
+            ("BAW028  ", "BAW28", Some(LeadingZeros)),
+            ("BAW28   ", "BAW028", Some(LeadingZeros)),
+            ("KLM0601 ", "KLM601", Some(LeadingZeros)),
+            ("SWR8    ", "SWR008", Some(LeadingZeros)),
+            ("AFR0    ", "AFR000", Some(LeadingZeros)),
+            ("BAW028A ", "BAW28A", Some(LeadingZeros)),
+            // One trailing letter, for a re-dispatch or repeated number
+            ("DLH400A ", "DLH400", Some(Suffixed)),
+            ("UAL12A  ", "UAL12", Some(Suffixed)),
+            ("AFR1234X", "AFR1234", Some(Suffixed)),
+            ("DLH040A ", "DLH40", Some(Suffixed)),
+            // Other flights
+            ("UAL1234", "UAL12", None),
+            ("UAL120", "UAL12", None),
+            ("UAL1", "UAL12", None),
+            ("DLH4001 ", "DLH400", None),
+            ("AAL100", "AAL10", None),
+            ("UAL12A3", "UAL12", None),
+            ("EZY12XY ", "EZY12", None),
+            ("DLH400AB", "DLH400", None),
+            ("DLH400  ", "DLH400A", None),
+            ("DLH400B ", "DLH400A", None),
+            ("BAW285", "AAL285", None),
+            ("N123ABC ", "N123AB", None),
+            ("        ", "BAW1", None),
+        ];
+        for (candidate, target, expected) in pairs {
+            assert_eq!(
+                callsign_matches(candidate, target),
+                expected,
+                "{candidate:?} {target}"
+            );
+        }
+        assert!(Exact < Padded && Padded < LeadingZeros && LeadingZeros < Suffixed);
+    }
+
+    #[test]
+    fn test_leading_zeros_rank_between_padded_and_suffixed() {
+        let states = vec![state("e1", "BAW28A"), state("e2", "BAW028  ")];
+        assert_eq!(matches(states, "BA28"), vec!["e2"]);
+        let states = vec![
+            state("e1", "BAW28A"),
+            state("e2", "BAW028  "),
+            state("e3", "BAW28   "),
+        ];
+        assert_eq!(matches(states, "BA28"), vec!["e3"]);
+    }
+
+    #[test]
+    fn test_exact_callsign_beats_longer_flight_number() {
+        // Searching UA12 must not latch onto UAL1234, listed first
+        let states = vec![
+            state("a1", "UAL1234"),
+            state("a2", "UAL12"),
+            state("a3", "UAL120"),
+        ];
+        assert_eq!(matches(states, "UA12"), vec!["a2"]);
+
+        // DL8 is not any of DL800-DL899
+        let states = vec![
+            state("b1", "DAL800"),
+            state("b2", "DAL812"),
+            state("b3", "DAL899"),
+        ];
+        assert!(matches(states, "DL8").is_empty());
+    }
+
+    #[test]
+    fn test_exact_beats_padded_beats_suffixed() {
+        let states = vec![state("c1", "BAW285A"), state("c2", "BAW285  ")];
+        assert_eq!(matches(states, "BA285"), vec!["c2"]);
+
+        let states = vec![
+            state("c1", "BAW285A"),
+            state("c2", "BAW285  "),
+            state("c3", "BAW285"),
+        ];
+        assert_eq!(matches(states, "BA285"), vec!["c3"]);
+
+        // A letter suffix alone still matches
+        let states = vec![state("c1", "BAW285A"), state("c4", "BAW2851")];
+        assert_eq!(matches(states, "BA285"), vec!["c1"]);
+    }
+
+    #[test]
+    fn test_ambiguous_matches_are_all_returned() {
+        let states = vec![
+            state("d1", "UAL12A"),
+            state("d2", "UAL12B"),
+            state("d3", "UAL1234"),
+        ];
+        assert_eq!(matches(states, "UA12"), vec!["d1", "d2"]);
+
+        // Aircraft without a callsign never match
+        let mut anonymous = state("d4", "");
+        anonymous.callsign = None;
+        assert!(matches(vec![anonymous], "UA12").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ambiguous_search_picks_nothing() {
+        let (base_url, server) = mock::serve(1, |_| {
+            let states = [("d1", "UAL12A"), ("d2", "UAL12B")]
+                .map(|(icao, cs)| {
+                    UAL123_STATE
+                        .replacen("a808c4", icao, 1)
+                        .replacen("UAL123", cs, 1)
+                })
+                .join(",");
+            (
+                "200 OK",
+                format!(r#"{{"time":1700000005,"states":[{}]}}"#, states),
+            )
+        })
+        .await;
+        let client = client_with(base_url, None);
+
+        let snapshot = client.search_flight("UA12").await.unwrap();
+        assert!(snapshot.state.is_none());
+        assert_eq!(snapshot.candidates.len(), 2);
+        server.await.unwrap();
+    }
 
     #[test]
     fn test_normalize_callsign_major_us_airlines() {
@@ -186,6 +1015,75 @@ mod tests {
         assert_eq!(normalize_callsign("SQ26"), "SIA26");
     }
 
+    #[test]
+    fn test_iata_flight_number() {
+        assert_eq!(iata_flight_number("BAW285"), "BA285");
+        assert_eq!(iata_flight_number("UAL900"), "UA900");
+        assert_eq!(iata_flight_number("JBU1234"), "B61234");
+        assert_eq!(iata_flight_number(" dlh400 "), "LH400");
+        // Already IATA, unknown ICAO airline, or not a flight number
+        assert_eq!(iata_flight_number("BA285"), "BA285");
+        assert_eq!(iata_flight_number("XYZ123"), "XYZ123");
+        assert_eq!(iata_flight_number("BAWX"), "BAWX");
+        assert_eq!(iata_flight_number("BA"), "BA");
+    }
+
+    #[test]
+    fn test_airline_codes_round_trip() {
+        for (iata, icao, _) in AIRLINE_CODES {
+            let flight_number = format!("{}123", iata);
+            let callsign = normalize_callsign(&flight_number);
+            if iata.chars().all(|c| c.is_ascii_alphabetic()) {
+                assert_eq!(callsign, format!("{}123", icao));
+            }
+            assert_eq!(iata_flight_number(&format!("{}123", icao)), flight_number);
+        }
+    }
+
+    #[test]
+    fn test_callsign_prefix() {
+        assert_eq!(callsign_prefix("EJU73AB"), Some("EJU".to_string()));
+        assert_eq!(callsign_prefix(" ual123 "), Some("UAL".to_string()));
+        // Registrations, IATA flight numbers and bare designators
+        assert_eq!(callsign_prefix("GABCD"), None);
+        assert_eq!(callsign_prefix("UA123"), None);
+        assert_eq!(callsign_prefix("UAL"), None);
+        assert_eq!(callsign_prefix(""), None);
+    }
+
+    #[test]
+    fn test_operating_airline() {
+        // easyJet Europe flying an easyJet flight number
+        assert_eq!(
+            operating_airline("U28341", "EJU73AB", "easyJet"),
+            Some("easyJet Europe")
+        );
+        assert_eq!(
+            operating_airline("UA9", "EDW9", "United Airlines"),
+            Some("Edelweiss Air")
+        );
+        // Same designator, or an airline name the operator's name already heads
+        assert_eq!(operating_airline("U28341", "EZY8341", "easyJet"), None);
+        assert_eq!(operating_airline("EZY8341", "EZY8341", "Easyjet"), None);
+        assert_eq!(operating_airline("WK9", "EDW9", "Edelweiss Air AG"), None);
+        // Unknown operators and callsigns that aren't airline callsigns
+        assert_eq!(operating_airline("UA9", "XYZ9", "United Airlines"), None);
+        assert_eq!(operating_airline("UA9", "", "United Airlines"), None);
+        assert_eq!(operating_airline("UA9", "N12345", "United Airlines"), None);
+    }
+
+    #[test]
+    fn test_airline_names_unique() {
+        for (i, (_, icao, name)) in AIRLINE_CODES.iter().enumerate() {
+            assert_eq!(airline_name(icao), Some(*name));
+            assert!(
+                !AIRLINE_CODES[i + 1..].iter().any(|(_, c, _)| c == icao),
+                "{icao}"
+            );
+        }
+        assert_eq!(airline_name("edw"), Some("Edelweiss Air"));
+    }
+
     #[test]
     fn test_normalize_callsign_case_insensitive() {
         assert_eq!(normalize_callsign("ua123"), "UAL123");
@@ -213,6 +1111,418 @@ mod tests {
         assert_eq!(normalize_callsign("BAW285"), "BAW285");
     }
 
+    /// One state vector for `UAL123`, as OpenSky encodes it.
+    const UAL123_STATE: &str = r#"["a808c4", "UAL123  ", "United States", 1700000000, 1700000001,
+        -73.5, 41.0, 10668.0, false, 240.5, 87.0, 0.0, null, 10900.0, "1200", false, 0]"#;
+
+    /// 401 for any request carrying credentials, an empty state list otherwise.
+    fn reject_credentials(head: &str) -> (&'static str, String) {
+        if head.contains("authorization:") {
+            ("401 Unauthorized", String::new())
+        } else {
+            ("200 OK", r#"{"time":0,"states":[]}"#.to_string())
+        }
+    }
+
+    /// Nothing inside a bounding box, `UAL123` in the unbounded snapshot.
+    fn only_unbounded(head: &str) -> (&'static str, String) {
+        let states = if head.contains("lamin=") {
+            ""
+        } else {
+            UAL123_STATE
+        };
+        (
+            "200 OK",
+            format!(r#"{{"time":1700000005,"states":[{}]}}"#, states),
+        )
+    }
+
+    /// A client with `username` and a password, if given, whose credential
+    /// check has already passed.
+    fn client_with(base_url: String, username: Option<&str>) -> OpenSkyClient {
+        OpenSkyClient {
+            client: Client::new(),
+            base_url,
+            username: username.map(str::to_string),
+            password: username.map(|_| "wrong".to_string()),
+            missing_credential: None,
+            credentials_rejected: Arc::new(AtomicBool::new(false)),
+            probe: Arc::new(OnceCell::new_with(Some(true))),
+            cache: Cache::new(Duration::from_secs(CACHE_TTL_SECS)),
+            snapshot: Cache::new(Duration::from_secs(CACHE_TTL_SECS)),
+            tracks: Cache::new(Duration::from_secs(TRACK_CACHE_TTL_SECS)),
+            usage: PersistentCache::in_memory(Duration::from_secs(USAGE_TTL_SECS)),
+            telemetry: Telemetry::default(),
+            breaker: CircuitBreaker::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejected_credentials_fall_back_to_anonymous() {
+        let (base_url, server) = mock::serve(3, reject_credentials).await;
+        let client = client_with(base_url, Some("pilot"));
+
+        let err = client.get_state("abc123").await.unwrap_err();
+        assert!(matches!(err, AppError::Unauthorized));
+        assert!(err.user_message().contains("OPENSKY_USERNAME/PASSWORD"));
+
+        // Later requests, including from clones, go out without credentials
+        assert!(client.get_state("def456").await.unwrap().state.is_none());
+        assert!(client
+            .clone()
+            .search_flight("BA285")
+            .await
+            .unwrap()
+            .state
+            .is_none());
+
+        let diagnostics = client.diagnostics();
+        assert_eq!(diagnostics.auth, "credentials rejected, anonymous");
+        assert_eq!(diagnostics.resolution.as_deref(), Some("10s (anonymous)"));
+        assert_eq!(diagnostics.stats.calls, 3);
+        assert_eq!(client.requests_today(), 3);
+        assert_eq!(
+            diagnostics.stats.last_failure.unwrap().1,
+            "Credentials rejected"
+        );
+        assert!(diagnostics.stats.last_success.is_some());
+        assert_eq!(diagnostics.cache.entries, 2);
+
+        let seen = server.await.unwrap();
+        assert!(seen[0].contains("authorization:"));
+        assert!(!seen[1].contains("authorization:"));
+        assert!(!seen[2].contains("authorization:"));
+    }
+
+    #[tokio::test]
+    async fn test_credential_check_before_first_use() {
+        let (base_url, server) = mock::serve(3, reject_credentials).await;
+        let client = OpenSkyClient {
+            probe: Arc::new(OnceCell::new()),
+            ..client_with(base_url, Some("pilot"))
+        };
+        assert_eq!(client.auth_mode(), AuthMode::Basic);
+        assert_eq!(client.diagnostics().auth, "basic auth, not checked yet");
+
+        // The check catches the bad password, so the search itself works
+        assert!(client.search_flight("BA285").await.unwrap().state.is_none());
+        assert!(client
+            .clone()
+            .get_state("abc123")
+            .await
+            .unwrap()
+            .state
+            .is_none());
+        assert_eq!(client.auth_mode(), AuthMode::Anonymous);
+        assert_eq!(client.diagnostics().auth, "credentials rejected, anonymous");
+        // It costs no credits
+        assert_eq!(client.requests_today(), 2);
+
+        let seen = server.await.unwrap();
+        assert!(seen[0].starts_with("get /states/own "));
+        assert!(seen[0].contains("authorization:"));
+        assert!(!seen[1].contains("authorization:"));
+        assert!(!seen[2].contains("authorization:"));
+    }
+
+    #[tokio::test]
+    async fn test_accepted_credentials_are_checked_once() {
+        let (base_url, server) =
+            mock::serve(3, |_| ("200 OK", r#"{"time":0,"states":[]}"#.to_string())).await;
+        let client = OpenSkyClient {
+            probe: Arc::new(OnceCell::new()),
+            ..client_with(base_url, Some("pilot"))
+        };
+
+        client.get_state("abc123").await.unwrap();
+        client.clone().get_state("def456").await.unwrap();
+        assert_eq!(client.auth_mode(), AuthMode::Basic);
+        assert_eq!(client.diagnostics().auth, "basic auth");
+
+        let seen = server.await.unwrap();
+        assert_eq!(
+            seen.iter()
+                .filter(|h| h.starts_with("get /states/own "))
+                .count(),
+            1
+        );
+        assert!(seen.iter().all(|h| h.contains("authorization:")));
+    }
+
+    #[test]
+    fn test_lone_credential_is_anonymous() {
+        let client = OpenSkyClient {
+            password: None,
+            missing_credential: Some("OPENSKY_PASSWORD"),
+            ..client_with(String::new(), Some("pilot"))
+        };
+        assert_eq!(client.auth_mode(), AuthMode::Anonymous);
+        assert_eq!(
+            client.diagnostics().auth,
+            "anonymous (OPENSKY_PASSWORD not set)"
+        );
+        assert_eq!(
+            client.diagnostics().resolution.as_deref(),
+            Some("10s (anonymous)")
+        );
+    }
+
+    /// A two-waypoint track for `a808c4`, as OpenSky encodes it.
+    const A808C4_TRACK: &str = r#"{"icao24":"a808c4","callsign":"UAL123  ",
+        "startTime":1700000000,"endTime":1700000600,"path":[
+        [1700000000,40.6413,-73.7781,0.0,310.0,true],
+        [1700000600,41.0,-73.5,10668.0,87.0,false]]}"#;
+
+    #[tokio::test]
+    async fn test_track_is_fetched_and_cached() {
+        let (base_url, server) = mock::serve(1, |_| ("200 OK", A808C4_TRACK.to_string())).await;
+        let client = client_with(base_url, Some("pilot"));
+
+        let track = client.get_track("A808C4").await.unwrap().unwrap();
+        assert_eq!(track.path.len(), 2);
+        assert_eq!(track.path[1].baro_altitude, Some(10668.0));
+        // Served from the cache the second time
+        assert!(client.get_track("a808c4").await.unwrap().is_some());
+        assert_eq!(client.requests_today(), 1);
+
+        let seen = server.await.unwrap();
+        assert!(seen[0].starts_with("get /tracks/all?icao24=a808c4&time=0 "));
+        assert!(seen[0].contains("authorization:"));
+    }
+
+    #[tokio::test]
+    async fn test_track_degrades_silently() {
+        // Anonymous clients don't ask at all
+        let anonymous = client_with(String::new(), None);
+        assert!(anonymous.get_track("a808c4").await.unwrap().is_none());
+        assert_eq!(anonymous.diagnostics().stats.calls, 0);
+
+        let (base_url, server) = mock::serve(1, |_| ("403 Forbidden", String::new())).await;
+        let client = client_with(base_url, Some("pilot"));
+        assert!(client.get_track("a808c4").await.unwrap().is_none());
+        // A refusal isn't retried while cached, and doesn't drop the credentials
+        assert!(client.get_track("a808c4").await.unwrap().is_none());
+        assert_eq!(client.diagnostics().auth, "basic auth");
+        assert_eq!(
+            client.diagnostics().resolution.as_deref(),
+            Some("5s (authenticated)")
+        );
+        assert_eq!(server.await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_states_url() {
+        let base = "https://example.org/api";
+        assert_eq!(
+            states_url(base, None, None, true),
+            "https://example.org/api/states/all?extended=1"
+        );
+        assert_eq!(
+            states_url(base, None, None, false),
+            "https://example.org/api/states/all"
+        );
+        assert_eq!(
+            states_url(base, Some("a808c4"), None, false),
+            "https://example.org/api/states/all?icao24=a808c4"
+        );
+
+        let area = BoundingBox::around(51.47, -0.45, SEARCH_MARGIN_DEG);
+        assert_eq!(
+            states_url(base, None, Some(&area), true),
+            "https://example.org/api/states/all?\
+             lamin=48.4700&lamax=54.4700&lomin=-3.4500&lomax=2.5500&extended=1"
+        );
+        assert_eq!(
+            states_url(base, Some("a808c4"), Some(&area), false),
+            "https://example.org/api/states/all?icao24=a808c4\
+             &lamin=48.4700&lamax=54.4700&lomin=-3.4500&lomax=2.5500"
+        );
+    }
+
+    #[test]
+    fn test_search_area_only_for_anonymous_queries() {
+        let anonymous = client_with(String::new(), None);
+        assert!(anonymous.search_area(None).is_none());
+        let area = anonymous.search_area(Some((40.64, -73.78))).unwrap();
+        assert!(area.contains(40.64, -73.78));
+
+        let authenticated = client_with(String::new(), Some("pilot"));
+        assert!(authenticated.search_area(Some((40.64, -73.78))).is_none());
+        authenticated
+            .credentials_rejected
+            .store(true, Ordering::Relaxed);
+        assert!(authenticated.search_area(Some((40.64, -73.78))).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_bounded_miss_widens_to_unbounded_query() {
+        let (base_url, server) = mock::serve(4, only_unbounded).await;
+        let client = client_with(base_url, None);
+
+        let found = client
+            .search_flight_near("UA123", Some((51.47, -0.45)))
+            .await
+            .unwrap();
+        assert_eq!(found.time, 1700000005);
+        assert_eq!(found.state.unwrap().icao24, "a808c4");
+        let found = client
+            .get_state_near("A808C4", Some((51.47, -0.45)))
+            .await
+            .unwrap();
+        assert_eq!(found.state.unwrap().icao24, "a808c4");
+
+        let seen = server.await.unwrap();
+        let paths: Vec<&str> = seen
+            .iter()
+            .map(|head| head.split_whitespace().nth(1).unwrap())
+            .collect();
+        assert!(paths[0].starts_with("/states/all?lamin=48.4700"));
+        assert_eq!(paths[1], "/states/all?extended=1");
+        assert!(paths[2].starts_with("/states/all?icao24=a808c4&lamin="));
+        assert_eq!(paths[3], "/states/all?icao24=a808c4");
+    }
+
+    /// Five airliners in one unbounded snapshot.
+    fn five_flights(_: &str) -> (&'static str, String) {
+        let states = [
+            ("a808c4", "UAL123"),
+            ("4005c1", "BAW285"),
+            ("3c6444", "DLH400"),
+            ("c07b1a", "ACA856"),
+            ("a1b2c3", "DAL15"),
+        ]
+        .map(|(icao, cs)| {
+            UAL123_STATE
+                .replacen("a808c4", icao, 1)
+                .replacen("UAL123", cs, 1)
+        })
+        .join(",");
+        (
+            "200 OK",
+            format!(r#"{{"time":1700000005,"states":[{}]}}"#, states),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_searches_share_one_snapshot() {
+        let (base_url, server) = mock::serve(1, five_flights).await;
+        let client = client_with(base_url, None);
+        let clone = client.clone();
+
+        let found = tokio::join!(
+            client.search_flight("UA123"),
+            client.search_flight("BA285"),
+            clone.search_flight("LH400"),
+            client.search_flight("AC856"),
+            client.search_flight("DL15"),
+        );
+        let icao24 = |found: Result<StateSnapshot, AppError>| found.unwrap().state.unwrap().icao24;
+        assert_eq!(
+            [found.0, found.1, found.2, found.3, found.4].map(icao24),
+            ["a808c4", "4005c1", "3c6444", "c07b1a", "a1b2c3"]
+        );
+        // A later search within the snapshot's TTL is answered from it too
+        let missing = client.search_flight("AF1").await.unwrap();
+        assert!(missing.state.is_none() && missing.candidates.is_empty());
+        assert_eq!(missing.time, 1700000005);
+
+        assert_eq!(server.await.unwrap().len(), 1);
+        assert_eq!(client.diagnostics().stats.calls, 1);
+        assert_eq!(client.requests_today(), 1);
+
+        // Clearing the caches drops the snapshot with the rest
+        let (base_url, server) = mock::serve(1, five_flights).await;
+        let client = OpenSkyClient { base_url, ..client };
+        client.clear_cache();
+        assert!(client.search_flight("UA123").await.unwrap().state.is_some());
+        assert!(client.search_flight("BA285").await.unwrap().state.is_some());
+        assert_eq!(server.await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_bounded_hit_skips_unbounded_query() {
+        let (base_url, server) = mock::serve(1, |_| {
+            (
+                "200 OK",
+                format!(r#"{{"time":0,"states":[{}]}}"#, UAL123_STATE),
+            )
+        })
+        .await;
+        let client = client_with(base_url, None);
+
+        let found = client
+            .search_flight_near("UA123", Some((41.0, -73.5)))
+            .await
+            .unwrap();
+        assert!(found.state.is_some());
+        assert_eq!(server.await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_html_error_page_is_a_short_parse_error() {
+        let (base_url, server) = mock::serve_as(1, "text/html; charset=utf-8", |_| {
+            let filler = "<p>upstream unavailable</p>\n".repeat(100);
+            (
+                "502 Bad Gateway",
+                format!("<html>\n<body>\n{}</body>\n</html>", filler),
+            )
+        })
+        .await;
+        let client = client_with(base_url, None);
+
+        let err = client.get_state("a808c4").await.unwrap_err();
+        let AppError::Parse(detail) = &err else {
+            panic!("expected a parse error, got {err:?}");
+        };
+        assert!(detail.starts_with(
+            "expected JSON but got text/html; charset=utf-8 (HTTP 502): <html> <body>"
+        ));
+        assert!(!detail.contains('\n'));
+        assert!(detail.chars().count() <= 121);
+        assert_eq!(err.user_message(), "Failed to parse flight data.");
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_empty_body_means_no_aircraft() {
+        let (base_url, server) = mock::serve(1, |_| ("200 OK", String::new())).await;
+        let client = client_with(base_url, None);
+
+        let snapshot = client.get_state("a808c4").await.unwrap();
+        assert!(snapshot.state.is_none());
+        assert!(snapshot.time > 0);
+        server.await.unwrap();
+    }
+
+    #[test]
+    fn test_parse_states() {
+        let empty = parse_states(200, None, " \n", 1_700_000_000).unwrap();
+        assert_eq!(empty.time, 1_700_000_000);
+        assert!(empty.states.is_none());
+
+        let parsed = parse_states(
+            200,
+            Some("application/json"),
+            &format!(r#"{{"time":5,"states":[{}]}}"#, UAL123_STATE),
+            0,
+        )
+        .unwrap();
+        assert_eq!(parsed.time, 5);
+        assert_eq!(parsed.states.unwrap().len(), 1);
+
+        // Cut off mid-array
+        let truncated = format!(r#"{{"time":5,"states":[{}"#, &UAL123_STATE[..40]);
+        let err = parse_states(200, Some("application/json"), &truncated, 0).unwrap_err();
+        let AppError::Parse(detail) = err else {
+            panic!("expected a parse error");
+        };
+        assert!(detail.contains("EOF while parsing"), "{detail}");
+        assert!(detail.ends_with("(HTTP 200)"));
+
+        // No content type to go on, so the body decides
+        assert!(parse_states(200, None, "<html></html>", 0).is_err());
+    }
+
     #[test]
     fn test_normalize_callsign_edge_cases() {
         assert_eq!(normalize_callsign("123"), "123"); // No airline code
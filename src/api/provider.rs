@@ -0,0 +1,203 @@
+//! Provider traits abstracting the live position and schedule sources.
+//!
+//! The TUI talks to providers through these traits so the real HTTP
+//! clients can be swapped for the scripted demo provider.
+
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use futures::future::BoxFuture;
+
+use super::aviationstack::{AviationStackClient, FlightData};
+use super::opensky::OpenSkyClient;
+use super::telemetry::{AuthMode, ProviderDiagnostics};
+use super::types::{FlightTrack, StateSnapshot};
+use crate::error::AppError;
+
+/// A source of live aircraft positions.
+pub trait PositionProvider: Send + Sync {
+    /// Find the aircraft currently flying `flight_number`.
+    fn search_flight<'a>(
+        &'a self,
+        flight_number: &'a str,
+    ) -> BoxFuture<'a, Result<StateSnapshot, AppError>>;
+
+    /// Fetch the current state of an aircraft by ICAO24 address.
+    fn get_state<'a>(&'a self, icao24: &'a str) -> BoxFuture<'a, Result<StateSnapshot, AppError>>;
+
+    /// Configuration and activity for the diagnostics overlay.
+    fn diagnostics(&self) -> ProviderDiagnostics;
+
+    /// Find the aircraft flying `flight_number`, given a rough idea of where
+    /// it is that the provider may use to narrow the query.
+    fn search_flight_near<'a>(
+        &'a self,
+        flight_number: &'a str,
+        _near: Option<(f64, f64)>,
+    ) -> BoxFuture<'a, Result<StateSnapshot, AppError>> {
+        self.search_flight(flight_number)
+    }
+
+    /// Fetch an aircraft's state, given a rough idea of where it is that the
+    /// provider may use to narrow the query.
+    fn get_state_near<'a>(
+        &'a self,
+        icao24: &'a str,
+        _near: Option<(f64, f64)>,
+    ) -> BoxFuture<'a, Result<StateSnapshot, AppError>> {
+        self.get_state(icao24)
+    }
+
+    /// Waypoints of the aircraft's current flight, or `None` if the
+    /// provider keeps no history or won't share it.
+    fn get_track<'a>(
+        &'a self,
+        _icao24: &'a str,
+    ) -> BoxFuture<'a, Result<Option<FlightTrack>, AppError>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Requests made against a daily credit allowance today, or `None` if
+    /// the provider is not metered.
+    fn requests_today(&self) -> Option<u32> {
+        None
+    }
+
+    /// How requests are authenticated right now, or `None` if the provider
+    /// has no notion of it.
+    fn auth_mode(&self) -> Option<AuthMode> {
+        None
+    }
+
+    /// Drop cached responses so the next lookups go to the network.
+    fn clear_cache(&self) {}
+}
+
+/// A source of flight schedules, routes and delays.
+pub trait ScheduleProvider: Send + Sync {
+    /// Whether lookups can return data (e.g. an API key is configured).
+    fn is_enabled(&self) -> bool;
+
+    /// Configuration and activity for the diagnostics overlay.
+    fn diagnostics(&self) -> ProviderDiagnostics;
+
+    /// Look up the schedule for a flight, optionally on a given date.
+    fn get_flight<'a>(
+        &'a self,
+        flight_number: &'a str,
+        date: Option<NaiveDate>,
+    ) -> BoxFuture<'a, Result<Option<FlightData>, AppError>>;
+
+    /// Re-fetch the schedule of a tracked flight, reusing a cached result
+    /// only if it is younger than `max_age`.
+    fn refresh_flight<'a>(
+        &'a self,
+        flight_number: &'a str,
+        date: Option<NaiveDate>,
+        _max_age: Duration,
+    ) -> BoxFuture<'a, Result<Option<FlightData>, AppError>> {
+        self.get_flight(flight_number, date)
+    }
+
+    /// Requests made against a metered quota this month, or `None` if the
+    /// provider is not metered.
+    fn requests_this_month(&self) -> Option<u32> {
+        None
+    }
+
+    /// Drop cached schedules, including remembered misses, so the next
+    /// lookups go to the network.
+    fn clear_cache(&self) {}
+}
+
+impl PositionProvider for OpenSkyClient {
+    fn search_flight<'a>(
+        &'a self,
+        flight_number: &'a str,
+    ) -> BoxFuture<'a, Result<StateSnapshot, AppError>> {
+        Box::pin(OpenSkyClient::search_flight(self, flight_number))
+    }
+
+    fn get_state<'a>(&'a self, icao24: &'a str) -> BoxFuture<'a, Result<StateSnapshot, AppError>> {
+        Box::pin(OpenSkyClient::get_state(self, icao24))
+    }
+
+    fn diagnostics(&self) -> ProviderDiagnostics {
+        OpenSkyClient::diagnostics(self)
+    }
+
+    fn search_flight_near<'a>(
+        &'a self,
+        flight_number: &'a str,
+        near: Option<(f64, f64)>,
+    ) -> BoxFuture<'a, Result<StateSnapshot, AppError>> {
+        Box::pin(OpenSkyClient::search_flight_near(self, flight_number, near))
+    }
+
+    fn get_state_near<'a>(
+        &'a self,
+        icao24: &'a str,
+        near: Option<(f64, f64)>,
+    ) -> BoxFuture<'a, Result<StateSnapshot, AppError>> {
+        Box::pin(OpenSkyClient::get_state_near(self, icao24, near))
+    }
+
+    fn get_track<'a>(
+        &'a self,
+        icao24: &'a str,
+    ) -> BoxFuture<'a, Result<Option<FlightTrack>, AppError>> {
+        Box::pin(OpenSkyClient::get_track(self, icao24))
+    }
+
+    fn requests_today(&self) -> Option<u32> {
+        Some(OpenSkyClient::requests_today(self))
+    }
+
+    fn auth_mode(&self) -> Option<AuthMode> {
+        Some(OpenSkyClient::auth_mode(self))
+    }
+
+    fn clear_cache(&self) {
+        OpenSkyClient::clear_cache(self)
+    }
+}
+
+impl ScheduleProvider for AviationStackClient {
+    fn is_enabled(&self) -> bool {
+        self.has_api_key()
+    }
+
+    fn diagnostics(&self) -> ProviderDiagnostics {
+        AviationStackClient::diagnostics(self)
+    }
+
+    fn get_flight<'a>(
+        &'a self,
+        flight_number: &'a str,
+        date: Option<NaiveDate>,
+    ) -> BoxFuture<'a, Result<Option<FlightData>, AppError>> {
+        Box::pin(AviationStackClient::get_flight(self, flight_number, date))
+    }
+
+    fn refresh_flight<'a>(
+        &'a self,
+        flight_number: &'a str,
+        date: Option<NaiveDate>,
+        max_age: Duration,
+    ) -> BoxFuture<'a, Result<Option<FlightData>, AppError>> {
+        Box::pin(AviationStackClient::refresh_flight(
+            self,
+            flight_number,
+            date,
+            max_age,
+        ))
+    }
+
+    fn requests_this_month(&self) -> Option<u32> {
+        Some(AviationStackClient::requests_this_month(self))
+    }
+
+    fn clear_cache(&self) {
+        AviationStackClient::clear_cache(self)
+    }
+}
@@ -0,0 +1,53 @@
+//! Provider payloads as kept for the raw debug view.
+
+use serde::Serialize;
+
+use super::aviationstack::redact_api_key;
+
+/// Longest payload kept per flight and provider; anything past it is cut.
+pub const MAX_RAW_PAYLOAD_BYTES: usize = 16 * 1024;
+
+/// A payload as pretty-printed JSON, with any AviationStack access key
+/// redacted and cut to [`MAX_RAW_PAYLOAD_BYTES`].
+///
+/// ```
+/// use flight_tracker_tui::api::raw_payload;
+///
+/// let json = raw_payload(&serde_json::json!({"url": "/flights?access_key=s3cret"}));
+/// assert_eq!(json, "{\n  \"url\": \"/flights?access_key=REDACTED\"\n}");
+/// ```
+pub fn raw_payload(value: &impl Serialize) -> String {
+    let json = serde_json::to_string_pretty(value)
+        .unwrap_or_else(|e| format!("(could not serialize: {})", e));
+    truncate(redact_api_key(&json), MAX_RAW_PAYLOAD_BYTES)
+}
+
+fn truncate(mut text: String, max_bytes: usize) -> String {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+    text.push_str("\n… (truncated)");
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_payloads_are_cut() {
+        let long = vec!["ü"; MAX_RAW_PAYLOAD_BYTES];
+        let json = raw_payload(&long);
+        assert!(json.ends_with("\n… (truncated)"));
+        assert!(json.len() <= MAX_RAW_PAYLOAD_BYTES + "\n… (truncated)".len());
+
+        assert_eq!(truncate("short".to_string(), 10), "short");
+        // Never in the middle of a character
+        assert_eq!(truncate("aü".to_string(), 2), "a\n… (truncated)");
+    }
+}
@@ -0,0 +1,113 @@
+//! Request bookkeeping the API clients keep for the diagnostics overlay.
+
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+
+use super::breaker::BreakerState;
+use crate::cache::CacheStats;
+use crate::error::AppError;
+
+/// What a provider's network requests have done this session. Cache hits
+/// are not requests and are not counted.
+#[derive(Debug, Clone, Default)]
+pub struct ProviderStats {
+    /// Network requests made this session.
+    pub calls: u64,
+    pub last_success: Option<DateTime<Utc>>,
+    /// When the most recent failed request happened, and why it failed.
+    pub last_failure: Option<(DateTime<Utc>, String)>,
+}
+
+impl ProviderStats {
+    /// Whether the latest request failed.
+    ///
+    /// ```
+    /// use chrono::{Duration, Utc};
+    /// use flight_tracker_tui::api::ProviderStats;
+    ///
+    /// let now = Utc::now();
+    /// let mut stats = ProviderStats::default();
+    /// assert!(!stats.is_failing());
+    /// stats.last_failure = Some((now - Duration::seconds(30), "timed out".to_string()));
+    /// assert!(stats.is_failing());
+    /// stats.last_success = Some(now);
+    /// assert!(!stats.is_failing());
+    /// ```
+    pub fn is_failing(&self) -> bool {
+        match (&self.last_failure, self.last_success) {
+            (Some((failed, _)), Some(succeeded)) => *failed > succeeded,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+}
+
+/// Thread-safe [`ProviderStats`] recorder. Clones share the same counters,
+/// like the clients that own them.
+///
+/// ```
+/// use flight_tracker_tui::api::Telemetry;
+/// use flight_tracker_tui::AppError;
+///
+/// let telemetry = Telemetry::default();
+/// telemetry.record(&Ok::<_, AppError>(()));
+/// telemetry.clone().record(&Err::<(), _>(AppError::RateLimited));
+///
+/// let stats = telemetry.stats();
+/// assert_eq!(stats.calls, 2);
+/// assert!(stats.last_success.is_some());
+/// assert_eq!(stats.last_failure.unwrap().1, "Rate limit exceeded");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Telemetry {
+    stats: Arc<Mutex<ProviderStats>>,
+}
+
+impl Telemetry {
+    /// Count a finished request and note whether it succeeded.
+    pub fn record<T>(&self, result: &Result<T, AppError>) {
+        let Ok(mut stats) = self.stats.lock() else {
+            return;
+        };
+        stats.calls += 1;
+        match result {
+            Ok(_) => stats.last_success = Some(Utc::now()),
+            Err(e) => stats.last_failure = Some((Utc::now(), e.to_string())),
+        }
+    }
+
+    /// Copy of the counters so far.
+    pub fn stats(&self) -> ProviderStats {
+        self.stats.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+}
+
+/// How a provider's requests are authenticated, as actually in effect
+/// rather than as configured.
+///
+/// OpenSky's OAuth client credentials aren't supported, so there is no mode
+/// for them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AuthMode {
+    #[default]
+    Anonymous,
+    /// Username and password on every request.
+    Basic,
+}
+
+/// A provider's configuration and activity, for the diagnostics overlay.
+#[derive(Debug, Clone)]
+pub struct ProviderDiagnostics {
+    pub name: &'static str,
+    /// How the provider authenticates, e.g. "API key loaded" or "anonymous".
+    pub auth: String,
+    pub stats: ProviderStats,
+    /// Size and hit rate of the provider's response cache.
+    pub cache: CacheStats,
+    /// Whether requests are being skipped after repeated failures.
+    pub breaker: BreakerState,
+    /// How finely the provider's data is timed, when it limits that, e.g.
+    /// "10s (anonymous)".
+    pub resolution: Option<String>,
+}
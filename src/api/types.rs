@@ -1,14 +1,11 @@
 //! OpenSky Network API response types.
 //!
 //! These types represent the JSON response from the OpenSky Network REST API.
-//! Some fields are deserialized but not actively used - they are kept for
-//! API completeness and potential future use.
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 /// Response from the OpenSky `/states/all` endpoint.
 #[derive(Debug, Deserialize)]
-#[allow(dead_code)]
 pub struct OpenSkyResponse {
     /// Unix timestamp of the response.
     pub time: i64,
@@ -16,9 +13,85 @@ pub struct OpenSkyResponse {
     pub states: Option<Vec<StateVector>>,
 }
 
+/// Response from the OpenSky `/tracks/all` endpoint: the waypoints of one
+/// aircraft's current (or most recent) flight.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FlightTrack {
+    /// ICAO 24-bit transponder address (hex).
+    pub icao24: String,
+    /// Unix timestamp of the first waypoint.
+    pub start_time: i64,
+    /// Unix timestamp of the last waypoint.
+    pub end_time: i64,
+    /// Callsign of the aircraft, if known.
+    pub callsign: Option<String>,
+    /// Waypoints, oldest first.
+    #[serde(default)]
+    pub path: Vec<Waypoint>,
+}
+
+/// One point of a [`FlightTrack`].
+///
+/// Like state vectors, waypoints are positional JSON arrays:
+/// `[time, latitude, longitude, baro_altitude, true_track, on_ground]`,
+/// and are written back the same way.
+///
+/// ```
+/// use flight_tracker_tui::api::Waypoint;
+///
+/// let json = "[1700000000, 51.47, -0.45, 457.2, 270.0, false]";
+/// let waypoint: Waypoint = serde_json::from_str(json).unwrap();
+/// assert_eq!(waypoint.baro_altitude, Some(457.2));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Waypoint {
+    /// Unix timestamp of the position.
+    pub time: i64,
+    /// Latitude in decimal degrees.
+    pub latitude: Option<f64>,
+    /// Longitude in decimal degrees.
+    pub longitude: Option<f64>,
+    /// Barometric altitude in meters.
+    pub baro_altitude: Option<f64>,
+    /// True track (heading) in degrees.
+    pub true_track: Option<f64>,
+    /// Whether the aircraft was on the ground.
+    pub on_ground: bool,
+}
+
+/// Result of looking up a single aircraft: its state, if it was found, and
+/// when the snapshot it came from was generated.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    /// Unix timestamp of the snapshot, from [`OpenSkyResponse::time`].
+    pub time: i64,
+    /// State of the requested aircraft, or `None` if it wasn't in the snapshot.
+    pub state: Option<StateVector>,
+    /// Aircraft that matched a callsign search equally well, leaving `state`
+    /// empty rather than guessing. Empty otherwise.
+    pub candidates: Vec<StateVector>,
+}
+
 /// Aircraft state vector from ADS-B data.
+///
+/// OpenSky encodes each state as a positional JSON array rather than an
+/// object, so this type has a hand-written `Deserialize` implementation.
+/// It reads the 17 standard elements, the optional 18th (category), and
+/// ignores anything after that. `Serialize` writes the same 18-element
+/// array back, with `null` for the elements that aren't kept.
+///
+/// ```
+/// use flight_tracker_tui::api::StateVector;
+///
+/// let json = r#"["a808c4", "UAL123  ", "United States", 1700000000, 1700000001,
+///     -122.4, 37.6, 10668.0, false, 240.5, 87.0, 0.0, null, 10900.0, "1200", false, 0]"#;
+/// let sv: StateVector = serde_json::from_str(json).unwrap();
+///
+/// assert_eq!(sv.callsign.as_deref(), Some("UAL123"));
+/// assert_eq!(sv.baro_altitude, Some(10668.0));
+/// ```
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub struct StateVector {
     /// ICAO 24-bit transponder address (hex).
     pub icao24: String,
@@ -48,6 +121,114 @@ pub struct StateVector {
     pub geo_altitude: Option<f64>,
     /// Transponder squawk code.
     pub squawk: Option<String>,
+    /// Emitter category, present only in `extended=1` responses.
+    pub category: Option<AircraftCategory>,
+}
+
+/// ADS-B emitter category, from the 18th element of an extended state.
+///
+/// ```
+/// use flight_tracker_tui::api::AircraftCategory;
+///
+/// assert_eq!(AircraftCategory::from_code(6), Some(AircraftCategory::Heavy));
+/// assert_eq!(AircraftCategory::from_code(0), None); // no information
+/// assert_eq!(AircraftCategory::Heavy.to_string(), "Heavy (> 300,000 lb)");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AircraftCategory {
+    Light,
+    Small,
+    Large,
+    HighVortexLarge,
+    Heavy,
+    HighPerformance,
+    Rotorcraft,
+    Glider,
+    LighterThanAir,
+    Parachutist,
+    Ultralight,
+    Uav,
+    Space,
+    SurfaceEmergencyVehicle,
+    SurfaceServiceVehicle,
+    Obstacle,
+}
+
+impl AircraftCategory {
+    /// Decode OpenSky's category number. Codes meaning "no information" or
+    /// "reserved", and unknown codes, give `None`.
+    pub fn from_code(code: u8) -> Option<Self> {
+        use AircraftCategory::*;
+
+        Some(match code {
+            2 => Light,
+            3 => Small,
+            4 => Large,
+            5 => HighVortexLarge,
+            6 => Heavy,
+            7 => HighPerformance,
+            8 => Rotorcraft,
+            9 => Glider,
+            10 => LighterThanAir,
+            11 => Parachutist,
+            12 => Ultralight,
+            14 => Uav,
+            15 => Space,
+            16 => SurfaceEmergencyVehicle,
+            17 => SurfaceServiceVehicle,
+            18..=20 => Obstacle,
+            _ => return None,
+        })
+    }
+
+    /// OpenSky's number for the category, the first of the range for
+    /// obstacles.
+    pub fn code(self) -> u8 {
+        use AircraftCategory::*;
+
+        match self {
+            Light => 2,
+            Small => 3,
+            Large => 4,
+            HighVortexLarge => 5,
+            Heavy => 6,
+            HighPerformance => 7,
+            Rotorcraft => 8,
+            Glider => 9,
+            LighterThanAir => 10,
+            Parachutist => 11,
+            Ultralight => 12,
+            Uav => 14,
+            Space => 15,
+            SurfaceEmergencyVehicle => 16,
+            SurfaceServiceVehicle => 17,
+            Obstacle => 18,
+        }
+    }
+}
+
+impl std::fmt::Display for AircraftCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            AircraftCategory::Light => "Light (< 15,500 lb)",
+            AircraftCategory::Small => "Small (15,500-75,000 lb)",
+            AircraftCategory::Large => "Large (75,000-300,000 lb)",
+            AircraftCategory::HighVortexLarge => "High vortex large",
+            AircraftCategory::Heavy => "Heavy (> 300,000 lb)",
+            AircraftCategory::HighPerformance => "High performance",
+            AircraftCategory::Rotorcraft => "Rotorcraft",
+            AircraftCategory::Glider => "Glider",
+            AircraftCategory::LighterThanAir => "Lighter than air",
+            AircraftCategory::Parachutist => "Parachutist",
+            AircraftCategory::Ultralight => "Ultralight",
+            AircraftCategory::Uav => "UAV",
+            AircraftCategory::Space => "Space vehicle",
+            AircraftCategory::SurfaceEmergencyVehicle => "Surface emergency vehicle",
+            AircraftCategory::SurfaceServiceVehicle => "Surface service vehicle",
+            AircraftCategory::Obstacle => "Obstacle",
+        };
+        write!(f, "{}", name)
+    }
 }
 
 impl<'de> Deserialize<'de> for StateVector {
@@ -63,7 +244,7 @@ impl<'de> Deserialize<'de> for StateVector {
             type Value = StateVector;
 
             fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                formatter.write_str("a sequence of 17 elements")
+                formatter.write_str("a sequence of at least 17 elements")
             }
 
             fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
@@ -95,6 +276,9 @@ impl<'de> Deserialize<'de> for StateVector {
                 let squawk: Option<String> = seq.next_element()?.unwrap_or(None);
                 let _spi: Option<bool> = seq.next_element()?.unwrap_or(None);
                 let _position_source: Option<i32> = seq.next_element()?.unwrap_or(None);
+                let category: Option<u8> = seq.next_element()?.unwrap_or(None);
+                // Tolerate fields added after the category
+                while seq.next_element::<serde::de::IgnoredAny>()?.is_some() {}
 
                 Ok(StateVector {
                     icao24,
@@ -111,6 +295,7 @@ impl<'de> Deserialize<'de> for StateVector {
                     vertical_rate,
                     geo_altitude,
                     squawk,
+                    category: category.and_then(AircraftCategory::from_code),
                 })
             }
         }
@@ -118,3 +303,250 @@ impl<'de> Deserialize<'de> for StateVector {
         deserializer.deserialize_seq(StateVectorVisitor)
     }
 }
+
+impl Serialize for StateVector {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeSeq;
+
+        let none = Option::<()>::None;
+        let mut seq = serializer.serialize_seq(Some(18))?;
+        seq.serialize_element(&self.icao24)?;
+        seq.serialize_element(&self.callsign)?;
+        seq.serialize_element(&self.origin_country)?;
+        seq.serialize_element(&self.time_position)?;
+        seq.serialize_element(&self.last_contact)?;
+        seq.serialize_element(&self.longitude)?;
+        seq.serialize_element(&self.latitude)?;
+        seq.serialize_element(&self.baro_altitude)?;
+        seq.serialize_element(&self.on_ground)?;
+        seq.serialize_element(&self.velocity)?;
+        seq.serialize_element(&self.true_track)?;
+        seq.serialize_element(&self.vertical_rate)?;
+        seq.serialize_element(&none)?; // sensors
+        seq.serialize_element(&self.geo_altitude)?;
+        seq.serialize_element(&self.squawk)?;
+        seq.serialize_element(&none)?; // spi
+        seq.serialize_element(&none)?; // position_source
+        seq.serialize_element(&self.category.map(AircraftCategory::code))?;
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Waypoint {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::{Error, SeqAccess, Visitor};
+
+        struct WaypointVisitor;
+
+        impl<'de> Visitor<'de> for WaypointVisitor {
+            type Value = Waypoint;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a sequence of 6 elements")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: SeqAccess<'de>,
+            {
+                let time: i64 = seq
+                    .next_element()?
+                    .ok_or_else(|| Error::invalid_length(0, &self))?;
+                let latitude: Option<f64> = seq.next_element()?.unwrap_or(None);
+                let longitude: Option<f64> = seq.next_element()?.unwrap_or(None);
+                let baro_altitude: Option<f64> = seq.next_element()?.unwrap_or(None);
+                let true_track: Option<f64> = seq.next_element()?.unwrap_or(None);
+                let on_ground: bool = seq
+                    .next_element()?
+                    .ok_or_else(|| Error::invalid_length(5, &self))?;
+                while seq.next_element::<serde::de::IgnoredAny>()?.is_some() {}
+
+                Ok(Waypoint {
+                    time,
+                    latitude,
+                    longitude,
+                    baro_altitude,
+                    true_track,
+                    on_ground,
+                })
+            }
+        }
+
+        deserializer.deserialize_seq(WaypointVisitor)
+    }
+}
+
+impl Serialize for Waypoint {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeSeq;
+
+        let mut seq = serializer.serialize_seq(Some(6))?;
+        seq.serialize_element(&self.time)?;
+        seq.serialize_element(&self.latitude)?;
+        seq.serialize_element(&self.longitude)?;
+        seq.serialize_element(&self.baro_altitude)?;
+        seq.serialize_element(&self.true_track)?;
+        seq.serialize_element(&self.on_ground)?;
+        seq.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A standard 17-element state.
+    const STATE_17: &str = r#"["4ca7b4", "RYR42AB ", "Ireland", 1700000000, 1700000001,
+        -6.27, 53.42, 3048.0, false, 150.2, 270.0, -5.2, null, 3100.0, "2000", false, 0]"#;
+
+    /// The same state from an `extended=1` request, with category 4 (Large).
+    const STATE_18: &str = r#"["4ca7b4", "RYR42AB ", "Ireland", 1700000000, 1700000001,
+        -6.27, 53.42, 3048.0, false, 150.2, 270.0, -5.2, null, 3100.0, "2000", false, 0, 4]"#;
+
+    #[test]
+    fn test_state_without_category() {
+        let sv: StateVector = serde_json::from_str(STATE_17).unwrap();
+        assert_eq!(sv.callsign.as_deref(), Some("RYR42AB"));
+        assert_eq!(sv.squawk.as_deref(), Some("2000"));
+        assert_eq!(sv.category, None);
+    }
+
+    #[test]
+    fn test_state_with_category() {
+        let sv: StateVector = serde_json::from_str(STATE_18).unwrap();
+        assert_eq!(sv.squawk.as_deref(), Some("2000"));
+        assert_eq!(sv.category, Some(AircraftCategory::Large));
+
+        // "No information" category
+        let json = STATE_18.replace(", 0, 4]", ", 0, 0]");
+        let sv: StateVector = serde_json::from_str(&json).unwrap();
+        assert_eq!(sv.category, None);
+    }
+
+    #[test]
+    fn test_state_ignores_trailing_elements() {
+        let json = STATE_18.replace(", 0, 4]", r#", 0, 8, "future", [1, 2]]"#);
+        let sv: StateVector = serde_json::from_str(&json).unwrap();
+        assert_eq!(sv.category, Some(AircraftCategory::Rotorcraft));
+    }
+
+    #[test]
+    fn test_response_mixes_state_lengths() {
+        let json = format!(
+            r#"{{"time": 1700000002, "states": [{}, {}]}}"#,
+            STATE_17, STATE_18
+        );
+        let response: OpenSkyResponse = serde_json::from_str(&json).unwrap();
+        let states = response.states.unwrap();
+        assert_eq!(states.len(), 2);
+        assert_eq!(states[1].category, Some(AircraftCategory::Large));
+    }
+
+    #[test]
+    fn test_state_serializes_back_to_an_array() {
+        let sv: StateVector = serde_json::from_str(STATE_18).unwrap();
+        let json = serde_json::to_string(&sv).unwrap();
+        assert!(json.starts_with(r#"["4ca7b4","RYR42AB","Ireland",1700000000,1700000001,"#));
+        assert!(json.ends_with(r#","2000",null,null,4]"#), "{json}");
+
+        let again: StateVector = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&again).unwrap(), json);
+
+        for code in 0..=21 {
+            if let Some(category) = AircraftCategory::from_code(code) {
+                assert_eq!(AircraftCategory::from_code(category.code()), Some(category));
+            }
+        }
+    }
+
+    #[test]
+    fn test_state_too_short_is_an_error() {
+        let json = r#"["4ca7b4", "RYR42AB ", "Ireland", 1700000000]"#;
+        assert!(serde_json::from_str::<StateVector>(json).is_err());
+    }
+
+    /// A shortened `/tracks/all` response: pushback, take-off roll, climb,
+    /// and a waypoint with no position.
+    const TRACK: &str = r#"{
+        "icao24": "4009d8",
+        "callsign": "BAW285  ",
+        "startTime": 1700000000,
+        "endTime": 1700001800,
+        "path": [
+            [1700000000, 51.4700, -0.4543, 0.0, 270.0, true],
+            [1700000600, 51.4650, -0.4900, 0.0, 270.0, true],
+            [1700000900, 51.4640, -0.5500, 609.6, 272.0, false],
+            [1700001200, null, null, 3048.0, null, false],
+            [1700001800, 51.7012, -1.9034, 10058.4, 290.5, false]
+        ]
+    }"#;
+
+    #[test]
+    fn test_track_response() {
+        let track: FlightTrack = serde_json::from_str(TRACK).unwrap();
+        assert_eq!(track.icao24, "4009d8");
+        assert_eq!(track.callsign.as_deref(), Some("BAW285  "));
+        assert_eq!((track.start_time, track.end_time), (1700000000, 1700001800));
+        assert_eq!(track.path.len(), 5);
+        assert!(track.path[0].on_ground);
+        assert_eq!(
+            track.path[4],
+            Waypoint {
+                time: 1700001800,
+                latitude: Some(51.7012),
+                longitude: Some(-1.9034),
+                baro_altitude: Some(10058.4),
+                true_track: Some(290.5),
+                on_ground: false,
+            }
+        );
+        assert_eq!(track.path[3].latitude, None);
+        assert_eq!(track.path[3].baro_altitude, Some(3048.0));
+
+        // Written back in the same shape
+        let json = serde_json::to_string(&track).unwrap();
+        assert!(
+            json.contains(r#"[1700001200,null,null,3048.0,null,false]"#),
+            "{json}"
+        );
+        let again: FlightTrack = serde_json::from_str(&json).unwrap();
+        assert_eq!(again.path, track.path);
+    }
+
+    #[test]
+    fn test_track_without_path() {
+        let json = r#"{"icao24": "4009d8", "callsign": null, "startTime": 0, "endTime": 0}"#;
+        let track: FlightTrack = serde_json::from_str(json).unwrap();
+        assert!(track.path.is_empty());
+
+        assert!(serde_json::from_str::<Waypoint>("[1700000000, 51.47, -0.45]").is_err());
+    }
+
+    #[test]
+    fn test_category_codes() {
+        for code in [0, 1, 13, 21, 255] {
+            assert_eq!(AircraftCategory::from_code(code), None, "code {code}");
+        }
+        assert_eq!(
+            AircraftCategory::from_code(2),
+            Some(AircraftCategory::Light)
+        );
+        assert_eq!(
+            AircraftCategory::from_code(8),
+            Some(AircraftCategory::Rotorcraft)
+        );
+        assert_eq!(
+            AircraftCategory::from_code(20),
+            Some(AircraftCategory::Obstacle)
+        );
+    }
+}
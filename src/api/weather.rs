@@ -0,0 +1,89 @@
+//! Airport weather reports (METAR) and forecasts (TAF) from the Aviation
+//! Weather Center, which needs no key.
+
+use std::time::Duration;
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use super::http_client;
+use crate::error::AppError;
+
+const AVIATION_WEATHER_URL: &str = "https://aviationweather.gov/api/data";
+/// Longest a request may take before it counts as failed.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The latest report and forecast for an airport, as raw text.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StationWeather {
+    pub metar: Option<String>,
+    /// On one line, however many the service split it over.
+    pub taf: Option<String>,
+}
+
+/// Client for the Aviation Weather Center's data API.
+#[derive(Clone)]
+pub struct WeatherClient {
+    client: Client,
+    base_url: String,
+}
+
+impl Default for WeatherClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WeatherClient {
+    pub fn new() -> Self {
+        Self {
+            client: http_client(REQUEST_TIMEOUT),
+            base_url: AVIATION_WEATHER_URL.to_string(),
+        }
+    }
+
+    /// The METAR and TAF for the airport with ICAO code `icao`. Either is
+    /// `None` when the airport doesn't issue one.
+    pub async fn station(&self, icao: &str) -> Result<StationWeather, AppError> {
+        Ok(StationWeather {
+            metar: self.fetch("metar", icao).await?,
+            taf: self.fetch("taf", icao).await?,
+        })
+    }
+
+    async fn fetch(&self, product: &str, icao: &str) -> Result<Option<String>, AppError> {
+        debug!(product, icao, "Fetching airport weather");
+        let text = self
+            .client
+            .get(format!("{}/{}", self.base_url, product))
+            .query(&[("ids", icao), ("format", "raw")])
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        Ok(one_line(&text))
+    }
+}
+
+/// The report squeezed onto one line, or `None` for an empty response.
+fn one_line(text: &str) -> Option<String> {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!line.is_empty()).then_some(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_line() {
+        assert_eq!(
+            one_line("TAF KJFK 121130Z 1212/1318 20012KT P6SM BKN030\n  FM121800 22015KT\n")
+                .as_deref(),
+            Some("TAF KJFK 121130Z 1212/1318 20012KT P6SM BKN030 FM121800 22015KT")
+        );
+        assert_eq!(one_line(" \n"), None);
+    }
+}
@@ -1,15 +1,460 @@
-use std::time::Instant;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use crate::api::{FlightData, StateVector};
-use crate::flight::{Airport, Flight, FlightStatus};
+use crate::action::{Action, Effect};
+use crate::aircraft::AircraftTypes;
+use crate::alert::{self, AlertCondition, AlertRule, Mute};
+use crate::api::{
+    missing_credential, normalize_callsign, raw_payload, AuthMode, FlightData, FlightTrack,
+    LiveInfo, ProviderDiagnostics, ProviderStats, StateVector, StationWeather,
+};
+use crate::budget::{self, BudgetPlan};
+use crate::config::{Config, ScreenLayout, TimeFormat};
+use crate::flight::{
+    format_duration, return_flight_numbers, Airport, Flight, FlightStatus, PositionLimits,
+    PositionSource, Reading, ScheduleField, TrackPoint,
+};
+use crate::geo::BoundingBox;
 use crate::history::History;
-use chrono::Utc;
+use crate::import::{self, FlightQuery};
+use crate::onboarding;
+use crate::palette;
+use crate::session::{Session, SessionFlight};
+use crate::settings;
+use crate::taf::Taf;
+use crate::theme::Theme;
+use crate::trip::Trip;
+use crate::ui_state::{self, UiState};
+use crate::watchlist;
+use crate::webhook::{Debouncer, StatusChange};
+use chrono::{DateTime, Days, Local, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::task::AbortHandle;
+use tracing::{debug, info, warn};
+
+/// AviationStack requests kept back from schedule refreshes so new searches
+/// still work near the end of the month.
+const SCHEDULE_REFRESH_RESERVE: u32 = 10;
+
+/// Narrowest map overview viewport, in degrees, so a lone flight isn't
+/// zoomed in to a featureless square.
+const MAP_MIN_SPAN_DEG: f64 = 10.0;
+
+const METERS_TO_FEET: f64 = 3.28084;
+
+/// How long a search may run before it is given up on, so a hung request
+/// can't keep the spinner going and block refreshes.
+pub const SEARCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How far the position feed may lag behind the clock before the status bar
+/// warns about it. OpenSky snapshots are normally a few seconds old, plus up
+/// to the 10 second client cache.
+const FEED_LAG_WARNING_SECS: i64 = 30;
+
+/// How long restored session flights wait for `r` or `d` before they are
+/// searched anyway.
+const RESTORE_GRACE: Duration = Duration::from_secs(30);
+
+/// Removals `u` can take back, oldest dropped first.
+const MAX_UNDO: usize = 20;
+
+/// Announcements kept for the accessible layout, which shows the newest.
+const MAX_ANNOUNCEMENTS: usize = 50;
+
+/// Altitudes announced as flight levels rather than feet.
+const FLIGHT_LEVEL_FROM_FT: f64 = 18_000.0;
+
+/// Shortest gap between callsign searches for a flight whose aircraft looks
+/// swapped, as each costs position credits.
+const REMATCH_INTERVAL: Duration = Duration::from_secs(600);
+
+/// How often a destination's METAR and TAF are fetched again.
+const WEATHER_REFRESH: Duration = Duration::from_secs(30 * 60);
+
+/// The latest weather at a destination airport.
+#[derive(Debug, Clone, Default)]
+pub struct Weather {
+    /// Current conditions, as reported
+    pub metar: Option<String>,
+    /// Forecast, if the airport issues one and it could be read
+    pub taf: Option<Taf>,
+}
 
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub enum AppMode {
     #[default]
     Input,
     Viewing,
+    /// Entering an alert rule for the selected flight.
+    AlertInput,
+    /// Entering the path of a file to import flights from.
+    ImportInput,
+    /// The first-run walkthrough in the details pane.
+    Onboarding,
+    /// Typing text to narrow the flight list by, which applies as it is
+    /// typed.
+    ListSearch,
+    /// Typing a command into the `:` palette.
+    Command,
+    /// Moving through and changing the `,` settings overlay.
+    Settings,
+}
+
+/// Which flights the list shows. The tracked flights themselves are never
+/// filtered, only the view of them.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListFilter {
+    #[default]
+    All,
+    /// Hide flights that have landed, been cancelled, or weren't found.
+    Active,
+    /// Only flights that need attention.
+    Problems,
+}
+
+impl ListFilter {
+    /// The next filter in the `f` key cycle.
+    pub fn next(self) -> Self {
+        match self {
+            ListFilter::All => ListFilter::Active,
+            ListFilter::Active => ListFilter::Problems,
+            ListFilter::Problems => ListFilter::All,
+        }
+    }
+
+    pub fn matches(self, flight: &Flight) -> bool {
+        match self {
+            ListFilter::All => true,
+            ListFilter::Active => !matches!(
+                flight.status,
+                FlightStatus::Landed | FlightStatus::Cancelled | FlightStatus::NotFound
+            ),
+            ListFilter::Problems => matches!(
+                flight.status,
+                FlightStatus::Delayed | FlightStatus::Cancelled | FlightStatus::NotFound
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for ListFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ListFilter::All => write!(f, "all"),
+            ListFilter::Active => write!(f, "active"),
+            ListFilter::Problems => write!(f, "problems"),
+        }
+    }
+}
+
+/// When the latest position snapshot was generated, and how far behind the
+/// clock it was on arrival.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeedTime {
+    pub snapshot: DateTime<Utc>,
+    pub lag_secs: i64,
+}
+
+/// Contents of the diagnostics overlay.
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+    /// Position provider first, then schedule provider, each with its role
+    /// as in [`ProviderHealth`].
+    pub providers: Vec<(&'static str, ProviderDiagnostics)>,
+    pub data_dir: Option<PathBuf>,
+    pub config_path: Option<PathBuf>,
+    pub log_path: Option<PathBuf>,
+}
+
+/// How many tracked flights are in each headline state, for the dashboard
+/// line. A flight counts in at most one of the states.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FlightCounts {
+    pub tracked: usize,
+    pub en_route: usize,
+    /// Not yet departed, and running late.
+    pub delayed: usize,
+    /// Landed, or arrived at the destination.
+    pub landed: usize,
+}
+
+/// A provider's recent requests, for the dashboard line. Refreshed from its
+/// telemetry every tick.
+#[derive(Debug, Clone, Default)]
+pub struct ProviderHealth {
+    pub name: &'static str,
+    /// What the provider supplies, "position" or "schedule".
+    pub role: &'static str,
+    pub stats: ProviderStats,
+    /// Being skipped after repeated failures, until a probe succeeds.
+    pub degraded: bool,
+    /// Requests made against a metered monthly quota, if it has one.
+    pub requests_this_month: Option<u32>,
+    /// How its requests are authenticated, if it has a notion of that.
+    pub auth: Option<AuthMode>,
+}
+
+/// A provider's response cache, as cleared from the diagnostics overlay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CacheKind {
+    Position,
+    Schedule,
+}
+
+impl CacheKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            CacheKind::Position => "position",
+            CacheKind::Schedule => "schedule",
+        }
+    }
+}
+
+/// A tab of the right-hand panel, in the order of its number key.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RightPanel {
+    #[default]
+    Details,
+    Map,
+    /// What the pinned flight has done so far
+    Log,
+    /// The destination's METAR and TAF
+    Weather,
+    /// The pinned flight's last payload from each provider
+    Raw,
+}
+
+impl RightPanel {
+    pub const ALL: [RightPanel; 5] = [
+        RightPanel::Details,
+        RightPanel::Map,
+        RightPanel::Log,
+        RightPanel::Weather,
+        RightPanel::Raw,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            RightPanel::Details => "Details",
+            RightPanel::Map => "Map",
+            RightPanel::Log => "Log",
+            RightPanel::Weather => "Weather",
+            RightPanel::Raw => "Raw",
+        }
+    }
+
+    /// The tab behind `n`, counting from 1.
+    pub fn numbered(n: u32) -> Option<Self> {
+        let index = usize::try_from(n).ok()?.checked_sub(1)?;
+        Self::ALL.get(index).copied()
+    }
+
+    /// Whether the tab is about one flight, so each flight remembers it.
+    /// The map shows them all.
+    pub fn per_flight(self) -> bool {
+        self != RightPanel::Map
+    }
+
+    /// Whether PgUp and PgDn scroll the tab.
+    pub fn scrolls(self) -> bool {
+        matches!(self, RightPanel::Log | RightPanel::Raw)
+    }
+
+    fn cycled(self, backward: bool) -> Self {
+        let i = Self::ALL
+            .iter()
+            .position(|&p| p == self)
+            .unwrap_or_default();
+        let step = if backward { Self::ALL.len() - 1 } else { 1 };
+        Self::ALL[(i + step) % Self::ALL.len()]
+    }
+}
+
+/// Which flights a bulk removal takes, once confirmed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BulkRemoval {
+    /// Landed, cancelled and not-found flights
+    Finished,
+    All,
+}
+
+impl BulkRemoval {
+    pub fn takes(self, flight: &Flight) -> bool {
+        match self {
+            // Exactly what the active filter hides
+            BulkRemoval::Finished => !ListFilter::Active.matches(flight),
+            BulkRemoval::All => true,
+        }
+    }
+}
+
+/// Flights taken off the list together, for `u` to put back.
+#[derive(Debug, Clone)]
+struct Removal {
+    /// Each flight with its index before the removal, in index order
+    flights: Vec<(usize, Flight)>,
+    /// Flight number of the selection before the removal
+    selected: Option<String>,
+}
+
+/// The flight selected in a list view when it was left, and where in the
+/// view it was.
+#[derive(Debug, Clone)]
+struct ViewSelection {
+    flight_number: String,
+    position: usize,
+}
+
+/// Which optional integrations are configured, detected once at startup.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Capabilities {
+    /// An AviationStack key is set, so routes and schedules can be fetched.
+    pub schedules: bool,
+    /// OpenSky credentials are set. They may still be rejected later.
+    pub opensky_authenticated: bool,
+    /// The OpenSky credential that is missing when only the other one is
+    /// set, which leaves access anonymous.
+    pub opensky_missing: Option<&'static str>,
+    /// Scripted demo providers, which need no configuration.
+    pub demo: bool,
+}
+
+/// Where keys can be put instead of exporting them.
+pub const ENV_FILE_HINT: &str =
+    "Keys can go in a .env file in the working directory or ~/.config/flight-tracker-tui";
+
+impl Capabilities {
+    /// Detect integrations from the process environment.
+    pub fn from_env() -> Self {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    /// Detect integrations from environment variables returned by `lookup`.
+    /// Empty values count as unset.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let value = |key: &str| lookup(key).filter(|v| !v.trim().is_empty());
+        let (username, password) = (value("OPENSKY_USERNAME"), value("OPENSKY_PASSWORD"));
+        Self {
+            schedules: value("AVIATIONSTACK_API_KEY").is_some(),
+            opensky_authenticated: username.is_some() && password.is_some(),
+            opensky_missing: missing_credential(username.as_deref(), password.as_deref()),
+            demo: false,
+        }
+    }
+
+    pub fn demo() -> Self {
+        Self {
+            demo: true,
+            ..Default::default()
+        }
+    }
+
+    /// Setup hints for the integrations that are missing.
+    pub fn warnings(&self) -> Vec<&'static str> {
+        if self.demo {
+            return Vec::new();
+        }
+        let mut warnings = Vec::new();
+        if !self.schedules {
+            warnings.push("Route data disabled — set AVIATIONSTACK_API_KEY (optional)");
+        }
+        match self.opensky_missing {
+            Some("OPENSKY_PASSWORD") => warnings.push(
+                "OPENSKY_USERNAME is set without OPENSKY_PASSWORD — using anonymous OpenSky access",
+            ),
+            Some(_) => warnings.push(
+                "OPENSKY_PASSWORD is set without OPENSKY_USERNAME — using anonymous OpenSky access",
+            ),
+            None if !self.opensky_authenticated => warnings.push(
+                "Using anonymous OpenSky access — set OPENSKY_USERNAME/PASSWORD for higher limits",
+            ),
+            None => {}
+        }
+        warnings
+    }
+
+    /// One-line summary of the missing integrations for the status bar.
+    pub fn banner(&self) -> Option<String> {
+        let warnings = self.warnings();
+        if warnings.is_empty() {
+            return None;
+        }
+        Some(format!("{} (see .env)", warnings.join(" | ")))
+    }
+}
+
+/// Flights from the previous session held back at startup, so restoring a
+/// long list doesn't fire a search for every flight before the user has
+/// seen what's there.
+#[derive(Debug, Clone)]
+pub struct RestoreSummary {
+    /// Searches to run once restoring resumes, in session order.
+    pub queries: Vec<FlightQuery>,
+    /// Flight numbers among them that have probably landed since.
+    pub likely_landed: Vec<String>,
+    /// When the searches go out without a key press.
+    pub resume_at: Instant,
+}
+
+impl RestoreSummary {
+    /// Headline for the details pane.
+    pub fn message(&self) -> String {
+        let count = self.queries.len();
+        let flights = if count == 1 { "flight" } else { "flights" };
+        match self.likely_landed.len() {
+            0 => format!(
+                "Restored {} {} from last session — press r to refresh all",
+                count, flights
+            ),
+            landed => format!(
+                "Restored {} {} from last session — {} likely landed, \
+                 press r to refresh all or d to prune",
+                count, flights, landed
+            ),
+        }
+    }
+}
+
+/// Outcome of queueing a batch of flights for tracking.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ImportReport {
+    pub added: usize,
+    pub duplicates: usize,
+    pub invalid: usize,
+}
+
+impl std::fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Added {} flight(s), skipped {} duplicate(s), {} invalid",
+            self.added, self.duplicates, self.invalid
+        )
+    }
+}
+
+/// The guessed return leg the input was opened with by `o`, shown in its
+/// title until the input is submitted or left.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReturnPrompt {
+    /// The flight whose return leg is guessed
+    pub of: String,
+    /// Its route reversed, as airport codes
+    pub from: String,
+    pub to: String,
+    /// Guesses, the one in the input first; Tab moves to the next
+    pub guesses: Vec<String>,
+}
+
+/// A search sent out and not yet answered.
+#[derive(Debug)]
+struct RunningSearch {
+    query: FlightQuery,
+    deadline: Instant,
+    /// The task doing the lookups, aborted if it runs past the deadline
+    task: Option<AbortHandle>,
 }
 
 #[derive(Debug)]
@@ -19,21 +464,161 @@ pub struct App {
 
     pub input_buffer: String,
     pub cursor_position: usize,
+    /// Tracked flight the input line already names, if any
+    pub input_match: Option<String>,
+    /// Guessed return flight the input was prefilled with, if any
+    pub return_prompt: Option<ReturnPrompt>,
+    /// Flight number input left with Esc, restored on the next `/`
+    pub input_draft: String,
+    draft_cursor: usize,
 
     pub tracked_flights: Vec<Flight>,
+    /// Index into `tracked_flights` (not the filtered view) of the selection
     pub selected_index: Option<usize>,
+    /// Flight the view is locked onto with `F`, by flight number. While set,
+    /// the details pane and map stay on it and `selected_index` is only the
+    /// list cursor.
+    pub following: Option<String>,
+    pub filter: ListFilter,
+    /// Free text the list is narrowed by on top of `filter`, from Ctrl+F
+    pub list_query: String,
+    /// Whether the Arrived section of the list is expanded
+    pub show_arrived: bool,
+    /// Named groups of tracked flights, listed above the other flights
+    pub trips: Vec<Trip>,
+    /// Trips opened with → or closed with ← this session, by name, over
+    /// the default of closing once a leg has landed
+    trips_expanded: HashMap<String, bool>,
 
     pub loading: bool,
     pub last_error: Option<String>,
     pub status_message: Option<String>,
 
     pub last_api_call: Option<Instant>,
+    /// Newest position snapshot received
+    pub feed_time: Option<FeedTime>,
+    /// Position readings ignored this session as implausible, by kind
+    pub rejected_readings: HashMap<Reading, u64>,
+    /// Refresh interval for flights whose phase is unknown.
     pub update_interval_secs: u64,
+    /// When each flight's position was last requested, by flight number
+    last_polled: HashMap<String, Instant>,
+    /// When each flight's schedule was last requested, by flight number
+    last_schedule_refresh: HashMap<String, Instant>,
+
+    pub config: Config,
+    /// Colors for the terminal's background, detected at startup
+    pub theme: Theme,
+    /// Names shown for aircraft type codes
+    pub aircraft_types: AircraftTypes,
+
+    /// Whether the terminal has focus. Terminals that never report focus
+    /// changes stay focused.
+    pub focused: bool,
+    /// Refresh interval multiplier while unfocused; 0 pauses refreshing.
+    pub background_multiplier: u64,
+    /// How the daily position credits are being spread, or `None` when the
+    /// position provider isn't metered
+    pub budget: Option<BudgetPlan>,
+    /// Integrations configured at startup
+    pub capabilities: Capabilities,
+
+    /// The right-hand panel's active tab
+    pub right_panel: RightPanel,
+    /// Last tab shown for each flight, by flight number, to go back to
+    /// when the flight is pinned again
+    panel_by_flight: HashMap<String, RightPanel>,
+    /// Lines scrolled past in the active tab, if it scrolls
+    pub panel_scroll: u16,
+    /// Whether the selected flight's altitude chart is shown under the
+    /// right-hand panel
+    pub show_profile: bool,
+    /// Area shown by the map overview, fitted to the tracked positions
+    pub map_viewport: Option<BoundingBox>,
+    /// Diagnostics overlay contents while it is open
+    pub diagnostics: Option<Diagnostics>,
+    /// Row of the settings overlay the cursor is on
+    pub settings_cursor: usize,
+    /// Cache the diagnostics overlay is waiting for `y` to clear
+    pub pending_cache_clear: Option<CacheKind>,
+    /// Bulk removal waiting for `y`
+    pub pending_removal: Option<BulkRemoval>,
+    /// Position provider first, then schedule provider
+    pub provider_health: Vec<ProviderHealth>,
 
     /// Flight history for quick re-tracking
     pub history: History,
     /// Currently selected history index (for cycling through history)
     pub history_index: Option<usize>,
+
+    /// Most recent alert, shown in the status bar until the next key press
+    pub alert_message: Option<String>,
+    /// Alerts fired since the last drain, awaiting bell/desktop delivery
+    pub pending_alerts: Vec<String>,
+    /// Whether the layout is replaced by linear plain text for screen
+    /// readers (`--accessible`)
+    pub accessible: bool,
+    /// Changes announced at the bottom of the accessible layout, newest last
+    pub announcements: Vec<String>,
+
+    /// Flights waiting to be searched on both providers
+    pub pending_searches: Vec<FlightQuery>,
+    /// (flight number, ICAO24) pairs waiting for a track lookup
+    pub pending_tracks: Vec<(String, String)>,
+    /// Flights whose aircraft looks swapped, waiting for a callsign search
+    pub pending_rematches: Vec<String>,
+    /// Weather at the tracked flights' destinations, by ICAO code
+    pub weather: HashMap<String, Weather>,
+    /// Session flights held back until `r`, `d` or the grace period ends
+    pub restore: Option<RestoreSummary>,
+    /// Data directory the first-run walkthrough writes to while it is shown
+    pub onboarding_dir: Option<PathBuf>,
+    /// Searches dispatched but not yet answered, by flight number
+    searches_in_progress: HashMap<String, RunningSearch>,
+    /// Searches given up on after `SEARCH_TIMEOUT`, sent again by `r`
+    timed_out_searches: Vec<FlightQuery>,
+    /// Saved session entries whose alert rules and delay history are
+    /// re-attached when each flight is added
+    restored_flights: HashMap<String, SessionFlight>,
+    /// Watchlist file contents read since the last refresh, applied once it
+    /// is over
+    pub watchlist_pending: Option<Vec<FlightQuery>>,
+    /// Flight numbers the watchlist file listed when last applied
+    watchlist_listed: HashSet<String>,
+    /// Watchlist flights queued or being searched, by flight number
+    watchlist_queued: HashSet<String>,
+    /// Aircraft whose track has been asked for, by ICAO24
+    tracks_requested: HashSet<String>,
+    /// When each flight last queued a callsign search for a swapped
+    /// aircraft, by flight number
+    rematched: HashMap<String, Instant>,
+    /// When each destination's weather was last asked for, by ICAO code
+    weather_requested: HashMap<String, Instant>,
+    /// Flights whose arriving-soon notice has gone off, by flight number
+    arrival_notices_sent: HashSet<String>,
+    /// Status changes waiting to be posted to the configured webhook
+    webhooks: Debouncer,
+    /// Whether a webhook post has failed since the last one that went
+    /// through, so the failure is only shown once
+    webhook_failing: bool,
+    /// Removals made with `d` or a bulk removal, newest last
+    undo_stack: Vec<Removal>,
+    /// Selection last held under each filter, restored on cycling back
+    view_selections: HashMap<ListFilter, ViewSelection>,
+    /// Clock chosen with `t` this session or a previous one, which wins
+    /// over the config's
+    chosen_time_format: Option<TimeFormat>,
+    /// Layout chosen with Ctrl+T this session or a previous one, which wins
+    /// over the config's
+    chosen_layout: Option<ScreenLayout>,
+    /// `config.toml` keys changed in the settings overlay this session, with
+    /// their new values. All of them are written on each close, so a write
+    /// still queued can't lose an earlier change.
+    settings_changed: Vec<(&'static str, String)>,
+    /// When the view preferences last changed, while they wait to be saved
+    ui_changed_at: Option<Instant>,
+    /// Whether tracked flights and view preferences are written back to disk
+    persist_session: bool,
 }
 
 impl Default for App {
@@ -43,26 +628,483 @@ impl Default for App {
             should_quit: false,
             input_buffer: String::new(),
             cursor_position: 0,
+            input_match: None,
+            return_prompt: None,
+            input_draft: String::new(),
+            draft_cursor: 0,
             tracked_flights: Vec::new(),
             selected_index: None,
+            following: None,
+            filter: ListFilter::default(),
+            list_query: String::new(),
+            show_arrived: false,
+            trips: Vec::new(),
+            trips_expanded: HashMap::new(),
             loading: false,
             last_error: None,
             status_message: None,
             last_api_call: None,
+            feed_time: None,
+            rejected_readings: HashMap::new(),
             update_interval_secs: 30,
+            last_polled: HashMap::new(),
+            last_schedule_refresh: HashMap::new(),
+            config: Config::default(),
+            theme: Theme::default(),
+            aircraft_types: AircraftTypes::default(),
+            focused: true,
+            background_multiplier: 4,
+            budget: None,
+            capabilities: Capabilities::default(),
+            right_panel: RightPanel::Details,
+            panel_by_flight: HashMap::new(),
+            panel_scroll: 0,
+            show_profile: false,
+            map_viewport: None,
+            diagnostics: None,
+            settings_cursor: 0,
+            pending_cache_clear: None,
+            pending_removal: None,
+            provider_health: Vec::new(),
             history: History::default(),
             history_index: None,
+            alert_message: None,
+            pending_alerts: Vec::new(),
+            accessible: false,
+            announcements: Vec::new(),
+            pending_searches: Vec::new(),
+            pending_tracks: Vec::new(),
+            pending_rematches: Vec::new(),
+            weather: HashMap::new(),
+            restore: None,
+            onboarding_dir: None,
+            searches_in_progress: HashMap::new(),
+            timed_out_searches: Vec::new(),
+            restored_flights: HashMap::new(),
+            watchlist_pending: None,
+            watchlist_listed: HashSet::new(),
+            watchlist_queued: HashSet::new(),
+            tracks_requested: HashSet::new(),
+            rematched: HashMap::new(),
+            weather_requested: HashMap::new(),
+            arrival_notices_sent: HashSet::new(),
+            webhooks: Debouncer::default(),
+            webhook_failing: false,
+            undo_stack: Vec::new(),
+            view_selections: HashMap::new(),
+            chosen_time_format: None,
+            chosen_layout: None,
+            settings_changed: Vec::new(),
+            ui_changed_at: None,
+            persist_session: false,
         }
     }
 }
 
 impl App {
-    /// Create a new App with history loaded from disk.
+    /// Create a new App with history and the previous session loaded from disk.
     pub fn new() -> Self {
-        Self {
+        let mut app = Self {
             history: History::load(),
+            config: Config::load(),
+            aircraft_types: AircraftTypes::load(),
+            persist_session: true,
             ..Default::default()
+        };
+        app.background_multiplier = app.config.background_refresh;
+        app.restore_ui_state(UiState::load());
+        app.restore_session(Session::load(), Local::now(), Instant::now());
+        if app.backfill_history_routes() {
+            app.history.save();
+        }
+        app
+    }
+
+    /// Bring history entries up to date with the routes saved in the
+    /// restored session. Returns whether any changed.
+    fn backfill_history_routes(&mut self) -> bool {
+        let mut changed = false;
+        for flight in self.restored_flights.values() {
+            if let Some(route) = &flight.route {
+                changed |= self
+                    .history
+                    .update_route(&flight.flight_number, route.clone());
+            }
+        }
+        changed
+    }
+
+    /// Hold the session's flights back for `RESTORE_GRACE` after `at`,
+    /// noting which have probably landed by `now`.
+    fn restore_session(&mut self, session: Session, now: DateTime<Local>, at: Instant) {
+        self.trips = session.trips;
+        if session.flights.is_empty() {
+            return;
+        }
+        let queries = session
+            .flights
+            .iter()
+            .map(|f| FlightQuery {
+                flight_number: f.flight_number.clone(),
+                date: f.date,
+            })
+            .collect();
+        let likely_landed = session
+            .flights
+            .iter()
+            .filter(|f| f.likely_landed(now))
+            .map(|f| f.flight_number.clone())
+            .collect();
+        self.restored_flights = session
+            .flights
+            .into_iter()
+            .map(|f| (f.flight_number.clone(), f))
+            .collect();
+        self.restore = Some(RestoreSummary {
+            queries,
+            likely_landed,
+            resume_at: at + RESTORE_GRACE,
+        });
+        self.mode = AppMode::Viewing;
+    }
+
+    /// Queue the held-back session flights for searching.
+    pub fn resume_restored(&mut self) {
+        let Some(restore) = self.restore.take() else {
+            return;
+        };
+        info!(count = restore.queries.len(), "Refreshing restored flights");
+        self.queue_flights(restore.queries, 0);
+    }
+
+    /// Open the first-run walkthrough if `dir` looks like a fresh install
+    /// and no API key is set.
+    pub fn begin_onboarding(&mut self, dir: PathBuf) {
+        let has_keys = self.capabilities.schedules || self.capabilities.opensky_authenticated;
+        if onboarding::is_first_run(&dir, has_keys) {
+            self.mode = AppMode::Onboarding;
+            self.onboarding_dir = Some(dir);
+        }
+    }
+
+    /// Write the commented `config.toml` and `.env` templates from the
+    /// walkthrough, reporting what was created.
+    pub fn create_starter_files(&mut self) {
+        let Some(dir) = &self.onboarding_dir else {
+            return;
+        };
+        self.status_message = Some(match onboarding::write_templates(dir) {
+            Ok(written) if written.is_empty() => format!("Already set up in {}", dir.display()),
+            Ok(written) => {
+                let names: Vec<_> = written
+                    .iter()
+                    .filter_map(|p| p.file_name())
+                    .map(|n| n.to_string_lossy())
+                    .collect();
+                format!("Created {} in {}", names.join(" and "), dir.display())
+            }
+            Err(e) => format!("Could not create {}: {}", dir.display(), e),
+        });
+    }
+
+    /// Close the walkthrough for good, optionally with the suggested flight
+    /// typed into the input ready to search.
+    pub fn finish_onboarding(&mut self, try_suggestion: bool) {
+        let Some(dir) = self.onboarding_dir.take() else {
+            return;
+        };
+        onboarding::mark_done(&dir);
+        self.mode = AppMode::Input;
+        if try_suggestion {
+            self.input_buffer = onboarding::SUGGESTED_FLIGHT.to_string();
+            self.cursor_position = self.input_buffer.len();
+        }
+    }
+
+    /// Drop the restored flights that have probably landed, then search for
+    /// the rest.
+    pub fn prune_restored(&mut self) {
+        let Some(restore) = self.restore.as_mut() else {
+            return;
+        };
+        let landed = std::mem::take(&mut restore.likely_landed);
+        restore
+            .queries
+            .retain(|q| !landed.contains(&q.flight_number));
+        for flight_number in &landed {
+            self.restored_flights.remove(flight_number);
+        }
+        info!(
+            count = landed.len(),
+            "Pruned landed flights from the restored session"
+        );
+        self.resume_restored();
+        if self.pending_searches.is_empty() {
+            // Nothing left to search whose result would rewrite the session
+            self.save_session();
+        }
+        self.status_message = Some(format!("Pruned {} landed flight(s)", landed.len()));
+    }
+
+    /// Resume restoring once the grace period has passed without `r` or `d`.
+    pub fn end_restore_grace(&mut self, now: Instant) {
+        if self.restore.as_ref().is_some_and(|r| now >= r.resume_at) {
+            self.resume_restored();
+        }
+    }
+
+    fn save_session(&self) {
+        if self.persist_session {
+            self.session().save();
+        }
+    }
+
+    /// The session to save: the tracked flights, then the restored ones
+    /// not added yet, whether still held back or searched without an
+    /// answer, so saving meanwhile doesn't lose them.
+    fn session(&self) -> Session {
+        let mut session = Session::from_flights(&self.tracked_flights);
+        let mut held: Vec<&SessionFlight> = self
+            .restored_flights
+            .values()
+            .filter(|f| {
+                !session
+                    .flights
+                    .iter()
+                    .any(|s| s.flight_number == f.flight_number)
+            })
+            .collect();
+        held.sort_by(|a, b| a.flight_number.cmp(&b.flight_number));
+        session.flights.extend(held.into_iter().cloned());
+        session.trips = self.trips.clone();
+        session
+    }
+
+    /// Put the view back the way it was saved, over the config's defaults.
+    fn restore_ui_state(&mut self, state: UiState) {
+        if let Some(time_format) = state.time_format {
+            self.config.time_format = time_format;
+        }
+        self.chosen_time_format = state.time_format;
+        if let Some(layout) = state.layout {
+            self.config.layout = layout;
+        }
+        self.chosen_layout = state.layout;
+        self.filter = state.filter;
+        self.show_arrived = state.show_arrived;
+        self.show_profile = state.show_profile;
+        if state.show_map {
+            self.right_panel = RightPanel::Map;
+            self.fit_map();
+        }
+    }
+
+    /// The view preferences as they stand.
+    pub fn ui_state(&self) -> UiState {
+        UiState {
+            time_format: self.chosen_time_format,
+            layout: self.chosen_layout,
+            filter: self.filter,
+            show_arrived: self.show_arrived,
+            show_map: self.right_panel == RightPanel::Map,
+            show_profile: self.show_profile,
+        }
+    }
+
+    /// Note that a view preference changed, restarting the wait to save.
+    fn ui_changed(&mut self) {
+        self.ui_changed_at = Some(Instant::now());
+    }
+
+    /// Save the view preferences once they have stayed unchanged for
+    /// `SAVE_DELAY`, or straight away when `force`d, as on quitting.
+    /// Returns whether they were saved.
+    pub fn save_ui_state(&mut self, now: Instant, force: bool) -> bool {
+        let due = self
+            .ui_changed_at
+            .is_some_and(|at| force || now.duration_since(at) >= ui_state::SAVE_DELAY);
+        if !due {
+            return false;
+        }
+        self.ui_changed_at = None;
+        if self.persist_session {
+            self.ui_state().save();
+        }
+        true
+    }
+}
+
+impl App {
+    /// Carry out an action's state change. Anything that needs the API
+    /// clients or the filesystem comes back as effects for the caller.
+    pub fn apply(&mut self, action: Action) -> Vec<Effect> {
+        let mut effects = vec![];
+        match action {
+            Action::Quit => {
+                if self.mode == AppMode::Settings {
+                    self.close_settings();
+                }
+                self.should_quit = true;
+            }
+            Action::InsertChar(c) => self.input_char(c),
+            Action::Backspace => self.input_backspace(),
+            Action::ClearInput => self.clear_input(),
+            Action::Submit => match self.mode {
+                AppMode::Input => {
+                    if let Some(flight_number) = self.input_match.clone() {
+                        self.jump_to_flight(&flight_number);
+                    } else if let Some(input) = self.submit_input() {
+                        self.last_error = None;
+                        self.queue_input(&input);
+                    }
+                }
+                AppMode::AlertInput => {
+                    self.last_error = None;
+                    self.submit_alert_input();
+                }
+                AppMode::ImportInput => {
+                    self.last_error = None;
+                    self.submit_import_input();
+                }
+                AppMode::ListSearch => self.end_list_search(true),
+                AppMode::Command => match palette::resolve(&self.input_buffer) {
+                    Ok(action) => {
+                        self.last_error = None;
+                        self.mode = AppMode::Viewing;
+                        self.input_buffer.clear();
+                        self.cursor_position = 0;
+                        effects = self.apply(action);
+                    }
+                    Err(message) => self.last_error = Some(message),
+                },
+                AppMode::Viewing | AppMode::Onboarding | AppMode::Settings => {}
+            },
+            Action::CancelInput => match self.mode {
+                AppMode::Input => self.leave_input(),
+                AppMode::AlertInput | AppMode::ImportInput | AppMode::Command => {
+                    self.mode = AppMode::Viewing;
+                    self.input_buffer.clear();
+                    self.cursor_position = 0;
+                }
+                AppMode::ListSearch => self.end_list_search(false),
+                AppMode::Viewing | AppMode::Onboarding | AppMode::Settings => {}
+            },
+            Action::HistoryNext => self.history_next(),
+            Action::HistoryPrevious => self.history_previous(),
+            Action::CompleteCommand => {
+                if let Some(line) = palette::tab_complete(&self.input_buffer) {
+                    self.input_buffer = line;
+                    self.cursor_position = self.input_buffer.len();
+                }
+            }
+            Action::BeginInput => self.begin_input(),
+            Action::BeginRetrack => self.begin_retrack_input(),
+            Action::BeginReturnInput => self.begin_return_input(),
+            Action::NextReturnGuess => self.next_return_guess(),
+            Action::RetrySearch => self.retry_selected_search(),
+            Action::RepeatLastSearch => self.repeat_last_search(),
+            // The tabs belong to the pinned flight, which only the cursor
+            // moves away from while following
+            Action::SelectPrevious => {
+                self.select_previous();
+                if self.following.is_none() {
+                    self.restore_flight_panel();
+                }
+            }
+            Action::SelectNext => {
+                self.select_next();
+                if self.following.is_none() {
+                    self.restore_flight_panel();
+                }
+            }
+            Action::Delete if self.restore.is_some() => self.prune_restored(),
+            Action::Delete => self.remove_selected_flight(),
+            Action::CycleFilter => self.cycle_filter(),
+            Action::BeginListSearch => self.begin_list_search(),
+            Action::ToggleArrived => self.toggle_arrived(),
+            Action::StepIntoTrip => self.expand_trip(true),
+            Action::StepOutOfTrip => self.expand_trip(false),
+            Action::BeginAlertInput => self.begin_alert_input(),
+            Action::BeginImportInput => self.begin_import_input(),
+            Action::ToggleMap => self.toggle_panel(RightPanel::Map),
+            Action::ToggleFollow => self.toggle_follow(),
+            Action::ToggleProfile => self.toggle_profile(),
+            Action::ToggleRawView => self.toggle_panel(RightPanel::Raw),
+            Action::ShowPanel(panel) => self.show_panel(panel),
+            Action::CyclePanel { backward } => self.show_panel(self.right_panel.cycled(backward)),
+            Action::ScrollPanel(lines) => self.scroll_panel(lines),
+            Action::ZoomMap { zoom_in } => self.zoom_map(zoom_in),
+            Action::ToggleTimeFormat => self.toggle_time_format(),
+            Action::ToggleLayout => self.toggle_layout(),
+            Action::ToggleRecurring => self.toggle_recurring(),
+            Action::CycleMute => self.cycle_mute(Utc::now()),
+            Action::ExportReport => effects.push(Effect::ExportReport),
+            Action::Refresh if self.restore.is_some() => self.resume_restored(),
+            Action::Refresh => {
+                self.retry_timed_out_searches();
+                if !self.tracked_flights.is_empty() && !self.loading {
+                    effects.push(Effect::StartRefresh(self.refreshable_flights()));
+                }
+            }
+            Action::BeginCommand => {
+                self.mode = AppMode::Command;
+                self.input_buffer.clear();
+                self.cursor_position = 0;
+            }
+            Action::ClearCache(kind) => {
+                self.status_message = Some(format!("Cleared the {} cache", kind.label()));
+                effects.push(Effect::ClearCache(kind));
+            }
+            Action::SetMinRefresh(secs) => {
+                self.config.min_refresh_secs = secs;
+                self.status_message =
+                    Some(format!("Refreshing each flight at most every {}s", secs));
+            }
+            Action::SetColumns(columns) => {
+                self.config.table.columns = columns;
+                let titles: Vec<&str> = self
+                    .config
+                    .table
+                    .resolve()
+                    .iter()
+                    .map(|c| c.title)
+                    .collect();
+                self.status_message =
+                    Some(format!("List and report columns: {}", titles.join(", ")));
+            }
+            Action::AddTrip { name, legs } => self.add_trip(name, legs),
+            Action::RemoveTrip(name) => self.remove_trip(&name),
+            Action::OpenSettings => {
+                self.diagnostics = None;
+                self.mode = AppMode::Settings;
+            }
+            Action::MoveSettingsCursor(delta) => {
+                let last = settings::SETTINGS.len() as isize - 1;
+                let row = (self.settings_cursor as isize + delta as isize).clamp(0, last);
+                self.settings_cursor = row as usize;
+            }
+            Action::ChangeSetting { backward } => self.change_setting(backward),
+            Action::CloseSettings => self.close_settings(),
+            Action::ToggleDiagnostics if self.diagnostics.is_some() => self.diagnostics = None,
+            Action::ToggleDiagnostics => effects.push(Effect::OpenDiagnostics),
+            Action::CloseDiagnostics => self.diagnostics = None,
+            Action::RequestCacheClear(kind) => self.request_cache_clear(kind),
+            Action::RequestRemoval(kind) => self.request_removal(kind),
+            Action::AnswerRemoval(confirmed) => self.answer_removal(confirmed),
+            Action::Undo => self.undo_removal(),
+            Action::AnswerCacheClear(confirmed) => {
+                if let Some(kind) = self.answer_cache_clear(confirmed) {
+                    effects.push(Effect::ClearCache(kind));
+                }
+            }
+            Action::CreateStarterFiles => self.create_starter_files(),
+            Action::FinishOnboarding { try_suggestion } => self.finish_onboarding(try_suggestion),
         }
+        // A palette command's own apply may have asked already
+        if !self.pending_searches.is_empty() && !effects.contains(&Effect::StartSearches) {
+            effects.push(Effect::StartSearches);
+        }
+        effects
     }
 }
 
@@ -71,6 +1113,8 @@ impl App {
         self.input_buffer.insert(self.cursor_position, c);
         self.cursor_position += 1;
         self.history_index = None; // Reset history navigation on typing
+        self.update_input_match();
+        self.update_list_query();
     }
 
     pub fn input_backspace(&mut self) {
@@ -79,6 +1123,8 @@ impl App {
             self.input_buffer.remove(self.cursor_position);
         }
         self.history_index = None; // Reset history navigation on typing
+        self.update_input_match();
+        self.update_list_query();
     }
 
     pub fn submit_input(&mut self) -> Option<String> {
@@ -89,40 +1135,458 @@ impl App {
         self.input_buffer.clear();
         self.cursor_position = 0;
         self.history_index = None;
+        self.input_match = None;
+        self.return_prompt = None;
         Some(input)
     }
 
-    /// Cycle to previous history entry (up arrow in input mode).
-    pub fn history_previous(&mut self) {
-        if self.history.is_empty() {
-            return;
+    /// Switch to flight number input, restoring any draft left with Esc.
+    pub fn begin_input(&mut self) {
+        self.mode = AppMode::Input;
+        if self.input_buffer.is_empty() {
+            self.input_buffer = std::mem::take(&mut self.input_draft);
+            self.cursor_position = self.draft_cursor.min(self.input_buffer.len());
         }
+        self.history_index = None;
+        self.return_prompt = None;
+        self.update_input_match();
+    }
 
-        let entries: Vec<_> = self.history.entries().collect();
-        let new_index = match self.history_index {
-            None => 0,
-            Some(i) => (i + 1).min(entries.len() - 1),
+    /// Leave flight number input, keeping unsubmitted text as a draft.
+    pub fn leave_input(&mut self) {
+        self.mode = AppMode::Viewing;
+        self.input_draft = std::mem::take(&mut self.input_buffer);
+        self.draft_cursor = self.cursor_position;
+        self.cursor_position = 0;
+        self.history_index = None;
+        self.input_match = None;
+        self.return_prompt = None;
+    }
+
+    /// Discard the input line (Ctrl+U).
+    pub fn clear_input(&mut self) {
+        self.input_buffer.clear();
+        self.cursor_position = 0;
+        self.history_index = None;
+        self.input_match = None;
+    }
+
+    /// Note whether the input line names a single flight that is already
+    /// tracked. IATA and ICAO forms of the same flight match each other.
+    fn update_input_match(&mut self) {
+        let (valid, invalid) = import::parse_queries(&self.input_buffer, Local::now().date_naive());
+        self.input_match = match (valid.as_slice(), invalid.is_empty()) {
+            ([query], true) => {
+                let callsign = normalize_callsign(&query.flight_number);
+                self.tracked_flights
+                    .iter()
+                    .find(|f| normalize_callsign(&f.flight_number) == callsign)
+                    .map(|f| f.flight_number.clone())
+            }
+            _ => None,
         };
+    }
 
-        self.history_index = Some(new_index);
-        if let Some(entry) = entries.get(new_index) {
-            self.input_buffer = entry.flight_number.clone();
-            self.cursor_position = self.input_buffer.len();
+    /// Select a tracked flight from the input line instead of searching for
+    /// it again, revealing it if the filter or a collapsed Arrived section
+    /// hides it. Returns whether the flight is tracked.
+    pub fn jump_to_flight(&mut self, flight_number: &str) -> bool {
+        let Some(index) = self
+            .tracked_flights
+            .iter()
+            .position(|f| f.flight_number == flight_number)
+        else {
+            return false;
+        };
+        let flight = &self.tracked_flights[index];
+        if !self.filter.matches(flight) {
+            self.filter = ListFilter::All;
+        }
+        if !flight.matches_text(&self.list_query) {
+            self.list_query.clear();
+        }
+        if flight.arrived_at.is_some() {
+            self.show_arrived = true;
         }
+        self.selected_index = Some(index);
+
+        self.mode = AppMode::Viewing;
+        self.input_buffer.clear();
+        self.cursor_position = 0;
+        self.history_index = None;
+        self.input_match = None;
+        self.status_message = Some(format!("Already tracking {}", flight_number));
+        true
     }
 
-    /// Cycle to next history entry (down arrow in input mode).
-    pub fn history_next(&mut self) {
-        if self.history.is_empty() {
+    /// Queue every flight number in a submitted input line for searching.
+    pub fn queue_input(&mut self, input: &str) {
+        let (valid, invalid) = import::parse_queries(input, Local::now().date_naive());
+
+        if valid.is_empty() {
+            if let Some(token) = invalid.first() {
+                self.last_error = Some(format!("Not a valid flight number: {}", token));
+            }
             return;
         }
-
-        match self.history_index {
-            None => {}
-            Some(0) => {
-                self.history_index = None;
-                self.input_buffer.clear();
-                self.cursor_position = 0;
+        if let [query] = valid.as_slice() {
+            if invalid.is_empty() && self.searches_in_progress.contains_key(&query.flight_number) {
+                self.status_message = Some(format!(
+                    "Search for {} already running",
+                    query.flight_number
+                ));
+                return;
+            }
+        }
+
+        let report = self.queue_flights(valid, invalid.len());
+        if report.added + report.duplicates + report.invalid > 1 {
+            self.status_message = Some(report.to_string());
+        } else if report.duplicates == 1 {
+            self.status_message = Some("Flight is already tracked".to_string());
+        }
+    }
+
+    /// Queue validated flights for searching, skipping flight numbers that
+    /// are already tracked, queued or being searched, or repeated in the batch.
+    pub fn queue_flights(
+        &mut self,
+        queries: impl IntoIterator<Item = FlightQuery>,
+        invalid: usize,
+    ) -> ImportReport {
+        let mut report = ImportReport {
+            invalid,
+            ..Default::default()
+        };
+        let mut seen: HashSet<String> = self
+            .tracked_flights
+            .iter()
+            .map(|f| f.flight_number.clone())
+            .chain(
+                self.pending_searches
+                    .iter()
+                    .map(|q| q.flight_number.clone()),
+            )
+            .chain(self.searches_in_progress.keys().cloned())
+            .collect();
+
+        for query in queries {
+            if seen.insert(query.flight_number.clone()) {
+                self.pending_searches.push(query);
+                report.added += 1;
+            } else {
+                report.duplicates += 1;
+            }
+        }
+
+        report
+    }
+
+    /// Make the tracked flights mirror the watchlist file's latest contents:
+    /// search for the flights it added and drop the ones it no longer lists.
+    /// Waits for the refresh under way and for a held-back session, so a
+    /// removal never lands in the middle of either.
+    pub fn apply_watchlist(&mut self) {
+        if self.loading || self.restore.is_some() {
+            return;
+        }
+        let Some(listed) = self.watchlist_pending.take() else {
+            return;
+        };
+        let changes = watchlist::reconcile(
+            &listed,
+            self.tracked_flights
+                .iter()
+                .map(|f| (f.flight_number.as_str(), f.from_watchlist)),
+        );
+        self.watchlist_listed = listed.into_iter().map(|q| q.flight_number).collect();
+        if changes.is_empty() {
+            return;
+        }
+
+        let removed: Vec<usize> = self
+            .tracked_flights
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| changes.remove.contains(&f.flight_number))
+            .map(|(i, _)| i)
+            .collect();
+        if !removed.is_empty() {
+            info!(flights = ?changes.remove, "Dropped flights no longer on the watchlist");
+            self.remove_flights(&removed);
+        }
+        // Only what this queues is the watchlist's; a search already under
+        // way for the same flight keeps its origin
+        let queued_before = self.pending_searches.len();
+        let report = self.queue_flights(changes.add, 0);
+        self.watchlist_queued.extend(
+            self.pending_searches[queued_before..]
+                .iter()
+                .map(|q| q.flight_number.clone()),
+        );
+        self.status_message = Some(format!(
+            "Watchlist: {} added, {} removed",
+            report.added,
+            removed.len()
+        ));
+    }
+
+    /// Take the queued searches for dispatch, marking them as in progress.
+    pub fn take_pending_searches(&mut self) -> Vec<FlightQuery> {
+        let deadline = Instant::now() + SEARCH_TIMEOUT;
+        for query in &self.pending_searches {
+            let search = RunningSearch {
+                query: query.clone(),
+                deadline,
+                task: None,
+            };
+            self.searches_in_progress
+                .insert(query.flight_number.clone(), search);
+        }
+        let pending = &self.pending_searches;
+        self.timed_out_searches.retain(|query| {
+            !pending
+                .iter()
+                .any(|p| p.flight_number == query.flight_number)
+        });
+        std::mem::take(&mut self.pending_searches)
+    }
+
+    /// Note the task running a dispatched search, so it can be aborted if it
+    /// overruns.
+    pub fn watch_search(&mut self, flight_number: &str, task: AbortHandle) {
+        if let Some(search) = self.searches_in_progress.get_mut(flight_number) {
+            search.task = Some(task);
+        }
+    }
+
+    /// Whether any dispatched search is still waiting for its answer.
+    pub fn is_searching(&self) -> bool {
+        !self.searches_in_progress.is_empty()
+    }
+
+    /// Mark a search as answered so its flight number can be submitted
+    /// again. Call it once the answer has been added, if it was: a
+    /// watchlist search that didn't add its flight stops counting as the
+    /// watchlist's here, so adding the flight later by hand keeps it manual.
+    pub fn finish_search(&mut self, flight_number: &str) {
+        self.searches_in_progress.remove(flight_number);
+        self.watchlist_queued.remove(flight_number);
+    }
+
+    /// Give up on searches past their deadline at `now`: abort their
+    /// tasks, stop the spinner if nothing else is searching, and keep them
+    /// for `r` to retry.
+    pub fn expire_searches(&mut self, now: Instant) {
+        let mut overdue: Vec<String> = self
+            .searches_in_progress
+            .iter()
+            .filter(|(_, search)| search.deadline <= now)
+            .map(|(flight_number, _)| flight_number.clone())
+            .collect();
+        if overdue.is_empty() {
+            return;
+        }
+        overdue.sort();
+        for flight_number in &overdue {
+            let Some(search) = self.searches_in_progress.remove(flight_number) else {
+                continue;
+            };
+            warn!(flight = %flight_number, "Search timed out");
+            // A retry with `r` is the user's, not the watchlist's
+            self.watchlist_queued.remove(flight_number);
+            if let Some(task) = search.task {
+                task.abort();
+            }
+            self.timed_out_searches.push(search.query);
+        }
+        if self.searches_in_progress.is_empty() {
+            self.loading = false;
+        }
+        self.status_message = Some(format!(
+            "Search timed out for {} — press r to retry",
+            overdue.join(", ")
+        ));
+    }
+
+    /// Queue the searches that timed out again.
+    fn retry_timed_out_searches(&mut self) {
+        let queries = std::mem::take(&mut self.timed_out_searches);
+        if !queries.is_empty() {
+            info!(count = queries.len(), "Retrying timed out searches");
+            self.queue_flights(queries, 0);
+        }
+    }
+
+    /// Read flights from a CSV or JSON file and queue them for searching.
+    pub fn import_file(&mut self, path: &Path) {
+        match import::load(path) {
+            Ok(file) => {
+                let report = self.queue_flights(file.entries, file.invalid);
+                self.status_message = Some(report.to_string());
+            }
+            Err(e) => self.last_error = Some(e.user_message()),
+        }
+    }
+
+    /// Switch to the import path prompt.
+    pub fn begin_import_input(&mut self) {
+        self.mode = AppMode::ImportInput;
+        self.input_buffer.clear();
+        self.cursor_position = 0;
+    }
+
+    /// Import from the path typed at the prompt.
+    pub fn submit_import_input(&mut self) {
+        let path = std::mem::take(&mut self.input_buffer);
+        self.cursor_position = 0;
+        self.mode = AppMode::Viewing;
+
+        let path = path.trim();
+        if !path.is_empty() {
+            self.import_file(Path::new(path));
+        }
+    }
+
+    /// Open the input prefilled with the selected flight's number, so one
+    /// more Enter re-tracks it.
+    pub fn begin_retrack_input(&mut self) {
+        let Some(flight_number) = self.selected_flight().map(|f| f.flight_number.clone()) else {
+            return;
+        };
+        self.input_buffer = flight_number;
+        self.cursor_position = self.input_buffer.len();
+        self.history_index = None;
+        self.return_prompt = None;
+        self.input_draft.clear();
+        self.mode = AppMode::Input;
+        self.update_input_match();
+    }
+
+    /// Open the input prefilled with a guess at the selected flight's return
+    /// leg, for the user to confirm, change or cancel. Nothing is searched
+    /// until they submit it. Needs the flight's airline and route.
+    pub fn begin_return_input(&mut self) {
+        let Some(flight) = self.selected_flight() else {
+            return;
+        };
+        let (Some(_), Some(origin), Some(destination)) =
+            (&flight.airline, &flight.origin, &flight.destination)
+        else {
+            self.status_message = Some(format!(
+                "{} has no known route to reverse",
+                flight.flight_number
+            ));
+            return;
+        };
+        let guesses = return_flight_numbers(&flight.flight_number);
+        let Some(guess) = guesses.first().cloned() else {
+            self.status_message = Some(format!(
+                "Can't guess a return flight for {}",
+                flight.flight_number
+            ));
+            return;
+        };
+        self.return_prompt = Some(ReturnPrompt {
+            of: flight.flight_number.clone(),
+            from: destination.code().to_string(),
+            to: origin.code().to_string(),
+            guesses,
+        });
+        self.input_buffer = guess;
+        self.cursor_position = self.input_buffer.len();
+        self.history_index = None;
+        self.input_draft.clear();
+        self.mode = AppMode::Input;
+        self.update_input_match();
+    }
+
+    /// Put the next return-leg guess in the input (Tab).
+    pub fn next_return_guess(&mut self) {
+        let Some(prompt) = &mut self.return_prompt else {
+            return;
+        };
+        prompt.guesses.rotate_left(1);
+        self.input_buffer = prompt.guesses[0].clone();
+        self.cursor_position = self.input_buffer.len();
+        self.update_input_match();
+    }
+
+    /// Search the selected flight again if nothing was found for it. The
+    /// entry stays in the list until the new search answers, and is then
+    /// replaced.
+    pub fn retry_selected_search(&mut self) {
+        let Some(flight) = self
+            .selected_flight()
+            .filter(|f| f.status == FlightStatus::NotFound)
+        else {
+            return;
+        };
+        let query = FlightQuery {
+            flight_number: flight.flight_number.clone(),
+            date: flight.flight_date,
+        };
+        let queued = self
+            .pending_searches
+            .iter()
+            .any(|q| q.flight_number == query.flight_number);
+        if queued || self.searches_in_progress.contains_key(&query.flight_number) {
+            self.status_message = Some(format!(
+                "Search for {} already running",
+                query.flight_number
+            ));
+            return;
+        }
+        self.status_message = Some(format!("Searching for {} again", query.flight_number));
+        self.pending_searches.push(query);
+    }
+
+    /// Track the most recent history entry again, without the input line.
+    pub fn repeat_last_search(&mut self) {
+        let Some(flight_number) = self
+            .history
+            .entries()
+            .next()
+            .map(|e| e.flight_number.clone())
+        else {
+            self.status_message = Some("No recent flights to repeat".to_string());
+            return;
+        };
+        self.queue_input(&flight_number);
+    }
+
+    /// Cycle to previous history entry (up arrow in input mode).
+    pub fn history_previous(&mut self) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        let entries: Vec<_> = self.history.entries().collect();
+        let new_index = match self.history_index {
+            None => 0,
+            Some(i) => (i + 1).min(entries.len() - 1),
+        };
+
+        self.history_index = Some(new_index);
+        if let Some(entry) = entries.get(new_index) {
+            self.input_buffer = entry.flight_number.clone();
+            self.cursor_position = self.input_buffer.len();
+        }
+        self.update_input_match();
+    }
+
+    /// Cycle to next history entry (down arrow in input mode).
+    pub fn history_next(&mut self) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        match self.history_index {
+            None => {}
+            Some(0) => {
+                self.history_index = None;
+                self.input_buffer.clear();
+                self.cursor_position = 0;
             }
             Some(i) => {
                 let entries: Vec<_> = self.history.entries().collect();
@@ -133,354 +1597,5064 @@ impl App {
                 }
             }
         }
+        self.update_input_match();
+    }
+
+    /// Indices into `tracked_flights` of the flights the list shows, in
+    /// display order: the legs shown under each trip, then the other active
+    /// flights, then the Arrived section if it is expanded.
+    pub fn visible_indices(&self) -> Vec<usize> {
+        let mut visible: Vec<usize> = self.trips.iter().flat_map(|t| self.trip_rows(t)).collect();
+        visible.extend(self.filtered_indices(false));
+        if self.show_arrived {
+            visible.extend(self.arrived_indices());
+        }
+        visible
+    }
+
+    /// Indices of arrived flights outside any trip the filter shows,
+    /// whether or not the Arrived section is expanded.
+    pub fn arrived_indices(&self) -> Vec<usize> {
+        self.filtered_indices(true)
+    }
+
+    /// Indices of the flights outside any trip the filter shows, arrived
+    /// or not.
+    fn filtered_indices(&self, arrived: bool) -> Vec<usize> {
+        let mut indices: Vec<usize> = self
+            .tracked_flights
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| f.arrived_at.is_some() == arrived && self.shows(f))
+            .filter(|(_, f)| self.trip_of(&f.flight_number).is_none())
+            .map(|(i, _)| i)
+            .collect();
+        // Flight numbers that found nothing, likely typos, sink to the bottom
+        indices.sort_by_key(|&i| self.tracked_flights[i].status == FlightStatus::NotFound);
+        indices
+    }
+
+    /// Whether the filter and the list search let `flight` through.
+    fn shows(&self, flight: &Flight) -> bool {
+        self.filter.matches(flight) && flight.matches_text(&self.list_query)
+    }
+
+    /// The trip `flight_number` is a leg of, if any.
+    pub fn trip_of(&self, flight_number: &str) -> Option<&Trip> {
+        self.trips.iter().find(|trip| trip.contains(flight_number))
+    }
+
+    /// Whether a trip lists all its legs, rather than only the current one.
+    /// Trips close by themselves once a leg has landed, unless opened with →.
+    pub fn is_trip_expanded(&self, trip: &Trip) -> bool {
+        self.trips_expanded
+            .get(&trip.name)
+            .copied()
+            .unwrap_or_else(|| trip.legs_done(&self.tracked_flights) == 0)
+    }
+
+    /// Indices of the legs listed under `trip` that the filter shows: all
+    /// of them while it is expanded, else only the current leg, or the last
+    /// one once every leg has landed.
+    pub fn trip_rows(&self, trip: &Trip) -> Vec<usize> {
+        let legs = trip.tracked_legs(&self.tracked_flights);
+        let shown = if self.is_trip_expanded(trip) {
+            legs
+        } else {
+            let current = trip
+                .current_leg(&self.tracked_flights)
+                .or(legs.last().copied());
+            current.into_iter().collect()
+        };
+        shown
+            .into_iter()
+            .filter(|&i| self.shows(&self.tracked_flights[i]))
+            .collect()
+    }
+
+    /// Step into the selected flight's trip, listing every leg, or back out
+    /// of it to the current leg, which takes the selection.
+    pub fn expand_trip(&mut self, expand: bool) {
+        let Some(trip) = self
+            .selected_flight()
+            .and_then(|flight| self.trip_of(&flight.flight_number))
+            .cloned()
+        else {
+            return;
+        };
+        self.trips_expanded.insert(trip.name.clone(), expand);
+        if !expand {
+            if let Some(&row) = self.trip_rows(&trip).first() {
+                self.selected_index = Some(row);
+            }
+        }
+        self.ensure_visible_selection();
+    }
+
+    /// Group flights as a trip, queueing a search for each leg not already
+    /// tracked. A flight is a leg of one trip at most.
+    pub fn add_trip(&mut self, name: String, legs: Vec<FlightQuery>) {
+        if self.trips.iter().any(|trip| trip.name == name) {
+            self.last_error = Some(format!("Trip {} already exists", name));
+            return;
+        }
+        let taken = legs
+            .iter()
+            .find_map(|leg| Some((&leg.flight_number, self.trip_of(&leg.flight_number)?)));
+        if let Some((flight_number, trip)) = taken {
+            self.last_error = Some(format!(
+                "{} is already in trip {}",
+                flight_number,
+                trip.label()
+            ));
+            return;
+        }
+        let trip = Trip::new(
+            name,
+            legs.iter().map(|leg| leg.flight_number.clone()).collect(),
+        );
+        info!(trip = %trip.name, legs = ?trip.legs, "Trip added");
+        self.status_message = Some(format!("Trip {}: {}", trip.label(), trip.legs.join(", ")));
+        self.trips.push(trip);
+        self.queue_flights(legs, 0);
+        self.ensure_visible_selection();
+        self.save_session();
+    }
+
+    /// Ungroup a trip, leaving its flights tracked.
+    pub fn remove_trip(&mut self, name: &str) {
+        let Some(index) = self.trips.iter().position(|trip| trip.name == name) else {
+            self.last_error = Some(format!("No trip named {}", name));
+            return;
+        };
+        let trip = self.trips.remove(index);
+        self.trips_expanded.remove(&trip.name);
+        self.status_message = Some(format!("Ungrouped trip {}", trip.label()));
+        self.ensure_visible_selection();
+        self.save_session();
+    }
+
+    /// Expand or collapse the Arrived section.
+    pub fn toggle_arrived(&mut self) {
+        self.show_arrived = !self.show_arrived;
+        self.ensure_visible_selection();
+        self.ui_changed();
+    }
+
+    /// Position in the filtered view of the selected flight.
+    pub fn selected_view_index(&self) -> Option<usize> {
+        let selected = self.selected_index?;
+        self.visible_indices().iter().position(|&i| i == selected)
     }
 
     pub fn select_next(&mut self) {
-        if self.tracked_flights.is_empty() {
+        let visible = self.visible_indices();
+        if visible.is_empty() {
             return;
         }
-        self.selected_index = Some(match self.selected_index {
-            Some(i) => (i + 1) % self.tracked_flights.len(),
+        let view_index = match self.selected_view_index() {
+            Some(i) => (i + 1) % visible.len(),
             None => 0,
-        });
+        };
+        self.selected_index = Some(visible[view_index]);
     }
 
     pub fn select_previous(&mut self) {
-        if self.tracked_flights.is_empty() {
+        let visible = self.visible_indices();
+        if visible.is_empty() {
             return;
         }
-        self.selected_index = Some(match self.selected_index {
-            Some(0) => self.tracked_flights.len() - 1,
+        let view_index = match self.selected_view_index() {
+            Some(0) | None => visible.len() - 1,
             Some(i) => i - 1,
-            None => self.tracked_flights.len() - 1,
+        };
+        self.selected_index = Some(visible[view_index]);
+    }
+
+    /// Move to the next list filter, going back to the flight selected the
+    /// last time it was on.
+    pub fn cycle_filter(&mut self) {
+        if let (Some(position), Some(flight)) = (self.selected_view_index(), self.selected_flight())
+        {
+            let selection = ViewSelection {
+                flight_number: flight.flight_number.clone(),
+                position,
+            };
+            self.view_selections.insert(self.filter, selection);
+        }
+        self.filter = self.filter.next();
+        self.restore_view_selection();
+        self.ui_changed();
+    }
+
+    /// Select the flight last selected under the current filter, or if it
+    /// has been removed or is no longer shown, the one now in its place.
+    /// A filter not used yet keeps the selection if it is still shown.
+    fn restore_view_selection(&mut self) {
+        let Some(saved) = self.view_selections.get(&self.filter) else {
+            self.ensure_visible_selection();
+            return;
+        };
+        let visible = self.visible_indices();
+        let same = visible
+            .iter()
+            .find(|&&i| self.tracked_flights[i].flight_number == saved.flight_number);
+        let neighbor = visible.get(saved.position).or(visible.last());
+        self.selected_index = same.or(neighbor).copied();
+    }
+
+    /// Start typing text to narrow the list by, from the current one.
+    pub fn begin_list_search(&mut self) {
+        self.mode = AppMode::ListSearch;
+        self.input_buffer = self.list_query.clone();
+        self.cursor_position = self.input_buffer.len();
+    }
+
+    /// Narrow the list to the text typed so far.
+    fn update_list_query(&mut self) {
+        if self.mode == AppMode::ListSearch {
+            self.list_query = self.input_buffer.trim().to_string();
+            self.ensure_visible_selection();
+        }
+    }
+
+    /// Leave the list search prompt, keeping the text applied (Enter) or
+    /// clearing it (Esc).
+    pub fn end_list_search(&mut self, keep: bool) {
+        self.mode = AppMode::Viewing;
+        if !keep {
+            self.list_query.clear();
+        }
+        self.input_buffer.clear();
+        self.cursor_position = 0;
+        self.input_match = None;
+        self.ensure_visible_selection();
+    }
+
+    /// Whether anything on screen animates, so it needs redrawing between
+    /// update ticks.
+    pub fn is_animating(&self) -> bool {
+        self.loading
+    }
+
+    /// Set the clock from the settings overlay. It goes to `config.toml`
+    /// rather than the UI state, so a clock chosen with `t` is forgotten.
+    pub fn set_time_format(&mut self, time_format: TimeFormat) {
+        self.config.time_format = time_format;
+        if self.chosen_time_format.take().is_some() {
+            self.ui_changed();
+        }
+    }
+
+    /// Set the layout from the settings overlay, forgetting one chosen with
+    /// Ctrl+T as [`App::set_time_format`] does the clock.
+    pub fn set_layout(&mut self, layout: ScreenLayout) {
+        self.config.layout = layout;
+        if self.chosen_layout.take().is_some() {
+            self.ui_changed();
+        }
+    }
+
+    /// Move the setting under the settings cursor to its next value, or its
+    /// previous one going `backward`, and apply it.
+    fn change_setting(&mut self, backward: bool) {
+        let Some(setting) = settings::SETTINGS.get(self.settings_cursor) else {
+            return;
+        };
+        let choice = setting.step(self, backward);
+        if !setting.apply(self, choice.value) {
+            return;
+        }
+        match self
+            .settings_changed
+            .iter_mut()
+            .find(|(key, _)| *key == setting.key)
+        {
+            Some(changed) => changed.1 = choice.value.to_string(),
+            None => self
+                .settings_changed
+                .push((setting.key, choice.value.to_string())),
+        }
+        self.status_message = Some(format!("{}: {}", setting.label, choice.label));
+    }
+
+    /// Leave the settings overlay, writing what was changed in it to
+    /// `config.toml`.
+    fn close_settings(&mut self) {
+        self.mode = AppMode::Viewing;
+        if self.settings_changed.is_empty() {
+            return;
+        }
+        if self.persist_session {
+            Config::save_values(&self.settings_changed);
+            self.status_message = Some("Settings saved to config.toml".to_string());
+        } else {
+            self.status_message = Some("Settings kept for this session only".to_string());
+        }
+    }
+
+    /// Switch between 24-hour and 12-hour times, remembered across restarts.
+    pub fn toggle_time_format(&mut self) {
+        self.config.time_format = self.config.time_format.toggle();
+        self.chosen_time_format = Some(self.config.time_format);
+        self.status_message = Some(format!("Showing {} times", self.config.time_format));
+        self.ui_changed();
+    }
+
+    /// Switch between the full layout and the ticker, remembered across
+    /// restarts.
+    pub fn toggle_layout(&mut self) {
+        self.config.layout = self.config.layout.toggle();
+        self.chosen_layout = Some(self.config.layout);
+        self.status_message = Some(match self.config.layout {
+            ScreenLayout::Full => "Full layout".to_string(),
+            ScreenLayout::Ticker => "Ticker layout, Ctrl+T for the full one".to_string(),
+        });
+        self.ui_changed();
+    }
+
+    /// If the selected flight is hidden by the filter or a collapsed
+    /// Arrived section, select the nearest shown flight after it, or the
+    /// last one.
+    fn ensure_visible_selection(&mut self) {
+        if self.selected_view_index().is_some() {
+            return;
+        }
+        let visible = self.visible_indices();
+        let from = self.selected_index.unwrap_or(0);
+        self.selected_index = visible
+            .iter()
+            .find(|&&i| i >= from)
+            .or(visible.last())
+            .copied();
+    }
+
+    pub fn remove_selected_flight(&mut self) {
+        let Some(index) = self
+            .selected_index
+            .filter(|&i| i < self.tracked_flights.len())
+        else {
+            return;
+        };
+        let flight_number = self.tracked_flights[index].flight_number.clone();
+        self.remove_undoably(&[index]);
+        self.status_message = Some(format!("Removed {}, u to undo", flight_number));
+    }
+
+    /// Indices of the flights a bulk removal would take.
+    fn removal_indices(&self, kind: BulkRemoval) -> Vec<usize> {
+        self.tracked_flights
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| kind.takes(f))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// How many flights the pending bulk removal would take.
+    pub fn pending_removal_count(&self) -> usize {
+        self.pending_removal
+            .map_or(0, |kind| self.removal_indices(kind).len())
+    }
+
+    /// Ask for confirmation before removing several flights at once.
+    pub fn request_removal(&mut self, kind: BulkRemoval) {
+        if self.removal_indices(kind).is_empty() {
+            self.status_message = Some(match kind {
+                BulkRemoval::Finished => "No landed, cancelled or unfound flights".to_string(),
+                BulkRemoval::All => "No flights to remove".to_string(),
+            });
+            return;
+        }
+        self.pending_removal = Some(kind);
+    }
+
+    /// Answer the pending bulk removal, removing the flights if `confirmed`.
+    pub fn answer_removal(&mut self, confirmed: bool) {
+        let Some(kind) = self.pending_removal.take() else {
+            return;
+        };
+        let indices = self.removal_indices(kind);
+        if !confirmed || indices.is_empty() {
+            self.status_message = Some("Removed nothing".to_string());
+            return;
+        }
+        self.remove_undoably(&indices);
+        info!(count = indices.len(), ?kind, "Removed flights");
+        self.status_message = Some(format!("Removed {} flight(s), u to undo", indices.len()));
+    }
+
+    /// Remove flights on the user's behalf, keeping them for `u`. A removed
+    /// selection moves to the flight that followed it, else the last one.
+    fn remove_undoably(&mut self, indices: &[usize]) {
+        let selected = self.selected_index;
+        let removal = Removal {
+            flights: indices
+                .iter()
+                .filter_map(|&i| Some((i, self.tracked_flights.get(i)?.clone())))
+                .collect(),
+            selected: self.selected_flight().map(|f| f.flight_number.clone()),
+        };
+        if self.undo_stack.len() == MAX_UNDO {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(removal);
+
+        self.remove_flights(indices);
+        if let Some(index) = selected.filter(|i| indices.contains(i)) {
+            let shifted = index - indices.iter().filter(|&&i| i < index).count();
+            self.selected_index = match self.tracked_flights.len() {
+                0 => None,
+                len => Some(shifted.min(len - 1)),
+            };
+        }
+        self.ensure_visible_selection();
+    }
+
+    /// Put back the flights of the latest removal where they were, along
+    /// with the selection. Flights tracked again since then stay as they are.
+    pub fn undo_removal(&mut self) {
+        let Some(removal) = self.undo_stack.pop() else {
+            self.status_message = Some("Nothing to undo".to_string());
+            return;
+        };
+        let current = self.selected_flight().map(|f| f.flight_number.clone());
+        let mut restored = 0;
+        for (index, flight) in removal.flights {
+            if self
+                .tracked_flights
+                .iter()
+                .any(|f| f.flight_number == flight.flight_number)
+            {
+                continue;
+            }
+            let index = index.min(self.tracked_flights.len());
+            self.tracked_flights.insert(index, flight);
+            restored += 1;
+        }
+        if let Some(selected) = removal.selected.or(current) {
+            if let Some(i) = self
+                .tracked_flights
+                .iter()
+                .position(|f| f.flight_number == selected)
+            {
+                self.selected_index = Some(i);
+            }
+        }
+        self.ensure_visible_selection();
+        self.fit_map();
+        self.save_session();
+        self.status_message = Some(format!("Restored {} flight(s)", restored));
+    }
+
+    /// Stop tracking the flights at the given indices, keeping the selection
+    /// on the same flight if it stays.
+    fn remove_flights(&mut self, indices: &[usize]) {
+        let selected = self.selected_flight().map(|f| f.flight_number.clone());
+        let mut index = 0;
+        self.tracked_flights.retain(|_| {
+            index += 1;
+            !indices.contains(&(index - 1))
         });
+        let tracked: HashSet<&str> = self
+            .tracked_flights
+            .iter()
+            .map(|f| f.flight_number.as_str())
+            .collect();
+        self.last_polled.retain(|n, _| tracked.contains(n.as_str()));
+        self.rematched.retain(|n, _| tracked.contains(n.as_str()));
+        self.last_schedule_refresh
+            .retain(|n, _| tracked.contains(n.as_str()));
+        if self
+            .following
+            .as_deref()
+            .is_some_and(|n| !tracked.contains(n))
+        {
+            self.following = None;
+        }
+        if let Some(selected) = selected {
+            if let Some(i) = self
+                .tracked_flights
+                .iter()
+                .position(|f| f.flight_number == selected)
+            {
+                self.selected_index = Some(i);
+            }
+        }
+        self.fit_map();
+        self.save_session();
+    }
+
+    /// Move a flight whose journey is over to the Arrived section, where it
+    /// is no longer refreshed.
+    fn mark_arrival(&mut self, index: usize, now: DateTime<Utc>) {
+        let dwell = chrono::Duration::minutes(self.config.arrived_after_mins as i64);
+        let Some(flight) = self.tracked_flights.get_mut(index) else {
+            return;
+        };
+        if flight.arrived_at.is_some() || !flight.has_arrived(now, dwell) {
+            return;
+        }
+        info!(flight = %flight.flight_number, "Flight arrived");
+        flight.arrived_at = Some(now);
+        if !self.show_arrived {
+            self.status_message = Some(format!(
+                "{} moved to Arrived (A to expand)",
+                flight.flight_number
+            ));
+        }
+        self.ensure_visible_selection();
+    }
+
+    /// Mark a flight Landed once its positions say so, which stops its
+    /// refreshes. Returns whether the status changed.
+    fn detect_landing(&mut self, index: usize, now: DateTime<Utc>) -> bool {
+        let taxi = chrono::Duration::minutes(self.config.landed_after_taxi_mins as i64);
+        let missed_polls = self.config.landed_after_missed_polls;
+        let Some(flight) = self.tracked_flights.get_mut(index) else {
+            return false;
+        };
+        let Some(reason) = flight.inferred_landing(now, taxi, missed_polls) else {
+            return false;
+        };
+        info!(flight = %flight.flight_number, reason, "Landing inferred from position data");
+        flight.position_status = Some(FlightStatus::Landed);
+        flight.resolve_status(now);
+        self.ensure_visible_selection();
+        true
+    }
+
+    /// Toggle whether the selected flight rolls over to the next day's
+    /// instance once it is over.
+    pub fn toggle_recurring(&mut self) {
+        let Some(flight) = self
+            .selected_index
+            .and_then(|i| self.tracked_flights.get_mut(i))
+        else {
+            return;
+        };
+        flight.recurring = !flight.recurring;
+        self.status_message = Some(if flight.recurring {
+            format!(
+                "{} will roll over to the next day once it lands",
+                flight.flight_number
+            )
+        } else {
+            format!("{} no longer rolls over", flight.flight_number)
+        });
+        self.save_session();
+    }
+
+    /// Move the selected flight's mute on to the next step: 15 minutes, an
+    /// hour, until it lands, then off.
+    pub fn cycle_mute(&mut self, now: DateTime<Utc>) {
+        let Some(flight) = self
+            .selected_index
+            .and_then(|i| self.tracked_flights.get_mut(i))
+        else {
+            return;
+        };
+        flight.muted_until = Mute::cycle(flight.muted_until, now);
+        self.status_message = Some(match flight.muted_until {
+            Some(mute) => format!("{} {}", flight.flight_number, mute),
+            None => format!("{} notifications back on", flight.flight_number),
+        });
+        self.save_session();
+    }
+
+    /// Unmute flights whose mute has run out.
+    pub fn expire_mutes(&mut self, now: DateTime<Utc>) {
+        let mut expired = false;
+        for flight in &mut self.tracked_flights {
+            if flight.muted_until.is_some() && !flight.is_muted(now) {
+                debug!(flight = %flight.flight_number, "Mute ran out");
+                flight.muted_until = None;
+                expired = true;
+            }
+        }
+        if expired {
+            self.save_session();
+        }
+    }
+
+    /// Move recurring flights whose day is over on to their next instance,
+    /// with a schedule lookup for the new date on the next tick.
+    pub fn roll_over_recurring(&mut self, now: DateTime<Local>) {
+        let today = now.date_naive();
+        let mut rolled = false;
+        for flight in &mut self.tracked_flights {
+            let Some(date) = flight.rollover_date(today) else {
+                continue;
+            };
+            info!(flight = %flight.flight_number, %date, "Rolling recurring flight over");
+            flight.roll_over(date);
+            self.arrival_notices_sent.remove(&flight.flight_number);
+            self.last_polled.remove(&flight.flight_number);
+            self.last_schedule_refresh.remove(&flight.flight_number);
+            self.rematched.remove(&flight.flight_number);
+            self.status_message = Some(format!(
+                "{} rolled over to {}",
+                flight.flight_number,
+                date.format("%a %Y-%m-%d")
+            ));
+            rolled = true;
+        }
+        if rolled {
+            self.ensure_visible_selection();
+            self.save_session();
+        }
+    }
+
+    /// When a flight's arriving-soon notice goes off, following its current
+    /// ETA, and whether it already has. `None` without an ETA or with the
+    /// notice turned off.
+    pub fn arrival_notice(&self, flight: &Flight) -> Option<(DateTime<Utc>, bool)> {
+        let lead = self.config.arrival_notice_mins;
+        if lead == 0 {
+            return None;
+        }
+        let due = flight.eta()? - chrono::Duration::minutes(lead as i64);
+        Some((
+            due,
+            self.arrival_notices_sent.contains(&flight.flight_number),
+        ))
+    }
+
+    /// Fire the arriving-soon notice of each flight whose notice time has
+    /// come, at most once per flight. The time follows the ETA on every
+    /// call, so a delay pushes it back until it fires; a flight whose ETA
+    /// has already passed gets none.
+    pub fn check_arrival_notices(&mut self, now: DateTime<Utc>) {
+        let tracked: HashSet<&str> = self
+            .tracked_flights
+            .iter()
+            .map(|f| f.flight_number.as_str())
+            .collect();
+        self.arrival_notices_sent
+            .retain(|n| tracked.contains(n.as_str()));
+
+        let mut fired = vec![];
+        for flight in &self.tracked_flights {
+            let (Some((due, false)), Some(eta)) = (self.arrival_notice(flight), flight.eta())
+            else {
+                continue;
+            };
+            if now < due || now >= eta {
+                continue;
+            }
+            let message = format!(
+                "{} arriving in {}",
+                flight.flight_number,
+                format_duration(eta - now)
+            );
+            fired.push((flight.flight_number.clone(), message, flight.is_muted(now)));
+        }
+        for (flight_number, message, muted) in fired {
+            info!(%message, muted, "Arrival notice fired");
+            self.arrival_notices_sent.insert(flight_number);
+            self.alert_message = Some(message.clone());
+            self.announce(format!("Alert: {}", message));
+            if !muted {
+                self.pending_alerts.push(message);
+            }
+        }
+    }
+
+    /// Ask for confirmation before clearing a cache. Only offered while the
+    /// diagnostics overlay is open.
+    pub fn request_cache_clear(&mut self, kind: CacheKind) {
+        if self.diagnostics.is_some() {
+            self.pending_cache_clear = Some(kind);
+        }
+    }
+
+    /// Answer the pending confirmation, returning the cache to clear if it
+    /// was `confirmed`.
+    pub fn answer_cache_clear(&mut self, confirmed: bool) -> Option<CacheKind> {
+        let kind = self.pending_cache_clear.take()?;
+        self.status_message = Some(if confirmed {
+            format!("Cleared the {} cache", kind.label())
+        } else {
+            format!("Kept the {} cache", kind.label())
+        });
+        confirmed.then_some(kind)
+    }
+
+    /// Land flights that have taxied long enough, move newly arrived flights
+    /// to the Arrived section and remove those kept past the retention
+    /// period. Run on every tick, since a flight parked at its destination
+    /// may not be refreshed again.
+    pub fn sweep_arrivals(&mut self) {
+        let now = Utc::now();
+        for index in 0..self.tracked_flights.len() {
+            if self.detect_landing(index, now) {
+                self.check_alerts(index);
+            }
+            self.mark_arrival(index, now);
+        }
+        self.purge_arrived_at(now);
+    }
+
+    /// Remove arrived flights older than the retention period.
+    fn purge_arrived_at(&mut self, now: DateTime<Utc>) {
+        if self.config.arrived_retention_hours == 0 {
+            return;
+        }
+        let retention = chrono::Duration::hours(self.config.arrived_retention_hours as i64);
+        let expired: Vec<usize> = self
+            .tracked_flights
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| !f.recurring && f.arrived_at.is_some_and(|at| now - at >= retention))
+            .map(|(i, _)| i)
+            .collect();
+        if expired.is_empty() {
+            return;
+        }
+        info!(count = expired.len(), "Purging arrived flights");
+        let removed_selection = self.selected_index.is_some_and(|i| expired.contains(&i));
+        self.remove_flights(&expired);
+        if removed_selection || self.tracked_flights.is_empty() {
+            self.selected_index = None;
+        }
+        self.ensure_visible_selection();
+    }
+
+    /// Switch to the alert prompt for the selected flight.
+    pub fn begin_alert_input(&mut self) {
+        if self.selected_flight().is_some() {
+            self.mode = AppMode::AlertInput;
+            self.input_buffer.clear();
+            self.cursor_position = 0;
+        }
+    }
+
+    /// Apply the alert prompt to the selected flight.
+    ///
+    /// An empty prompt clears the flight's rules.
+    pub fn submit_alert_input(&mut self) {
+        let input = std::mem::take(&mut self.input_buffer);
+        self.cursor_position = 0;
+
+        let Some(index) = self
+            .selected_index
+            .filter(|&i| i < self.tracked_flights.len())
+        else {
+            self.mode = AppMode::Viewing;
+            return;
+        };
+
+        if input.trim().is_empty() {
+            let flight = &mut self.tracked_flights[index];
+            flight.alerts.clear();
+            self.status_message = Some(format!("Cleared alerts for {}", flight.flight_number));
+        } else {
+            let Some(condition) = AlertCondition::parse(&input) else {
+                self.input_buffer = input;
+                self.cursor_position = self.input_buffer.len();
+                self.last_error = Some("Alert must look like 150km, 3000ft or landed".to_string());
+                return;
+            };
+            let flight = &mut self.tracked_flights[index];
+            self.status_message =
+                Some(format!("Alert set: {} {}", flight.flight_number, condition));
+            flight.alerts.push(AlertRule::new(condition));
+            self.check_alerts(index);
+        }
+
+        self.mode = AppMode::Viewing;
+        self.save_session();
+    }
+
+    pub fn selected_flight(&self) -> Option<&Flight> {
+        self.selected_index
+            .and_then(|i| self.tracked_flights.get(i))
+    }
+
+    /// Evaluate alert rules on a flight, queueing any that fire for the
+    /// bell and desktop unless the flight is muted.
+    fn check_alerts(&mut self, index: usize) {
+        let muted = self.tracked_flights[index].is_muted(Utc::now());
+        let fired = alert::evaluate(&mut self.tracked_flights[index]);
+        for message in &fired {
+            info!(%message, muted, "Alert fired");
+        }
+        for message in &fired {
+            self.announce(format!("Alert: {}", message));
+        }
+        if let Some(last) = fired.last() {
+            self.alert_message = Some(last.clone());
+            if !muted {
+                self.pending_alerts.extend(fired);
+            }
+            self.save_session();
+        }
+    }
+
+    pub fn add_flight(
+        &mut self,
+        query: impl Into<FlightQuery>,
+        state: Option<StateVector>,
+        schedule: Option<FlightData>,
+    ) {
+        let FlightQuery {
+            flight_number,
+            date,
+        } = query.into();
+
+        let from_watchlist = self.watchlist_queued.remove(&flight_number);
+        if from_watchlist && !self.watchlist_listed.contains(&flight_number) {
+            info!(flight = %flight_number, "Taken off the watchlist while being searched");
+            return;
+        }
+
+        let existing = self
+            .tracked_flights
+            .iter()
+            .position(|f| f.flight_number == flight_number);
+        // A retried search replaces the entry that found nothing
+        let retried = match existing {
+            Some(index) if self.tracked_flights[index].status == FlightStatus::NotFound => {
+                let retried = self.tracked_flights[index].clone();
+                self.remove_flights(&[index]);
+                Some(retried)
+            }
+            Some(_) => {
+                self.status_message = Some(format!("Flight {} is already tracked", flight_number));
+                return;
+            }
+            None => None,
+        };
+
+        let restored = self.restored_flights.remove(&flight_number);
+        let mut flight = Flight {
+            flight_number: flight_number.clone(),
+            status: FlightStatus::NotFound,
+            flight_date: date,
+            last_updated: Some(Utc::now()),
+            alerts: restored
+                .as_ref()
+                .map(|r| r.alerts.clone())
+                .unwrap_or_default(),
+            recurring: restored.as_ref().is_some_and(|r| r.recurring),
+            from_watchlist: from_watchlist || restored.as_ref().is_some_and(|r| r.watchlist),
+            muted_until: restored.as_ref().and_then(|r| r.muted_until),
+            delay_history: restored.map(|r| r.delay_history).unwrap_or_default(),
+            ..Default::default()
+        };
+        if let Some(retried) = retried {
+            flight.alerts = retried.alerts;
+            flight.recurring = retried.recurring;
+            flight.from_watchlist |= retried.from_watchlist;
+            flight.muted_until = retried.muted_until;
+        }
+
+        // Apply schedule data first (from AviationStack)
+        if let Some(sched) = schedule {
+            apply_schedule_data(&mut flight, sched);
+            flight.record_delay(Utc::now());
+        }
+
+        // Apply live position data (from OpenSky) - this may override status
+        if let Some(sv) = state {
+            let rejected = apply_position_data(&mut flight, sv, &self.config.position_limits);
+            count_rejected(&mut self.rejected_readings, &flight, rejected);
+        }
+
+        // Add to history and save
+        self.history
+            .add(flight_number.clone(), flight.route_label());
+        self.history.save();
+        if flight.status == FlightStatus::NotFound {
+            self.status_message = Some(match self.history.near_match(&flight_number) {
+                Some(near) => format!("{} not found, did you mean {}?", flight_number, near),
+                None => format!("{} not found", flight_number),
+            });
+        }
+
+        debug!(
+            flight = %flight.flight_number,
+            status = %flight.status,
+            icao24 = %flight.icao24,
+            "Flight added"
+        );
+        self.announce(format!(
+            "{} added, {}",
+            flight.flight_number,
+            status_phrase(&flight)
+        ));
+        // The search that produced this flight counts as its first poll
+        let now = Instant::now();
+        self.last_polled.insert(flight.flight_number.clone(), now);
+        self.last_schedule_refresh
+            .insert(flight.flight_number.clone(), now);
+        self.tracked_flights.push(flight);
+        let previous_selection = self.selected_index;
+        self.selected_index = Some(self.tracked_flights.len() - 1);
+        if self.selected_view_index().is_none() {
+            // Filtered out of the list; don't select a flight that isn't shown
+            self.selected_index = previous_selection;
+            self.ensure_visible_selection();
+        }
+        self.fit_map();
+        self.check_alerts(self.tracked_flights.len() - 1);
+        self.mark_arrival(self.tracked_flights.len() - 1, Utc::now());
+        self.queue_track(self.tracked_flights.len() - 1);
+        self.save_session();
+    }
+
+    /// Report that several aircraft matched a new flight's callsign, so
+    /// none was tracked.
+    pub fn ambiguous_position(&mut self, flight_number: &str, candidates: &[StateVector]) {
+        let callsigns: Vec<&str> = candidates
+            .iter()
+            .filter_map(|s| s.callsign.as_deref())
+            .map(str::trim)
+            .collect();
+        self.status_message = Some(format!(
+            "{} aircraft match {} ({}); position not tracked",
+            candidates.len(),
+            flight_number,
+            callsigns.join(", ")
+        ));
+    }
+
+    pub fn update_flight(&mut self, flight_number: &str, state: Option<StateVector>) {
+        if let Some(index) = self
+            .tracked_flights
+            .iter()
+            .position(|f| f.flight_number == flight_number)
+        {
+            let flight = &mut self.tracked_flights[index];
+            let previous = flight.status.clone();
+            match state {
+                Some(sv) => {
+                    let rejected = apply_position_data(flight, sv, &self.config.position_limits);
+                    count_rejected(&mut self.rejected_readings, flight, rejected);
+                }
+                None => {
+                    flight.missed_polls += 1;
+                    flight.resolve_status(Utc::now());
+                }
+            }
+            flight.last_updated = Some(Utc::now());
+            let off_map = match (flight.latitude, flight.longitude) {
+                (Some(lat), Some(lon)) => self.map_viewport.is_none_or(|v| !v.contains(lat, lon)),
+                _ => false,
+            };
+            if flight.status != previous {
+                debug!(
+                    flight = %flight.flight_number,
+                    from = %previous,
+                    to = %flight.status,
+                    "Status changed"
+                );
+                self.ensure_visible_selection();
+            }
+            self.detect_landing(index, Utc::now());
+            self.check_eta_divergence(index);
+            self.check_swap(index, Instant::now());
+            self.status_changed(index, &previous);
+            self.check_alerts(index);
+            self.mark_arrival(index, Utc::now());
+            self.queue_track(index);
+            if off_map || self.following.as_deref() == Some(flight_number) {
+                self.fit_map();
+            }
+        }
+    }
+
+    /// Compare the track-based ETA with the airline's estimate after a
+    /// position update, saying so once when they drift apart.
+    fn check_eta_divergence(&mut self, index: usize) {
+        let flight = &mut self.tracked_flights[index];
+        let comparison = flight.eta_comparison();
+        let diverged = comparison.is_some_and(|c| c.diverges());
+        if diverged == flight.eta_diverged {
+            return;
+        }
+        flight.eta_diverged = diverged;
+        let Some(comparison) = comparison.filter(|_| diverged) else {
+            return;
+        };
+        let minutes = comparison.gap().num_minutes();
+        info!(
+            flight = %flight.flight_number,
+            minutes,
+            "Track-based ETA diverges from the airline's"
+        );
+        let message = format!(
+            "{}: {}",
+            flight.flight_number,
+            comparison.summary(self.config.time_format)
+        );
+        self.status_message = Some(message.clone());
+        self.announce(message);
+    }
+
+    /// Queue a callsign search for a flight whose aircraft looks swapped,
+    /// at most once per [`REMATCH_INTERVAL`].
+    fn check_swap(&mut self, index: usize, now: Instant) {
+        let flight = &self.tracked_flights[index];
+        let Some(reason) = flight.suspected_swap() else {
+            return;
+        };
+        if self
+            .rematched
+            .get(&flight.flight_number)
+            .is_some_and(|at| now.duration_since(*at) < REMATCH_INTERVAL)
+        {
+            return;
+        }
+        info!(
+            flight = %flight.flight_number,
+            icao24 = %flight.icao24,
+            reason,
+            "Aircraft may have been swapped, searching by callsign"
+        );
+        self.rematched.insert(flight.flight_number.clone(), now);
+        self.pending_rematches.push(flight.flight_number.clone());
+    }
+
+    /// Take the queued swap searches for dispatch.
+    pub fn take_pending_rematches(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.pending_rematches)
+    }
+
+    /// Handle the callsign search for a flight whose aircraft looked
+    /// swapped. A different aircraft in the air under the flight's callsign
+    /// takes over; anything else leaves the flight as it is.
+    pub fn rematch_found(&mut self, flight_number: &str, state: Option<StateVector>) {
+        let Some(flight) = self
+            .tracked_flights
+            .iter()
+            .find(|f| f.flight_number == flight_number)
+        else {
+            return;
+        };
+        // The flight's number, as the aircraft polled so far may have been
+        // broadcasting its own next flight
+        let callsign = normalize_callsign(&flight.flight_number);
+        let Some(sv) = state.filter(|sv| {
+            !sv.on_ground
+                && !sv.icao24.eq_ignore_ascii_case(&flight.icao24)
+                && sv
+                    .callsign
+                    .as_deref()
+                    .is_some_and(|c| c.trim().eq_ignore_ascii_case(&callsign))
+        }) else {
+            debug!(flight = %flight_number, "No other aircraft flying it");
+            return;
+        };
+
+        let message = format!(
+            "{} is now flown by {} (was {})",
+            flight_number, sv.icao24, flight.icao24
+        );
+        info!(flight = %flight_number, from = %flight.icao24, to = %sv.icao24, "Aircraft swapped");
+        self.update_flight(flight_number, Some(sv));
+        self.status_message = Some(message.clone());
+        self.announce(message);
+        self.save_session();
+    }
+
+    /// Destinations of flights still to arrive whose weather is due, at
+    /// most once per [`WEATHER_REFRESH`] each. Weather for airports no
+    /// flight is heading to any more is dropped.
+    pub fn take_due_weather(&mut self, now: Instant) -> Vec<String> {
+        let destinations: HashSet<String> = self
+            .tracked_flights
+            .iter()
+            .filter(|f| f.arrived_at.is_none())
+            .filter_map(|f| f.destination.as_ref()?.icao_code())
+            .map(str::to_string)
+            .collect();
+        self.weather.retain(|icao, _| destinations.contains(icao));
+        self.weather_requested
+            .retain(|icao, _| destinations.contains(icao));
+
+        let mut due: Vec<String> = destinations
+            .into_iter()
+            .filter(|icao| {
+                self.weather_requested
+                    .get(icao)
+                    .is_none_or(|at| now.duration_since(*at) >= WEATHER_REFRESH)
+            })
+            .collect();
+        due.sort();
+        for icao in &due {
+            self.weather_requested.insert(icao.clone(), now);
+        }
+        due
+    }
+
+    /// Store a destination's weather. A TAF that can't be read is left
+    /// out, keeping the METAR.
+    pub fn weather_received(&mut self, icao: &str, station: StationWeather) {
+        let taf = station
+            .taf
+            .as_deref()
+            .and_then(|raw| match Taf::parse(raw, Utc::now()) {
+                Ok(taf) => Some(taf),
+                Err(e) => {
+                    debug!(icao, error = %e, "Unreadable TAF");
+                    None
+                }
+            });
+        self.weather.insert(
+            icao.to_string(),
+            Weather {
+                metar: station.metar,
+                taf,
+            },
+        );
+    }
+
+    /// Ask for the provider's track of a flight's aircraft, once per
+    /// aircraft, so the trail covers the flight before tracking began.
+    fn queue_track(&mut self, index: usize) {
+        let flight = &self.tracked_flights[index];
+        if flight.icao24.is_empty()
+            || flight.arrived_at.is_some()
+            || !self.tracks_requested.insert(flight.icao24.clone())
+        {
+            return;
+        }
+        self.pending_tracks
+            .push((flight.flight_number.clone(), flight.icao24.clone()));
+    }
+
+    /// Take the queued track lookups for dispatch.
+    pub fn take_pending_tracks(&mut self) -> Vec<(String, String)> {
+        std::mem::take(&mut self.pending_tracks)
+    }
+
+    /// Fill in a flight's track with the provider's waypoints, unless the
+    /// flight has since moved to another aircraft.
+    pub fn apply_track(&mut self, flight_number: &str, track: FlightTrack) {
+        let Some(flight) = self.tracked_flights.iter_mut().find(|f| {
+            f.flight_number == flight_number && f.icao24.eq_ignore_ascii_case(&track.icao24)
+        }) else {
+            return;
+        };
+        let before = flight.track.len();
+        flight.merge_track(track.path.iter().filter_map(|w| {
+            Some(TrackPoint {
+                at: DateTime::from_timestamp(w.time, 0)?,
+                latitude: w.latitude?,
+                longitude: w.longitude?,
+                altitude_ft: w.baro_altitude.map(|a| a * METERS_TO_FEET),
+            })
+        }));
+        debug!(
+            flight = %flight_number,
+            added = flight.track.len() - before,
+            "Track history merged"
+        );
+    }
+
+    /// Note the generation time (Unix seconds) of a position snapshot that
+    /// just arrived for a flight, and was applied to it. Snapshots older
+    /// than the newest one seen don't count for the feed as a whole.
+    pub fn record_feed_time(&mut self, flight_number: &str, time: i64) {
+        // Only a fix that came in this snapshot is dated by it
+        if let Some(flight) = self
+            .tracked_flights
+            .iter_mut()
+            .find(|f| f.flight_number == flight_number && f.snapshot_time.is_none())
+            .filter(|f| f.position_source == Some(PositionSource::OpenSky))
+        {
+            flight.snapshot_time = DateTime::from_timestamp(time, 0);
+        }
+        self.record_feed_time_at(time, Utc::now());
+    }
+
+    fn record_feed_time_at(&mut self, time: i64, now: DateTime<Utc>) {
+        let Some(snapshot) = DateTime::from_timestamp(time, 0) else {
+            return;
+        };
+        if self.feed_time.is_some_and(|f| f.snapshot > snapshot) {
+            return;
+        }
+        self.feed_time = Some(FeedTime {
+            snapshot,
+            lag_secs: (now - snapshot).num_seconds().max(0),
+        });
+    }
+
+    /// The newest snapshot, if it lagged far enough behind to warn about.
+    pub fn lagging_feed(&self) -> Option<FeedTime> {
+        self.feed_time
+            .filter(|f| f.lag_secs > FEED_LAG_WARNING_SECS)
+    }
+
+    /// Switch the right-hand panel to `panel`, remembering it for the
+    /// pinned flight if it is about one.
+    pub fn show_panel(&mut self, panel: RightPanel) {
+        let was_map = self.right_panel == RightPanel::Map;
+        self.right_panel = panel;
+        self.panel_scroll = 0;
+        if panel == RightPanel::Map {
+            self.fit_map();
+        }
+        if let Some(flight_number) = self.pinned_flight().map(|f| f.flight_number.clone()) {
+            if panel.per_flight() {
+                self.panel_by_flight.insert(flight_number, panel);
+            }
+        }
+        // Only whether the map is open outlives the session
+        if was_map != (panel == RightPanel::Map) {
+            self.ui_changed();
+        }
+    }
+
+    /// Open `panel`, or go back from it to the pinned flight's last tab.
+    pub fn toggle_panel(&mut self, panel: RightPanel) {
+        if self.right_panel == panel {
+            self.show_panel(self.flight_panel());
+        } else {
+            self.show_panel(panel);
+        }
+    }
+
+    /// The tab last shown for the pinned flight, other than the map.
+    fn flight_panel(&self) -> RightPanel {
+        self.pinned_flight()
+            .and_then(|f| self.panel_by_flight.get(&f.flight_number))
+            .copied()
+            .filter(|&panel| panel != self.right_panel)
+            .unwrap_or_default()
+    }
+
+    /// Go back to the tab last shown for the newly pinned flight. The map
+    /// stays open, as it shows every flight.
+    fn restore_flight_panel(&mut self) {
+        self.panel_scroll = 0;
+        if self.right_panel.per_flight() {
+            self.right_panel = self
+                .pinned_flight()
+                .and_then(|f| self.panel_by_flight.get(&f.flight_number))
+                .copied()
+                .unwrap_or_default();
+        }
+    }
+
+    /// Move the active tab by `lines`, stopping at either end, if it
+    /// scrolls.
+    pub fn scroll_panel(&mut self, lines: i16) {
+        let Some(flight) = self.pinned_flight().filter(|_| self.right_panel.scrolls()) else {
+            return;
+        };
+        let last = match self.right_panel {
+            RightPanel::Raw => {
+                let count = |raw: &Option<String>| raw.as_deref().map_or(1, |r| r.lines().count());
+                count(&flight.raw_state) + count(&flight.raw_schedule)
+            }
+            _ => flight.log().len(),
+        };
+        let last = u16::try_from(last).unwrap_or(u16::MAX);
+        self.panel_scroll = self.panel_scroll.saturating_add_signed(lines).min(last);
+    }
+
+    /// Halve or double the area the map shows, about its centre. Zooming in
+    /// stops at the narrowest span the map is fitted to.
+    pub fn zoom_map(&mut self, zoom_in: bool) {
+        if self.right_panel != RightPanel::Map {
+            return;
+        }
+        let view = self.map_viewport.unwrap_or(BoundingBox::WORLD);
+        let span = (view.max_lat - view.min_lat).min(view.max_lon - view.min_lon);
+        if zoom_in && span / 2.0 < MAP_MIN_SPAN_DEG {
+            return;
+        }
+        self.map_viewport = Some(view.scaled(if zoom_in { 0.5 } else { 2.0 }));
+    }
+
+    /// Index of the followed flight, while following one.
+    pub fn followed_index(&self) -> Option<usize> {
+        let following = self.following.as_deref()?;
+        self.tracked_flights
+            .iter()
+            .position(|f| f.flight_number == following)
+    }
+
+    /// The flight the details pane, charts and map highlight are about: the
+    /// followed one, else the selection.
+    pub fn pinned_index(&self) -> Option<usize> {
+        self.followed_index().or(self.selected_index)
+    }
+
+    pub fn pinned_flight(&self) -> Option<&Flight> {
+        self.pinned_index()
+            .and_then(|i| self.tracked_flights.get(i))
+    }
+
+    /// Lock the view onto the selected flight, or let go of the followed
+    /// one. Letting go leaves the details on wherever the cursor moved.
+    pub fn toggle_follow(&mut self) {
+        if let Some(flight_number) = self.following.take() {
+            self.status_message = Some(format!("Stopped following {}", flight_number));
+        } else if let Some(flight) = self.selected_flight() {
+            self.following = Some(flight.flight_number.clone());
+        } else {
+            self.status_message = Some("Select a flight to follow".to_string());
+            return;
+        }
+        self.restore_flight_panel();
+        self.fit_map();
+    }
+
+    /// Toggle the altitude profile chart.
+    pub fn toggle_profile(&mut self) {
+        self.show_profile = !self.show_profile;
+        self.ui_changed();
+    }
+
+    /// Fit the map viewport around every tracked position, or center it on
+    /// the followed flight. Changing the selection deliberately leaves the
+    /// viewport alone.
+    pub fn fit_map(&mut self) {
+        let followed = self.followed_index().map(|i| &self.tracked_flights[i]);
+        if let Some((lat, lon)) = followed.and_then(|f| Some((f.latitude?, f.longitude?))) {
+            self.map_viewport = Some(BoundingBox::around(lat, lon, MAP_MIN_SPAN_DEG / 2.0));
+            return;
+        }
+        let points: Vec<(f64, f64)> = self
+            .tracked_flights
+            .iter()
+            .filter_map(|f| Some((f.latitude?, f.longitude?)))
+            .collect();
+        self.map_viewport = BoundingBox::fit(&points, MAP_MIN_SPAN_DEG);
+    }
+
+    /// Stretch an interval by the background multiplier while unfocused, or
+    /// `None` when refreshing is paused in the background.
+    fn background_interval(&self, secs: u64) -> Option<u64> {
+        if self.focused {
+            Some(secs)
+        } else if self.background_multiplier == 0 {
+            None
+        } else {
+            Some(secs * self.background_multiplier)
+        }
+    }
+
+    /// Refresh interval for one flight: its phase's interval, no shorter than
+    /// the configured minimum and stretched in the background. A followed
+    /// flight refreshes at the minimum. `None` when the flight needs no more
+    /// refreshing or refreshing is paused.
+    pub fn flight_refresh_interval(&self, flight: &Flight) -> Option<u64> {
+        let phase_secs = flight
+            .phase()
+            .refresh_interval_secs(self.update_interval_secs)?;
+        let secs = if self.following.as_deref() == Some(flight.flight_number.as_str()) {
+            self.config.min_refresh_secs
+        } else {
+            phase_secs.max(self.config.min_refresh_secs)
+        };
+        let secs = match self.budget {
+            Some(BudgetPlan::Exhausted) => return None,
+            Some(BudgetPlan::Interval(budget)) => secs.max(budget),
+            None => secs,
+        };
+        self.background_interval(secs)
+    }
+
+    /// Re-plan position refreshes so the credits left today last until
+    /// local midnight. `used_today` is `None` for unmetered providers.
+    pub fn update_budget(&mut self, used_today: Option<u32>, now: DateTime<Local>) {
+        let plan = used_today.map(|used| {
+            let auth = self
+                .provider_health
+                .iter()
+                .find_map(|p| p.auth)
+                .unwrap_or_default();
+            let remaining = self.config.daily_credits(auth).saturating_sub(used);
+            let midnight = (now.date_naive() + Days::new(1)).and_time(NaiveTime::MIN);
+            let hours_left = (midnight - now.naive_local()).num_seconds() as f64 / 3600.0;
+            let flights = self
+                .refreshable_flights()
+                .into_iter()
+                .filter(|&i| {
+                    self.tracked_flights[i]
+                        .phase()
+                        .refresh_interval_secs(self.update_interval_secs)
+                        .is_some()
+                })
+                .count();
+            budget::plan(remaining, hours_left, flights)
+        });
+        if plan == Some(BudgetPlan::Exhausted) && self.budget != plan {
+            info!("daily position credits used up; refreshing on request only");
+        }
+        self.budget = plan;
+    }
+
+    /// Take each provider's latest health, saying once when one starts
+    /// being skipped after repeated failures and once when it recovers, and
+    /// once when its credentials are rejected.
+    pub fn set_provider_health(&mut self, health: Vec<ProviderHealth>) {
+        for provider in &health {
+            let previous = self
+                .provider_health
+                .iter()
+                .find(|p| p.name == provider.name);
+            let mut notes = Vec::new();
+            // Configured credentials only leave basic auth when rejected
+            if self.capabilities.opensky_authenticated
+                && provider.auth == Some(AuthMode::Anonymous)
+                && previous.and_then(|p| p.auth) != Some(AuthMode::Anonymous)
+            {
+                warn!(
+                    provider = provider.name,
+                    "credentials rejected, now anonymous"
+                );
+                notes.push(
+                    "credentials rejected, using anonymous access — check \
+                     OPENSKY_USERNAME/PASSWORD and restart"
+                        .to_string(),
+                );
+            }
+            let was_degraded = previous.is_some_and(|p| p.degraded);
+            let change = match (was_degraded, provider.degraded) {
+                (false, true) => Some("degraded, trying again shortly"),
+                (true, false) => Some("recovered"),
+                _ => None,
+            };
+            if let Some(change) = change {
+                info!(provider = provider.name, change, "provider health changed");
+                notes.push(format!("{} provider {}", provider.role, change));
+            }
+            if !notes.is_empty() {
+                self.status_message = Some(format!("{}: {}", provider.name, notes.join("; ")));
+            }
+        }
+        self.provider_health = health;
+    }
+
+    /// Pass the flight's move away from `previous`, if its status did
+    /// change, to the webhook and the accessible mode's announcements.
+    fn status_changed(&mut self, index: usize, previous: &FlightStatus) {
+        let flight = &self.tracked_flights[index];
+        if flight.status == *previous {
+            return;
+        }
+        if self.config.webhook_url.is_some() && !flight.is_muted(Utc::now()) {
+            self.webhooks
+                .push(StatusChange::new(flight, previous, Utc::now()));
+        }
+        let announcement = format!("{} now {}", flight.flight_number, status_phrase(flight));
+        self.announce(announcement);
+    }
+
+    /// Add a line to the bottom of the accessible layout, dropping the
+    /// oldest past `MAX_ANNOUNCEMENTS`. Does nothing in the normal layout.
+    fn announce(&mut self, message: String) {
+        if !self.accessible {
+            return;
+        }
+        self.announcements.push(message);
+        let excess = self.announcements.len().saturating_sub(MAX_ANNOUNCEMENTS);
+        self.announcements.drain(..excess);
+    }
+
+    /// Status changes due to be posted to the webhook at `now`.
+    pub fn take_webhooks(&mut self, now: Instant) -> Vec<StatusChange> {
+        self.webhooks.due(now)
+    }
+
+    /// Note how a webhook post went. A failure shows in the status bar once,
+    /// until a post goes through again.
+    pub fn webhook_finished(&mut self, error: Option<String>) {
+        match error {
+            Some(error) if !self.webhook_failing => {
+                warn!(%error, "Webhook post failed");
+                self.webhook_failing = true;
+                self.status_message = Some(format!("Webhook failed: {}", error));
+            }
+            Some(error) => debug!(%error, "Webhook post failed again"),
+            None => self.webhook_failing = false,
+        }
+    }
+
+    /// Seconds until a flight is due for a position refresh, or `None` if it
+    /// is not scheduled to be refreshed.
+    fn seconds_until_flight_update(&self, flight: &Flight) -> Option<u64> {
+        if flight.icao24.is_empty() || flight.arrived_at.is_some() {
+            return None;
+        }
+        let interval = self.flight_refresh_interval(flight)?;
+        Some(match self.last_polled.get(&flight.flight_number) {
+            Some(last) => interval.saturating_sub(last.elapsed().as_secs()),
+            None => 0,
+        })
+    }
+
+    /// Indices of flights whose refresh interval has elapsed.
+    pub fn due_flights(&self) -> Vec<usize> {
+        self.tracked_flights
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| self.seconds_until_flight_update(f) == Some(0))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Indices of every flight with a known transponder that hasn't
+    /// arrived, for a forced refresh.
+    pub fn refreshable_flights(&self) -> Vec<usize> {
+        self.tracked_flights
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| !f.icao24.is_empty() && f.arrived_at.is_none())
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Record that position requests were sent for the given flights.
+    pub fn mark_polled(&mut self, indices: &[usize]) {
+        let now = Instant::now();
+        for &index in indices {
+            if let Some(flight) = self.tracked_flights.get(index) {
+                self.last_polled.insert(flight.flight_number.clone(), now);
+            }
+        }
+        self.last_api_call = Some(now);
+    }
+
+    pub fn should_update(&self) -> bool {
+        !self.loading && !self.due_flights().is_empty()
+    }
+
+    /// Seconds until the next flight is due for a refresh.
+    pub fn seconds_until_update(&self) -> Option<u64> {
+        self.tracked_flights
+            .iter()
+            .filter_map(|f| self.seconds_until_flight_update(f))
+            .min()
+    }
+
+    /// Tracked flights per headline state.
+    pub fn flight_counts(&self) -> FlightCounts {
+        self.tracked_flights.iter().fold(
+            FlightCounts {
+                tracked: self.tracked_flights.len(),
+                ..Default::default()
+            },
+            |mut counts, flight| {
+                let late = flight.current_delay().is_some_and(|minutes| minutes > 0);
+                match flight.status {
+                    _ if flight.arrived_at.is_some() => counts.landed += 1,
+                    FlightStatus::Landed => counts.landed += 1,
+                    FlightStatus::EnRoute => counts.en_route += 1,
+                    FlightStatus::Delayed => counts.delayed += 1,
+                    FlightStatus::Scheduled | FlightStatus::Unknown if late => counts.delayed += 1,
+                    _ => {}
+                }
+                counts
+            },
+        )
+    }
+
+    /// Interval between schedule refreshes, or `None` when paused in the
+    /// background.
+    pub fn schedule_refresh_interval(&self) -> Option<Duration> {
+        self.background_interval(self.config.schedule_refresh_mins * 60)
+            .map(Duration::from_secs)
+    }
+
+    /// Indices of flights whose schedule is due for a refresh, limited so
+    /// the AviationStack quota keeps a reserve for new searches.
+    pub fn schedule_due_flights(&self, requests_this_month: Option<u32>) -> Vec<usize> {
+        let Some(interval) = self.schedule_refresh_interval() else {
+            return Vec::new();
+        };
+        let budget = match requests_this_month {
+            Some(used) => self
+                .config
+                .aviationstack_monthly_limit
+                .saturating_sub(used)
+                .saturating_sub(SCHEDULE_REFRESH_RESERVE) as usize,
+            None => usize::MAX,
+        };
+
+        self.tracked_flights
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| !matches!(f.status, FlightStatus::Landed | FlightStatus::Cancelled))
+            .filter(|(_, f)| f.arrived_at.is_none())
+            .filter(|(_, f)| {
+                self.last_schedule_refresh
+                    .get(&f.flight_number)
+                    .is_none_or(|last| last.elapsed() >= interval)
+            })
+            .map(|(i, _)| i)
+            .take(budget)
+            .collect()
+    }
+
+    /// Record that schedule requests were sent for the given flights.
+    pub fn mark_schedule_polled(&mut self, indices: &[usize]) {
+        let now = Instant::now();
+        for &index in indices {
+            if let Some(flight) = self.tracked_flights.get(index) {
+                self.last_schedule_refresh
+                    .insert(flight.flight_number.clone(), now);
+            }
+        }
+    }
+
+    /// Merge refreshed schedule data into a tracked flight.
+    ///
+    /// Live position fields are left alone, and the schedule's status only
+    /// shows once the position data has gone stale, unless it says the
+    /// flight was cancelled.
+    pub fn update_schedule(&mut self, flight_number: &str, data: FlightData) {
+        let Some(index) = self
+            .tracked_flights
+            .iter()
+            .position(|f| f.flight_number == flight_number)
+        else {
+            return;
+        };
+
+        let flight = &mut self.tracked_flights[index];
+        let previous = flight.status.clone();
+        apply_schedule_data(flight, data);
+        flight.last_updated = Some(Utc::now());
+        if flight.status != previous {
+            debug!(
+                flight = %flight.flight_number,
+                from = %previous,
+                to = %flight.status,
+                "Status changed"
+            );
+            self.ensure_visible_selection();
+        }
+        self.status_changed(index, &previous);
+        self.record_delay(index);
+        self.note_route(index);
+        self.check_alerts(index);
+        self.mark_arrival(index, Utc::now());
+        // A schedule may carry a position, over the oceans
+        if self.following.as_deref() == Some(flight_number) {
+            self.fit_map();
+        }
+    }
+
+    /// Bring the flight's history entry up to date once its route is known,
+    /// as the search that added it may not have found both airports.
+    fn note_route(&mut self, index: usize) {
+        let flight = &self.tracked_flights[index];
+        let Some(route) = flight.route_label() else {
+            return;
+        };
+        if self.history.update_route(&flight.flight_number, route) {
+            self.history.save();
+        }
+    }
+
+    /// Add a flight's current delay to its history, announcing changes in
+    /// the status bar and the log.
+    fn record_delay(&mut self, index: usize) {
+        let now = Utc::now();
+        let flight = &mut self.tracked_flights[index];
+        if !flight.record_delay(now) {
+            return;
+        }
+        if let Some(trend) = flight.delay_trend(now) {
+            info!(flight = %flight.flight_number, %trend, "Delay changed");
+            self.status_message = Some(format!("{} delay {}", flight.flight_number, trend));
+        }
+        self.save_session();
+    }
+
+    /// Record a focus change. Returns true when focus was regained and an
+    /// immediate refresh is due.
+    pub fn set_focused(&mut self, focused: bool) -> bool {
+        let regained = focused && !self.focused;
+        if focused != self.focused {
+            debug!(focused, "Terminal focus changed");
+        }
+        self.focused = focused;
+        regained && !self.tracked_flights.is_empty() && !self.loading
+    }
+}
+
+/// Merge a state vector into the flight. Fields the vector leaves out keep
+/// their known values rather than going blank, except that a change of
+/// aircraft starts over, and on the ground a missing altitude, climb rate or
+/// speed is cleared (feeds routinely drop them there, and a stale airborne
+/// reading would be worse than none).
+/// A flight's status for an announcement, with where it is when known:
+/// "En Route at FL350", "On Ground at EGLL", "Scheduled".
+fn status_phrase(flight: &Flight) -> String {
+    if let Some(airport) = flight.ground_airport() {
+        return format!("{} at {}", flight.status, airport.icao);
+    }
+    match flight.altitude_ft {
+        Some(alt) if flight.status == FlightStatus::EnRoute && alt >= FLIGHT_LEVEL_FROM_FT => {
+            format!("{} at FL{:03.0}", flight.status, alt / 100.0)
+        }
+        Some(alt) if flight.status == FlightStatus::EnRoute => {
+            format!("{} at {:.0} ft", flight.status, alt)
+        }
+        _ => flight.status.to_string(),
+    }
+}
+
+/// Apply a state vector to `flight`. Readings outside `limits` are left
+/// out, keeping the last good ones, and returned.
+fn apply_position_data(
+    flight: &mut Flight,
+    sv: StateVector,
+    limits: &PositionLimits,
+) -> Vec<(Reading, f64)> {
+    const MPS_TO_KNOTS: f64 = 1.94384;
+
+    flight.raw_state = Some(raw_payload(&sv));
+    // A different aircraft's trail and readings don't belong to this one
+    if !flight.icao24.is_empty() && flight.icao24 != sv.icao24 {
+        flight.track.clear();
+        flight.latitude = None;
+        flight.longitude = None;
+        flight.position_time = None;
+        flight.position_source = None;
+        flight.snapshot_time = None;
+        flight.altitude_ft = None;
+        flight.heading = None;
+        flight.vertical_rate = None;
+        flight.ground_speed_kts = None;
+        flight.squawk = None;
+        flight.category = None;
+    }
+    let airborne = |incoming: Option<f64>, known: Option<f64>| {
+        incoming.or(if sv.on_ground { None } else { known })
+    };
+
+    if let Some(callsign) = sv.callsign.filter(|c| !c.trim().is_empty()) {
+        flight.callsign = callsign;
+    }
+    flight.icao24 = sv.icao24;
+    // Without both coordinates the last fix and its time stand
+    if let (Some(latitude), Some(longitude)) = (sv.latitude, sv.longitude) {
+        flight.latitude = Some(latitude);
+        flight.longitude = Some(longitude);
+        flight.position_time =
+            DateTime::from_timestamp(sv.time_position.unwrap_or(sv.last_contact), 0);
+        flight.position_source = Some(PositionSource::OpenSky);
+        // Filled in by `App::record_feed_time` once the snapshot is known
+        flight.snapshot_time = None;
+    }
+    let altitude_ft = sv.baro_altitude.map(|a| a * METERS_TO_FEET);
+    let vertical_rate = sv.vertical_rate.map(|v| v * METERS_TO_FEET * 60.0);
+    let ground_speed_kts = sv.velocity.map(|v| v * MPS_TO_KNOTS);
+    let mut rejected = Vec::new();
+    let mut plausible = |reading, value: Option<f64>| match value {
+        Some(value) if !limits.allows(reading, value) => {
+            rejected.push((reading, value));
+            false
+        }
+        _ => true,
+    };
+    if plausible(Reading::Altitude, altitude_ft) {
+        flight.altitude_ft = airborne(altitude_ft, flight.altitude_ft);
+    }
+    flight.heading = sv.true_track.or(flight.heading);
+    if plausible(Reading::VerticalRate, vertical_rate) {
+        flight.vertical_rate = airborne(vertical_rate, flight.vertical_rate);
+    }
+    if plausible(Reading::GroundSpeed, ground_speed_kts) {
+        flight.ground_speed_kts = airborne(ground_speed_kts, flight.ground_speed_kts);
+    }
+    if !sv.on_ground {
+        flight.on_ground_since = None;
+    } else if !flight.on_ground || flight.on_ground_since.is_none() {
+        flight.on_ground_since = Some(Utc::now());
+    }
+    flight.on_ground = sv.on_ground;
+    flight.note_position(Utc::now());
+    flight.record_vertical_rate(Utc::now());
+    flight.record_track_point();
+    if let Some(squawk) = sv.squawk.filter(|s| !s.trim().is_empty()) {
+        flight.squawk = Some(squawk);
+    }
+    if sv.category.is_some() {
+        flight.category = sv.category;
+    }
+
+    flight.position_status = Some(if sv.on_ground {
+        FlightStatus::OnGround
+    } else {
+        FlightStatus::EnRoute
+    });
+    flight.resolve_status(Utc::now());
+    rejected
+}
+
+/// Log readings [`apply_position_data`] left out and count them for the
+/// diagnostics overlay.
+fn count_rejected(
+    counts: &mut HashMap<Reading, u64>,
+    flight: &Flight,
+    rejected: Vec<(Reading, f64)>,
+) {
+    for (reading, value) in rejected {
+        warn!(flight = %flight.flight_number, %reading, value, "Ignoring implausible reading");
+        *counts.entry(reading).or_default() += 1;
+    }
+}
+
+fn apply_schedule_data(flight: &mut Flight, data: FlightData) {
+    flight.raw_schedule = Some(raw_payload(&data));
+    // Status
+    if let Some(status) = &data.flight_status {
+        flight.schedule_status = Some(FlightStatus::from_api_status(status));
+        flight.resolve_status(Utc::now());
+    }
+
+    // Airline
+    if let Some(airline) = &data.airline {
+        flight.airline = airline.name.clone();
+    }
+
+    // Aircraft
+    if let Some(aircraft) = &data.aircraft {
+        flight.aircraft_type = aircraft.iata.clone().or(aircraft.icao.clone());
+        flight.registration = aircraft.registration.clone();
+    }
+
+    // Origin airport
+    if let Some(dep) = &data.departure {
+        flight.origin = Some(Airport {
+            name: dep.airport.clone(),
+            iata: dep.iata.clone(),
+            icao: dep.icao.clone(),
+        });
+        flight.set_schedule_time(ScheduleField::DepartureScheduled, dep.scheduled.as_deref());
+        flight.set_schedule_time(ScheduleField::DepartureEstimated, dep.estimated.as_deref());
+        flight.set_schedule_time(ScheduleField::DepartureActual, dep.actual.as_deref());
+        flight.departure_delay = dep.delay;
+    }
+
+    // Destination airport
+    if let Some(arr) = &data.arrival {
+        flight.destination = Some(Airport {
+            name: arr.airport.clone(),
+            iata: arr.iata.clone(),
+            icao: arr.icao.clone(),
+        });
+        flight.set_schedule_time(ScheduleField::ArrivalScheduled, arr.scheduled.as_deref());
+        flight.set_schedule_time(ScheduleField::ArrivalEstimated, arr.estimated.as_deref());
+        flight.set_schedule_time(ScheduleField::ArrivalActual, arr.actual.as_deref());
+        flight.arrival_delay = arr.delay;
+    }
+
+    if let Some(live) = &data.live {
+        apply_live_data(flight, live);
+    }
+}
+
+/// Fill the position from AviationStack's `live` block when it is newer
+/// than what OpenSky last gave, as over oceans where OpenSky has no
+/// coverage. Fields the block leaves out keep their known values. Unlike a
+/// state vector it doesn't count towards the landing heuristics, being too
+/// coarse for them.
+fn apply_live_data(flight: &mut Flight, live: &LiveInfo) {
+    let (Some(latitude), Some(longitude), Some(updated)) =
+        (live.latitude, live.longitude, live.updated_at())
+    else {
+        return;
+    };
+    if flight.position_time.is_some_and(|known| known >= updated) {
+        return;
+    }
+    flight.latitude = Some(latitude);
+    flight.longitude = Some(longitude);
+    flight.position_time = Some(updated);
+    flight.position_source = Some(PositionSource::AviationStack);
+    flight.altitude_ft = live.altitude_ft().or(flight.altitude_ft);
+    flight.heading = live.direction.or(flight.heading);
+    flight.vertical_rate = live.vertical_rate_fpm().or(flight.vertical_rate);
+    flight.ground_speed_kts = live.ground_speed_kts().or(flight.ground_speed_kts);
+    if !live.is_ground {
+        flight.on_ground_since = None;
+    }
+    flight.on_ground = live.is_ground;
+    flight.record_track_point();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::AircraftCategory;
+    use crate::fixtures;
+    use chrono::{NaiveDate, TimeZone};
+
+    #[test]
+    fn test_app_default() {
+        let app = App::default();
+
+        assert_eq!(app.mode, AppMode::Input);
+        assert!(!app.should_quit);
+        assert!(app.input_buffer.is_empty());
+        assert!(app.tracked_flights.is_empty());
+        assert!(app.selected_index.is_none());
+        assert!(!app.loading);
+    }
+
+    #[test]
+    fn test_input_char() {
+        let mut app = App::default();
+
+        app.input_char('U');
+        app.input_char('A');
+        app.input_char('1');
+
+        assert_eq!(app.input_buffer, "UA1");
+        assert_eq!(app.cursor_position, 3);
+    }
+
+    #[test]
+    fn test_input_backspace() {
+        let mut app = App::default();
+
+        app.input_char('U');
+        app.input_char('A');
+        app.input_backspace();
+
+        assert_eq!(app.input_buffer, "U");
+        assert_eq!(app.cursor_position, 1);
+
+        // Backspace on empty should do nothing
+        app.input_backspace();
+        app.input_backspace();
+        assert_eq!(app.input_buffer, "");
+        assert_eq!(app.cursor_position, 0);
+    }
+
+    #[test]
+    fn test_leaving_input_keeps_draft() {
+        let mut app = App::default();
+        app.input_char('B');
+        app.input_char('A');
+        app.input_char('2');
+        app.cursor_position = 1;
+
+        app.leave_input();
+        assert_eq!(app.mode, AppMode::Viewing);
+        assert!(app.input_buffer.is_empty());
+        assert_eq!(app.input_draft, "BA2");
+
+        // Other prompts share the buffer without disturbing the draft
+        app.add_flight("UA123".to_string(), None, None);
+        app.begin_alert_input();
+        for c in "landed".chars() {
+            app.input_char(c);
+        }
+        app.submit_alert_input();
+        assert_eq!(app.mode, AppMode::Viewing);
+        assert_eq!(app.input_draft, "BA2");
+
+        app.begin_input();
+        assert_eq!(app.mode, AppMode::Input);
+        assert_eq!(app.input_buffer, "BA2");
+        assert_eq!(app.cursor_position, 1);
+        assert!(app.input_draft.is_empty());
+
+        app.clear_input();
+        assert!(app.input_buffer.is_empty());
+        assert_eq!(app.cursor_position, 0);
+        app.leave_input();
+        app.begin_input();
+        assert!(app.input_buffer.is_empty());
+    }
+
+    #[test]
+    fn test_leaving_input_resets_history_navigation() {
+        let mut app = App::default();
+        app.history.add("UA123".to_string(), None);
+        app.history.add("BA285".to_string(), None);
+
+        app.history_previous();
+        app.history_previous();
+        assert_eq!(app.input_buffer, "UA123");
+
+        app.leave_input();
+        assert_eq!(app.history_index, None);
+        assert_eq!(app.input_draft, "UA123");
+
+        // Browsing starts again from the newest entry
+        app.begin_input();
+        app.history_previous();
+        assert_eq!(app.history_index, Some(0));
+        assert_eq!(app.input_buffer, "BA285");
+    }
+
+    #[test]
+    fn test_submit_input() {
+        let mut app = App::default();
+
+        app.input_char('u');
+        app.input_char('a');
+        app.input_char('1');
+        app.input_char('2');
+        app.input_char('3');
+
+        let result = app.submit_input();
+
+        assert_eq!(result, Some("UA123".to_string())); // Should be uppercased
+        assert!(app.input_buffer.is_empty());
+        assert_eq!(app.cursor_position, 0);
+
+        // Submit on empty should return None
+        assert_eq!(app.submit_input(), None);
+    }
+
+    #[test]
+    fn test_add_flight() {
+        let mut app = App::default();
+
+        app.add_flight("UA123".to_string(), None, None);
+
+        assert_eq!(app.tracked_flights.len(), 1);
+        assert_eq!(app.tracked_flights[0].flight_number, "UA123");
+        assert_eq!(app.tracked_flights[0].status, FlightStatus::NotFound);
+        assert_eq!(app.selected_index, Some(0));
+    }
+
+    #[test]
+    fn test_add_duplicate_flight() {
+        let mut app = App::default();
+
+        app.add_flight("UA123".to_string(), None, None);
+        app.add_flight("UA123".to_string(), None, None);
+
+        assert_eq!(app.tracked_flights.len(), 1);
+        assert!(app.status_message.is_some());
+    }
+
+    #[test]
+    fn test_select_next_previous() {
+        let mut app = App::default();
+
+        app.add_flight("UA123".to_string(), None, None);
+        app.add_flight("BA285".to_string(), None, None);
+        app.add_flight("AF007".to_string(), None, None);
+
+        assert_eq!(app.selected_index, Some(2)); // Last added is selected
+
+        app.select_previous();
+        assert_eq!(app.selected_index, Some(1));
+
+        app.select_previous();
+        assert_eq!(app.selected_index, Some(0));
+
+        app.select_previous(); // Should wrap to end
+        assert_eq!(app.selected_index, Some(2));
+
+        app.select_next(); // Should wrap to beginning
+        assert_eq!(app.selected_index, Some(0));
+    }
+
+    #[test]
+    fn test_select_on_empty_list() {
+        let mut app = App::default();
+
+        app.select_next();
+        app.select_previous();
+
+        assert!(app.selected_index.is_none());
+    }
+
+    #[test]
+    fn test_remove_selected_flight() {
+        let mut app = App::default();
+
+        app.add_flight("UA123".to_string(), None, None);
+        app.add_flight("BA285".to_string(), None, None);
+
+        app.selected_index = Some(0);
+        app.remove_selected_flight();
+
+        assert_eq!(app.tracked_flights.len(), 1);
+        assert_eq!(app.tracked_flights[0].flight_number, "BA285");
+        assert_eq!(app.selected_index, Some(0));
+    }
+
+    #[test]
+    fn test_remove_last_flight() {
+        let mut app = App::default();
+
+        app.add_flight("UA123".to_string(), None, None);
+        app.remove_selected_flight();
+
+        assert!(app.tracked_flights.is_empty());
+        assert!(app.selected_index.is_none());
+    }
+
+    #[test]
+    fn test_should_update() {
+        let mut app = App::default();
+
+        // Empty list should not update
+        assert!(!app.should_update());
+
+        // Without a transponder address there is nothing to poll
+        app.add_flight("UA123".to_string(), None, None);
+        assert!(!app.should_update());
+
+        // A never-polled flight with a position source is due
+        app.tracked_flights[0].icao24 = "a808c4".to_string();
+        app.last_polled.clear();
+        assert!(app.should_update());
+
+        // While loading, should not update
+        app.loading = true;
+        assert!(!app.should_update());
+    }
+
+    #[test]
+    fn test_due_flights_follow_phase_interval() {
+        let mut app = App::default();
+        app.add_flight("UA123".to_string(), None, None);
+        app.add_flight("BA285".to_string(), None, None);
+        for flight in &mut app.tracked_flights {
+            flight.icao24 = "a808c4".to_string();
+            flight.status = FlightStatus::EnRoute;
+        }
+        // UA123 on approach, BA285 cruising
+        app.tracked_flights[0].altitude_ft = Some(5_000.0);
+        app.tracked_flights[0].vertical_rate = Some(-900.0);
+        app.tracked_flights[1].altitude_ft = Some(37_000.0);
+        app.tracked_flights[1].vertical_rate = Some(0.0);
+
+        let fifteen_secs_ago = Instant::now() - Duration::from_secs(15);
+        app.last_polled
+            .insert("UA123".to_string(), fifteen_secs_ago);
+        app.last_polled
+            .insert("BA285".to_string(), fifteen_secs_ago);
+
+        assert_eq!(app.due_flights(), vec![0]);
+        assert_eq!(app.seconds_until_update(), Some(0));
+
+        app.mark_polled(&[0]);
+        assert!(app.due_flights().is_empty());
+        assert_eq!(app.seconds_until_update(), Some(10));
+    }
+
+    #[test]
+    fn test_ui_state_round_trips_through_app() {
+        let mut app = App::default();
+        app.toggle_time_format();
+        app.cycle_filter();
+        app.toggle_arrived();
+        app.toggle_profile();
+        app.toggle_layout();
+        let saved = app.ui_state();
+        assert_eq!(saved.time_format, Some(TimeFormat::TwelveHour));
+        assert_eq!(saved.layout, Some(ScreenLayout::Ticker));
+        assert_eq!(saved.filter, ListFilter::Active);
+        assert!(saved.show_arrived && saved.show_profile && !saved.show_map);
+
+        let mut restored = App::default();
+        restored.restore_ui_state(saved.clone());
+        assert_eq!(restored.ui_state(), saved);
+        assert_eq!(restored.config.time_format, TimeFormat::TwelveHour);
+        assert_eq!(restored.config.layout, ScreenLayout::Ticker);
+        assert_eq!(restored.filter, ListFilter::Active);
+        // Restoring isn't a change to save again
+        assert!(!restored.save_ui_state(Instant::now(), true));
+    }
+
+    #[test]
+    fn test_config_is_the_default_until_a_key_changes_it() {
+        let mut app = App::default();
+        app.config.time_format = TimeFormat::TwelveHour;
+
+        // Never toggled: the config's clock stands and nothing is saved over it
+        app.restore_ui_state(UiState::default());
+        assert_eq!(app.config.time_format, TimeFormat::TwelveHour);
+        app.toggle_panel(RightPanel::Map);
+        assert_eq!(app.ui_state().time_format, None);
+
+        // Toggled in an earlier run: that choice wins over the config
+        app.restore_ui_state(UiState {
+            time_format: Some(TimeFormat::TwentyFourHour),
+            ..Default::default()
+        });
+        assert_eq!(app.config.time_format, TimeFormat::TwentyFourHour);
+        app.toggle_time_format();
+        assert_eq!(app.ui_state().time_format, Some(TimeFormat::TwelveHour));
+    }
+
+    #[test]
+    fn test_ui_state_saves_once_changes_settle() {
+        let mut app = App::default();
+        let start = Instant::now();
+        assert!(!app.save_ui_state(start, false));
+
+        app.toggle_panel(RightPanel::Map);
+        assert!(!app.save_ui_state(Instant::now(), false));
+        // A second change restarts the wait
+        app.ui_changed_at = Some(start);
+        app.toggle_profile();
+        let changed = app.ui_changed_at.unwrap();
+        assert!(!app.save_ui_state(changed + ui_state::SAVE_DELAY / 2, false));
+        assert!(app.save_ui_state(changed + ui_state::SAVE_DELAY, false));
+        assert!(!app.save_ui_state(changed + ui_state::SAVE_DELAY * 2, false));
+
+        // Quitting doesn't wait
+        app.cycle_filter();
+        assert!(app.save_ui_state(Instant::now(), true));
+    }
+
+    #[test]
+    fn test_flight_refresh_interval() {
+        let mut app = App::default();
+        let mut flight = Flight {
+            status: FlightStatus::EnRoute,
+            altitude_ft: Some(3_000.0),
+            vertical_rate: Some(-700.0),
+            ..Default::default()
+        };
+        assert_eq!(app.flight_refresh_interval(&flight), Some(10));
+
+        // The configured minimum caps how often a flight is polled
+        app.config.min_refresh_secs = 25;
+        assert_eq!(app.flight_refresh_interval(&flight), Some(25));
+
+        app.focused = false;
+        assert_eq!(app.flight_refresh_interval(&flight), Some(100));
+
+        flight.status = FlightStatus::Landed;
+        assert_eq!(app.flight_refresh_interval(&flight), None);
+    }
+
+    #[test]
+    fn test_follow_pins_details_while_cursor_moves() {
+        let mut app = App::default();
+        for number in ["UA123", "BA285", "LH400"] {
+            app.add_flight(number.to_string(), None, None);
+        }
+        app.selected_index = Some(0);
+        app.apply(Action::ToggleRawView);
+        app.panel_scroll = 3;
+
+        app.apply(Action::ToggleFollow);
+        assert_eq!(app.following.as_deref(), Some("UA123"));
+        assert_eq!((app.right_panel, app.panel_scroll), (RightPanel::Raw, 0));
+
+        // j/k move only the cursor; the details stay on the followed flight
+        app.apply(Action::SelectNext);
+        app.apply(Action::SelectNext);
+        app.panel_scroll = 2;
+        app.apply(Action::SelectPrevious);
+        assert_eq!(app.selected_index, Some(1));
+        assert_eq!(app.pinned_flight().unwrap().flight_number, "UA123");
+        assert_eq!((app.right_panel, app.panel_scroll), (RightPanel::Raw, 2));
+
+        // Removing a flight ahead of it keeps following it at its new index
+        app.selected_index = Some(0);
+        app.tracked_flights.swap(0, 2);
+        app.remove_selected_flight();
+        assert_eq!(app.followed_index(), Some(1));
+        assert_eq!(app.pinned_flight().unwrap().flight_number, "UA123");
+
+        // Letting go hands the details back to the cursor
+        app.apply(Action::ToggleFollow);
+        assert_eq!(app.following, None);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Stopped following UA123")
+        );
+        assert_eq!(app.pinned_index(), app.selected_index);
+        assert_eq!(app.pinned_flight().unwrap().flight_number, "BA285");
+    }
+
+    #[test]
+    fn test_follow_ends_when_its_flight_goes() {
+        let mut app = App::default();
+        app.add_flight("UA123".to_string(), None, None);
+        app.add_flight("BA285".to_string(), None, None);
+        app.apply(Action::ToggleFollow);
+        assert_eq!(app.following.as_deref(), Some("BA285"));
+        app.remove_selected_flight();
+        assert_eq!(app.following, None);
+        assert_eq!(app.pinned_flight().unwrap().flight_number, "UA123");
+
+        // Nothing to follow without a selection
+        let mut empty = App::default();
+        empty.apply(Action::ToggleFollow);
+        assert_eq!(empty.following, None);
+        assert_eq!(
+            empty.status_message.as_deref(),
+            Some("Select a flight to follow")
+        );
+    }
+
+    #[test]
+    fn test_follow_recenters_map_and_refreshes_at_minimum() {
+        let mut app = App::default();
+        app.add_flight("UA123".to_string(), Some(position(51.5, -0.5)), None);
+        app.add_flight("BA285".to_string(), Some(position(40.6, -73.8)), None);
+        app.selected_index = Some(0);
+        app.toggle_panel(RightPanel::Map);
+        let fitted = app.map_viewport.unwrap();
+        assert!(fitted.contains(40.6, -73.8));
+
+        app.apply(Action::ToggleFollow);
+        let centered = |app: &App, lat: f64, lon: f64| {
+            let view = app.map_viewport.unwrap();
+            let (mid_lat, mid_lon) = (
+                (view.min_lat + view.max_lat) / 2.0,
+                (view.min_lon + view.max_lon) / 2.0,
+            );
+            (mid_lat - lat).abs() < 1e-9 && (mid_lon - lon).abs() < 1e-9
+        };
+        assert!(centered(&app, 51.5, -0.5));
+
+        // Every update of the followed flight recenters, even inside the view
+        app.update_flight("UA123", Some(position(51.6, -0.4)));
+        assert!(centered(&app, 51.6, -0.4));
+        // Other flights leaving the view don't take it over
+        app.update_flight("BA285", Some(position(35.5, 139.8)));
+        assert!(centered(&app, 51.6, -0.4));
+
+        let cruising = Flight {
+            flight_number: "UA123".to_string(),
+            status: FlightStatus::EnRoute,
+            altitude_ft: Some(37_000.0),
+            vertical_rate: Some(0.0),
+            ..Default::default()
+        };
+        let phase_secs = cruising
+            .phase()
+            .refresh_interval_secs(app.update_interval_secs);
+        assert!(phase_secs.unwrap() > app.config.min_refresh_secs);
+        assert_eq!(
+            app.flight_refresh_interval(&cruising),
+            Some(app.config.min_refresh_secs)
+        );
+        let landed = Flight {
+            status: FlightStatus::Landed,
+            ..cruising.clone()
+        };
+        assert_eq!(app.flight_refresh_interval(&landed), None);
+
+        // Letting go goes back to fitting everything, at the phase's pace
+        app.apply(Action::ToggleFollow);
+        assert!(app.map_viewport.unwrap().contains(35.5, 139.8));
+        assert_eq!(app.flight_refresh_interval(&cruising), phase_secs);
+    }
+
+    fn schedule(status: &str, departure_delay: i32) -> FlightData {
+        serde_json::from_value(serde_json::json!({
+            "flight_status": status,
+            "departure": {"airport": "San Francisco International", "iata": "SFO",
+                          "scheduled": "2024-07-14T20:05:00+00:00", "delay": departure_delay},
+            "arrival": {"airport": "Frankfurt", "iata": "FRA"},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_schedule_refresh_tracks_delay_changes() {
+        let mut app = App::default();
+        app.add_flight("UA900".to_string(), None, Some(schedule("scheduled", 15)));
+        assert_eq!(app.tracked_flights[0].delay_history.len(), 1);
+
+        app.status_message = None;
+        app.update_schedule("UA900", schedule("scheduled", 15));
+        assert_eq!(app.tracked_flights[0].delay_history.len(), 1);
+        assert!(app.status_message.is_none());
+
+        app.update_schedule("UA900", schedule("scheduled", 40));
+        assert_eq!(app.tracked_flights[0].delay_history.len(), 2);
+        let message = app.status_message.take().unwrap();
+        assert!(
+            message.starts_with("UA900 delay +15 → +40 over the last"),
+            "{message}"
+        );
+
+        app.update_schedule("UA900", schedule("scheduled", 40));
+        assert!(app.status_message.is_none());
+    }
+
+    #[test]
+    fn test_flight_counts() {
+        let mut app = app_with_statuses(&[
+            FlightStatus::EnRoute,
+            FlightStatus::EnRoute,
+            FlightStatus::Scheduled,
+            FlightStatus::Scheduled,
+            FlightStatus::Landed,
+            FlightStatus::OnGround,
+            FlightStatus::Cancelled,
+        ]);
+        app.tracked_flights[2].departure_delay = Some(25);
+        // On the ground at the destination counts as landed
+        app.tracked_flights[5].arrived_at = Some(Utc::now());
+
+        assert_eq!(
+            app.flight_counts(),
+            FlightCounts {
+                tracked: 7,
+                en_route: 2,
+                delayed: 1,
+                landed: 2,
+            }
+        );
+        assert_eq!(App::default().flight_counts(), FlightCounts::default());
+    }
+
+    #[test]
+    fn test_later_schedule_fills_in_history_route() {
+        let mut app = App::default();
+        app.add_flight("UA900".to_string(), None, None);
+        assert_eq!(app.history.entries().next().unwrap().route, None);
+
+        app.add_flight("BA285".to_string(), None, None);
+        app.update_schedule("UA900", schedule("scheduled", 0));
+        let entries: Vec<_> = app.history.entries().collect();
+        assert_eq!(entries[1].flight_number, "UA900");
+        assert_eq!(entries[1].route.as_deref(), Some("SFO→FRA"));
+    }
+
+    #[test]
+    fn test_update_schedule_keeps_position() {
+        let mut app = App::default();
+        app.add_flight("UA900".to_string(), None, Some(schedule("active", 0)));
+        let flight = &mut app.tracked_flights[0];
+        flight.latitude = Some(52.1);
+        flight.altitude_ft = Some(37_000.0);
+        flight.position_time = Some(Utc::now());
+        flight.position_status = Some(FlightStatus::EnRoute);
+
+        // A lagging "scheduled" row doesn't override the live status
+        app.update_schedule("UA900", schedule("scheduled", 45));
+        let flight = &app.tracked_flights[0];
+        assert_eq!(flight.departure_delay, Some(45));
+        assert_eq!(flight.latitude, Some(52.1));
+        assert_eq!(flight.altitude_ft, Some(37_000.0));
+        assert_eq!(flight.status, FlightStatus::EnRoute);
+
+        app.update_schedule("UA900", schedule("cancelled", 45));
+        assert_eq!(app.tracked_flights[0].status, FlightStatus::Cancelled);
+
+        // Once the positions stop coming the schedule takes over
+        app.tracked_flights[0].position_time = Some(Utc::now() - chrono::Duration::minutes(10));
+        app.update_schedule("UA900", schedule("landed", 45));
+        assert_eq!(app.tracked_flights[0].status, FlightStatus::Landed);
+    }
+
+    #[test]
+    fn test_status_changes_queue_webhooks() {
+        let mut app = App::default();
+        app.add_flight("UA900".to_string(), None, Some(schedule("scheduled", 0)));
+        // Nothing is queued without a webhook
+        app.update_schedule("UA900", schedule("active", 0));
+        assert!(app.take_webhooks(Instant::now()).is_empty());
+
+        app.config.webhook_url = Some("https://example.com/hook".to_string());
+        app.update_schedule("UA900", schedule("active", 0));
+        app.update_schedule("UA900", schedule("cancelled", 20));
+        let changes = app.take_webhooks(Instant::now());
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes[0].summary(),
+            "UA900 SFO→FRA: En Route → Cancelled, 20 min late"
+        );
+
+        app.webhook_finished(Some("Network error".to_string()));
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Webhook failed: Network error")
+        );
+        // Repeated failures stay out of the status bar until one goes through
+        app.status_message = None;
+        app.webhook_finished(Some("Network error".to_string()));
+        assert_eq!(app.status_message, None);
+        app.webhook_finished(None);
+        app.webhook_finished(Some("Network error".to_string()));
+        assert!(app.status_message.is_some());
+    }
+
+    #[test]
+    fn test_muted_flight_only_notifies_in_the_app() {
+        let mut app = App::default();
+        app.config.webhook_url = Some("https://example.com/hook".to_string());
+        app.add_flight("UA900".to_string(), None, Some(schedule("active", 0)));
+        app.tracked_flights[0]
+            .alerts
+            .push(AlertRule::new(AlertCondition::Landed));
+        app.selected_index = Some(0);
+        let now = Utc::now();
+
+        app.cycle_mute(now);
+        assert_eq!(app.status_message.as_deref(), Some("UA900 muted for 15m"));
+        app.update_schedule("UA900", schedule("landed", 0));
+        // The status bar still says so, but no bell, desktop or webhook
+        assert_eq!(app.alert_message.as_deref(), Some("UA900 is landed"));
+        assert!(app.pending_alerts.is_empty());
+        assert!(app.take_webhooks(Instant::now()).is_empty());
+
+        // 15 minutes, an hour, until landed, off
+        app.selected_index = Some(0);
+        app.cycle_mute(now);
+        assert_eq!(app.status_message.as_deref(), Some("UA900 muted for 1h"));
+        app.cycle_mute(now);
+        assert_eq!(app.tracked_flights[0].muted_until, Some(Mute::UntilLanded));
+        app.cycle_mute(now);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("UA900 notifications back on")
+        );
+        assert_eq!(app.tracked_flights[0].muted_until, None);
+    }
+
+    #[test]
+    fn test_mutes_expire() {
+        let mut app = App::default();
+        app.add_flight("UA900".to_string(), None, Some(schedule("active", 0)));
+        app.add_flight("BA285".to_string(), None, Some(schedule("active", 0)));
+        let now = Utc::now();
+        app.tracked_flights[0].muted_until = Mute::cycle(None, now);
+        app.tracked_flights[1].muted_until = Some(Mute::UntilLanded);
+
+        app.expire_mutes(now + chrono::Duration::minutes(14));
+        assert!(app.tracked_flights.iter().all(|f| f.muted_until.is_some()));
+        app.expire_mutes(now + chrono::Duration::minutes(15));
+        assert_eq!(app.tracked_flights[0].muted_until, None);
+        assert!(app.tracked_flights[1].is_muted(now + chrono::Duration::days(1)));
+
+        app.update_schedule("BA285", schedule("landed", 0));
+        app.expire_mutes(now);
+        assert_eq!(app.tracked_flights[1].muted_until, None);
+    }
+
+    #[test]
+    fn test_accessible_mode_announces_changes() {
+        let mut app = App::default();
+        app.add_flight("UA900".to_string(), None, Some(schedule("scheduled", 0)));
+        app.update_schedule("UA900", schedule("active", 0));
+        assert!(app.announcements.is_empty());
+
+        app.accessible = true;
+        app.tracked_flights[0].altitude_ft = Some(35_040.0);
+        app.update_schedule("UA900", schedule("cancelled", 0));
+        app.tracked_flights[0].status = FlightStatus::EnRoute;
+        app.update_schedule("UA900", schedule("cancelled", 0));
+        app.add_flight("BA285".to_string(), None, Some(schedule("scheduled", 0)));
+        assert_eq!(
+            app.announcements,
+            [
+                "UA900 now Cancelled",
+                "UA900 now Cancelled",
+                "BA285 added, Scheduled"
+            ]
+        );
+
+        app.tracked_flights[0].status = FlightStatus::Scheduled;
+        app.tracked_flights[0].position_status = Some(FlightStatus::EnRoute);
+        app.tracked_flights[0].position_time = Some(Utc::now());
+        app.update_schedule("UA900", schedule("active", 0));
+        assert_eq!(
+            app.announcements.last().unwrap(),
+            "UA900 now En Route at FL350"
+        );
+
+        for _ in 0..MAX_ANNOUNCEMENTS {
+            app.announce("filler".to_string());
+        }
+        assert_eq!(app.announcements.len(), MAX_ANNOUNCEMENTS);
+        assert!(app.announcements.iter().all(|a| a == "filler"));
+    }
+
+    #[test]
+    fn test_schedule_due_flights() {
+        let mut app = App::default();
+        app.add_flight("UA900".to_string(), None, Some(schedule("active", 0)));
+        app.add_flight("BA285".to_string(), None, Some(schedule("scheduled", 0)));
+        app.add_flight("LH400".to_string(), None, Some(schedule("landed", 0)));
+
+        // Just searched, so nothing is due yet
+        assert!(app.schedule_due_flights(None).is_empty());
+
+        let an_hour_ago = Instant::now() - Duration::from_secs(3600);
+        for number in ["UA900", "BA285", "LH400"] {
+            app.last_schedule_refresh
+                .insert(number.to_string(), an_hour_ago);
+        }
+        // Landed flights are not refreshed
+        assert_eq!(app.schedule_due_flights(None), vec![0, 1]);
+
+        // Only spend what's left above the reserve
+        assert_eq!(app.schedule_due_flights(Some(89)), vec![0]);
+        assert!(app.schedule_due_flights(Some(90)).is_empty());
+
+        app.mark_schedule_polled(&[0, 1]);
+        assert!(app.schedule_due_flights(None).is_empty());
+    }
+
+    fn position(lat: f64, lon: f64) -> StateVector {
+        StateVector {
+            icao24: "a808c4".to_string(),
+            callsign: None,
+            origin_country: String::new(),
+            time_position: None,
+            last_contact: 0,
+            longitude: Some(lon),
+            latitude: Some(lat),
+            baro_altitude: Some(11_000.0),
+            on_ground: false,
+            velocity: None,
+            true_track: None,
+            vertical_rate: None,
+            geo_altitude: None,
+            squawk: None,
+            category: None,
+        }
+    }
+
+    #[test]
+    fn test_eta_divergence_announced_once() {
+        let now = Utc::now();
+        let mut app = App::default();
+        app.tracked_flights.push(Flight {
+            flight_number: "LH400".to_string(),
+            status: FlightStatus::EnRoute,
+            destination: Some(fixtures::airport("FRA")),
+            arrival_estimated: Some((now + chrono::Duration::hours(2)).fixed_offset()),
+            ..Default::default()
+        });
+        // Half an hour out at 450 kts, far sooner than the airline says
+        let inbound = || StateVector {
+            time_position: Some(now.timestamp()),
+            velocity: Some(231.5),
+            ..position(50.5, 4.0)
+        };
+
+        app.update_flight("LH400", Some(inbound()));
+        let message = app.status_message.take().unwrap();
+        assert!(message.starts_with("LH400: airline estimate "), "{message}");
+        assert!(
+            message.ends_with(" — possible hold or early arrival"),
+            "{message}"
+        );
+        assert!(app.tracked_flights[0].eta_diverged);
+
+        app.update_flight("LH400", Some(inbound()));
+        assert!(app.status_message.is_none());
+
+        // Back in agreement, then apart again: said again
+        let track_eta = app.tracked_flights[0].track_eta().unwrap();
+        app.tracked_flights[0].arrival_estimated = Some(track_eta.fixed_offset());
+        app.update_flight("LH400", Some(inbound()));
+        assert!(!app.tracked_flights[0].eta_diverged);
+        assert!(app.status_message.is_none());
+        app.tracked_flights[0].arrival_estimated = Some(now.fixed_offset());
+        app.update_flight("LH400", Some(inbound()));
+        let message = app.status_message.take().unwrap();
+        assert!(
+            message.ends_with(" — running behind the airline's estimate"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn test_position_update_keeps_known_fields() {
+        let mut flight = Flight::default();
+        apply_position_data(
+            &mut flight,
+            StateVector {
+                callsign: Some("BAW285".to_string()),
+                time_position: Some(1_700_000_000),
+                true_track: Some(270.0),
+                velocity: Some(250.0),
+                vertical_rate: Some(5.0),
+                squawk: Some("2341".to_string()),
+                ..position(51.0, -1.0)
+            },
+            &PositionLimits::default(),
+        );
+
+        // Blank callsign and squawk, no altitude, speed or coordinates
+        apply_position_data(
+            &mut flight,
+            StateVector {
+                callsign: Some(String::new()),
+                squawk: Some(" ".to_string()),
+                time_position: None,
+                last_contact: 1_700_000_060,
+                latitude: None,
+                longitude: None,
+                baro_altitude: None,
+                ..position(0.0, 0.0)
+            },
+            &PositionLimits::default(),
+        );
+        assert_eq!(flight.callsign, "BAW285");
+        assert_eq!(flight.squawk.as_deref(), Some("2341"));
+        assert_eq!(
+            (flight.latitude, flight.longitude),
+            (Some(51.0), Some(-1.0))
+        );
+        assert_eq!(
+            flight.position_time,
+            DateTime::from_timestamp(1_700_000_000, 0)
+        );
+        assert!((flight.altitude_ft.unwrap() - 36_089.0).abs() < 1.0);
+        assert_eq!(flight.heading, Some(270.0));
+        assert!(flight.ground_speed_kts.is_some() && flight.vertical_rate.is_some());
+        assert_eq!(flight.track.len(), 1);
+
+        // New values still replace old ones
+        apply_position_data(
+            &mut flight,
+            StateVector {
+                callsign: Some("BAW285A".to_string()),
+                time_position: Some(1_700_000_120),
+                true_track: Some(265.0),
+                ..position(51.1, -1.2)
+            },
+            &PositionLimits::default(),
+        );
+        assert_eq!(flight.callsign, "BAW285A");
+        assert_eq!(flight.latitude, Some(51.1));
+        assert_eq!(flight.heading, Some(265.0));
+        assert_eq!(flight.track.len(), 2);
+    }
+
+    #[test]
+    fn test_position_gaps_on_the_ground_clear_readings() {
+        let mut flight = Flight::default();
+        apply_position_data(
+            &mut flight,
+            StateVector {
+                velocity: Some(70.0),
+                vertical_rate: Some(-4.0),
+                ..position(51.47, -0.45)
+            },
+            &PositionLimits::default(),
+        );
+        apply_position_data(
+            &mut flight,
+            StateVector {
+                on_ground: true,
+                baro_altitude: None,
+                true_track: None,
+                ..position(51.47, -0.46)
+            },
+            &PositionLimits::default(),
+        );
+        assert_eq!(flight.altitude_ft, None);
+        assert_eq!(flight.vertical_rate, None);
+        assert_eq!(flight.ground_speed_kts, None);
+    }
+
+    #[test]
+    fn test_implausible_readings_keep_the_last_good_ones() {
+        let limits = PositionLimits::default();
+        let mut flight = Flight::default();
+        let rejected = apply_position_data(
+            &mut flight,
+            StateVector {
+                velocity: Some(250.0),
+                vertical_rate: Some(5.0),
+                ..position(51.0, -1.0)
+            },
+            &limits,
+        );
+        assert!(rejected.is_empty());
+        let (speed, rate) = (flight.ground_speed_kts, flight.vertical_rate);
+
+        // Mach 3.4, 3,000 ft below sea level and a 20,000 fpm climb
+        let rejected = apply_position_data(
+            &mut flight,
+            StateVector {
+                velocity: Some(1_000.0),
+                baro_altitude: Some(-1_000.0),
+                vertical_rate: Some(100.0),
+                ..position(51.1, -1.1)
+            },
+            &limits,
+        );
+        let kinds: Vec<Reading> = rejected.iter().map(|(reading, _)| *reading).collect();
+        assert_eq!(
+            kinds,
+            [
+                Reading::Altitude,
+                Reading::VerticalRate,
+                Reading::GroundSpeed
+            ]
+        );
+        assert!((flight.altitude_ft.unwrap() - 36_089.0).abs() < 1.0);
+        assert_eq!(
+            (flight.ground_speed_kts, flight.vertical_rate),
+            (speed, rate)
+        );
+        // The position itself still moves; the trail carries the good altitude
+        assert_eq!(flight.latitude, Some(51.1));
+        let last = flight.track.last().unwrap();
+        assert!((last.altitude_ft.unwrap() - 36_089.0).abs() < 1.0);
+
+        // Looser bounds let the same readings through
+        let limits = PositionLimits {
+            max_ground_speed_kts: 3_000.0,
+            min_altitude_ft: -5_000.0,
+            max_vertical_rate_fpm: 30_000.0,
+            ..PositionLimits::default()
+        };
+        let rejected = apply_position_data(
+            &mut flight,
+            StateVector {
+                velocity: Some(1_000.0),
+                baro_altitude: Some(-1_000.0),
+                vertical_rate: Some(100.0),
+                ..position(51.2, -1.2)
+            },
+            &limits,
+        );
+        assert!(rejected.is_empty());
+        assert!(flight.ground_speed_kts.unwrap() > 1_900.0);
+        assert!(flight.altitude_ft.unwrap() < -3_000.0);
+    }
+
+    #[test]
+    fn test_rejected_readings_are_counted() {
+        let mut app = App::default();
+        app.add_flight("BA285".to_string(), Some(position(51.0, -1.0)), None);
+        let too_fast = || StateVector {
+            velocity: Some(1_000.0),
+            ..position(51.1, -1.1)
+        };
+        app.update_flight("BA285", Some(too_fast()));
+        app.update_flight("BA285", Some(too_fast()));
+        app.update_flight("BA285", Some(position(51.2, -1.2)));
+        assert_eq!(app.rejected_readings.get(&Reading::GroundSpeed), Some(&2));
+        assert_eq!(app.rejected_readings.get(&Reading::Altitude), None);
+        assert_eq!(app.tracked_flights[0].ground_speed_kts, None);
+    }
+
+    #[test]
+    fn test_new_aircraft_starts_over() {
+        let mut flight = Flight::default();
+        apply_position_data(
+            &mut flight,
+            StateVector {
+                callsign: Some("BAW285".to_string()),
+                squawk: Some("7700".to_string()),
+                true_track: Some(90.0),
+                ..position(51.0, -1.0)
+            },
+            &PositionLimits::default(),
+        );
+        apply_position_data(
+            &mut flight,
+            StateVector {
+                icao24: "4ca7b4".to_string(),
+                latitude: None,
+                longitude: None,
+                baro_altitude: None,
+                ..position(0.0, 0.0)
+            },
+            &PositionLimits::default(),
+        );
+        // The callsign names the flight, not the aircraft
+        assert_eq!(flight.callsign, "BAW285");
+        assert_eq!(flight.icao24, "4ca7b4");
+        assert_eq!(
+            (flight.latitude, flight.altitude_ft, flight.heading),
+            (None, None, None)
+        );
+        assert_eq!(flight.squawk, None);
+        assert!(flight.track.is_empty());
+    }
+
+    /// BA117 from Heathrow to JFK, in the air by the schedule, last polled
+    /// on aircraft a808c4.
+    fn swap_candidate() -> App {
+        let mut app = App::default();
+        app.tracked_flights.push(Flight {
+            flight_number: "BA117".to_string(),
+            callsign: "BAW117".to_string(),
+            icao24: "a808c4".to_string(),
+            schedule_status: Some(FlightStatus::EnRoute),
+            status: FlightStatus::EnRoute,
+            origin: Some(fixtures::airport("LHR")),
+            destination: Some(fixtures::airport("JFK")),
+            ..Default::default()
+        });
+        app
+    }
+
+    #[test]
+    fn test_parked_aircraft_is_swapped_for_the_one_flying() {
+        let mut app = swap_candidate();
+        let parked = StateVector {
+            callsign: Some("DLH9XK".to_string()),
+            on_ground: true,
+            baro_altitude: None,
+            ..position(50.03, 8.57)
+        };
+
+        // Sitting at Frankfurt while the schedule has it over the Atlantic
+        app.update_flight("BA117", Some(parked.clone()));
+        assert_eq!(app.take_pending_rematches(), ["BA117"]);
+        // One search per interval, however many polls agree
+        app.update_flight("BA117", Some(parked));
+        assert!(app.take_pending_rematches().is_empty());
+        app.take_pending_tracks();
+
+        // Nothing better turns up: the same aircraft, another one on the
+        // ground, or one flying a different callsign
+        let airborne = |icao24: &str, callsign: &str| StateVector {
+            icao24: icao24.to_string(),
+            callsign: Some(callsign.to_string()),
+            ..position(55.0, -30.0)
+        };
+        app.rematch_found("BA117", None);
+        app.rematch_found("BA117", Some(airborne("a808c4", "BAW117")));
+        app.rematch_found(
+            "BA117",
+            Some(StateVector {
+                on_ground: true,
+                ..airborne("4ca7b4", "BAW117")
+            }),
+        );
+        app.rematch_found("BA117", Some(airborne("4ca7b4", "BAW1175")));
+        assert_eq!(app.tracked_flights[0].icao24, "a808c4");
+        assert!(app.status_message.is_none());
+
+        app.rematch_found("BA117", Some(airborne("4ca7b4", "BAW117  ")));
+        let flight = &app.tracked_flights[0];
+        assert_eq!(flight.icao24, "4ca7b4");
+        assert_eq!(
+            (flight.latitude, flight.longitude),
+            (Some(55.0), Some(-30.0))
+        );
+        assert!(!flight.on_ground);
+        // The parked aircraft's trail is gone and the new one's is fetched
+        assert_eq!(flight.track.len(), 1);
+        assert_eq!(
+            app.take_pending_tracks(),
+            [("BA117".into(), "4ca7b4".into())]
+        );
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("BA117 is now flown by 4ca7b4 (was a808c4)")
+        );
+
+        // Polling the new aircraft raises no more suspicion
+        app.update_flight("BA117", Some(airborne("4ca7b4", "BAW117")));
+        assert!(app.take_pending_rematches().is_empty());
+    }
+
+    #[test]
+    fn test_aircraft_missing_from_the_feed_is_searched_again() {
+        let mut app = swap_candidate();
+        app.update_flight("BA117", Some(position(52.0, -20.0)));
+        for _ in 0..4 {
+            app.update_flight("BA117", None);
+        }
+        assert!(app.take_pending_rematches().is_empty());
+
+        app.update_flight("BA117", None);
+        assert_eq!(app.take_pending_rematches(), ["BA117"]);
+        // Still nothing once the search comes back empty
+        app.rematch_found("BA117", None);
+        assert_eq!(app.tracked_flights[0].icao24, "a808c4");
+
+        // A later miss searches again once the interval is up
+        let later = Instant::now() + REMATCH_INTERVAL;
+        app.check_swap(0, Instant::now());
+        assert!(app.take_pending_rematches().is_empty());
+        app.check_swap(0, later);
+        assert_eq!(app.take_pending_rematches(), ["BA117"]);
+    }
+
+    #[test]
+    fn test_flight_on_route_is_not_searched_again() {
+        // On the ground at Heathrow before departure, then at JFK after
+        // landing: both ends of the route
+        let mut app = swap_candidate();
+        let on_ground = |lat, lon| StateVector {
+            on_ground: true,
+            baro_altitude: None,
+            ..position(lat, lon)
+        };
+        app.update_flight("BA117", Some(on_ground(51.47, -0.45)));
+        app.update_flight("BA117", Some(position(50.0, -30.0)));
+        app.update_flight("BA117", Some(on_ground(40.64, -73.78)));
+        assert!(app.take_pending_rematches().is_empty());
+
+        // Diverted to Gander, it has flown the flight: not a swap either
+        app.update_flight("BA117", Some(on_ground(48.94, -54.57)));
+        assert!(app.take_pending_rematches().is_empty());
+    }
+
+    #[test]
+    fn test_destination_weather_is_fetched_every_half_hour() {
+        let mut app = App::default();
+        app.add_flight("UA900".to_string(), None, Some(schedule("active", 0)));
+        app.add_flight("LH455".to_string(), None, Some(schedule("active", 0)));
+        let now = Instant::now();
+        // Frankfurt, known by its IATA code only, once for both flights
+        assert_eq!(app.take_due_weather(now), ["EDDF"]);
+        assert!(app
+            .take_due_weather(now + Duration::from_secs(60))
+            .is_empty());
+        assert_eq!(app.take_due_weather(now + WEATHER_REFRESH), ["EDDF"]);
+
+        app.weather_received(
+            "EDDF",
+            StationWeather {
+                metar: Some("EDDF 121150Z 24010KT 9999 FEW030 22/12 Q1015".to_string()),
+                taf: Some("TAF EDDF 121100Z 1212/1318 24010KT 9999 SCT030".to_string()),
+            },
+        );
+        let weather = &app.weather["EDDF"];
+        assert!(weather
+            .metar
+            .as_deref()
+            .unwrap()
+            .starts_with("EDDF 121150Z"));
+        assert_eq!(weather.taf.as_ref().unwrap().station, "EDDF");
+
+        // Once both have arrived, Frankfurt's weather is no longer needed
+        for flight in &mut app.tracked_flights {
+            flight.arrived_at = Some(Utc::now());
+        }
+        assert!(app.take_due_weather(now + WEATHER_REFRESH * 2).is_empty());
+        assert!(app.weather.is_empty());
+    }
+
+    #[test]
+    fn test_unreadable_taf_keeps_the_metar() {
+        let mut app = App::default();
+        app.weather_received(
+            "EDDF",
+            StationWeather {
+                metar: Some("EDDF 121150Z 24010KT CAVOK 22/12 Q1015".to_string()),
+                taf: Some("TAF EDDF 121100Z NIL".to_string()),
+            },
+        );
+        let weather = &app.weather["EDDF"];
+        assert!(weather.metar.is_some());
+        assert!(weather.taf.is_none());
+    }
+
+    fn live(updated: &str, lat: f64, lon: f64) -> LiveInfo {
+        serde_json::from_value(serde_json::json!({
+            "updated": updated, "latitude": lat, "longitude": lon, "altitude": 10972.8,
+            "direction": 62.0, "speed_horizontal": 907.4, "speed_vertical": 0.0,
+            "is_ground": false,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_live_block_fills_in_without_opensky() {
+        let mut app = App::default();
+        let data = FlightData {
+            live: Some(live("2024-07-14T23:42:00+00:00", 58.7, -41.3)),
+            ..schedule("active", 0)
+        };
+        app.add_flight("UA900".to_string(), None, Some(data));
+
+        let flight = &app.tracked_flights[0];
+        assert_eq!(
+            (flight.latitude, flight.longitude),
+            (Some(58.7), Some(-41.3))
+        );
+        assert_eq!(flight.position_source, Some(PositionSource::AviationStack));
+        assert!((flight.altitude_ft.unwrap() - 36_000.0).abs() < 1.0);
+        assert!((flight.ground_speed_kts.unwrap() - 490.0).abs() < 0.1);
+        assert_eq!(flight.heading, Some(62.0));
+        assert_eq!(flight.track.len(), 1);
+        // Too coarse for the landing heuristics
+        assert!(!flight.seen_airborne);
+
+        // A later refresh moves it along
+        let data = FlightData {
+            live: Some(live("2024-07-14T23:52:00+00:00", 59.4, -38.9)),
+            ..schedule("active", 0)
+        };
+        app.update_schedule("UA900", data);
+        assert_eq!(app.tracked_flights[0].latitude, Some(59.4));
+        assert_eq!(app.tracked_flights[0].track.len(), 2);
+    }
+
+    #[test]
+    fn test_live_block_never_replaces_fresher_opensky_fixes() {
+        let mut flight = Flight::default();
+        let opensky_time = DateTime::parse_from_rfc3339("2024-07-14T23:45:00Z").unwrap();
+        apply_position_data(
+            &mut flight,
+            StateVector {
+                time_position: Some(opensky_time.timestamp()),
+                ..position(51.0, -1.0)
+            },
+            &PositionLimits::default(),
+        );
+        apply_live_data(&mut flight, &live("2024-07-14T23:42:00+00:00", 58.7, -41.3));
+        assert_eq!(flight.latitude, Some(51.0));
+        assert_eq!(flight.position_source, Some(PositionSource::OpenSky));
+
+        // Once OpenSky loses the aircraft, newer live reports take over
+        apply_live_data(&mut flight, &live("2024-07-14T23:55:00+00:00", 52.0, -5.0));
+        assert_eq!(flight.latitude, Some(52.0));
+        assert_eq!(flight.position_source, Some(PositionSource::AviationStack));
+
+        // Without coordinates or a time there is nothing to go on
+        let mut blank = live("2024-07-15T00:10:00+00:00", 0.0, 0.0);
+        blank.latitude = None;
+        apply_live_data(&mut flight, &blank);
+        assert_eq!(flight.latitude, Some(52.0));
+    }
+
+    #[test]
+    fn test_track_history_fills_in_the_trail() {
+        use crate::api::Waypoint;
+
+        let mut app = App::default();
+        let fix = StateVector {
+            time_position: Some(1_700_000_600),
+            ..position(41.0, -73.5)
+        };
+        app.add_flight("UA123".to_string(), Some(fix.clone()), None);
+        app.add_flight("BA285".to_string(), None, None);
+        app.update_flight("UA123", Some(fix));
+        // One lookup per aircraft, and none without one
+        assert_eq!(
+            app.take_pending_tracks(),
+            vec![("UA123".into(), "a808c4".into())]
+        );
+        assert!(app.take_pending_tracks().is_empty());
+        assert_eq!(app.tracked_flights[0].track.len(), 1);
+
+        let waypoint = |time, altitude| Waypoint {
+            time,
+            latitude: Some(40.64),
+            longitude: Some(-73.78),
+            baro_altitude: Some(altitude),
+            true_track: None,
+            on_ground: false,
+        };
+        let track = FlightTrack {
+            icao24: "A808C4".to_string(),
+            start_time: 1_700_000_000,
+            end_time: 1_700_000_600,
+            callsign: None,
+            path: vec![
+                waypoint(1_700_000_000, 0.0),
+                Waypoint {
+                    latitude: None,
+                    ..waypoint(1_700_000_100, 300.0)
+                },
+                waypoint(1_700_000_300, 3_000.0),
+                waypoint(1_700_000_600, 11_000.0),
+            ],
+        };
+        app.apply_track("UA123", track.clone());
+        let altitudes: Vec<f64> = app.tracked_flights[0]
+            .track
+            .iter()
+            .map(|p| p.altitude_ft.unwrap().round())
+            .collect();
+        assert_eq!(altitudes, vec![0.0, 9_843.0, 36_089.0]);
+
+        // Another aircraft's track is ignored
+        app.apply_track("BA285", track);
+        assert!(app.tracked_flights[1].track.is_empty());
+    }
+
+    #[test]
+    fn test_map_viewport_fits_positions() {
+        let mut app = App::default();
+        app.add_flight("UA123".to_string(), Some(position(51.5, -0.5)), None);
+        app.add_flight("BA285".to_string(), None, None);
+
+        app.apply(Action::ToggleMap);
+        assert_eq!(app.right_panel, RightPanel::Map);
+        let view = app.map_viewport.unwrap();
+        assert!(view.contains(51.5, -0.5));
+
+        // Selecting another flight keeps the viewport
+        app.select_next();
+        assert_eq!(app.map_viewport, Some(view));
+
+        // A flight leaving the viewport refits it
+        app.update_flight("UA123", Some(position(48.0, 11.0)));
+        assert!(app.map_viewport.unwrap().contains(48.0, 11.0));
+
+        // Zooming keeps the centre, and stops short of a featureless square
+        app.apply(Action::ZoomMap { zoom_in: false });
+        let wider = app.map_viewport.unwrap();
+        assert!(wider.max_lat - wider.min_lat > view.max_lat - view.min_lat);
+        for _ in 0..20 {
+            app.apply(Action::ZoomMap { zoom_in: true });
+        }
+        let narrowest = app.map_viewport.unwrap();
+        assert!(narrowest.max_lon - narrowest.min_lon >= MAP_MIN_SPAN_DEG);
+
+        app.apply(Action::ToggleMap);
+        assert_eq!(app.right_panel, RightPanel::Details);
+        // Off the map, the zoom keys do nothing
+        app.apply(Action::ZoomMap { zoom_in: false });
+        assert_eq!(app.map_viewport, Some(narrowest));
+    }
+
+    #[test]
+    fn test_tabs_remembered_per_flight() {
+        let mut app = App::default();
+        for number in ["UA123", "BA285", "LH400"] {
+            app.add_flight(number.to_string(), None, None);
+        }
+        app.selected_index = Some(0);
+
+        app.apply(Action::ShowPanel(RightPanel::Weather));
+        app.apply(Action::SelectNext);
+        // A flight with no tab of its own opens on the details
+        assert_eq!(app.right_panel, RightPanel::Details);
+        app.apply(Action::CyclePanel { backward: true });
+        assert_eq!(app.right_panel, RightPanel::Raw);
+        app.apply(Action::SelectPrevious);
+        assert_eq!(app.right_panel, RightPanel::Weather);
+        app.apply(Action::SelectNext);
+        assert_eq!(app.right_panel, RightPanel::Raw);
+
+        // The map isn't anyone's, and stays open across flights
+        app.apply(Action::CyclePanel { backward: false });
+        assert_eq!(app.right_panel, RightPanel::Details);
+        app.apply(Action::CyclePanel { backward: false });
+        assert_eq!(app.right_panel, RightPanel::Map);
+        app.apply(Action::SelectPrevious);
+        assert_eq!(app.right_panel, RightPanel::Map);
+        assert!(app.ui_state().show_map);
+        // Closing it goes back to where this flight was
+        app.apply(Action::ToggleMap);
+        assert_eq!(app.right_panel, RightPanel::Weather);
+    }
+
+    fn app_with_statuses(statuses: &[FlightStatus]) -> App {
+        let mut app = App::default();
+        for (i, status) in statuses.iter().enumerate() {
+            app.add_flight(format!("FL{}", i), None, None);
+            app.tracked_flights[i].status = status.clone();
+        }
+        app
+    }
+
+    #[test]
+    fn test_remove_finished_flights_and_undo() {
+        use FlightStatus::*;
+        let mut app = app_with_statuses(&[EnRoute, Landed, Cancelled, Delayed, NotFound]);
+        app.selected_index = Some(1);
+
+        app.apply(Action::RequestRemoval(BulkRemoval::Finished));
+        assert_eq!(app.pending_removal_count(), 3);
+        // Cancelling keeps everything
+        app.apply(Action::AnswerRemoval(false));
+        assert_eq!(app.pending_removal, None);
+        assert_eq!(app.tracked_flights.len(), 5);
+
+        app.apply(Action::RequestRemoval(BulkRemoval::Finished));
+        app.apply(Action::AnswerRemoval(true));
+        let numbers: Vec<&str> = app
+            .tracked_flights
+            .iter()
+            .map(|f| f.flight_number.as_str())
+            .collect();
+        assert_eq!(numbers, ["FL0", "FL3"]);
+        // The selection moves to the flight that followed it
+        assert_eq!(app.selected_flight().unwrap().flight_number, "FL3");
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Removed 3 flight(s), u to undo")
+        );
+
+        // Nothing left to take
+        app.apply(Action::RequestRemoval(BulkRemoval::Finished));
+        assert_eq!(app.pending_removal, None);
+
+        app.apply(Action::Undo);
+        let numbers: Vec<&str> = app
+            .tracked_flights
+            .iter()
+            .map(|f| f.flight_number.as_str())
+            .collect();
+        assert_eq!(numbers, ["FL0", "FL1", "FL2", "FL3", "FL4"]);
+        assert_eq!(app.tracked_flights[2].status, Cancelled);
+        assert_eq!(app.selected_flight().unwrap().flight_number, "FL1");
+        assert_eq!(app.status_message.as_deref(), Some("Restored 3 flight(s)"));
+        app.apply(Action::Undo);
+        assert_eq!(app.status_message.as_deref(), Some("Nothing to undo"));
+    }
+
+    #[test]
+    fn test_bulk_removal_selection_outcomes() {
+        use FlightStatus::*;
+        // A selected flight that stays keeps the selection
+        let mut app = app_with_statuses(&[Landed, EnRoute, Landed]);
+        app.selected_index = Some(1);
+        app.request_removal(BulkRemoval::Finished);
+        app.answer_removal(true);
+        assert_eq!(app.selected_index, Some(0));
+        assert_eq!(app.selected_flight().unwrap().flight_number, "FL1");
+
+        // With nothing after the selection, the last remaining flight
+        let mut app = app_with_statuses(&[EnRoute, Delayed, Landed, Landed]);
+        app.selected_index = Some(3);
+        app.request_removal(BulkRemoval::Finished);
+        app.answer_removal(true);
+        assert_eq!(app.selected_flight().unwrap().flight_number, "FL1");
+
+        // Clearing everything leaves no selection, and undo brings it back
+        app.request_removal(BulkRemoval::All);
+        assert_eq!(app.pending_removal_count(), 2);
+        app.answer_removal(true);
+        assert!(app.tracked_flights.is_empty());
+        assert_eq!(app.selected_index, None);
+        app.request_removal(BulkRemoval::All);
+        assert_eq!(app.pending_removal, None);
+        assert_eq!(app.status_message.as_deref(), Some("No flights to remove"));
+        app.undo_removal();
+        assert_eq!(app.tracked_flights.len(), 2);
+        assert_eq!(app.selected_flight().unwrap().flight_number, "FL1");
+    }
+
+    #[test]
+    fn test_undo_skips_flights_tracked_again() {
+        let mut app = app_with_statuses(&[FlightStatus::EnRoute, FlightStatus::Landed]);
+        app.selected_index = Some(0);
+        app.apply(Action::Delete);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Removed FL0, u to undo")
+        );
+        app.request_removal(BulkRemoval::All);
+        app.answer_removal(true);
+
+        // FL1 comes back first, and FL0 was added again in the meantime
+        app.add_flight("FL0".to_string(), None, None);
+        app.undo_removal();
+        app.undo_removal();
+        let numbers: Vec<&str> = app
+            .tracked_flights
+            .iter()
+            .map(|f| f.flight_number.as_str())
+            .collect();
+        assert_eq!(numbers, ["FL1", "FL0"]);
+        assert_eq!(app.status_message.as_deref(), Some("Restored 0 flight(s)"));
+    }
+
+    #[test]
+    fn test_filter_view_indices() {
+        let mut app = app_with_statuses(&[
+            FlightStatus::EnRoute,
+            FlightStatus::Landed,
+            FlightStatus::Delayed,
+            FlightStatus::NotFound,
+            FlightStatus::Scheduled,
+        ]);
+        // Not found sinks below everything else, Landed included
+        assert_eq!(app.visible_indices(), vec![0, 1, 2, 4, 3]);
+
+        app.cycle_filter();
+        assert_eq!(app.filter, ListFilter::Active);
+        assert_eq!(app.visible_indices(), vec![0, 2, 4]);
+
+        app.cycle_filter();
+        assert_eq!(app.filter, ListFilter::Problems);
+        assert_eq!(app.visible_indices(), vec![2, 3]);
+
+        app.cycle_filter();
+        assert_eq!(app.filter, ListFilter::All);
+    }
+
+    #[test]
+    fn test_retry_replaces_the_not_found_entry() {
+        let mut app = App::default();
+        app.history
+            .add("UA900".to_string(), Some("SFO→FRA".to_string()));
+        app.add_flight("UA9000".to_string(), None, None);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("UA9000 not found, did you mean UA900?")
+        );
+        app.add_flight("BA285".to_string(), None, Some(schedule("active", 0)));
+        app.tracked_flights[0].alerts = vec![AlertRule::new(AlertCondition::Landed)];
+        app.selected_index = Some(0);
+
+        app.apply(Action::RetrySearch);
+        assert_eq!(app.pending_searches.len(), 1);
+        assert_eq!(app.take_pending_searches()[0].flight_number, "UA9000");
+        app.apply(Action::RetrySearch);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Search for UA9000 already running")
+        );
+
+        // The answer replaces the old entry, keeping its alerts
+        app.finish_search("UA9000");
+        app.add_flight("UA9000".to_string(), None, Some(schedule("scheduled", 0)));
+        assert_eq!(app.tracked_flights.len(), 2);
+        let flight = app
+            .tracked_flights
+            .iter()
+            .find(|f| f.flight_number == "UA9000")
+            .unwrap();
+        assert_eq!(flight.status, FlightStatus::Scheduled);
+        assert_eq!(flight.alerts.len(), 1);
+
+        // Found flights aren't searched again, nor replaced
+        app.apply(Action::RetrySearch);
+        assert!(app.pending_searches.is_empty());
+        app.add_flight("UA9000".to_string(), None, None);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Flight UA9000 is already tracked")
+        );
+    }
+
+    #[test]
+    fn test_navigation_skips_filtered_flights() {
+        let mut app = app_with_statuses(&[
+            FlightStatus::EnRoute,
+            FlightStatus::Landed,
+            FlightStatus::Delayed,
+            FlightStatus::Landed,
+        ]);
+        app.filter = ListFilter::Active;
+        app.selected_index = Some(0);
+
+        app.select_next();
+        assert_eq!(app.selected_index, Some(2));
+        assert_eq!(app.selected_view_index(), Some(1));
+        app.select_next();
+        assert_eq!(app.selected_index, Some(0));
+        app.select_previous();
+        assert_eq!(app.selected_index, Some(2));
+    }
+
+    #[test]
+    fn test_filter_moves_hidden_selection() {
+        let mut app = app_with_statuses(&[
+            FlightStatus::EnRoute,
+            FlightStatus::Landed,
+            FlightStatus::Delayed,
+        ]);
+        app.selected_index = Some(1);
+
+        app.cycle_filter();
+        assert_eq!(app.selected_index, Some(2));
+
+        // Removing it selects the nearest shown flight
+        app.remove_selected_flight();
+        assert_eq!(app.selected_index, Some(0));
+
+        // Nothing shown, nothing selected
+        app.filter = ListFilter::Problems;
+        app.ensure_visible_selection();
+        assert_eq!(app.selected_index, None);
+    }
+
+    #[test]
+    fn test_filter_remembers_selection_per_view() {
+        use FlightStatus::*;
+        let mut app = app_with_statuses(&[EnRoute, Landed, Delayed, EnRoute, Cancelled]);
+        app.selected_index = Some(3);
+
+        // Active shows 0, 2 and 3: the selection stays, then moves there
+        app.cycle_filter();
+        assert_eq!(app.selected_index, Some(3));
+        app.selected_index = Some(0);
+        app.cycle_filter();
+        assert_eq!(app.selected_index, Some(2));
+        app.cycle_filter();
+        assert_eq!(app.selected_index, Some(3));
+        app.cycle_filter();
+        assert_eq!(app.selected_index, Some(0));
+    }
+
+    #[test]
+    fn test_filter_restores_neighbor_after_removal() {
+        use FlightStatus::*;
+        let mut app = app_with_statuses(&[EnRoute, Landed, Delayed, EnRoute, Scheduled]);
+        app.selected_index = Some(3);
+        app.cycle_filter();
+        app.cycle_filter();
+        assert_eq!(app.filter, ListFilter::Problems);
+
+        // FL3 goes while its view is away; back there, the flight that took
+        // its place is selected
+        let index = app
+            .tracked_flights
+            .iter()
+            .position(|f| f.flight_number == "FL3");
+        app.remove_flights(&[index.unwrap()]);
+        app.cycle_filter();
+        assert_eq!(app.filter, ListFilter::All);
+        assert_eq!(app.selected_flight().unwrap().flight_number, "FL4");
+
+        // The last one in the view falls back to the new last one
+        app.selected_index = Some(3);
+        app.cycle_filter();
+        app.remove_flights(&[3]);
+        app.cycle_filter();
+        app.cycle_filter();
+        assert_eq!(app.selected_flight().unwrap().flight_number, "FL2");
+    }
+
+    #[test]
+    fn test_list_search_narrows_as_typed() {
+        let mut app = app_with_statuses(&[
+            FlightStatus::EnRoute,
+            FlightStatus::Landed,
+            FlightStatus::EnRoute,
+            FlightStatus::Delayed,
+        ]);
+        app.tracked_flights[1].airline = Some("Lufthansa".to_string());
+        app.tracked_flights[3].airline = Some("Lufthansa".to_string());
+        app.mode = AppMode::Viewing;
+        app.selected_index = Some(0);
+
+        app.apply(Action::BeginListSearch);
+        for c in "lufth".chars() {
+            app.apply(Action::InsertChar(c));
+        }
+        assert_eq!(app.visible_indices(), vec![1, 3]);
+        // The selection follows the narrowed list, and so does navigation
+        assert_eq!(app.selected_index, Some(1));
+        app.select_next();
+        assert_eq!(app.selected_index, Some(3));
+        app.select_next();
+        assert_eq!(app.selected_index, Some(1));
+
+        // It stacks with the status filter
+        app.filter = ListFilter::Active;
+        assert_eq!(app.visible_indices(), vec![3]);
+        app.filter = ListFilter::All;
+
+        app.apply(Action::Submit);
+        assert_eq!(app.mode, AppMode::Viewing);
+        assert_eq!(app.list_query, "lufth");
+        assert!(app.input_buffer.is_empty());
+        assert_eq!(app.visible_indices(), vec![1, 3]);
+
+        // Reopening starts from the kept text; Esc clears it
+        app.apply(Action::BeginListSearch);
+        assert_eq!(app.input_buffer, "lufth");
+        app.apply(Action::Backspace);
+        assert_eq!(app.list_query, "luft");
+        app.apply(Action::CancelInput);
+        assert_eq!(app.mode, AppMode::Viewing);
+        assert!(app.list_query.is_empty());
+        assert_eq!(app.visible_indices(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_jump_clears_a_hiding_list_search() {
+        let mut app = app_with_statuses(&[FlightStatus::EnRoute, FlightStatus::EnRoute]);
+        app.list_query = "FL1".to_string();
+        assert_eq!(app.visible_indices(), vec![1]);
+
+        assert!(app.jump_to_flight("FL0"));
+        assert!(app.list_query.is_empty());
+        assert_eq!(app.selected_view_index(), Some(0));
+    }
+
+    #[test]
+    fn test_ambiguous_position_lists_candidates() {
+        let mut app = App::default();
+        let candidate = |callsign: &str| StateVector {
+            callsign: Some(format!("{:<8}", callsign)),
+            ..position(40.0, -75.0)
+        };
+        app.add_flight("UA12".to_string(), None, None);
+        app.ambiguous_position("UA12", &[candidate("UAL12A"), candidate("UAL12B")]);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("2 aircraft match UA12 (UAL12A, UAL12B); position not tracked")
+        );
+        assert!(app.tracked_flights[0].latitude.is_none());
+    }
+
+    #[test]
+    fn test_arrived_section() {
+        let mut app = app_with_statuses(&[
+            FlightStatus::EnRoute,
+            FlightStatus::Landed,
+            FlightStatus::Delayed,
+        ]);
+        app.selected_index = Some(1);
+        app.sweep_arrivals();
+        assert!(app.tracked_flights[1].arrived_at.is_some());
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("FL1 moved to Arrived (A to expand)")
+        );
+
+        // Collapsed: the arrived flight is hidden and can't stay selected
+        assert_eq!(app.visible_indices(), vec![0, 2]);
+        assert_eq!(app.arrived_indices(), vec![1]);
+        assert_eq!(app.selected_index, Some(2));
+
+        // Expanded: arrived flights come after the active ones
+        app.toggle_arrived();
+        assert_eq!(app.visible_indices(), vec![0, 2, 1]);
+        app.select_next();
+        assert_eq!(app.selected_index, Some(1));
+
+        app.toggle_arrived();
+        assert_eq!(app.selected_index, Some(2));
+    }
+
+    #[test]
+    fn test_trip_groups_its_legs() {
+        let mut app = app_with_statuses(&[
+            FlightStatus::EnRoute,
+            FlightStatus::Scheduled,
+            FlightStatus::Landed,
+            FlightStatus::Scheduled,
+        ]);
+        let legs = import::parse_queries("FL2 FL1 NH6", Local::now().date_naive()).0;
+        let effects = app.apply(Action::AddTrip {
+            name: "NYC-TYO".to_string(),
+            legs,
+        });
+        // Only the leg not tracked yet is searched for
+        assert_eq!(effects, vec![Effect::StartSearches]);
+        assert_eq!(app.take_pending_searches().len(), 1);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Trip NYC→TYO: FL2, FL1, NH6")
+        );
+
+        // FL2 has landed, so the trip shows only its current leg, first
+        assert_eq!(app.trip_of("FL1").map(|t| t.name.as_str()), Some("NYC-TYO"));
+        assert_eq!(app.visible_indices(), vec![1, 0, 3]);
+
+        // Stepping in lists every leg in order, stepping out selects the current one
+        app.selected_index = Some(1);
+        app.apply(Action::StepIntoTrip);
+        assert_eq!(app.visible_indices(), vec![2, 1, 0, 3]);
+        app.select_previous();
+        assert_eq!(app.selected_index, Some(2));
+        app.apply(Action::StepOutOfTrip);
+        assert_eq!(app.visible_indices(), vec![1, 0, 3]);
+        assert_eq!(app.selected_index, Some(1));
+        // Outside a trip the keys do nothing
+        app.selected_index = Some(0);
+        app.apply(Action::StepIntoTrip);
+        assert_eq!(app.visible_indices(), vec![1, 0, 3]);
+
+        // A flight is in one trip at most, and names are unique
+        let legs = import::parse_queries("FL3 FL1", Local::now().date_naive()).0;
+        app.add_trip("LHR-JFK".to_string(), legs.clone());
+        assert_eq!(
+            app.last_error.as_deref(),
+            Some("FL1 is already in trip NYC→TYO")
+        );
+        app.add_trip("NYC-TYO".to_string(), legs);
+        assert_eq!(
+            app.last_error.as_deref(),
+            Some("Trip NYC-TYO already exists")
+        );
+        assert_eq!(app.trips.len(), 1);
+
+        app.apply(Action::RemoveTrip("NYC-TYO".to_string()));
+        assert!(app.trips.is_empty());
+        assert_eq!(app.visible_indices(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_trip_opens_until_a_leg_lands() {
+        let mut app = app_with_statuses(&[FlightStatus::EnRoute, FlightStatus::Scheduled]);
+        app.trips.push(Trip::new(
+            "SFO-HND",
+            vec!["FL0".to_string(), "FL1".to_string()],
+        ));
+        let trip = app.trips[0].clone();
+        assert!(app.is_trip_expanded(&trip));
+        assert_eq!(app.visible_indices(), vec![0, 1]);
+
+        app.tracked_flights[0].status = FlightStatus::Landed;
+        assert!(!app.is_trip_expanded(&trip));
+        assert_eq!(app.visible_indices(), vec![1]);
+
+        // Once all have landed the last leg stands for the trip, unless
+        // the filter hides finished flights
+        app.tracked_flights[1].status = FlightStatus::Landed;
+        assert_eq!(app.visible_indices(), vec![1]);
+        app.filter = ListFilter::Active;
+        assert!(app.visible_indices().is_empty());
+    }
+
+    #[test]
+    fn test_arrived_flights_are_not_refreshed() {
+        let mut app = app_with_statuses(&[FlightStatus::OnGround, FlightStatus::OnGround]);
+        for flight in &mut app.tracked_flights {
+            flight.icao24 = "a808c4".to_string();
+        }
+        app.last_polled.clear();
+        app.last_schedule_refresh.clear();
+        app.tracked_flights[1].arrived_at = Some(Utc::now());
+
+        assert_eq!(app.due_flights(), vec![0]);
+        assert_eq!(app.refreshable_flights(), vec![0]);
+        assert_eq!(app.schedule_due_flights(None), vec![0]);
+    }
+
+    #[test]
+    fn test_landing_inferred_without_schedule() {
+        let mut app = App::default();
+        let mut approach = position(51.47, -0.3);
+        approach.baro_altitude = Some(600.0);
+        approach.vertical_rate = Some(-4.0);
+        app.add_flight("BA285".to_string(), Some(approach), None);
+        app.add_flight("UA900".to_string(), Some(position(50.0, 8.0)), None);
+
+        // Dropped out of the feed on short final
+        for _ in 0..2 {
+            app.update_flight("BA285", None);
+            app.update_flight("UA900", None);
+        }
+        assert_eq!(app.tracked_flights[0].status, FlightStatus::EnRoute);
+        app.update_flight("BA285", None);
+        app.update_flight("UA900", None);
+        assert_eq!(app.tracked_flights[0].status, FlightStatus::Landed);
+        assert!(app.tracked_flights[0].arrived_at.is_some());
+        assert!(!app.refreshable_flights().contains(&0));
+        // Lost at cruise altitude is just out of coverage
+        assert_eq!(app.tracked_flights[1].status, FlightStatus::EnRoute);
+
+        // Taxiing long enough lands it on the next sweep
+        let mut rollout = position(50.03, 8.56);
+        rollout.on_ground = true;
+        rollout.velocity = Some(5.0);
+        app.update_flight("UA900", Some(rollout));
+        assert_eq!(app.tracked_flights[1].status, FlightStatus::OnGround);
+        app.sweep_arrivals();
+        assert_eq!(app.tracked_flights[1].status, FlightStatus::OnGround);
+        app.tracked_flights[1].taxiing_since = Some(Utc::now() - chrono::Duration::minutes(6));
+        app.sweep_arrivals();
+        assert_eq!(app.tracked_flights[1].status, FlightStatus::Landed);
+    }
+
+    #[test]
+    fn test_recurring_flight_rolls_over_at_midnight() {
+        let mut app = app_with_statuses(&[FlightStatus::Landed, FlightStatus::Landed]);
+        let landed = Utc::now() - chrono::Duration::hours(20);
+        for flight in &mut app.tracked_flights {
+            flight.flight_date = Some(NaiveDate::from_ymd_opt(2024, 7, 15).unwrap());
+            flight.arrived_at = Some(landed);
+        }
+        app.tracked_flights[0].from_watchlist = true;
+        app.selected_index = Some(0);
+        app.toggle_recurring();
+        assert!(app.tracked_flights[0].recurring);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("FL0 will roll over to the next day once it lands")
+        );
+
+        let before_midnight = Local.with_ymd_and_hms(2024, 7, 15, 23, 59, 59).unwrap();
+        app.roll_over_recurring(before_midnight);
+        assert_eq!(app.tracked_flights[0].status, FlightStatus::Landed);
+
+        app.last_schedule_refresh.clear();
+        app.mark_schedule_polled(&[0, 1]);
+        let midnight = Local.with_ymd_and_hms(2024, 7, 16, 0, 0, 0).unwrap();
+        app.roll_over_recurring(midnight);
+        let rolled = &app.tracked_flights[0];
+        assert_eq!(rolled.status, FlightStatus::Scheduled);
+        assert_eq!(rolled.flight_date, NaiveDate::from_ymd_opt(2024, 7, 16));
+        assert_eq!(rolled.arrived_at, None);
+        // Still the watchlist's to remove
+        assert!(rolled.from_watchlist);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("FL0 rolled over to Tue 2024-07-16")
+        );
+        // The new date's schedule is looked up straight away
+        assert_eq!(app.schedule_due_flights(None), vec![0]);
+        // The other flight stays where it was
+        assert_eq!(app.tracked_flights[1].status, FlightStatus::Landed);
+
+        // Recurring flights are never purged as arrived
+        app.tracked_flights[0].arrived_at = Some(Utc::now() - chrono::Duration::hours(13));
+        app.purge_arrived_at(Utc::now());
+        assert_eq!(app.tracked_flights.len(), 1);
+        assert_eq!(app.tracked_flights[0].flight_number, "FL0");
+        app.selected_index = Some(0);
+
+        app.toggle_recurring();
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("FL0 no longer rolls over")
+        );
+    }
+
+    #[test]
+    fn test_purge_arrived_flights() {
+        let mut app = app_with_statuses(&[
+            FlightStatus::Landed,
+            FlightStatus::EnRoute,
+            FlightStatus::Landed,
+        ]);
+        let now = Utc::now();
+        app.tracked_flights[0].arrived_at = Some(now - chrono::Duration::hours(13));
+        app.tracked_flights[2].arrived_at = Some(now - chrono::Duration::hours(2));
+        app.show_arrived = true;
+        app.selected_index = Some(2);
+
+        app.config.arrived_retention_hours = 0;
+        app.purge_arrived_at(now);
+        assert_eq!(app.tracked_flights.len(), 3);
+
+        app.config.arrived_retention_hours = 12;
+        app.purge_arrived_at(now);
+        let remaining: Vec<&str> = app
+            .tracked_flights
+            .iter()
+            .map(|f| f.flight_number.as_str())
+            .collect();
+        assert_eq!(remaining, vec!["FL1", "FL2"]);
+        assert!(!app.last_polled.contains_key("FL0"));
+        // The selection follows its flight to its new index
+        assert_eq!(app.selected_index, Some(1));
+    }
+
+    #[test]
+    fn test_filtered_out_new_flight_keeps_selection() {
+        let mut app = app_with_statuses(&[FlightStatus::Delayed]);
+        app.filter = ListFilter::Problems;
+        app.selected_index = Some(0);
+
+        let landed: FlightData =
+            serde_json::from_value(serde_json::json!({"flight_status": "landed"})).unwrap();
+        app.add_flight("LH400".to_string(), None, Some(landed));
+
+        assert_eq!(app.tracked_flights.len(), 2);
+        assert_eq!(app.selected_index, Some(0));
+    }
+
+    #[test]
+    fn test_submit_alert_input() {
+        let mut app = App::default();
+        app.add_flight("UA123".to_string(), None, None);
+
+        app.begin_alert_input();
+        assert_eq!(app.mode, AppMode::AlertInput);
+
+        app.input_buffer = "150KM".to_string();
+        app.submit_alert_input();
+
+        assert_eq!(app.mode, AppMode::Viewing);
+        assert_eq!(app.tracked_flights[0].alerts.len(), 1);
+        assert!(app.tracked_flights[0].alerts[0].armed);
+    }
+
+    #[test]
+    fn test_submit_invalid_alert_keeps_prompt() {
+        let mut app = App::default();
+        app.add_flight("UA123".to_string(), None, None);
+
+        app.begin_alert_input();
+        app.input_buffer = "soon".to_string();
+        app.submit_alert_input();
+
+        assert_eq!(app.mode, AppMode::AlertInput);
+        assert_eq!(app.input_buffer, "soon");
+        assert!(app.last_error.is_some());
+        assert!(app.tracked_flights[0].alerts.is_empty());
+    }
+
+    #[test]
+    fn test_empty_alert_input_clears_rules() {
+        let mut app = App::default();
+        app.add_flight("UA123".to_string(), None, None);
+        app.tracked_flights[0]
+            .alerts
+            .push(AlertRule::new(AlertCondition::Landed));
+
+        app.begin_alert_input();
+        app.submit_alert_input();
+
+        assert!(app.tracked_flights[0].alerts.is_empty());
+    }
+
+    #[test]
+    fn test_alert_fires_on_update() {
+        let mut app = App::default();
+        app.add_flight("UA123".to_string(), None, None);
+        app.tracked_flights[0]
+            .alerts
+            .push(AlertRule::new(AlertCondition::AltitudeBelowFt(5000.0)));
+
+        let sv = StateVector {
+            icao24: "abc123".to_string(),
+            callsign: Some("UAL123".to_string()),
+            origin_country: "United States".to_string(),
+            time_position: None,
+            last_contact: 0,
+            longitude: Some(-0.9),
+            latitude: Some(51.4),
+            baro_altitude: Some(900.0),
+            on_ground: false,
+            velocity: Some(80.0),
+            true_track: Some(90.0),
+            vertical_rate: Some(-4.0),
+            geo_altitude: None,
+            squawk: None,
+            category: Some(AircraftCategory::Large),
+        };
+        app.update_flight("UA123", Some(sv));
+
+        assert_eq!(
+            app.tracked_flights[0].category,
+            Some(AircraftCategory::Large)
+        );
+        assert_eq!(app.pending_alerts.len(), 1);
+        assert!(app.alert_message.is_some());
+        assert!(!app.tracked_flights[0].alerts[0].armed);
+    }
+
+    #[test]
+    fn test_begin_alert_input_requires_selection() {
+        let mut app = App {
+            mode: AppMode::Viewing,
+            ..Default::default()
+        };
+
+        app.begin_alert_input();
+
+        assert_eq!(app.mode, AppMode::Viewing);
+    }
+
+    #[test]
+    fn test_queue_input_multiple_flights() {
+        let mut app = App::default();
+        app.add_flight("UA123".to_string(), None, None);
+
+        app.queue_input("UA123, BA285 AF007 nonsense BA285");
+
+        let queued: Vec<_> = app
+            .pending_searches
+            .iter()
+            .map(|q| q.flight_number.as_str())
+            .collect();
+        assert_eq!(queued, vec!["BA285", "AF007"]);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Added 2 flight(s), skipped 2 duplicate(s), 1 invalid")
+        );
+    }
+
+    fn type_input(app: &mut App, text: &str) {
+        app.mode = AppMode::Input;
+        for c in text.chars() {
+            app.input_char(c);
+        }
+    }
+
+    #[test]
+    fn test_input_match_follows_keystrokes() {
+        let mut app = App::default();
+        app.add_flight("UA123".to_string(), None, None);
+
+        type_input(&mut app, "UA12");
+        assert_eq!(app.input_match, None);
+        app.input_char('3');
+        assert_eq!(app.input_match.as_deref(), Some("UA123"));
+        app.input_char('4');
+        assert_eq!(app.input_match, None);
+        app.input_backspace();
+        assert_eq!(app.input_match.as_deref(), Some("UA123"));
+
+        // Several flights on one line are a batch, not a jump
+        type_input(&mut app, " BA285");
+        assert_eq!(app.input_match, None);
+    }
+
+    fn restored_session(now: DateTime<Local>) -> Session {
+        let flight = |number: &str| SessionFlight {
+            flight_number: number.to_string(),
+            date: None,
+            alerts: Vec::new(),
+            delay_history: Vec::new(),
+            arrival: Some((now + chrono::Duration::hours(2)).fixed_offset()),
+            arrived: false,
+            recurring: false,
+            watchlist: false,
+            route: None,
+            muted_until: None,
+        };
+        Session {
+            flights: vec![
+                SessionFlight {
+                    arrived: true,
+                    ..flight("BA285")
+                },
+                SessionFlight {
+                    alerts: vec![AlertRule::new(AlertCondition::Landed)],
+                    recurring: true,
+                    route: Some("SFO→FRA".to_string()),
+                    muted_until: Some(Mute::UntilLanded),
+                    ..flight("UA900")
+                },
+                SessionFlight {
+                    arrival: Some((now - chrono::Duration::minutes(5)).fixed_offset()),
+                    ..flight("LH400")
+                },
+            ],
+            trips: vec![Trip::new(
+                "LHR-FRA",
+                vec!["BA285".to_string(), "LH400".to_string()],
+            )],
+        }
+    }
+
+    #[test]
+    fn test_restored_session_backfills_history_routes() {
+        let now = Local::now();
+        let mut app = App::default();
+        app.history.add("LH400".to_string(), None);
+        app.history.add("UA900".to_string(), None);
+        app.restore_session(restored_session(now), now, Instant::now());
+        assert_eq!(app.trips[0].legs, ["BA285", "LH400"]);
+
+        assert!(app.backfill_history_routes());
+        let routes: Vec<_> = app.history.entries().map(|e| e.route.as_deref()).collect();
+        assert_eq!(routes, vec![Some("SFO→FRA"), None]);
+        assert!(!app.backfill_history_routes());
+    }
+
+    #[test]
+    fn test_restored_session_waits_for_grace_period() {
+        let mut app = App::default();
+        let at = Instant::now();
+        app.restore_session(restored_session(Local::now()), Local::now(), at);
+
+        assert_eq!(app.mode, AppMode::Viewing);
+        assert!(app.pending_searches.is_empty());
+        let restore = app.restore.as_ref().unwrap();
+        assert_eq!(restore.likely_landed, vec!["BA285", "LH400"]);
+        assert_eq!(
+            restore.message(),
+            "Restored 3 flights from last session — 2 likely landed, \
+             press r to refresh all or d to prune"
+        );
+
+        app.end_restore_grace(at + Duration::from_secs(29));
+        assert!(app.restore.is_some());
+        assert!(app.pending_searches.is_empty());
+
+        app.end_restore_grace(at + RESTORE_GRACE);
+        assert!(app.restore.is_none());
+        let queued: Vec<&str> = app
+            .pending_searches
+            .iter()
+            .map(|q| q.flight_number.as_str())
+            .collect();
+        assert_eq!(queued, vec!["BA285", "UA900", "LH400"]);
+    }
+
+    #[test]
+    fn test_prune_restored_keeps_flights_still_flying() {
+        let mut app = App::default();
+        app.restore_session(restored_session(Local::now()), Local::now(), Instant::now());
+
+        app.prune_restored();
+        assert!(app.restore.is_none());
+        assert_eq!(app.pending_searches.len(), 1);
+        assert_eq!(app.pending_searches[0].flight_number, "UA900");
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Pruned 2 landed flight(s)")
+        );
+
+        // The kept flight still gets its saved alert, recurring flag and
+        // mute back
+        app.take_pending_searches();
+        app.add_flight("UA900".to_string(), None, None);
+        assert_eq!(app.tracked_flights[0].alerts.len(), 1);
+        assert!(app.tracked_flights[0].recurring);
+        assert_eq!(app.tracked_flights[0].muted_until, Some(Mute::UntilLanded));
+        assert!(!app.restored_flights.contains_key("BA285"));
+
+        // Nothing held back any more
+        app.prune_restored();
+        assert!(app.pending_searches.is_empty());
+    }
+
+    #[test]
+    fn test_saving_during_grace_period_keeps_held_flights() {
+        let mut app = App::default();
+        app.restore_session(restored_session(Local::now()), Local::now(), Instant::now());
+        app.add_flight("AF7".to_string(), None, Some(schedule("active", 0)));
+
+        let session = app.session();
+        let numbers: Vec<&str> = session
+            .flights
+            .iter()
+            .map(|f| f.flight_number.as_str())
+            .collect();
+        assert_eq!(numbers, ["AF7", "BA285", "LH400", "UA900"]);
+        let ua900 = &session.flights[3];
+        assert!(ua900.recurring);
+        assert_eq!(ua900.alerts.len(), 1);
+        assert_eq!(session.trips.len(), 1);
+
+        // A restored flight whose search failed is still kept; one that was
+        // answered is saved as tracked, once
+        app.resume_restored();
+        app.take_pending_searches();
+        app.finish_search("LH400");
+        app.add_flight("UA900".to_string(), None, Some(schedule("active", 0)));
+        app.finish_search("UA900");
+        let session = app.session();
+        let numbers: Vec<&str> = session
+            .flights
+            .iter()
+            .map(|f| f.flight_number.as_str())
+            .collect();
+        assert_eq!(numbers, ["AF7", "UA900", "BA285", "LH400"]);
+        assert!(session.flights[1].recurring);
+    }
+
+    #[test]
+    fn test_resume_restored_and_empty_session() {
+        let mut app = App::default();
+        app.restore_session(Session::default(), Local::now(), Instant::now());
+        assert!(app.restore.is_none());
+        assert_eq!(app.mode, AppMode::Input);
+
+        let mut session = restored_session(Local::now());
+        session.flights.truncate(1);
+        session.flights[0].arrived = false;
+        app.restore_session(session, Local::now(), Instant::now());
+        assert_eq!(
+            app.restore.as_ref().unwrap().message(),
+            "Restored 1 flight from last session — press r to refresh all"
+        );
+        app.resume_restored();
+        assert!(app.restore.is_none());
+        assert_eq!(app.pending_searches.len(), 1);
+    }
+
+    #[test]
+    fn test_input_match_normalizes_callsigns() {
+        let mut app = App::default();
+        app.add_flight("UA123".to_string(), None, None);
+
+        type_input(&mut app, "UAL123");
+        assert_eq!(app.input_match.as_deref(), Some("UA123"));
+    }
+
+    #[test]
+    fn test_jump_to_flight_reveals_it() {
+        let mut app = App::default();
+        app.add_flight("UA123".to_string(), None, None);
+        app.add_flight("BA285".to_string(), None, None);
+        app.tracked_flights[1].arrived_at = Some(Utc::now());
+        app.filter = ListFilter::Active;
+        app.selected_index = Some(0);
+
+        type_input(&mut app, "BA285");
+        let flight_number = app.input_match.clone().unwrap();
+        assert!(app.jump_to_flight(&flight_number));
+
+        assert_eq!(app.selected_index, Some(1));
+        assert_eq!(app.selected_view_index(), Some(1));
+        assert_eq!(app.filter, ListFilter::All);
+        assert!(app.show_arrived);
+        assert_eq!(app.mode, AppMode::Viewing);
+        assert!(app.input_buffer.is_empty());
+        assert_eq!(app.input_match, None);
+        assert!(app.pending_searches.is_empty());
+
+        assert!(!app.jump_to_flight("AF007"));
+    }
+
+    #[test]
+    fn test_queue_input_with_date() {
+        let mut app = App::default();
+
+        app.queue_input("UA123 2024-07-14");
+
+        assert_eq!(app.pending_searches.len(), 1);
+        assert_eq!(
+            app.pending_searches[0].date,
+            chrono::NaiveDate::from_ymd_opt(2024, 7, 14)
+        );
+        assert!(app.status_message.is_none());
+    }
+
+    #[test]
+    fn test_add_flight_keeps_query_date() {
+        let mut app = App::default();
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 7, 14);
+
+        app.add_flight(
+            FlightQuery {
+                flight_number: "UA123".to_string(),
+                date,
+            },
+            None,
+            None,
+        );
+
+        assert_eq!(app.tracked_flights[0].flight_date, date);
+    }
+
+    #[test]
+    fn test_queue_input_single_invalid() {
+        let mut app = App::default();
+
+        app.queue_input("HELLO");
+
+        assert!(app.pending_searches.is_empty());
+        assert!(app.last_error.is_some());
+    }
+
+    #[test]
+    fn test_queue_flights_skips_pending() {
+        let mut app = App::default();
+        app.pending_searches.push("UA123".to_string().into());
+
+        let report = app.queue_flights(
+            vec!["UA123".to_string().into(), "DL1".to_string().into()],
+            0,
+        );
+
+        assert_eq!(
+            report,
+            ImportReport {
+                added: 1,
+                duplicates: 1,
+                invalid: 0
+            }
+        );
+        assert_eq!(app.pending_searches.len(), 2);
+        assert_eq!(app.pending_searches[1].flight_number, "DL1");
+    }
+
+    #[test]
+    fn test_watchlist_mirrors_the_file() {
+        let mut app = App::default();
+        app.add_flight("LH400".to_string(), None, Some(schedule("active", 0)));
+        app.watchlist_pending = Some(watchlist::parse("BA285\nUA123\nLH400\n"));
+
+        // Held back while a refresh is under way
+        app.loading = true;
+        app.apply_watchlist();
+        assert!(app.pending_searches.is_empty());
+        app.loading = false;
+        app.apply_watchlist();
+        let queued: Vec<_> = app.take_pending_searches();
+        assert_eq!(queued.len(), 2);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Watchlist: 2 added, 0 removed")
+        );
+        for query in queued {
+            let flight_number = query.flight_number.clone();
+            app.add_flight(query, None, Some(schedule("active", 0)));
+            app.finish_search(&flight_number);
+        }
+        let from_watchlist = |app: &App| -> Vec<String> {
+            let mut numbers: Vec<String> = app
+                .tracked_flights
+                .iter()
+                .filter(|f| f.from_watchlist)
+                .map(|f| f.flight_number.clone())
+                .collect();
+            numbers.sort();
+            numbers
+        };
+        // LH400 was added by hand first, so it stays manual
+        assert_eq!(from_watchlist(&app), ["BA285", "UA123"]);
+
+        // Emptying the file drops only what it added
+        app.watchlist_pending = Some(watchlist::parse("# nothing today\n"));
+        app.apply_watchlist();
+        let numbers: Vec<&str> = app
+            .tracked_flights
+            .iter()
+            .map(|f| f.flight_number.as_str())
+            .collect();
+        assert_eq!(numbers, ["LH400"]);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Watchlist: 0 added, 2 removed")
+        );
+        assert!(app.undo_stack.is_empty());
+    }
+
+    #[test]
+    fn test_watchlist_flight_dropped_while_searching() {
+        let mut app = App {
+            watchlist_pending: Some(watchlist::parse("AF7\n")),
+            ..Default::default()
+        };
+        app.apply_watchlist();
+        let query = app.take_pending_searches().remove(0);
+
+        app.watchlist_pending = Some(Vec::new());
+        app.apply_watchlist();
+        app.add_flight(query, None, Some(schedule("active", 0)));
+        app.finish_search("AF7");
+        assert!(app.tracked_flights.is_empty());
+
+        // The flag survives a saved session
+        app.watchlist_pending = Some(watchlist::parse("AF7\n"));
+        app.apply_watchlist();
+        let query = app.take_pending_searches().remove(0);
+        app.add_flight(query, None, Some(schedule("active", 0)));
+        let session = Session::from_flights(&app.tracked_flights);
+        assert!(session.flights[0].watchlist);
+    }
+
+    #[test]
+    fn test_failed_watchlist_search_leaves_a_manual_add_manual() {
+        let mut app = App {
+            watchlist_pending: Some(watchlist::parse("AF7\nBA285\n")),
+            ..Default::default()
+        };
+        app.apply_watchlist();
+        assert_eq!(app.take_pending_searches().len(), 2);
+
+        // AF7's search fails and BA285's times out, adding neither
+        app.finish_search("AF7");
+        app.expire_searches(Instant::now() + SEARCH_TIMEOUT);
+
+        for flight_number in ["AF7", "BA285"] {
+            app.queue_input(flight_number);
+            let query = app.take_pending_searches().remove(0);
+            app.add_flight(query, None, Some(schedule("active", 0)));
+            app.finish_search(flight_number);
+        }
+        assert_eq!(app.tracked_flights.len(), 2);
+        assert!(app.tracked_flights.iter().all(|f| !f.from_watchlist));
+    }
+
+    #[test]
+    fn test_double_submit_while_searching() {
+        let mut app = App::default();
+
+        app.queue_input("UA123");
+        assert_eq!(app.take_pending_searches().len(), 1);
+
+        app.queue_input("UA123");
+        assert!(app.pending_searches.is_empty());
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Search for UA123 already running")
+        );
+
+        // Batches skip it as a duplicate
+        let report = app.queue_flights(
+            vec![
+                FlightQuery::from("UA123".to_string()),
+                FlightQuery::from("BA285".to_string()),
+            ],
+            0,
+        );
+        assert_eq!(report.duplicates, 1);
+        assert_eq!(app.take_pending_searches().len(), 1);
+
+        app.finish_search("UA123");
+        app.queue_input("UA123");
+        assert_eq!(app.pending_searches.len(), 1);
+    }
+
+    #[test]
+    fn test_stale_searches_expire() {
+        let mut app = App::default();
+        app.queue_input("UA123");
+        app.take_pending_searches();
+
+        app.loading = true;
+        app.expire_searches(Instant::now());
+        assert!(app.loading);
+
+        app.expire_searches(Instant::now() + SEARCH_TIMEOUT);
+        assert!(!app.loading);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Search timed out for UA123 — press r to retry")
+        );
+        app.queue_input("UA123");
+        assert_eq!(app.pending_searches.len(), 1);
+        // Searching it by hand means r has nothing left to retry
+        app.take_pending_searches();
+        assert!(app.timed_out_searches.is_empty());
     }
 
-    pub fn remove_selected_flight(&mut self) {
-        if let Some(index) = self.selected_index {
-            if index < self.tracked_flights.len() {
-                self.tracked_flights.remove(index);
-                if self.tracked_flights.is_empty() {
-                    self.selected_index = None;
-                } else if index >= self.tracked_flights.len() {
-                    self.selected_index = Some(self.tracked_flights.len() - 1);
-                }
-            }
-        }
+    #[test]
+    fn test_import_missing_file() {
+        let mut app = App::default();
+
+        app.import_file(Path::new("/nonexistent/trips.csv"));
+
+        assert!(app.last_error.is_some());
+        assert!(app.pending_searches.is_empty());
     }
 
-    pub fn add_flight(
-        &mut self,
-        flight_number: String,
-        state: Option<StateVector>,
-        schedule: Option<FlightData>,
-    ) {
-        if self
-            .tracked_flights
-            .iter()
-            .any(|f| f.flight_number == flight_number)
-        {
-            self.status_message = Some(format!("Flight {} is already tracked", flight_number));
-            return;
+    fn env(vars: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        move |key| {
+            vars.iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.to_string())
         }
+    }
 
-        let mut flight = Flight {
-            flight_number: flight_number.clone(),
-            status: FlightStatus::NotFound,
-            last_updated: Some(Utc::now()),
+    #[test]
+    fn test_apply_returns_effects() {
+        let mut app = App {
+            mode: AppMode::Viewing,
             ..Default::default()
         };
+        // Nothing to refresh yet
+        assert!(app.apply(Action::Refresh).is_empty());
+        assert_eq!(
+            app.apply(Action::ToggleDiagnostics),
+            vec![Effect::OpenDiagnostics]
+        );
+        assert_eq!(app.apply(Action::ExportReport), vec![Effect::ExportReport]);
 
-        // Apply schedule data first (from AviationStack)
-        if let Some(sched) = schedule {
-            apply_schedule_data(&mut flight, sched);
-        }
+        let mut app = app_with_statuses(&[FlightStatus::EnRoute, FlightStatus::Scheduled]);
+        app.mode = AppMode::Viewing;
+        assert!(matches!(
+            app.apply(Action::Refresh)[..],
+            [Effect::StartRefresh(_)]
+        ));
 
-        // Apply live position data (from OpenSky) - this may override status
-        if let Some(sv) = state {
-            apply_position_data(&mut flight, sv);
+        app.diagnostics = Some(Diagnostics {
+            providers: vec![],
+            data_dir: None,
+            config_path: None,
+            log_path: None,
+        });
+        assert!(app
+            .apply(Action::RequestCacheClear(CacheKind::Schedule))
+            .is_empty());
+        assert_eq!(
+            app.apply(Action::AnswerCacheClear(true)),
+            vec![Effect::ClearCache(CacheKind::Schedule)]
+        );
+        assert!(app.apply(Action::ToggleDiagnostics).is_empty());
+        assert!(app.diagnostics.is_none());
+
+        app.apply(Action::BeginInput);
+        for c in "DL1".chars() {
+            app.apply(Action::InsertChar(c));
         }
+        assert_eq!(app.apply(Action::Submit), vec![Effect::StartSearches]);
+        assert_eq!(app.pending_searches[0].flight_number, "DL1");
 
-        // Build route string for history
-        let route = match (&flight.origin, &flight.destination) {
-            (Some(orig), Some(dest)) => {
-                let orig_code = orig.iata.as_deref().or(orig.icao.as_deref()).unwrap_or("???");
-                let dest_code = dest.iata.as_deref().or(dest.icao.as_deref()).unwrap_or("???");
-                Some(format!("{}→{}", orig_code, dest_code))
+        app.apply(Action::Quit);
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn test_command_palette_runs_actions() {
+        let mut app = App {
+            mode: AppMode::Viewing,
+            ..Default::default()
+        };
+        let run = |app: &mut App, line: &str| {
+            app.apply(Action::BeginCommand);
+            for c in line.chars() {
+                app.apply(Action::InsertChar(c));
             }
-            _ => None,
+            app.apply(Action::Submit)
         };
 
-        // Add to history and save
-        self.history.add(flight_number, route);
-        self.history.save();
+        assert_eq!(run(&mut app, "exp"), vec![Effect::ExportReport]);
+        assert_eq!(app.mode, AppMode::Viewing);
+        assert!(app.input_buffer.is_empty());
 
-        self.tracked_flights.push(flight);
-        self.selected_index = Some(self.tracked_flights.len() - 1);
-    }
+        assert_eq!(
+            run(&mut app, "clear cache position"),
+            vec![Effect::ClearCache(CacheKind::Position)]
+        );
+        assert!(run(&mut app, "set interval 60").is_empty());
+        assert_eq!(app.config.min_refresh_secs, 60);
+        run(&mut app, "set columns flight,squawk,vertical_rate");
+        assert_eq!(
+            app.config.table.columns,
+            ["flight", "squawk", "vertical_rate"]
+        );
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("List and report columns: Flight, Squawk, V/S")
+        );
 
-    pub fn update_flight(&mut self, flight_number: &str, state: Option<StateVector>) {
-        if let Some(flight) = self
-            .tracked_flights
-            .iter_mut()
-            .find(|f| f.flight_number == flight_number)
-        {
-            if let Some(sv) = state {
-                apply_position_data(flight, sv);
-            }
-            flight.last_updated = Some(Utc::now());
-        }
-    }
+        // Actions that open a prompt leave the palette for it
+        run(&mut app, "import");
+        assert_eq!(app.mode, AppMode::ImportInput);
+        app.apply(Action::CancelInput);
 
-    pub fn should_update(&self) -> bool {
-        if self.tracked_flights.is_empty() || self.loading {
-            return false;
-        }
+        // A mistake stays in the palette to be fixed
+        assert!(run(&mut app, "set interval soon").is_empty());
+        assert_eq!(app.mode, AppMode::Command);
+        assert!(app.last_error.as_deref().unwrap().contains("soon"));
+        app.apply(Action::CancelInput);
+        assert_eq!(app.mode, AppMode::Viewing);
 
-        match self.last_api_call {
-            Some(last) => last.elapsed().as_secs() >= self.update_interval_secs,
-            None => true,
-        }
+        // Tab fills in the best match
+        app.apply(Action::BeginCommand);
+        app.apply(Action::InsertChar('s'));
+        app.apply(Action::CompleteCommand);
+        assert_eq!(app.input_buffer, "set interval ");
+        assert_eq!(app.cursor_position, app.input_buffer.len());
     }
 
-    pub fn seconds_until_update(&self) -> Option<u64> {
-        self.last_api_call.map(|last| {
-            let elapsed = last.elapsed().as_secs();
-            self.update_interval_secs.saturating_sub(elapsed)
-        })
-    }
-}
+    #[test]
+    fn test_arrival_notice_fires_once_and_follows_the_eta() {
+        let at = |hm: &str| {
+            DateTime::parse_from_rfc3339(&format!("2024-07-14T{}:00+00:00", hm)).unwrap()
+        };
+        let mut app = app_with_statuses(&[FlightStatus::EnRoute, FlightStatus::EnRoute]);
+        app.tracked_flights[0].arrival_scheduled = Some(at("12:00"));
+        let now = |hm: &str| at(hm).to_utc();
 
-fn apply_position_data(flight: &mut Flight, sv: StateVector) {
-    const METERS_TO_FEET: f64 = 3.28084;
-    const MPS_TO_KNOTS: f64 = 1.94384;
+        // Armed 20 minutes before the ETA
+        assert_eq!(
+            app.arrival_notice(&app.tracked_flights[0]),
+            Some((now("11:40"), false))
+        );
+        assert_eq!(app.arrival_notice(&app.tracked_flights[1]), None);
+        app.check_arrival_notices(now("11:39"));
+        assert!(app.pending_alerts.is_empty());
 
-    flight.callsign = sv.callsign.unwrap_or_default();
-    flight.icao24 = sv.icao24;
-    flight.latitude = sv.latitude;
-    flight.longitude = sv.longitude;
-    flight.altitude_ft = sv.baro_altitude.map(|a| a * METERS_TO_FEET);
-    flight.heading = sv.true_track;
-    flight.vertical_rate = sv.vertical_rate.map(|v| v * METERS_TO_FEET * 60.0);
-    flight.ground_speed_kts = sv.velocity.map(|v| v * MPS_TO_KNOTS);
-    flight.on_ground = sv.on_ground;
-    flight.squawk = sv.squawk;
+        // A later estimate moves it back
+        app.tracked_flights[0].arrival_estimated = Some(at("12:30"));
+        app.check_arrival_notices(now("11:45"));
+        assert!(app.pending_alerts.is_empty());
 
-    // Update status based on live position
-    if sv.on_ground {
-        flight.status = FlightStatus::OnGround;
-    } else {
-        flight.status = FlightStatus::EnRoute;
-    }
-}
+        app.check_arrival_notices(now("12:12"));
+        assert_eq!(app.pending_alerts, vec!["FL0 arriving in 18m".to_string()]);
+        assert_eq!(app.alert_message.as_deref(), Some("FL0 arriving in 18m"));
+        assert_eq!(
+            app.arrival_notice(&app.tracked_flights[0]),
+            Some((now("12:10"), true))
+        );
 
-fn apply_schedule_data(flight: &mut Flight, data: FlightData) {
-    // Status
-    if let Some(status) = &data.flight_status {
-        flight.status = FlightStatus::from_api_status(status);
+        // At most once, even if the ETA shifts again
+        app.tracked_flights[0].arrival_estimated = Some(at("12:25"));
+        app.check_arrival_notices(now("12:15"));
+        assert_eq!(app.pending_alerts.len(), 1);
     }
 
-    // Airline
-    if let Some(airline) = &data.airline {
-        flight.airline = airline.name.clone();
-    }
+    #[test]
+    fn test_arrival_notice_skips_late_and_disabled() {
+        let at = |hm: &str| {
+            DateTime::parse_from_rfc3339(&format!("2024-07-14T{}:00+00:00", hm)).unwrap()
+        };
+        let mut app = app_with_statuses(&[FlightStatus::EnRoute]);
+        app.tracked_flights[0].arrival_scheduled = Some(at("12:00"));
 
-    // Aircraft
-    if let Some(aircraft) = &data.aircraft {
-        flight.aircraft_type = aircraft.iata.clone().or(aircraft.icao.clone());
-        flight.registration = aircraft.registration.clone();
+        // Started after the ETA had passed
+        app.check_arrival_notices(at("12:05").to_utc());
+        assert!(app.pending_alerts.is_empty());
+
+        app.config.arrival_notice_mins = 0;
+        assert_eq!(app.arrival_notice(&app.tracked_flights[0]), None);
+        app.check_arrival_notices(at("11:55").to_utc());
+        assert!(app.pending_alerts.is_empty());
     }
 
-    // Origin airport
-    if let Some(dep) = &data.departure {
-        flight.origin = Some(Airport {
-            name: dep.airport.clone(),
-            iata: dep.iata.clone(),
-            icao: dep.icao.clone(),
-        });
-        flight.departure_scheduled = dep.scheduled.clone();
-        flight.departure_estimated = dep.estimated.clone();
-        flight.departure_actual = dep.actual.clone();
-        flight.departure_delay = dep.delay;
+    #[test]
+    fn test_skipping_onboarding() {
+        let dir = std::env::temp_dir().join(format!("onboarding-skip-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // Keys already set mean it isn't a fresh install
+        let mut app = App::default();
+        app.capabilities.schedules = true;
+        app.begin_onboarding(dir.clone());
+        assert_eq!(app.mode, AppMode::Input);
+        assert!(app.onboarding_dir.is_none());
+
+        let mut app = App::default();
+        app.begin_onboarding(dir.clone());
+        assert_eq!(app.mode, AppMode::Onboarding);
+        app.finish_onboarding(false);
+        assert_eq!(app.mode, AppMode::Input);
+        assert!(app.input_buffer.is_empty());
+        flight_tracker_tui::persist::flush();
+        assert!(dir.join(onboarding::MARKER_FILE).exists());
+        // Nothing but the marker was written
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        let _ = std::fs::remove_dir_all(dir);
     }
 
-    // Destination airport
-    if let Some(arr) = &data.arrival {
-        flight.destination = Some(Airport {
-            name: arr.airport.clone(),
-            iata: arr.iata.clone(),
-            icao: arr.icao.clone(),
-        });
-        flight.arrival_scheduled = arr.scheduled.clone();
-        flight.arrival_estimated = arr.estimated.clone();
-        flight.arrival_actual = arr.actual.clone();
-        flight.arrival_delay = arr.delay;
+    #[test]
+    fn test_capabilities_from_env() {
+        let none = Capabilities::from_lookup(env(&[]));
+        assert!(!none.schedules && !none.opensky_authenticated);
+        assert_eq!(none.warnings().len(), 2);
+        assert!(none.warnings()[0].contains("AVIATIONSTACK_API_KEY"));
+        assert!(none.banner().unwrap().ends_with("(see .env)"));
+
+        // A username without a password is still anonymous, and says so;
+        // empty values count as unset
+        let partial = Capabilities::from_lookup(env(&[
+            ("AVIATIONSTACK_API_KEY", "  "),
+            ("OPENSKY_USERNAME", "pilot"),
+            ("OPENSKY_PASSWORD", ""),
+        ]));
+        assert!(!partial.schedules && !partial.opensky_authenticated);
+        assert_eq!(partial.opensky_missing, Some("OPENSKY_PASSWORD"));
+        assert!(partial.warnings()[1].starts_with("OPENSKY_USERNAME is set without"));
+        let partial = Capabilities::from_lookup(env(&[("OPENSKY_PASSWORD", "secret")]));
+        assert!(partial.warnings()[1].starts_with("OPENSKY_PASSWORD is set without"));
+
+        let all = Capabilities::from_lookup(env(&[
+            ("AVIATIONSTACK_API_KEY", "key"),
+            ("OPENSKY_USERNAME", "pilot"),
+            ("OPENSKY_PASSWORD", "secret"),
+        ]));
+        assert!(all.schedules && all.opensky_authenticated);
+        assert!(all.warnings().is_empty());
+        assert_eq!(all.banner(), None);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_demo_needs_no_setup() {
+        assert!(Capabilities::demo().warnings().is_empty());
+    }
 
     #[test]
-    fn test_app_default() {
-        let app = App::default();
+    fn test_background_interval() {
+        let mut app = App::default();
+        app.add_flight("UA123".to_string(), None, None);
+        app.tracked_flights[0].icao24 = "a808c4".to_string();
+        let flight = app.tracked_flights[0].clone();
+        assert_eq!(app.flight_refresh_interval(&flight), Some(30));
 
-        assert_eq!(app.mode, AppMode::Input);
-        assert!(!app.should_quit);
-        assert!(app.input_buffer.is_empty());
-        assert!(app.tracked_flights.is_empty());
-        assert!(app.selected_index.is_none());
-        assert!(!app.loading);
+        app.set_focused(false);
+        assert_eq!(app.flight_refresh_interval(&flight), Some(120));
+
+        app.background_multiplier = 0;
+        assert_eq!(app.flight_refresh_interval(&flight), None);
+        assert!(app.seconds_until_update().is_none());
     }
 
     #[test]
-    fn test_input_char() {
+    fn test_daily_budget_stretches_then_stops_refreshing() {
+        use chrono::TimeZone;
+
         let mut app = App::default();
+        app.add_flight("UA123".to_string(), None, None);
+        app.tracked_flights[0].icao24 = "a808c4".to_string();
+        let flight = app.tracked_flights[0].clone();
+        let afternoon = Local.with_ymd_and_hms(2024, 7, 14, 14, 0, 0).unwrap();
 
-        app.input_char('U');
-        app.input_char('A');
-        app.input_char('1');
+        // Unmetered providers are not budgeted
+        app.update_budget(None, afternoon);
+        assert_eq!(app.budget, None);
+        assert_eq!(app.flight_refresh_interval(&flight), Some(30));
 
-        assert_eq!(app.input_buffer, "UA1");
-        assert_eq!(app.cursor_position, 3);
+        // 400 credits over the 10 hours to midnight
+        app.update_budget(Some(0), afternoon);
+        assert_eq!(app.budget, Some(BudgetPlan::Interval(90)));
+        assert_eq!(app.flight_refresh_interval(&flight), Some(90));
+
+        // A generous budget never shortens the phase interval
+        app.config.opensky_daily_credits = Some(4000);
+        app.update_budget(Some(0), afternoon);
+        assert_eq!(app.flight_refresh_interval(&flight), Some(30));
+
+        // Unset, the budget follows the access in effect
+        app.config.opensky_daily_credits = None;
+        let opensky = |auth| {
+            vec![ProviderHealth {
+                name: "OpenSky",
+                auth: Some(auth),
+                ..Default::default()
+            }]
+        };
+        app.set_provider_health(opensky(AuthMode::Basic));
+        app.update_budget(Some(400), afternoon);
+        assert_eq!(app.flight_refresh_interval(&flight), Some(30));
+        app.set_provider_health(opensky(AuthMode::Anonymous));
+        app.update_budget(Some(400), afternoon);
+        assert_eq!(app.budget, Some(BudgetPlan::Exhausted));
+        assert_eq!(app.flight_refresh_interval(&flight), None);
+        assert!(!app.should_update());
+        // A manual refresh is still possible
+        assert_eq!(app.refreshable_flights(), vec![0]);
     }
 
     #[test]
-    fn test_input_backspace() {
+    fn test_degraded_provider_is_announced_once() {
+        let health = |degraded| {
+            vec![ProviderHealth {
+                name: "AviationStack",
+                role: "schedule",
+                degraded,
+                ..Default::default()
+            }]
+        };
         let mut app = App::default();
+        app.set_provider_health(health(false));
+        assert_eq!(app.status_message, None);
 
-        app.input_char('U');
-        app.input_char('A');
-        app.input_backspace();
-
-        assert_eq!(app.input_buffer, "U");
-        assert_eq!(app.cursor_position, 1);
+        app.set_provider_health(health(true));
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("AviationStack: schedule provider degraded, trying again shortly")
+        );
+        // Later ticks don't repeat it
+        app.status_message = None;
+        app.set_provider_health(health(true));
+        assert_eq!(app.status_message, None);
 
-        // Backspace on empty should do nothing
-        app.input_backspace();
-        app.input_backspace();
-        assert_eq!(app.input_buffer, "");
-        assert_eq!(app.cursor_position, 0);
+        app.set_provider_health(health(false));
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("AviationStack: schedule provider recovered")
+        );
     }
 
     #[test]
-    fn test_submit_input() {
+    fn test_rejected_credentials_are_announced_once() {
+        let health = |auth| {
+            vec![ProviderHealth {
+                name: "OpenSky",
+                role: "position",
+                auth: Some(auth),
+                ..Default::default()
+            }]
+        };
         let mut app = App::default();
+        // Anonymous without credentials is what the startup banner covers
+        app.set_provider_health(health(AuthMode::Anonymous));
+        assert_eq!(app.status_message, None);
 
-        app.input_char('u');
-        app.input_char('a');
-        app.input_char('1');
-        app.input_char('2');
-        app.input_char('3');
+        let mut app = App::default();
+        app.capabilities.opensky_authenticated = true;
+        app.set_provider_health(health(AuthMode::Basic));
+        assert_eq!(app.status_message, None);
+        app.set_provider_health(health(AuthMode::Anonymous));
+        assert!(app.status_message.as_deref().is_some_and(
+            |m| m.contains("credentials rejected") && m.contains("OPENSKY_USERNAME/PASSWORD")
+        ));
 
-        let result = app.submit_input();
+        app.status_message = None;
+        app.set_provider_health(health(AuthMode::Anonymous));
+        assert_eq!(app.status_message, None);
 
-        assert_eq!(result, Some("UA123".to_string())); // Should be uppercased
-        assert!(app.input_buffer.is_empty());
-        assert_eq!(app.cursor_position, 0);
+        // Rejected before the first tick
+        let mut app = App::default();
+        app.capabilities.opensky_authenticated = true;
+        app.set_provider_health(health(AuthMode::Anonymous));
+        assert!(app.status_message.is_some());
 
-        // Submit on empty should return None
-        assert_eq!(app.submit_input(), None);
+        // Degraded on the same tick: both are told
+        let mut app = App::default();
+        app.capabilities.opensky_authenticated = true;
+        app.set_provider_health(health(AuthMode::Basic));
+        let mut rejected = health(AuthMode::Anonymous);
+        rejected[0].degraded = true;
+        app.set_provider_health(rejected);
+        let message = app.status_message.unwrap();
+        assert!(
+            message.starts_with("OpenSky: credentials rejected"),
+            "{message}"
+        );
+        assert!(message.ends_with("; position provider degraded, trying again shortly"));
+        assert!(app.provider_health[0].degraded);
     }
 
     #[test]
-    fn test_add_flight() {
-        let mut app = App::default();
-
+    fn test_paused_in_background_does_not_update() {
+        let mut app = App {
+            background_multiplier: 0,
+            ..Default::default()
+        };
         app.add_flight("UA123".to_string(), None, None);
+        app.tracked_flights[0].icao24 = "a808c4".to_string();
+        app.last_polled.clear();
 
-        assert_eq!(app.tracked_flights.len(), 1);
-        assert_eq!(app.tracked_flights[0].flight_number, "UA123");
-        assert_eq!(app.tracked_flights[0].status, FlightStatus::NotFound);
-        assert_eq!(app.selected_index, Some(0));
+        app.set_focused(false);
+        assert!(!app.should_update());
+
+        app.set_focused(true);
+        assert!(app.should_update());
     }
 
     #[test]
-    fn test_add_duplicate_flight() {
+    fn test_focus_regained_requests_refresh() {
         let mut app = App::default();
 
+        // No flights: nothing to refresh
+        app.set_focused(false);
+        assert!(!app.set_focused(true));
+
         app.add_flight("UA123".to_string(), None, None);
+        assert!(!app.set_focused(true)); // Already focused
+        app.set_focused(false);
+        assert!(app.set_focused(true));
+    }
+
+    #[test]
+    fn test_retrack_input_prefills_selected_flight() {
+        let mut app = App::default();
+        app.begin_retrack_input();
+        assert_eq!(app.mode, AppMode::Input);
+        assert!(app.input_buffer.is_empty());
+
         app.add_flight("UA123".to_string(), None, None);
+        app.mode = AppMode::Viewing;
+        app.begin_retrack_input();
+        assert_eq!(app.mode, AppMode::Input);
+        assert_eq!(app.input_buffer, "UA123");
+        assert_eq!(app.cursor_position, 5);
 
-        assert_eq!(app.tracked_flights.len(), 1);
-        assert!(app.status_message.is_some());
+        // Submitting unchanged hits the normal duplicate check
+        let input = app.submit_input().unwrap();
+        app.queue_input(&input);
+        assert!(app.pending_searches.is_empty());
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Flight is already tracked")
+        );
     }
 
     #[test]
-    fn test_select_next_previous() {
+    fn test_return_input_guesses_but_never_searches() {
         let mut app = App::default();
+        app.add_flight("LH400".to_string(), None, None);
+        app.mode = AppMode::Viewing;
+        // Without a route there is nothing to reverse
+        app.apply(Action::BeginReturnInput);
+        assert_eq!(app.mode, AppMode::Viewing);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("LH400 has no known route to reverse")
+        );
 
-        app.add_flight("UA123".to_string(), None, None);
-        app.add_flight("BA285".to_string(), None, None);
-        app.add_flight("AF007".to_string(), None, None);
+        app.tracked_flights.clear();
+        app.add_flight("LH400".to_string(), None, Some(schedule("scheduled", 0)));
+        app.tracked_flights[0].airline = Some("Lufthansa".to_string());
+        app.mode = AppMode::Viewing;
+        assert!(app.apply(Action::BeginReturnInput).is_empty());
+        assert_eq!(app.mode, AppMode::Input);
+        assert_eq!(app.input_buffer, "LH401");
+        let prompt = app.return_prompt.as_ref().unwrap();
+        assert_eq!(prompt.of, "LH400");
+        assert_eq!((prompt.from.as_str(), prompt.to.as_str()), ("FRA", "SFO"));
+        assert!(app.pending_searches.is_empty());
 
-        assert_eq!(app.selected_index, Some(2)); // Last added is selected
+        app.apply(Action::NextReturnGuess);
+        assert_eq!(app.input_buffer, "LH399");
+        app.apply(Action::NextReturnGuess);
+        assert_eq!(app.input_buffer, "LH401");
 
-        app.select_previous();
-        assert_eq!(app.selected_index, Some(1));
+        // Esc drops the guess; confirming it runs the normal search
+        app.apply(Action::CancelInput);
+        assert!(app.return_prompt.is_none() && app.pending_searches.is_empty());
+        app.apply(Action::BeginReturnInput);
+        assert_eq!(app.apply(Action::Submit), vec![Effect::StartSearches]);
+        assert_eq!(app.pending_searches[0].flight_number, "LH401");
+        assert!(app.return_prompt.is_none());
+    }
 
-        app.select_previous();
-        assert_eq!(app.selected_index, Some(0));
+    #[test]
+    fn test_raw_view_keeps_last_payloads() {
+        let mut app = App::default();
+        app.add_flight("UA900".to_string(), Some(position(50.0, 8.0)), None);
+        app.update_schedule("UA900", schedule("active", 0));
+        let flight = &app.tracked_flights[0];
+        assert!(flight
+            .raw_state
+            .as_deref()
+            .unwrap()
+            .starts_with("[\n  \"a808c4\","));
+        assert!(flight
+            .raw_schedule
+            .as_deref()
+            .unwrap()
+            .contains("\"flight_status\": \"active\""));
 
-        app.select_previous(); // Should wrap to end
-        assert_eq!(app.selected_index, Some(2));
+        app.apply(Action::ToggleRawView);
+        assert_eq!((app.right_panel, app.panel_scroll), (RightPanel::Raw, 0));
+        app.apply(Action::ScrollPanel(10));
+        assert_eq!(app.panel_scroll, 10);
+        app.apply(Action::ScrollPanel(-20));
+        assert_eq!(app.panel_scroll, 0);
+        // No further than the last line
+        app.apply(Action::ScrollPanel(i16::MAX));
+        let lines = app.tracked_flights[0]
+            .raw_state
+            .as_deref()
+            .unwrap()
+            .lines()
+            .count()
+            + app.tracked_flights[0]
+                .raw_schedule
+                .as_deref()
+                .unwrap()
+                .lines()
+                .count();
+        assert_eq!(app.panel_scroll, lines as u16);
 
-        app.select_next(); // Should wrap to beginning
-        assert_eq!(app.selected_index, Some(0));
+        app.apply(Action::SelectNext);
+        assert_eq!((app.right_panel, app.panel_scroll), (RightPanel::Raw, 0));
+        app.apply(Action::ToggleRawView);
+        assert_eq!(app.right_panel, RightPanel::Details);
+        // The details don't scroll
+        app.apply(Action::ScrollPanel(10));
+        assert_eq!(app.panel_scroll, 0);
     }
 
     #[test]
-    fn test_select_on_empty_list() {
+    fn test_repeat_last_search() {
         let mut app = App::default();
+        app.repeat_last_search();
+        assert!(app.pending_searches.is_empty());
+        assert!(app.status_message.is_some());
 
-        app.select_next();
-        app.select_previous();
+        app.history.add("UA123".to_string(), None);
+        app.history.add("BA285".to_string(), None);
+        app.status_message = None;
+        app.repeat_last_search();
+        assert_eq!(app.pending_searches.len(), 1);
+        assert_eq!(app.pending_searches[0].flight_number, "BA285");
 
-        assert!(app.selected_index.is_none());
+        // Already queued: not queued twice
+        app.repeat_last_search();
+        assert_eq!(app.pending_searches.len(), 1);
     }
 
     #[test]
-    fn test_remove_selected_flight() {
+    fn test_toggle_time_format() {
         let mut app = App::default();
+        assert_eq!(app.config.time_format, TimeFormat::TwentyFourHour);
 
-        app.add_flight("UA123".to_string(), None, None);
-        app.add_flight("BA285".to_string(), None, None);
+        app.toggle_time_format();
+        assert_eq!(app.config.time_format, TimeFormat::TwelveHour);
+        assert_eq!(app.status_message.as_deref(), Some("Showing 12-hour times"));
+        app.toggle_time_format();
+        assert_eq!(app.config.time_format, TimeFormat::TwentyFourHour);
+    }
 
-        app.selected_index = Some(0);
-        app.remove_selected_flight();
+    #[test]
+    fn test_settings_overlay() {
+        let mut app = App::default();
+        app.apply(Action::OpenSettings);
+        assert_eq!(app.mode, AppMode::Settings);
 
-        assert_eq!(app.tracked_flights.len(), 1);
-        assert_eq!(app.tracked_flights[0].flight_number, "BA285");
-        assert_eq!(app.selected_index, Some(0));
+        // The first row is the refresh interval, from 10s to 15s and back
+        app.apply(Action::ChangeSetting { backward: false });
+        assert_eq!(app.config.min_refresh_secs, 15);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Refresh interval: at most every 15s")
+        );
+        app.apply(Action::MoveSettingsCursor(-1));
+        assert_eq!(app.settings_cursor, 0);
+        app.apply(Action::ChangeSetting { backward: true });
+        assert_eq!(app.config.min_refresh_secs, 10);
+
+        let clock = settings::SETTINGS
+            .iter()
+            .position(|s| s.key == "time_format")
+            .unwrap();
+        app.apply(Action::MoveSettingsCursor(clock as i16));
+        app.apply(Action::ChangeSetting { backward: false });
+        assert_eq!(app.config.time_format, TimeFormat::TwelveHour);
+        app.apply(Action::MoveSettingsCursor(100));
+        assert_eq!(app.settings_cursor, settings::SETTINGS.len() - 1);
+
+        // Each key once, with its latest value
+        assert_eq!(
+            app.settings_changed,
+            [
+                ("min_refresh_secs", "10".to_string()),
+                ("time_format", "\"12h\"".to_string())
+            ]
+        );
+        app.apply(Action::CloseSettings);
+        assert_eq!(app.mode, AppMode::Viewing);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Settings kept for this session only")
+        );
     }
 
     #[test]
-    fn test_remove_last_flight() {
+    fn test_clock_from_settings_replaces_the_t_choice() {
         let mut app = App::default();
+        app.toggle_time_format();
+        assert_eq!(app.ui_state().time_format, Some(TimeFormat::TwelveHour));
+        app.ui_changed_at = None;
 
-        app.add_flight("UA123".to_string(), None, None);
-        app.remove_selected_flight();
+        app.set_time_format(TimeFormat::TwentyFourHour);
+        assert_eq!(app.config.time_format, TimeFormat::TwentyFourHour);
+        assert_eq!(app.ui_state().time_format, None);
+        assert!(app.ui_changed_at.is_some());
+    }
 
-        assert!(app.tracked_flights.is_empty());
-        assert!(app.selected_index.is_none());
+    #[test]
+    fn test_quitting_closes_settings() {
+        let mut app = App::default();
+        app.apply(Action::OpenSettings);
+        app.apply(Action::Quit);
+        assert!(app.should_quit);
+        assert_eq!(app.mode, AppMode::Viewing);
     }
 
     #[test]
-    fn test_should_update() {
+    fn test_feed_lag_warning() {
         let mut app = App::default();
+        let now = DateTime::from_timestamp(1_700_000_100, 0).unwrap();
+        assert!(app.lagging_feed().is_none());
 
-        // Empty list should not update
-        assert!(!app.should_update());
+        app.record_feed_time_at(1_700_000_090, now);
+        assert_eq!(app.feed_time.unwrap().lag_secs, 10);
+        assert!(app.lagging_feed().is_none());
 
-        app.add_flight("UA123".to_string(), None, None);
+        // A stale snapshot arriving late doesn't replace a fresher one
+        app.record_feed_time_at(1_700_000_000, now);
+        assert_eq!(app.feed_time.unwrap().lag_secs, 10);
 
-        // With flights but no last call, should update
-        assert!(app.should_update());
+        // The feed stalls: newer snapshot, but well behind the clock
+        let later = now + chrono::Duration::seconds(120);
+        app.record_feed_time_at(1_700_000_095, later);
+        let feed = app.lagging_feed().unwrap();
+        assert_eq!(feed.lag_secs, 125);
+        assert_eq!(feed.snapshot.timestamp(), 1_700_000_095);
+    }
 
-        // While loading, should not update
-        app.loading = true;
-        assert!(!app.should_update());
+    #[test]
+    fn test_fix_is_dated_by_the_snapshot_it_came_in() {
+        let mut app = App::default();
+        let fix = |time_position| StateVector {
+            time_position: Some(time_position),
+            ..position(50.0, 8.0)
+        };
+        app.add_flight("UA900".to_string(), Some(fix(1_700_000_000)), None);
+        app.record_feed_time("UA900", 1_700_000_010);
+        let snapshot = |app: &App| app.tracked_flights[0].snapshot_time.unwrap().timestamp();
+        assert_eq!(snapshot(&app), 1_700_000_010);
+
+        // A snapshot without the aircraft says nothing about the old fix
+        app.update_flight("UA900", None);
+        app.record_feed_time("UA900", 1_700_000_020);
+        assert_eq!(snapshot(&app), 1_700_000_010);
+
+        app.update_flight("UA900", Some(fix(1_700_000_025)));
+        app.record_feed_time("UA900", 1_700_000_030);
+        assert_eq!(snapshot(&app), 1_700_000_030);
     }
 
     #[test]
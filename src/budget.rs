@@ -0,0 +1,70 @@
+//! Spreading OpenSky's daily credit allowance over the rest of the day.
+//!
+//! Every tracked flight costs one request per refresh, so the credits left
+//! divided by the number of flights gives the refresh cycles still
+//! affordable today. Spacing those cycles evenly until midnight gives the
+//! shortest interval that doesn't run dry early.
+
+/// How often flights can be refreshed without exceeding the daily budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetPlan {
+    /// Refresh each flight at most once per this many seconds. 0 means the
+    /// budget doesn't hold refreshing back.
+    Interval(u64),
+    /// Not enough credits for another refresh of every flight today.
+    Exhausted,
+}
+
+/// Plan refreshes so `remaining_credits` last the `hours_left` until
+/// midnight when each refresh costs one credit per tracked flight.
+///
+/// ```
+/// use flight_tracker_tui::budget::{plan, BudgetPlan};
+///
+/// // 100 cycles of 2 flights spread over 10 hours
+/// assert_eq!(plan(200, 10.0, 2), BudgetPlan::Interval(360));
+/// assert_eq!(plan(1, 10.0, 2), BudgetPlan::Exhausted);
+/// ```
+pub fn plan(remaining_credits: u32, hours_left: f64, flights: usize) -> BudgetPlan {
+    if flights == 0 {
+        return BudgetPlan::Interval(0);
+    }
+    let cycles = remaining_credits as usize / flights;
+    if cycles == 0 {
+        return BudgetPlan::Exhausted;
+    }
+    let secs_left = hours_left.max(0.0) * 3600.0;
+    BudgetPlan::Interval((secs_left / cycles as f64).ceil() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval_stretches_with_more_flights() {
+        assert_eq!(plan(400, 8.0, 1), BudgetPlan::Interval(72));
+        assert_eq!(plan(400, 8.0, 4), BudgetPlan::Interval(288));
+        // Leftover credits that can't pay for a whole cycle are not counted
+        assert_eq!(plan(401, 8.0, 4), BudgetPlan::Interval(288));
+    }
+
+    #[test]
+    fn test_interval_shrinks_towards_midnight() {
+        assert_eq!(plan(100, 1.0, 1), BudgetPlan::Interval(36));
+        assert_eq!(plan(100, 0.0, 1), BudgetPlan::Interval(0));
+        assert_eq!(plan(100, -1.0, 1), BudgetPlan::Interval(0));
+    }
+
+    #[test]
+    fn test_exhausted_when_a_cycle_is_unaffordable() {
+        assert_eq!(plan(0, 5.0, 1), BudgetPlan::Exhausted);
+        assert_eq!(plan(2, 5.0, 3), BudgetPlan::Exhausted);
+        assert_eq!(plan(3, 5.0, 3), BudgetPlan::Interval(18_000));
+    }
+
+    #[test]
+    fn test_no_flights_costs_nothing() {
+        assert_eq!(plan(0, 5.0, 0), BudgetPlan::Interval(0));
+    }
+}
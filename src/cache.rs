@@ -1,15 +1,162 @@
+//! In-memory and on-disk TTL caches used by the API clients.
+//!
+//! Both caches offer `get_or_fetch`, which answers from the cache or runs
+//! the given fetch and stores its result. Concurrent misses for one key
+//! share a single fetch, and a failed fetch stores nothing. Entries the
+//! cache was told are negative (a lookup that found nothing) can be given a
+//! shorter TTL of their own with `with_negative_ttl`.
+
 use std::collections::HashMap;
 use std::fs;
+use std::future::Future;
 use std::path::PathBuf;
-use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+use tracing::debug;
+
+use crate::paths::data_dir;
+use crate::persist;
+
+/// How a cache has been used this session, for the diagnostics overlay.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Entries held now, including expired ones not yet evicted.
+    pub entries: usize,
+    /// Lookups answered from the cache.
+    pub hits: u64,
+    /// Lookups that found nothing, or only an expired entry.
+    pub misses: u64,
+    pub inserts: u64,
+}
+
+/// Lookup and insert counts shared by a cache's clones.
+#[derive(Debug, Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    inserts: AtomicU64,
+}
+
+impl Counters {
+    /// Count a lookup and pass its result through.
+    fn lookup<T>(&self, found: Option<T>) -> Option<T> {
+        let counter = if found.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+
+    fn insert(&self) {
+        self.inserts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn stats(&self, entries: usize) -> CacheStats {
+        CacheStats {
+            entries,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            inserts: self.inserts.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Fetches in progress, one lock per key, so a second miss for a key waits
+/// for the first fetch rather than making its own.
+#[derive(Debug, Default)]
+struct InFlight {
+    locks: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+}
+
+impl InFlight {
+    /// Wait until no other fetch of `key` is running, then hold it.
+    async fn acquire(self: &Arc<Self>, key: &str) -> InFlightGuard {
+        let lock = match self.locks.lock() {
+            Ok(mut locks) => locks.entry(key.to_string()).or_default().clone(),
+            // Another caller panicked holding the map; fetch without sharing
+            Err(_) => Arc::default(),
+        };
+        InFlightGuard {
+            _held: lock.lock_owned().await,
+            key: key.to_string(),
+            in_flight: Arc::clone(self),
+        }
+    }
+}
+
+/// A held fetch of one key. Dropping it, even when the fetch is cancelled,
+/// lets the next waiter in, and forgets the key once nobody is waiting.
+struct InFlightGuard {
+    _held: OwnedMutexGuard<()>,
+    key: String,
+    in_flight: Arc<InFlight>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if let Ok(mut locks) = self.in_flight.locks.lock() {
+            // The map's reference and this guard's are the only ones left
+            if locks
+                .get(&self.key)
+                .is_some_and(|lock| Arc::strong_count(lock) <= 2)
+            {
+                locks.remove(&self.key);
+            }
+        }
+    }
+}
+
+/// TTL for entries that record a lookup finding nothing.
+struct NegativeTtl<T> {
+    ttl: Duration,
+    is_negative: fn(&T) -> bool,
+}
+
+impl<T> Clone for NegativeTtl<T> {
+    fn clone(&self) -> Self {
+        Self {
+            ttl: self.ttl,
+            is_negative: self.is_negative,
+        }
+    }
+}
+
+impl<T> NegativeTtl<T> {
+    /// The TTL that applies to `value`, given the cache's usual one.
+    fn for_value(this: &Option<Self>, value: &T, ttl: Duration) -> Duration {
+        match this {
+            Some(negative) if (negative.is_negative)(value) => negative.ttl,
+            _ => ttl,
+        }
+    }
+}
 
+/// A thread-safe in-memory cache whose entries expire after a fixed TTL.
+///
+/// Clones share the same underlying storage.
+///
+/// ```
+/// use std::time::Duration;
+/// use flight_tracker_tui::cache::Cache;
+///
+/// let cache: Cache<u32> = Cache::new(Duration::from_secs(60));
+/// cache.set("UAL123".to_string(), 42);
+/// assert_eq!(cache.get("UAL123"), Some(42));
+/// assert_eq!(cache.get("BAW285"), None);
+/// ```
 #[derive(Clone)]
 pub struct Cache<T: Clone> {
     data: Arc<RwLock<HashMap<String, CacheEntry<T>>>>,
     ttl: Duration,
+    negative: Option<NegativeTtl<T>>,
+    counters: Arc<Counters>,
+    in_flight: Arc<InFlight>,
 }
 
 struct CacheEntry<T> {
@@ -22,21 +169,59 @@ impl<T: Clone> Cache<T> {
         Self {
             data: Arc::new(RwLock::new(HashMap::new())),
             ttl,
+            negative: None,
+            counters: Arc::default(),
+            in_flight: Arc::default(),
         }
     }
 
+    /// Expire entries for which `is_negative` holds after `ttl` instead.
+    pub fn with_negative_ttl(mut self, ttl: Duration, is_negative: fn(&T) -> bool) -> Self {
+        self.negative = Some(NegativeTtl { ttl, is_negative });
+        self
+    }
+
     pub fn get(&self, key: &str) -> Option<T> {
+        self.counters.lookup(self.lookup(key))
+    }
+
+    fn lookup(&self, key: &str) -> Option<T> {
         let data = self.data.read().ok()?;
         let entry = data.get(key)?;
 
-        if entry.inserted_at.elapsed() < self.ttl {
+        let ttl = NegativeTtl::for_value(&self.negative, &entry.value, self.ttl);
+        if entry.inserted_at.elapsed() < ttl {
             Some(entry.value.clone())
         } else {
             None
         }
     }
 
+    /// The cached value for `key`, or else the result of `fetch`, which is
+    /// stored if it succeeds. While one caller fetches a key, others asking
+    /// for it wait and then take the stored value.
+    pub async fn get_or_fetch<F, Fut, E>(&self, key: &str, fetch: F) -> Result<T, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        if let Some(value) = self.get(key) {
+            debug!(key, "Cache hit");
+            return Ok(value);
+        }
+        let _guard = self.in_flight.acquire(key).await;
+        if let Some(value) = self.lookup(key) {
+            debug!(key, "Cache filled by a concurrent fetch");
+            return Ok(value);
+        }
+        debug!(key, "Cache miss");
+        let value = fetch().await?;
+        self.set(key.to_string(), value.clone());
+        Ok(value)
+    }
+
     pub fn set(&self, key: String, value: T) {
+        self.counters.insert();
         if let Ok(mut data) = self.data.write() {
             data.insert(
                 key,
@@ -49,25 +234,37 @@ impl<T: Clone> Cache<T> {
     }
 
     /// Remove all expired entries from the cache.
-    #[allow(dead_code)]
     pub fn clear_expired(&self) {
         if let Ok(mut data) = self.data.write() {
             data.retain(|_, entry| entry.inserted_at.elapsed() < self.ttl);
         }
     }
 
-    #[cfg(test)]
+    /// Remove every entry. The usage counters keep counting.
+    pub fn clear(&self) {
+        if let Ok(mut data) = self.data.write() {
+            data.clear();
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.counters.stats(self.len())
+    }
+
+    /// Number of entries held, including expired ones not yet cleared.
     pub fn len(&self) -> usize {
         self.data.read().map(|d| d.len()).unwrap_or(0)
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
 }
 
 // ============================================================================
 // Persistent Cache (saves to disk)
 // ============================================================================
 
-const CONFIG_DIR: &str = "flight-tracker-tui";
-
 #[derive(Serialize, Deserialize)]
 struct PersistentEntry<T> {
     value: T,
@@ -76,6 +273,9 @@ struct PersistentEntry<T> {
 }
 
 /// A cache that persists to disk, surviving app restarts.
+///
+/// Entries are stored as JSON under `$XDG_CONFIG_HOME/flight-tracker-tui/`,
+/// written in the background by [`crate::persist`] after each change.
 #[derive(Clone)]
 pub struct PersistentCache<T>
 where
@@ -83,7 +283,11 @@ where
 {
     data: Arc<RwLock<HashMap<String, PersistentEntry<T>>>>,
     ttl_secs: u64,
-    file_name: String,
+    negative: Option<NegativeTtl<T>>,
+    /// File in the data directory, or `None` for a cache kept in memory only.
+    path: Option<PathBuf>,
+    counters: Arc<Counters>,
+    in_flight: Arc<InFlight>,
 }
 
 impl<T> PersistentCache<T>
@@ -94,25 +298,96 @@ where
         let cache = Self {
             data: Arc::new(RwLock::new(HashMap::new())),
             ttl_secs: ttl.as_secs(),
-            file_name: file_name.to_string(),
+            negative: None,
+            path: data_dir().map(|dir| dir.join(file_name)),
+            counters: Arc::default(),
+            in_flight: Arc::default(),
         };
         cache.load();
         cache
     }
 
+    /// A cache that is never read from or written to disk.
+    pub fn in_memory(ttl: Duration) -> Self {
+        Self {
+            data: Arc::new(RwLock::new(HashMap::new())),
+            ttl_secs: ttl.as_secs(),
+            negative: None,
+            path: None,
+            counters: Arc::default(),
+            in_flight: Arc::default(),
+        }
+    }
+
+    /// Expire entries for which `is_negative` holds after `ttl` instead.
+    pub fn with_negative_ttl(mut self, ttl: Duration, is_negative: fn(&T) -> bool) -> Self {
+        self.negative = Some(NegativeTtl { ttl, is_negative });
+        self
+    }
+
     pub fn get(&self, key: &str) -> Option<T> {
+        self.get_fresh(key, Duration::from_secs(self.ttl_secs))
+    }
+
+    /// Get an entry only if it is younger than both the TTL and `max_age`.
+    pub fn get_fresh(&self, key: &str, max_age: Duration) -> Option<T> {
+        self.counters.lookup(self.lookup(key, max_age))
+    }
+
+    fn lookup(&self, key: &str, max_age: Duration) -> Option<T> {
         let data = self.data.read().ok()?;
         let entry = data.get(key)?;
 
         let now = current_timestamp();
-        if now.saturating_sub(entry.inserted_at) < self.ttl_secs {
+        let ttl = Duration::from_secs(self.ttl_secs);
+        let ttl_secs = NegativeTtl::for_value(&self.negative, &entry.value, ttl).as_secs();
+        let max_age_secs = ttl_secs.min(max_age.as_secs());
+        if now.saturating_sub(entry.inserted_at) < max_age_secs {
             Some(entry.value.clone())
         } else {
             None
         }
     }
 
+    /// [`Cache::get_or_fetch`] for this cache.
+    pub async fn get_or_fetch<F, Fut, E>(&self, key: &str, fetch: F) -> Result<T, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        self.get_or_fetch_fresh(key, Duration::from_secs(self.ttl_secs), fetch)
+            .await
+    }
+
+    /// Like [`get_or_fetch`](Self::get_or_fetch), but only reuses an entry
+    /// younger than `max_age`.
+    pub async fn get_or_fetch_fresh<F, Fut, E>(
+        &self,
+        key: &str,
+        max_age: Duration,
+        fetch: F,
+    ) -> Result<T, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        if let Some(value) = self.get_fresh(key, max_age) {
+            debug!(key, "Cache hit");
+            return Ok(value);
+        }
+        let _guard = self.in_flight.acquire(key).await;
+        if let Some(value) = self.lookup(key, max_age) {
+            debug!(key, "Cache filled by a concurrent fetch");
+            return Ok(value);
+        }
+        debug!(key, "Cache miss");
+        let value = fetch().await?;
+        self.set(key.to_string(), value.clone());
+        Ok(value)
+    }
+
     pub fn set(&self, key: String, value: T) {
+        self.counters.insert();
         if let Ok(mut data) = self.data.write() {
             data.insert(
                 key,
@@ -121,22 +396,51 @@ where
                     inserted_at: current_timestamp(),
                 },
             );
+            self.save(&data);
+        }
+    }
+
+    /// Number of entries held, including expired ones.
+    pub fn len(&self) -> usize {
+        self.data.read().map(|d| d.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove every entry, on disk too. Remembered misses go with them, so
+    /// the next lookup of anything goes to the network.
+    pub fn clear(&self) {
+        if let Ok(mut data) = self.data.write() {
+            data.clear();
+            self.save(&data);
         }
-        self.save();
     }
 
-    fn config_path(&self) -> Option<PathBuf> {
-        dirs_config_dir().map(|mut p| {
-            p.push(CONFIG_DIR);
-            p.push(&self.file_name);
-            p
-        })
+    pub fn stats(&self) -> CacheStats {
+        self.counters.stats(self.len())
+    }
+
+    /// Drop the entries whose key fails `keep`, on disk too, such as keys
+    /// written in a format no lookup uses any more.
+    pub fn retain(&self, keep: impl Fn(&str) -> bool) {
+        if let Ok(mut data) = self.data.write() {
+            let before = data.len();
+            data.retain(|key, _| keep(key));
+            if data.len() < before {
+                debug!(dropped = before - data.len(), "Dropped cache entries");
+                self.save(&data);
+            }
+        }
     }
 
     fn load(&self) {
-        if let Some(path) = self.config_path() {
-            if let Ok(contents) = fs::read_to_string(&path) {
-                if let Ok(loaded) = serde_json::from_str::<HashMap<String, PersistentEntry<T>>>(&contents) {
+        if let Some(path) = &self.path {
+            if let Ok(contents) = fs::read_to_string(path) {
+                if let Ok(loaded) =
+                    serde_json::from_str::<HashMap<String, PersistentEntry<T>>>(&contents)
+                {
                     if let Ok(mut data) = self.data.write() {
                         *data = loaded;
                     }
@@ -145,15 +449,12 @@ where
         }
     }
 
-    fn save(&self) {
-        if let Some(path) = self.config_path() {
-            if let Some(parent) = path.parent() {
-                let _ = fs::create_dir_all(parent);
-            }
-            if let Ok(data) = self.data.read() {
-                if let Ok(contents) = serde_json::to_string_pretty(&*data) {
-                    let _ = fs::write(&path, contents);
-                }
+    /// Queue `data` for writing. Called with the write lock still held, so
+    /// the writes are queued in the order the changes were made.
+    fn save(&self, data: &HashMap<String, PersistentEntry<T>>) {
+        if let Some(path) = &self.path {
+            if let Ok(contents) = serde_json::to_string_pretty(data) {
+                persist::write(path.clone(), contents);
             }
         }
     }
@@ -166,15 +467,6 @@ fn current_timestamp() -> u64 {
         .unwrap_or(0)
 }
 
-fn dirs_config_dir() -> Option<PathBuf> {
-    if let Ok(xdg) = std::env::var("XDG_CONFIG_HOME") {
-        return Some(PathBuf::from(xdg));
-    }
-    std::env::var("HOME")
-        .ok()
-        .map(|home| PathBuf::from(home).join(".config"))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -252,4 +544,209 @@ mod tests {
         cache2.set("key2".to_string(), "value2".to_string());
         assert_eq!(cache1.get("key2"), Some("value2".to_string()));
     }
+
+    #[test]
+    fn test_cache_stats() {
+        let cache: Cache<u32> = Cache::new(Duration::from_millis(50));
+        cache.set("a".to_string(), 1);
+        cache.get("a");
+        cache.get("b");
+        thread::sleep(Duration::from_millis(60));
+        // Expired entries are misses
+        cache.get("a");
+
+        let stats = cache.stats();
+        assert_eq!(
+            (stats.hits, stats.misses, stats.inserts, stats.entries),
+            (1, 2, 1, 1)
+        );
+
+        cache.clear();
+        assert_eq!(cache.stats().entries, 0);
+        assert_eq!(cache.stats().inserts, 1);
+    }
+
+    #[test]
+    fn test_persistent_cache_clear_forgets_misses() {
+        let cache: PersistentCache<Option<u32>> =
+            PersistentCache::in_memory(Duration::from_secs(60));
+        cache.set("UA900".to_string(), None);
+        assert_eq!(cache.get("UA900"), Some(None));
+
+        cache.clear();
+        assert_eq!(cache.get("UA900"), None);
+        let stats = cache.stats();
+        assert_eq!(
+            (stats.hits, stats.misses, stats.inserts, stats.entries),
+            (1, 1, 1, 0)
+        );
+    }
+
+    #[test]
+    fn test_persistent_cache_retain_drops_old_keys() {
+        let dir = std::env::temp_dir().join(format!("cache-retain-{}", std::process::id()));
+        let path = dir.join("schedules.json");
+        let cache: PersistentCache<u32> = PersistentCache {
+            path: Some(path.clone()),
+            ..PersistentCache::in_memory(Duration::from_secs(60))
+        };
+        cache.set("UA900".to_string(), 1);
+        cache.set("UA900#2024-07-15".to_string(), 2);
+
+        cache.retain(|key| key.contains('#'));
+        assert_eq!(cache.get("UA900"), None);
+        assert_eq!(cache.get("UA900#2024-07-15"), Some(2));
+        persist::flush();
+
+        let loaded: PersistentCache<u32> = PersistentCache {
+            path: Some(path),
+            ..PersistentCache::in_memory(Duration::from_secs(60))
+        };
+        loaded.load();
+        assert_eq!(loaded.len(), 1);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_persistent_cache_file_matches_last_state() {
+        let dir = std::env::temp_dir().join(format!("cache-test-{}", std::process::id()));
+        let path = dir.join("flood.json");
+        let cache: PersistentCache<u32> = PersistentCache {
+            path: Some(path.clone()),
+            ..PersistentCache::in_memory(Duration::from_secs(60))
+        };
+
+        for i in 0..1_000 {
+            cache.set(format!("key{}", i % 10), i);
+        }
+        persist::flush();
+
+        let loaded = PersistentCache {
+            path: Some(path),
+            ..PersistentCache::in_memory(Duration::from_secs(60))
+        };
+        loaded.load();
+        assert_eq!(loaded.len(), 10);
+        for k in 0..10 {
+            let key = format!("key{}", k);
+            assert_eq!(loaded.get(&key), Some(990 + k), "{key}");
+        }
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn test_get_or_fetch_stores_successes_only() {
+        let cache: Cache<u32> = Cache::new(Duration::from_secs(60));
+        let failed: Result<u32, &str> = cache.get_or_fetch("UAL1", || async { Err("down") }).await;
+        assert_eq!(failed, Err("down"));
+        assert!(cache.is_empty());
+
+        let fetched = cache
+            .get_or_fetch("UAL1", || async { Ok::<_, &str>(7) })
+            .await;
+        assert_eq!(fetched, Ok(7));
+        // Answered from the cache, so this fetch never runs
+        let cached = cache
+            .get_or_fetch("UAL1", || async { Ok::<_, &str>(8) })
+            .await;
+        assert_eq!(cached, Ok(7));
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.inserts), (1, 2, 1));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_misses_share_one_fetch() {
+        let cache: PersistentCache<u32> = PersistentCache::in_memory(Duration::from_secs(60));
+        let fetches = AtomicU64::new(0);
+        let fetch = || async {
+            fetches.fetch_add(1, Ordering::Relaxed);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok::<_, ()>(42)
+        };
+
+        let results =
+            futures::future::join_all((0..8).map(|_| cache.get_or_fetch("BA285", fetch))).await;
+        assert!(results.iter().all(|r| *r == Ok(42)));
+        assert_eq!(fetches.load(Ordering::Relaxed), 1);
+        assert_eq!(cache.stats().inserts, 1);
+
+        // Other keys aren't held up, and nothing is left in flight
+        assert_eq!(cache.get_or_fetch("LH400", fetch).await, Ok(42));
+        assert!(cache.in_flight.locks.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_fetch_lets_the_next_caller_in() {
+        let cache: Cache<u32> = Cache::new(Duration::from_secs(60));
+        let hung = cache.get_or_fetch("UAL1", std::future::pending::<Result<u32, ()>>);
+        let timed_out = tokio::time::timeout(Duration::from_millis(10), hung).await;
+        assert!(timed_out.is_err());
+
+        assert_eq!(
+            cache
+                .get_or_fetch("UAL1", || async { Ok::<_, ()>(1) })
+                .await,
+            Ok(1)
+        );
+    }
+
+    #[test]
+    fn test_negative_entries_expire_sooner() {
+        let cache: Cache<Option<u32>> = Cache::new(Duration::from_secs(60))
+            .with_negative_ttl(Duration::from_millis(50), Option::is_none);
+        cache.set("found".to_string(), Some(1));
+        cache.set("missing".to_string(), None);
+        assert_eq!(cache.get("missing"), Some(None));
+
+        thread::sleep(Duration::from_millis(60));
+        assert_eq!(cache.get("found"), Some(Some(1)));
+        assert_eq!(cache.get("missing"), None);
+
+        let persistent: PersistentCache<Option<u32>> =
+            PersistentCache::in_memory(Duration::from_secs(60))
+                .with_negative_ttl(Duration::ZERO, Option::is_none);
+        persistent.set("missing".to_string(), None);
+        persistent.set("found".to_string(), Some(1));
+        assert_eq!(persistent.get("missing"), None);
+        assert_eq!(persistent.get("found"), Some(Some(1)));
+    }
+
+    #[test]
+    fn test_stats_count_concurrent_access() {
+        const THREADS: u64 = 8;
+        const ROUNDS: u64 = 500;
+        let cache: Cache<u64> = Cache::new(Duration::from_secs(60));
+        let persistent: PersistentCache<u64> = PersistentCache::in_memory(Duration::from_secs(60));
+        cache.set("shared".to_string(), 0);
+        persistent.set("shared".to_string(), 0);
+
+        let handles: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let (cache, persistent) = (cache.clone(), persistent.clone());
+                thread::spawn(move || {
+                    for i in 0..ROUNDS {
+                        cache.get("shared");
+                        cache.get("missing");
+                        cache.set(format!("{t}-{i}"), i);
+                        persistent.get("shared");
+                        persistent.get("missing");
+                        persistent.set(format!("{t}-{i}"), i);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let expected = CacheStats {
+            entries: (THREADS * ROUNDS + 1) as usize,
+            hits: THREADS * ROUNDS,
+            misses: THREADS * ROUNDS,
+            inserts: THREADS * ROUNDS + 1,
+        };
+        assert_eq!(cache.stats(), expected);
+        assert_eq!(persistent.stats(), expected);
+    }
 }
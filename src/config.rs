@@ -0,0 +1,744 @@
+//! User preferences loaded from `config.toml` in the data directory.
+
+use chrono::Timelike;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::api::AuthMode;
+use crate::flight::PositionLimits;
+use crate::paths::data_dir;
+use crate::persist;
+use crate::report::Table;
+use crate::tracks::Retention;
+
+const CONFIG_FILE: &str = "config.toml";
+
+/// Settings read once at startup. Missing keys fall back to their defaults,
+/// so an empty or partial file is valid.
+///
+/// ```
+/// use flight_tracker_tui::config::Config;
+///
+/// let config = Config::parse("min_refresh_secs = 30").unwrap();
+/// assert_eq!(config.min_refresh_secs, 30);
+/// assert_eq!(Config::parse("").unwrap().min_refresh_secs, 10);
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Lower bound on any flight's refresh interval, however busy its phase.
+    pub min_refresh_secs: u64,
+    /// How often schedules of tracked flights are re-fetched, in minutes.
+    pub schedule_refresh_mins: u64,
+    /// AviationStack requests included in the plan each month.
+    pub aviationstack_monthly_limit: u32,
+    /// OpenSky credits to spend per day. Refreshing slows down to make them
+    /// last until midnight. Unset, it is the allowance for the access in
+    /// effect (see [`Config::daily_credits`]).
+    pub opensky_daily_credits: Option<u32>,
+    /// Seconds an OpenSky request may take before it is abandoned.
+    pub opensky_timeout_secs: u64,
+    /// Seconds an AviationStack request may take before it is abandoned, so
+    /// a slow schedule lookup doesn't hold up adding a flight.
+    pub aviationstack_timeout_secs: u64,
+    /// Where the user is watching from, for distance and bearing to aircraft.
+    /// `OBSERVER_LAT`/`OBSERVER_LON` override it.
+    pub observer: Option<Location>,
+    /// Clock used for every time shown in the UI.
+    pub time_format: TimeFormat,
+    /// File to keep updated with tracked positions as GeoJSON.
+    /// `--geojson` overrides it.
+    pub geojson_path: Option<PathBuf>,
+    /// Shared file of flights to track while they are listed, one per line.
+    /// `--watchlist` overrides it.
+    pub watchlist_path: Option<PathBuf>,
+    /// URL to POST flight status changes to as JSON, such as a Slack or
+    /// Discord incoming webhook or an ntfy topic.
+    pub webhook_url: Option<String>,
+    /// Minutes a flight must sit on the ground at its destination before it
+    /// moves to the Arrived section. Landed flights move straight away.
+    pub arrived_after_mins: u64,
+    /// Hours an arrived flight is kept before it is removed; 0 keeps it
+    /// until removed by hand.
+    pub arrived_retention_hours: u64,
+    /// Minutes an aircraft that has flown must taxi below 30 kts before it
+    /// counts as landed without a schedule saying so; 0 turns this off.
+    pub landed_after_taxi_mins: u64,
+    /// Polls in a row without a position, after descending below 5,000 ft,
+    /// before an aircraft counts as landed; 0 turns this off.
+    pub landed_after_missed_polls: u32,
+    /// Move aircraft along their heading between position updates, marked
+    /// as estimated.
+    pub dead_reckoning: bool,
+    /// Milliseconds between update ticks, which also redraw the screen.
+    pub tick_rate_ms: u64,
+    /// Refresh interval multiplier while the terminal is unfocused; 0
+    /// pauses refreshing. `--background-refresh` overrides it.
+    pub background_refresh: u64,
+    /// Smoothed vertical rates (ft/min) smaller than this either way are
+    /// shown as level.
+    pub level_below_fpm: u32,
+    /// Minutes before a flight's ETA to notify that it is arriving soon; 0
+    /// turns the notice off.
+    pub arrival_notice_mins: u64,
+    /// What to do when another instance already holds the data directory.
+    pub second_instance: SecondInstance,
+    /// Terminal background the colors are chosen for.
+    pub background: Background,
+    /// The full layout, or one line per flight for small panes.
+    pub layout: ScreenLayout,
+    /// Draw nothing but ASCII, with stand-ins for arrows, status glyphs and
+    /// borders, for terminals that garble anything else.
+    pub ascii_only: bool,
+    /// Keep the terminal title on a summary of the followed flight.
+    pub terminal_title: bool,
+    /// How much recorded track to keep on disk.
+    pub track_retention: Retention,
+    /// Bounds past which a position report's readings are ignored.
+    pub position_limits: PositionLimits,
+    /// Columns of the exported report.
+    pub table: Table,
+}
+
+/// A point on the ground in decimal degrees.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Location {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// How a second instance behaves while the first holds the data directory.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+pub enum SecondInstance {
+    /// Run, but never write history, the session or caches.
+    #[default]
+    #[serde(rename = "read-only")]
+    ReadOnly,
+    /// Exit with a message naming the running instance.
+    #[serde(rename = "refuse")]
+    Refuse,
+}
+
+/// Whether the terminal background is dark or light.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Background {
+    /// Ask the terminal at startup, assuming dark if it doesn't say.
+    #[default]
+    Auto,
+    Dark,
+    Light,
+}
+
+/// The panes and borders of the full layout (`"full"`), or just a line
+/// per flight and a status line (`"ticker"`).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreenLayout {
+    #[default]
+    Full,
+    Ticker,
+}
+
+impl ScreenLayout {
+    /// The other layout.
+    pub fn toggle(self) -> Self {
+        match self {
+            ScreenLayout::Full => ScreenLayout::Ticker,
+            ScreenLayout::Ticker => ScreenLayout::Full,
+        }
+    }
+}
+
+/// 24-hour (`"24h"`) or 12-hour (`"12h"`) clock.
+///
+/// ```
+/// use chrono::NaiveTime;
+/// use flight_tracker_tui::config::TimeFormat;
+///
+/// let time = NaiveTime::from_hms_opt(14, 5, 9).unwrap();
+/// assert_eq!(TimeFormat::TwentyFourHour.format(time, false), "14:05");
+/// assert_eq!(TimeFormat::TwelveHour.format(time, true), "2:05:09 pm");
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TimeFormat {
+    #[default]
+    #[serde(rename = "24h")]
+    TwentyFourHour,
+    #[serde(rename = "12h")]
+    TwelveHour,
+}
+
+impl TimeFormat {
+    /// The other clock.
+    pub fn toggle(self) -> Self {
+        match self {
+            TimeFormat::TwentyFourHour => TimeFormat::TwelveHour,
+            TimeFormat::TwelveHour => TimeFormat::TwentyFourHour,
+        }
+    }
+
+    /// Format a time of day, optionally with seconds.
+    pub fn format(self, time: impl Timelike, seconds: bool) -> String {
+        let secs = if seconds {
+            format!(":{:02}", time.second())
+        } else {
+            String::new()
+        };
+        match self {
+            TimeFormat::TwentyFourHour => {
+                format!("{:02}:{:02}{}", time.hour(), time.minute(), secs)
+            }
+            TimeFormat::TwelveHour => {
+                let (pm, hour) = time.hour12();
+                let suffix = if pm { "pm" } else { "am" };
+                format!("{}:{:02}{} {}", hour, time.minute(), secs, suffix)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for TimeFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeFormat::TwentyFourHour => write!(f, "24-hour"),
+            TimeFormat::TwelveHour => write!(f, "12-hour"),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            min_refresh_secs: 10,
+            schedule_refresh_mins: 30,
+            aviationstack_monthly_limit: 100,
+            opensky_daily_credits: None,
+            opensky_timeout_secs: 10,
+            aviationstack_timeout_secs: 10,
+            observer: None,
+            time_format: TimeFormat::default(),
+            geojson_path: None,
+            watchlist_path: None,
+            webhook_url: None,
+            arrived_after_mins: 10,
+            arrived_retention_hours: 12,
+            landed_after_taxi_mins: 5,
+            landed_after_missed_polls: 3,
+            dead_reckoning: false,
+            tick_rate_ms: 1000,
+            background_refresh: 4,
+            level_below_fpm: 100,
+            arrival_notice_mins: 20,
+            second_instance: SecondInstance::default(),
+            background: Background::default(),
+            layout: ScreenLayout::default(),
+            ascii_only: false,
+            terminal_title: false,
+            track_retention: Retention::default(),
+            position_limits: PositionLimits::default(),
+            table: Table::default(),
+        }
+    }
+}
+
+/// OpenSky's daily credit allowance for anonymous access.
+pub const ANONYMOUS_DAILY_CREDITS: u32 = 400;
+/// OpenSky's daily credit allowance for a registered account.
+pub const AUTHENTICATED_DAILY_CREDITS: u32 = 4000;
+
+impl Config {
+    /// OpenSky credits to spend today: `opensky_daily_credits` if set, else
+    /// the allowance for how requests are authenticated right now, so a
+    /// rejected password drops the budget to the anonymous one.
+    pub fn daily_credits(&self, auth: AuthMode) -> u32 {
+        self.opensky_daily_credits.unwrap_or(match auth {
+            AuthMode::Anonymous => ANONYMOUS_DAILY_CREDITS,
+            AuthMode::Basic => AUTHENTICATED_DAILY_CREDITS,
+        })
+    }
+
+    /// Parse a config file's contents.
+    pub fn parse(contents: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(contents)
+    }
+
+    /// Load the config file, or return defaults if it is missing or invalid,
+    /// then apply environment overrides.
+    pub fn load() -> Self {
+        Self::load_file().with_observer_env(
+            std::env::var("OBSERVER_LAT").ok().as_deref(),
+            std::env::var("OBSERVER_LON").ok().as_deref(),
+        )
+    }
+
+    fn load_file() -> Self {
+        let Some(path) = Self::config_path() else {
+            return Self::default();
+        };
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        Self::parse(&contents)
+            .map(Self::validated)
+            .unwrap_or_else(|e| {
+                tracing::warn!(path = %path.display(), error = %e, "Ignoring invalid config file");
+                Self::default()
+            })
+    }
+
+    /// Drop settings that parse but name nothing, with a warning for each.
+    fn validated(mut self) -> Self {
+        for id in self.table.remove_unknown() {
+            tracing::warn!(column = %id, "Ignoring unknown table column");
+        }
+        self
+    }
+
+    /// Replace the observer location with the `OBSERVER_LAT`/`OBSERVER_LON`
+    /// values when both are present and valid coordinates.
+    fn with_observer_env(mut self, lat: Option<&str>, lon: Option<&str>) -> Self {
+        let (Some(lat), Some(lon)) = (lat, lon) else {
+            return self;
+        };
+        match (lat.trim().parse::<f64>(), lon.trim().parse::<f64>()) {
+            (Ok(latitude), Ok(longitude))
+                if (-90.0..=90.0).contains(&latitude) && (-180.0..=180.0).contains(&longitude) =>
+            {
+                self.observer = Some(Location {
+                    latitude,
+                    longitude,
+                });
+            }
+            _ => tracing::warn!(lat, lon, "Ignoring invalid OBSERVER_LAT/OBSERVER_LON"),
+        }
+        self
+    }
+
+    /// Get the config file path.
+    pub fn config_path() -> Option<PathBuf> {
+        data_dir().map(|p| p.join(CONFIG_FILE))
+    }
+
+    /// Write `(key, TOML value)` pairs into the config file in the
+    /// background, keeping the rest of it as it is. A file that exists but
+    /// can't be read is left alone.
+    pub fn save_values(values: &[(&str, String)]) {
+        let Some(path) = Self::config_path() else {
+            return;
+        };
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                tracing::warn!(path = %path.display(), error = %e, "Not saving settings");
+                return;
+            }
+        };
+        let contents = values.iter().fold(contents, |contents, (key, value)| {
+            set_value(&contents, key, value)
+        });
+        persist::write(path, contents);
+    }
+}
+
+/// A config file's `contents` with the top-level `key` set to `value`,
+/// which must already be TOML. The line setting it is rewritten in place,
+/// keeping its comment; failing that the setting goes under a commented-out
+/// `# key = ...` line, as the starter file has, or else before the first
+/// table. Every other line stays as it was.
+///
+/// ```
+/// use flight_tracker_tui::config::set_value;
+///
+/// let file = "# Mine\nmin_refresh_secs = 10  # polite\n";
+/// let file = set_value(file, "min_refresh_secs", "30");
+/// assert_eq!(file, "# Mine\nmin_refresh_secs = 30  # polite\n");
+/// ```
+pub fn set_value(contents: &str, key: &str, value: &str) -> String {
+    let sets_key = |line: &str| {
+        line.trim_start()
+            .strip_prefix(key)
+            .is_some_and(|rest| rest.trim_start().starts_with('='))
+    };
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+    let first_table = lines
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .unwrap_or(lines.len());
+    let setting = format!("{} = {}", key, value);
+
+    if let Some(i) = lines[..first_table].iter().position(|line| sets_key(line)) {
+        lines[i] = format!("{}{}", setting, trailing_comment(&lines[i]));
+    } else if let Some(i) = lines[..first_table]
+        .iter()
+        .position(|line| line.trim_start().strip_prefix('#').is_some_and(sets_key))
+    {
+        lines.insert(i + 1, setting);
+    } else {
+        // Above the first table and the comments that introduce it
+        let mut at = first_table;
+        while at > 0 && lines[at - 1].trim_start().starts_with('#') {
+            at -= 1;
+        }
+        while at > 0 && lines[at - 1].trim().is_empty() {
+            at -= 1;
+        }
+        if lines.get(at).is_some_and(|line| !line.trim().is_empty()) {
+            lines.insert(at, String::new());
+        }
+        lines.insert(at, setting);
+    }
+
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    contents
+}
+
+/// The comment after a `key = value` line's value, with the spaces before
+/// it, or "" when there is none.
+fn trailing_comment(line: &str) -> &str {
+    let Some((_, value)) = line.split_once('=') else {
+        return "";
+    };
+    let mut quoted = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &value[value[..i].trim_end().len()..],
+            _ => {}
+        }
+    }
+    ""
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ignores_unknown_keys() {
+        let config = Config::parse("min_refresh_secs = 5\nfuture_option = true").unwrap();
+        assert_eq!(config.min_refresh_secs, 5);
+    }
+
+    #[test]
+    fn test_parse_observer() {
+        let config = Config::parse("[observer]\nlatitude = 51.47\nlongitude = -0.45").unwrap();
+        assert_eq!(
+            config.observer,
+            Some(Location {
+                latitude: 51.47,
+                longitude: -0.45
+            })
+        );
+        assert!(Config::default().observer.is_none());
+    }
+
+    #[test]
+    fn test_observer_env_overrides_file() {
+        let file = Config::parse("[observer]\nlatitude = 51.47\nlongitude = -0.45").unwrap();
+
+        let config = file
+            .clone()
+            .with_observer_env(Some("40.64"), Some(" -73.78 "));
+        assert_eq!(config.observer.unwrap().latitude, 40.64);
+        assert_eq!(config.observer.unwrap().longitude, -73.78);
+
+        // Half a pair or an invalid value leaves the file setting alone
+        let config = file.clone().with_observer_env(Some("40.64"), None);
+        assert_eq!(config.observer.unwrap().latitude, 51.47);
+        let config = file.with_observer_env(Some("140"), Some("0"));
+        assert_eq!(config.observer.unwrap().latitude, 51.47);
+    }
+
+    #[test]
+    fn test_parse_time_format() {
+        assert_eq!(Config::default().time_format, TimeFormat::TwentyFourHour);
+        let config = Config::parse("time_format = \"12h\"").unwrap();
+        assert_eq!(config.time_format, TimeFormat::TwelveHour);
+        assert!(Config::parse("time_format = \"12\"").is_err());
+    }
+
+    #[test]
+    fn test_time_format_midnight_and_noon() {
+        use chrono::NaiveTime;
+
+        let at = |h, m, s| NaiveTime::from_hms_opt(h, m, s).unwrap();
+        let (h24, h12) = (TimeFormat::TwentyFourHour, TimeFormat::TwelveHour);
+
+        assert_eq!(h24.format(at(0, 0, 0), false), "00:00");
+        assert_eq!(h12.format(at(0, 0, 0), false), "12:00 am");
+        assert_eq!(h12.format(at(0, 59, 59), true), "12:59:59 am");
+        assert_eq!(h12.format(at(1, 0, 0), false), "1:00 am");
+        assert_eq!(h12.format(at(11, 59, 0), false), "11:59 am");
+        assert_eq!(h24.format(at(12, 0, 0), true), "12:00:00");
+        assert_eq!(h12.format(at(12, 0, 0), false), "12:00 pm");
+        assert_eq!(h12.format(at(13, 30, 0), false), "1:30 pm");
+        assert_eq!(h12.format(at(23, 59, 59), true), "11:59:59 pm");
+        assert_eq!(h24.toggle(), h12);
+        assert_eq!(h12.toggle(), h24);
+    }
+
+    #[test]
+    fn test_parse_geojson_path() {
+        assert!(Config::default().geojson_path.is_none());
+        let config = Config::parse("geojson_path = \"/tmp/flights.geojson\"").unwrap();
+        assert_eq!(
+            config.geojson_path,
+            Some(PathBuf::from("/tmp/flights.geojson"))
+        );
+    }
+
+    #[test]
+    fn test_parse_watchlist_path() {
+        assert!(Config::default().watchlist_path.is_none());
+        let config = Config::parse("watchlist_path = \"/mnt/ops/watchlist.txt\"").unwrap();
+        assert_eq!(
+            config.watchlist_path,
+            Some(PathBuf::from("/mnt/ops/watchlist.txt"))
+        );
+    }
+
+    #[test]
+    fn test_parse_arrived_settings() {
+        let config = Config::default();
+        assert_eq!(
+            (config.arrived_after_mins, config.arrived_retention_hours),
+            (10, 12)
+        );
+        let config = Config::parse("arrived_after_mins = 5\narrived_retention_hours = 0").unwrap();
+        assert_eq!(
+            (config.arrived_after_mins, config.arrived_retention_hours),
+            (5, 0)
+        );
+    }
+
+    #[test]
+    fn test_parse_dead_reckoning() {
+        assert!(!Config::default().dead_reckoning);
+        assert!(
+            Config::parse("dead_reckoning = true")
+                .unwrap()
+                .dead_reckoning
+        );
+    }
+
+    #[test]
+    fn test_parse_background_refresh() {
+        assert_eq!(Config::default().background_refresh, 4);
+        assert_eq!(
+            Config::parse("background_refresh = 0")
+                .unwrap()
+                .background_refresh,
+            0
+        );
+    }
+
+    #[test]
+    fn test_parse_layout() {
+        assert_eq!(Config::default().layout, ScreenLayout::Full);
+        let config = Config::parse("layout = \"ticker\"").unwrap();
+        assert_eq!(config.layout, ScreenLayout::Ticker);
+        assert!(Config::parse("layout = \"compact\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_position_limits() {
+        let limits = Config::default().position_limits;
+        assert_eq!(limits.max_ground_speed_kts, 1000.0);
+        let config = Config::parse("[position_limits]\nmax_altitude_ft = 70000").unwrap();
+        assert_eq!(config.position_limits.max_altitude_ft, 70000.0);
+        assert_eq!(config.position_limits.max_ground_speed_kts, 1000.0);
+    }
+
+    #[test]
+    fn test_parse_ascii_only() {
+        assert!(!Config::default().ascii_only);
+        assert!(Config::parse("ascii_only = true").unwrap().ascii_only);
+    }
+
+    #[test]
+    fn test_parse_terminal_title() {
+        assert!(!Config::default().terminal_title);
+        assert!(
+            Config::parse("terminal_title = true")
+                .unwrap()
+                .terminal_title
+        );
+    }
+
+    #[test]
+    fn test_parse_tick_rate() {
+        assert_eq!(Config::default().tick_rate_ms, 1000);
+        assert_eq!(
+            Config::parse("tick_rate_ms = 250").unwrap().tick_rate_ms,
+            250
+        );
+    }
+
+    #[test]
+    fn test_parse_level_threshold() {
+        assert_eq!(Config::default().level_below_fpm, 100);
+        assert_eq!(
+            Config::parse("level_below_fpm = 250")
+                .unwrap()
+                .level_below_fpm,
+            250
+        );
+    }
+
+    #[test]
+    fn test_parse_arrival_notice() {
+        assert_eq!(Config::default().arrival_notice_mins, 20);
+        assert_eq!(
+            Config::parse("arrival_notice_mins = 0")
+                .unwrap()
+                .arrival_notice_mins,
+            0
+        );
+    }
+
+    #[test]
+    fn test_parse_second_instance() {
+        assert_eq!(Config::default().second_instance, SecondInstance::ReadOnly);
+        let config = Config::parse("second_instance = \"refuse\"").unwrap();
+        assert_eq!(config.second_instance, SecondInstance::Refuse);
+        assert!(Config::parse("second_instance = \"share\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_background() {
+        assert_eq!(Config::default().background, Background::Auto);
+        assert_eq!(
+            Config::parse("background = \"light\"").unwrap().background,
+            Background::Light
+        );
+        assert!(Config::parse("background = \"white\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_landing_thresholds() {
+        let config = Config::default();
+        assert_eq!(
+            (
+                config.landed_after_taxi_mins,
+                config.landed_after_missed_polls
+            ),
+            (5, 3)
+        );
+        let config =
+            Config::parse("landed_after_taxi_mins = 0\nlanded_after_missed_polls = 6").unwrap();
+        assert_eq!(
+            (
+                config.landed_after_taxi_mins,
+                config.landed_after_missed_polls
+            ),
+            (0, 6)
+        );
+    }
+
+    #[test]
+    fn test_parse_daily_credits() {
+        let config = Config::default();
+        assert_eq!(config.daily_credits(AuthMode::Anonymous), 400);
+        assert_eq!(config.daily_credits(AuthMode::Basic), 4000);
+        // A set value wins whatever the access
+        let config = Config::parse("opensky_daily_credits = 1000").unwrap();
+        assert_eq!(config.opensky_daily_credits, Some(1000));
+        assert_eq!(config.daily_credits(AuthMode::Anonymous), 1000);
+        assert_eq!(config.daily_credits(AuthMode::Basic), 1000);
+    }
+
+    #[test]
+    fn test_parse_webhook_url() {
+        assert!(Config::default().webhook_url.is_none());
+        let config = Config::parse("webhook_url = \"https://ntfy.sh/my-flights\"").unwrap();
+        assert_eq!(
+            config.webhook_url.as_deref(),
+            Some("https://ntfy.sh/my-flights")
+        );
+    }
+
+    #[test]
+    fn test_table_columns_are_validated() {
+        let config = Config::parse("[table]\ncolumns = [\"flight\", \"gate\", \"squawk\"]")
+            .unwrap()
+            .validated();
+        assert_eq!(config.table.columns, ["flight", "squawk"]);
+
+        let config = Config::parse("[table]\ncolumns = [\"gate\"]")
+            .unwrap()
+            .validated();
+        assert_eq!(config.table, Table::default());
+    }
+
+    #[test]
+    fn test_parse_timeouts() {
+        let config = Config::parse("aviationstack_timeout_secs = 4").unwrap();
+        assert_eq!(config.aviationstack_timeout_secs, 4);
+        assert_eq!(config.opensky_timeout_secs, 10);
+    }
+
+    #[test]
+    fn test_parse_rejects_wrong_type() {
+        assert!(Config::parse("min_refresh_secs = \"fast\"").is_err());
+    }
+
+    #[test]
+    fn test_set_value_rewrites_in_place() {
+        let file = "# Mine\n\nmin_refresh_secs = 10 # be polite\ntime_format = \"24h\"\n\n\
+                    [observer]\nlatitude = 51.47\n";
+        let file = set_value(file, "min_refresh_secs", "30");
+        let file = set_value(&file, "time_format", "\"12h\"");
+        assert_eq!(
+            file,
+            "# Mine\n\nmin_refresh_secs = 30 # be polite\ntime_format = \"12h\"\n\n\
+             [observer]\nlatitude = 51.47\n"
+        );
+        // A # inside a string isn't a comment
+        let file = set_value(
+            "webhook_url = \"https://x/#a\" # hook",
+            "webhook_url",
+            "\"y\"",
+        );
+        assert_eq!(file, "webhook_url = \"y\" # hook\n");
+        // Keys in tables and keys that only start the same are left alone
+        let file = "min_refresh_secs_x = 1\n[t]\nmin_refresh_secs = 2\n";
+        assert_eq!(
+            set_value(file, "min_refresh_secs", "5"),
+            "min_refresh_secs_x = 1\nmin_refresh_secs = 5\n\n[t]\nmin_refresh_secs = 2\n"
+        );
+    }
+
+    #[test]
+    fn test_set_value_adds_missing_keys() {
+        assert_eq!(
+            set_value("", "dead_reckoning", "true"),
+            "dead_reckoning = true\n"
+        );
+
+        // Under the starter file's commented-out line, which stays as a hint
+        let file = "# Clock: \"24h\" or \"12h\"\n# time_format = \"24h\"\n\n# [observer]\n";
+        assert_eq!(
+            set_value(file, "time_format", "\"12h\""),
+            "# Clock: \"24h\" or \"12h\"\n# time_format = \"24h\"\ntime_format = \"12h\"\n\n\
+             # [observer]\n"
+        );
+
+        // Above the first table and its comment
+        let file = "# Where I am\n[observer]\nlatitude = 51.47\nlongitude = -0.45\n";
+        assert_eq!(
+            set_value(file, "background", "\"light\""),
+            format!("background = \"light\"\n\n{}", file)
+        );
+
+        // Whatever was written reads back
+        let config = Config::parse(&set_value(file, "background", "\"light\"")).unwrap();
+        assert_eq!(config.background, Background::Light);
+        assert_eq!(config.observer.unwrap().latitude, 51.47);
+    }
+}
@@ -1,5 +1,8 @@
 use thiserror::Error;
 
+/// Longest parse error detail kept, in characters.
+const MAX_PARSE_DETAIL_CHARS: usize = 120;
+
 /// Application-level errors for the flight tracker.
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -9,17 +12,104 @@ pub enum AppError {
     #[error("Rate limit exceeded")]
     RateLimited,
 
+    #[error("Credentials rejected")]
+    Unauthorized,
+
+    #[error("HTTPS is not included in the API plan")]
+    HttpsRestricted,
+
+    #[error("API error: {0}")]
+    Api(String),
+
+    /// Not sent: the provider failed too often lately and is being rested.
+    #[error("{0} skipped after repeated failures")]
+    ProviderDegraded(&'static str),
+
     #[error("Network error: {0}")]
     Network(#[from] reqwest::Error),
+
+    /// A network error read back from a session recording, which keeps
+    /// only its message.
+    #[error("Network error: {0}")]
+    RecordedNetwork(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
 }
 
 impl AppError {
+    /// A parse error whose detail is squeezed onto one line and cut short,
+    /// so an HTML error page or a whole response body doesn't end up in the
+    /// log or the diagnostics panel.
+    ///
+    /// ```
+    /// use flight_tracker_tui::AppError;
+    ///
+    /// let err = AppError::parse("<html>\n  <body>502 Bad Gateway</body>\n</html>");
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "Failed to parse response: <html> <body>502 Bad Gateway</body> </html>"
+    /// );
+    /// ```
+    pub fn parse(detail: impl AsRef<str>) -> Self {
+        let mut clean = detail
+            .as_ref()
+            .split(|c: char| c.is_whitespace() || c.is_control())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if let Some((cut, _)) = clean.char_indices().nth(MAX_PARSE_DETAIL_CHARS) {
+            clean.truncate(cut);
+            clean.push('…');
+        }
+        Self::Parse(clean)
+    }
+
     /// Returns a user-friendly error message suitable for display in the UI.
     pub fn user_message(&self) -> String {
         match self {
             Self::RateLimited => "API rate limit reached. Try again later.".to_string(),
-            Self::Network(_) => "Network error. Check your connection.".to_string(),
+            Self::Unauthorized => {
+                "OpenSky credentials rejected — check OPENSKY_USERNAME/PASSWORD".to_string()
+            }
+            Self::HttpsRestricted => {
+                "AviationStack plan has no HTTPS — unset AVIATIONSTACK_HTTPS to allow HTTP"
+                    .to_string()
+            }
+            Self::Api(message) => format!("Schedule lookup failed: {}", message),
+            Self::ProviderDegraded(provider) => {
+                format!("{} is failing, trying again shortly", provider)
+            }
+            Self::Network(_) | Self::RecordedNetwork(_) => {
+                "Network error. Check your connection.".to_string()
+            }
             Self::Parse(_) => "Failed to parse flight data.".to_string(),
+            Self::Io(e) => format!("Could not read file: {}", e),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_detail_is_one_short_line() {
+        let AppError::Parse(detail) = AppError::parse("line one\r\n\tline\u{7}two  ") else {
+            unreachable!();
+        };
+        assert_eq!(detail, "line one line two");
+
+        // Cut on a character boundary, however wide the characters are
+        let AppError::Parse(detail) = AppError::parse("✈".repeat(500)) else {
+            unreachable!();
+        };
+        assert_eq!(detail.chars().count(), MAX_PARSE_DETAIL_CHARS + 1);
+        assert!(detail.ends_with("✈…"));
+
+        let AppError::Parse(detail) = AppError::parse("") else {
+            unreachable!();
+        };
+        assert_eq!(detail, "");
+    }
+}
@@ -1,36 +1,63 @@
 //! Terminal event handling for the TUI.
 
 use crossterm::event::{self, Event as CrosstermEvent, KeyEvent, KeyEventKind};
-use futures::{FutureExt, StreamExt};
+use futures::{FutureExt, Stream, StreamExt};
+use std::io;
 use std::time::Duration;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
+use tokio::time::MissedTickBehavior;
+
+/// Redraw cadence while something on screen is animating.
+const RENDER_RATE: Duration = Duration::from_millis(100);
+
+/// Shortest accepted update tick, so a zero in the config can't spin.
+const MIN_TICK_RATE: Duration = Duration::from_millis(50);
 
 /// Terminal events that the application can handle.
 #[derive(Debug, Clone, Copy)]
 pub enum Event {
     /// Periodic tick for updates.
     Tick,
+    /// Redraw-only tick, sent only while animating.
+    Render,
     /// Keyboard input.
     Key(KeyEvent),
     /// Terminal resize (width, height).
     #[allow(dead_code)]
     Resize(u16, u16),
+    /// Terminal window gained focus.
+    FocusGained,
+    /// Terminal window lost focus.
+    FocusLost,
 }
 
 pub struct EventHandler {
     rx: mpsc::UnboundedReceiver<Event>,
+    animating: watch::Sender<bool>,
     _task: tokio::task::JoinHandle<()>,
 }
 
 impl EventHandler {
     pub fn new(tick_rate: Duration) -> Self {
+        Self::with_source(event::EventStream::new(), tick_rate, RENDER_RATE)
+    }
+
+    /// Read terminal events from `source`, sending a `Tick` every
+    /// `tick_rate` and, while animating, a `Render` every `render_rate`.
+    fn with_source<S>(mut reader: S, tick_rate: Duration, render_rate: Duration) -> Self
+    where
+        S: Stream<Item = io::Result<CrosstermEvent>> + Unpin + Send + 'static,
+    {
         let (tx, rx) = mpsc::unbounded_channel();
+        let (animating, mut animating_rx) = watch::channel(false);
 
         let task = tokio::spawn(async move {
-            let mut reader = event::EventStream::new();
-            let mut tick_interval = tokio::time::interval(tick_rate);
+            let mut tick_interval = tokio::time::interval(tick_rate.max(MIN_TICK_RATE));
+            let mut render_interval = tokio::time::interval(render_rate);
+            render_interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
             loop {
+                let rendering = *animating_rx.borrow_and_update();
                 let tick_delay = tick_interval.tick();
                 let crossterm_event = reader.next().fuse();
 
@@ -40,6 +67,16 @@ impl EventHandler {
                             break;
                         }
                     }
+                    _ = render_interval.tick(), if rendering => {
+                        if tx.send(Event::Render).is_err() {
+                            break;
+                        }
+                    }
+                    changed = animating_rx.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                    }
                     maybe_event = crossterm_event => {
                         match maybe_event {
                             Some(Ok(evt)) => {
@@ -48,6 +85,8 @@ impl EventHandler {
                                         Some(Event::Key(key))
                                     }
                                     CrosstermEvent::Resize(w, h) => Some(Event::Resize(w, h)),
+                                    CrosstermEvent::FocusGained => Some(Event::FocusGained),
+                                    CrosstermEvent::FocusLost => Some(Event::FocusLost),
                                     _ => None,
                                 };
                                 if let Some(event) = event {
@@ -64,10 +103,80 @@ impl EventHandler {
             }
         });
 
-        Self { rx, _task: task }
+        Self {
+            rx,
+            animating,
+            _task: task,
+        }
+    }
+
+    /// Turn `Render` ticks on or off. Idle screens only redraw on update
+    /// ticks and input.
+    pub fn set_animating(&self, animating: bool) {
+        self.animating.send_if_modified(|current| {
+            let changed = *current != animating;
+            *current = animating;
+            changed
+        });
     }
 
     pub async fn next(&mut self) -> Option<Event> {
         self.rx.recv().await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flight_tracker_tui::config::Config;
+    use tokio::time::{timeout_at, Instant};
+
+    /// Count the update and render ticks received until `deadline`. The app
+    /// draws once per event, so these are the draw calls.
+    async fn count_draws(events: &mut EventHandler, deadline: Instant) -> (usize, usize) {
+        let (mut ticks, mut renders) = (0, 0);
+        while let Ok(Some(event)) = timeout_at(deadline, events.next()).await {
+            match event {
+                Event::Tick => ticks += 1,
+                Event::Render => renders += 1,
+                _ => {}
+            }
+        }
+        (ticks, renders)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_idle_draws_only_on_update_ticks() {
+        let start = Instant::now();
+        let tick_rate = Duration::from_millis(Config::default().tick_rate_ms);
+        let mut events =
+            EventHandler::with_source(futures::stream::pending(), tick_rate, RENDER_RATE);
+
+        // Ten idle seconds at the default rate: the immediate first tick plus
+        // one a second
+        let idle = count_draws(&mut events, start + Duration::from_millis(10_500)).await;
+        assert_eq!(idle, (11, 0));
+
+        // While animating, render ticks come ten times a second
+        events.set_animating(true);
+        let (ticks, renders) =
+            count_draws(&mut events, start + Duration::from_millis(11_500)).await;
+        assert_eq!(ticks, 1);
+        assert!((9..=11).contains(&renders), "{renders} render ticks");
+
+        // A render tick already queued may still arrive after switching off
+        events.set_animating(false);
+        count_draws(&mut events, start + Duration::from_millis(11_600)).await;
+        let idle = count_draws(&mut events, start + Duration::from_millis(13_500)).await;
+        assert_eq!(idle, (2, 0));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_zero_tick_rate_is_clamped() {
+        let start = Instant::now();
+        let mut events =
+            EventHandler::with_source(futures::stream::pending(), Duration::ZERO, RENDER_RATE);
+        let (ticks, _) = count_draws(&mut events, start + Duration::from_millis(1_010)).await;
+        assert_eq!(ticks, 21);
+    }
+}
@@ -0,0 +1,28 @@
+//! Flights and airports shared by the binary's tests, which can't see the
+//! library's `cfg(test)` ones.
+
+use flight_tracker_tui::flight::{Airport, Flight, FlightStatus};
+
+/// An airport known only by its IATA code.
+pub fn airport(iata: &str) -> Airport {
+    Airport {
+        iata: Some(iata.to_string()),
+        ..Default::default()
+    }
+}
+
+/// UA123 from San Francisco to New York, cruising at FL360 and 480 kt over
+/// Oklahoma.
+pub fn cruising() -> Flight {
+    Flight {
+        flight_number: "UA123".to_string(),
+        status: FlightStatus::EnRoute,
+        origin: Some(airport("SFO")),
+        destination: Some(airport("JFK")),
+        latitude: Some(36.5),
+        longitude: Some(-98.0),
+        altitude_ft: Some(36_000.0),
+        ground_speed_kts: Some(480.0),
+        ..Default::default()
+    }
+}
@@ -1,24 +1,137 @@
-use chrono::{DateTime, Utc};
+//! Flight and airport data model merged from the position and schedule APIs.
 
+use std::collections::{HashMap, HashSet};
+
+use chrono::{
+    DateTime, FixedOffset, Local, LocalResult, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::airports::{self, AirportLocation};
+use crate::alert::{self, AlertRule, Mute};
+use crate::api::{self, AircraftCategory};
+use crate::config::TimeFormat;
+use crate::geo;
+
+/// How close to an airport, in km, an aircraft on the ground counts as
+/// being at it.
+const AT_AIRPORT_KM: f64 = 5.0;
+
+/// Age past which a fix is stale: it is no longer extrapolated along its
+/// heading, and the schedule's status takes over from the one it gave.
+const STALE_POSITION_SECS: i64 = 300;
+
+const KNOTS_TO_KMH: f64 = 1.852;
+
+/// Ground speed (kts) below which an aircraft on the ground is taxiing.
+const TAXI_SPEED_KTS: f64 = 30.0;
+/// Altitude (ft) below which a descending aircraft that drops out of the
+/// feed is taken to have landed rather than gone out of coverage.
+const LOST_ON_APPROACH_FT: f64 = 5_000.0;
+
+/// Distance (km) from both ends of its route beyond which an aircraft parked
+/// on the ground is taken not to be flying the route.
+const OFF_ROUTE_KM: f64 = 50.0;
+/// Polls in a row without a state vector, while the schedule has the flight
+/// in the air, before its aircraft is suspected of having been swapped.
+const SWAP_AFTER_MISSED_POLLS: u32 = 5;
+
+/// Delay changes kept per flight, a few hours' worth of schedule refreshes.
+const MAX_DELAY_HISTORY: usize = 12;
+/// Delay values shown in a trend; older ones only count towards the span.
+const DELAY_TREND_VALUES: usize = 4;
+
+/// Positions kept per flight for the map trail and altitude profile; a
+/// long-haul flight reported every 10 seconds fits twice over.
+const MAX_TRACK_POINTS: usize = 4_000;
+
+/// Feet below a track's highest altitude still counted as cruise, so small
+/// step climbs don't move the top of climb or descent.
+const CRUISE_BAND_FT: f64 = 2_000.0;
+/// Feet below cruise a track must reach before or after it for the climb
+/// or descent to count, rather than the track starting or ending at cruise.
+const CLIMB_DESCENT_FT: f64 = 5_000.0;
+
+/// Vertical rate samples kept per flight for smoothing.
+const MAX_RATE_SAMPLES: usize = 6;
+/// Weight of the newest sample in the vertical rate average.
+const RATE_SMOOTHING: f64 = 0.4;
+/// Seconds between samples after which the older ones no longer count, as
+/// after a coverage gap the aircraft may be doing something else entirely.
+const RATE_GAP_SECS: i64 = 120;
+
+/// Minutes before the ETA from which a flight counts as arriving within the
+/// hour, and as arriving imminently.
+const ETA_WITHIN_HOUR_MINS: i64 = 60;
+const ETA_IMMINENT_MINS: i64 = 15;
+
+/// Minutes the track-based ETA may be off the airline's estimate, either
+/// way, before the two are flagged as diverging.
+const ETA_DIVERGENCE_MINS: i64 = 15;
+/// Ground speed (kts) below which no ETA is worked out from the track, as
+/// the aircraft is climbing out, taxiing or reporting a bad reading.
+const TRACK_ETA_MIN_SPEED_KTS: f64 = 100.0;
+
+/// A tracked flight combining OpenSky position and AviationStack schedule data.
 #[derive(Debug, Clone, Default)]
 pub struct Flight {
     pub flight_number: String,
     pub callsign: String,
     pub icao24: String,
 
+    /// The status shown, settled from the two below by
+    /// [`Flight::resolve_status`].
     pub status: FlightStatus,
+    /// Status given by the latest position report, or Landed once the
+    /// positions show a landing.
+    pub position_status: Option<FlightStatus>,
+    /// Status the schedule provider last reported.
+    pub schedule_status: Option<FlightStatus>,
 
     // Position data (from OpenSky)
     pub latitude: Option<f64>,
     pub longitude: Option<f64>,
+    /// When the aircraft reported its latest position.
+    pub position_time: Option<DateTime<Utc>>,
+    /// Which provider the latest position came from.
+    pub position_source: Option<PositionSource>,
+    /// Generation time of the OpenSky snapshot the latest position came
+    /// in, which can be several seconds newer than the position itself.
+    pub snapshot_time: Option<DateTime<Utc>>,
     pub altitude_ft: Option<f64>,
     pub heading: Option<f64>,
     pub vertical_rate: Option<f64>,
+    /// Recent vertical rate reports, oldest first, for
+    /// [`Flight::smoothed_vertical_rate`].
+    pub vertical_rate_samples: Vec<RateSample>,
     pub ground_speed_kts: Option<f64>,
     pub on_ground: bool,
+    /// Positions flown so far, oldest first: the provider's track from
+    /// before tracking began, then each fix since.
+    pub track: Vec<TrackPoint>,
+    /// When the feed first reported the aircraft on the ground, cleared once
+    /// it is airborne again.
+    pub on_ground_since: Option<DateTime<Utc>>,
     pub squawk: Option<String>,
+    /// ADS-B emitter category, when the feed reports one.
+    pub category: Option<AircraftCategory>,
+    /// When the aircraft slowed to taxi speed on the ground, cleared once it
+    /// speeds up or takes off.
+    pub taxiing_since: Option<DateTime<Utc>>,
+    /// Whether the feed has reported the aircraft airborne since tracking
+    /// began, so taxiing out for departure isn't mistaken for a landing.
+    pub seen_airborne: bool,
+    /// Position polls in a row that found no state vector.
+    pub missed_polls: u32,
+    /// Whether the track-based ETA was far off the airline's estimate at
+    /// the last position update, so a divergence is announced once.
+    pub eta_diverged: bool,
 
     // Route data (from AviationStack)
+    /// Departure date the schedule was queried for, if the user asked for one.
+    pub flight_date: Option<NaiveDate>,
     pub airline: Option<String>,
     pub aircraft_type: Option<String>,
     pub registration: Option<String>,
@@ -26,114 +139,3019 @@ pub struct Flight {
     pub origin: Option<Airport>,
     pub destination: Option<Airport>,
 
-    pub departure_scheduled: Option<String>,
-    pub departure_estimated: Option<String>,
-    pub departure_actual: Option<String>,
+    pub departure_scheduled: Option<DateTime<FixedOffset>>,
+    pub departure_estimated: Option<DateTime<FixedOffset>>,
+    pub departure_actual: Option<DateTime<FixedOffset>>,
     pub departure_delay: Option<i32>,
 
-    pub arrival_scheduled: Option<String>,
-    pub arrival_estimated: Option<String>,
-    pub arrival_actual: Option<String>,
+    pub arrival_scheduled: Option<DateTime<FixedOffset>>,
+    pub arrival_estimated: Option<DateTime<FixedOffset>>,
+    pub arrival_actual: Option<DateTime<FixedOffset>>,
     pub arrival_delay: Option<i32>,
+    /// Each change in the reported delay, oldest first.
+    pub delay_history: Vec<DelaySample>,
+    /// Schedule times the API sent in a form that couldn't be parsed, kept
+    /// as written so they can still be shown.
+    pub unparsed_times: HashMap<ScheduleField, String>,
+    /// Schedule times sent without an offset that fell on a daylight saving
+    /// change, so they could be an hour off either way.
+    pub uncertain_times: HashSet<ScheduleField>,
 
     pub last_updated: Option<DateTime<Utc>>,
+    /// When the flight moved to the Arrived section of the list.
+    pub arrived_at: Option<DateTime<Utc>>,
+
+    /// User-defined alert rules for this flight.
+    pub alerts: Vec<AlertRule>,
+    /// Roll over to the next day's instance once this one is over.
+    pub recurring: bool,
+    /// Added from the watchlist file, and removed once it is no longer listed.
+    pub from_watchlist: bool,
+    /// Bell, desktop and webhook notifications held back, chosen with `z`.
+    pub muted_until: Option<Mute>,
+
+    // Payloads as last received (see `api::raw_payload`), for the raw view
+    pub raw_state: Option<String>,
+    pub raw_schedule: Option<String>,
 }
 
-#[derive(Debug, Clone, Default)]
-pub struct Airport {
-    pub name: Option<String>,
-    pub iata: Option<String>,
-    pub icao: Option<String>,
+/// A flight's delay as reported by a schedule refresh.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DelaySample {
+    pub at: DateTime<Utc>,
+    /// Minutes late; negative when early.
+    pub minutes: i32,
 }
 
-#[derive(Debug, Clone, Default, PartialEq)]
-pub enum FlightStatus {
-    #[default]
-    Unknown,
-    Scheduled,
-    EnRoute,
-    Landed,
-    OnGround,
-    Delayed,
-    Cancelled,
-    NotFound,
+/// Something that happened to a flight, for the log tab.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    pub at: DateTime<Utc>,
+    pub event: String,
 }
 
-impl FlightStatus {
-    pub fn from_api_status(status: &str) -> Self {
-        match status.to_lowercase().as_str() {
-            "scheduled" => FlightStatus::Scheduled,
-            "active" | "en-route" => FlightStatus::EnRoute,
-            "landed" => FlightStatus::Landed,
-            "delayed" => FlightStatus::Delayed,
-            "cancelled" => FlightStatus::Cancelled,
-            _ => FlightStatus::Unknown,
+/// A position along a flight's track.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackPoint {
+    pub at: DateTime<Utc>,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude_ft: Option<f64>,
+}
+
+/// Where a track reached cruise and where it left it.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CruiseMarkers {
+    pub top_of_climb: Option<TrackPoint>,
+    pub top_of_descent: Option<TrackPoint>,
+}
+
+/// Find the top of climb and top of descent in a track, oldest point first.
+///
+/// Cruise is anywhere within 2,000 ft of the track's highest altitude. The
+/// top of climb is the first point at cruise, provided the track was at
+/// least 5,000 ft below cruise before it; the top of descent is the last
+/// point at cruise, provided the track goes 5,000 ft below cruise after it.
+/// Points without an altitude are skipped.
+pub fn cruise_markers(track: &[TrackPoint]) -> CruiseMarkers {
+    let points: Vec<(&TrackPoint, f64)> = track
+        .iter()
+        .filter_map(|p| Some((p, p.altitude_ft?)))
+        .collect();
+    let Some(peak) = points.iter().map(|(_, alt)| *alt).reduce(f64::max) else {
+        return CruiseMarkers::default();
+    };
+    let cruise = peak - CRUISE_BAND_FT;
+    let below_cruise = |(_, alt): &(&TrackPoint, f64)| *alt <= cruise - CLIMB_DESCENT_FT;
+
+    // The peak itself is at cruise, so both searches succeed
+    let first = points
+        .iter()
+        .position(|(_, alt)| *alt >= cruise)
+        .unwrap_or(0);
+    let last = points
+        .iter()
+        .rposition(|(_, alt)| *alt >= cruise)
+        .unwrap_or(0);
+    CruiseMarkers {
+        top_of_climb: points[..first]
+            .iter()
+            .any(below_cruise)
+            .then(|| *points[first].0),
+        top_of_descent: points[last + 1..]
+            .iter()
+            .any(below_cruise)
+            .then(|| *points[last].0),
+    }
+}
+
+/// Split a track wherever consecutive points are more than `max_gap` apart,
+/// so a chart breaks its line at a coverage gap instead of drawing straight
+/// across it.
+pub fn split_at_gaps(track: &[TrackPoint], max_gap: chrono::Duration) -> Vec<&[TrackPoint]> {
+    track.chunk_by(|a, b| b.at - a.at <= max_gap).collect()
+}
+
+/// A vertical rate report from the position feed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateSample {
+    pub at: DateTime<Utc>,
+    /// Feet per minute; negative when descending.
+    pub fpm: f64,
+}
+
+/// Exponential moving average of vertical rates, oldest sample first,
+/// starting over after any gap longer than two minutes.
+///
+/// ```
+/// use chrono::{Duration, Utc};
+/// use flight_tracker_tui::flight::{smooth_vertical_rate, RateSample};
+///
+/// let now = Utc::now();
+/// let samples = [
+///     RateSample { at: now, fpm: 100.0 },
+///     RateSample { at: now + Duration::seconds(10), fpm: -100.0 },
+/// ];
+/// assert_eq!(smooth_vertical_rate(&samples), Some(20.0));
+/// assert_eq!(smooth_vertical_rate(&[]), None);
+/// ```
+pub fn smooth_vertical_rate(samples: &[RateSample]) -> Option<f64> {
+    let start = samples
+        .windows(2)
+        .rposition(|pair| (pair[1].at - pair[0].at).num_seconds() > RATE_GAP_SECS)
+        .map_or(0, |i| i + 1);
+    let (first, rest) = samples[start..].split_first()?;
+    Some(rest.iter().fold(first.fpm, |average, sample| {
+        average + RATE_SMOOTHING * (sample.fpm - average)
+    }))
+}
+
+impl Flight {
+    /// The delay the schedule reports now: the arrival delay once there is
+    /// one, otherwise the departure delay.
+    pub fn current_delay(&self) -> Option<i32> {
+        self.arrival_delay.or(self.departure_delay)
+    }
+
+    /// The airline flying the aircraft when its callsign shows a different
+    /// operator from the airline selling the flight.
+    pub fn operating_airline(&self) -> Option<&'static str> {
+        let airline = self.airline.as_deref()?;
+        api::operating_airline(&self.flight_number, &self.callsign, airline)
+    }
+
+    /// Whether `query` appears, ignoring case, in the flight number,
+    /// callsign, airline (selling or operating) or an airport code. An empty
+    /// query matches every flight.
+    pub fn matches_text(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return true;
+        }
+        let airports = [&self.origin, &self.destination].into_iter().flatten();
+        let codes = airports.flat_map(|a| [a.iata.as_deref(), a.icao.as_deref()]);
+        [
+            Some(self.flight_number.as_str()),
+            Some(self.callsign.as_str()),
+        ]
+        .into_iter()
+        .chain([self.airline.as_deref(), self.operating_airline()])
+        .chain(codes)
+        .flatten()
+        .any(|field| field.to_lowercase().contains(&query))
+    }
+
+    /// Add the current delay to the history unless it repeats the last
+    /// sample, dropping the oldest past the limit. Returns whether a sample
+    /// was added.
+    pub fn record_delay(&mut self, at: DateTime<Utc>) -> bool {
+        let Some(minutes) = self.current_delay() else {
+            return false;
+        };
+        if self
+            .delay_history
+            .last()
+            .is_some_and(|s| s.minutes == minutes)
+        {
+            return false;
+        }
+        self.delay_history.push(DelaySample { at, minutes });
+        if self.delay_history.len() > MAX_DELAY_HISTORY {
+            self.delay_history.remove(0);
+        }
+        true
+    }
+
+    /// Add the latest vertical rate report to the samples, once per report
+    /// time, dropping the oldest past the limit.
+    pub fn record_vertical_rate(&mut self, now: DateTime<Utc>) {
+        let Some(fpm) = self.vertical_rate else {
+            return;
+        };
+        let at = self.position_time.unwrap_or(now);
+        if self
+            .vertical_rate_samples
+            .last()
+            .is_some_and(|s| s.at >= at)
+        {
+            return;
+        }
+        self.vertical_rate_samples.push(RateSample { at, fpm });
+        if self.vertical_rate_samples.len() > MAX_RATE_SAMPLES {
+            self.vertical_rate_samples.remove(0);
+        }
+    }
+
+    /// Add the latest fix to the track, once per report time.
+    pub fn record_track_point(&mut self) {
+        let (Some(latitude), Some(longitude), Some(at)) =
+            (self.latitude, self.longitude, self.position_time)
+        else {
+            return;
+        };
+        if self.track.last().is_some_and(|p| p.at >= at) {
+            return;
+        }
+        self.track.push(TrackPoint {
+            at,
+            latitude,
+            longitude,
+            altitude_ft: self.altitude_ft,
+        });
+        if self.track.len() > MAX_TRACK_POINTS {
+            self.track.remove(0);
+        }
+    }
+
+    /// Fill in the track with the provider's history of this flight. Only
+    /// points older than the first fix already recorded are used, so live
+    /// fixes are never replaced.
+    pub fn merge_track(&mut self, history: impl IntoIterator<Item = TrackPoint>) {
+        let first = self.track.first().map(|p| p.at);
+        let mut merged: Vec<TrackPoint> = history
+            .into_iter()
+            .filter(|p| first.is_none_or(|first| p.at < first))
+            .collect();
+        merged.sort_by_key(|p| p.at);
+        merged.dedup_by_key(|p| p.at);
+        merged.append(&mut self.track);
+        let excess = merged.len().saturating_sub(MAX_TRACK_POINTS);
+        merged.drain(..excess);
+        self.track = merged;
+    }
+
+    /// The vertical rate to show: the recent reports averaged, or `None`
+    /// when the feed currently gives no rate.
+    pub fn smoothed_vertical_rate(&self) -> Option<f64> {
+        self.vertical_rate?;
+        smooth_vertical_rate(&self.vertical_rate_samples)
+    }
+
+    /// The last few delays and how long ago the first of them was reported,
+    /// e.g. "+15 → +40 → +55 over the last 1h 05m". `None` until the delay
+    /// has changed at least once.
+    pub fn delay_trend(&self, now: DateTime<Utc>) -> Option<String> {
+        if self.delay_history.len() < 2 {
+            return None;
+        }
+        let start = self.delay_history.len().saturating_sub(DELAY_TREND_VALUES);
+        let shown = &self.delay_history[start..];
+        let values: Vec<String> = shown
+            .iter()
+            .map(|s| match s.minutes {
+                0 => "0".to_string(),
+                m => format!("{:+}", m),
+            })
+            .collect();
+        Some(format!(
+            "{} over the last {}",
+            values.join(" → "),
+            format_duration(now - shown[0].at)
+        ))
+    }
+
+    /// What the flight has done so far, oldest first: its departure, each
+    /// delay reported, taxiing and landing, from the times it keeps.
+    pub fn log(&self) -> Vec<LogEntry> {
+        let mut log = Vec::new();
+        if let Some(at) = self.departure_actual {
+            let event = match &self.origin {
+                Some(origin) => format!("Departed {}", origin.code()),
+                None => "Departed".to_string(),
+            };
+            log.push(LogEntry {
+                at: at.to_utc(),
+                event,
+            });
+        }
+        for sample in &self.delay_history {
+            let event = match sample.minutes {
+                0 => "On time".to_string(),
+                m if m > 0 => format!("Running {} min late", m),
+                m => format!("Running {} min early", -m),
+            };
+            log.push(LogEntry {
+                at: sample.at,
+                event,
+            });
+        }
+        if let Some(at) = self.taxiing_since {
+            log.push(LogEntry {
+                at,
+                event: "Taxiing".to_string(),
+            });
+        }
+        // Inferred from the position feed when the schedule has no time
+        let landed = self
+            .arrival_actual
+            .map(|at| at.to_utc())
+            .or(self.arrived_at);
+        if let Some(at) = landed {
+            let event = match &self.destination {
+                Some(destination) => format!("Landed at {}", destination.code()),
+                None => "Landed".to_string(),
+            };
+            log.push(LogEntry { at, event });
+        }
+        log.sort_by_key(|entry| entry.at);
+        log
+    }
+
+    /// Short route label such as "SFO→LHR", once both airports are known.
+    pub fn route_label(&self) -> Option<String> {
+        let (origin, destination) = (self.origin.as_ref()?, self.destination.as_ref()?);
+        Some(format!("{}→{}", origin.code(), destination.code()))
+    }
+
+    /// Great-circle distance from the current position to the destination
+    /// airport, if both are known.
+    pub fn distance_to_destination_km(&self) -> Option<f64> {
+        let (lat, lon) = (self.latitude?, self.longitude?);
+        let (dest_lat, dest_lon) = self.destination.as_ref()?.coordinates()?;
+        Some(geo::haversine_km(lat, lon, dest_lat, dest_lon))
+    }
+
+    /// How far along the great circle between its airports the aircraft
+    /// is, when both airports are in the built-in table and there is a fix.
+    /// The share flown is measured along the route to the point on it
+    /// nearest the aircraft, clamped to 0–1, so a detour, a holding
+    /// pattern or an overshoot never reads below 0% or above 100%.
+    pub fn route_progress(&self) -> Option<RouteProgress> {
+        let (lat, lon) = (self.latitude?, self.longitude?);
+        let (from_lat, from_lon) = self.origin.as_ref()?.coordinates()?;
+        let (to_lat, to_lon) = self.destination.as_ref()?.coordinates()?;
+        let total_km = geo::haversine_km(from_lat, from_lon, to_lat, to_lon);
+        if total_km <= 0.0 {
+            return None;
+        }
+        let along = geo::along_track_km(from_lat, from_lon, to_lat, to_lon, lat, lon);
+        Some(RouteProgress {
+            total_km,
+            flown: (along / total_km).clamp(0.0, 1.0),
+        })
+    }
+
+    /// The airport an aircraft on the ground is at, from the nearest one in
+    /// the built-in table to its last fix.
+    pub fn ground_airport(&self) -> Option<AirportLocation> {
+        if !self.on_ground {
+            return None;
+        }
+        let (airport, _) = airports::nearest(self.latitude?, self.longitude?, AT_AIRPORT_KM)?;
+        Some(airport)
+    }
+
+    /// The airport the aircraft is on the ground at, when it is neither end
+    /// of the scheduled route: most likely a diversion.
+    pub fn possible_diversion(&self) -> Option<AirportLocation> {
+        let airport = self.ground_airport()?;
+        let destination = self.destination.as_ref()?;
+        let on_route = [Some(destination), self.origin.as_ref()]
+            .into_iter()
+            .flatten()
+            .any(|end| end.is(&airport));
+        (!on_route).then_some(airport)
+    }
+
+    /// Roughly where the aircraft is: its last fix, or else the midpoint of
+    /// its route when both airports are in the built-in table.
+    pub fn approximate_position(&self) -> Option<(f64, f64)> {
+        if let (Some(lat), Some(lon)) = (self.latitude, self.longitude) {
+            return Some((lat, lon));
+        }
+        geo::route_midpoint(
+            self.origin.as_ref().and_then(Airport::coordinates),
+            self.destination.as_ref().and_then(Airport::coordinates),
+        )
+    }
+
+    /// Where the aircraft should be by `now`, carried along its heading at
+    /// its ground speed from the last fix. `None` on the ground, without a
+    /// heading, speed or fix time, or once the fix is too old to trust.
+    pub fn estimated_position(&self, now: DateTime<Utc>) -> Option<(f64, f64)> {
+        if self.on_ground {
+            return None;
+        }
+        let (lat, lon) = (self.latitude?, self.longitude?);
+        let (heading, speed) = (self.heading?, self.ground_speed_kts?);
+        let elapsed = (now - self.position_time?).num_milliseconds() as f64 / 1000.0;
+        if !(0.0..=STALE_POSITION_SECS as f64).contains(&elapsed) {
+            return None;
+        }
+        let distance_km = speed * KNOTS_TO_KMH * elapsed / 3600.0;
+        Some(geo::destination_point(lat, lon, heading, distance_km))
+    }
+
+    /// Distance, bearing and elevation of the aircraft as seen by an
+    /// observer at the given position, if the aircraft's position is known.
+    pub fn seen_from(&self, latitude: f64, longitude: f64) -> Option<geo::LookAngles> {
+        const FEET_TO_METERS: f64 = 0.3048;
+
+        let (lat, lon) = (self.latitude?, self.longitude?);
+        let altitude_m = self.altitude_ft.map(|ft| ft * FEET_TO_METERS);
+        Some(geo::look_angles(latitude, longitude, lat, lon, altitude_m))
+    }
+
+    /// Where `now` falls on the journey from departure to arrival, for the
+    /// timeline bar. `None` without both a departure and an arrival time.
+    pub fn journey_progress(&self, now: DateTime<Utc>) -> Option<JourneyProgress> {
+        let scheduled = self.departure_scheduled.map(|t| t.to_utc());
+        let actual = self.departure_actual.map(|t| t.to_utc());
+        let arrival = self
+            .arrival_actual
+            .or(self.arrival_estimated)
+            .or(self.arrival_scheduled)?
+            .to_utc();
+
+        // An early pushback starts the bar at the actual departure
+        let start = match (scheduled, actual) {
+            (Some(s), Some(a)) => s.min(a),
+            (s, a) => s.or(a)?,
+        };
+        let span = (arrival - start).num_seconds().max(1) as f64;
+        let fraction = |t: DateTime<Utc>| ((t - start).num_seconds() as f64 / span).clamp(0.0, 1.0);
+
+        Some(JourneyProgress {
+            departed: actual.map(fraction),
+            now: fraction(now),
+        })
+    }
+
+    /// Scheduled gate-to-gate time. `None` without both scheduled times or
+    /// when the arrival isn't after the departure. Times carry their own
+    /// offsets, so overnight and cross-timezone flights come out right.
+    pub fn block_time(&self) -> Option<chrono::Duration> {
+        let block = self.arrival_scheduled? - self.departure_scheduled?;
+        (block > chrono::Duration::zero()).then_some(block)
+    }
+
+    /// When the flight is expected in: the estimated arrival, else the
+    /// scheduled one. `None` once it has landed, arrived or been cancelled.
+    pub fn eta(&self) -> Option<DateTime<Utc>> {
+        let done = self.arrival_actual.is_some()
+            || self.arrived_at.is_some()
+            || matches!(self.status, FlightStatus::Landed | FlightStatus::Cancelled);
+        if done {
+            return None;
+        }
+        Some(self.arrival_estimated.or(self.arrival_scheduled)?.to_utc())
+    }
+
+    /// How close the ETA is at `now`, for the badge in the flight list.
+    /// Flights past their ETA count as imminent.
+    pub fn eta_band(&self, now: DateTime<Utc>) -> Option<EtaBand> {
+        let left = (self.eta()? - now).num_minutes();
+        Some(if left < ETA_IMMINENT_MINS {
+            EtaBand::Imminent
+        } else if left < ETA_WITHIN_HOUR_MINS {
+            EtaBand::WithinHour
+        } else {
+            EtaBand::Later
+        })
+    }
+
+    /// When the aircraft gets in at its current ground speed, flying the
+    /// great circle from its last fix to the destination. `None` on the
+    /// ground, below [`TRACK_ETA_MIN_SPEED_KTS`], without a fix time or a
+    /// destination in the built-in table, and once the flight is done.
+    pub fn track_eta(&self) -> Option<DateTime<Utc>> {
+        if self.on_ground || self.eta().is_none() {
+            return None;
+        }
+        let speed = self
+            .ground_speed_kts
+            .filter(|&kts| kts >= TRACK_ETA_MIN_SPEED_KTS)?;
+        let hours = self.distance_to_destination_km()? / (speed * KNOTS_TO_KMH);
+        let flying = chrono::Duration::milliseconds((hours * 3_600_000.0).round() as i64);
+        Some(self.position_time? + flying)
+    }
+
+    /// The airline's arrival estimate next to the track-based ETA, while
+    /// both are known.
+    pub fn eta_comparison(&self) -> Option<EtaComparison> {
+        let mut comparison = compare_etas(self.arrival_estimated, self.track_eta())?;
+        comparison.uncertain = self
+            .uncertain_times
+            .contains(&ScheduleField::ArrivalEstimated);
+        Some(comparison)
+    }
+
+    /// Time since departure and time left until arrival, while the flight
+    /// is under way. Departure is the actual time when known; arrival is the
+    /// estimate when there is one. `None` before departure and once landed.
+    pub fn time_en_route(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Option<(chrono::Duration, chrono::Duration)> {
+        if self.arrival_actual.is_some() {
+            return None;
+        }
+        let departure = self.departure_actual.or(self.departure_scheduled)?.to_utc();
+        let arrival = self.arrival_estimated.or(self.arrival_scheduled)?.to_utc();
+        if now < departure {
+            return None;
+        }
+        Some((
+            now - departure,
+            (arrival - now).max(chrono::Duration::zero()),
+        ))
+    }
+
+    pub fn schedule_time(&self, field: ScheduleField) -> Option<DateTime<FixedOffset>> {
+        match field {
+            ScheduleField::DepartureScheduled => self.departure_scheduled,
+            ScheduleField::DepartureEstimated => self.departure_estimated,
+            ScheduleField::DepartureActual => self.departure_actual,
+            ScheduleField::ArrivalScheduled => self.arrival_scheduled,
+            ScheduleField::ArrivalEstimated => self.arrival_estimated,
+            ScheduleField::ArrivalActual => self.arrival_actual,
+        }
+    }
+
+    /// Store a schedule time as sent by the API. A time without an offset
+    /// is taken as local time at the airport when its zone is known. A value
+    /// that can't be parsed is logged and kept in `unparsed_times` instead.
+    pub fn set_schedule_time(&mut self, field: ScheduleField, value: Option<&str>) {
+        let airport = match field {
+            ScheduleField::DepartureScheduled
+            | ScheduleField::DepartureEstimated
+            | ScheduleField::DepartureActual => &self.origin,
+            _ => &self.destination,
+        };
+        let zone = airport
+            .as_ref()
+            .and_then(|a| a.iata.as_deref().or(a.icao.as_deref()))
+            .and_then(airports::timezone);
+        let parsed = value.and_then(|raw| parse_time_in(raw, zone));
+        match (value, parsed) {
+            (Some(raw), None) => {
+                warn!(flight = %self.flight_number, ?field, value = raw, "Unparseable schedule time");
+                self.unparsed_times.insert(field, raw.to_string());
+            }
+            _ => {
+                self.unparsed_times.remove(&field);
+            }
+        }
+        match parsed {
+            Some((_, true)) => {
+                warn!(
+                    flight = %self.flight_number,
+                    ?field,
+                    value,
+                    "Schedule time falls on a daylight saving change; taking the earlier offset"
+                );
+                self.uncertain_times.insert(field);
+            }
+            _ => {
+                self.uncertain_times.remove(&field);
+            }
+        }
+        let slot = match field {
+            ScheduleField::DepartureScheduled => &mut self.departure_scheduled,
+            ScheduleField::DepartureEstimated => &mut self.departure_estimated,
+            ScheduleField::DepartureActual => &mut self.departure_actual,
+            ScheduleField::ArrivalScheduled => &mut self.arrival_scheduled,
+            ScheduleField::ArrivalEstimated => &mut self.arrival_estimated,
+            ScheduleField::ArrivalActual => &mut self.arrival_actual,
+        };
+        *slot = parsed.map(|(time, _)| time);
+    }
+
+    /// A schedule time on `clock`, in the airport's own offset, or as the
+    /// API wrote it if it couldn't be parsed. One that may be an hour off
+    /// is marked "±1h".
+    pub fn format_schedule_time(&self, field: ScheduleField, clock: TimeFormat) -> Option<String> {
+        match self.schedule_time(field) {
+            Some(time) if self.uncertain_times.contains(&field) => {
+                Some(format!("{} ±1h", clock.format(time, false)))
+            }
+            Some(time) => Some(clock.format(time, false)),
+            None => self.unparsed_times.get(&field).cloned(),
+        }
+    }
+
+    /// The flight on one line of at most `width` characters, e.g.
+    /// "UA123 SFO→LHR ✈ FL360 480kt 62% ETA 18:42". Facts are left out,
+    /// least important first, until the line fits: the ground speed, the
+    /// flight level, the share flown, the status, the route, then the ETA.
+    /// The flight number always stays, cut short with "…" if need be.
+    pub fn summary(&self, clock: TimeFormat, width: usize) -> String {
+        let mut facts = vec![(SummaryFact::Number, self.flight_number.clone())];
+        if let Some(route) = self.route_label() {
+            facts.push((SummaryFact::Route, route));
+        }
+        if self.status == FlightStatus::EnRoute {
+            facts.push((SummaryFact::Status, "✈".to_string()));
+            if let Some(alt) = self.altitude_ft {
+                facts.push((SummaryFact::Altitude, format!("FL{:03.0}", alt / 100.0)));
+            }
+            if let Some(kts) = self.ground_speed_kts {
+                facts.push((SummaryFact::Speed, format!("{:.0}kt", kts)));
+            }
+            if let Some(progress) = self.route_progress() {
+                facts.push((SummaryFact::Progress, format!("{}%", progress.percent())));
+            }
+        } else {
+            facts.push((SummaryFact::Status, self.status.to_string()));
+        }
+        // In the destination's own offset, like the schedule times
+        if let Some(eta) = self
+            .eta()
+            .and(self.arrival_estimated.or(self.arrival_scheduled))
+        {
+            facts.push((
+                SummaryFact::Eta,
+                format!("ETA {}", clock.format(eta, false)),
+            ));
+        }
+
+        let length = |facts: &[(SummaryFact, String)]| {
+            facts
+                .iter()
+                .map(|(_, text)| text.chars().count() + 1)
+                .sum::<usize>()
+                - 1
+        };
+        while facts.len() > 1 && length(&facts) > width {
+            let least = (0..facts.len()).min_by_key(|i| facts[*i].0).unwrap_or(0);
+            facts.remove(least);
+        }
+        let line = facts
+            .into_iter()
+            .map(|(_, text)| text)
+            .collect::<Vec<_>>()
+            .join(" ");
+        if line.chars().count() <= width {
+            return line;
+        }
+        let mut cut: String = line.chars().take(width.saturating_sub(1)).collect();
+        if width > 0 {
+            cut.push('…');
+        }
+        cut
+    }
+
+    /// Update the landing heuristics after a state vector has been applied
+    /// at `now`.
+    pub fn note_position(&mut self, now: DateTime<Utc>) {
+        self.missed_polls = 0;
+        if !self.on_ground {
+            self.seen_airborne = true;
+        }
+        let taxiing =
+            self.on_ground && self.ground_speed_kts.is_none_or(|kts| kts < TAXI_SPEED_KTS);
+        if !taxiing {
+            self.taxiing_since = None;
+        } else if self.taxiing_since.is_none() {
+            self.taxiing_since = Some(now);
+        }
+    }
+
+    /// Why the positions alone say the flight has landed, for when no
+    /// schedule reports it: taxiing for longer than `taxi` after having been
+    /// airborne, or `missed_polls` polls in a row without a state vector
+    /// after descending below 5,000 ft. A zero threshold turns that check
+    /// off.
+    pub fn inferred_landing(
+        &self,
+        now: DateTime<Utc>,
+        taxi: chrono::Duration,
+        missed_polls: u32,
+    ) -> Option<&'static str> {
+        if !self.seen_airborne
+            || !matches!(self.status, FlightStatus::EnRoute | FlightStatus::OnGround)
+        {
+            return None;
+        }
+        if taxi > chrono::Duration::zero()
+            && self.on_ground
+            && self.taxiing_since.is_some_and(|since| now - since > taxi)
+        {
+            return Some("taxiing");
+        }
+        if missed_polls > 0
+            && self.missed_polls >= missed_polls
+            && !self.on_ground
+            && self.altitude_ft.is_some_and(|ft| ft < LOST_ON_APPROACH_FT)
+            && self.vertical_rate.is_some_and(|fpm| fpm < 0.0)
+        {
+            return Some("lost on approach");
+        }
+        None
+    }
+
+    /// Why the aircraft being polled may no longer be the one operating the
+    /// flight, as after the airline swaps airframes: while the schedule has
+    /// the flight in the air, the aircraft is parked more than 50 km from
+    /// both ends of the route without having flown (having flown and landed
+    /// there, it has diverted), or has been missing from the feed for five
+    /// polls in a row. `None` without such a sign, or without a schedule to
+    /// go by.
+    pub fn suspected_swap(&self) -> Option<&'static str> {
+        if self.icao24.is_empty()
+            || self.schedule_status != Some(FlightStatus::EnRoute)
+            || self.position_status == Some(FlightStatus::Landed)
+        {
+            return None;
+        }
+        if self.missed_polls >= SWAP_AFTER_MISSED_POLLS {
+            return Some("missing from the feed");
+        }
+        let (Some(lat), Some(lon)) = (self.latitude, self.longitude) else {
+            return None;
+        };
+        let ends: Vec<(f64, f64)> = [&self.origin, &self.destination]
+            .into_iter()
+            .flatten()
+            .filter_map(Airport::coordinates)
+            .collect();
+        let far = |&(end_lat, end_lon): &(f64, f64)| {
+            geo::haversine_km(lat, lon, end_lat, end_lon) > OFF_ROUTE_KM
+        };
+        let off_route = !ends.is_empty() && ends.iter().all(far);
+        let parked = self.on_ground && !self.seen_airborne && self.missed_polls == 0;
+        (parked && off_route).then_some("parked off its route")
+    }
+
+    /// Whether the flight's notifications are muted at `now`.
+    pub fn is_muted(&self, now: DateTime<Utc>) -> bool {
+        alert::is_muted(self.muted_until, &self.status, now)
+    }
+
+    /// Whether the latest fix is recent enough to trust over the schedule.
+    pub fn position_is_fresh(&self, now: DateTime<Utc>) -> bool {
+        self.position_time
+            .is_some_and(|at| (now - at).num_seconds() <= STALE_POSITION_SECS)
+    }
+
+    /// Settle `status` from the position and schedule sources when they
+    /// disagree. A cancellation always wins, as does a landing seen in the
+    /// positions. Otherwise the position-derived status wins while the fix
+    /// is fresh and the schedule's afterwards, with a stale position still
+    /// beating an unknown schedule status. Without either source the status
+    /// is left as it is.
+    pub fn resolve_status(&mut self, now: DateTime<Utc>) {
+        let fresh = self.position_is_fresh(now);
+        self.status = match (self.schedule_status.clone(), self.position_status.clone()) {
+            (Some(FlightStatus::Cancelled), _) => FlightStatus::Cancelled,
+            (_, Some(FlightStatus::Landed)) => FlightStatus::Landed,
+            (_, Some(position)) if fresh => position,
+            (Some(schedule), _) if schedule != FlightStatus::Unknown => schedule,
+            (_, Some(position)) => position,
+            (Some(schedule), None) => schedule,
+            (None, None) => return,
+        };
+    }
+
+    /// The day this instance of the flight operates: the date it was
+    /// searched for, else the local date of its scheduled departure, else
+    /// the local date it arrived.
+    pub fn service_date(&self) -> Option<NaiveDate> {
+        self.flight_date
+            .or_else(|| self.departure_scheduled.map(|t| t.date_naive()))
+            .or_else(|| {
+                self.arrived_at
+                    .map(|t| t.with_timezone(&Local).date_naive())
+            })
+    }
+
+    /// The date a recurring flight should roll over to, once it has landed
+    /// or been cancelled and its day is over by `today`. That's `today`:
+    /// the day after it flew, or later if the app wasn't running at
+    /// midnight.
+    pub fn rollover_date(&self, today: NaiveDate) -> Option<NaiveDate> {
+        let over = self.arrived_at.is_some()
+            || matches!(self.status, FlightStatus::Landed | FlightStatus::Cancelled);
+        if !self.recurring || !over {
+            return None;
+        }
+        (self.service_date()? < today).then_some(today)
+    }
+
+    /// Start over as the instance on `date`: keep the flight number, alert
+    /// rules (re-armed), recurring flag and whether it came from the
+    /// watchlist, and drop everything the providers reported about the
+    /// previous day.
+    pub fn roll_over(&mut self, date: NaiveDate) {
+        let mut alerts = std::mem::take(&mut self.alerts);
+        for rule in &mut alerts {
+            rule.armed = true;
+        }
+        *self = Flight {
+            flight_number: std::mem::take(&mut self.flight_number),
+            flight_date: Some(date),
+            status: FlightStatus::Scheduled,
+            alerts,
+            recurring: true,
+            from_watchlist: self.from_watchlist,
+            ..Default::default()
+        };
+    }
+
+    /// Whether the journey is over: the flight has landed, or has been on the
+    /// ground at its destination for at least `dwell`. An aircraft on the
+    /// ground near an airport missing from the built-in table never counts.
+    pub fn has_arrived(&self, now: DateTime<Utc>, dwell: chrono::Duration) -> bool {
+        match self.status {
+            FlightStatus::Landed => true,
+            FlightStatus::OnGround => {
+                self.on_ground_since
+                    .is_some_and(|since| now - since >= dwell)
+                    && self
+                        .distance_to_destination_km()
+                        .is_some_and(|km| km <= AT_AIRPORT_KM)
+            }
+            _ => false,
+        }
+    }
+
+    /// Classify the flight's current phase from its status and latest position.
+    pub fn phase(&self) -> FlightPhase {
+        /// Vertical rate (ft/min) below which the aircraft counts as level.
+        const LEVEL_FPM: f64 = 300.0;
+        /// Altitude (ft) below which the aircraft is in the terminal area.
+        const TERMINAL_ALTITUDE_FT: f64 = 10_000.0;
+
+        match self.status {
+            FlightStatus::Landed | FlightStatus::Cancelled => return FlightPhase::Finished,
+            FlightStatus::Scheduled | FlightStatus::Delayed | FlightStatus::OnGround
+                if self.altitude_ft.is_none() =>
+            {
+                return FlightPhase::Ground;
+            }
+            _ => {}
+        }
+
+        if self.on_ground {
+            return FlightPhase::Ground;
+        }
+        let Some(altitude) = self.altitude_ft else {
+            return FlightPhase::Unknown;
+        };
+
+        let vertical_rate = self.vertical_rate.unwrap_or(0.0);
+        if vertical_rate < -LEVEL_FPM && altitude < TERMINAL_ALTITUDE_FT {
+            FlightPhase::Approach
+        } else if vertical_rate.abs() > LEVEL_FPM || altitude < TERMINAL_ALTITUDE_FT {
+            FlightPhase::ClimbDescent
+        } else {
+            FlightPhase::Cruise
         }
     }
 }
 
-impl std::fmt::Display for FlightStatus {
+/// Where a flight's position came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PositionSource {
+    /// An OpenSky state vector, normally seconds old.
+    OpenSky,
+    /// AviationStack's `live` block, used when OpenSky has no coverage.
+    /// Updated every few minutes at best, so less fresh.
+    AviationStack,
+}
+
+/// A reading from a position report that can be checked against
+/// [`PositionLimits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Reading {
+    Altitude,
+    GroundSpeed,
+    VerticalRate,
+}
+
+impl Reading {
+    pub const ALL: [Reading; 3] = [
+        Reading::Altitude,
+        Reading::GroundSpeed,
+        Reading::VerticalRate,
+    ];
+}
+
+impl std::fmt::Display for Reading {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            FlightStatus::Unknown => write!(f, "Unknown"),
-            FlightStatus::Scheduled => write!(f, "Scheduled"),
-            FlightStatus::EnRoute => write!(f, "En Route"),
-            FlightStatus::Landed => write!(f, "Landed"),
-            FlightStatus::OnGround => write!(f, "On Ground"),
-            FlightStatus::Delayed => write!(f, "Delayed"),
-            FlightStatus::Cancelled => write!(f, "Cancelled"),
-            FlightStatus::NotFound => write!(f, "Not Found"),
+            Reading::Altitude => write!(f, "altitude"),
+            Reading::GroundSpeed => write!(f, "ground speed"),
+            Reading::VerticalRate => write!(f, "vertical rate"),
         }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Readings no aircraft could report, from the `[position_limits]` table.
+/// ADS-B glitches produce the odd 4,000 kt ground speed; a reading outside
+/// these bounds is ignored and the last good one kept.
+///
+/// ```
+/// use flight_tracker_tui::config::Config;
+/// use flight_tracker_tui::flight::Reading;
+///
+/// let config = Config::parse("[position_limits]\nmax_ground_speed_kts = 700").unwrap();
+/// let limits = config.position_limits;
+/// assert!(limits.allows(Reading::GroundSpeed, 650.0));
+/// assert!(!limits.allows(Reading::GroundSpeed, 4_000.0));
+/// assert!(!limits.allows(Reading::Altitude, -3_000.0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct PositionLimits {
+    pub max_ground_speed_kts: f64,
+    /// Barometric altitude, which reads a little below sea level at the
+    /// lowest airports.
+    pub min_altitude_ft: f64,
+    pub max_altitude_ft: f64,
+    /// Climb or descent rate either way, in ft/min.
+    pub max_vertical_rate_fpm: f64,
+}
 
-    #[test]
-    fn test_flight_status_from_api_status() {
-        assert_eq!(FlightStatus::from_api_status("scheduled"), FlightStatus::Scheduled);
-        assert_eq!(FlightStatus::from_api_status("SCHEDULED"), FlightStatus::Scheduled);
-        assert_eq!(FlightStatus::from_api_status("active"), FlightStatus::EnRoute);
-        assert_eq!(FlightStatus::from_api_status("en-route"), FlightStatus::EnRoute);
-        assert_eq!(FlightStatus::from_api_status("landed"), FlightStatus::Landed);
-        assert_eq!(FlightStatus::from_api_status("delayed"), FlightStatus::Delayed);
-        assert_eq!(FlightStatus::from_api_status("cancelled"), FlightStatus::Cancelled);
-        assert_eq!(FlightStatus::from_api_status("unknown_status"), FlightStatus::Unknown);
-        assert_eq!(FlightStatus::from_api_status(""), FlightStatus::Unknown);
+impl Default for PositionLimits {
+    fn default() -> Self {
+        Self {
+            max_ground_speed_kts: 1_000.0,
+            min_altitude_ft: -1_500.0,
+            max_altitude_ft: 60_000.0,
+            max_vertical_rate_fpm: 10_000.0,
+        }
     }
+}
 
-    #[test]
-    fn test_flight_status_display() {
-        assert_eq!(format!("{}", FlightStatus::Unknown), "Unknown");
-        assert_eq!(format!("{}", FlightStatus::Scheduled), "Scheduled");
-        assert_eq!(format!("{}", FlightStatus::EnRoute), "En Route");
-        assert_eq!(format!("{}", FlightStatus::Landed), "Landed");
-        assert_eq!(format!("{}", FlightStatus::OnGround), "On Ground");
-        assert_eq!(format!("{}", FlightStatus::Delayed), "Delayed");
-        assert_eq!(format!("{}", FlightStatus::Cancelled), "Cancelled");
-        assert_eq!(format!("{}", FlightStatus::NotFound), "Not Found");
+impl PositionLimits {
+    /// Whether `value` is believable for `reading`, in knots, feet or
+    /// feet per minute.
+    pub fn allows(&self, reading: Reading, value: f64) -> bool {
+        match reading {
+            Reading::Altitude => (self.min_altitude_ft..=self.max_altitude_ft).contains(&value),
+            Reading::GroundSpeed => (0.0..=self.max_ground_speed_kts).contains(&value),
+            Reading::VerticalRate => value.abs() <= self.max_vertical_rate_fpm,
+        }
     }
+}
 
-    #[test]
-    fn test_flight_default() {
-        let flight = Flight::default();
+/// How long until a flight's ETA, in the list badge's three colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EtaBand {
+    /// More than an hour away.
+    Later,
+    WithinHour,
+    /// Under 15 minutes away, or overdue.
+    Imminent,
+}
 
-        assert_eq!(flight.flight_number, "");
-        assert_eq!(flight.status, FlightStatus::Unknown);
-        assert!(!flight.on_ground);
-        assert!(flight.latitude.is_none());
-        assert!(flight.origin.is_none());
+/// The airline's arrival estimate and the ETA worked out from the track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EtaComparison {
+    /// As the schedule gave it, in the arrival airport's offset
+    pub airline: DateTime<FixedOffset>,
+    /// In the same offset as `airline`, so the two read side by side
+    pub track: DateTime<FixedOffset>,
+    /// Whether the estimate fell in a daylight saving change and may be an
+    /// hour out, which leaves too much doubt to call a divergence
+    pub uncertain: bool,
+}
+
+impl EtaComparison {
+    /// How much later the track says the flight gets in; negative when it
+    /// says earlier.
+    pub fn gap(&self) -> chrono::Duration {
+        self.track - self.airline
     }
 
-    #[test]
-    fn test_airport_default() {
-        let airport = Airport::default();
+    /// Whether the two are more than [`ETA_DIVERGENCE_MINS`] apart.
+    pub fn diverges(&self) -> bool {
+        !self.uncertain && self.gap().abs() > chrono::Duration::minutes(ETA_DIVERGENCE_MINS)
+    }
 
-        assert!(airport.name.is_none());
-        assert!(airport.iata.is_none());
-        assert!(airport.icao.is_none());
+    /// What a divergence may mean, if there is one. An aircraft on course
+    /// to beat the airline's estimate may be expected to hold; one behind it
+    /// is flying slower or further than planned.
+    pub fn reading(&self) -> Option<&'static str> {
+        if !self.diverges() {
+            None
+        } else if self.gap() < chrono::Duration::zero() {
+            Some("possible hold or early arrival")
+        } else {
+            Some("running behind the airline's estimate")
+        }
+    }
+
+    /// "airline estimate 18:55, track-based 18:31", followed by the reading
+    /// when the two diverge.
+    pub fn summary(&self, clock: TimeFormat) -> String {
+        let uncertain = if self.uncertain { " ±1h" } else { "" };
+        let mut summary = format!(
+            "airline estimate {}{}, track-based {}",
+            clock.format(self.airline, false),
+            uncertain,
+            clock.format(self.track, false)
+        );
+        if let Some(reading) = self.reading() {
+            summary.push_str(" — ");
+            summary.push_str(reading);
+        }
+        summary
+    }
+}
+
+/// Compare the airline's arrival estimate with a track-based ETA. `None`
+/// unless both are known. The comparison is between instants, whatever
+/// offset the estimate was given in; the track ETA is put in the same one.
+///
+/// ```
+/// use chrono::{DateTime, TimeZone, Utc};
+/// use flight_tracker_tui::flight::compare_etas;
+///
+/// let airline = DateTime::parse_from_rfc3339("2025-06-01T18:55:00+09:00").unwrap();
+/// let track = Utc.with_ymd_and_hms(2025, 6, 1, 9, 31, 0).unwrap();
+/// let comparison = compare_etas(Some(airline), Some(track)).unwrap();
+///
+/// assert_eq!(comparison.track.format("%H:%M").to_string(), "18:31");
+/// assert_eq!(comparison.reading(), Some("possible hold or early arrival"));
+/// assert!(compare_etas(None, Some(track)).is_none());
+/// ```
+pub fn compare_etas(
+    airline: Option<DateTime<FixedOffset>>,
+    track: Option<DateTime<Utc>>,
+) -> Option<EtaComparison> {
+    let airline = airline?;
+    Some(EtaComparison {
+        airline,
+        track: track?.with_timezone(airline.offset()),
+        uncertain: false,
+    })
+}
+
+/// Positions along the journey as fractions of the time from departure
+/// (0.0) to arrival (1.0), clamped to that range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JourneyProgress {
+    /// When the aircraft actually left, if it has.
+    pub departed: Option<f64>,
+    /// The current time.
+    pub now: f64,
+}
+
+/// Where the aircraft is along its route, from [`Flight::route_progress`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RouteProgress {
+    /// Great-circle distance from the origin to the destination.
+    pub total_km: f64,
+    /// Share of the route behind the aircraft, from 0.0 to 1.0.
+    pub flown: f64,
+}
+
+impl RouteProgress {
+    /// The share flown as a whole percentage.
+    pub fn percent(&self) -> u8 {
+        (self.flown * 100.0).round() as u8
+    }
+}
+
+/// What [`Flight::summary`] can show, least important first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum SummaryFact {
+    Speed,
+    Altitude,
+    Progress,
+    Status,
+    Route,
+    Eta,
+    Number,
+}
+
+/// One of a flight's six schedule times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScheduleField {
+    DepartureScheduled,
+    DepartureEstimated,
+    DepartureActual,
+    ArrivalScheduled,
+    ArrivalEstimated,
+    ArrivalActual,
+}
+
+/// Parse a schedule time from AviationStack. Times are RFC 3339; one
+/// without an offset is taken as UTC.
+///
+/// ```
+/// use flight_tracker_tui::flight::parse_time;
+///
+/// let time = parse_time("2024-07-14T20:05:00-07:00").unwrap();
+/// assert_eq!(time.offset().local_minus_utc(), -7 * 3600);
+/// assert_eq!(parse_time("2024-07-14T20:05:00"), parse_time("2024-07-14T20:05:00+00:00"));
+/// assert_eq!(parse_time("soon"), None);
+/// ```
+pub fn parse_time(time: &str) -> Option<DateTime<FixedOffset>> {
+    parse_time_in(time, None).map(|(time, _)| time)
+}
+
+/// Like [`parse_time`], but a time without an offset is taken as local time
+/// in `zone` when there is one. Also returns whether that local time was
+/// ambiguous or skipped, see [`resolve_local`].
+pub fn parse_time_in(time: &str, zone: Option<Tz>) -> Option<(DateTime<FixedOffset>, bool)> {
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(time) {
+        return Some((parsed, false));
+    }
+    let naive = NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M"))
+        .ok()?;
+    match zone {
+        Some(zone) => Some(resolve_local(naive, zone)),
+        None => {
+            debug!(value = time, "Schedule time without offset; assuming UTC");
+            Some((naive.and_utc().fixed_offset(), false))
+        }
+    }
+}
+
+/// The instant a local time in `zone` stands for, and whether it was in
+/// doubt. When the clocks go back the hour repeats, and when they go
+/// forward it is skipped; either way the offset in force before the change
+/// is taken and the time is flagged, as it may be an hour off.
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use flight_tracker_tui::flight::resolve_local;
+///
+/// // 01:30 happened twice in New York on 3 November 2024
+/// let naive = NaiveDate::from_ymd_opt(2024, 11, 3).unwrap().and_hms_opt(1, 30, 0).unwrap();
+/// let (time, ambiguous) = resolve_local(naive, chrono_tz::America::New_York);
+/// assert_eq!(time.to_rfc3339(), "2024-11-03T01:30:00-04:00");
+/// assert!(ambiguous);
+/// ```
+pub fn resolve_local(naive: NaiveDateTime, zone: Tz) -> (DateTime<FixedOffset>, bool) {
+    match zone.from_local_datetime(&naive) {
+        LocalResult::Single(time) => (time.fixed_offset(), false),
+        LocalResult::Ambiguous(earlier, _) => (earlier.fixed_offset(), true),
+        LocalResult::None => {
+            // Changes are months apart, so a day earlier is before this one
+            let before = zone
+                .offset_from_utc_datetime(&(naive - chrono::Duration::days(1)))
+                .fix();
+            (
+                DateTime::from_naive_utc_and_offset(naive - before, before),
+                true,
+            )
+        }
+    }
+}
+
+/// Hours and minutes, e.g. "11h 05m", or just "45m" under an hour.
+/// Negative durations show as "0m".
+///
+/// ```
+/// use flight_tracker_tui::flight::format_duration;
+///
+/// assert_eq!(format_duration(chrono::Duration::minutes(665)), "11h 05m");
+/// assert_eq!(format_duration(chrono::Duration::minutes(45)), "45m");
+/// ```
+pub fn format_duration(duration: chrono::Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    if minutes < 60 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+}
+
+/// Likely numbers for the return leg of a flight, best guess first.
+///
+/// Airlines mostly number a route's two directions one apart, the outbound
+/// even and the return odd, so an even number guesses the next one up and
+/// an odd number the one below, with the other neighbour as the fallback.
+/// Leading zeros are kept. Numbers that don't start with an IATA or ICAO
+/// airline designator followed by one to four digits have no guess.
+///
+/// ```
+/// use flight_tracker_tui::flight::return_flight_numbers;
+///
+/// assert_eq!(return_flight_numbers("LH400"), ["LH401", "LH399"]);
+/// assert_eq!(return_flight_numbers("BA0285"), ["BA0284", "BA0286"]);
+/// assert!(return_flight_numbers("G-EUPT").is_empty());
+/// ```
+pub fn return_flight_numbers(flight_number: &str) -> Vec<String> {
+    let flight_number = flight_number.trim().to_uppercase();
+    let split = (2..=3).find(|&at| {
+        flight_number
+            .split_at_checked(at)
+            .is_some_and(|(airline, number)| {
+                // IATA designators may have a digit, ICAO ones never do
+                let letters = airline.chars().filter(|c| c.is_ascii_alphabetic()).count();
+                (letters == at || (at == 2 && letters == 1))
+                    && airline.chars().all(|c| c.is_ascii_alphanumeric())
+                    && (1..=4).contains(&number.len())
+                    && number.chars().all(|c| c.is_ascii_digit())
+            })
+    });
+    let Some((airline, number)) = split.map(|at| flight_number.split_at(at)) else {
+        return Vec::new();
+    };
+    let Ok(n) = number.parse::<u32>() else {
+        return Vec::new();
+    };
+    let neighbours = if n % 2 == 0 {
+        [n + 1, n.wrapping_sub(1)]
+    } else {
+        [n - 1, n + 1]
+    };
+    neighbours
+        .into_iter()
+        .filter(|&m| (1..=9999).contains(&m))
+        .map(|m| format!("{}{:0width$}", airline, m, width = number.len()))
+        .collect()
+}
+
+/// Coarse flight phase, used to pick how often a flight is refreshed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlightPhase {
+    /// Descending below 10,000 ft.
+    Approach,
+    /// Climbing or descending above 10,000 ft, or low and level.
+    ClimbDescent,
+    /// Level flight above 10,000 ft.
+    Cruise,
+    /// On the ground or not yet departed.
+    Ground,
+    /// Landed or cancelled; nothing left to poll for.
+    Finished,
+    /// No position or status to go on.
+    Unknown,
+}
+
+impl FlightPhase {
+    /// Desired refresh interval in seconds, or `None` when the flight no
+    /// longer needs refreshing. `Unknown` uses `default_secs`.
+    ///
+    /// ```
+    /// use flight_tracker_tui::flight::FlightPhase;
+    ///
+    /// assert_eq!(FlightPhase::Approach.refresh_interval_secs(30), Some(10));
+    /// assert_eq!(FlightPhase::Unknown.refresh_interval_secs(30), Some(30));
+    /// assert_eq!(FlightPhase::Finished.refresh_interval_secs(30), None);
+    /// ```
+    pub fn refresh_interval_secs(self, default_secs: u64) -> Option<u64> {
+        match self {
+            FlightPhase::Approach => Some(10),
+            FlightPhase::ClimbDescent => Some(20),
+            FlightPhase::Cruise => Some(60),
+            FlightPhase::Ground => Some(300),
+            FlightPhase::Finished => None,
+            FlightPhase::Unknown => Some(default_secs),
+        }
+    }
+}
+
+impl std::fmt::Display for FlightPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FlightPhase::Approach => write!(f, "approach"),
+            FlightPhase::ClimbDescent => write!(f, "climb/descent"),
+            FlightPhase::Cruise => write!(f, "cruise"),
+            FlightPhase::Ground => write!(f, "ground"),
+            FlightPhase::Finished => write!(f, "finished"),
+            FlightPhase::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// An origin or destination airport.
+#[derive(Debug, Clone, Default)]
+pub struct Airport {
+    pub name: Option<String>,
+    pub iata: Option<String>,
+    pub icao: Option<String>,
+}
+
+impl Airport {
+    /// IATA code, else ICAO code, else "???".
+    pub fn code(&self) -> &str {
+        self.iata
+            .as_deref()
+            .or(self.icao.as_deref())
+            .unwrap_or("???")
+    }
+
+    /// ICAO code, looked up from the IATA code when the provider left it out.
+    pub fn icao_code(&self) -> Option<&str> {
+        self.icao.as_deref().or_else(|| {
+            self.iata
+                .as_deref()
+                .and_then(airports::lookup)
+                .map(|a| a.icao)
+        })
+    }
+
+    /// Whether this is the given airport from the built-in table.
+    pub fn is(&self, location: &AirportLocation) -> bool {
+        self.iata.as_deref() == Some(location.iata) || self.icao.as_deref() == Some(location.icao)
+    }
+
+    /// Latitude and longitude from the built-in airport table.
+    pub fn coordinates(&self) -> Option<(f64, f64)> {
+        self.iata
+            .as_deref()
+            .and_then(airports::lookup)
+            .or_else(|| self.icao.as_deref().and_then(airports::lookup))
+            .map(|a| (a.latitude, a.longitude))
+    }
+}
+
+/// High-level flight status shown in the UI.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum FlightStatus {
+    #[default]
+    Unknown,
+    Scheduled,
+    EnRoute,
+    Landed,
+    OnGround,
+    Delayed,
+    Cancelled,
+    NotFound,
+}
+
+impl FlightStatus {
+    /// Map an AviationStack `flight_status` string onto a status.
+    ///
+    /// ```
+    /// use flight_tracker_tui::flight::FlightStatus;
+    ///
+    /// assert_eq!(FlightStatus::from_api_status("Landed"), FlightStatus::Landed);
+    /// assert_eq!(FlightStatus::from_api_status("diverted"), FlightStatus::Unknown);
+    /// ```
+    pub fn from_api_status(status: &str) -> Self {
+        match status.to_lowercase().as_str() {
+            "scheduled" => FlightStatus::Scheduled,
+            "active" | "en-route" => FlightStatus::EnRoute,
+            "landed" => FlightStatus::Landed,
+            "delayed" => FlightStatus::Delayed,
+            "cancelled" => FlightStatus::Cancelled,
+            _ => FlightStatus::Unknown,
+        }
+    }
+}
+
+impl std::fmt::Display for FlightStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FlightStatus::Unknown => write!(f, "Unknown"),
+            FlightStatus::Scheduled => write!(f, "Scheduled"),
+            FlightStatus::EnRoute => write!(f, "En Route"),
+            FlightStatus::Landed => write!(f, "Landed"),
+            FlightStatus::OnGround => write!(f, "On Ground"),
+            FlightStatus::Delayed => write!(f, "Delayed"),
+            FlightStatus::Cancelled => write!(f, "Cancelled"),
+            FlightStatus::NotFound => write!(f, "Not Found"),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Airport {
+    /// An airport known only by its IATA code.
+    pub(crate) fn test(iata: &str) -> Self {
+        Airport {
+            iata: Some(iata.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+impl Flight {
+    /// UA123 from San Francisco to New York, cruising at FL360 and 480 kt
+    /// over Oklahoma.
+    pub(crate) fn test_cruising() -> Self {
+        Flight {
+            flight_number: "UA123".to_string(),
+            status: FlightStatus::EnRoute,
+            origin: Some(Airport::test("SFO")),
+            destination: Some(Airport::test("JFK")),
+            latitude: Some(36.5),
+            longitude: Some(-98.0),
+            altitude_ft: Some(36_000.0),
+            ground_speed_kts: Some(480.0),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flight_status_from_api_status() {
+        assert_eq!(
+            FlightStatus::from_api_status("scheduled"),
+            FlightStatus::Scheduled
+        );
+        assert_eq!(
+            FlightStatus::from_api_status("SCHEDULED"),
+            FlightStatus::Scheduled
+        );
+        assert_eq!(
+            FlightStatus::from_api_status("active"),
+            FlightStatus::EnRoute
+        );
+        assert_eq!(
+            FlightStatus::from_api_status("en-route"),
+            FlightStatus::EnRoute
+        );
+        assert_eq!(
+            FlightStatus::from_api_status("landed"),
+            FlightStatus::Landed
+        );
+        assert_eq!(
+            FlightStatus::from_api_status("delayed"),
+            FlightStatus::Delayed
+        );
+        assert_eq!(
+            FlightStatus::from_api_status("cancelled"),
+            FlightStatus::Cancelled
+        );
+        assert_eq!(
+            FlightStatus::from_api_status("unknown_status"),
+            FlightStatus::Unknown
+        );
+        assert_eq!(FlightStatus::from_api_status(""), FlightStatus::Unknown);
+    }
+
+    #[test]
+    fn test_flight_status_display() {
+        assert_eq!(format!("{}", FlightStatus::Unknown), "Unknown");
+        assert_eq!(format!("{}", FlightStatus::Scheduled), "Scheduled");
+        assert_eq!(format!("{}", FlightStatus::EnRoute), "En Route");
+        assert_eq!(format!("{}", FlightStatus::Landed), "Landed");
+        assert_eq!(format!("{}", FlightStatus::OnGround), "On Ground");
+        assert_eq!(format!("{}", FlightStatus::Delayed), "Delayed");
+        assert_eq!(format!("{}", FlightStatus::Cancelled), "Cancelled");
+        assert_eq!(format!("{}", FlightStatus::NotFound), "Not Found");
+    }
+
+    #[test]
+    fn test_flight_default() {
+        let flight = Flight::default();
+
+        assert_eq!(flight.flight_number, "");
+        assert_eq!(flight.status, FlightStatus::Unknown);
+        assert!(!flight.on_ground);
+        assert!(flight.latitude.is_none());
+        assert!(flight.origin.is_none());
+    }
+
+    #[test]
+    fn test_airport_default() {
+        let airport = Airport::default();
+
+        assert!(airport.name.is_none());
+        assert!(airport.iata.is_none());
+        assert!(airport.icao.is_none());
+    }
+
+    #[test]
+    fn test_airport_coordinates_fallback_to_icao() {
+        let airport = Airport {
+            iata: Some("???".to_string()),
+            icao: Some("EGLL".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(airport.coordinates(), Some((51.4700, -0.4543)));
+        assert_eq!(Airport::default().coordinates(), None);
+    }
+
+    #[test]
+    fn test_airport_icao_code() {
+        let airport = Airport::test("LHR");
+        assert_eq!(airport.icao_code(), Some("EGLL"));
+        let airport = Airport {
+            icao: Some("KJFK".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(airport.icao_code(), Some("KJFK"));
+        assert_eq!(Airport::default().icao_code(), None);
+    }
+
+    #[test]
+    fn test_distance_to_destination() {
+        let mut flight = Flight {
+            latitude: Some(37.6213),
+            longitude: Some(-122.3790),
+            destination: Some(Airport::test("LAX")),
+            ..Default::default()
+        };
+        let d = flight.distance_to_destination_km().unwrap();
+        assert!((d - 543.0).abs() < 5.0);
+
+        flight.latitude = None;
+        assert!(flight.distance_to_destination_km().is_none());
+    }
+
+    #[test]
+    fn test_route_progress() {
+        // Over Oklahoma on the way from San Francisco to New York
+        let mut flight = Flight::test_cruising();
+        let progress = flight.route_progress().unwrap();
+        assert!(
+            (progress.total_km - 4152.0).abs() < 10.0,
+            "{}",
+            progress.total_km
+        );
+        assert!(
+            (40..=50).contains(&progress.percent()),
+            "{}",
+            progress.percent()
+        );
+
+        // At either end
+        (flight.latitude, flight.longitude) = (Some(37.6213), Some(-122.3790));
+        assert_eq!(flight.route_progress().unwrap().percent(), 0);
+        flight.latitude = Some(40.6413);
+        flight.longitude = Some(-73.7781);
+        assert_eq!(flight.route_progress().unwrap().percent(), 100);
+
+        // Without a fix or an airport the table knows, nothing
+        flight.latitude = None;
+        assert!(flight.route_progress().is_none());
+        flight.latitude = Some(36.5);
+        flight.destination = Some(Airport::test("???"));
+        assert!(flight.route_progress().is_none());
+    }
+
+    #[test]
+    fn test_route_progress_stays_in_range_off_the_great_circle() {
+        let at = |lat, lon| {
+            let flight = Flight {
+                latitude: Some(lat),
+                longitude: Some(lon),
+                origin: Some(Airport::test("SFO")),
+                destination: Some(Airport::test("LAX")),
+                ..Default::default()
+            };
+            flight.route_progress().unwrap()
+        };
+        // Routed out over the Pacific abeam Los Angeles, having flown far
+        // more than the direct distance
+        let detour = at(32.0, -121.5);
+        assert!((0.0..=1.0).contains(&detour.flown));
+        assert!(detour.percent() > 80, "{}", detour.percent());
+        // Overshooting the destination, and holding short of the origin
+        assert_eq!(at(32.0, -117.0).percent(), 100);
+        assert_eq!(at(38.5, -123.0).percent(), 0);
+        // Same airport at both ends: no route to measure
+        let flight = Flight {
+            latitude: Some(37.0),
+            longitude: Some(-122.0),
+            origin: Some(Airport::test("SFO")),
+            destination: Some(Airport::test("SFO")),
+            ..Default::default()
+        };
+        assert!(flight.route_progress().is_none());
+    }
+
+    #[test]
+    fn test_approximate_position() {
+        let mut flight = Flight {
+            origin: Some(Airport::test("JFK")),
+            destination: Some(Airport::test("LHR")),
+            ..Default::default()
+        };
+        // Great-circle midpoint of JFK-LHR lies south of Greenland
+        let (lat, lon) = flight.approximate_position().unwrap();
+        assert!((lat - 52.4).abs() < 1.0, "lat {lat}");
+        assert!((lon + 41.2).abs() < 1.0, "lon {lon}");
+
+        // A live fix wins over the route
+        flight.latitude = Some(50.0);
+        flight.longitude = Some(-20.0);
+        assert_eq!(flight.approximate_position(), Some((50.0, -20.0)));
+
+        assert!(Flight::default().approximate_position().is_none());
+    }
+
+    fn airborne(altitude_ft: f64, vertical_rate: f64) -> Flight {
+        Flight {
+            status: FlightStatus::EnRoute,
+            altitude_ft: Some(altitude_ft),
+            vertical_rate: Some(vertical_rate),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_flight_phase() {
+        let cases = [
+            (
+                "final approach",
+                airborne(4_000.0, -800.0),
+                FlightPhase::Approach,
+            ),
+            (
+                "initial climb",
+                airborne(4_000.0, 2_000.0),
+                FlightPhase::ClimbDescent,
+            ),
+            (
+                "low and level",
+                airborne(4_000.0, 0.0),
+                FlightPhase::ClimbDescent,
+            ),
+            (
+                "top of descent",
+                airborne(30_000.0, -1_500.0),
+                FlightPhase::ClimbDescent,
+            ),
+            ("cruise", airborne(37_000.0, 64.0), FlightPhase::Cruise),
+            (
+                "cruise without vertical rate",
+                Flight {
+                    vertical_rate: None,
+                    ..airborne(37_000.0, 0.0)
+                },
+                FlightPhase::Cruise,
+            ),
+            (
+                "taxiing",
+                Flight {
+                    on_ground: true,
+                    ..airborne(0.0, 0.0)
+                },
+                FlightPhase::Ground,
+            ),
+            (
+                "scheduled",
+                Flight {
+                    status: FlightStatus::Scheduled,
+                    ..Default::default()
+                },
+                FlightPhase::Ground,
+            ),
+            (
+                "landed with stale position",
+                Flight {
+                    status: FlightStatus::Landed,
+                    ..airborne(2_000.0, -700.0)
+                },
+                FlightPhase::Finished,
+            ),
+            (
+                "cancelled",
+                Flight {
+                    status: FlightStatus::Cancelled,
+                    ..Default::default()
+                },
+                FlightPhase::Finished,
+            ),
+            ("no data", Flight::default(), FlightPhase::Unknown),
+        ];
+
+        for (name, flight, expected) in cases {
+            assert_eq!(flight.phase(), expected, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_phase_refresh_intervals() {
+        assert_eq!(FlightPhase::Approach.refresh_interval_secs(30), Some(10));
+        assert_eq!(
+            FlightPhase::ClimbDescent.refresh_interval_secs(30),
+            Some(20)
+        );
+        assert_eq!(FlightPhase::Cruise.refresh_interval_secs(30), Some(60));
+        assert_eq!(FlightPhase::Ground.refresh_interval_secs(30), Some(300));
+        assert_eq!(FlightPhase::Finished.refresh_interval_secs(30), None);
+        assert_eq!(FlightPhase::Unknown.refresh_interval_secs(45), Some(45));
+    }
+
+    fn scheduled_journey() -> Flight {
+        Flight {
+            departure_scheduled: time("2024-07-14T10:00:00+00:00"),
+            arrival_scheduled: time("2024-07-14T20:00:00+00:00"),
+            ..Default::default()
+        }
+    }
+
+    fn time(time: &str) -> Option<DateTime<FixedOffset>> {
+        Some(parse_time(time).unwrap())
+    }
+
+    fn utc(time: &str) -> DateTime<Utc> {
+        parse_time(time).unwrap().to_utc()
+    }
+
+    #[test]
+    fn test_journey_progress() {
+        let mut flight = scheduled_journey();
+
+        let progress = flight
+            .journey_progress(utc("2024-07-14T09:00:00Z"))
+            .unwrap();
+        assert_eq!(
+            progress,
+            JourneyProgress {
+                departed: None,
+                now: 0.0
+            }
+        );
+
+        // Left an hour late; an estimated arrival replaces the scheduled one
+        flight.departure_actual = time("2024-07-14T11:00:00+00:00");
+        flight.arrival_estimated = time("2024-07-14T20:00:00+00:00");
+        flight.arrival_scheduled = time("2024-07-14T19:00:00+00:00");
+        let progress = flight
+            .journey_progress(utc("2024-07-14T16:00:00Z"))
+            .unwrap();
+        assert_eq!(progress.departed, Some(0.1));
+        assert_eq!(progress.now, 0.6);
+
+        let progress = flight
+            .journey_progress(utc("2024-07-15T00:00:00Z"))
+            .unwrap();
+        assert_eq!(progress.now, 1.0);
+    }
+
+    #[test]
+    fn test_journey_progress_early_departure() {
+        let mut flight = scheduled_journey();
+        flight.departure_actual = time("2024-07-14T09:00:00+00:00");
+
+        let progress = flight
+            .journey_progress(utc("2024-07-14T09:00:00Z"))
+            .unwrap();
+        assert_eq!(
+            progress,
+            JourneyProgress {
+                departed: Some(0.0),
+                now: 0.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_journey_progress_degenerate_times() {
+        // Arrival before departure (local times labelled as UTC) is clamped
+        let mut flight = scheduled_journey();
+        flight.arrival_scheduled = time("2024-07-14T06:00:00+00:00");
+        let progress = flight
+            .journey_progress(utc("2024-07-14T08:00:00Z"))
+            .unwrap();
+        assert_eq!(progress.now, 0.0);
+        let progress = flight
+            .journey_progress(utc("2024-07-14T12:00:00Z"))
+            .unwrap();
+        assert_eq!(progress.now, 1.0);
+
+        // Missing or unparseable times
+        flight.set_schedule_time(ScheduleField::ArrivalScheduled, Some("soon"));
+        assert!(flight
+            .journey_progress(utc("2024-07-14T12:00:00Z"))
+            .is_none());
+        assert!(Flight::default()
+            .journey_progress(utc("2024-07-14T12:00:00Z"))
+            .is_none());
+    }
+
+    #[test]
+    fn test_block_time_across_midnight_and_timezones() {
+        let mut flight = scheduled_journey();
+        assert_eq!(flight.block_time(), Some(chrono::Duration::hours(10)));
+
+        // SFO 20:05 PDT to FRA 16:10 CEST the next day is 11h 05m
+        flight.departure_scheduled = time("2024-07-14T20:05:00-07:00");
+        flight.arrival_scheduled = time("2024-07-15T16:10:00+02:00");
+        let block = flight.block_time().unwrap();
+        assert_eq!(format_duration(block), "11h 05m");
+
+        // Negative or zero spans and missing endpoints are skipped
+        flight.arrival_scheduled = time("2024-07-14T20:05:00-07:00");
+        assert_eq!(flight.block_time(), None);
+        flight.arrival_scheduled = time("2024-07-14T19:00:00-07:00");
+        assert_eq!(flight.block_time(), None);
+        flight.arrival_scheduled = None;
+        assert_eq!(flight.block_time(), None);
+    }
+
+    #[test]
+    fn test_time_en_route() {
+        let mut flight = scheduled_journey();
+        assert_eq!(flight.time_en_route(utc("2024-07-14T09:00:00Z")), None);
+
+        // Late departure: elapsed counts from the actual time, remaining to
+        // the estimated arrival
+        flight.departure_actual = time("2024-07-14T11:00:00+00:00");
+        flight.arrival_estimated = time("2024-07-14T20:30:00+00:00");
+        let (elapsed, remaining) = flight.time_en_route(utc("2024-07-14T13:15:00Z")).unwrap();
+        assert_eq!(format_duration(elapsed), "2h 15m");
+        assert_eq!(format_duration(remaining), "7h 15m");
+
+        // Running past the estimate leaves nothing remaining, not a negative
+        let (_, remaining) = flight.time_en_route(utc("2024-07-14T21:00:00Z")).unwrap();
+        assert_eq!(remaining, chrono::Duration::zero());
+
+        flight.arrival_actual = time("2024-07-14T20:40:00+00:00");
+        assert_eq!(flight.time_en_route(utc("2024-07-14T21:00:00Z")), None);
+    }
+
+    #[test]
+    fn test_eta_bands() {
+        let mut flight = scheduled_journey();
+        assert_eq!(flight.eta(), Some(utc("2024-07-14T20:00:00Z")));
+        assert_eq!(
+            flight.eta_band(utc("2024-07-14T18:59:00Z")),
+            Some(EtaBand::Later)
+        );
+        assert_eq!(
+            flight.eta_band(utc("2024-07-14T19:00:00Z")),
+            Some(EtaBand::Later)
+        );
+        assert_eq!(
+            flight.eta_band(utc("2024-07-14T19:00:30Z")),
+            Some(EtaBand::WithinHour)
+        );
+        assert_eq!(
+            flight.eta_band(utc("2024-07-14T19:45:30Z")),
+            Some(EtaBand::Imminent)
+        );
+        assert_eq!(
+            flight.eta_band(utc("2024-07-14T20:30:00Z")),
+            Some(EtaBand::Imminent)
+        );
+
+        // The estimate wins over the schedule
+        flight.arrival_estimated = time("2024-07-14T21:30:00+00:00");
+        assert_eq!(
+            flight.eta_band(utc("2024-07-14T20:00:00Z")),
+            Some(EtaBand::Later)
+        );
+
+        flight.status = FlightStatus::Landed;
+        assert_eq!(flight.eta(), None);
+        assert_eq!(
+            Flight::default().eta_band(utc("2024-07-14T20:00:00Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_compare_etas() {
+        // Given in Tokyo time, compared as instants
+        let airline = time("2024-07-14T18:55:00+09:00");
+        let compare = |track: &str| compare_etas(airline, Some(utc(track))).unwrap();
+
+        let early = compare("2024-07-14T09:31:00Z");
+        assert_eq!(early.gap(), chrono::Duration::minutes(-24));
+        assert_eq!(early.reading(), Some("possible hold or early arrival"));
+        assert_eq!(
+            early.summary(TimeFormat::TwentyFourHour),
+            "airline estimate 18:55, track-based 18:31 — possible hold or early arrival"
+        );
+
+        let late = compare("2024-07-14T10:20:00Z");
+        assert_eq!(
+            late.reading(),
+            Some("running behind the airline's estimate")
+        );
+
+        // Within 15 minutes either way is in agreement
+        for track in [
+            "2024-07-14T09:40:00Z",
+            "2024-07-14T10:10:00Z",
+            "2024-07-14T09:55:00Z",
+        ] {
+            let close = compare(track);
+            assert!(!close.diverges(), "{track}");
+            assert_eq!(close.reading(), None);
+        }
+        assert_eq!(
+            compare("2024-07-14T10:10:00Z").summary(TimeFormat::TwelveHour),
+            "airline estimate 6:55 pm, track-based 7:10 pm"
+        );
+
+        // An estimate that may be an hour out can't be called either way
+        let doubtful = EtaComparison {
+            uncertain: true,
+            ..early
+        };
+        assert!(!doubtful.diverges());
+        assert!(doubtful
+            .summary(TimeFormat::TwentyFourHour)
+            .contains("18:55 ±1h,"));
+
+        assert_eq!(compare_etas(None, Some(utc("2024-07-14T09:31:00Z"))), None);
+        assert_eq!(compare_etas(airline, None), None);
+    }
+
+    #[test]
+    fn test_track_eta() {
+        let mut flight = Flight {
+            latitude: Some(40.0),
+            longitude: Some(-80.0),
+            ground_speed_kts: Some(450.0),
+            position_time: Some(utc("2024-07-14T18:00:00Z")),
+            destination: Some(Airport::test("JFK")),
+            ..scheduled_journey()
+        };
+        let km = flight.distance_to_destination_km().unwrap();
+        let minutes = (km / (450.0 * KNOTS_TO_KMH) * 60.0).round() as i64;
+        let eta = flight.track_eta().unwrap();
+        assert_eq!((eta - utc("2024-07-14T18:00:00Z")).num_minutes(), minutes);
+        assert!((30..=45).contains(&minutes), "{minutes}");
+
+        // Only the airline's estimate takes part, not the schedule
+        assert_eq!(flight.eta_comparison(), None);
+        flight.arrival_estimated = time("2024-07-14T19:30:00+00:00");
+        assert!(flight.eta_comparison().unwrap().diverges());
+
+        flight.ground_speed_kts = Some(80.0);
+        assert_eq!(flight.track_eta(), None);
+        flight.ground_speed_kts = Some(450.0);
+        flight.on_ground = true;
+        assert_eq!(flight.track_eta(), None);
+        flight.on_ground = false;
+        flight.status = FlightStatus::Landed;
+        assert_eq!(flight.track_eta(), None);
+    }
+
+    #[test]
+    fn test_summary_drops_the_least_important_facts_first() {
+        let mut flight = Flight {
+            destination: Some(Airport::test("LHR")),
+            latitude: Some(62.0),
+            longitude: Some(-60.0),
+            altitude_ft: Some(36_020.0),
+            ground_speed_kts: Some(480.4),
+            arrival_estimated: time("2024-07-15T18:42:00+01:00"),
+            ..Flight::test_cruising()
+        };
+        let clock = TimeFormat::TwentyFourHour;
+        let full = "UA123 SFO→LHR ✈ FL360 480kt 58% ETA 18:42";
+        assert_eq!(flight.summary(clock, 80), full);
+        assert_eq!(flight.summary(clock, full.chars().count()), full);
+
+        let narrower = [
+            (40, "UA123 SFO→LHR ✈ FL360 58% ETA 18:42"),
+            (34, "UA123 SFO→LHR ✈ 58% ETA 18:42"),
+            (28, "UA123 SFO→LHR ✈ ETA 18:42"),
+            (24, "UA123 SFO→LHR ETA 18:42"),
+            (20, "UA123 ETA 18:42"),
+            (12, "UA123"),
+            (4, "UA1…"),
+            (0, ""),
+        ];
+        for (width, line) in narrower {
+            assert_eq!(flight.summary(clock, width), line, "{width}");
+            assert!(line.chars().count() <= width);
+        }
+
+        // Before departure the status stands in for the aircraft's state
+        flight.status = FlightStatus::Scheduled;
+        assert_eq!(
+            flight.summary(TimeFormat::TwelveHour, 80),
+            "UA123 SFO→LHR Scheduled ETA 6:42 pm"
+        );
+        flight.status = FlightStatus::Landed;
+        assert_eq!(flight.summary(clock, 80), "UA123 SFO→LHR Landed");
+    }
+
+    #[test]
+    fn test_resolve_local_across_dst_changes() {
+        let local = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M").unwrap();
+        let resolve = |s: &str, zone| {
+            let (time, doubtful) = resolve_local(local(s), zone);
+            format!(
+                "{}{}",
+                time.to_rfc3339(),
+                if doubtful { " ±1h" } else { "" }
+            )
+        };
+        let new_york = chrono_tz::America::New_York;
+        // An ordinary evening and the hours either side of each change
+        assert_eq!(
+            resolve("2024-07-14T20:05", new_york),
+            "2024-07-14T20:05:00-04:00"
+        );
+        assert_eq!(
+            resolve("2024-11-03T00:59", new_york),
+            "2024-11-03T00:59:00-04:00"
+        );
+        assert_eq!(
+            resolve("2024-11-03T02:00", new_york),
+            "2024-11-03T02:00:00-05:00"
+        );
+        assert_eq!(
+            resolve("2024-03-10T01:59", new_york),
+            "2024-03-10T01:59:00-05:00"
+        );
+        assert_eq!(
+            resolve("2024-03-10T03:00", new_york),
+            "2024-03-10T03:00:00-04:00"
+        );
+
+        // The repeated hour takes daylight time, the first pass through it
+        assert_eq!(
+            resolve("2024-11-03T01:30", new_york),
+            "2024-11-03T01:30:00-04:00 ±1h"
+        );
+        // The skipped hour is read on standard time, landing after the change
+        let (skipped, doubtful) = resolve_local(local("2024-03-10T02:30"), new_york);
+        assert!(doubtful);
+        assert_eq!(skipped.to_utc(), utc("2024-03-10T07:30:00Z"));
+
+        // London goes back at 02:00 BST on the last Sunday of October
+        let london = chrono_tz::Europe::London;
+        assert_eq!(
+            resolve("2024-10-27T01:15", london),
+            "2024-10-27T01:15:00+01:00 ±1h"
+        );
+        assert!(resolve("2024-03-31T01:15", london).ends_with("±1h"));
+    }
+
+    #[test]
+    fn test_naive_schedule_times_use_the_airport_zone() {
+        let mut flight = Flight {
+            origin: Some(Airport::test("JFK")),
+            destination: Some(Airport::test("LHR")),
+            ..Default::default()
+        };
+        flight.set_schedule_time(
+            ScheduleField::DepartureScheduled,
+            Some("2024-11-03T01:30:00"),
+        );
+        flight.set_schedule_time(ScheduleField::ArrivalScheduled, Some("2024-11-03T13:40:00"));
+        assert_eq!(flight.departure_scheduled, time("2024-11-03T05:30:00Z"));
+        assert_eq!(flight.arrival_scheduled, time("2024-11-03T13:40:00Z"));
+        let clock = TimeFormat::TwentyFourHour;
+        assert_eq!(
+            flight.format_schedule_time(ScheduleField::DepartureScheduled, clock),
+            Some("01:30 ±1h".to_string())
+        );
+        assert_eq!(
+            flight.format_schedule_time(ScheduleField::ArrivalScheduled, clock),
+            Some("13:40".to_string())
+        );
+
+        // A later value with an offset clears the doubt
+        flight.set_schedule_time(
+            ScheduleField::DepartureScheduled,
+            Some("2024-11-03T01:30:00-05:00"),
+        );
+        assert!(flight.uncertain_times.is_empty());
+
+        // Without a known zone the time is still taken as UTC
+        flight.origin = Some(Airport::test("ZZZ"));
+        flight.set_schedule_time(
+            ScheduleField::DepartureScheduled,
+            Some("2024-11-03T01:30:00"),
+        );
+        assert_eq!(flight.departure_scheduled, time("2024-11-03T01:30:00Z"));
+        assert!(flight.uncertain_times.is_empty());
+    }
+
+    #[test]
+    fn test_set_schedule_time() {
+        let mut flight = Flight::default();
+        flight.set_schedule_time(
+            ScheduleField::DepartureScheduled,
+            Some("2024-07-14T20:05:00-07:00"),
+        );
+        assert_eq!(flight.departure_scheduled, time("2024-07-15T03:05:00Z"));
+        // Shown in the airport's own offset
+        assert_eq!(
+            flight.format_schedule_time(
+                ScheduleField::DepartureScheduled,
+                TimeFormat::TwentyFourHour
+            ),
+            Some("20:05".to_string())
+        );
+
+        // Unparseable values are kept raw until a good value replaces them
+        flight.set_schedule_time(ScheduleField::DepartureScheduled, Some("TBA"));
+        assert_eq!(flight.departure_scheduled, None);
+        assert_eq!(
+            flight.format_schedule_time(
+                ScheduleField::DepartureScheduled,
+                TimeFormat::TwentyFourHour
+            ),
+            Some("TBA".to_string())
+        );
+        flight.set_schedule_time(ScheduleField::DepartureScheduled, None);
+        assert!(flight.unparsed_times.is_empty());
+        assert_eq!(
+            flight.format_schedule_time(
+                ScheduleField::DepartureScheduled,
+                TimeFormat::TwentyFourHour
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_operating_airline() {
+        let mut flight = Flight {
+            flight_number: "U28341".to_string(),
+            callsign: "EJU73AB".to_string(),
+            ..Default::default()
+        };
+        // Nothing to compare against without the marketing airline
+        assert_eq!(flight.operating_airline(), None);
+
+        flight.airline = Some("easyJet".to_string());
+        assert_eq!(flight.operating_airline(), Some("easyJet Europe"));
+
+        flight.callsign = "EZY8341".to_string();
+        assert_eq!(flight.operating_airline(), None);
+    }
+
+    #[test]
+    fn test_matches_text() {
+        let flight = Flight {
+            flight_number: "U28341".to_string(),
+            callsign: "EJU73AB".to_string(),
+            airline: Some("easyJet".to_string()),
+            origin: Some(Airport {
+                iata: Some("LGW".to_string()),
+                icao: Some("EGKK".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        for query in [
+            "",
+            "  ",
+            "u283",
+            "eju73",
+            "EASYJET",
+            "jet europe",
+            "lgw",
+            "egk",
+        ] {
+            assert!(flight.matches_text(query), "{query:?}");
+        }
+        for query in ["BA", "EGLL", "easyjet uk"] {
+            assert!(!flight.matches_text(query), "{query:?}");
+        }
+        // Airports that aren't known yet match nothing
+        assert!(!Flight::default().matches_text("LGW"));
+    }
+
+    #[test]
+    fn test_record_delay_ignores_repeats() {
+        let mut flight = Flight::default();
+        let start = utc("2024-07-14T10:00:00Z");
+        let at = |mins| start + chrono::Duration::minutes(mins);
+
+        // Nothing to record without a delay
+        assert!(!flight.record_delay(at(0)));
+
+        flight.departure_delay = Some(15);
+        assert!(flight.record_delay(at(0)));
+        assert!(!flight.record_delay(at(30)));
+        assert!(!flight.record_delay(at(60)));
+        assert_eq!(flight.delay_history.len(), 1);
+
+        // Once there is an arrival delay it takes over
+        flight.arrival_delay = Some(40);
+        assert!(flight.record_delay(at(90)));
+        assert_eq!(
+            flight.delay_history[1],
+            DelaySample {
+                at: at(90),
+                minutes: 40
+            }
+        );
+
+        // A delay that goes away and comes back is a change both times
+        flight.arrival_delay = Some(15);
+        assert!(flight.record_delay(at(120)));
+        flight.arrival_delay = Some(40);
+        assert!(flight.record_delay(at(150)));
+        assert_eq!(flight.delay_history.len(), 4);
+    }
+
+    fn rate_samples(start: DateTime<Utc>, values: &[(i64, f64)]) -> Vec<RateSample> {
+        values
+            .iter()
+            .map(|&(secs, fpm)| RateSample {
+                at: start + chrono::Duration::seconds(secs),
+                fpm,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_smoothed_rate_converges() {
+        let start = utc("2024-07-14T10:00:00Z");
+        // Jitter around level flight stays close to zero
+        let jitter = rate_samples(start, &[(0, 64.0), (10, -64.0), (20, 64.0), (30, -64.0)]);
+        assert!(smooth_vertical_rate(&jitter).unwrap().abs() < 40.0);
+
+        // A steady climb pulls the average up towards it
+        let climb: Vec<(i64, f64)> = (0..12).map(|i| (i * 10, 2_000.0)).collect();
+        let mut samples = rate_samples(start, &[(-10, 0.0)]);
+        samples.extend(rate_samples(start, &climb));
+        let mut previous = 0.0;
+        for n in 2..=samples.len() {
+            let smoothed = smooth_vertical_rate(&samples[..n]).unwrap();
+            assert!(smoothed > previous && smoothed <= 2_000.0);
+            previous = smoothed;
+        }
+        assert!(previous > 1_990.0);
+    }
+
+    #[test]
+    fn test_smoothed_rate_resets_after_gap() {
+        let start = utc("2024-07-14T10:00:00Z");
+        let samples = rate_samples(start, &[(0, 1_500.0), (10, 1_500.0), (400, -800.0)]);
+        assert_eq!(smooth_vertical_rate(&samples), Some(-800.0));
+
+        // A gap of exactly two minutes still counts as continuous
+        let samples = rate_samples(start, &[(0, 1_000.0), (120, 0.0)]);
+        assert_eq!(smooth_vertical_rate(&samples), Some(600.0));
+    }
+
+    #[test]
+    fn test_record_vertical_rate() {
+        let start = utc("2024-07-14T10:00:00Z");
+        let mut flight = Flight::default();
+        flight.record_vertical_rate(start);
+        assert!(flight.vertical_rate_samples.is_empty());
+
+        flight.vertical_rate = Some(500.0);
+        flight.position_time = Some(start);
+        flight.record_vertical_rate(start);
+        // The same report polled again is one sample
+        flight.record_vertical_rate(start + chrono::Duration::seconds(10));
+        assert_eq!(flight.vertical_rate_samples.len(), 1);
+
+        for i in 1..=10 {
+            flight.position_time = Some(start + chrono::Duration::seconds(i * 10));
+            flight.record_vertical_rate(start);
+        }
+        assert_eq!(flight.vertical_rate_samples.len(), MAX_RATE_SAMPLES);
+        assert_eq!(flight.smoothed_vertical_rate(), Some(500.0));
+
+        flight.vertical_rate = None;
+        assert_eq!(flight.smoothed_vertical_rate(), None);
+    }
+
+    fn track_point(at: DateTime<Utc>, altitude_ft: f64) -> TrackPoint {
+        TrackPoint {
+            at,
+            latitude: 51.0,
+            longitude: -1.0,
+            altitude_ft: Some(altitude_ft),
+        }
+    }
+
+    #[test]
+    fn test_record_track_point() {
+        let start = utc("2024-07-14T10:00:00Z");
+        let mut flight = Flight {
+            position_time: Some(start),
+            ..Default::default()
+        };
+        flight.record_track_point();
+        assert!(flight.track.is_empty(), "no position yet");
+
+        flight.latitude = Some(51.0);
+        flight.longitude = Some(-1.0);
+        flight.altitude_ft = Some(3_000.0);
+        flight.record_track_point();
+        flight.record_track_point();
+        assert_eq!(flight.track, vec![track_point(start, 3_000.0)]);
+    }
+
+    #[test]
+    fn test_merge_track_keeps_live_fixes() {
+        let start = utc("2024-07-14T10:00:00Z");
+        let at = |mins| start + chrono::Duration::minutes(mins);
+        let mut flight = Flight {
+            track: vec![track_point(at(30), 35_000.0), track_point(at(31), 35_100.0)],
+            ..Default::default()
+        };
+
+        // The provider's points overlap the live ones and arrive unordered
+        flight.merge_track([
+            track_point(at(20), 30_000.0),
+            track_point(at(0), 0.0),
+            track_point(at(10), 12_000.0),
+            track_point(at(10), 12_000.0),
+            track_point(at(30), 34_000.0),
+            track_point(at(31), 34_000.0),
+        ]);
+        let altitudes: Vec<f64> = flight.track.iter().filter_map(|p| p.altitude_ft).collect();
+        assert_eq!(altitudes, vec![0.0, 12_000.0, 30_000.0, 35_000.0, 35_100.0]);
+
+        // With nothing recorded yet the whole history is taken
+        let mut fresh = Flight::default();
+        fresh.merge_track([track_point(at(0), 0.0), track_point(at(5), 4_000.0)]);
+        assert_eq!(fresh.track.len(), 2);
+    }
+
+    /// A track with one point per 10 minutes at these altitudes.
+    fn profile(altitudes: &[f64]) -> Vec<TrackPoint> {
+        let start = utc("2024-07-14T10:00:00Z");
+        altitudes
+            .iter()
+            .enumerate()
+            .map(|(i, &alt)| track_point(start + chrono::Duration::minutes(i as i64 * 10), alt))
+            .collect()
+    }
+
+    #[test]
+    fn test_cruise_markers() {
+        let altitude = |p: Option<TrackPoint>| p.and_then(|p| p.altitude_ft);
+
+        // Climb with a step climb in cruise, then descent
+        let track = profile(&[
+            0.0, 15_000.0, 34_000.0, 35_000.0, 37_000.0, 36_500.0, 20_000.0, 0.0,
+        ]);
+        let markers = cruise_markers(&track);
+        assert_eq!(markers.top_of_climb, Some(track[3]));
+        assert_eq!(markers.top_of_descent, Some(track[5]));
+
+        // Tracking began in cruise and the flight is still there
+        let markers = cruise_markers(&profile(&[36_000.0, 36_100.0, 35_900.0]));
+        assert_eq!(markers, CruiseMarkers::default());
+
+        // Descending, with no climb seen
+        let markers = cruise_markers(&profile(&[38_000.0, 38_000.0, 30_000.0, 10_000.0]));
+        assert_eq!(markers.top_of_climb, None);
+        assert_eq!(altitude(markers.top_of_descent), Some(38_000.0));
+
+        // A short hop that never levels off peaks once
+        let markers = cruise_markers(&profile(&[0.0, 9_000.0, 12_000.0, 6_000.0, 0.0]));
+        assert_eq!(altitude(markers.top_of_climb), Some(12_000.0));
+        assert_eq!(altitude(markers.top_of_descent), Some(12_000.0));
+    }
+
+    #[test]
+    fn test_cruise_markers_without_altitudes() {
+        assert_eq!(cruise_markers(&[]), CruiseMarkers::default());
+        let mut track = profile(&[0.0, 30_000.0, 0.0]);
+        for point in &mut track {
+            point.altitude_ft = None;
+        }
+        assert_eq!(cruise_markers(&track), CruiseMarkers::default());
+        // On the ground throughout
+        assert_eq!(
+            cruise_markers(&profile(&[0.0, 0.0])),
+            CruiseMarkers::default()
+        );
+    }
+
+    #[test]
+    fn test_split_at_gaps() {
+        let mut track = profile(&[0.0, 10_000.0, 20_000.0, 30_000.0]);
+        track[3].at += chrono::Duration::minutes(30);
+        let segments = split_at_gaps(&track, chrono::Duration::minutes(15));
+        assert_eq!(segments, vec![&track[..3], &track[3..]]);
+
+        // Exactly the limit is not a gap
+        assert_eq!(
+            split_at_gaps(&track[..3], chrono::Duration::minutes(10)).len(),
+            1
+        );
+        assert!(split_at_gaps(&[], chrono::Duration::minutes(10)).is_empty());
+    }
+
+    #[test]
+    fn test_delay_history_is_bounded() {
+        let mut flight = Flight::default();
+        let start = utc("2024-07-14T10:00:00Z");
+        for minutes in 0..20 {
+            flight.departure_delay = Some(minutes);
+            flight.record_delay(start + chrono::Duration::minutes(minutes as i64));
+        }
+        assert_eq!(flight.delay_history.len(), MAX_DELAY_HISTORY);
+        assert_eq!(flight.delay_history[0].minutes, 8);
+        assert_eq!(flight.delay_history.last().unwrap().minutes, 19);
+    }
+
+    #[test]
+    fn test_delay_trend() {
+        let mut flight = Flight::default();
+        let start = utc("2024-07-14T10:00:00Z");
+        let at = |mins| start + chrono::Duration::minutes(mins);
+
+        flight.departure_delay = Some(15);
+        flight.record_delay(at(0));
+        assert_eq!(flight.delay_trend(at(10)), None);
+
+        flight.departure_delay = Some(40);
+        flight.record_delay(at(30));
+        flight.departure_delay = Some(55);
+        flight.record_delay(at(50));
+        assert_eq!(
+            flight.delay_trend(at(60)).as_deref(),
+            Some("+15 → +40 → +55 over the last 1h 00m")
+        );
+
+        // Only the latest values are listed, and the span starts at the first shown
+        for (minutes, mins_in) in [(0, 70), (-5, 80)] {
+            flight.departure_delay = Some(minutes);
+            flight.record_delay(at(mins_in));
+        }
+        assert_eq!(
+            flight.delay_trend(at(90)).as_deref(),
+            Some("+40 → +55 → 0 → -5 over the last 1h 00m")
+        );
+    }
+
+    #[test]
+    fn test_log_in_time_order() {
+        let start = utc("2024-07-14T10:00:00Z");
+        let at = |mins| start + chrono::Duration::minutes(mins);
+        let mut flight = Flight {
+            origin: Some(Airport::test("LHR")),
+            departure_actual: Some(at(20).fixed_offset()),
+            taxiing_since: Some(at(5)),
+            arrived_at: Some(at(600)),
+            ..Default::default()
+        };
+        assert!(Flight::default().log().is_empty());
+
+        for (minutes, mins_in) in [(15, 0), (0, 30), (-5, 300)] {
+            flight.departure_delay = Some(minutes);
+            flight.record_delay(at(mins_in));
+        }
+        let log: Vec<_> = flight.log().into_iter().map(|e| (e.at, e.event)).collect();
+        assert_eq!(
+            log,
+            [
+                (at(0), "Running 15 min late".to_string()),
+                (at(5), "Taxiing".to_string()),
+                (at(20), "Departed LHR".to_string()),
+                (at(30), "On time".to_string()),
+                (at(300), "Running 5 min early".to_string()),
+                (at(600), "Landed".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(chrono::Duration::zero()), "0m");
+        assert_eq!(format_duration(chrono::Duration::seconds(59)), "0m");
+        assert_eq!(format_duration(chrono::Duration::minutes(60)), "1h 00m");
+        assert_eq!(format_duration(chrono::Duration::minutes(-30)), "0m");
+    }
+
+    #[test]
+    fn test_return_flight_numbers() {
+        assert_eq!(return_flight_numbers("LH401"), ["LH400", "LH402"]);
+        assert_eq!(return_flight_numbers(" ua900 "), ["UA901", "UA899"]);
+        // Digits in the designator, and ICAO designators
+        assert_eq!(return_flight_numbers("U21234"), ["U21235", "U21233"]);
+        assert_eq!(return_flight_numbers("BAW285"), ["BAW284", "BAW286"]);
+        // No flight 0 or 10000
+        assert_eq!(return_flight_numbers("DL1"), ["DL2"]);
+        assert_eq!(return_flight_numbers("DL9999"), ["DL9998"]);
+        assert!(return_flight_numbers("LH400A").is_empty());
+        assert!(return_flight_numbers("12345").is_empty());
+    }
+
+    #[test]
+    fn test_seen_from_observer() {
+        // Over Windsor at 5,000 ft, watched from Heathrow
+        let mut flight = Flight {
+            latitude: Some(51.4839),
+            longitude: Some(-0.6044),
+            altitude_ft: Some(5_000.0),
+            ..Default::default()
+        };
+        let view = flight.seen_from(51.4700, -0.4543).unwrap();
+        assert!((view.distance_km - 10.5).abs() < 0.5);
+        assert!((view.bearing - 279.0).abs() < 2.0);
+        assert!(view.elevation.unwrap() > 8.0);
+
+        flight.altitude_ft = None;
+        assert!(flight
+            .seen_from(51.4700, -0.4543)
+            .unwrap()
+            .elevation
+            .is_none());
+        flight.latitude = None;
+        assert!(flight.seen_from(51.4700, -0.4543).is_none());
+    }
+
+    #[test]
+    fn test_estimated_position() {
+        let fix = Flight {
+            latitude: Some(0.0),
+            longitude: Some(0.0),
+            position_time: Some(utc("2024-03-01T12:00:00Z")),
+            heading: Some(90.0),
+            // 1 nm a minute
+            ground_speed_kts: Some(60.0),
+            ..Default::default()
+        };
+
+        let (lat, lon) = fix.estimated_position(utc("2024-03-01T12:00:30Z")).unwrap();
+        assert!(lat.abs() < 1e-9);
+        assert!((geo::haversine_km(0.0, 0.0, lat, lon) - 0.926).abs() < 1e-3);
+        assert!(lon > 0.0);
+
+        assert_eq!(
+            fix.estimated_position(utc("2024-03-01T12:00:00Z")),
+            Some((0.0, 0.0))
+        );
+        // Too stale, or from the future
+        assert!(fix
+            .estimated_position(utc("2024-03-01T12:05:01Z"))
+            .is_none());
+        assert!(fix
+            .estimated_position(utc("2024-03-01T11:59:59Z"))
+            .is_none());
+
+        let parked = Flight {
+            on_ground: true,
+            ..fix.clone()
+        };
+        assert!(parked
+            .estimated_position(utc("2024-03-01T12:00:30Z"))
+            .is_none());
+        let no_speed = Flight {
+            ground_speed_kts: None,
+            ..fix
+        };
+        assert!(no_speed
+            .estimated_position(utc("2024-03-01T12:00:30Z"))
+            .is_none());
+    }
+
+    /// Apply a synthetic position report at `at` the way the app does.
+    fn report(flight: &mut Flight, on_ground: bool, kts: f64, ft: f64, fpm: f64, at: &str) {
+        flight.on_ground = on_ground;
+        flight.ground_speed_kts = Some(kts);
+        flight.altitude_ft = Some(ft);
+        flight.vertical_rate = Some(fpm);
+        flight.status = if on_ground {
+            FlightStatus::OnGround
+        } else {
+            FlightStatus::EnRoute
+        };
+        flight.note_position(utc(at));
+    }
+
+    #[test]
+    fn test_resolve_status_precedence() {
+        use FlightStatus::*;
+        let now = utc("2024-03-01T12:00:00Z");
+        let fresh = Some(utc("2024-03-01T11:56:00Z"));
+        let stale = Some(utc("2024-03-01T11:54:00Z"));
+        // (schedule, position, fix time, resolved)
+        let table = [
+            (None, None, None, NotFound),
+            (Some(Scheduled), None, None, Scheduled),
+            (Some(Unknown), None, None, Unknown),
+            (None, Some(EnRoute), stale, EnRoute),
+            (Some(Scheduled), Some(EnRoute), fresh, EnRoute),
+            (Some(Scheduled), Some(EnRoute), stale, Scheduled),
+            (Some(Scheduled), Some(EnRoute), None, Scheduled),
+            (Some(Landed), Some(EnRoute), fresh, EnRoute),
+            (Some(Landed), Some(OnGround), stale, Landed),
+            (Some(Delayed), Some(OnGround), fresh, OnGround),
+            (Some(Unknown), Some(EnRoute), stale, EnRoute),
+            (Some(Cancelled), Some(EnRoute), fresh, Cancelled),
+            (Some(Cancelled), Some(Landed), fresh, Cancelled),
+            (Some(EnRoute), Some(Landed), stale, Landed),
+        ];
+        for (schedule, position, position_time, expected) in table {
+            let mut flight = Flight {
+                status: NotFound,
+                schedule_status: schedule.clone(),
+                position_status: position.clone(),
+                position_time,
+                ..Default::default()
+            };
+            flight.resolve_status(now);
+            assert_eq!(
+                flight.status, expected,
+                "{schedule:?} {position:?} {position_time:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_landing_inferred_from_taxiing() {
+        let taxi = chrono::Duration::minutes(5);
+        let mut flight = Flight::default();
+        report(
+            &mut flight,
+            false,
+            150.0,
+            1_500.0,
+            -800.0,
+            "2024-03-01T12:00:00Z",
+        );
+        // Rollout, then taxiing in
+        report(&mut flight, true, 90.0, 0.0, 0.0, "2024-03-01T12:01:00Z");
+        assert_eq!(flight.taxiing_since, None);
+        report(&mut flight, true, 20.0, 0.0, 0.0, "2024-03-01T12:02:00Z");
+        report(&mut flight, true, 12.0, 0.0, 0.0, "2024-03-01T12:05:00Z");
+        assert_eq!(flight.taxiing_since, Some(utc("2024-03-01T12:02:00Z")));
+        assert_eq!(
+            flight.inferred_landing(utc("2024-03-01T12:07:00Z"), taxi, 3),
+            None
+        );
+        assert_eq!(
+            flight.inferred_landing(utc("2024-03-01T12:07:01Z"), taxi, 3),
+            Some("taxiing")
+        );
+        // Turned off
+        assert_eq!(
+            flight.inferred_landing(utc("2024-03-01T13:00:00Z"), chrono::Duration::zero(), 3),
+            None
+        );
+
+        // Speeding up again restarts the clock
+        report(&mut flight, true, 40.0, 0.0, 0.0, "2024-03-01T12:06:00Z");
+        assert_eq!(flight.taxiing_since, None);
+        report(&mut flight, true, 10.0, 0.0, 0.0, "2024-03-01T12:07:00Z");
+        assert_eq!(
+            flight.inferred_landing(utc("2024-03-01T12:10:00Z"), taxi, 3),
+            None
+        );
+
+        // Already landed by the schedule
+        flight.status = FlightStatus::Landed;
+        assert_eq!(
+            flight.inferred_landing(utc("2024-03-01T13:00:00Z"), taxi, 3),
+            None
+        );
+    }
+
+    #[test]
+    fn test_taxi_out_is_not_a_landing() {
+        let taxi = chrono::Duration::minutes(5);
+        let mut flight = Flight::default();
+        report(&mut flight, true, 0.0, 0.0, 0.0, "2024-03-01T12:00:00Z");
+        report(&mut flight, true, 15.0, 0.0, 0.0, "2024-03-01T12:20:00Z");
+        assert!(!flight.seen_airborne);
+        assert_eq!(
+            flight.inferred_landing(utc("2024-03-01T12:30:00Z"), taxi, 3),
+            None
+        );
+
+        // Takeoff
+        report(
+            &mut flight,
+            false,
+            160.0,
+            800.0,
+            2_000.0,
+            "2024-03-01T12:31:00Z",
+        );
+        assert!(flight.seen_airborne);
+        assert_eq!(flight.taxiing_since, None);
+    }
+
+    #[test]
+    fn test_landing_inferred_from_lost_signal() {
+        let taxi = chrono::Duration::minutes(5);
+        let now = utc("2024-03-01T12:10:00Z");
+        let mut flight = Flight::default();
+        report(
+            &mut flight,
+            false,
+            180.0,
+            3_000.0,
+            -900.0,
+            "2024-03-01T12:00:00Z",
+        );
+        flight.missed_polls = 2;
+        assert_eq!(flight.inferred_landing(now, taxi, 3), None);
+        flight.missed_polls = 3;
+        assert_eq!(
+            flight.inferred_landing(now, taxi, 3),
+            Some("lost on approach")
+        );
+        assert_eq!(flight.inferred_landing(now, taxi, 0), None);
+
+        // A position resets the count
+        report(
+            &mut flight,
+            false,
+            170.0,
+            2_500.0,
+            -700.0,
+            "2024-03-01T12:11:00Z",
+        );
+        assert_eq!(flight.missed_polls, 0);
+
+        // Out of coverage at cruise, or climbing out after departure
+        for (ft, fpm) in [(35_000.0, -500.0), (3_000.0, 1_500.0), (3_000.0, 0.0)] {
+            let mut flight = Flight::default();
+            report(&mut flight, false, 300.0, ft, fpm, "2024-03-01T12:00:00Z");
+            flight.missed_polls = 10;
+            assert_eq!(
+                flight.inferred_landing(now, taxi, 3),
+                None,
+                "{ft} ft, {fpm} fpm"
+            );
+        }
+    }
+
+    fn day(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_rollover_waits_for_the_day_to_end() {
+        let mut flight = Flight {
+            flight_number: "LH400".to_string(),
+            status: FlightStatus::Landed,
+            recurring: true,
+            departure_scheduled: time("2024-07-15T10:00:00+02:00"),
+            ..Default::default()
+        };
+        // Landed in the afternoon, still the same day
+        assert_eq!(flight.rollover_date(day("2024-07-15")), None);
+        // Midnight
+        assert_eq!(
+            flight.rollover_date(day("2024-07-16")),
+            Some(day("2024-07-16"))
+        );
+        // Not running over a long weekend: straight to today
+        assert_eq!(
+            flight.rollover_date(day("2024-07-19")),
+            Some(day("2024-07-19"))
+        );
+
+        // The searched date beats the departure time
+        flight.flight_date = Some(day("2024-07-16"));
+        assert_eq!(flight.rollover_date(day("2024-07-16")), None);
+
+        // Only recurring flights that are over
+        flight.flight_date = None;
+        flight.status = FlightStatus::EnRoute;
+        assert_eq!(flight.rollover_date(day("2024-07-16")), None);
+        flight.status = FlightStatus::Landed;
+        flight.recurring = false;
+        assert_eq!(flight.rollover_date(day("2024-07-16")), None);
+    }
+
+    #[test]
+    fn test_rollover_around_midnight_uses_the_departure_day() {
+        // 23:50 departure lands after midnight; its day is still the 15th
+        let flight = Flight {
+            status: FlightStatus::Landed,
+            recurring: true,
+            departure_scheduled: time("2024-07-15T23:50:00+02:00"),
+            arrival_actual: time("2024-07-16T01:05:00+02:00"),
+            ..Default::default()
+        };
+        assert_eq!(flight.service_date(), Some(day("2024-07-15")));
+        assert_eq!(
+            flight.rollover_date(day("2024-07-16")),
+            Some(day("2024-07-16"))
+        );
+
+        // Without any date to go on there is nothing to roll over from
+        let undated = Flight {
+            status: FlightStatus::Landed,
+            recurring: true,
+            ..Default::default()
+        };
+        assert_eq!(undated.rollover_date(day("2024-07-16")), None);
+    }
+
+    #[test]
+    fn test_rollover_skips_cancelled_days() {
+        let mut flight = Flight {
+            status: FlightStatus::Cancelled,
+            recurring: true,
+            flight_date: Some(day("2024-07-16")),
+            ..Default::default()
+        };
+        // Tomorrow's instance cancelled in advance: wait for its day to pass
+        assert_eq!(flight.rollover_date(day("2024-07-15")), None);
+        assert_eq!(flight.rollover_date(day("2024-07-16")), None);
+        assert_eq!(
+            flight.rollover_date(day("2024-07-17")),
+            Some(day("2024-07-17"))
+        );
+
+        // Moved to the Arrived section on the ground, without a Landed status
+        flight.status = FlightStatus::OnGround;
+        flight.arrived_at = Some(utc("2024-07-16T12:00:00Z"));
+        assert_eq!(
+            flight.rollover_date(day("2024-07-17")),
+            Some(day("2024-07-17"))
+        );
+    }
+
+    #[test]
+    fn test_roll_over_resets_the_previous_instance() {
+        let mut rule = AlertRule::new(crate::alert::AlertCondition::Landed);
+        rule.armed = false;
+        let mut flight = Flight {
+            flight_number: "LH400".to_string(),
+            icao24: "3c6444".to_string(),
+            status: FlightStatus::Landed,
+            recurring: true,
+            latitude: Some(40.6),
+            arrival_delay: Some(25),
+            delay_history: vec![DelaySample {
+                at: utc("2024-07-15T12:00:00Z"),
+                minutes: 25,
+            }],
+            arrived_at: Some(utc("2024-07-15T16:00:00Z")),
+            alerts: vec![rule],
+            ..Default::default()
+        };
+
+        flight.roll_over(day("2024-07-16"));
+        assert_eq!(flight.flight_number, "LH400");
+        assert_eq!(flight.flight_date, Some(day("2024-07-16")));
+        assert_eq!(flight.status, FlightStatus::Scheduled);
+        assert!(flight.recurring);
+        assert!(flight.alerts[0].armed);
+        assert!(flight.icao24.is_empty());
+        assert_eq!(flight.latitude, None);
+        assert_eq!(flight.arrival_delay, None);
+        assert!(flight.delay_history.is_empty());
+        assert_eq!(flight.arrived_at, None);
+    }
+
+    #[test]
+    fn test_has_arrived() {
+        let now = utc("2024-03-01T12:00:00Z");
+        let dwell = chrono::Duration::minutes(10);
+        let taxiing = Flight {
+            status: FlightStatus::OnGround,
+            on_ground: true,
+            on_ground_since: Some(utc("2024-03-01T11:55:00Z")),
+            // On a taxiway at Heathrow
+            latitude: Some(51.465),
+            longitude: Some(-0.44),
+            destination: Some(Airport::test("LHR")),
+            ..Default::default()
+        };
+        assert!(!taxiing.has_arrived(now, dwell));
+
+        let parked = Flight {
+            on_ground_since: Some(utc("2024-03-01T11:50:00Z")),
+            ..taxiing.clone()
+        };
+        assert!(parked.has_arrived(now, dwell));
+
+        // On the ground long enough, but at the origin
+        let at_origin = Flight {
+            latitude: Some(40.64),
+            longitude: Some(-73.78),
+            ..parked.clone()
+        };
+        assert!(!at_origin.has_arrived(now, dwell));
+
+        let no_destination = Flight {
+            destination: None,
+            ..parked
+        };
+        assert!(!no_destination.has_arrived(now, dwell));
+
+        let landed = Flight {
+            status: FlightStatus::Landed,
+            ..Default::default()
+        };
+        assert!(landed.has_arrived(now, dwell));
+        assert!(!Flight::default().has_arrived(now, dwell));
+    }
+
+    #[test]
+    fn test_ground_airport_and_diversion() {
+        // On the ground at Gander, on the way from Heathrow to JFK
+        let diverted = Flight {
+            on_ground: true,
+            latitude: Some(48.94),
+            longitude: Some(-54.57),
+            origin: Some(Airport::test("LHR")),
+            destination: Some(Airport::test("JFK")),
+            ..Default::default()
+        };
+        assert_eq!(diverted.ground_airport().map(|a| a.icao), Some("CYQX"));
+        assert_eq!(
+            diverted.possible_diversion().map(|a| a.name),
+            Some("Gander Intl")
+        );
+
+        // Airborne over the same spot, it isn't at the airport
+        let overhead = Flight {
+            on_ground: false,
+            ..diverted.clone()
+        };
+        assert_eq!(overhead.ground_airport(), None);
+
+        // Either end of the route, matched by ICAO code too, is no diversion
+        let arrived = Flight {
+            latitude: Some(40.64),
+            longitude: Some(-73.78),
+            ..diverted.clone()
+        };
+        assert_eq!(arrived.ground_airport().map(|a| a.iata), Some("JFK"));
+        assert_eq!(arrived.possible_diversion(), None);
+        let before_departure = Flight {
+            origin: Some(Airport {
+                icao: Some("CYQX".to_string()),
+                ..Default::default()
+            }),
+            ..diverted.clone()
+        };
+        assert_eq!(before_departure.possible_diversion(), None);
+
+        // Without a scheduled destination there is nothing to divert from
+        let unscheduled = Flight {
+            destination: None,
+            ..diverted
+        };
+        assert!(unscheduled.ground_airport().is_some());
+        assert_eq!(unscheduled.possible_diversion(), None);
+    }
+
+    #[test]
+    fn test_suspected_swap() {
+        // Scheduled Heathrow to JFK and in the air, but the aircraft polled
+        // sits at Frankfurt
+        let parked = Flight {
+            icao24: "a808c4".to_string(),
+            schedule_status: Some(FlightStatus::EnRoute),
+            position_status: Some(FlightStatus::OnGround),
+            on_ground: true,
+            latitude: Some(50.03),
+            longitude: Some(8.57),
+            origin: Some(Airport::test("LHR")),
+            destination: Some(Airport::test("JFK")),
+            ..Default::default()
+        };
+        assert_eq!(parked.suspected_swap(), Some("parked off its route"));
+
+        // At the gate at either end, it may just be late
+        let at_origin = Flight {
+            latitude: Some(51.47),
+            longitude: Some(-0.45),
+            ..parked.clone()
+        };
+        assert_eq!(at_origin.suspected_swap(), None);
+        // Having flown, it landed somewhere else: a diversion
+        let diverted = Flight {
+            seen_airborne: true,
+            ..parked.clone()
+        };
+        assert_eq!(diverted.suspected_swap(), None);
+        // Before the schedule says it departed, a parked aircraft is expected
+        let not_departed = Flight {
+            schedule_status: Some(FlightStatus::Scheduled),
+            ..parked.clone()
+        };
+        assert_eq!(not_departed.suspected_swap(), None);
+        // Airports outside the table leave nothing to measure against
+        let unknown_route = Flight {
+            origin: Some(Airport::test("ZZZ")),
+            destination: None,
+            ..parked.clone()
+        };
+        assert_eq!(unknown_route.suspected_swap(), None);
+    }
+
+    #[test]
+    fn test_suspected_swap_after_missed_polls() {
+        let mut flight = Flight {
+            icao24: "a808c4".to_string(),
+            schedule_status: Some(FlightStatus::EnRoute),
+            seen_airborne: true,
+            altitude_ft: Some(35_000.0),
+            ..Default::default()
+        };
+        flight.missed_polls = SWAP_AFTER_MISSED_POLLS - 1;
+        assert_eq!(flight.suspected_swap(), None);
+        flight.missed_polls = SWAP_AFTER_MISSED_POLLS;
+        assert_eq!(flight.suspected_swap(), Some("missing from the feed"));
+
+        // Unless it has landed, or there is no schedule saying it flies
+        flight.position_status = Some(FlightStatus::Landed);
+        assert_eq!(flight.suspected_swap(), None);
+        flight.position_status = None;
+        flight.schedule_status = None;
+        assert_eq!(flight.suspected_swap(), None);
     }
 
     #[test]
@@ -162,6 +3180,9 @@ mod tests {
         assert_eq!(flight.status, FlightStatus::EnRoute);
         assert_eq!(flight.latitude, Some(37.7749));
         assert!(flight.origin.is_some());
-        assert_eq!(flight.origin.as_ref().unwrap().iata, Some("SFO".to_string()));
+        assert_eq!(
+            flight.origin.as_ref().unwrap().iata,
+            Some("SFO".to_string())
+        );
     }
 }
@@ -0,0 +1,467 @@
+//! Great-circle geometry helpers.
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Great-circle distance between two points in kilometres (haversine formula).
+///
+/// ```
+/// use flight_tracker_tui::geo::haversine_km;
+///
+/// // London Heathrow to New York JFK is roughly 5,540 km.
+/// let d = haversine_km(51.4700, -0.4543, 40.6413, -73.7781);
+/// assert!((d - 5540.0).abs() < 20.0);
+/// ```
+pub fn haversine_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (lon2 - lon1).to_radians();
+
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+/// Point a given fraction of the way along the great circle between two
+/// points, as `(latitude, longitude)` in degrees.
+///
+/// ```
+/// use flight_tracker_tui::geo::intermediate_point;
+///
+/// let (lat, lon) = intermediate_point(0.0, 0.0, 0.0, 90.0, 0.5);
+/// assert!(lat.abs() < 1e-9 && (lon - 45.0).abs() < 1e-9);
+/// ```
+pub fn intermediate_point(lat1: f64, lon1: f64, lat2: f64, lon2: f64, fraction: f64) -> (f64, f64) {
+    let angular = haversine_km(lat1, lon1, lat2, lon2) / EARTH_RADIUS_KM;
+    if angular == 0.0 {
+        return (lat1, lon1);
+    }
+
+    let (phi1, lambda1) = (lat1.to_radians(), lon1.to_radians());
+    let (phi2, lambda2) = (lat2.to_radians(), lon2.to_radians());
+    let a = ((1.0 - fraction) * angular).sin() / angular.sin();
+    let b = (fraction * angular).sin() / angular.sin();
+
+    let x = a * phi1.cos() * lambda1.cos() + b * phi2.cos() * lambda2.cos();
+    let y = a * phi1.cos() * lambda1.sin() + b * phi2.cos() * lambda2.sin();
+    let z = a * phi1.sin() + b * phi2.sin();
+
+    (
+        z.atan2((x * x + y * y).sqrt()).to_degrees(),
+        y.atan2(x).to_degrees(),
+    )
+}
+
+/// Great-circle midpoint of a route between two `(latitude, longitude)`
+/// points, when both ends are known.
+///
+/// ```
+/// use flight_tracker_tui::geo::route_midpoint;
+///
+/// let (lat, lon) = route_midpoint(Some((0.0, 0.0)), Some((0.0, 90.0))).unwrap();
+/// assert!(lat.abs() < 1e-9 && (lon - 45.0).abs() < 1e-9);
+/// assert!(route_midpoint(None, Some((0.0, 90.0))).is_none());
+/// ```
+pub fn route_midpoint(from: Option<(f64, f64)>, to: Option<(f64, f64)>) -> Option<(f64, f64)> {
+    let ((from_lat, from_lon), (to_lat, to_lon)) = (from?, to?);
+    Some(intermediate_point(from_lat, from_lon, to_lat, to_lon, 0.5))
+}
+
+/// Point reached by travelling `distance_km` along a great circle from a
+/// start point on an initial bearing (degrees from true north), as
+/// `(latitude, longitude)` with the longitude in -180..=180.
+///
+/// ```
+/// use flight_tracker_tui::geo::destination_point;
+///
+/// // A degree of latitude due north is about 111 km
+/// let (lat, lon) = destination_point(0.0, 0.0, 0.0, 111.195);
+/// assert!((lat - 1.0).abs() < 1e-3 && lon.abs() < 1e-9);
+/// ```
+pub fn destination_point(lat: f64, lon: f64, bearing: f64, distance_km: f64) -> (f64, f64) {
+    let angular = distance_km / EARTH_RADIUS_KM;
+    let (phi1, lambda1) = (lat.to_radians(), lon.to_radians());
+    let theta = bearing.to_radians();
+
+    let phi2 = (phi1.sin() * angular.cos() + phi1.cos() * angular.sin() * theta.cos()).asin();
+    let lambda2 = lambda1
+        + (theta.sin() * angular.sin() * phi1.cos()).atan2(angular.cos() - phi1.sin() * phi2.sin());
+
+    let lon2 = (lambda2.to_degrees() + 540.0).rem_euclid(360.0) - 180.0;
+    (phi2.to_degrees(), lon2)
+}
+
+/// Initial great-circle bearing from the first point to the second, in
+/// degrees clockwise from true north (0–360).
+pub fn initial_bearing(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let d_lambda = (lon2 - lon1).to_radians();
+
+    let y = d_lambda.sin() * phi2.cos();
+    let x = phi1.cos() * phi2.sin() - phi1.sin() * phi2.cos() * d_lambda.cos();
+    (y.atan2(x).to_degrees() + 360.0) % 360.0
+}
+
+/// How far along the great circle from the first point towards the second
+/// the third point lies, in kilometres: the distance to the spot on the
+/// route closest to it. Negative when it is behind the first point.
+///
+/// ```
+/// use flight_tracker_tui::geo::along_track_km;
+///
+/// // 1° north of the equator at 10°E, on a route east along the equator
+/// let d = along_track_km(0.0, 0.0, 0.0, 90.0, 1.0, 10.0);
+/// assert!((d - 1111.95).abs() < 1.0);
+/// ```
+pub fn along_track_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64, lat: f64, lon: f64) -> f64 {
+    let angular = haversine_km(lat1, lon1, lat, lon) / EARTH_RADIUS_KM;
+    let off_course = (initial_bearing(lat1, lon1, lat, lon)
+        - initial_bearing(lat1, lon1, lat2, lon2))
+    .to_radians();
+    let cross_track = (angular.sin() * off_course.sin()).asin();
+    let along = (angular.cos() / cross_track.cos()).clamp(-1.0, 1.0).acos();
+    along.copysign(off_course.cos()) * EARTH_RADIUS_KM
+}
+
+/// Sixteen-point compass abbreviation for a bearing in degrees.
+///
+/// ```
+/// use flight_tracker_tui::geo::compass_point;
+///
+/// assert_eq!(compass_point(310.0), "NW");
+/// assert_eq!(compass_point(359.0), "N");
+/// ```
+pub fn compass_point(bearing: f64) -> &'static str {
+    const POINTS: [&str; 16] = [
+        "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW",
+        "NW", "NNW",
+    ];
+    let index = (bearing.rem_euclid(360.0) / 22.5).round() as usize % POINTS.len();
+    POINTS[index]
+}
+
+/// Angle above the horizon, in degrees, of a target at `altitude_m` seen
+/// from sea level `distance_km` away along the ground. Accounts for the
+/// Earth's curvature, so targets beyond the horizon come out negative.
+pub fn elevation_angle(distance_km: f64, altitude_m: f64) -> f64 {
+    let central = distance_km / EARTH_RADIUS_KM;
+    let ratio = EARTH_RADIUS_KM / (EARTH_RADIUS_KM + altitude_m / 1000.0);
+    (central.cos() - ratio).atan2(central.sin()).to_degrees()
+}
+
+/// Where a target appears from an observer's position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LookAngles {
+    /// Ground distance in kilometres.
+    pub distance_km: f64,
+    /// Bearing from the observer in degrees from true north.
+    pub bearing: f64,
+    /// Elevation above the horizon in degrees, if the target's altitude is known.
+    pub elevation: Option<f64>,
+}
+
+/// Distance, bearing and elevation of a target from a sea-level observer.
+///
+/// ```
+/// use flight_tracker_tui::geo::look_angles;
+///
+/// // An aircraft at 3,000 m about 10 km north of the observer
+/// let view = look_angles(51.47, -0.45, 51.56, -0.45, Some(3000.0));
+/// assert!((view.distance_km - 10.0).abs() < 0.1);
+/// assert!(view.bearing < 1.0);
+/// assert!((view.elevation.unwrap() - 16.7).abs() < 0.5);
+/// ```
+pub fn look_angles(
+    observer_lat: f64,
+    observer_lon: f64,
+    lat: f64,
+    lon: f64,
+    altitude_m: Option<f64>,
+) -> LookAngles {
+    let distance_km = haversine_km(observer_lat, observer_lon, lat, lon);
+    LookAngles {
+        distance_km,
+        bearing: initial_bearing(observer_lat, observer_lon, lat, lon),
+        elevation: altitude_m.map(|alt| elevation_angle(distance_km, alt)),
+    }
+}
+
+/// A latitude/longitude rectangle in degrees.
+///
+/// Boxes do not wrap around the antimeridian.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min_lat: f64,
+    pub max_lat: f64,
+    pub min_lon: f64,
+    pub max_lon: f64,
+}
+
+impl BoundingBox {
+    /// The whole world.
+    pub const WORLD: BoundingBox = BoundingBox {
+        min_lat: -90.0,
+        max_lat: 90.0,
+        min_lon: -180.0,
+        max_lon: 180.0,
+    };
+
+    /// Smallest box around `(latitude, longitude)` points, padded by 10% and
+    /// spanning at least `min_span` degrees on each axis, clamped to the
+    /// world. `None` if there are no points.
+    ///
+    /// ```
+    /// use flight_tracker_tui::geo::BoundingBox;
+    ///
+    /// let view = BoundingBox::fit(&[(51.47, -0.45)], 10.0).unwrap();
+    /// assert!(view.contains(51.47, -0.45));
+    /// assert_eq!(view.max_lon - view.min_lon, 10.0);
+    /// ```
+    pub fn fit(points: &[(f64, f64)], min_span: f64) -> Option<Self> {
+        let (first_lat, first_lon) = *points.first()?;
+        let mut bounds = BoundingBox {
+            min_lat: first_lat,
+            max_lat: first_lat,
+            min_lon: first_lon,
+            max_lon: first_lon,
+        };
+        for &(lat, lon) in &points[1..] {
+            bounds.min_lat = bounds.min_lat.min(lat);
+            bounds.max_lat = bounds.max_lat.max(lat);
+            bounds.min_lon = bounds.min_lon.min(lon);
+            bounds.max_lon = bounds.max_lon.max(lon);
+        }
+
+        let (min_lat, max_lat) = pad_axis(bounds.min_lat, bounds.max_lat, min_span, 90.0);
+        let (min_lon, max_lon) = pad_axis(bounds.min_lon, bounds.max_lon, min_span, 180.0);
+        Some(BoundingBox {
+            min_lat,
+            max_lat,
+            min_lon,
+            max_lon,
+        })
+    }
+
+    /// Box reaching `margin` degrees either side of a point, clamped to the
+    /// world rather than wrapped across the poles or antimeridian.
+    ///
+    /// ```
+    /// use flight_tracker_tui::geo::BoundingBox;
+    ///
+    /// let area = BoundingBox::around(51.47, -0.45, 3.0);
+    /// assert_eq!((area.min_lat, area.max_lat), (48.47, 54.47));
+    /// ```
+    pub fn around(lat: f64, lon: f64, margin: f64) -> Self {
+        BoundingBox {
+            min_lat: (lat - margin).max(-90.0),
+            max_lat: (lat + margin).min(90.0),
+            min_lon: (lon - margin).max(-180.0),
+            max_lon: (lon + margin).min(180.0),
+        }
+    }
+
+    /// The box `factor` times as tall and wide about its centre, shifted
+    /// back inside the world where it would spill over an edge.
+    ///
+    /// ```
+    /// use flight_tracker_tui::geo::BoundingBox;
+    ///
+    /// let area = BoundingBox::around(51.47, -0.45, 4.0).scaled(0.5);
+    /// assert_eq!((area.min_lat, area.max_lat), (49.47, 53.47));
+    /// assert_eq!(area.scaled(1000.0), BoundingBox::WORLD);
+    /// ```
+    pub fn scaled(&self, factor: f64) -> Self {
+        let (min_lat, max_lat) = scale_axis(self.min_lat, self.max_lat, factor, 90.0);
+        let (min_lon, max_lon) = scale_axis(self.min_lon, self.max_lon, factor, 180.0);
+        BoundingBox {
+            min_lat,
+            max_lat,
+            min_lon,
+            max_lon,
+        }
+    }
+
+    /// Whether the point lies inside the box.
+    pub fn contains(&self, lat: f64, lon: f64) -> bool {
+        (self.min_lat..=self.max_lat).contains(&lat) && (self.min_lon..=self.max_lon).contains(&lon)
+    }
+}
+
+/// Scale a `[min, max]` range about its centre, then shift it back inside
+/// `[-limit, limit]`.
+fn scale_axis(min: f64, max: f64, factor: f64, limit: f64) -> (f64, f64) {
+    let span = ((max - min) * factor).min(2.0 * limit);
+    let centre = (min + max) / 2.0;
+    let low = (centre - span / 2.0).clamp(-limit, limit - span);
+    (low, low + span)
+}
+
+/// Pad a `[min, max]` range by 10%, widen it to `min_span` around its
+/// centre, then shift it back inside `[-limit, limit]`.
+fn pad_axis(min: f64, max: f64, min_span: f64, limit: f64) -> (f64, f64) {
+    let span = ((max - min) * 1.2).max(min_span).min(2.0 * limit);
+    let centre = (min + max) / 2.0;
+    let low = (centre - span / 2.0).clamp(-limit, limit - span);
+    (low, low + span)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_haversine_same_point() {
+        assert_eq!(haversine_km(37.6213, -122.3790, 37.6213, -122.3790), 0.0);
+    }
+
+    #[test]
+    fn test_haversine_known_distance() {
+        // SFO to LAX is about 543 km
+        let d = haversine_km(37.6213, -122.3790, 33.9416, -118.4085);
+        assert!((d - 543.0).abs() < 5.0);
+    }
+
+    #[test]
+    fn test_intermediate_point_endpoints() {
+        let (lat, lon) = intermediate_point(37.6213, -122.3790, 40.6413, -73.7781, 0.0);
+        assert!((lat - 37.6213).abs() < 1e-6 && (lon + 122.3790).abs() < 1e-6);
+
+        let (lat, lon) = intermediate_point(37.6213, -122.3790, 40.6413, -73.7781, 1.0);
+        assert!((lat - 40.6413).abs() < 1e-6 && (lon + 73.7781).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_intermediate_point_splits_distance() {
+        let (lat, lon) = intermediate_point(51.4700, -0.4543, 40.6413, -73.7781, 0.25);
+        let total = haversine_km(51.4700, -0.4543, 40.6413, -73.7781);
+        let flown = haversine_km(51.4700, -0.4543, lat, lon);
+        assert!((flown / total - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_along_track() {
+        let (lat1, lon1, lat2, lon2) = (51.4700, -0.4543, 40.6413, -73.7781);
+        let total = haversine_km(lat1, lon1, lat2, lon2);
+        // On the route, a quarter of the way along
+        let (lat, lon) = intermediate_point(lat1, lon1, lat2, lon2, 0.25);
+        assert!((along_track_km(lat1, lon1, lat2, lon2, lat, lon) - total / 4.0).abs() < 1.0);
+        // At the ends
+        assert!(along_track_km(lat1, lon1, lat2, lon2, lat1, lon1).abs() < 1e-6);
+        assert!((along_track_km(lat1, lon1, lat2, lon2, lat2, lon2) - total).abs() < 1.0);
+        // Behind the start
+        assert!(along_track_km(lat1, lon1, lat2, lon2, 52.0, 10.0) < -500.0);
+    }
+
+    #[test]
+    fn test_destination_point_round_trip() {
+        // Heathrow towards JFK for 1,000 km
+        let bearing = initial_bearing(51.4700, -0.4543, 40.6413, -73.7781);
+        let (lat, lon) = destination_point(51.4700, -0.4543, bearing, 1_000.0);
+        assert!((haversine_km(51.4700, -0.4543, lat, lon) - 1_000.0).abs() < 1e-6);
+        assert!((initial_bearing(51.4700, -0.4543, lat, lon) - bearing).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_destination_point_edge_cases() {
+        let (lat, lon) = destination_point(37.6213, -122.3790, 123.0, 0.0);
+        assert!((lat - 37.6213).abs() < 1e-9 && (lon + 122.3790).abs() < 1e-9);
+
+        // Eastbound across the antimeridian wraps to negative longitudes
+        let (lat, lon) = destination_point(0.0, 179.5, 90.0, 111.195);
+        assert!(lat.abs() < 1e-9);
+        assert!((lon + 179.5).abs() < 1e-3, "{lon}");
+    }
+
+    #[test]
+    fn test_initial_bearing_cardinal() {
+        assert!((initial_bearing(0.0, 0.0, 10.0, 0.0) - 0.0).abs() < 1e-9);
+        assert!((initial_bearing(0.0, 0.0, 0.0, 10.0) - 90.0).abs() < 1e-9);
+        assert!((initial_bearing(10.0, 0.0, 0.0, 0.0) - 180.0).abs() < 1e-9);
+        assert!((initial_bearing(0.0, 10.0, 0.0, 0.0) - 270.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_initial_bearing_known_routes() {
+        // Heathrow to JFK leaves heading west-north-west
+        let b = initial_bearing(51.4700, -0.4543, 40.6413, -73.7781);
+        assert!((b - 288.3).abs() < 0.5, "{b}");
+        // ...and the return leg north-east
+        let b = initial_bearing(40.6413, -73.7781, 51.4700, -0.4543);
+        assert!((b - 51.4).abs() < 0.5, "{b}");
+    }
+
+    #[test]
+    fn test_compass_point() {
+        assert_eq!(compass_point(0.0), "N");
+        assert_eq!(compass_point(11.0), "N");
+        assert_eq!(compass_point(12.0), "NNE");
+        assert_eq!(compass_point(90.0), "E");
+        assert_eq!(compass_point(200.0), "SSW");
+        assert_eq!(compass_point(340.0), "NNW");
+        assert_eq!(compass_point(349.0), "N");
+        assert_eq!(compass_point(360.0), "N");
+        assert_eq!(compass_point(-45.0), "NW");
+    }
+
+    #[test]
+    fn test_elevation_angle() {
+        // Directly overhead
+        assert!((elevation_angle(0.0, 10_000.0) - 90.0).abs() < 1e-9);
+        // Close by, curvature is negligible: atan(10/10) = 45°
+        assert!((elevation_angle(10.0, 10_000.0) - 45.0).abs() < 0.1);
+        // Cruise altitude disappears below the horizon after ~357 km
+        assert!(elevation_angle(300.0, 10_668.0) > 0.0);
+        assert!(elevation_angle(400.0, 10_668.0) < 0.0);
+    }
+
+    #[test]
+    fn test_look_angles_without_altitude() {
+        let view = look_angles(51.4700, -0.4543, 51.4700, -0.4543, None);
+        assert_eq!(view.distance_km, 0.0);
+        assert!(view.elevation.is_none());
+    }
+
+    #[test]
+    fn test_bounding_box_fit() {
+        assert!(BoundingBox::fit(&[], 10.0).is_none());
+
+        // LHR and JFK: longitude padded by 10% of the span on each side
+        let view = BoundingBox::fit(&[(51.47, -0.45), (40.64, -73.78)], 10.0).unwrap();
+        assert!(view.contains(51.47, -0.45) && view.contains(40.64, -73.78));
+        assert!((view.max_lon - view.min_lon - 73.33 * 1.2).abs() < 1e-6);
+        // ...and latitude, which already exceeds the minimum span
+        assert!((view.max_lat - view.min_lat - 12.996).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_bounding_box_clamped_to_world() {
+        let view = BoundingBox::fit(&[(89.0, 179.0)], 10.0).unwrap();
+        assert_eq!(view.max_lat, 90.0);
+        assert_eq!(view.max_lon, 180.0);
+        assert_eq!(view.min_lat, 80.0);
+        assert!(view.contains(89.0, 179.0));
+
+        let view = BoundingBox::fit(&[(-80.0, -170.0), (80.0, 170.0)], 10.0).unwrap();
+        assert_eq!(view, BoundingBox::WORLD);
+    }
+
+    #[test]
+    fn test_bounding_box_around() {
+        let area = BoundingBox::around(40.64, -73.78, 3.0);
+        assert!(area.contains(40.64, -73.78));
+        assert!(area.contains(43.0, -71.0));
+        assert!(!area.contains(44.0, -73.78));
+        assert!((area.max_lon - area.min_lon - 6.0).abs() < 1e-9);
+
+        // Clamped, not wrapped, near the poles and the antimeridian
+        let area = BoundingBox::around(88.5, 179.0, 3.0);
+        assert_eq!(area.max_lat, 90.0);
+        assert_eq!(area.max_lon, 180.0);
+        assert_eq!(area.min_lon, 176.0);
+    }
+
+    #[test]
+    fn test_haversine_is_symmetric() {
+        let a = haversine_km(1.3644, 103.9915, -33.9399, 151.1753);
+        let b = haversine_km(-33.9399, 151.1753, 1.3644, 103.9915);
+        assert!((a - b).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,252 @@
+//! GeoJSON export of tracked flight positions for external map tools.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+
+use crate::flight::Flight;
+
+/// A `FeatureCollection` with a `Point` for each flight with a known
+/// position, followed by a `LineString` of the path it has flown when its
+/// track has at least two points. Flights with neither are left out.
+///
+/// ```
+/// use flight_tracker_tui::flight::Flight;
+/// use flight_tracker_tui::geojson::feature_collection;
+///
+/// let flight = Flight {
+///     flight_number: "BA285".to_string(),
+///     latitude: Some(51.47),
+///     longitude: Some(-0.45),
+///     ..Default::default()
+/// };
+/// let collection = feature_collection(&[flight, Flight::default()]);
+///
+/// assert_eq!(collection["features"].as_array().unwrap().len(), 1);
+/// assert_eq!(collection["features"][0]["geometry"]["coordinates"][0], -0.45);
+/// ```
+pub fn feature_collection(flights: &[Flight]) -> Value {
+    let features: Vec<Value> = flights
+        .iter()
+        .flat_map(|flight| [point_feature(flight), track_feature(flight)])
+        .flatten()
+        .collect();
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
+
+fn point_feature(flight: &Flight) -> Option<Value> {
+    let (lat, lon) = (flight.latitude?, flight.longitude?);
+    Some(json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "Point",
+            // GeoJSON positions are longitude first
+            "coordinates": [lon, lat],
+        },
+        "properties": {
+            "flight_number": flight.flight_number,
+            "callsign": flight.callsign.trim(),
+            "altitude_ft": flight.altitude_ft.map(f64::round),
+            "speed_kts": flight.ground_speed_kts.map(f64::round),
+            "heading": flight.heading,
+            "status": flight.status.to_string(),
+        },
+    }))
+}
+
+/// The flight's track as a `LineString`, oldest point first. A single
+/// point isn't a line, so shorter tracks have none.
+fn track_feature(flight: &Flight) -> Option<Value> {
+    if flight.track.len() < 2 {
+        return None;
+    }
+    let coordinates: Vec<[f64; 2]> = flight
+        .track
+        .iter()
+        .map(|point| [point.longitude, point.latitude])
+        .collect();
+    Some(json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "LineString",
+            "coordinates": coordinates,
+        },
+        "properties": {
+            "flight_number": flight.flight_number,
+            "callsign": flight.callsign.trim(),
+        },
+    }))
+}
+
+/// Writes the feature collection to a file whenever it changes.
+#[derive(Debug)]
+pub struct GeoJsonWriter {
+    path: PathBuf,
+    last_written: Option<String>,
+}
+
+impl GeoJsonWriter {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            last_written: None,
+        }
+    }
+
+    /// Write the flights' positions unless they are unchanged since the last
+    /// call. The file is replaced atomically, so readers never see a partial
+    /// document. Returns whether the file was written.
+    pub fn write(&mut self, flights: &[Flight]) -> io::Result<bool> {
+        let contents = feature_collection(flights).to_string();
+        if self.last_written.as_ref() == Some(&contents) {
+            return Ok(false);
+        }
+        // Remember the attempt even if it fails, so a bad path is reported
+        // once per change rather than on every call
+        self.last_written = Some(contents.clone());
+
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, contents)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::{FlightStatus, TrackPoint};
+
+    fn airborne() -> Flight {
+        Flight {
+            flight_number: "UA900".to_string(),
+            callsign: "UAL900".to_string(),
+            status: FlightStatus::EnRoute,
+            latitude: Some(60.2),
+            longitude: Some(-45.1),
+            altitude_ft: Some(37_001.6),
+            ground_speed_kts: Some(486.7),
+            heading: Some(62.5),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_feature_schema() {
+        let collection = feature_collection(&[airborne()]);
+        assert_eq!(collection["type"], "FeatureCollection");
+
+        let feature = &collection["features"][0];
+        assert_eq!(feature["type"], "Feature");
+        assert_eq!(feature["geometry"]["type"], "Point");
+        assert_eq!(feature["geometry"]["coordinates"], json!([-45.1, 60.2]));
+        assert_eq!(
+            feature["properties"],
+            json!({
+                "flight_number": "UA900",
+                "callsign": "UAL900",
+                "altitude_ft": 37002.0,
+                "speed_kts": 487.0,
+                "heading": 62.5,
+                "status": "En Route",
+            })
+        );
+    }
+
+    #[test]
+    fn test_unknown_values_are_null() {
+        let flight = Flight {
+            altitude_ft: None,
+            ground_speed_kts: None,
+            heading: None,
+            ..airborne()
+        };
+        let properties = &feature_collection(&[flight])["features"][0]["properties"];
+        assert!(properties["altitude_ft"].is_null());
+        assert!(properties["speed_kts"].is_null());
+        assert!(properties["heading"].is_null());
+    }
+
+    #[test]
+    fn test_track_becomes_a_line_string() {
+        let point = |latitude: f64, longitude: f64| TrackPoint {
+            at: chrono::Utc::now(),
+            latitude,
+            longitude,
+            altitude_ft: None,
+        };
+        let flight = Flight {
+            track: vec![point(59.8, -47.0), point(60.0, -46.1), point(60.2, -45.1)],
+            ..airborne()
+        };
+        let features = &feature_collection(&[flight])["features"];
+        assert_eq!(features.as_array().unwrap().len(), 2);
+        assert_eq!(features[1]["geometry"]["type"], "LineString");
+        assert_eq!(
+            features[1]["geometry"]["coordinates"],
+            json!([[-47.0, 59.8], [-46.1, 60.0], [-45.1, 60.2]])
+        );
+        assert_eq!(
+            features[1]["properties"],
+            json!({"flight_number": "UA900", "callsign": "UAL900"})
+        );
+
+        // One point is no line, but a track outlives a lost position
+        let single = Flight {
+            track: vec![point(60.2, -45.1)],
+            ..airborne()
+        };
+        assert_eq!(
+            feature_collection(&[single])["features"]
+                .as_array()
+                .unwrap()
+                .len(),
+            1
+        );
+        let lost = Flight {
+            latitude: None,
+            longitude: None,
+            track: vec![point(59.8, -47.0), point(60.2, -45.1)],
+            ..airborne()
+        };
+        let features = &feature_collection(&[lost])["features"];
+        assert_eq!(features.as_array().unwrap().len(), 1);
+        assert_eq!(features[0]["geometry"]["type"], "LineString");
+    }
+
+    #[test]
+    fn test_empty_collection() {
+        let collection = feature_collection(&[]);
+        assert_eq!(
+            collection,
+            json!({"type": "FeatureCollection", "features": []})
+        );
+    }
+
+    #[test]
+    fn test_writer_replaces_file_only_on_change() {
+        let dir = std::env::temp_dir().join(format!("geojson-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("flights.geojson");
+        let mut writer = GeoJsonWriter::new(path.clone());
+
+        let mut flights = vec![airborne()];
+        assert!(writer.write(&flights).unwrap());
+        assert!(!writer.write(&flights).unwrap());
+
+        flights[0].latitude = Some(60.5);
+        assert!(writer.write(&flights).unwrap());
+
+        let written: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["features"][0]["geometry"]["coordinates"][1], 60.5);
+        assert!(!dir.join("flights.geojson.tmp").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,155 @@
+//! ASCII stand-ins for the non-ASCII characters the app prints, for
+//! terminals and SSH sessions that show them as mojibake.
+//!
+//! [`GLYPHS`] pairs each character with two fallbacks: one for text whose
+//! length doesn't matter, such as the exported report, and one that fits the
+//! single terminal cell the character took, so that swapping it on screen
+//! keeps every column where it was. Anything not in the table becomes `?`.
+//!
+//! ```
+//! use flight_tracker_tui::glyphs;
+//!
+//! assert_eq!(glyphs::to_ascii("SFO→LHR ✈ FL360"), "SFO->LHR * FL360");
+//! assert_eq!(glyphs::cell('→'), '>');
+//! ```
+
+use std::borrow::Cow;
+
+/// A character the app prints and what stands in for it in ASCII mode.
+struct Glyph {
+    unicode: char,
+    /// In running text
+    text: &'static str,
+    /// In the one terminal cell the character took
+    cell: char,
+}
+
+const fn glyph(unicode: char, text: &'static str, cell: char) -> Glyph {
+    Glyph {
+        unicode,
+        text,
+        cell,
+    }
+}
+
+const GLYPHS: &[Glyph] = &[
+    // Routes, trends and list markers
+    glyph('→', "->", '>'),
+    glyph('←', "<-", '<'),
+    glyph('↑', "^", '^'),
+    glyph('↓', "v", 'v'),
+    glyph('›', ">", '>'),
+    glyph('▸', ">", '>'),
+    glyph('▾', "v", 'v'),
+    // Status glyphs
+    glyph('✈', "*", '*'),
+    glyph('●', "o", 'o'),
+    glyph('◎', "@", '@'),
+    glyph('⚠', "!", '!'),
+    glyph('↻', "r", 'r'),
+    glyph('≡', "=", '='),
+    glyph('†', "+", '+'),
+    glyph('🔕', "(muted)", 'z'),
+    // Punctuation and units
+    glyph('—', "--", '-'),
+    glyph('·', "|", '|'),
+    glyph('…', "...", '.'),
+    glyph('±', "+/-", '~'),
+    glyph('°', "deg", ' '),
+    glyph('×', "x", 'x'),
+    // Sparklines and bars, lowest to highest
+    glyph('▁', "_", '_'),
+    glyph('▂', ".", '.'),
+    glyph('▃', "-", '-'),
+    glyph('▄', "=", '='),
+    glyph('▅', "+", '+'),
+    glyph('▆', "*", '*'),
+    glyph('▇', "#", '#'),
+    glyph('█', "#", '#'),
+    // Borders
+    glyph('─', "-", '-'),
+    glyph('━', "=", '='),
+    glyph('│', "|", '|'),
+    glyph('┃', "|", '|'),
+];
+
+/// Frames of the loading spinner, and their ASCII counterparts.
+pub const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+pub const ASCII_SPINNER: [&str; 4] = ["|", "/", "-", "\\"];
+
+fn lookup(c: char) -> Option<&'static Glyph> {
+    GLYPHS.iter().find(|glyph| glyph.unicode == c)
+}
+
+/// The ASCII character that takes `c`'s place in a terminal cell. Corners
+/// and the rest of the box-drawing set become `+`, braille map dots `.`.
+pub fn cell(c: char) -> char {
+    if c.is_ascii() {
+        return c;
+    }
+    match lookup(c) {
+        Some(glyph) => glyph.cell,
+        None => match c {
+            '\u{2800}' => ' ',
+            '\u{2801}'..='\u{28ff}' => '.',
+            '\u{2500}'..='\u{257f}' => '+',
+            '\u{2580}'..='\u{259f}' => '#',
+            _ => '?',
+        },
+    }
+}
+
+/// `text` with every non-ASCII character spelled out in ASCII, e.g. the
+/// route arrow as `->`.
+pub fn to_ascii(text: &str) -> Cow<'_, str> {
+    if text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+    let mut ascii = String::with_capacity(text.len());
+    for c in text.chars() {
+        match lookup(c) {
+            _ if c.is_ascii() => ascii.push(c),
+            Some(glyph) => ascii.push_str(glyph.text),
+            None => ascii.push(cell(c)),
+        }
+    }
+    Cow::Owned(ascii)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallbacks_are_ascii() {
+        for glyph in GLYPHS {
+            assert!(!glyph.unicode.is_ascii());
+            assert!(
+                glyph.text.is_ascii() && glyph.cell.is_ascii(),
+                "{}",
+                glyph.unicode
+            );
+        }
+        assert!(SPINNER.iter().all(|frame| !frame.is_ascii()));
+        assert!(ASCII_SPINNER.iter().all(|frame| frame.is_ascii()));
+    }
+
+    #[test]
+    fn test_to_ascii() {
+        assert!(matches!(to_ascii("UA123 En Route"), Cow::Borrowed(_)));
+        assert_eq!(to_ascii("Delayed — 18:42 ±1h"), "Delayed -- 18:42 +/-1h");
+        // Names from the downloaded tables, and box corners
+        assert_eq!(to_ascii("Zürich ┌"), "Z?rich +");
+    }
+
+    #[test]
+    fn test_cells() {
+        assert_eq!(cell('a'), 'a');
+        assert_eq!(cell('🔕'), 'z');
+        assert_eq!(cell('┐'), '+');
+        assert_eq!(cell('⣿'), '.');
+        assert_eq!(cell('\u{2800}'), ' ');
+        assert_eq!(cell('▀'), '#');
+        assert_eq!(cell('ü'), '?');
+    }
+}
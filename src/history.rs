@@ -5,10 +5,13 @@ use std::collections::VecDeque;
 use std::fs;
 use std::path::PathBuf;
 
+use crate::paths::data_dir;
+use crate::persist;
+
 const MAX_HISTORY_SIZE: usize = 20;
-const CONFIG_DIR: &str = "flight-tracker-tui";
 const HISTORY_FILE: &str = "history.json";
 
+/// A previously tracked flight.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HistoryEntry {
     pub flight_number: String,
@@ -17,6 +20,18 @@ pub struct HistoryEntry {
     pub route: Option<String>,
 }
 
+/// Most-recently-used list of tracked flights, newest first.
+///
+/// ```
+/// use flight_tracker_tui::history::History;
+///
+/// let mut history = History::default();
+/// history.add("UA123".to_string(), Some("SFO→LHR".to_string()));
+/// history.add("BA285".to_string(), None);
+///
+/// assert_eq!(history.entries().next().unwrap().flight_number, "BA285");
+/// assert_eq!(history.matching("ua").len(), 1);
+/// ```
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct History {
     entries: VecDeque<HistoryEntry>,
@@ -38,16 +53,11 @@ impl History {
         Self::default()
     }
 
-    /// Save history to the config file.
+    /// Save history to the config file in the background.
     pub fn save(&self) {
         if let Some(path) = Self::config_path() {
-            // Ensure config directory exists
-            if let Some(parent) = path.parent() {
-                let _ = fs::create_dir_all(parent);
-            }
-
             if let Ok(contents) = serde_json::to_string_pretty(self) {
-                let _ = fs::write(&path, contents);
+                persist::write(path, contents);
             }
         }
     }
@@ -69,13 +79,30 @@ impl History {
         }
     }
 
+    /// Set the route of an existing entry without moving it, for flights
+    /// whose airports only became known after they were added. Returns
+    /// whether anything changed; flights not in the history are ignored.
+    pub fn update_route(&mut self, flight_number: &str, route: String) -> bool {
+        let Some(entry) = self
+            .entries
+            .iter_mut()
+            .find(|e| e.flight_number == flight_number)
+        else {
+            return false;
+        };
+        if entry.route.as_ref() == Some(&route) {
+            return false;
+        }
+        entry.route = Some(route);
+        true
+    }
+
     /// Get all history entries.
     pub fn entries(&self) -> impl Iterator<Item = &HistoryEntry> {
         self.entries.iter()
     }
 
     /// Get entries that match a prefix (for autocomplete suggestions).
-    #[allow(dead_code)]
     pub fn matching(&self, prefix: &str) -> Vec<&HistoryEntry> {
         let prefix_upper = prefix.to_uppercase();
         self.entries
@@ -84,6 +111,15 @@ impl History {
             .collect()
     }
 
+    /// The most recent other entry one edit away from `flight_number`, as
+    /// a likely correction for a flight number that found nothing.
+    pub fn near_match(&self, flight_number: &str) -> Option<&str> {
+        suggest_correction(
+            flight_number,
+            self.entries.iter().map(|e| e.flight_number.as_str()),
+        )
+    }
+
     /// Check if history is empty.
     pub fn is_empty(&self) -> bool {
         self.entries.is_empty()
@@ -97,24 +133,45 @@ impl History {
 
     /// Get the config file path.
     fn config_path() -> Option<PathBuf> {
-        dirs_config_dir().map(|mut p| {
-            p.push(CONFIG_DIR);
-            p.push(HISTORY_FILE);
-            p
-        })
+        data_dir().map(|p| p.join(HISTORY_FILE))
     }
 }
 
-/// Get the user's config directory.
-fn dirs_config_dir() -> Option<PathBuf> {
-    // Try XDG_CONFIG_HOME first, then fall back to ~/.config
-    if let Ok(xdg) = std::env::var("XDG_CONFIG_HOME") {
-        return Some(PathBuf::from(xdg));
-    }
+/// The first candidate exactly one edit (a letter added, dropped or
+/// changed) away from `typed`, ignoring case.
+///
+/// ```
+/// use flight_tracker_tui::history::suggest_correction;
+///
+/// assert_eq!(suggest_correction("UA9000", ["BA285", "UA900"]), Some("UA900"));
+/// assert_eq!(suggest_correction("UA900", ["UA900"]), None);
+/// ```
+pub fn suggest_correction<'a>(
+    typed: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let typed = typed.to_uppercase();
+    candidates
+        .into_iter()
+        .find(|candidate| one_edit_apart(&typed, &candidate.to_uppercase()))
+}
 
-    std::env::var("HOME")
-        .ok()
-        .map(|home| PathBuf::from(home).join(".config"))
+fn one_edit_apart(a: &str, b: &str) -> bool {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    // Past the common prefix and suffix, one letter at most may remain
+    let prefix = short.iter().zip(&long).take_while(|(x, y)| x == y).count();
+    let suffix = short[prefix..]
+        .iter()
+        .rev()
+        .zip(long[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    match long.len() - short.len() {
+        0 => short.len() - prefix - suffix == 1,
+        1 => prefix + suffix >= short.len(),
+        _ => false,
+    }
 }
 
 #[cfg(test)]
@@ -165,6 +222,32 @@ mod tests {
         assert_eq!(entries[0].flight_number, "FL024");
     }
 
+    #[test]
+    fn test_update_route_keeps_order() {
+        let mut history = History::default();
+        history.add("UA123".to_string(), None);
+        history.add("BA285".to_string(), None);
+
+        assert!(history.update_route("UA123", "SFO→LHR".to_string()));
+        // Setting the same route again is not a change
+        assert!(!history.update_route("UA123", "SFO→LHR".to_string()));
+
+        let entries: Vec<_> = history.entries().collect();
+        assert_eq!(entries[0].flight_number, "BA285");
+        assert_eq!(entries[1].route.as_deref(), Some("SFO→LHR"));
+    }
+
+    #[test]
+    fn test_update_route_for_missing_flight() {
+        let mut history = History::default();
+        assert!(!history.update_route("UA123", "SFO→LHR".to_string()));
+        assert!(history.is_empty());
+
+        history.add("BA285".to_string(), None);
+        assert!(!history.update_route("ua123", "SFO→LHR".to_string()));
+        assert_eq!(history.entries().next().unwrap().route, None);
+    }
+
     #[test]
     fn test_history_matching() {
         let mut history = History::default();
@@ -183,6 +266,35 @@ mod tests {
         assert!(matches.is_empty());
     }
 
+    #[test]
+    fn test_one_edit_apart() {
+        // Changed, dropped and added letters
+        assert!(one_edit_apart("UA900", "UA901"));
+        assert!(one_edit_apart("UA900", "UA90"));
+        assert!(one_edit_apart("UA90", "UA900"));
+        assert!(one_edit_apart("A900", "UA900"));
+        // Identical, two edits, or a swap (two changes)
+        assert!(!one_edit_apart("UA900", "UA900"));
+        assert!(!one_edit_apart("UA900", "UA9000X"));
+        assert!(!one_edit_apart("UA900", "UA811"));
+        assert!(!one_edit_apart("BA285", "AB285"));
+        assert!(!one_edit_apart("", ""));
+        assert!(one_edit_apart("", "U"));
+    }
+
+    #[test]
+    fn test_near_match_prefers_recent_entries() {
+        let mut history = History::default();
+        history.add("UA901".to_string(), None);
+        history.add("BA285".to_string(), None);
+        history.add("UA900".to_string(), None);
+        history.add("UA9000".to_string(), None);
+
+        assert_eq!(history.near_match("UA9000"), Some("UA900"));
+        assert_eq!(history.near_match("ba28"), Some("BA285"));
+        assert_eq!(history.near_match("LH400"), None);
+    }
+
     #[test]
     fn test_history_serialization() {
         let mut history = History::default();
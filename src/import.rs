@@ -0,0 +1,413 @@
+//! Parsing of flight lists from the input box and from import files.
+
+use std::fs;
+use std::path::Path;
+
+use chrono::{Days, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+/// Largest `+N`/`-N` day offset accepted after a flight number.
+const MAX_DAY_OFFSET: u64 = 30;
+
+/// A flight number to look up, optionally on a specific date.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlightQuery {
+    pub flight_number: String,
+    /// Departure date to query; `None` means whatever the provider considers current.
+    pub date: Option<NaiveDate>,
+}
+
+impl From<String> for FlightQuery {
+    fn from(flight_number: String) -> Self {
+        Self {
+            flight_number,
+            date: None,
+        }
+    }
+}
+
+/// Result of parsing an import file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportFile {
+    pub entries: Vec<FlightQuery>,
+    /// Rows that could not be parsed into a valid flight.
+    pub invalid: usize,
+}
+
+/// Split user input into upper-cased tokens on commas, semicolons and whitespace.
+///
+/// This is the tokenizer behind multi-flight submission in the input box
+/// and the row parser for CSV imports.
+///
+/// ```
+/// use flight_tracker_tui::import::tokenize;
+///
+/// assert_eq!(tokenize("ua123, ba285;AF7"), vec!["UA123", "BA285", "AF7"]);
+/// ```
+pub fn tokenize(input: &str) -> Vec<String> {
+    input
+        .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+        .map(|t| t.trim_matches('"'))
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_uppercase())
+        .collect()
+}
+
+/// Whether `s` looks like an IATA or ICAO flight number (e.g. `UA123`,
+/// `B6100`, `BAW285`, `KL1234A`).
+///
+/// ```
+/// use flight_tracker_tui::import::is_valid_flight_number;
+///
+/// assert!(is_valid_flight_number("UA123"));
+/// assert!(!is_valid_flight_number("HELLO"));
+/// ```
+pub fn is_valid_flight_number(s: &str) -> bool {
+    split_flight_number(&s.to_uppercase()).is_some()
+}
+
+/// `flight_number` upper-cased and without leading zeros in its number, so
+/// that `nh006` and `NH6` compare equal. Anything that isn't a flight number
+/// is only upper-cased.
+///
+/// ```
+/// use flight_tracker_tui::import::normalize_flight_number;
+///
+/// assert_eq!(normalize_flight_number("nh006"), "NH6");
+/// assert_eq!(normalize_flight_number("BAW0285"), "BAW285");
+/// assert_eq!(normalize_flight_number("B6100"), "B6100");
+/// ```
+pub fn normalize_flight_number(flight_number: &str) -> String {
+    let upper = flight_number.to_uppercase();
+    match split_flight_number(&upper) {
+        Some((airline, digits, suffix)) => {
+            let digits = match digits.trim_start_matches('0') {
+                "" => "0",
+                digits => digits,
+            };
+            format!("{}{}{}", airline, digits, suffix)
+        }
+        None => upper,
+    }
+}
+
+/// An upper-cased flight number's airline code, digits and optional
+/// letter suffix, or `None` if it isn't one.
+fn split_flight_number(s: &str) -> Option<(&str, &str, &str)> {
+    if !s.is_ascii() {
+        return None;
+    }
+    let number = s
+        .strip_suffix(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(s);
+    let suffix = &s[number.len()..];
+
+    // IATA airline codes are two alphanumerics, ICAO codes three letters
+    let airline_ok = |airline: &str| match airline.len() {
+        2 => {
+            airline.chars().all(|c| c.is_ascii_alphanumeric())
+                && airline.chars().any(|c| c.is_ascii_alphabetic())
+        }
+        3 => airline.chars().all(|c| c.is_ascii_alphabetic()),
+        _ => false,
+    };
+
+    [2, 3].iter().find_map(|&len| {
+        if number.len() <= len {
+            return None;
+        }
+        let (airline, digits) = number.split_at(len);
+        let ok =
+            airline_ok(airline) && digits.len() <= 4 && digits.chars().all(|c| c.is_ascii_digit());
+        ok.then_some((airline, digits, suffix))
+    })
+}
+
+/// Parse a line of input into flight queries.
+///
+/// Each flight number may carry a date, either as a following `YYYY-MM-DD`
+/// token or as a `+N`/`-N` day offset from `today`. Tokens that are neither
+/// are returned as invalid.
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use flight_tracker_tui::import::parse_queries;
+///
+/// let today = NaiveDate::from_ymd_opt(2024, 7, 13).unwrap();
+/// let (queries, invalid) = parse_queries("UA123 2024-07-20, BA285+1 ???", today);
+///
+/// assert_eq!(queries[0].date, NaiveDate::from_ymd_opt(2024, 7, 20));
+/// assert_eq!(queries[1].date, NaiveDate::from_ymd_opt(2024, 7, 14));
+/// assert_eq!(invalid, vec!["???"]);
+/// ```
+pub fn parse_queries(input: &str, today: NaiveDate) -> (Vec<FlightQuery>, Vec<String>) {
+    let mut queries: Vec<FlightQuery> = Vec::new();
+    let mut invalid = Vec::new();
+    // Whether the previous token was a flight still waiting for an explicit date
+    let mut awaiting_date = false;
+
+    for token in tokenize(input) {
+        if let Ok(date) = NaiveDate::parse_from_str(&token, "%Y-%m-%d") {
+            match queries.last_mut() {
+                Some(last) if awaiting_date => last.date = Some(date),
+                _ => invalid.push(token),
+            }
+            awaiting_date = false;
+            continue;
+        }
+
+        let (flight_number, date) = match split_day_offset(&token, today) {
+            Some((flight_number, date)) => (flight_number, Some(date)),
+            None => (token.as_str(), None),
+        };
+
+        if is_valid_flight_number(flight_number) {
+            awaiting_date = date.is_none();
+            queries.push(FlightQuery {
+                flight_number: flight_number.to_string(),
+                date,
+            });
+        } else {
+            awaiting_date = false;
+            invalid.push(token);
+        }
+    }
+
+    (queries, invalid)
+}
+
+/// Split `UA123+1` into the flight number and `today` shifted by the offset.
+fn split_day_offset(token: &str, today: NaiveDate) -> Option<(&str, NaiveDate)> {
+    let pos = token.rfind(['+', '-'])?;
+    let (flight_number, offset) = token.split_at(pos);
+    let days: u64 = offset[1..].parse().ok().filter(|d| *d <= MAX_DAY_OFFSET)?;
+
+    let date = if offset.starts_with('+') {
+        today.checked_add_days(Days::new(days))?
+    } else {
+        today.checked_sub_days(Days::new(days))?
+    };
+    Some((flight_number, date))
+}
+
+/// Parse CSV (or plain one-per-line) contents.
+///
+/// Each row holds a flight number and an optional date, in any form
+/// accepted by [`parse_queries`].
+/// A leading BOM, CRLF line endings, blank lines, `#` comments and a
+/// `flight`/`flight_number` header row are tolerated.
+pub fn parse_csv(contents: &str) -> ImportFile {
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+    let mut file = ImportFile::default();
+
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if i == 0
+            && tokenize(line)
+                .first()
+                .is_some_and(|t| t == "FLIGHT" || t == "FLIGHT_NUMBER")
+        {
+            continue;
+        }
+
+        match parse_row(line) {
+            Some(entry) => file.entries.push(entry),
+            None => file.invalid += 1,
+        }
+    }
+
+    file
+}
+
+/// Parse a single import row, which must describe exactly one flight.
+fn parse_row(row: &str) -> Option<FlightQuery> {
+    let (mut queries, invalid) = parse_queries(row, Local::now().date_naive());
+    if queries.len() == 1 && invalid.is_empty() {
+        queries.pop()
+    } else {
+        None
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonEntry {
+    Number(String),
+    Record {
+        #[serde(alias = "flight")]
+        flight_number: String,
+        #[serde(default)]
+        date: Option<String>,
+    },
+}
+
+/// Parse a JSON array of flight numbers or `{"flight_number", "date"}` objects.
+pub fn parse_json(contents: &str) -> Result<ImportFile, AppError> {
+    let contents = contents.strip_prefix('\u{feff}').unwrap_or(contents);
+    let rows: Vec<serde_json::Value> =
+        serde_json::from_str(contents).map_err(|e| AppError::parse(e.to_string()))?;
+
+    let mut file = ImportFile::default();
+    for row in rows {
+        let row = match serde_json::from_value(row) {
+            Ok(JsonEntry::Number(flight)) => flight,
+            Ok(JsonEntry::Record {
+                flight_number,
+                date,
+            }) => format!("{} {}", flight_number, date.unwrap_or_default()),
+            Err(_) => String::new(),
+        };
+
+        match parse_row(&row) {
+            Some(entry) => file.entries.push(entry),
+            None => file.invalid += 1,
+        }
+    }
+
+    Ok(file)
+}
+
+/// Read an import file, choosing the parser from the `.json` extension.
+pub fn load(path: &Path) -> Result<ImportFile, AppError> {
+    let contents = fs::read_to_string(path)?;
+    let is_json = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+
+    if is_json {
+        parse_json(&contents)
+    } else {
+        Ok(parse_csv(&contents))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flights(file: &ImportFile) -> Vec<&str> {
+        file.entries
+            .iter()
+            .map(|e| e.flight_number.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("UA123"), vec!["UA123"]);
+        assert_eq!(
+            tokenize("  ua123 ,ba285\tAF007 "),
+            vec!["UA123", "BA285", "AF007"]
+        );
+        assert_eq!(
+            tokenize("\"UA123\",\"2024-07-14\""),
+            vec!["UA123", "2024-07-14"]
+        );
+        assert!(tokenize(" , ; ").is_empty());
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(y, m, d)
+    }
+
+    #[test]
+    fn test_parse_queries_dates() {
+        let today = date(2024, 7, 13).unwrap();
+
+        let (queries, invalid) = parse_queries("UA123 2024-07-14", today);
+        assert_eq!(queries.len(), 1);
+        assert_eq!(queries[0].flight_number, "UA123");
+        assert_eq!(queries[0].date, date(2024, 7, 14));
+        assert!(invalid.is_empty());
+
+        let (queries, _) = parse_queries("ua123+1 BA285-1 AF007+0 DL1", today);
+        let dates: Vec<_> = queries.iter().map(|q| q.date).collect();
+        assert_eq!(
+            dates,
+            vec![date(2024, 7, 14), date(2024, 7, 12), Some(today), None]
+        );
+    }
+
+    #[test]
+    fn test_parse_queries_invalid_dates() {
+        let today = date(2024, 7, 13).unwrap();
+
+        // A date with no flight before it, a second date, and an offset that's too large
+        let (queries, invalid) =
+            parse_queries("2024-07-14 UA123 2024-07-14 2024-07-15 BA285+99", today);
+        assert_eq!(queries.len(), 1);
+        assert_eq!(invalid, vec!["2024-07-14", "2024-07-15", "BA285+99"]);
+
+        // A date after a flight that already has an offset
+        let (queries, invalid) = parse_queries("UA123+1 2024-07-20", today);
+        assert_eq!(queries[0].date, date(2024, 7, 14));
+        assert_eq!(invalid, vec!["2024-07-20"]);
+    }
+
+    #[test]
+    fn test_is_valid_flight_number() {
+        assert!(is_valid_flight_number("UA123"));
+        assert!(is_valid_flight_number("B6100"));
+        assert!(is_valid_flight_number("BAW285"));
+        assert!(is_valid_flight_number("KL1234A"));
+        assert!(is_valid_flight_number("sq26"));
+
+        assert!(!is_valid_flight_number(""));
+        assert!(!is_valid_flight_number("123"));
+        assert!(!is_valid_flight_number("UA"));
+        assert!(!is_valid_flight_number("UA12345"));
+        assert!(!is_valid_flight_number("HELLO"));
+        assert!(!is_valid_flight_number("UÄ123"));
+    }
+
+    #[test]
+    fn test_parse_csv_one_per_line() {
+        let file = parse_csv("UA123\nBA285\nAF007\n");
+        assert_eq!(flights(&file), vec!["UA123", "BA285", "AF007"]);
+        assert_eq!(file.invalid, 0);
+    }
+
+    #[test]
+    fn test_parse_csv_bom_crlf_and_blank_lines() {
+        let file =
+            parse_csv("\u{feff}flight_number,date\r\nUA123,2024-07-14\r\n\r\n  \r\nba285\r\n");
+
+        assert_eq!(flights(&file), vec!["UA123", "BA285"]);
+        assert_eq!(file.entries[0].date, NaiveDate::from_ymd_opt(2024, 7, 14));
+        assert_eq!(file.entries[1].date, None);
+        assert_eq!(file.invalid, 0);
+    }
+
+    #[test]
+    fn test_parse_csv_counts_invalid_rows() {
+        let file = parse_csv(
+            "# my trips\nUA123\nnot a flight\nBA285,tomorrow\nAF007,2024-02-30\nDL1 DL2\n",
+        );
+
+        assert_eq!(flights(&file), vec!["UA123"]);
+        assert_eq!(file.invalid, 4);
+    }
+
+    #[test]
+    fn test_parse_json_strings_and_records() {
+        let file = parse_json(
+            r#"["ua123", {"flight_number": "BA285", "date": "2024-07-14"}, {"flight": "AF007"}, 42]"#,
+        )
+        .unwrap();
+
+        assert_eq!(flights(&file), vec!["UA123", "BA285", "AF007"]);
+        assert_eq!(file.entries[1].date, NaiveDate::from_ymd_opt(2024, 7, 14));
+        assert_eq!(file.invalid, 1);
+    }
+
+    #[test]
+    fn test_parse_json_rejects_non_array() {
+        assert!(parse_json(r#"{"flights": []}"#).is_err());
+    }
+}
@@ -0,0 +1,45 @@
+//! Flight tracking building blocks shared by the `flight-tracker-tui` binary.
+//!
+//! The crate exposes the OpenSky and AviationStack API clients, the merged
+//! [`flight::Flight`] model, a couple of small caches, and the persisted
+//! search history. The terminal UI itself lives in the binary target.
+//!
+//! ```
+//! use flight_tracker_tui::api::normalize_callsign;
+//! use flight_tracker_tui::flight::FlightStatus;
+//!
+//! assert_eq!(normalize_callsign("BA285"), "BAW285");
+//! assert_eq!(FlightStatus::from_api_status("active"), FlightStatus::EnRoute);
+//! ```
+
+pub mod aircraft;
+pub mod airports;
+pub mod alert;
+pub mod api;
+pub mod budget;
+pub mod cache;
+pub mod config;
+pub mod flight;
+pub mod geo;
+pub mod geojson;
+pub mod glyphs;
+pub mod history;
+pub mod import;
+pub mod lock;
+pub mod onboarding;
+pub mod paths;
+pub mod persist;
+pub mod recording;
+pub mod refdata;
+pub mod report;
+pub mod session;
+pub mod squawk;
+pub mod taf;
+pub mod tracks;
+pub mod trip;
+pub mod watchlist;
+pub mod webhook;
+
+mod error;
+
+pub use error::AppError;
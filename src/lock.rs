@@ -0,0 +1,209 @@
+//! Keeping two instances from writing over each other's files.
+//!
+//! [`InstanceLock::acquire`] puts an `instance.lock` file holding this
+//! process's PID in the data directory, and dropping the lock removes it. A
+//! lock whose PID no longer names a running process was left behind by a
+//! crash and is taken over.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use thiserror::Error;
+
+/// Lock file in the data directory, holding the owner's PID.
+pub const LOCK_FILE: &str = "instance.lock";
+
+/// Why the data directory couldn't be locked.
+#[derive(Error, Debug)]
+pub enum LockError {
+    #[error("Another instance (pid {pid}) is using {}", dir.display())]
+    Held { pid: u32, dir: PathBuf },
+
+    #[error("Could not create the lock file: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// The data directory, held until dropped.
+#[derive(Debug)]
+pub struct InstanceLock {
+    path: PathBuf,
+    pid: u32,
+}
+
+impl InstanceLock {
+    /// Lock `dir` for this process, creating it if needed.
+    pub fn acquire(dir: &Path) -> Result<Self, LockError> {
+        Self::acquire_as(dir, std::process::id(), is_running)
+    }
+
+    /// [`acquire`](Self::acquire) as process `pid`, with liveness checked
+    /// through `is_running`.
+    fn acquire_as(
+        dir: &Path,
+        pid: u32,
+        is_running: impl Fn(u32) -> bool,
+    ) -> Result<Self, LockError> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(LOCK_FILE);
+        // Write the PID first and link it into place, so the lock file is
+        // never seen empty and the link fails if another process got there
+        let staging = dir.join(format!("{LOCK_FILE}.{pid}"));
+        fs::write(&staging, pid.to_string())?;
+        let result = Self::link(&staging, &path, dir, &is_running);
+        let _ = fs::remove_file(&staging);
+        result?;
+        Ok(Self { path, pid })
+    }
+
+    fn link(
+        staging: &Path,
+        path: &Path,
+        dir: &Path,
+        is_running: &impl Fn(u32) -> bool,
+    ) -> Result<(), LockError> {
+        // A second try after clearing a stale lock; losing that race to
+        // another instance leaves its lock in place
+        for _ in 0..2 {
+            match fs::hard_link(staging, path) {
+                Ok(()) => return Ok(()),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(e) => return Err(e.into()),
+            }
+            match holder(path) {
+                Some(pid) if is_running(pid) => {
+                    return Err(LockError::Held {
+                        pid,
+                        dir: dir.to_path_buf(),
+                    })
+                }
+                stale => {
+                    tracing::info!(pid = ?stale, "Taking over a stale instance lock");
+                    match fs::remove_file(path) {
+                        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        match holder(path) {
+            Some(pid) => Err(LockError::Held {
+                pid,
+                dir: dir.to_path_buf(),
+            }),
+            None => Err(io::Error::other("lock file keeps changing").into()),
+        }
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        // Leave the file alone if it was taken over in the meantime
+        if holder(&self.path) == Some(self.pid) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// The PID in a lock file, if it has a readable one.
+fn holder(path: &Path) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Whether a process with this PID exists.
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    let procfs = Path::new("/proc");
+    if procfs.join("self").exists() {
+        return procfs.join(pid.to_string()).exists();
+    }
+    // No procfs (macOS): signal 0 checks for the process without touching it
+    Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(Stdio::null())
+        .status()
+        .map_or(true, |status| status.success())
+}
+
+/// Whether a process with this PID exists.
+#[cfg(windows)]
+fn is_running(pid: u32) -> bool {
+    Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {pid}"), "/NH"])
+        .stderr(Stdio::null())
+        .output()
+        .map_or(true, |out| {
+            String::from_utf8_lossy(&out.stdout).contains(&pid.to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lock-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_second_handle_is_refused_until_released() {
+        let dir = temp_dir("held");
+        let first = InstanceLock::acquire_as(&dir, 100, |_| true).unwrap();
+        assert_eq!(fs::read_to_string(dir.join(LOCK_FILE)).unwrap(), "100");
+
+        match InstanceLock::acquire_as(&dir, 200, |_| true) {
+            Err(LockError::Held { pid, .. }) => assert_eq!(pid, 100),
+            other => panic!("expected a held lock, got {other:?}"),
+        }
+        // The refused attempt leaves nothing behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        drop(first);
+        assert!(!dir.join(LOCK_FILE).exists());
+        let second = InstanceLock::acquire_as(&dir, 200, |_| true).unwrap();
+        drop(second);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_stale_locks_are_taken_over() {
+        let dir = temp_dir("stale");
+        let crashed = InstanceLock::acquire_as(&dir, 100, |_| true).unwrap();
+        std::mem::forget(crashed);
+
+        let lock = InstanceLock::acquire_as(&dir, 200, |pid| pid != 100).unwrap();
+        assert_eq!(fs::read_to_string(dir.join(LOCK_FILE)).unwrap(), "200");
+        drop(lock);
+
+        // Unreadable contents count as stale too
+        fs::write(dir.join(LOCK_FILE), "garbage").unwrap();
+        drop(InstanceLock::acquire_as(&dir, 300, |_| true).unwrap());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_taken_over_lock_is_not_removed() {
+        let dir = temp_dir("takeover");
+        let first = InstanceLock::acquire_as(&dir, 100, |_| true).unwrap();
+        fs::write(dir.join(LOCK_FILE), "200").unwrap();
+        drop(first);
+        assert_eq!(fs::read_to_string(dir.join(LOCK_FILE)).unwrap(), "200");
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_this_process_is_running() {
+        assert!(is_running(std::process::id()));
+        let dir = temp_dir("real");
+        let lock = InstanceLock::acquire(&dir).unwrap();
+        assert!(matches!(
+            InstanceLock::acquire(&dir),
+            Err(LockError::Held { .. })
+        ));
+        drop(lock);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,52 @@
+//! File-based diagnostic logging.
+//!
+//! The TUI owns stdout/stderr, so log records only ever go to
+//! `flight-tracker.log` in the data directory.
+
+use std::fs::{self, OpenOptions};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use tracing_subscriber::EnvFilter;
+
+use flight_tracker_tui::paths;
+
+const LOG_FILE: &str = "flight-tracker.log";
+const DEFAULT_LEVEL: &str = "info";
+
+/// Where log records are written.
+pub fn log_path() -> Option<PathBuf> {
+    paths::data_dir().map(|dir| dir.join(LOG_FILE))
+}
+
+/// Install the global subscriber.
+///
+/// `level` (from `--log-level`) takes precedence over `RUST_LOG`. If the log
+/// file cannot be opened, logging is silently disabled.
+pub fn init(level: Option<&str>) {
+    let filter = match level {
+        Some(level) => EnvFilter::try_new(level).ok(),
+        None => EnvFilter::try_from_default_env().ok(),
+    }
+    .unwrap_or_else(|| EnvFilter::new(DEFAULT_LEVEL));
+
+    let Some(dir) = paths::data_dir() else {
+        return;
+    };
+    if fs::create_dir_all(&dir).is_err() {
+        return;
+    }
+    let Ok(file) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(LOG_FILE))
+    else {
+        return;
+    };
+
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_ansi(false)
+        .with_writer(Mutex::new(file))
+        .try_init();
+}
@@ -1,180 +1,905 @@
-mod api;
+mod action;
 mod app;
-mod cache;
-mod error;
 mod event;
-mod flight;
-mod history;
+#[cfg(test)]
+mod fixtures;
+mod logging;
+mod notify;
+mod palette;
+mod replay;
+mod settings;
+mod theme;
+mod title;
 mod ui;
+mod ui_state;
 
+use flight_tracker_tui::{
+    aircraft, alert, api, budget, config, flight, geo, geojson, glyphs, history, import, lock,
+    onboarding, paths, persist, recording, refdata, report, session, squawk, taf, tracks, trip,
+    watchlist, webhook, AppError,
+};
+use import::FlightQuery;
+
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use chrono::{Local, Utc};
+use color_eyre::eyre::eyre;
 use color_eyre::Result;
-use crossterm::event::{KeyCode, KeyModifiers};
+use crossterm::event::{DisableFocusChange, EnableFocusChange};
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
-use api::{AviationStackClient, FlightData, OpenSkyClient, StateVector};
-use app::{App, AppMode};
+use action::{Action, Effect};
+use api::{
+    iata_flight_number, AviationStackClient, DemoProvider, FlightData, FlightTrack, OpenSkyClient,
+    PositionProvider, ScheduleProvider, StateSnapshot, StationWeather, WeatherClient,
+};
+use app::{App, AppMode, CacheKind, Capabilities, Diagnostics, ProviderHealth};
 use event::{Event, EventHandler};
 
+/// Report file written by `E`, in the data directory.
+const REPORT_FILE: &str = "flight-report.md";
+
 enum ApiResponse {
     FlightSearch {
-        flight_number: String,
-        position: Result<Option<StateVector>, error::AppError>,
-        schedule: Option<FlightData>,
+        query: FlightQuery,
+        /// `None` if the position wasn't looked up (flights on other days)
+        position: Result<Option<StateSnapshot>, AppError>,
+        schedule: Option<Box<FlightData>>,
     },
-    FlightUpdate(String, Result<Option<StateVector>, error::AppError>),
+    FlightUpdate(String, Result<StateSnapshot, AppError>),
+    ScheduleUpdate(String, Result<Option<Box<FlightData>>, AppError>),
+    Track(String, Result<Option<FlightTrack>, AppError>),
+    /// A callsign search for a flight whose aircraft looked swapped
+    Rematch(String, Result<StateSnapshot, AppError>),
+    Webhook(Result<(), AppError>),
+    /// A destination's METAR and TAF, by ICAO code
+    Weather(String, Result<StationWeather, AppError>),
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Load .env file if present
     let _ = dotenvy::dotenv();
+    // Then the one the first-run walkthrough creates; set variables win
+    if let Some(dir) = paths::data_dir() {
+        let _ = dotenvy::from_path(dir.join(".env"));
+    }
 
     color_eyre::install()?;
 
+    let args = CliArgs::parse()?;
+    logging::init(args.log_level.as_deref());
+    if args.update_data {
+        return update_data().await;
+    }
+    if args.once {
+        return print_once(&args).await;
+    }
+    tracing::info!("Starting flight tracker");
+
+    let config = config::Config::load();
+    // Demo mode and replays never save anything, so they run alongside anything
+    let (instance_lock, read_only_notice) = if args.demo || args.replay.is_some() {
+        (None, None)
+    } else {
+        lock_data_dir(config.second_instance)?
+    };
+    // Only the instance that owns the data directory deletes anything
+    if instance_lock.is_some() {
+        sweep_tracks(&config.track_retention);
+    }
+    // The demo's scripted flights are written against the built-in tables
+    if !args.demo {
+        load_reference_data();
+    }
+    // Asking the terminal has to happen before ratatui takes over its input
+    let theme = theme::Theme::load(config.background);
+
     let mut terminal = ratatui::init();
-    let result = run(&mut terminal).await;
+    // Best-effort: terminals without focus reporting simply never send the events
+    let _ = crossterm::execute!(std::io::stdout(), EnableFocusChange);
+    // Owned here so the title is blanked however `run` ends
+    let mut terminal_title = title::TerminalTitle::default();
+    let result = run(
+        &mut terminal,
+        &mut terminal_title,
+        args,
+        theme,
+        read_only_notice,
+    )
+    .await;
+    // Sessions, history and caches are saved in the background
+    persist::flush();
+    drop(instance_lock);
+    terminal_title.clear();
+    let _ = crossterm::execute!(std::io::stdout(), DisableFocusChange);
     ratatui::restore();
 
     result
 }
 
+/// Lock the data directory for this instance. When another instance holds
+/// it, either refuse to start or carry on without saving, as configured;
+/// the second value is then the status line saying so.
+fn lock_data_dir(
+    on_conflict: config::SecondInstance,
+) -> Result<(Option<lock::InstanceLock>, Option<String>)> {
+    let Some(dir) = paths::data_dir() else {
+        return Ok((None, None));
+    };
+    match lock::InstanceLock::acquire(&dir) {
+        Ok(lock) => Ok((Some(lock), None)),
+        Err(e @ lock::LockError::Held { pid, .. }) => match on_conflict {
+            config::SecondInstance::Refuse => Err(eyre!(
+                "{}; quit it first, or set second_instance = \"read-only\" in config.toml",
+                e
+            )),
+            config::SecondInstance::ReadOnly => {
+                tracing::warn!(pid, "Another instance holds the data directory, not saving");
+                persist::set_read_only();
+                let notice = format!(
+                    "Read-only: instance {} is running, nothing will be saved",
+                    pid
+                );
+                Ok((None, Some(notice)))
+            }
+        },
+        Err(e) => {
+            // Not worth refusing to start over
+            tracing::warn!(error = %e, "Could not lock the data directory");
+            Ok((None, None))
+        }
+    }
+}
+
+/// Rotate and trim the track recordings to the configured retention.
+fn sweep_tracks(retention: &tracks::Retention) {
+    let Some(dir) = tracks::tracks_dir() else {
+        return;
+    };
+    match tracks::sweep(&dir, retention) {
+        Ok(0) => {}
+        Ok(bytes) => tracing::info!(bytes, dir = %dir.display(), "Reclaimed track recording space"),
+        Err(e) => tracing::warn!(error = %e, "Could not sweep the track recordings"),
+    }
+}
+
+/// Download the airline and airport tables into the data directory for
+/// `--update-data`, keeping the old ones if anything fails.
+async fn update_data() -> Result<()> {
+    let dir = paths::data_dir().ok_or_else(|| eyre!("No data directory to save the tables in"))?;
+    println!("Downloading airports from {}", refdata::AIRPORTS_URL);
+    println!("Downloading airlines from {}", refdata::AIRLINES_URL);
+    let counts = refdata::update(&dir)
+        .await
+        .map_err(|e| eyre!("Update failed, the previous tables are unchanged: {}", e))?;
+    println!(
+        "Saved {} airports and {} airlines to {}",
+        counts.airports,
+        counts.airlines,
+        dir.display()
+    );
+    Ok(())
+}
+
+/// Search once for the saved session's flights, or those of `--import` or
+/// the demo, and print them as a plain-text table for `--once`. Nothing is
+/// saved, so it can run next to an open instance.
+async fn print_once(args: &CliArgs) -> Result<()> {
+    persist::set_read_only();
+    if !args.demo {
+        load_reference_data();
+    }
+    let mut app = if args.demo {
+        App::default()
+    } else {
+        App::new()
+    };
+    let clients = if args.demo {
+        app.queue_flights(
+            DemoProvider::flight_numbers().map(|n| n.to_string().into()),
+            0,
+        );
+        ApiClients::demo()
+    } else {
+        ApiClients::live(&app.config)
+    };
+    match &args.import {
+        Some(path) => app.import_file(path),
+        // No grace period to wait out: every restored flight is wanted
+        None => app.resume_restored(),
+    }
+
+    let (api_tx, mut api_rx) = mpsc::channel::<ApiResponse>(32);
+    dispatch_searches(&mut app, &clients, &api_tx);
+    let mut ticks = tokio::time::interval(Duration::from_secs(1));
+    while app.is_searching() {
+        tokio::select! {
+            Some(response) = api_rx.recv() => handle_api_response(&mut app, response),
+            _ = ticks.tick() => app.expire_searches(Instant::now()),
+        }
+    }
+    if let Some(error) = app.last_error.take() {
+        eprintln!("{}", error);
+    }
+
+    let contents = report::render(
+        &app.tracked_flights,
+        &app.config.table.resolve(),
+        report::ReportFormat::Text,
+        app.config.time_format,
+    );
+    if app.config.ascii_only {
+        print!("{}", glyphs::to_ascii(&contents));
+    } else {
+        print!("{}", contents);
+    }
+    Ok(())
+}
+
+/// Use the tables `--update-data` saved, where there are any.
+fn load_reference_data() {
+    let Some(dir) = paths::data_dir() else {
+        return;
+    };
+    let counts = refdata::load(&dir);
+    if counts != refdata::Counts::default() {
+        tracing::info!(
+            airports = counts.airports,
+            airlines = counts.airlines,
+            "Using downloaded reference data"
+        );
+    }
+}
+
+/// Command-line options.
+#[derive(Debug, Default)]
+struct CliArgs {
+    /// CSV or JSON file of flights to track on startup.
+    import: Option<PathBuf>,
+    /// Refresh interval multiplier while the terminal is unfocused (0 pauses).
+    background_refresh: Option<u64>,
+    /// Log filter directive, overriding `RUST_LOG`.
+    log_level: Option<String>,
+    /// Serve scripted flights instead of calling the real APIs.
+    demo: bool,
+    /// File to keep updated with tracked positions as GeoJSON.
+    geojson: Option<PathBuf>,
+    /// Shared file of flights to keep tracked while they are listed.
+    watchlist: Option<PathBuf>,
+    /// Download fresh airline and airport tables, then exit.
+    update_data: bool,
+    /// Print the tracked flights as a plain-text table, then exit.
+    once: bool,
+    /// Linear plain-text layout for screen readers.
+    accessible: bool,
+    /// File to append every key action and API response to.
+    record: Option<PathBuf>,
+    /// Recording to play back instead of calling the real APIs.
+    replay: Option<PathBuf>,
+    /// How many times faster than recorded to play it back.
+    replay_speed: Option<f64>,
+}
+
+impl CliArgs {
+    fn parse() -> Result<Self> {
+        let mut args = CliArgs::default();
+        let mut iter = std::env::args().skip(1);
+
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--import" => {
+                    let path = iter
+                        .next()
+                        .ok_or_else(|| eyre!("--import requires a path"))?;
+                    args.import = Some(PathBuf::from(path));
+                }
+                "--background-refresh" => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| eyre!("--background-refresh requires a multiplier"))?;
+                    let multiplier = match value.as_str() {
+                        "pause" => 0,
+                        n => n
+                            .parse()
+                            .map_err(|_| eyre!("Invalid --background-refresh value: {}", n))?,
+                    };
+                    args.background_refresh = Some(multiplier);
+                }
+                "--demo" => args.demo = true,
+                "--update-data" => args.update_data = true,
+                "--once" => args.once = true,
+                "--accessible" => args.accessible = true,
+                "--geojson" => {
+                    let path = iter
+                        .next()
+                        .ok_or_else(|| eyre!("--geojson requires a path"))?;
+                    args.geojson = Some(PathBuf::from(path));
+                }
+                "--watchlist" => {
+                    let path = iter
+                        .next()
+                        .ok_or_else(|| eyre!("--watchlist requires a path"))?;
+                    args.watchlist = Some(PathBuf::from(path));
+                }
+                "--log-level" => {
+                    let level = iter
+                        .next()
+                        .ok_or_else(|| eyre!("--log-level requires a level"))?;
+                    args.log_level = Some(level);
+                }
+                "--record" => {
+                    let path = iter
+                        .next()
+                        .ok_or_else(|| eyre!("--record requires a path"))?;
+                    args.record = Some(PathBuf::from(path));
+                }
+                "--replay" => {
+                    let path = iter
+                        .next()
+                        .ok_or_else(|| eyre!("--replay requires a path"))?;
+                    args.replay = Some(PathBuf::from(path));
+                }
+                "--replay-speed" => {
+                    let value = iter
+                        .next()
+                        .ok_or_else(|| eyre!("--replay-speed requires a multiplier"))?;
+                    let speed = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|speed| speed.is_finite() && *speed > 0.0)
+                        .ok_or_else(|| eyre!("Invalid --replay-speed value: {}", value))?;
+                    args.replay_speed = Some(speed);
+                }
+                other => return Err(eyre!("Unknown argument: {}", other)),
+            }
+        }
+
+        if args.replay.is_some() && (args.demo || args.record.is_some()) {
+            return Err(eyre!("--replay can't be combined with --demo or --record"));
+        }
+        if args.once && (args.replay.is_some() || args.record.is_some()) {
+            return Err(eyre!("--once can't be combined with --replay or --record"));
+        }
+        if args.replay_speed.is_some() && args.replay.is_none() {
+            return Err(eyre!("--replay-speed needs --replay"));
+        }
+
+        Ok(args)
+    }
+}
+
 struct ApiClients {
-    opensky: OpenSkyClient,
-    aviationstack: AviationStackClient,
+    position: Arc<dyn PositionProvider>,
+    schedule: Arc<dyn ScheduleProvider>,
+    /// Where status changes are posted, if configured; never in demo mode
+    webhook: Option<webhook::Webhook>,
+    /// Destination weather; never in demo mode
+    weather: Option<WeatherClient>,
+    /// Whether the providers are a replay's, which never answer
+    replay: bool,
+}
+
+impl ApiClients {
+    fn live(config: &config::Config) -> Self {
+        let timeout = Duration::from_secs;
+        Self {
+            position: Arc::new(
+                OpenSkyClient::new().with_timeout(timeout(config.opensky_timeout_secs)),
+            ),
+            schedule: Arc::new(
+                AviationStackClient::new().with_timeout(timeout(config.aviationstack_timeout_secs)),
+            ),
+            webhook: config.webhook_url.as_deref().map(webhook::Webhook::new),
+            weather: Some(WeatherClient::new()),
+            replay: false,
+        }
+    }
+
+    fn demo() -> Self {
+        let demo = DemoProvider::new();
+        Self {
+            position: Arc::new(demo.clone()),
+            schedule: Arc::new(demo),
+            webhook: None,
+            weather: None,
+            replay: false,
+        }
+    }
+
+    /// Providers that never answer, for replaying a recording.
+    fn replay() -> Self {
+        Self {
+            position: Arc::new(replay::Silent),
+            schedule: Arc::new(replay::Silent),
+            webhook: None,
+            weather: None,
+            replay: true,
+        }
+    }
+
+    fn clear_cache(&self, kind: CacheKind) {
+        match kind {
+            CacheKind::Position => self.position.clear_cache(),
+            CacheKind::Schedule => self.schedule.clear_cache(),
+        }
+    }
+
+    /// Each provider's recent requests, for the dashboard line.
+    fn health(&self) -> Vec<ProviderHealth> {
+        let (position, schedule) = (self.position.diagnostics(), self.schedule.diagnostics());
+        vec![
+            ProviderHealth {
+                name: position.name,
+                role: "position",
+                stats: position.stats,
+                degraded: position.breaker.is_degraded(),
+                requests_this_month: None,
+                auth: self.position.auth_mode(),
+            },
+            ProviderHealth {
+                name: schedule.name,
+                role: "schedule",
+                stats: schedule.stats,
+                degraded: schedule.breaker.is_degraded(),
+                requests_this_month: self.schedule.requests_this_month(),
+                auth: None,
+            },
+        ]
+    }
+
+    /// Current contents for the diagnostics overlay.
+    fn diagnostics(&self) -> Diagnostics {
+        Diagnostics {
+            providers: vec![
+                ("position", self.position.diagnostics()),
+                ("schedule", self.schedule.diagnostics()),
+            ],
+            data_dir: paths::data_dir(),
+            config_path: config::Config::config_path(),
+            log_path: logging::log_path(),
+        }
+    }
 }
 
-async fn run(terminal: &mut ratatui::DefaultTerminal) -> Result<()> {
-    let mut app = App::new();
-    let mut events = EventHandler::new(Duration::from_millis(250));
+async fn run(
+    terminal: &mut ratatui::DefaultTerminal,
+    terminal_title: &mut title::TerminalTitle,
+    args: CliArgs,
+    theme: theme::Theme,
+    read_only_notice: Option<String>,
+) -> Result<()> {
+    // Demo mode and replays start from a clean slate and never touch the
+    // saved session
+    let fresh = args.demo || args.replay.is_some();
+    let mut app = if fresh { App::default() } else { App::new() };
+    app.theme = theme;
+    app.accessible = args.accessible;
+    if let Some(multiplier) = args.background_refresh {
+        app.background_multiplier = multiplier;
+    }
+    let mut events = EventHandler::new(Duration::from_millis(app.config.tick_rate_ms));
+    let mut geojson_writer = args
+        .geojson
+        .clone()
+        .or_else(|| app.config.geojson_path.clone())
+        .map(geojson::GeoJsonWriter::new);
+    // Demo mode and replays keep to their own flights
+    let mut watchlist = args
+        .watchlist
+        .clone()
+        .or_else(|| app.config.watchlist_path.clone())
+        .filter(|_| !fresh)
+        .map(watchlist::Watchlist::new);
 
-    let clients = ApiClients {
-        opensky: OpenSkyClient::new(),
-        aviationstack: AviationStackClient::new(),
+    let clients = if args.demo {
+        ApiClients::demo()
+    } else if args.replay.is_some() {
+        ApiClients::replay()
+    } else {
+        ApiClients::live(&app.config)
     };
 
-    // Show hint if AviationStack API key is available
-    if clients.aviationstack.has_api_key() {
+    app.capabilities = if fresh {
+        Capabilities::demo()
+    } else {
+        Capabilities::from_env()
+    };
+    if args.demo {
+        app.queue_flights(
+            DemoProvider::flight_numbers().map(|n| n.to_string().into()),
+            0,
+        );
+        app.status_message = Some("Demo mode: scripted flights, no network".to_string());
+    } else if let Some(banner) = app.capabilities.banner() {
+        // Until the first key press, say which keys are missing
+        app.status_message = Some(banner);
+    } else {
         app.status_message = Some("AviationStack API enabled for route data".to_string());
     }
+    if let Some(notice) = read_only_notice {
+        app.status_message = Some(notice);
+    }
 
     let (api_tx, mut api_rx) = mpsc::channel::<ApiResponse>(32);
+    let (replay_tx, mut replay_rx) = mpsc::channel::<replay::Recorded>(32);
+    let (watchlist_tx, mut watchlist_rx) = mpsc::channel(1);
+    let mut replaying = false;
+    if let Some(path) = &args.replay {
+        let speed = args.replay_speed.unwrap_or(1.0);
+        let count = replay::spawn(path, speed, replay_tx)
+            .map_err(|e| eyre!("Could not read {}: {}", path.display(), e))?;
+        replaying = true;
+        app.status_message = Some(format!("Replaying {} events at {}×", count, speed));
+    }
+    let mut recorder = match &args.record {
+        Some(path) => {
+            let mut secrets = recording::secrets_from_env();
+            secrets.extend(app.config.webhook_url.clone());
+            let recorder = recording::Recorder::create(path, secrets)
+                .map_err(|e| eyre!("Could not record to {}: {}", path.display(), e))?;
+            Some(recorder)
+        }
+        None => None,
+    };
+
+    if let Some(path) = args.import.as_ref().filter(|_| !replaying) {
+        app.import_file(path);
+    }
+
+    // A fresh install opens on the walkthrough, unless given flights to track
+    if !fresh && args.import.is_none() {
+        if let Some(dir) = paths::data_dir() {
+            app.begin_onboarding(dir);
+        }
+    }
+
+    // Re-track flights from the import file; the previous session's wait
+    // for r, d or the grace period
+    if !app.pending_searches.is_empty() {
+        app.mode = AppMode::Viewing;
+        dispatch_searches(&mut app, &clients, &api_tx);
+    }
 
     loop {
         terminal.draw(|frame| ui::draw(frame, &app))?;
+        events.set_animating(app.is_animating());
 
         tokio::select! {
             Some(event) = events.next() => {
                 match event {
                     Event::Key(key) => {
-                        handle_key_event(&mut app, key, &clients, api_tx.clone()).await;
+                        let action =
+                            handle_key_event(&mut app, key, &clients, api_tx.clone()).await;
+                        if let Some(action) = action {
+                            record(&mut recorder, &replay::Recorded::Action(action));
+                        }
                     }
                     Event::Tick => {
+                        // Read off the event loop; the watchlist comes back
+                        // with what it found
+                        if let Some(mut list) = watchlist.take_if(|list| list.tick()) {
+                            let tx = watchlist_tx.clone();
+                            tokio::task::spawn_blocking(move || {
+                                let read = list.check();
+                                let _ = tx.blocking_send((list, read));
+                            });
+                        }
                         handle_tick(&mut app, &clients, api_tx.clone()).await;
+                        // Export once a refresh cycle's responses are in
+                        if let Some(writer) = geojson_writer.as_mut().filter(|_| !app.loading) {
+                            if let Err(e) = writer.write(&app.tracked_flights) {
+                                tracing::warn!(error = %e, "Could not write GeoJSON file");
+                            }
+                        }
+                    }
+                    Event::Render | Event::Resize(_, _) => {}
+                    Event::FocusGained => {
+                        if app.set_focused(true) && !clients.replay {
+                            let indices = app.refreshable_flights();
+                            refresh_flights(&mut app, &indices, &clients, api_tx.clone()).await;
+                        }
+                    }
+                    Event::FocusLost => {
+                        app.set_focused(false);
                     }
-                    Event::Resize(_, _) => {}
                 }
             }
             Some(response) = api_rx.recv() => {
+                let recorded = Box::new(recording::Response::from(&response));
+                record(&mut recorder, &replay::Recorded::Response(recorded));
                 handle_api_response(&mut app, response);
+                dispatch_tracks(&mut app, &clients, &api_tx);
+                dispatch_rematches(&mut app, &clients, &api_tx);
+            }
+            Some((list, read)) = watchlist_rx.recv() => {
+                match read {
+                    Some(Ok(entries)) => app.watchlist_pending = Some(entries),
+                    Some(Err(e)) => {
+                        let path = list.path().display();
+                        tracing::warn!(error = %e, %path, "Could not read watchlist");
+                        app.status_message =
+                            Some(format!("Could not read watchlist {}: {}", path, e));
+                    }
+                    None => {}
+                }
+                watchlist = Some(list);
+            }
+            recorded = replay_rx.recv(), if replaying => match recorded {
+                // Stay on the final state to look at it
+                Some(replay::Recorded::Action(Action::Quit)) => {}
+                Some(replay::Recorded::Action(action)) => {
+                    handle_action(&mut app, action, &clients, &api_tx).await;
+                }
+                Some(replay::Recorded::Response(response)) => {
+                    handle_api_response(&mut app, (*response).into());
+                    dispatch_tracks(&mut app, &clients, &api_tx);
+                    dispatch_rematches(&mut app, &clients, &api_tx);
+                }
+                None => {
+                    replaying = false;
+                    app.status_message = Some("Replay finished — q to quit".to_string());
+                }
+            },
+        }
+        // From responses, or arrival notices on a tick; a replay has been
+        // notified once already
+        for message in app.pending_alerts.drain(..) {
+            if args.replay.is_none() {
+                notify::alert(&message);
             }
         }
 
+        if app.config.terminal_title {
+            let flight = app.followed_index().map(|i| &app.tracked_flights[i]);
+            let text = title::format_title(
+                flight,
+                app.config.time_format,
+                app.config.ascii_only,
+                title::MAX_CHARS,
+            );
+            terminal_title.update(text, Instant::now());
+        } else {
+            // Turned off in the settings
+            terminal_title.clear();
+        }
+
         if app.should_quit {
             break;
         }
     }
 
+    // A toggle still waiting out the save delay; flushed with the rest
+    app.save_ui_state(Instant::now(), true);
     Ok(())
 }
 
+/// Carry out what `key` means, returning the action it was.
 async fn handle_key_event(
     app: &mut App,
     key: crossterm::event::KeyEvent,
     clients: &ApiClients,
     api_tx: mpsc::Sender<ApiResponse>,
-) {
+) -> Option<Action> {
     // Clear transient messages
     app.status_message = None;
+    app.alert_message = None;
 
-    match app.mode {
-        AppMode::Input => {
-            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
-                app.should_quit = true;
-            } else {
-                match key.code {
-                    KeyCode::Enter => {
-                        if let Some(flight_number) = app.submit_input() {
-                            app.loading = true;
-                            app.last_error = None;
-
-                            let opensky = clients.opensky.clone();
-                            let aviationstack = clients.aviationstack.clone();
-                            let tx = api_tx.clone();
-                            let flight_num = flight_number.clone();
-
-                            tokio::spawn(async move {
-                                // Fetch from both APIs in parallel
-                                let (position_result, schedule_result) = tokio::join!(
-                                    opensky.search_flight(&flight_num),
-                                    aviationstack.get_flight(&flight_num)
-                                );
-
-                                let _ = tx
-                                    .send(ApiResponse::FlightSearch {
-                                        flight_number: flight_num,
-                                        position: position_result,
-                                        schedule: schedule_result.ok().flatten(),
-                                    })
-                                    .await;
-                            });
-                        }
-                    }
-                    KeyCode::Char(c) => {
-                        app.input_char(c.to_ascii_uppercase());
-                    }
-                    KeyCode::Backspace => {
-                        app.input_backspace();
-                    }
-                    KeyCode::Up => {
-                        app.history_next();
-                    }
-                    KeyCode::Down => {
-                        app.history_previous();
-                    }
-                    KeyCode::Esc => {
-                        app.mode = AppMode::Viewing;
-                        app.input_buffer.clear();
-                        app.cursor_position = 0;
-                        app.history_index = None;
-                    }
-                    _ => {}
-                }
-            }
+    let action = action::key_action(app, key)?;
+    handle_action(app, action.clone(), clients, &api_tx).await;
+    Some(action)
+}
+
+async fn handle_action(
+    app: &mut App,
+    action: Action,
+    clients: &ApiClients,
+    api_tx: &mpsc::Sender<ApiResponse>,
+) {
+    for effect in app.apply(action) {
+        run_effect(app, effect, clients, api_tx).await;
+    }
+}
+
+/// Append `event` to the recording, if there is one. A recording that
+/// can't be written to is given up on rather than interrupting the session.
+fn record(recorder: &mut Option<recording::Recorder>, event: &replay::Recorded) {
+    if let Some(Err(e)) = recorder.as_mut().map(|r| r.record(event)) {
+        tracing::warn!(error = %e, "Could not write to the recording, stopped recording");
+        *recorder = None;
+    }
+}
+
+/// Do the part of an action that needs the API clients or the filesystem.
+async fn run_effect(
+    app: &mut App,
+    effect: Effect,
+    clients: &ApiClients,
+    api_tx: &mpsc::Sender<ApiResponse>,
+) {
+    match effect {
+        Effect::StartSearches => dispatch_searches(app, clients, api_tx),
+        Effect::StartRefresh(indices) => {
+            refresh_flights(app, &indices, clients, api_tx.clone()).await;
         }
-        AppMode::Viewing => match key.code {
-            KeyCode::Char('q') => app.should_quit = true,
-            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                app.should_quit = true;
-            }
-            KeyCode::Char('/') | KeyCode::Char('a') => {
-                app.mode = AppMode::Input;
-            }
-            KeyCode::Up | KeyCode::Char('k') => app.select_previous(),
-            KeyCode::Down | KeyCode::Char('j') => app.select_next(),
-            KeyCode::Char('d') => app.remove_selected_flight(),
-            KeyCode::Char('r') => {
-                if !app.tracked_flights.is_empty() && !app.loading {
-                    trigger_refresh(app, clients, api_tx).await;
-                }
+        Effect::OpenDiagnostics => app.diagnostics = Some(clients.diagnostics()),
+        Effect::ClearCache(kind) => {
+            clients.clear_cache(kind);
+            // From the palette the overlay may be closed; leave it that way
+            if app.diagnostics.is_some() {
+                app.diagnostics = Some(clients.diagnostics());
             }
-            _ => {}
-        },
+        }
+        Effect::ExportReport => export_report(app),
+    }
+}
+
+/// Write a Markdown report of every tracked flight to the data directory,
+/// and copy it to the clipboard when there is one.
+fn export_report(app: &mut App) {
+    let contents = report::render(
+        &app.tracked_flights,
+        &app.config.table.resolve(),
+        report::ReportFormat::Markdown,
+        app.config.time_format,
+    );
+    let contents = if app.config.ascii_only {
+        glyphs::to_ascii(&contents).into_owned()
+    } else {
+        contents
+    };
+    let Some(path) = paths::data_dir().map(|dir| dir.join(REPORT_FILE)) else {
+        app.last_error = Some("No data directory to write the report to".to_string());
+        return;
+    };
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, &contents));
+    if let Err(e) = written {
+        app.last_error = Some(AppError::from(e).user_message());
+        return;
+    }
+
+    let copied = arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(contents))
+        .inspect_err(|e| tracing::debug!(error = %e, "Clipboard unavailable"))
+        .is_ok();
+    app.status_message = Some(format!(
+        "Report written to {}{}",
+        path.display(),
+        if copied {
+            " and copied to the clipboard"
+        } else {
+            ""
+        }
+    ));
+}
+
+/// Start a search for every queued flight number.
+fn dispatch_searches(app: &mut App, clients: &ApiClients, api_tx: &mpsc::Sender<ApiResponse>) {
+    if app.pending_searches.is_empty() {
+        return;
     }
+
+    app.loading = true;
+    for query in app.take_pending_searches() {
+        let flight_number = query.flight_number.clone();
+        // A flight searched again may already have been placed
+        let near = app
+            .tracked_flights
+            .iter()
+            .find(|f| f.flight_number == flight_number)
+            .and_then(flight::Flight::approximate_position);
+        let task = spawn_search(query, near, clients, api_tx.clone());
+        app.watch_search(&flight_number, task.abort_handle());
+    }
+}
+
+/// Fetch the position provider's track for each newly identified aircraft.
+fn dispatch_tracks(app: &mut App, clients: &ApiClients, api_tx: &mpsc::Sender<ApiResponse>) {
+    for (flight_number, icao24) in app.take_pending_tracks() {
+        let client = clients.position.clone();
+        let tx = api_tx.clone();
+        tokio::spawn(async move {
+            let result = client.get_track(&icao24).await;
+            let _ = tx.send(ApiResponse::Track(flight_number, result)).await;
+        });
+    }
+}
+
+/// Search by callsign for each flight whose aircraft looks swapped.
+fn dispatch_rematches(app: &mut App, clients: &ApiClients, api_tx: &mpsc::Sender<ApiResponse>) {
+    for flight_number in app.take_pending_rematches() {
+        let client = clients.position.clone();
+        let tx = api_tx.clone();
+        tokio::spawn(async move {
+            let result = client.search_flight(&flight_number).await;
+            let _ = tx.send(ApiResponse::Rematch(flight_number, result)).await;
+        });
+    }
+}
+
+/// Fetch the weather at each destination that is due for it.
+fn dispatch_weather(app: &mut App, clients: &ApiClients, api_tx: &mpsc::Sender<ApiResponse>) {
+    let Some(client) = &clients.weather else {
+        return;
+    };
+    for icao in app.take_due_weather(Instant::now()) {
+        let client = client.clone();
+        let tx = api_tx.clone();
+        tokio::spawn(async move {
+            let result = client.station(&icao).await;
+            let _ = tx.send(ApiResponse::Weather(icao, result)).await;
+        });
+    }
+}
+
+/// Look up a flight on both providers in parallel and report back as a
+/// `FlightSearch` response. The position search is narrowed around `near`
+/// when known; the schedule's route isn't waited for to narrow it.
+///
+/// Live position is only searched for today's flights; another day's
+/// callsign would match whichever leg is airborne right now.
+fn spawn_search(
+    query: FlightQuery,
+    near: Option<(f64, f64)>,
+    clients: &ApiClients,
+    api_tx: mpsc::Sender<ApiResponse>,
+) -> JoinHandle<()> {
+    let position = clients.position.clone();
+    let schedule = clients.schedule.clone();
+    let is_today = query
+        .date
+        .is_none_or(|date| date == chrono::Local::now().date_naive());
+
+    tokio::spawn(async move {
+        // Schedules are looked up by IATA number even if an ICAO callsign was
+        // typed
+        let schedule_number = iata_flight_number(&query.flight_number);
+        let position_search = async {
+            if is_today {
+                position
+                    .search_flight_near(&query.flight_number, near)
+                    .await
+                    .map(Some)
+            } else {
+                Ok(None)
+            }
+        };
+        let (position_result, schedule_result) = tokio::join!(
+            position_search,
+            schedule.get_flight(&schedule_number, query.date)
+        );
+
+        let _ = api_tx
+            .send(ApiResponse::FlightSearch {
+                query,
+                position: position_result,
+                schedule: schedule_result.ok().flatten().map(Box::new),
+            })
+            .await;
+    })
 }
 
 async fn handle_tick(app: &mut App, clients: &ApiClients, api_tx: mpsc::Sender<ApiResponse>) {
+    app.expire_searches(Instant::now());
+    // Restored session flights go out once the grace period is over
+    app.end_restore_grace(Instant::now());
+    app.apply_watchlist();
+    app.sweep_arrivals();
+    app.roll_over_recurring(Local::now());
+    app.expire_mutes(Utc::now());
+    app.check_arrival_notices(Utc::now());
+    app.save_ui_state(Instant::now(), false);
+    app.set_provider_health(clients.health());
+    if app.diagnostics.is_some() {
+        app.diagnostics = Some(clients.diagnostics());
+    }
+
     // Clear error after some time
     if app.last_error.is_some() {
         if let Some(last) = app.last_api_call {
@@ -184,66 +909,459 @@ async fn handle_tick(app: &mut App, clients: &ApiClients, api_tx: mpsc::Sender<A
         }
     }
 
-    // Auto-refresh
+    // A replay's responses all come from the recording
+    if clients.replay {
+        return;
+    }
+    dispatch_searches(app, clients, &api_tx);
+    post_webhooks(app, clients, &api_tx);
+    dispatch_weather(app, clients, &api_tx);
+
+    // Auto-refresh the flights whose phase interval has elapsed, slowed down
+    // to make the daily position credits last
+    app.update_budget(clients.position.requests_today(), Local::now());
     if app.should_update() {
-        trigger_refresh(app, clients, api_tx).await;
+        let indices = app.due_flights();
+        refresh_flights(app, &indices, clients, api_tx.clone()).await;
+    }
+
+    // Slow schedule refresh, for delays and gate changes posted after the search
+    if clients.schedule.is_enabled() {
+        let indices = app.schedule_due_flights(clients.schedule.requests_this_month());
+        refresh_schedules(app, &indices, clients, api_tx);
+    }
+}
+
+/// Post the status changes that are due to the webhook, if there is one.
+fn post_webhooks(app: &mut App, clients: &ApiClients, api_tx: &mpsc::Sender<ApiResponse>) {
+    let Some(webhook) = &clients.webhook else {
+        return;
+    };
+    for change in app.take_webhooks(Instant::now()) {
+        let webhook = webhook.clone();
+        let tx = api_tx.clone();
+        tokio::spawn(async move {
+            let result = webhook.send(&change).await;
+            let _ = tx.send(ApiResponse::Webhook(result)).await;
+        });
     }
 }
 
-async fn trigger_refresh(
+/// Re-fetch schedules for the given flights in the background.
+fn refresh_schedules(
     app: &mut App,
+    indices: &[usize],
     clients: &ApiClients,
     api_tx: mpsc::Sender<ApiResponse>,
 ) {
+    let Some(max_age) = app.schedule_refresh_interval() else {
+        return;
+    };
+    app.mark_schedule_polled(indices);
+
+    for &index in indices {
+        let flight = &app.tracked_flights[index];
+        let client = clients.schedule.clone();
+        let tx = api_tx.clone();
+        let flight_num = flight.flight_number.clone();
+        let date = flight.flight_date;
+
+        tokio::spawn(async move {
+            let result = client
+                .refresh_flight(&iata_flight_number(&flight_num), date, max_age)
+                .await
+                .map(|data| data.map(Box::new));
+            let _ = tx
+                .send(ApiResponse::ScheduleUpdate(flight_num, result))
+                .await;
+        });
+    }
+}
+
+/// Request fresh positions for the given flights.
+async fn refresh_flights(
+    app: &mut App,
+    indices: &[usize],
+    clients: &ApiClients,
+    api_tx: mpsc::Sender<ApiResponse>,
+) {
+    if indices.is_empty() {
+        return;
+    }
+
     app.loading = true;
-    app.last_api_call = Some(Instant::now());
     app.last_error = None;
+    app.mark_polled(indices);
 
-    for flight in &app.tracked_flights {
-        let client = clients.opensky.clone();
+    for &index in indices {
+        let flight = &app.tracked_flights[index];
+        let client = clients.position.clone();
         let tx = api_tx.clone();
         let icao24 = flight.icao24.clone();
         let flight_num = flight.flight_number.clone();
+        let near = flight.approximate_position();
 
-        if !icao24.is_empty() {
-            tokio::spawn(async move {
-                let result = client.get_state(&icao24).await;
-                let _ = tx.send(ApiResponse::FlightUpdate(flight_num, result)).await;
-            });
-        }
+        tokio::spawn(async move {
+            let result = client.get_state_near(&icao24, near).await;
+            let _ = tx.send(ApiResponse::FlightUpdate(flight_num, result)).await;
+        });
     }
 }
 
 fn handle_api_response(app: &mut App, response: ApiResponse) {
-    app.loading = false;
+    // Track lookups, swap searches and webhook posts run alongside searches
+    // and refreshes
+    if !matches!(
+        response,
+        ApiResponse::Track(..)
+            | ApiResponse::Rematch(..)
+            | ApiResponse::Webhook(_)
+            | ApiResponse::Weather(..)
+    ) {
+        app.loading = false;
+    }
 
     match response {
         ApiResponse::FlightSearch {
-            flight_number,
+            query,
             position,
             schedule,
-        } => match position {
-            Ok(state) => {
-                app.add_flight(flight_number, state, schedule);
-                app.last_api_call = Some(Instant::now());
-            }
-            Err(e) => {
-                // Even if position failed, we might have schedule data
-                if schedule.is_some() {
-                    app.add_flight(flight_number, None, schedule);
+        } => {
+            let flight_number = query.flight_number.clone();
+            match position {
+                Ok(snapshot) => {
+                    let time = snapshot.as_ref().map(|s| s.time);
+                    let schedule = schedule.map(|s| *s);
+                    let (state, candidates) = snapshot
+                        .map(|s| (s.state, s.candidates))
+                        .unwrap_or_default();
+                    app.add_flight(query, state, schedule);
+                    if let Some(time) = time {
+                        app.record_feed_time(&flight_number, time);
+                    }
+                    if !candidates.is_empty() {
+                        app.ambiguous_position(&flight_number, &candidates);
+                    }
                     app.last_api_call = Some(Instant::now());
-                } else {
-                    app.last_error = Some(e.user_message());
+                }
+                Err(e) => {
+                    // Even if position failed, we might have schedule data
+                    if let Some(schedule) = schedule {
+                        app.add_flight(query, None, Some(*schedule));
+                        app.last_api_call = Some(Instant::now());
+                    } else {
+                        app.last_error = Some(e.user_message());
+                    }
                 }
             }
-        },
+            // After adding, which needs to know if it was a watchlist search
+            app.finish_search(&flight_number);
+        }
         ApiResponse::FlightUpdate(flight_number, result) => match result {
-            Ok(state) => {
-                app.update_flight(&flight_number, state);
+            Ok(snapshot) => {
+                app.update_flight(&flight_number, snapshot.state);
+                app.record_feed_time(&flight_number, snapshot.time);
             }
+            // Said once when the provider started being skipped
+            Err(AppError::ProviderDegraded(_)) => {}
             Err(e) => {
                 app.last_error = Some(e.user_message());
             }
         },
+        ApiResponse::ScheduleUpdate(flight_number, result) => match result {
+            Ok(Some(data)) => app.update_schedule(&flight_number, *data),
+            Ok(None) | Err(AppError::ProviderDegraded(_)) => {}
+            Err(e) => {
+                app.last_error = Some(e.user_message());
+            }
+        },
+        // The trail just starts later without one, so failures stay quiet
+        ApiResponse::Track(flight_number, result) => match result {
+            Ok(Some(track)) => app.apply_track(&flight_number, track),
+            Ok(None) => {}
+            Err(e) => tracing::debug!(flight = %flight_number, error = %e, "No track history"),
+        },
+        // The aircraft polled so far stays tracked if the search fails
+        ApiResponse::Rematch(flight_number, result) => match result {
+            Ok(snapshot) => {
+                app.rematch_found(&flight_number, snapshot.state);
+                app.record_feed_time(&flight_number, snapshot.time);
+            }
+            Err(e) => tracing::debug!(flight = %flight_number, error = %e, "Swap search failed"),
+        },
+        ApiResponse::Webhook(result) => app.webhook_finished(result.err().map(|e| e.to_string())),
+        // Whatever weather was fetched before stays until the next refresh
+        ApiResponse::Weather(icao, result) => match result {
+            Ok(station) => app.weather_received(&icao, station),
+            Err(e) => tracing::debug!(icao, error = %e, "Weather fetch failed"),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use app::RestoreSummary;
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    #[tokio::test]
+    async fn test_double_submit_spawns_one_search() {
+        let mut app = App::default();
+        let clients = ApiClients::demo();
+        let (api_tx, mut api_rx) = mpsc::channel(8);
+
+        // Enter pressed twice before the first search answers
+        app.queue_input("UA900");
+        dispatch_searches(&mut app, &clients, &api_tx);
+        app.queue_input("UA900");
+        dispatch_searches(&mut app, &clients, &api_tx);
+        drop(api_tx);
+
+        let mut responses = 0;
+        while let Some(response) = api_rx.recv().await {
+            handle_api_response(&mut app, response);
+            responses += 1;
+        }
+
+        assert_eq!(responses, 1);
+        assert_eq!(app.tracked_flights.len(), 1);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Search for UA900 already running")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hung_search_times_out() {
+        let mut app = App::default();
+        // Lookups never finish, like a request that hangs without a client timeout
+        let clients = ApiClients::replay();
+        let (api_tx, mut api_rx) = mpsc::channel(8);
+
+        app.queue_input("UA123");
+        dispatch_searches(&mut app, &clients, &api_tx);
+        assert!(app.loading);
+        assert!(!app.should_update());
+
+        app.expire_searches(Instant::now() + app::SEARCH_TIMEOUT);
+        assert!(!app.loading);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Search timed out for UA123 — press r to retry")
+        );
+        // The aborted task drops its sender, closing the channel
+        drop(api_tx);
+        let closed = tokio::time::timeout(Duration::from_secs(5), api_rx.recv()).await;
+        assert!(closed.unwrap().is_none());
+
+        // r sends it out again
+        let (api_tx, _api_rx) = mpsc::channel(8);
+        app.mode = AppMode::Viewing;
+        handle_key_event(&mut app, KeyCode::Char('r').into(), &clients, api_tx).await;
+        assert!(app.loading);
+        app.queue_input("UA123");
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Search for UA123 already running")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_recorded_session_replays_to_the_same_flights() {
+        let path = std::env::temp_dir().join(format!("replay-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut recorder = Some(recording::Recorder::create(&path, Vec::new()).unwrap());
+
+        // Search a flight against the demo provider, recording as run() does
+        let mut app = App::default();
+        let clients = ApiClients::demo();
+        let (api_tx, mut api_rx) = mpsc::channel(8);
+        for code in "ba178".chars().map(KeyCode::Char).chain([KeyCode::Enter]) {
+            let action = handle_key_event(&mut app, code.into(), &clients, api_tx.clone()).await;
+            record(&mut recorder, &replay::Recorded::Action(action.unwrap()));
+        }
+        drop(api_tx);
+        while let Some(response) = api_rx.recv().await {
+            let recorded = Box::new(recording::Response::from(&response));
+            record(&mut recorder, &replay::Recorded::Response(recorded));
+            handle_api_response(&mut app, response);
+        }
+        drop(recorder);
+
+        // Played back fast against providers that never answer
+        let mut replayed = App::default();
+        let clients = ApiClients::replay();
+        let (api_tx, _api_rx) = mpsc::channel(8);
+        let (replay_tx, mut replay_rx) = mpsc::channel(8);
+        assert_eq!(replay::spawn(&path, 1000.0, replay_tx).unwrap(), 7);
+        while let Some(recorded) = replay_rx.recv().await {
+            match recorded {
+                replay::Recorded::Action(action) => {
+                    handle_action(&mut replayed, action, &clients, &api_tx).await;
+                }
+                replay::Recorded::Response(response) => {
+                    handle_api_response(&mut replayed, (*response).into());
+                }
+            }
+        }
+        let _ = std::fs::remove_file(&path);
+
+        let summary = |app: &App| {
+            let flight = &app.tracked_flights[0];
+            let position = (flight.latitude, flight.longitude);
+            format!(
+                "{} {} {:?} {:?}",
+                flight.flight_number, flight.status, position, flight.icao24
+            )
+        };
+        assert_eq!(replayed.tracked_flights.len(), 1);
+        assert_eq!(summary(&replayed), summary(&app));
+        assert!(!replayed.loading);
+    }
+
+    #[tokio::test]
+    async fn test_onboarding_keys() {
+        use crossterm::event::KeyEvent;
+
+        let home = std::env::temp_dir().join(format!("onboarding-keys-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&home);
+        let dir = paths::data_dir_with(|key| (key == "HOME").then(|| home.display().to_string()))
+            .unwrap();
+        let mut app = App::default();
+        app.begin_onboarding(dir.clone());
+        assert_eq!(app.mode, AppMode::Onboarding);
+        let clients = ApiClients::demo();
+        let (api_tx, _api_rx) = mpsc::channel(8);
+
+        handle_key_event(
+            &mut app,
+            KeyEvent::from(KeyCode::Char('c')),
+            &clients,
+            api_tx.clone(),
+        )
+        .await;
+        assert!(dir.join("config.toml").exists());
+        assert!(dir.join(".env").exists());
+        assert_eq!(app.mode, AppMode::Onboarding);
+
+        handle_key_event(&mut app, KeyEvent::from(KeyCode::Enter), &clients, api_tx).await;
+        assert_eq!(app.mode, AppMode::Input);
+        assert_eq!(app.input_buffer, onboarding::SUGGESTED_FLIGHT);
+        persist::flush();
+        assert!(dir.join(onboarding::MARKER_FILE).exists());
+
+        // Never again
+        let mut app = App::default();
+        app.begin_onboarding(dir);
+        assert_eq!(app.mode, AppMode::Input);
+        let _ = std::fs::remove_dir_all(home);
+    }
+
+    #[tokio::test]
+    async fn test_diagnostics_overlay_toggles() {
+        use crossterm::event::KeyEvent;
+
+        let mut app = App::default();
+        app.mode = AppMode::Viewing;
+        let clients = ApiClients::demo();
+        let (api_tx, _api_rx) = mpsc::channel(8);
+        let press = |c| KeyEvent::from(KeyCode::Char(c));
+
+        handle_key_event(&mut app, press('D'), &clients, api_tx.clone()).await;
+        let diagnostics = app.diagnostics.as_ref().unwrap();
+        let names: Vec<_> = diagnostics.providers.iter().map(|(_, p)| p.name).collect();
+        assert_eq!(names, ["Demo", "Demo"]);
+
+        handle_key_event(&mut app, press('D'), &clients, api_tx.clone()).await;
+        assert!(app.diagnostics.is_none());
+
+        handle_key_event(&mut app, press('D'), &clients, api_tx.clone()).await;
+        handle_key_event(&mut app, KeyEvent::from(KeyCode::Esc), &clients, api_tx).await;
+        assert!(app.diagnostics.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_esc_keeps_draft_and_ctrl_u_clears_it() {
+        use crossterm::event::KeyEvent;
+
+        let mut app = App::default();
+        let clients = ApiClients::demo();
+        let (api_tx, _api_rx) = mpsc::channel(8);
+        let key = |code| KeyEvent::from(code);
+
+        for c in "ba2".chars() {
+            handle_key_event(&mut app, key(KeyCode::Char(c)), &clients, api_tx.clone()).await;
+        }
+        handle_key_event(&mut app, key(KeyCode::Esc), &clients, api_tx.clone()).await;
+        assert_eq!(app.mode, AppMode::Viewing);
+        assert_eq!(app.input_draft, "BA2");
+
+        handle_key_event(&mut app, key(KeyCode::Char('/')), &clients, api_tx.clone()).await;
+        assert_eq!(app.input_buffer, "BA2");
+        assert_eq!(app.cursor_position, 3);
+
+        let ctrl_u = KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL);
+        handle_key_event(&mut app, ctrl_u, &clients, api_tx.clone()).await;
+        assert!(app.input_buffer.is_empty());
+        handle_key_event(&mut app, key(KeyCode::Esc), &clients, api_tx).await;
+        assert!(app.input_draft.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cache_clear_needs_confirmation() {
+        let mut app = App::default();
+        let clients = ApiClients::demo();
+        let (api_tx, _api_rx) = mpsc::channel(8);
+        app.mode = AppMode::Viewing;
+        let press = |c| KeyCode::Char(c).into();
+
+        // Only from the diagnostics overlay
+        handle_key_event(&mut app, press('s'), &clients, api_tx.clone()).await;
+        assert_eq!(app.pending_cache_clear, None);
+
+        handle_key_event(&mut app, press('D'), &clients, api_tx.clone()).await;
+        handle_key_event(&mut app, press('s'), &clients, api_tx.clone()).await;
+        assert_eq!(app.pending_cache_clear, Some(CacheKind::Schedule));
+        // Any other key cancels, without acting on it
+        handle_key_event(&mut app, press('q'), &clients, api_tx.clone()).await;
+        assert_eq!(app.pending_cache_clear, None);
+        assert!(!app.should_quit);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Kept the schedule cache")
+        );
+
+        handle_key_event(&mut app, press('p'), &clients, api_tx.clone()).await;
+        handle_key_event(&mut app, press('y'), &clients, api_tx).await;
+        assert_eq!(app.pending_cache_clear, None);
+        assert_eq!(
+            app.status_message.as_deref(),
+            Some("Cleared the position cache")
+        );
+        assert!(app.diagnostics.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_d_prunes_a_held_restore() {
+        let mut app = App::default();
+        let clients = ApiClients::demo();
+        let (api_tx, _api_rx) = mpsc::channel(8);
+        app.mode = AppMode::Viewing;
+        app.restore = Some(RestoreSummary {
+            queries: vec!["BA285".to_string().into(), "UA900".to_string().into()],
+            likely_landed: vec!["BA285".to_string()],
+            resume_at: Instant::now() + Duration::from_secs(30),
+        });
+
+        let action = action::key_action(&app, KeyCode::Char('d').into()).unwrap();
+        assert_eq!(app.apply(action), vec![Effect::StartSearches]);
+        assert!(app.restore.is_none());
+        assert_eq!(app.pending_searches.len(), 1);
+        assert_eq!(app.pending_searches[0].flight_number, "UA900");
+
+        // Without a held restore, r with nothing tracked does nothing
+        app.pending_searches.clear();
+        handle_key_event(&mut app, KeyCode::Char('r').into(), &clients, api_tx).await;
+        assert!(!app.loading);
     }
 }
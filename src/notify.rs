@@ -0,0 +1,39 @@
+//! Out-of-band alert delivery: terminal bell and desktop notifications.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const NOTIFICATION_TITLE: &str = "Flight Tracker";
+
+/// Ring the terminal bell and raise a desktop notification.
+///
+/// Delivery is best-effort; missing notification tools are ignored.
+pub fn alert(message: &str) {
+    let mut stdout = std::io::stdout();
+    let _ = stdout.write_all(b"\x07");
+    let _ = stdout.flush();
+
+    let _ = desktop_notification(message)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+}
+
+#[cfg(target_os = "macos")]
+fn desktop_notification(message: &str) -> Command {
+    let script = format!(
+        "display notification {:?} with title {:?}",
+        message, NOTIFICATION_TITLE
+    );
+    let mut cmd = Command::new("osascript");
+    cmd.arg("-e").arg(script);
+    cmd
+}
+
+#[cfg(not(target_os = "macos"))]
+fn desktop_notification(message: &str) -> Command {
+    let mut cmd = Command::new("notify-send");
+    cmd.arg(NOTIFICATION_TITLE).arg(message);
+    cmd
+}
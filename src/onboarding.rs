@@ -0,0 +1,173 @@
+//! The first-run walkthrough: spotting a fresh install and writing starter
+//! files.
+//!
+//! A launch counts as the first when the data directory has no config,
+//! history, session or `first_run_done` marker and no API keys are set.
+//! Finishing or skipping the walkthrough writes the marker, so it is shown
+//! at most once.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::persist;
+
+/// Written to the data directory once the walkthrough is over.
+pub const MARKER_FILE: &str = "first_run_done";
+
+/// A long-haul flight that operates daily, suggested as a first search.
+pub const SUGGESTED_FLIGHT: &str = "BA285";
+
+/// Files whose presence means the app has been used before.
+const EXISTING_STATE: &[&str] = &["config.toml", "history.json", "session.json", MARKER_FILE];
+
+const CONFIG_TEMPLATE: &str = "\
+# flight-tracker-tui preferences. Every key is optional; uncomment to change.
+
+# Never refresh a flight more often than this, in seconds
+# min_refresh_secs = 10
+# Re-fetch schedules (delays, estimated times) this often, in minutes
+# schedule_refresh_mins = 30
+# AviationStack requests in your plan per month
+# aviationstack_monthly_limit = 100
+# OpenSky credits to spend per day (default: 400 anonymous, 4000 signed in)
+# opensky_daily_credits = 4000
+# Seconds to wait for each provider before giving up on a request
+# opensky_timeout_secs = 10
+# aviationstack_timeout_secs = 10
+# Clock for schedule and update times: \"24h\" or \"12h\"
+# time_format = \"24h\"
+# Terminal background: \"dark\", \"light\", or \"auto\" to ask the terminal
+# background = \"auto\"
+# \"ticker\" shows one line per flight, for small panes; Ctrl+T switches
+# layout = \"full\"
+# Draw only ASCII, for terminals that show arrows and borders as garbage
+# ascii_only = false
+# Show the followed flight's summary in the terminal's window title
+# terminal_title = false
+# Refresh this many times less often while the terminal is unfocused; 0 pauses
+# background_refresh = 4
+# POST flight status changes as JSON to a Slack, Discord or ntfy webhook
+# webhook_url = \"https://hooks.slack.com/services/...\"
+
+# Your location, for distance and bearing to each aircraft
+# [observer]
+# latitude = 51.47
+# longitude = -0.45
+
+# Disk space for recorded tracks, in MiB: each flight's file is rotated at
+# max_file_mb, and the oldest files go once they total max_total_mb
+# [track_retention]
+# max_file_mb = 10
+# max_total_mb = 200
+
+# Position readings past these bounds are ignored, keeping the last good one
+# [position_limits]
+# max_ground_speed_kts = 1000
+# min_altitude_ft = -1500
+# max_altitude_ft = 60000
+# max_vertical_rate_fpm = 10000
+
+# Columns of the flight list and the `E` report, in order. Also available:
+# airline, squawk, altitude, speed, vertical_rate
+# [table]
+# columns = [\"flight\", \"route\", \"status\", \"departure\", \"arrival\", \"delay\", \"aircraft\"]
+";
+
+const ENV_TEMPLATE: &str = "\
+# API keys for flight-tracker-tui. Uncomment and fill in the ones you have.
+
+# Routes, schedules and delays (free tier at https://aviationstack.com)
+# AVIATIONSTACK_API_KEY=
+
+# Higher OpenSky rate limits (free account at https://opensky-network.org)
+# OPENSKY_USERNAME=
+# OPENSKY_PASSWORD=
+";
+
+/// Whether `dir` looks like a fresh install, given whether any API key is
+/// set.
+pub fn is_first_run(dir: &Path, has_keys: bool) -> bool {
+    !has_keys && !EXISTING_STATE.iter().any(|file| dir.join(file).exists())
+}
+
+/// Create `dir` with a commented `config.toml` and `.env`, leaving any that
+/// already exist alone. Returns the files written. Fails without writing
+/// anything while another instance owns the data directory.
+pub fn write_templates(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    if persist::is_read_only() {
+        return Err(io::Error::other("another instance owns the data directory"));
+    }
+    fs::create_dir_all(dir)?;
+    let mut written = Vec::new();
+    for (name, contents) in [("config.toml", CONFIG_TEMPLATE), (".env", ENV_TEMPLATE)] {
+        let path = dir.join(name);
+        if !path.exists() {
+            fs::write(&path, contents)?;
+            written.push(path);
+        }
+    }
+    Ok(written)
+}
+
+/// Record that the walkthrough is over, in the background like the other
+/// state files.
+pub fn mark_done(dir: &Path) {
+    persist::write(dir.join(MARKER_FILE), String::new());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::paths;
+
+    /// The data directory under a throwaway HOME.
+    fn temp_home(name: &str) -> PathBuf {
+        let home = std::env::temp_dir().join(format!("onboarding-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        let lookup = |key: &str| (key == "HOME").then(|| home.display().to_string());
+        paths::data_dir_with(lookup).unwrap()
+    }
+
+    #[test]
+    fn test_first_run_until_marked() {
+        let dir = temp_home("marker");
+        assert!(is_first_run(&dir, false));
+        assert!(!is_first_run(&dir, true));
+
+        mark_done(&dir);
+        persist::flush();
+        assert!(!is_first_run(&dir, false));
+        let _ = fs::remove_dir_all(dir.parent().unwrap().parent().unwrap());
+    }
+
+    #[test]
+    fn test_existing_state_is_not_a_first_run() {
+        let dir = temp_home("history");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("history.json"), "{}").unwrap();
+        assert!(!is_first_run(&dir, false));
+        let _ = fs::remove_dir_all(dir.parent().unwrap().parent().unwrap());
+    }
+
+    #[test]
+    fn test_templates_keep_existing_files() {
+        let dir = temp_home("templates");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(".env"), "AVIATIONSTACK_API_KEY=abc\n").unwrap();
+
+        let written = write_templates(&dir).unwrap();
+        assert_eq!(written, vec![dir.join("config.toml")]);
+        assert_eq!(
+            fs::read_to_string(dir.join(".env")).unwrap(),
+            "AVIATIONSTACK_API_KEY=abc\n"
+        );
+        // The template is all comments, so it parses to the defaults
+        let config = Config::parse(&fs::read_to_string(dir.join("config.toml")).unwrap()).unwrap();
+        assert_eq!(config.min_refresh_secs, Config::default().min_refresh_secs);
+
+        assert!(write_templates(&dir).unwrap().is_empty());
+        let _ = fs::remove_dir_all(dir.parent().unwrap().parent().unwrap());
+    }
+}
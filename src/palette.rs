@@ -0,0 +1,301 @@
+//! The `:` command palette, for actions too rare to deserve a key.
+//!
+//! [`COMMANDS`] lists what can be typed. [`complete`] ranks the commands
+//! against a partly typed name, and [`resolve`] turns a submitted line into
+//! the [`Action`] it names. A command may take one argument, the rest of the
+//! line after its name.
+
+use crate::action::Action;
+use crate::app::{BulkRemoval, CacheKind};
+use crate::import;
+use crate::report;
+use chrono::Local;
+
+/// A command the palette offers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Command {
+    pub name: &'static str,
+    /// Placeholder for the argument, for commands that take one
+    pub arg: Option<&'static str>,
+    pub help: &'static str,
+}
+
+const fn command(name: &'static str, arg: Option<&'static str>, help: &'static str) -> Command {
+    Command { name, arg, help }
+}
+
+pub const COMMANDS: &[Command] = &[
+    command(
+        "export report",
+        None,
+        "Write the Markdown report and copy it",
+    ),
+    command("diagnostics", None, "Open or close the diagnostics overlay"),
+    command(
+        "clear cache",
+        Some("position|schedule"),
+        "Empty a provider's cache",
+    ),
+    command(
+        "clear finished",
+        None,
+        "Remove landed, cancelled and unfound flights",
+    ),
+    command("clear all", None, "Remove every tracked flight"),
+    command("undo", None, "Put back the flights removed last"),
+    command(
+        "set interval",
+        Some("seconds"),
+        "Refresh no flight more often than this",
+    ),
+    command(
+        "set columns",
+        Some("id,id,..."),
+        "Choose the list and report columns",
+    ),
+    command(
+        "trip add",
+        Some("name flight..."),
+        "Group flights as one trip, tracking them",
+    ),
+    command(
+        "trip remove",
+        Some("name"),
+        "Ungroup a trip, keeping its flights",
+    ),
+    command(
+        "settings",
+        None,
+        "Change and save settings without editing config.toml",
+    ),
+    command("map", None, "Toggle the map overview"),
+    command("profile", None, "Toggle the altitude chart"),
+    command("follow", None, "Lock the view onto the selected flight"),
+    command("ticker", None, "Switch to one line per flight, or back"),
+    command("time format", None, "Switch between 12- and 24-hour times"),
+    command("recurring", None, "Roll the selected flight over daily"),
+    command("alert", None, "Set an alert on the selected flight"),
+    command("import", None, "Import flights from a file"),
+    command("refresh", None, "Refresh every flight now"),
+    command("quit", None, "Quit"),
+];
+
+/// How well `query` fits `name`, lower is better: a prefix, then the start
+/// of a later word, then anywhere, then the letters in order.
+fn rank(name: &str, query: &str) -> Option<u8> {
+    if name.starts_with(query) {
+        Some(0)
+    } else if name.split(' ').any(|word| word.starts_with(query)) {
+        Some(1)
+    } else if name.contains(query) {
+        Some(2)
+    } else {
+        let mut letters = name.chars();
+        query.chars().all(|q| letters.any(|c| c == q)).then_some(3)
+    }
+}
+
+/// Commands matching `query`, best first. Ties keep the table order.
+pub fn complete(query: &str) -> Vec<&'static Command> {
+    let query = query.trim().to_lowercase();
+    let mut matches: Vec<_> = COMMANDS
+        .iter()
+        .filter_map(|command| Some((rank(command.name, &query)?, command)))
+        .collect();
+    matches.sort_by_key(|(rank, _)| *rank);
+    matches.into_iter().map(|(_, command)| command).collect()
+}
+
+/// The action a submitted line asks for, or why it doesn't name one.
+///
+/// The whole line is matched first. Failing that, the line is split at its
+/// last space that leaves a command taking an argument before it, and the
+/// rest is that argument.
+pub fn resolve(line: &str) -> Result<Action, String> {
+    let line = line.trim().to_lowercase();
+    if line.is_empty() {
+        return Err("Type a command, Tab completes".to_string());
+    }
+    if let Some(command) = complete(&line).first() {
+        return match command.arg {
+            Some(arg) => Err(format!("{} needs {}", command.name, arg)),
+            None => build(command.name, ""),
+        };
+    }
+    let with_arg = line.match_indices(' ').rev().find_map(|(at, _)| {
+        let command = complete(&line[..at])
+            .into_iter()
+            .find(|c| c.arg.is_some())?;
+        Some((command, line[at..].trim()))
+    });
+    match with_arg {
+        Some((command, arg)) => build(command.name, arg),
+        None => Err(format!("Unknown command: {}", line)),
+    }
+}
+
+fn build(name: &str, arg: &str) -> Result<Action, String> {
+    let action = match name {
+        "export report" => Action::ExportReport,
+        "diagnostics" => Action::ToggleDiagnostics,
+        "clear cache" => match arg {
+            "position" => Action::ClearCache(CacheKind::Position),
+            "schedule" => Action::ClearCache(CacheKind::Schedule),
+            _ => return Err(format!("No {} cache, try position or schedule", arg)),
+        },
+        "clear finished" => Action::RequestRemoval(BulkRemoval::Finished),
+        "clear all" => Action::RequestRemoval(BulkRemoval::All),
+        "undo" => Action::Undo,
+        "set interval" => match arg.parse() {
+            Ok(secs) if secs > 0 => Action::SetMinRefresh(secs),
+            _ => return Err(format!("Not a number of seconds: {}", arg)),
+        },
+        "set columns" => Action::SetColumns(report::parse_columns(arg)?),
+        "trip add" => {
+            let (name, flights) = arg.split_once(' ').unwrap_or((arg, ""));
+            let name = name.to_uppercase();
+            let (legs, invalid) = import::parse_queries(flights, Local::now().date_naive());
+            if let Some(token) = invalid.first() {
+                return Err(format!("Not a valid flight number: {}", token));
+            }
+            if legs.is_empty() {
+                return Err(format!("Trip {} needs at least one flight", name));
+            }
+            Action::AddTrip { name, legs }
+        }
+        "trip remove" => Action::RemoveTrip(arg.to_uppercase()),
+        "settings" => Action::OpenSettings,
+        "map" => Action::ToggleMap,
+        "profile" => Action::ToggleProfile,
+        "follow" => Action::ToggleFollow,
+        "ticker" => Action::ToggleLayout,
+        "time format" => Action::ToggleTimeFormat,
+        "recurring" => Action::ToggleRecurring,
+        "alert" => Action::BeginAlertInput,
+        "import" => Action::BeginImportInput,
+        "refresh" => Action::Refresh,
+        "quit" => Action::Quit,
+        _ => unreachable!("command {name} has no action"),
+    };
+    Ok(action)
+}
+
+/// The input line after Tab: the best match's name, with a trailing space
+/// when it takes an argument. `None` if nothing matches.
+pub fn tab_complete(line: &str) -> Option<String> {
+    let command = complete(line).into_iter().next()?;
+    Some(match command.arg {
+        Some(_) => format!("{} ", command.name),
+        None => command.name.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(query: &str) -> Vec<&'static str> {
+        complete(query).iter().map(|c| c.name).collect()
+    }
+
+    #[test]
+    fn test_ranking() {
+        // Everything, in table order, before anything is typed
+        assert_eq!(names("").len(), COMMANDS.len());
+        assert_eq!(names("")[0], "export report");
+
+        // A prefix beats a later word, which beats the middle of a word
+        assert_eq!(names("re")[..3], ["recurring", "refresh", "export report"]);
+        assert_eq!(names("port"), ["export report", "import"]);
+        assert_eq!(names("cache"), ["clear cache"]);
+        // Letters in order, for abbreviations
+        assert_eq!(names("cc"), ["clear cache"]);
+        assert_eq!(names("tf"), ["time format"]);
+        assert_eq!(names("  MAP "), ["map"]);
+        assert!(names("zz").is_empty());
+    }
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(resolve("export report"), Ok(Action::ExportReport));
+        assert_eq!(resolve("exp"), Ok(Action::ExportReport));
+        assert_eq!(resolve("diag"), Ok(Action::ToggleDiagnostics));
+        assert_eq!(resolve("settings"), Ok(Action::OpenSettings));
+        assert_eq!(resolve("set interval 60"), Ok(Action::SetMinRefresh(60)));
+        assert_eq!(resolve("set int 90"), Ok(Action::SetMinRefresh(90)));
+        assert_eq!(
+            resolve("set columns flight,Squawk"),
+            Ok(Action::SetColumns(vec![
+                "flight".to_string(),
+                "squawk".to_string()
+            ]))
+        );
+        assert_eq!(
+            resolve("cc Schedule"),
+            Ok(Action::ClearCache(CacheKind::Schedule))
+        );
+        assert_eq!(
+            resolve("clear cache position"),
+            Ok(Action::ClearCache(CacheKind::Position))
+        );
+        // The argument runs to the end of the line
+        let Ok(Action::AddTrip { name, legs }) = resolve("trip add NYC-TYO UA79 nh006") else {
+            panic!("trip add didn't resolve");
+        };
+        assert_eq!(name, "NYC-TYO");
+        let legs: Vec<&str> = legs.iter().map(|leg| leg.flight_number.as_str()).collect();
+        assert_eq!(legs, ["UA79", "NH006"]);
+        assert_eq!(
+            resolve("trip remove nyc-tyo"),
+            Ok(Action::RemoveTrip("NYC-TYO".into()))
+        );
+    }
+
+    #[test]
+    fn test_resolve_errors() {
+        assert!(resolve("  ").is_err());
+        assert_eq!(
+            resolve("clear cache"),
+            Err("clear cache needs position|schedule".to_string())
+        );
+        assert_eq!(
+            resolve("set interval soon"),
+            Err("Not a number of seconds: soon".to_string())
+        );
+        assert!(resolve("set interval 0").is_err());
+        assert_eq!(
+            resolve("cc weather"),
+            Err("No weather cache, try position or schedule".into())
+        );
+        assert_eq!(resolve("map 5"), Err("Unknown command: map 5".to_string()));
+        assert_eq!(
+            resolve("trip add NYC"),
+            Err("Trip NYC needs at least one flight".into())
+        );
+        assert_eq!(
+            resolve("trip add NYC UA79 ??"),
+            Err("Not a valid flight number: ??".into())
+        );
+    }
+
+    #[test]
+    fn test_tab_complete() {
+        assert_eq!(tab_complete("exp").as_deref(), Some("export report"));
+        assert_eq!(tab_complete("set").as_deref(), Some("set interval "));
+        assert_eq!(tab_complete("zz"), None);
+    }
+
+    #[test]
+    fn test_every_command_builds() {
+        for command in COMMANDS {
+            let arg = match command.arg {
+                Some("seconds") => "30",
+                Some("id,id,...") => "flight,squawk",
+                Some("name flight...") => "nyc-tyo ua79 nh6",
+                Some(_) => "position",
+                None => "",
+            };
+            assert!(build(command.name, arg).is_ok(), "{}", command.name);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! Where the app keeps its files.
+//!
+//! Everything persisted (config, session, history, caches, the log) lives in
+//! one directory under the user config dir: `$XDG_CONFIG_HOME` if set,
+//! otherwise `~/.config`.
+
+use std::path::PathBuf;
+
+/// Directory under the user config dir holding all persisted state.
+pub const APP_DIR: &str = "flight-tracker-tui";
+
+/// The user config directory (`$XDG_CONFIG_HOME` or `~/.config`).
+pub fn config_dir() -> Option<PathBuf> {
+    config_dir_with(|key| std::env::var(key).ok())
+}
+
+/// Directory holding the app's persisted state and log file.
+pub fn data_dir() -> Option<PathBuf> {
+    data_dir_with(|key| std::env::var(key).ok())
+}
+
+/// [`config_dir`] with environment variables read through `lookup`.
+pub fn config_dir_with(lookup: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    if let Some(xdg) = lookup("XDG_CONFIG_HOME").filter(|v| !v.is_empty()) {
+        return Some(PathBuf::from(xdg));
+    }
+    lookup("HOME").map(|home| PathBuf::from(home).join(".config"))
+}
+
+/// [`data_dir`] with environment variables read through `lookup`.
+///
+/// ```
+/// use std::path::PathBuf;
+/// use flight_tracker_tui::paths;
+///
+/// let dir = paths::data_dir_with(|key| (key == "HOME").then(|| "/home/ada".to_string()));
+/// assert_eq!(dir, Some(PathBuf::from("/home/ada/.config/flight-tracker-tui")));
+/// ```
+pub fn data_dir_with(lookup: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    config_dir_with(lookup).map(|p| p.join(APP_DIR))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xdg_config_home_wins() {
+        let env = |key: &str| match key {
+            "XDG_CONFIG_HOME" => Some("/tmp/xdg".to_string()),
+            "HOME" => Some("/home/ada".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            data_dir_with(env),
+            Some(PathBuf::from("/tmp/xdg/flight-tracker-tui"))
+        );
+
+        // An empty value counts as unset
+        let env = |key: &str| match key {
+            "XDG_CONFIG_HOME" => Some(String::new()),
+            "HOME" => Some("/home/ada".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            config_dir_with(env),
+            Some(PathBuf::from("/home/ada/.config"))
+        );
+        assert_eq!(data_dir_with(|_| None), None);
+    }
+}
@@ -0,0 +1,140 @@
+//! Writing state files off the caller's thread.
+//!
+//! [`write`] hands a file's new contents to a single writer thread and
+//! returns at once, so a slow disk (an NFS home directory, say) doesn't
+//! stall key handling. Files are written in the order they were queued, and
+//! when several writes to one file are waiting only the latest is made.
+//! [`flush`] blocks until everything queued so far is on disk, for quitting.
+//! After [`set_read_only`], writes are dropped instead, for an instance
+//! running alongside another that owns the files.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::OnceLock;
+use std::thread;
+
+use tracing::warn;
+
+enum Job {
+    Write(PathBuf, String),
+    /// Answered once every earlier write is done.
+    Flush(Sender<()>),
+}
+
+/// The writer thread's queue, or `None` if the thread couldn't be started
+/// and writes happen on the caller's thread.
+static QUEUE: OnceLock<Option<Sender<Job>>> = OnceLock::new();
+
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+fn queue() -> Option<&'static Sender<Job>> {
+    QUEUE
+        .get_or_init(|| {
+            let (tx, rx) = mpsc::channel();
+            let spawned = thread::Builder::new()
+                .name("persist".to_string())
+                .spawn(move || run(rx));
+            match spawned {
+                Ok(_) => Some(tx),
+                Err(e) => {
+                    warn!(error = %e, "Could not start the file writer, saving inline");
+                    None
+                }
+            }
+        })
+        .as_ref()
+}
+
+/// Replace the file at `path` with `contents` in the background, creating
+/// its directory if needed.
+pub fn write(path: PathBuf, contents: String) {
+    if is_read_only() {
+        return;
+    }
+    let Some(tx) = queue() else {
+        return write_now(&path, &contents);
+    };
+    // The writer thread only goes away if it panicked; save inline then
+    let sent = tx.send(Job::Write(path, contents));
+    if let Err(mpsc::SendError(Job::Write(path, contents))) = sent {
+        write_now(&path, &contents);
+    }
+}
+
+/// Wait until every write queued before this call has been made.
+pub fn flush() {
+    let Some(tx) = queue() else {
+        return;
+    };
+    let (done_tx, done_rx) = mpsc::channel();
+    if tx.send(Job::Flush(done_tx)).is_ok() {
+        let _ = done_rx.recv();
+    }
+}
+
+/// Drop every later write, leaving the files to another instance.
+pub fn set_read_only() {
+    READ_ONLY.store(true, Ordering::Relaxed);
+}
+
+/// Whether [`set_read_only`] has been called.
+pub fn is_read_only() -> bool {
+    READ_ONLY.load(Ordering::Relaxed)
+}
+
+fn run(jobs: Receiver<Job>) {
+    while let Ok(job) = jobs.recv() {
+        // Take whatever else is queued too, keeping the latest per file
+        let mut pending: Vec<(PathBuf, String)> = Vec::new();
+        let mut flushes = Vec::new();
+        for job in std::iter::once(job).chain(jobs.try_iter()) {
+            match job {
+                Job::Write(path, contents) => {
+                    match pending.iter_mut().find(|(queued, _)| *queued == path) {
+                        Some(entry) => entry.1 = contents,
+                        None => pending.push((path, contents)),
+                    }
+                }
+                Job::Flush(done) => flushes.push(done),
+            }
+        }
+        for (path, contents) in pending {
+            write_now(&path, &contents);
+        }
+        for done in flushes {
+            let _ = done.send(());
+        }
+    }
+}
+
+fn write_now(path: &Path, contents: &str) {
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Err(e) = fs::write(path, contents) {
+        warn!(path = %path.display(), error = %e, "Could not save state file");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_write_wins() {
+        let dir = std::env::temp_dir().join(format!("persist-test-{}", std::process::id()));
+        let path = dir.join("nested").join("state.json");
+
+        for i in 0..200 {
+            write(path.clone(), i.to_string());
+        }
+        flush();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "199");
+
+        // Flushing with nothing queued returns straight away
+        flush();
+        let _ = fs::remove_dir_all(dir);
+    }
+}
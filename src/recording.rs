@@ -0,0 +1,287 @@
+//! Session recordings for bug reports: every event the app handled, one JSON
+//! object per line, and reading them back to replay.
+//!
+//! Each line is an [`Entry`]: the milliseconds since recording started and
+//! the event itself, which is whatever the caller records. Credentials are
+//! blanked out before anything reaches the file, so a recording can be
+//! attached to an issue as it is.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::api::{FlightData, FlightTrack, StateSnapshot, StationWeather};
+use crate::error::AppError;
+use crate::import::FlightQuery;
+
+/// What stands in for a credential in a recording.
+pub const REDACTED: &str = "[redacted]";
+
+/// Environment variables holding credentials the providers send.
+const SECRET_VARS: [&str; 3] = [
+    "AVIATIONSTACK_API_KEY",
+    "OPENSKY_USERNAME",
+    "OPENSKY_PASSWORD",
+];
+
+/// Query parameters whose values are credentials, wherever a URL turns up.
+const SECRET_PARAMS: [&str; 2] = ["access_key=", "token="];
+
+/// Shortest secret blanked wherever it turns up. A short username would
+/// match inside flight numbers, airports and times; it only ever goes out
+/// in the OpenSky login, which isn't recorded.
+pub const MIN_SECRET_CHARS: usize = 8;
+
+/// One recorded event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry<T> {
+    /// Milliseconds since the recording started
+    pub at_ms: u64,
+    pub event: T,
+}
+
+/// Appends events to a recording as they happen.
+pub struct Recorder {
+    file: File,
+    started: Instant,
+    secrets: Vec<String>,
+}
+
+impl Recorder {
+    /// Start recording to `path`, appending if it exists, with `secrets`
+    /// blanked out of everything written. Secrets shorter than
+    /// [`MIN_SECRET_CHARS`] are left in place.
+    pub fn create(path: &Path, secrets: Vec<String>) -> Result<Self, AppError> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file,
+            started: Instant::now(),
+            secrets: secrets.into_iter().filter(|s| is_redactable(s)).collect(),
+        })
+    }
+
+    /// Write `event` as the next line, stamped with the time since
+    /// recording started.
+    pub fn record<T: Serialize>(&mut self, event: &T) -> Result<(), AppError> {
+        let mut event = serde_json::to_value(event).map_err(|e| AppError::Parse(e.to_string()))?;
+        redact(&mut event, &self.secrets);
+        let entry = Entry {
+            at_ms: self.started.elapsed().as_millis() as u64,
+            event,
+        };
+        let mut line = serde_json::to_string(&entry).map_err(|e| AppError::Parse(e.to_string()))?;
+        line.push('\n');
+        // One write per line, so a crash leaves at most the last one torn
+        self.file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// The credentials configured in the environment, to pass to
+/// [`Recorder::create`].
+pub fn secrets_from_env() -> Vec<String> {
+    SECRET_VARS
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .collect()
+}
+
+/// Whether `secret` is long enough to blank wherever it turns up.
+fn is_redactable(secret: &str) -> bool {
+    secret.chars().count() >= MIN_SECRET_CHARS
+}
+
+/// Blank out every occurrence of `secrets` at least [`MIN_SECRET_CHARS`]
+/// long, and the values of credential query parameters, in the strings of
+/// `value`. Keys are left alone, so the result still reads back as the same
+/// type.
+///
+/// ```
+/// use flight_tracker_tui::recording::redact;
+///
+/// let mut value = serde_json::json!({
+///     "error": "GET http://api.aviationstack.com/v1/flights?access_key=abc123&flight_iata=BA2",
+///     "user": "alice.pilot",
+/// });
+/// redact(&mut value, &["alice.pilot".to_string()]);
+/// assert_eq!(
+///     value["error"],
+///     "GET http://api.aviationstack.com/v1/flights?access_key=[redacted]&flight_iata=BA2"
+/// );
+/// assert_eq!(value["user"], "[redacted]");
+/// ```
+pub fn redact(value: &mut Value, secrets: &[String]) {
+    match value {
+        Value::String(text) => {
+            for secret in secrets.iter().filter(|s| is_redactable(s)) {
+                if text.contains(secret.as_str()) {
+                    *text = text.replace(secret.as_str(), REDACTED);
+                }
+            }
+            for param in SECRET_PARAMS {
+                *text = redact_param(text, param);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| redact(item, secrets)),
+        Value::Object(fields) => fields.values_mut().for_each(|field| redact(field, secrets)),
+        _ => {}
+    }
+}
+
+/// `text` with the value after each `param` blanked, up to the next `&`,
+/// quote or whitespace.
+fn redact_param(text: &str, param: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(param) {
+        let (head, tail) = rest.split_at(start + param.len());
+        out.push_str(head);
+        let end = tail
+            .find(|c: char| c == '&' || c == '"' || c == '\'' || c.is_whitespace())
+            .unwrap_or(tail.len());
+        if end > 0 {
+            out.push_str(REDACTED);
+        }
+        rest = &tail[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Every entry of the recording at `path`, in order. Blank lines are
+/// skipped; anything else that doesn't read as an entry is an error naming
+/// its line.
+pub fn read<T: DeserializeOwned>(path: &Path) -> Result<Vec<Entry<T>>, AppError> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut entries = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line)
+            .map_err(|e| AppError::parse(format!("line {}: {}", number + 1, e)))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// An API response as recorded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Response {
+    FlightSearch {
+        query: FlightQuery,
+        position: Result<Option<StateSnapshot>, RecordedError>,
+        schedule: Option<Box<FlightData>>,
+    },
+    FlightUpdate(String, Result<StateSnapshot, RecordedError>),
+    ScheduleUpdate(String, Result<Option<Box<FlightData>>, RecordedError>),
+    Track(String, Result<Option<FlightTrack>, RecordedError>),
+    Rematch(String, Result<StateSnapshot, RecordedError>),
+    Webhook(Result<(), RecordedError>),
+    Weather(String, Result<StationWeather, RecordedError>),
+}
+
+/// An [`AppError`] as it is recorded: the variant and its message, which is
+/// all the app shows of one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "detail", rename_all = "snake_case")]
+pub enum RecordedError {
+    Parse(String),
+    RateLimited,
+    Unauthorized,
+    HttpsRestricted,
+    Api(String),
+    ProviderDegraded(String),
+    Network(String),
+    Io(String),
+}
+
+impl From<&AppError> for RecordedError {
+    fn from(error: &AppError) -> Self {
+        match error {
+            AppError::Parse(detail) => Self::Parse(detail.clone()),
+            AppError::RateLimited => Self::RateLimited,
+            AppError::Unauthorized => Self::Unauthorized,
+            AppError::HttpsRestricted => Self::HttpsRestricted,
+            AppError::Api(message) => Self::Api(message.clone()),
+            AppError::ProviderDegraded(provider) => Self::ProviderDegraded(provider.to_string()),
+            AppError::Network(e) => Self::Network(e.to_string()),
+            AppError::RecordedNetwork(message) => Self::Network(message.clone()),
+            AppError::Io(e) => Self::Io(e.to_string()),
+        }
+    }
+}
+
+impl From<RecordedError> for AppError {
+    fn from(error: RecordedError) -> Self {
+        match error {
+            RecordedError::Parse(detail) => Self::Parse(detail),
+            RecordedError::RateLimited => Self::RateLimited,
+            RecordedError::Unauthorized => Self::Unauthorized,
+            RecordedError::HttpsRestricted => Self::HttpsRestricted,
+            RecordedError::Api(message) => Self::Api(message),
+            RecordedError::ProviderDegraded(provider) => {
+                Self::ProviderDegraded(match provider.as_str() {
+                    "OpenSky" => "OpenSky",
+                    "AviationStack" => "AviationStack",
+                    _ => "A provider",
+                })
+            }
+            RecordedError::Network(message) => Self::RecordedNetwork(message),
+            RecordedError::Io(message) => Self::Io(std::io::Error::other(message)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_params() {
+        assert_eq!(
+            redact_param("a?access_key=k1&b=2 then access_key=k2", "access_key="),
+            "a?access_key=[redacted]&b=2 then access_key=[redacted]"
+        );
+        assert_eq!(redact_param("access_key=", "access_key="), "access_key=");
+        assert_eq!(redact_param("nothing here", "token="), "nothing here");
+    }
+
+    #[test]
+    fn test_short_secrets_leave_other_strings_alone() {
+        let mut value = serde_json::json!({
+            "flight": "UA123",
+            "route": "SFO-JFK",
+            "error": "login for ua rejected by 9f2c4e7a1b",
+        });
+        redact(&mut value, &["ua".to_string(), "9f2c4e7a1b".to_string()]);
+        assert_eq!(value["flight"], "UA123");
+        assert_eq!(value["route"], "SFO-JFK");
+        assert_eq!(value["error"], "login for ua rejected by [redacted]");
+    }
+
+    #[test]
+    fn test_errors_keep_what_the_app_shows() {
+        let errors = [
+            AppError::RateLimited,
+            AppError::Api("Usage limit reached".to_string()),
+            AppError::ProviderDegraded("OpenSky"),
+            AppError::RecordedNetwork("connection refused".to_string()),
+            AppError::Io(std::io::Error::other("disk full")),
+        ];
+        for error in errors {
+            let json = serde_json::to_string(&RecordedError::from(&error)).unwrap();
+            let back: RecordedError = serde_json::from_str(&json).unwrap();
+            let back = AppError::from(back);
+            assert_eq!(back.to_string(), error.to_string());
+            assert_eq!(back.user_message(), error.user_message());
+        }
+    }
+}
@@ -0,0 +1,380 @@
+//! Fresher airline and airport tables than the ones built in.
+//!
+//! `--update-data` calls [`update`], which downloads the airports CSV from
+//! OurAirports and the airline list from OpenFlights, checks that each has
+//! the columns it needs and enough usable rows, and only then swaps both
+//! into the data directory. At startup [`load`] reads them back and hands
+//! them to [`airports`](crate::airports) and the callsign helpers in
+//! [`api`](crate::api), which search them before their built-in tables. A
+//! download or file that doesn't pass leaves the previous copies in place.
+
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use tracing::{info, warn};
+
+use crate::airports::{self, AirportLocation};
+use crate::api::{self, AirlineCode};
+use crate::error::AppError;
+
+pub const AIRPORTS_URL: &str = "https://davidmegginson.github.io/ourairports-data/airports.csv";
+pub const AIRLINES_URL: &str =
+    "https://raw.githubusercontent.com/jpatokal/openflights/master/data/airlines.dat";
+
+/// Where the downloaded copies live in the data directory.
+pub const AIRPORTS_FILE: &str = "airports.csv";
+pub const AIRLINES_FILE: &str = "airlines.dat";
+
+/// Fewest usable rows a table may have; the real ones have several times
+/// more, so fewer means a truncated or wrong file.
+pub const MIN_AIRPORTS: usize = 1000;
+pub const MIN_AIRLINES: usize = 300;
+
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// OurAirports columns every row needs.
+const AIRPORT_COLUMNS: [&str; 5] = [
+    "ident",
+    "name",
+    "latitude_deg",
+    "longitude_deg",
+    "iata_code",
+];
+
+/// Columns of an OpenFlights `airlines.dat` row, which has no header.
+const AIRLINE_FIELDS: usize = 8;
+
+/// How many rows of each table an update or load put in use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub airports: usize,
+    pub airlines: usize,
+}
+
+/// Download both tables and, if both pass, save them to `dir`.
+pub async fn update(dir: &Path) -> Result<Counts, AppError> {
+    let client = reqwest::Client::builder()
+        .timeout(DOWNLOAD_TIMEOUT)
+        .build()?;
+    let airports_csv = download(&client, AIRPORTS_URL).await?;
+    let airlines_dat = download(&client, AIRLINES_URL).await?;
+    install(dir, &airports_csv, &airlines_dat)
+}
+
+async fn download(client: &reqwest::Client, url: &str) -> Result<String, AppError> {
+    info!(%url, "Downloading reference data");
+    let response = client.get(url).send().await?;
+    if !response.status().is_success() {
+        return Err(AppError::Api(format!(
+            "{} answered {}",
+            url,
+            response.status()
+        )));
+    }
+    Ok(response.text().await?)
+}
+
+/// Check both tables, then move them into `dir` together. Nothing is
+/// written unless both pass.
+pub fn install(dir: &Path, airports_csv: &str, airlines_dat: &str) -> Result<Counts, AppError> {
+    let counts = Counts {
+        airports: parse_airports(airports_csv, MIN_AIRPORTS)?.len(),
+        airlines: parse_airlines(airlines_dat, MIN_AIRLINES)?.len(),
+    };
+    fs::create_dir_all(dir)?;
+    let files = [(AIRPORTS_FILE, airports_csv), (AIRLINES_FILE, airlines_dat)];
+    // Both are written out in full before either replaces its old copy
+    for (name, contents) in files {
+        fs::write(dir.join(name).with_extension("tmp"), contents)?;
+    }
+    for (name, _) in files {
+        fs::rename(dir.join(name).with_extension("tmp"), dir.join(name))?;
+    }
+    Ok(counts)
+}
+
+/// Put the tables saved in `dir` in use. A table that is missing or no
+/// longer passes is skipped, leaving the built-in one.
+pub fn load(dir: &Path) -> Counts {
+    let mut counts = Counts::default();
+    if let Some(table) = read(dir, AIRPORTS_FILE, |text| {
+        parse_airports(text, MIN_AIRPORTS)
+    }) {
+        counts.airports = table.len();
+        airports::use_downloaded(table);
+    }
+    if let Some(table) = read(dir, AIRLINES_FILE, |text| {
+        parse_airlines(text, MIN_AIRLINES)
+    }) {
+        counts.airlines = table.len();
+        api::use_downloaded_airlines(table);
+    }
+    counts
+}
+
+fn read<T>(
+    dir: &Path,
+    name: &str,
+    parse: impl FnOnce(&str) -> Result<Vec<T>, AppError>,
+) -> Option<Vec<T>> {
+    let path = dir.join(name);
+    let text = fs::read_to_string(&path).ok()?;
+    parse(&text)
+        .inspect_err(|e| warn!(path = %path.display(), error = %e, "Ignoring downloaded table"))
+        .ok()
+}
+
+/// Airports with an IATA code from an OurAirports `airports.csv`. Rows
+/// that don't parse are skipped; a missing column, or fewer than
+/// `min_rows` airports, fails.
+///
+/// The strings are leaked: the table is read once and kept for good.
+pub fn parse_airports(csv: &str, min_rows: usize) -> Result<Vec<AirportLocation>, AppError> {
+    let mut lines = csv.strip_prefix('\u{feff}').unwrap_or(csv).lines();
+    let header = split_csv_line(lines.next().unwrap_or_default())
+        .ok_or_else(|| AppError::parse("airports.csv: unreadable header"))?;
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let mut index = [0; AIRPORT_COLUMNS.len()];
+    for (slot, name) in index.iter_mut().zip(AIRPORT_COLUMNS) {
+        *slot = column(name)
+            .ok_or_else(|| AppError::parse(format!("airports.csv: no {} column", name)))?;
+    }
+    let [ident, name, latitude, longitude, iata] = index;
+    // Newer files have an ICAO column; older ones only the GPS code
+    let icao = column("icao_code");
+    let gps = column("gps_code");
+
+    let mut table = Vec::new();
+    for line in lines {
+        let Some(row) = split_csv_line(line).filter(|row| row.len() == header.len()) else {
+            continue;
+        };
+        let code = row[iata].as_str();
+        if code.len() != 3 || !code.chars().all(|c| c.is_ascii_uppercase()) {
+            continue;
+        }
+        let (Ok(lat), Ok(lon)) = (row[latitude].parse::<f64>(), row[longitude].parse::<f64>())
+        else {
+            continue;
+        };
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+            continue;
+        }
+        let icao_code = [icao, gps]
+            .into_iter()
+            .flatten()
+            .map(|i| row[i].as_str())
+            .find(|c| !c.is_empty())
+            .unwrap_or(row[ident].as_str());
+        table.push(AirportLocation {
+            iata: leak(code),
+            icao: leak(icao_code),
+            name: leak(&row[name]),
+            latitude: lat,
+            longitude: lon,
+        });
+    }
+    enough("airports.csv", table, min_rows)
+}
+
+/// Active airlines with both an IATA and an ICAO designator from an
+/// OpenFlights `airlines.dat`. Rows that don't parse are skipped; fewer
+/// than `min_rows` airlines fails.
+///
+/// The strings are leaked: the table is read once and kept for good.
+pub fn parse_airlines(dat: &str, min_rows: usize) -> Result<Vec<AirlineCode>, AppError> {
+    let mut table = Vec::new();
+    for line in dat.lines() {
+        let Some(row) = split_csv_line(line).filter(|row| row.len() == AIRLINE_FIELDS) else {
+            continue;
+        };
+        let (name, iata, icao, active) = (&row[1], &row[3], &row[4], &row[7]);
+        let iata_ok = iata.len() == 2
+            && iata
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+            && iata.chars().any(|c| c.is_ascii_uppercase());
+        let icao_ok = icao.len() == 3 && icao.chars().all(|c| c.is_ascii_uppercase());
+        if active == "Y" && iata_ok && icao_ok && !name.is_empty() {
+            table.push((leak(iata), leak(icao), leak(name)));
+        }
+    }
+    enough("airlines.dat", table, min_rows)
+}
+
+fn enough<T>(file: &str, table: Vec<T>, min_rows: usize) -> Result<Vec<T>, AppError> {
+    if table.len() < min_rows {
+        return Err(AppError::parse(format!(
+            "{}: {} usable rows, expected at least {}",
+            file,
+            table.len(),
+            min_rows
+        )));
+    }
+    Ok(table)
+}
+
+fn leak(text: &str) -> &'static str {
+    Box::leak(text.trim().to_string().into_boxed_str())
+}
+
+/// The fields of one CSV line, with quotes removed and `""` unescaped.
+/// `None` if a quoted field isn't closed.
+fn split_csv_line(line: &str) -> Option<Vec<String>> {
+    let line = line.trim_end_matches('\r');
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return None;
+    }
+    fields.push(field);
+    // OpenFlights writes a missing value as \N
+    for field in &mut fields {
+        if field == "\\N" {
+            field.clear();
+        }
+    }
+    Some(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AIRPORTS_HEADER: &str = "\"id\",\"ident\",\"type\",\"name\",\"latitude_deg\",\
+        \"longitude_deg\",\"iso_country\",\"gps_code\",\"iata_code\"";
+
+    fn airports_csv(rows: &[&str]) -> String {
+        std::iter::once(AIRPORTS_HEADER)
+            .chain(rows.iter().copied())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    const AIRLINES_DAT: &str = "\
+324,\"All Nippon Airways\",\"ANA All Nippon Airways\",\"NH\",\"ANA\",\"ALL NIPPON\",\"Japan\",\"Y\"
+1355,\"British Airways\",\\N,\"BA\",\"BAW\",\"SPEEDBIRD\",\"United Kingdom\",\"Y\"
+2,\"135 Airways\",\\N,\"\",\"GNL\",\"GENERAL\",\"United States\",\"N\"
+99,\"Defunct Air\",\\N,\"DF\",\"DFN\",\\N,\"Nowhere\",\"N\"
+5,\"Short row\",\"BA\"
+";
+
+    #[test]
+    fn test_split_csv_line() {
+        let row = split_csv_line("1,\"Gander, NL\",\"say \"\"hi\"\"\",\\N,").unwrap();
+        assert_eq!(row, ["1", "Gander, NL", "say \"hi\"", "", ""]);
+        assert_eq!(split_csv_line("a\r").unwrap(), ["a"]);
+        assert_eq!(split_csv_line("1,\"unterminated"), None);
+    }
+
+    #[test]
+    fn test_parse_airports() {
+        let csv = airports_csv(&[
+            "1,\"CYQX\",\"large_airport\",\"Gander International Airport\",48.94,-54.57,\"CA\",\
+             \"CYQX\",\"YQX\"",
+            // No IATA code, a bad latitude, too few fields, an unclosed quote
+            "2,\"00AA\",\"small_airport\",\"Aero B Ranch\",38.7,-101.4,\"US\",\"00AA\",\"\"",
+            "3,\"XXXX\",\"small_airport\",\"Nowhere\",north,-1,\"US\",\"\",\"XXX\"",
+            "4,\"YYYY\",\"small_airport\",\"Short\",1,2",
+            "5,\"ZZZZ\",\"small_airport\",\"Broken,1,2,\"US\",\"\",\"ZZZ\"",
+            // Without a GPS code the ident stands in
+            "6,\"EGLL\",\"large_airport\",\"London Heathrow\",51.47,-0.46,\"GB\",\"\",\"LHR\"",
+        ]);
+        let table = parse_airports(&csv, 2).unwrap();
+        assert_eq!(table.len(), 2);
+        assert_eq!(table[0].iata, "YQX");
+        assert_eq!(table[0].icao, "CYQX");
+        assert_eq!(table[0].name, "Gander International Airport");
+        assert_eq!((table[0].latitude, table[0].longitude), (48.94, -54.57));
+        assert_eq!(table[1].icao, "EGLL");
+    }
+
+    #[test]
+    fn test_malformed_airports_fail() {
+        // Too few usable rows
+        let csv = airports_csv(
+            &["1,\"CYQX\",\"large_airport\",\"Gander\",48.9,-54.5,\"CA\",\
+             \"CYQX\",\"YQX\""],
+        );
+        let err = parse_airports(&csv, 2).unwrap_err().to_string();
+        assert!(err.contains("1 usable rows, expected at least 2"), "{err}");
+
+        // A required column missing, an empty file, HTML instead of CSV
+        let csv = "id,ident,name,latitude_deg,longitude_deg\n1,CYQX,Gander,48.9,-54.5";
+        let err = parse_airports(csv, 1).unwrap_err().to_string();
+        assert!(err.contains("no iata_code column"), "{err}");
+        assert!(parse_airports("", 0).is_err());
+        assert!(parse_airports("<html><body>404</body></html>", 0).is_err());
+    }
+
+    #[test]
+    fn test_parse_airlines() {
+        let table = parse_airlines(AIRLINES_DAT, 2).unwrap();
+        assert_eq!(
+            table,
+            [
+                ("NH", "ANA", "All Nippon Airways"),
+                ("BA", "BAW", "British Airways")
+            ]
+        );
+
+        let err = parse_airlines(AIRLINES_DAT, 3).unwrap_err().to_string();
+        assert!(err.contains("airlines.dat: 2 usable rows"), "{err}");
+        assert!(parse_airlines("not,a,table\n<html>", 1).is_err());
+    }
+
+    #[test]
+    fn test_install_keeps_previous_data_on_failure() {
+        let dir = std::env::temp_dir().join(format!("refdata-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(AIRPORTS_FILE), "previous airports").unwrap();
+        fs::write(dir.join(AIRLINES_FILE), "previous airlines").unwrap();
+
+        // The airports pass on their own but the airlines don't
+        let row = "1,\"CYQX\",\"large_airport\",\"Gander\",48.9,-54.5,\"CA\",\"CYQX\",\"YQX\"";
+        let airports = airports_csv(&vec![row; MIN_AIRPORTS]);
+        assert!(install(&dir, &airports, AIRLINES_DAT).is_err());
+        assert_eq!(
+            fs::read_to_string(dir.join(AIRPORTS_FILE)).unwrap(),
+            "previous airports"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join(AIRLINES_FILE)).unwrap(),
+            "previous airlines"
+        );
+
+        let airline = "1355,\"British Airways\",\\N,\"BA\",\"BAW\",\"SPEEDBIRD\",\"UK\",\"Y\"\n";
+        let airlines = airline.repeat(MIN_AIRLINES);
+        let counts = install(&dir, &airports, &airlines).unwrap();
+        assert_eq!(
+            counts,
+            Counts {
+                airports: MIN_AIRPORTS,
+                airlines: MIN_AIRLINES
+            }
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join(AIRLINES_FILE)).unwrap(),
+            airlines
+        );
+        // No temporary files are left behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,164 @@
+//! What `--record` writes and `--replay` feeds back: key actions and API
+//! responses, in the order the event loop handled them.
+//!
+//! [`ApiResponse`] carries [`AppError`]s, which can't be written out, so it
+//! is recorded as a [`Response`] with each error reduced to a
+//! [`RecordedError`] and turned back into one on replay. `Response` lives in
+//! the library, so the integration tests read recordings as the app does.
+
+use std::path::Path;
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use flight_tracker_tui::api::{
+    FlightData, PositionProvider, ProviderDiagnostics, ProviderStats, ScheduleProvider,
+    StateSnapshot,
+};
+use flight_tracker_tui::recording::{self, Entry, RecordedError, Response};
+use flight_tracker_tui::AppError;
+
+use crate::action::Action;
+use crate::ApiResponse;
+
+/// One thing the event loop handled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Recorded {
+    Action(Action),
+    Response(Box<Response>),
+}
+
+fn recorded<T: Clone>(result: &Result<T, AppError>) -> Result<T, RecordedError> {
+    match result {
+        Ok(value) => Ok(value.clone()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn replayed<T>(result: Result<T, RecordedError>) -> Result<T, AppError> {
+    result.map_err(AppError::from)
+}
+
+impl From<&ApiResponse> for Response {
+    fn from(response: &ApiResponse) -> Self {
+        match response {
+            ApiResponse::FlightSearch {
+                query,
+                position,
+                schedule,
+            } => Self::FlightSearch {
+                query: query.clone(),
+                position: recorded(position),
+                schedule: schedule.clone(),
+            },
+            ApiResponse::FlightUpdate(flight, result) => {
+                Self::FlightUpdate(flight.clone(), recorded(result))
+            }
+            ApiResponse::ScheduleUpdate(flight, result) => {
+                Self::ScheduleUpdate(flight.clone(), recorded(result))
+            }
+            ApiResponse::Track(flight, result) => Self::Track(flight.clone(), recorded(result)),
+            ApiResponse::Rematch(flight, result) => Self::Rematch(flight.clone(), recorded(result)),
+            ApiResponse::Webhook(result) => Self::Webhook(recorded(result)),
+            ApiResponse::Weather(icao, result) => Self::Weather(icao.clone(), recorded(result)),
+        }
+    }
+}
+
+impl From<Response> for ApiResponse {
+    fn from(response: Response) -> Self {
+        match response {
+            Response::FlightSearch {
+                query,
+                position,
+                schedule,
+            } => Self::FlightSearch {
+                query,
+                position: replayed(position),
+                schedule,
+            },
+            Response::FlightUpdate(flight, result) => Self::FlightUpdate(flight, replayed(result)),
+            Response::ScheduleUpdate(flight, result) => {
+                Self::ScheduleUpdate(flight, replayed(result))
+            }
+            Response::Track(flight, result) => Self::Track(flight, replayed(result)),
+            Response::Rematch(flight, result) => Self::Rematch(flight, replayed(result)),
+            Response::Webhook(result) => Self::Webhook(replayed(result)),
+            Response::Weather(icao, result) => Self::Weather(icao, replayed(result)),
+        }
+    }
+}
+
+/// Read the recording at `path` and send its events to `tx` on their
+/// original schedule, `speed` times faster. Returns how many there are.
+pub fn spawn(path: &Path, speed: f64, tx: mpsc::Sender<Recorded>) -> Result<usize, AppError> {
+    let entries: Vec<Entry<Recorded>> = recording::read(path)?;
+    let count = entries.len();
+    tokio::spawn(async move {
+        let started = tokio::time::Instant::now();
+        for entry in entries {
+            let due = Duration::from_millis(entry.at_ms).div_f64(speed);
+            tokio::time::sleep_until(started + due).await;
+            if tx.send(entry.event).await.is_err() {
+                break;
+            }
+        }
+    });
+    Ok(count)
+}
+
+/// Stands in for both providers during a replay: nothing it is asked ever
+/// answers, so the only responses are the recorded ones.
+pub struct Silent;
+
+impl Silent {
+    fn diagnostics() -> ProviderDiagnostics {
+        ProviderDiagnostics {
+            name: "Replay",
+            auth: String::new(),
+            stats: ProviderStats::default(),
+            cache: Default::default(),
+            breaker: Default::default(),
+            resolution: None,
+        }
+    }
+}
+
+impl PositionProvider for Silent {
+    fn search_flight<'a>(
+        &'a self,
+        _flight_number: &'a str,
+    ) -> BoxFuture<'a, Result<StateSnapshot, AppError>> {
+        Box::pin(std::future::pending())
+    }
+
+    fn get_state<'a>(&'a self, _icao24: &'a str) -> BoxFuture<'a, Result<StateSnapshot, AppError>> {
+        Box::pin(std::future::pending())
+    }
+
+    fn diagnostics(&self) -> ProviderDiagnostics {
+        Silent::diagnostics()
+    }
+}
+
+impl ScheduleProvider for Silent {
+    fn is_enabled(&self) -> bool {
+        true
+    }
+
+    fn diagnostics(&self) -> ProviderDiagnostics {
+        Silent::diagnostics()
+    }
+
+    fn get_flight<'a>(
+        &'a self,
+        _flight_number: &'a str,
+        _date: Option<NaiveDate>,
+    ) -> BoxFuture<'a, Result<Option<FlightData>, AppError>> {
+        Box::pin(std::future::pending())
+    }
+}
@@ -0,0 +1,533 @@
+//! Tables of tracked flights for pasting into emails and notes.
+//!
+//! Each column is a [`Column`] in the [`COLUMNS`] registry, picked by id
+//! from the `[table]` section of the config or the `set columns` command.
+
+use serde::Deserialize;
+
+use crate::config::TimeFormat;
+use crate::flight::{Airport, Flight, ScheduleField};
+
+/// Shown in place of data the providers didn't return.
+const MISSING: &str = "-";
+
+/// One column a table can have.
+#[derive(Debug, Clone, Copy)]
+pub struct Column {
+    /// Name used in the config and the palette.
+    pub id: &'static str,
+    pub title: &'static str,
+    /// Narrowest the column is padded to, so it keeps its width whether or
+    /// not any flight has a value for it.
+    pub width: usize,
+    /// The cell for one flight.
+    pub value: fn(&Flight, TimeFormat) -> String,
+}
+
+const fn column(
+    id: &'static str,
+    title: &'static str,
+    width: usize,
+    value: fn(&Flight, TimeFormat) -> String,
+) -> Column {
+    Column {
+        id,
+        title,
+        width,
+        value,
+    }
+}
+
+/// Every column, in the order `set columns` lists them.
+pub const COLUMNS: &[Column] = &[
+    column("flight", "Flight", 0, |f, _| f.flight_number.clone()),
+    column("route", "Route", 0, |f, _| route(f)),
+    column("status", "Status", 0, |f, _| f.status.to_string()),
+    column("departure", "Departure", 0, |f, clock| {
+        schedule_time(
+            f,
+            clock,
+            [
+                ScheduleField::DepartureScheduled,
+                ScheduleField::DepartureActual,
+                ScheduleField::DepartureEstimated,
+            ],
+        )
+    }),
+    column("arrival", "Arrival", 0, |f, clock| {
+        schedule_time(
+            f,
+            clock,
+            [
+                ScheduleField::ArrivalScheduled,
+                ScheduleField::ArrivalActual,
+                ScheduleField::ArrivalEstimated,
+            ],
+        )
+    }),
+    column("delay", "Delay", 0, |f, _| delay(f)),
+    column("aircraft", "Aircraft", 0, |f, _| aircraft(f)),
+    column("airline", "Airline", 0, |f, _| {
+        f.airline.clone().unwrap_or_else(|| MISSING.to_string())
+    }),
+    column("squawk", "Squawk", 0, |f, _| {
+        f.squawk.clone().unwrap_or_else(|| MISSING.to_string())
+    }),
+    column("altitude", "Altitude", 9, |f, _| {
+        f.altitude_ft
+            .map_or(MISSING.to_string(), |ft| format!("{:.0} ft", ft))
+    }),
+    column("speed", "Speed", 7, |f, _| {
+        f.ground_speed_kts
+            .map_or(MISSING.to_string(), |kts| format!("{:.0} kts", kts))
+    }),
+    column("vertical_rate", "V/S", 9, |f, _| {
+        f.smoothed_vertical_rate()
+            .map_or(MISSING.to_string(), |fpm| format!("{:+.0} fpm", fpm))
+    }),
+];
+
+/// The columns a table has when the config doesn't say.
+pub const DEFAULT_COLUMNS: [&str; 7] = [
+    "flight",
+    "route",
+    "status",
+    "departure",
+    "arrival",
+    "delay",
+    "aircraft",
+];
+
+/// The registry entry for `id`.
+///
+/// ```
+/// use flight_tracker_tui::report::column_by_id;
+///
+/// assert_eq!(column_by_id("squawk").unwrap().title, "Squawk");
+/// assert!(column_by_id("gate").is_none());
+/// ```
+pub fn column_by_id(id: &str) -> Option<&'static Column> {
+    COLUMNS.iter().find(|column| column.id == id)
+}
+
+/// A comma-separated list of column ids, as typed into the palette.
+pub fn parse_columns(list: &str) -> Result<Vec<String>, String> {
+    let ids: Vec<String> = list
+        .split(',')
+        .map(|id| id.trim().to_lowercase())
+        .filter(|id| !id.is_empty())
+        .collect();
+    if let Some(unknown) = ids.iter().find(|id| column_by_id(id).is_none()) {
+        let known: Vec<&str> = COLUMNS.iter().map(|c| c.id).collect();
+        return Err(format!("No {} column, try {}", unknown, known.join(", ")));
+    }
+    if ids.is_empty() {
+        return Err("List the columns, separated by commas".to_string());
+    }
+    Ok(ids)
+}
+
+/// The `[table]` section of the config.
+///
+/// ```
+/// use flight_tracker_tui::config::Config;
+///
+/// let config = Config::parse("[table]\ncolumns = [\"flight\", \"squawk\"]").unwrap();
+/// assert_eq!(config.table.columns, ["flight", "squawk"]);
+/// assert_eq!(Config::parse("").unwrap().table.columns.len(), 7);
+/// ```
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Table {
+    /// Column ids, in the order they are shown.
+    pub columns: Vec<String>,
+}
+
+impl Default for Table {
+    fn default() -> Self {
+        Self {
+            columns: DEFAULT_COLUMNS.map(str::to_string).to_vec(),
+        }
+    }
+}
+
+impl Table {
+    /// Drop the ids no column has and return them. With none left, the
+    /// default columns are used instead.
+    pub fn remove_unknown(&mut self) -> Vec<String> {
+        let (known, unknown) = std::mem::take(&mut self.columns)
+            .into_iter()
+            .partition(|id| column_by_id(id).is_some());
+        self.columns = known;
+        if self.columns.is_empty() {
+            *self = Self::default();
+        }
+        unknown
+    }
+
+    /// The registry entries for the listed columns.
+    pub fn resolve(&self) -> Vec<&'static Column> {
+        self.columns
+            .iter()
+            .filter_map(|id| column_by_id(id))
+            .collect()
+    }
+}
+
+/// How a report is laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// A Markdown table, with `|` in cells escaped.
+    Markdown,
+    /// Space-separated columns for a terminal or plain-text email.
+    Text,
+}
+
+/// One row per flight under a header row, columns padded to line up.
+///
+/// ```
+/// use flight_tracker_tui::config::TimeFormat;
+/// use flight_tracker_tui::flight::Flight;
+/// use flight_tracker_tui::report::{render, ReportFormat, Table};
+///
+/// let flight = Flight {
+///     flight_number: "BA285".to_string(),
+///     ..Default::default()
+/// };
+/// let columns = Table::default().resolve();
+/// let report = render(&[flight], &columns, ReportFormat::Markdown, TimeFormat::TwentyFourHour);
+///
+/// assert!(report.starts_with("| Flight | Route |"));
+/// assert!(report.lines().nth(2).unwrap().starts_with("| BA285  | -     |"));
+/// ```
+pub fn render(
+    flights: &[Flight],
+    columns: &[&Column],
+    format: ReportFormat,
+    clock: TimeFormat,
+) -> String {
+    let header: Vec<String> = columns.iter().map(|c| c.title.to_string()).collect();
+    let mut rows: Vec<Vec<String>> = flights.iter().map(|f| row(f, columns, clock)).collect();
+    if format == ReportFormat::Markdown {
+        for cell in rows.iter_mut().flatten() {
+            *cell = cell.replace('|', "\\|");
+        }
+    }
+
+    let mut widths: Vec<usize> = columns.iter().map(|c| c.width).collect();
+    for cells in std::iter::once(&header).chain(&rows) {
+        for (width, cell) in widths.iter_mut().zip(cells) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut lines = vec![format_row(&header, &widths, format)];
+    if format == ReportFormat::Markdown {
+        let rule: Vec<String> = widths.iter().map(|&w| "-".repeat(w)).collect();
+        lines.push(format_row(&rule, &widths, format));
+    }
+    lines.extend(rows.iter().map(|cells| format_row(cells, &widths, format)));
+
+    let mut report = lines.join("\n");
+    report.push('\n');
+    report
+}
+
+fn format_row(cells: &[String], widths: &[usize], format: ReportFormat) -> String {
+    let padded: Vec<String> = cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, &width)| format!("{:<width$}", cell))
+        .collect();
+    match format {
+        ReportFormat::Markdown => format!("| {} |", padded.join(" | ")),
+        ReportFormat::Text => padded.join("  ").trim_end().to_string(),
+    }
+}
+
+fn row(flight: &Flight, columns: &[&Column], clock: TimeFormat) -> Vec<String> {
+    columns
+        .iter()
+        .map(|column| (column.value)(flight, clock))
+        .collect()
+}
+
+fn route(flight: &Flight) -> String {
+    if flight.origin.is_none() && flight.destination.is_none() {
+        return MISSING.to_string();
+    }
+    let endpoint = |airport: &Option<Airport>| {
+        let Some(airport) = airport else {
+            return "?".to_string();
+        };
+        let code = airport
+            .iata
+            .as_deref()
+            .or(airport.icao.as_deref())
+            .unwrap_or("?");
+        match &airport.name {
+            Some(name) => format!("{} {}", code, name),
+            None => code.to_string(),
+        }
+    };
+    format!(
+        "{} → {}",
+        endpoint(&flight.origin),
+        endpoint(&flight.destination)
+    )
+}
+
+/// Scheduled time followed by the actual or, failing that, estimated time.
+fn schedule_time(flight: &Flight, clock: TimeFormat, fields: [ScheduleField; 3]) -> String {
+    let [scheduled, actual, estimated] = fields.map(|f| flight.format_schedule_time(f, clock));
+    let Some(mut cell) = scheduled else {
+        return MISSING.to_string();
+    };
+    if let Some(actual) = actual {
+        cell.push_str(&format!(" (actual {})", actual));
+    } else if let Some(estimated) = estimated {
+        cell.push_str(&format!(" (est {})", estimated));
+    }
+    cell
+}
+
+/// The arrival delay once known, otherwise the departure delay.
+fn delay(flight: &Flight) -> String {
+    match flight.current_delay() {
+        Some(minutes) if minutes > 0 => format!("+{} min", minutes),
+        Some(_) => "on time".to_string(),
+        None => MISSING.to_string(),
+    }
+}
+
+fn aircraft(flight: &Flight) -> String {
+    match (&flight.aircraft_type, &flight.registration) {
+        (Some(kind), Some(registration)) => format!("{} ({})", kind, registration),
+        (Some(value), None) | (None, Some(value)) => value.clone(),
+        (None, None) => MISSING.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flight::{parse_time, FlightStatus};
+
+    fn scheduled() -> Flight {
+        Flight {
+            flight_number: "UA900".to_string(),
+            status: FlightStatus::EnRoute,
+            origin: Some(Airport {
+                name: Some("San Francisco International".to_string()),
+                iata: Some("SFO".to_string()),
+                icao: Some("KSFO".to_string()),
+            }),
+            destination: Some(Airport {
+                name: Some("Frankfurt".to_string()),
+                iata: Some("FRA".to_string()),
+                icao: None,
+            }),
+            departure_scheduled: parse_time("2024-07-14T20:05:00+00:00"),
+            departure_actual: parse_time("2024-07-14T20:31:00+00:00"),
+            arrival_scheduled: parse_time("2024-07-15T16:10:00+00:00"),
+            arrival_estimated: parse_time("2024-07-15T16:30:00+00:00"),
+            departure_delay: Some(26),
+            arrival_delay: Some(20),
+            aircraft_type: Some("B789".to_string()),
+            registration: Some("N24974".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_markdown_table() {
+        let missing = Flight {
+            flight_number: "BA285".to_string(),
+            ..Default::default()
+        };
+        let report = render(
+            &[scheduled(), missing],
+            &Table::default().resolve(),
+            ReportFormat::Markdown,
+            TimeFormat::TwentyFourHour,
+        );
+        let lines: Vec<&str> = report.lines().collect();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[2],
+            "| UA900  | SFO San Francisco International → FRA Frankfurt | En Route \
+             | 20:05 (actual 20:31) | 16:10 (est 16:30) | +20 min | B789 (N24974) |"
+        );
+        assert_eq!(
+            lines[3],
+            "| BA285  | -                                               | Unknown  \
+             | -                    | -                 | -       | -             |"
+        );
+        assert!(lines[1].starts_with("| ------ | ------"));
+        // Every row lines up
+        let width = lines[0].chars().count();
+        assert!(lines.iter().all(|l| l.chars().count() == width));
+    }
+
+    #[test]
+    fn test_markdown_escapes_pipes() {
+        let mut flight = scheduled();
+        flight.origin.as_mut().unwrap().name = Some("Paris | Charles de Gaulle".to_string());
+
+        let columns = Table::default().resolve();
+        let markdown = render(
+            &[flight.clone()],
+            &columns,
+            ReportFormat::Markdown,
+            TimeFormat::TwentyFourHour,
+        );
+        assert!(markdown.contains("SFO Paris \\| Charles de Gaulle"));
+        // Escaped cells still line up with the header
+        let lines: Vec<&str> = markdown.lines().collect();
+        assert_eq!(lines[0].chars().count(), lines[2].chars().count());
+
+        let text = render(
+            &[flight],
+            &columns,
+            ReportFormat::Text,
+            TimeFormat::TwentyFourHour,
+        );
+        assert!(text.contains("SFO Paris | Charles de Gaulle"));
+    }
+
+    #[test]
+    fn test_text_columns() {
+        let columns = Table::default().resolve();
+        let report = render(
+            &[scheduled()],
+            &columns,
+            ReportFormat::Text,
+            TimeFormat::TwelveHour,
+        );
+        let lines: Vec<&str> = report.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("Flight  Route"));
+        assert!(lines[1].starts_with("UA900   SFO San Francisco"));
+        assert!(lines[1].contains("8:05 pm (actual 8:31 pm)"));
+        assert!(lines[1].ends_with("B789 (N24974)"));
+        // Columns start at the same offset in the header and the row
+        assert_eq!(
+            lines[0].find("Status"),
+            lines[1].find("En Route").map(|i| i - 2)
+        );
+    }
+
+    #[test]
+    fn test_partial_cells() {
+        let mut flight = scheduled();
+        flight.destination = None;
+        flight.arrival_delay = None;
+        flight.departure_delay = Some(0);
+        flight.registration = None;
+        flight.departure_actual = None;
+
+        let cells = row(
+            &flight,
+            &Table::default().resolve(),
+            TimeFormat::TwentyFourHour,
+        );
+        assert_eq!(cells[1], "SFO San Francisco International → ?");
+        assert_eq!(cells[3], "20:05");
+        assert_eq!(cells[5], "on time");
+        assert_eq!(cells[6], "B789");
+    }
+
+    #[test]
+    fn test_registry() {
+        let ids: Vec<&str> = COLUMNS.iter().map(|c| c.id).collect();
+        let mut unique = ids.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), ids.len(), "duplicate column ids");
+        assert!(DEFAULT_COLUMNS.iter().all(|id| column_by_id(id).is_some()));
+        assert_eq!(Table::default().resolve().len(), DEFAULT_COLUMNS.len());
+
+        let mut flight = scheduled();
+        flight.squawk = Some("7700".to_string());
+        flight.altitude_ft = Some(35_000.0);
+        let value = |id| (column_by_id(id).unwrap().value)(&flight, TimeFormat::TwentyFourHour);
+        assert_eq!(value("squawk"), "7700");
+        assert_eq!(value("altitude"), "35000 ft");
+        assert_eq!(value("vertical_rate"), "-");
+    }
+
+    #[test]
+    fn test_chosen_columns() {
+        let table = Table {
+            columns: vec![
+                "squawk".to_string(),
+                "flight".to_string(),
+                "delay".to_string(),
+            ],
+        };
+        let mut flight = scheduled();
+        flight.squawk = Some("1234".to_string());
+        let report = render(
+            &[flight],
+            &table.resolve(),
+            ReportFormat::Markdown,
+            TimeFormat::TwentyFourHour,
+        );
+        let lines: Vec<&str> = report.lines().collect();
+
+        assert_eq!(lines[0], "| Squawk | Flight | Delay   |");
+        assert_eq!(lines[2], "| 1234   | UA900  | +20 min |");
+    }
+
+    #[test]
+    fn test_width_hint_pads_empty_columns() {
+        let table = Table {
+            columns: vec!["vertical_rate".to_string()],
+        };
+        let report = render(
+            &[scheduled()],
+            &table.resolve(),
+            ReportFormat::Markdown,
+            TimeFormat::TwentyFourHour,
+        );
+        assert_eq!(report.lines().next(), Some("| V/S       |"));
+    }
+
+    #[test]
+    fn test_remove_unknown() {
+        let mut table = Table {
+            columns: vec![
+                "flight".to_string(),
+                "gate".to_string(),
+                "squawk".to_string(),
+            ],
+        };
+        assert_eq!(table.remove_unknown(), ["gate"]);
+        assert_eq!(table.columns, ["flight", "squawk"]);
+
+        // Nothing usable falls back to the defaults
+        let mut table = Table {
+            columns: vec!["gate".to_string()],
+        };
+        table.remove_unknown();
+        assert_eq!(table, Table::default());
+    }
+
+    #[test]
+    fn test_parse_columns() {
+        assert_eq!(
+            parse_columns("Flight, squawk,,vertical_rate"),
+            Ok(vec![
+                "flight".to_string(),
+                "squawk".to_string(),
+                "vertical_rate".to_string()
+            ])
+        );
+        assert!(parse_columns("flight,gate")
+            .unwrap_err()
+            .starts_with("No gate column, try"));
+        assert!(parse_columns(" , ").is_err());
+    }
+}
@@ -0,0 +1,267 @@
+//! Persistence of the tracked flight list so it survives restarts.
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::alert::{AlertRule, Mute};
+use crate::flight::{DelaySample, Flight};
+use crate::paths::data_dir;
+use crate::persist;
+use crate::trip::Trip;
+
+const SESSION_FILE: &str = "session.json";
+
+/// A tracked flight as stored in the session file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionFlight {
+    pub flight_number: String,
+    #[serde(default)]
+    pub date: Option<NaiveDate>,
+    #[serde(default)]
+    pub alerts: Vec<AlertRule>,
+    /// Delay changes seen so far, so the trend survives a restart.
+    #[serde(default)]
+    pub delay_history: Vec<DelaySample>,
+    /// Latest known arrival time: actual, else estimated, else scheduled.
+    #[serde(default)]
+    pub arrival: Option<DateTime<FixedOffset>>,
+    /// Whether the flight had arrived when the session was saved.
+    #[serde(default)]
+    pub arrived: bool,
+    /// Whether the flight rolls over to the next day once it is over.
+    #[serde(default)]
+    pub recurring: bool,
+    /// Whether the flight was added from the watchlist file.
+    #[serde(default)]
+    pub watchlist: bool,
+    /// Route label such as "SFO→LHR", once both airports were known.
+    #[serde(default)]
+    pub route: Option<String>,
+    /// Notifications held back, until it runs out.
+    #[serde(default)]
+    pub muted_until: Option<Mute>,
+}
+
+impl SessionFlight {
+    /// Whether the flight has probably landed since the session was saved:
+    /// it had already arrived, its arrival time has passed, or it was for
+    /// an earlier day. Recurring flights roll over instead, so never count.
+    pub fn likely_landed(&self, now: DateTime<Local>) -> bool {
+        if self.recurring {
+            return false;
+        }
+        self.arrived
+            || self.arrival.is_some_and(|at| at.to_utc() <= now.to_utc())
+            || self.date.is_some_and(|date| date < now.date_naive())
+    }
+}
+
+/// The set of flights being tracked, in display order.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Session {
+    pub flights: Vec<SessionFlight>,
+    /// Groups of the flights above, by flight number.
+    #[serde(default)]
+    pub trips: Vec<Trip>,
+}
+
+impl Session {
+    /// Snapshot the given flights.
+    pub fn from_flights<'a>(flights: impl IntoIterator<Item = &'a Flight>) -> Self {
+        Self {
+            flights: flights
+                .into_iter()
+                .map(|f| SessionFlight {
+                    flight_number: f.flight_number.clone(),
+                    date: f.flight_date,
+                    alerts: f.alerts.clone(),
+                    delay_history: f.delay_history.clone(),
+                    arrival: f
+                        .arrival_actual
+                        .or(f.arrival_estimated)
+                        .or(f.arrival_scheduled),
+                    arrived: f.arrived_at.is_some(),
+                    recurring: f.recurring,
+                    watchlist: f.from_watchlist,
+                    route: f.route_label(),
+                    muted_until: f.muted_until,
+                })
+                .collect(),
+            trips: Vec::new(),
+        }
+    }
+
+    /// Load the session from the config file, or return an empty session.
+    pub fn load() -> Self {
+        Self::config_path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the session to the config file in the background.
+    pub fn save(&self) {
+        if let Some(path) = Self::config_path() {
+            if let Ok(contents) = serde_json::to_string_pretty(self) {
+                persist::write(path, contents);
+            }
+        }
+    }
+
+    fn config_path() -> Option<PathBuf> {
+        data_dir().map(|p| p.join(SESSION_FILE))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alert::AlertCondition;
+    use crate::flight::Airport;
+
+    #[test]
+    fn test_session_from_flights() {
+        let flights = vec![
+            Flight {
+                flight_number: "UA123".to_string(),
+                alerts: vec![AlertRule::new(AlertCondition::Landed)],
+                ..Default::default()
+            },
+            Flight {
+                flight_number: "BA285".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        let session = Session::from_flights(&flights);
+
+        assert_eq!(session.flights.len(), 2);
+        assert_eq!(session.flights[0].flight_number, "UA123");
+        assert_eq!(session.flights[0].alerts.len(), 1);
+        assert!(session.flights[1].alerts.is_empty());
+    }
+
+    #[test]
+    fn test_session_serialization_defaults_alerts() {
+        let json = r#"{"flights":[{"flight_number":"AF007"}]}"#;
+        let session: Session = serde_json::from_str(json).unwrap();
+
+        assert_eq!(session.flights[0].flight_number, "AF007");
+        assert!(session.flights[0].date.is_none());
+        assert!(session.flights[0].alerts.is_empty());
+        assert!(session.flights[0].delay_history.is_empty());
+        assert!(session.trips.is_empty());
+    }
+
+    #[test]
+    fn test_session_round_trips_trips() {
+        let session = Session {
+            trips: vec![Trip::new(
+                "NYC-TYO",
+                vec!["UA79".to_string(), "NH6".to_string()],
+            )],
+            ..Session::from_flights(&[])
+        };
+        let json = serde_json::to_string(&session).unwrap();
+        let loaded: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.trips, session.trips);
+    }
+
+    #[test]
+    fn test_session_round_trips_delay_history() {
+        let flight = Flight {
+            flight_number: "UA900".to_string(),
+            delay_history: vec![DelaySample {
+                at: "2024-07-14T10:00:00Z".parse().unwrap(),
+                minutes: 25,
+            }],
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string(&Session::from_flights([&flight])).unwrap();
+        let session: Session = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(session.flights[0].delay_history, flight.delay_history);
+    }
+
+    #[test]
+    fn test_session_keeps_known_routes() {
+        let flights = [
+            Flight {
+                flight_number: "UA900".to_string(),
+                origin: Some(Airport::test("SFO")),
+                destination: Some(Airport::test("FRA")),
+                ..Default::default()
+            },
+            Flight {
+                flight_number: "BA285".to_string(),
+                origin: Some(Airport::test("LHR")),
+                ..Default::default()
+            },
+        ];
+        let session = Session::from_flights(&flights);
+        assert_eq!(session.flights[0].route.as_deref(), Some("SFO→FRA"));
+        assert_eq!(session.flights[1].route, None);
+    }
+
+    #[test]
+    fn test_likely_landed() {
+        let now = Local::now();
+        let hour = chrono::Duration::hours(1);
+        let flight = |arrival: Option<DateTime<Local>>| SessionFlight {
+            flight_number: "UA900".to_string(),
+            date: None,
+            alerts: Vec::new(),
+            delay_history: Vec::new(),
+            arrival: arrival.map(|at| at.fixed_offset()),
+            arrived: false,
+            recurring: false,
+            watchlist: false,
+            route: None,
+            muted_until: None,
+        };
+
+        assert!(!flight(None).likely_landed(now));
+        assert!(!flight(Some(now + hour)).likely_landed(now));
+        assert!(flight(Some(now - hour)).likely_landed(now));
+        assert!(SessionFlight {
+            arrived: true,
+            ..flight(Some(now + hour))
+        }
+        .likely_landed(now));
+
+        let yesterday = now.date_naive().pred_opt();
+        assert!(SessionFlight {
+            date: yesterday,
+            ..flight(None)
+        }
+        .likely_landed(now));
+        let commute = SessionFlight {
+            date: yesterday,
+            recurring: true,
+            ..flight(None)
+        };
+        assert!(!commute.likely_landed(now));
+        let today = Some(now.date_naive());
+        assert!(!SessionFlight {
+            date: today,
+            ..flight(None)
+        }
+        .likely_landed(now));
+    }
+
+    #[test]
+    fn test_session_saves_arrival() {
+        let flight = Flight {
+            flight_number: "UA900".to_string(),
+            arrival_scheduled: crate::flight::parse_time("2024-07-15T16:10:00+02:00"),
+            arrival_estimated: crate::flight::parse_time("2024-07-15T16:30:00+02:00"),
+            ..Default::default()
+        };
+        let saved = &Session::from_flights([&flight]).flights[0];
+        assert_eq!(saved.arrival, flight.arrival_estimated);
+        assert!(!saved.arrived);
+    }
+}
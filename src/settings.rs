@@ -0,0 +1,287 @@
+//! The settings overlay, for changing `config.toml` without an editor.
+//!
+//! [`SETTINGS`] lists what the overlay offers. Each [`Setting`] names its
+//! config key, the values `Enter` and the arrow keys cycle through, and how
+//! to read and apply them, so a new option only needs an entry here. A
+//! chosen value is parsed as the config file would parse it before it is
+//! applied, and `App` writes the changed keys back to `config.toml` when the
+//! overlay closes.
+
+use serde::Serialize;
+
+use crate::app::App;
+use crate::config::{Background, Config};
+use crate::theme::Theme;
+
+/// One value a setting can take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Choice {
+    /// As written in `config.toml`
+    pub value: &'static str,
+    pub label: &'static str,
+}
+
+const fn choice(value: &'static str, label: &'static str) -> Choice {
+    Choice { value, label }
+}
+
+/// An option the overlay can change.
+pub struct Setting {
+    /// Top-level key in `config.toml`
+    pub key: &'static str,
+    pub label: &'static str,
+    pub choices: &'static [Choice],
+    /// The value in effect, as written in `config.toml`
+    get: fn(&App) -> String,
+    /// Take this setting's value from a config holding just it
+    set: fn(&mut App, &Config),
+}
+
+impl Setting {
+    /// The label of the value in effect, or the value itself when it was
+    /// set by hand to something the overlay doesn't offer.
+    pub fn current(&self, app: &App) -> String {
+        let value = (self.get)(app);
+        match self.choices.iter().find(|c| c.value == value) {
+            Some(choice) => choice.label.to_string(),
+            None => value,
+        }
+    }
+
+    /// The choice after the value in effect, or before it going
+    /// `backward`, wrapping around. From a value the overlay doesn't offer
+    /// it starts at the first choice, or the last going backward.
+    pub fn step(&self, app: &App, backward: bool) -> &'static Choice {
+        let value = (self.get)(app);
+        let last = self.choices.len() - 1;
+        let index = match self.choices.iter().position(|c| c.value == value) {
+            Some(i) if backward => i.checked_sub(1).unwrap_or(last),
+            Some(i) if i == last => 0,
+            Some(i) => i + 1,
+            None if backward => last,
+            None => 0,
+        };
+        &self.choices[index]
+    }
+
+    /// Apply `value` to the app. Returns whether it was a valid value for
+    /// the key.
+    pub fn apply(&self, app: &mut App, value: &str) -> bool {
+        match Config::parse(&format!("{} = {}", self.key, value)) {
+            Ok(config) => {
+                (self.set)(app, &config);
+                true
+            }
+            Err(e) => {
+                tracing::warn!(key = self.key, value, error = %e, "Invalid setting");
+                false
+            }
+        }
+    }
+}
+
+/// A value as `config.toml` spells it.
+fn toml(value: impl Serialize) -> String {
+    toml::Value::try_from(value)
+        .map(|v| v.to_string())
+        .unwrap_or_default()
+}
+
+pub const SETTINGS: &[Setting] = &[
+    Setting {
+        key: "min_refresh_secs",
+        label: "Refresh interval",
+        choices: &[
+            choice("5", "at most every 5s"),
+            choice("10", "at most every 10s"),
+            choice("15", "at most every 15s"),
+            choice("30", "at most every 30s"),
+            choice("60", "at most every minute"),
+            choice("120", "at most every 2 minutes"),
+        ],
+        get: |app| toml(app.config.min_refresh_secs),
+        set: |app, config| app.config.min_refresh_secs = config.min_refresh_secs,
+    },
+    Setting {
+        key: "schedule_refresh_mins",
+        label: "Schedule refresh",
+        choices: &[
+            choice("15", "every 15 minutes"),
+            choice("30", "every 30 minutes"),
+            choice("60", "every hour"),
+            choice("120", "every 2 hours"),
+        ],
+        get: |app| toml(app.config.schedule_refresh_mins),
+        set: |app, config| app.config.schedule_refresh_mins = config.schedule_refresh_mins,
+    },
+    Setting {
+        key: "background_refresh",
+        label: "While unfocused",
+        choices: &[
+            choice("1", "refresh as usual"),
+            choice("2", "refresh 2× slower"),
+            choice("4", "refresh 4× slower"),
+            choice("8", "refresh 8× slower"),
+            choice("0", "pause refreshing"),
+        ],
+        get: |app| toml(app.background_multiplier),
+        set: |app, config| {
+            app.config.background_refresh = config.background_refresh;
+            app.background_multiplier = config.background_refresh;
+        },
+    },
+    Setting {
+        key: "time_format",
+        label: "Clock",
+        choices: &[choice("\"24h\"", "24-hour"), choice("\"12h\"", "12-hour")],
+        get: |app| toml(app.config.time_format),
+        set: |app, config| app.set_time_format(config.time_format),
+    },
+    Setting {
+        key: "layout",
+        label: "Layout",
+        choices: &[
+            choice("\"full\"", "flight list, details and status bar"),
+            choice("\"ticker\"", "one line per flight"),
+        ],
+        get: |app| toml(app.config.layout),
+        set: |app, config| app.set_layout(config.layout),
+    },
+    Setting {
+        key: "ascii_only",
+        label: "Characters",
+        choices: &[
+            choice("false", "Unicode arrows, glyphs and borders"),
+            choice("true", "ASCII only"),
+        ],
+        get: |app| toml(app.config.ascii_only),
+        set: |app, config| app.config.ascii_only = config.ascii_only,
+    },
+    Setting {
+        key: "terminal_title",
+        label: "Window title",
+        choices: &[
+            choice("false", "left to the terminal"),
+            choice("true", "followed flight's summary"),
+        ],
+        get: |app| toml(app.config.terminal_title),
+        set: |app, config| app.config.terminal_title = config.terminal_title,
+    },
+    Setting {
+        key: "background",
+        label: "Theme",
+        choices: &[
+            choice("\"auto\"", "ask the terminal at startup"),
+            choice("\"dark\"", "dark background"),
+            choice("\"light\"", "light background"),
+        ],
+        get: |app| toml(app.config.background),
+        // Asking the terminal only works before the event reader starts,
+        // so `auto` keeps the colors in use until the next start
+        set: |app, config| {
+            app.config.background = config.background;
+            match config.background {
+                Background::Dark => app.theme = Theme::DARK,
+                Background::Light => app.theme = Theme::LIGHT,
+                Background::Auto => {}
+            }
+        },
+    },
+    Setting {
+        key: "dead_reckoning",
+        label: "Between updates",
+        choices: &[
+            choice("false", "show the last position"),
+            choice("true", "estimate the position"),
+        ],
+        get: |app| toml(app.config.dead_reckoning),
+        set: |app, config| app.config.dead_reckoning = config.dead_reckoning,
+    },
+    Setting {
+        key: "arrival_notice_mins",
+        label: "Arriving soon notice",
+        choices: &[
+            choice("0", "off"),
+            choice("10", "10 minutes before"),
+            choice("20", "20 minutes before"),
+            choice("30", "30 minutes before"),
+        ],
+        get: |app| toml(app.config.arrival_notice_mins),
+        set: |app, config| app.config.arrival_notice_mins = config.arrival_notice_mins,
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TimeFormat;
+
+    fn setting(key: &str) -> &'static Setting {
+        SETTINGS.iter().find(|s| s.key == key).unwrap()
+    }
+
+    #[test]
+    fn test_every_choice_applies_and_reads_back() {
+        let mut app = App::default();
+        for setting in SETTINGS {
+            assert!(!setting.choices.is_empty(), "{}", setting.key);
+            for choice in setting.choices {
+                let applied = setting.apply(&mut app, choice.value);
+                assert!(applied, "{} = {}", setting.key, choice.value);
+                assert_eq!((setting.get)(&app), choice.value, "{}", setting.key);
+                assert_eq!(setting.current(&app), choice.label);
+            }
+        }
+    }
+
+    #[test]
+    fn test_defaults_are_offered() {
+        let app = App::default();
+        for setting in SETTINGS {
+            let value = (setting.get)(&app);
+            assert!(
+                setting.choices.iter().any(|c| c.value == value),
+                "{} = {} isn't offered",
+                setting.key,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_step_wraps_around() {
+        let mut app = App::default();
+        let clock = setting("time_format");
+        assert_eq!(clock.step(&app, false).label, "12-hour");
+        assert_eq!(clock.step(&app, true).label, "12-hour");
+
+        let refresh = setting("min_refresh_secs");
+        assert_eq!(refresh.step(&app, false).value, "15");
+        assert_eq!(refresh.step(&app, true).value, "5");
+        app.config.min_refresh_secs = 120;
+        assert_eq!(refresh.step(&app, false).value, "5");
+
+        // A value set by hand shows as it is and steps to either end
+        app.config.min_refresh_secs = 45;
+        assert_eq!(refresh.current(&app), "45");
+        assert_eq!(refresh.step(&app, false).value, "5");
+        assert_eq!(refresh.step(&app, true).value, "120");
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut app = App::default();
+        assert!(setting("background").apply(&mut app, "\"light\""));
+        assert_eq!(app.config.background, Background::Light);
+        assert_eq!(app.theme, Theme::LIGHT);
+
+        assert!(setting("background_refresh").apply(&mut app, "0"));
+        assert_eq!(
+            (app.background_multiplier, app.config.background_refresh),
+            (0, 0)
+        );
+
+        assert!(!setting("time_format").apply(&mut app, "\"13h\""));
+        assert_eq!(app.config.time_format, TimeFormat::TwentyFourHour);
+    }
+}
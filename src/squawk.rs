@@ -0,0 +1,139 @@
+//! Meaning of transponder squawk codes.
+//!
+//! Most codes are assigned by ATC for a single flight and mean nothing on
+//! their own. A handful are reserved worldwide (the emergency codes) or by
+//! convention in some regions; those come from the table below.
+
+/// What a squawk code says about the flight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SquawkKind {
+    /// 7500, 7600 or 7700. The label is the short description.
+    Emergency(&'static str),
+    /// Generic VFR or no-code squawk used to make the aircraft visible.
+    Conspicuity(&'static str),
+    /// A special-purpose code with a regional meaning.
+    Notable(&'static str),
+    /// A discrete code given by ATC, or not a valid squawk at all.
+    Assigned,
+}
+
+/// Reserved and conventional codes. Codes reused differently by region keep
+/// the most common meaning.
+const CODES: &[(&str, SquawkKind)] = &[
+    ("7500", SquawkKind::Emergency("HIJACK")),
+    ("7600", SquawkKind::Emergency("RADIO FAILURE")),
+    ("7700", SquawkKind::Emergency("EMERGENCY")),
+    ("1200", SquawkKind::Conspicuity("VFR, North America")),
+    ("7000", SquawkKind::Conspicuity("VFR, ICAO")),
+    ("2000", SquawkKind::Conspicuity("no code assigned")),
+    (
+        "1000",
+        SquawkKind::Conspicuity("Mode S conspicuity, Europe"),
+    ),
+    ("7004", SquawkKind::Notable("aerobatics, Europe")),
+    ("0033", SquawkKind::Notable("parachute drop, UK")),
+    ("1255", SquawkKind::Notable("firefighting, US")),
+    ("1277", SquawkKind::Notable("search and rescue, US")),
+    ("7400", SquawkKind::Notable("lost link, unmanned")),
+    ("7777", SquawkKind::Notable("military interception, US")),
+];
+
+impl SquawkKind {
+    /// Classify a squawk code. Surrounding whitespace is ignored.
+    ///
+    /// ```
+    /// use flight_tracker_tui::squawk::SquawkKind;
+    ///
+    /// assert_eq!(SquawkKind::classify("7700"), SquawkKind::Emergency("EMERGENCY"));
+    /// assert_eq!(SquawkKind::classify("4521"), SquawkKind::Assigned);
+    /// assert!(SquawkKind::classify("7600").is_emergency());
+    /// ```
+    pub fn classify(code: &str) -> Self {
+        let code = code.trim();
+        CODES
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map_or(SquawkKind::Assigned, |(_, kind)| *kind)
+    }
+
+    pub fn is_emergency(self) -> bool {
+        matches!(self, SquawkKind::Emergency(_))
+    }
+
+    /// Short description for display next to the code.
+    pub fn description(self) -> &'static str {
+        match self {
+            SquawkKind::Emergency(label)
+            | SquawkKind::Conspicuity(label)
+            | SquawkKind::Notable(label) => label,
+            SquawkKind::Assigned => "assigned code",
+        }
+    }
+}
+
+/// A squawk code with its meaning, e.g. `7700 (EMERGENCY)`.
+///
+/// ```
+/// use flight_tracker_tui::squawk::describe;
+///
+/// assert_eq!(describe("7700"), "7700 (EMERGENCY)");
+/// assert_eq!(describe("4521"), "4521 (assigned code)");
+/// ```
+pub fn describe(code: &str) -> String {
+    format!(
+        "{} ({})",
+        code.trim(),
+        SquawkKind::classify(code).description()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emergency_codes() {
+        for code in ["7500", "7600", "7700"] {
+            assert!(SquawkKind::classify(code).is_emergency(), "{code}");
+        }
+        assert_eq!(describe("7500"), "7500 (HIJACK)");
+        assert_eq!(describe("7600"), "7600 (RADIO FAILURE)");
+        // Near misses are ordinary assigned codes
+        for code in ["7701", "0770", "770", "77000"] {
+            assert!(!SquawkKind::classify(code).is_emergency(), "{code}");
+        }
+    }
+
+    #[test]
+    fn test_conspicuity_codes() {
+        assert_eq!(
+            SquawkKind::classify("1200"),
+            SquawkKind::Conspicuity("VFR, North America")
+        );
+        assert_eq!(
+            SquawkKind::classify(" 7000 "),
+            SquawkKind::Conspicuity("VFR, ICAO")
+        );
+        assert!(!SquawkKind::classify("1200").is_emergency());
+    }
+
+    #[test]
+    fn test_notable_and_fallback() {
+        assert_eq!(describe("7004"), "7004 (aerobatics, Europe)");
+        assert_eq!(SquawkKind::classify("3412"), SquawkKind::Assigned);
+        assert_eq!(SquawkKind::classify(""), SquawkKind::Assigned);
+        assert_eq!(SquawkKind::classify("ABCD"), SquawkKind::Assigned);
+    }
+
+    #[test]
+    fn test_table_codes_are_unique_octal() {
+        for (i, (code, _)) in CODES.iter().enumerate() {
+            assert_eq!(code.len(), 4);
+            assert!(code.chars().all(|c| ('0'..='7').contains(&c)), "{code}");
+            assert!(
+                !CODES[i + 1..].iter().any(|(c, _)| c == code),
+                "duplicate {code}"
+            );
+        }
+    }
+}
@@ -0,0 +1,528 @@
+//! TAF aerodrome forecasts, and the conditions they give for a moment such
+//! as a flight's ETA.
+//!
+//! [`Taf::parse`] splits a raw TAF into its base conditions and change
+//! groups (`FM`, `BECMG`, `TEMPO`, `PROB30`/`PROB40`), with every period
+//! turned into UTC times. [`Taf::forecast_at`] then replays the groups up
+//! to a moment: `FM` replaces everything, a `BECMG` that has finished
+//! replaces the elements it mentions, and `TEMPO`/`PROB` groups in force
+//! are listed alongside.
+
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc};
+
+use crate::error::AppError;
+
+/// What a group does to the conditions before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// The forecast's opening conditions.
+    Base,
+    /// `FMddhhmm`: from this time on, these conditions replace all others.
+    From,
+    /// `BECMG`: the conditions change at some point during the period and
+    /// then persist.
+    Becoming,
+    /// `TEMPO`: temporary fluctuations during the period.
+    Temporary,
+    /// `PROBnn`, alone or before `TEMPO`: an nn% chance during the period.
+    Probability(u8),
+}
+
+/// A set of conditions and the period it covers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    pub change: Change,
+    pub from: DateTime<Utc>,
+    /// For `Base` and `From` groups, when the next `FM` group or the whole
+    /// forecast ends.
+    pub to: DateTime<Utc>,
+    /// Wind, visibility, weather and cloud elements as written.
+    pub elements: Vec<String>,
+}
+
+/// A parsed TAF.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Taf {
+    /// ICAO code of the airport.
+    pub station: String,
+    pub valid_from: DateTime<Utc>,
+    pub valid_to: DateTime<Utc>,
+    /// The base group first, then the change groups in the order written.
+    pub groups: Vec<Group>,
+}
+
+/// Where a moment falls relative to a TAF's validity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coverage {
+    Within,
+    /// Before the forecast starts; its first conditions are used.
+    Before,
+    /// After it ends; its last conditions are used.
+    After,
+}
+
+/// The conditions forecast for one moment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Forecast {
+    /// The prevailing wind, visibility, weather and cloud.
+    pub conditions: Vec<String>,
+    /// `TEMPO`, `PROB` and unfinished `BECMG` groups in force, as written
+    /// without their periods: "TEMPO 3SM -SHRA".
+    pub variations: Vec<String>,
+    pub coverage: Coverage,
+}
+
+/// Kinds of element, for working out which ones a `BECMG` group replaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Element {
+    Wind,
+    Visibility,
+    Weather,
+    Cloud,
+}
+
+impl Taf {
+    /// Parse a raw TAF, one line or several. Day-of-month times are placed
+    /// in the month that puts them closest to the issue time, or to `now`
+    /// when the TAF doesn't give one.
+    ///
+    /// ```
+    /// use chrono::{TimeZone, Utc};
+    /// use flight_tracker_tui::taf::Taf;
+    ///
+    /// let now = Utc.with_ymd_and_hms(2024, 7, 12, 12, 0, 0).unwrap();
+    /// let taf = Taf::parse(
+    ///     "TAF KJFK 121130Z 1212/1318 20012KT P6SM BKN030 FM121800 22015G25KT P6SM SCT040",
+    ///     now,
+    /// )
+    /// .unwrap();
+    ///
+    /// let eta = Utc.with_ymd_and_hms(2024, 7, 12, 18, 40, 0).unwrap();
+    /// assert_eq!(taf.forecast_at(eta).conditions, ["22015G25KT", "P6SM", "SCT040"]);
+    /// ```
+    pub fn parse(raw: &str, now: DateTime<Utc>) -> Result<Self, AppError> {
+        let mut tokens = raw
+            .split_whitespace()
+            .map(|t| t.trim_end_matches('='))
+            .filter(|t| !t.is_empty())
+            .take_while(|t| *t != "RMK")
+            .skip_while(|t| matches!(*t, "TAF" | "AMD" | "COR"))
+            .peekable();
+
+        let station = tokens
+            .next()
+            .filter(|t| t.len() == 4 && t.chars().all(|c| c.is_ascii_alphanumeric()))
+            .ok_or_else(|| AppError::parse(format!("TAF without a station: {}", raw)))?
+            .to_string();
+        let mut reference = now;
+        if let Some(issued) = tokens.peek().and_then(|t| t.strip_suffix('Z')) {
+            if let Some(at) = day_time(issued, now) {
+                reference = at;
+            }
+            tokens.next();
+        }
+        let no_forecast =
+            |token| AppError::parse(format!("No forecast for {} ({})", station, token));
+        let validity = tokens.next().unwrap_or_default();
+        if matches!(validity, "NIL" | "CNL") {
+            return Err(no_forecast(validity));
+        }
+        let (valid_from, valid_to) = period(validity, reference)
+            .ok_or_else(|| AppError::parse(format!("TAF without a valid period: {}", raw)))?;
+
+        let mut groups = vec![Group {
+            change: Change::Base,
+            from: valid_from,
+            to: valid_to,
+            elements: Vec::new(),
+        }];
+        while let Some(token) = tokens.next() {
+            // A cancelled forecast still gives its period first
+            if matches!(token, "NIL" | "CNL") {
+                return Err(no_forecast(token));
+            }
+            let opened = if let Some(at) = token.strip_prefix("FM") {
+                day_time(at, reference).map(|from| (Change::From, from, valid_to))
+            } else if token == "BECMG" || token == "TEMPO" || token.starts_with("PROB") {
+                let change = match token {
+                    "BECMG" => Change::Becoming,
+                    "TEMPO" => Change::Temporary,
+                    _ => {
+                        if tokens.peek() == Some(&"TEMPO") {
+                            tokens.next();
+                        }
+                        Change::Probability(token[4..].parse().unwrap_or_default())
+                    }
+                };
+                tokens
+                    .next()
+                    .and_then(|p| period(p, reference))
+                    .map(|(from, to)| (change, from, to))
+            } else {
+                None
+            };
+            match opened {
+                Some((change, from, to)) => groups.push(Group {
+                    change,
+                    from,
+                    to,
+                    elements: Vec::new(),
+                }),
+                // Temperature forecasts and the like say nothing about arriving
+                None if token.starts_with("TX") || token.starts_with("TN") => {}
+                None if token == "NOSIG" => {}
+                None => {
+                    if let Some(group) = groups.last_mut() {
+                        group.elements.push(token.to_string());
+                    }
+                }
+            }
+        }
+
+        // Each FM group ends the one before it
+        let starts: Vec<DateTime<Utc>> = groups
+            .iter()
+            .filter(|g| g.change == Change::From)
+            .map(|g| g.from)
+            .collect();
+        for group in &mut groups {
+            if matches!(group.change, Change::Base | Change::From) {
+                group.to = starts
+                    .iter()
+                    .copied()
+                    .find(|start| *start > group.from)
+                    .unwrap_or(valid_to);
+            }
+        }
+
+        Ok(Self {
+            station,
+            valid_from,
+            valid_to,
+            groups,
+        })
+    }
+
+    /// The conditions forecast for `at`. Outside the forecast's validity,
+    /// the nearest moment within it stands in, and `coverage` says so.
+    pub fn forecast_at(&self, at: DateTime<Utc>) -> Forecast {
+        let (coverage, at) = if at < self.valid_from {
+            (Coverage::Before, self.valid_from)
+        } else if at >= self.valid_to {
+            (Coverage::After, self.valid_to - Duration::minutes(1))
+        } else {
+            (Coverage::Within, at)
+        };
+
+        let mut conditions = Vec::new();
+        let mut variations = Vec::new();
+        for group in &self.groups {
+            let started = group.from <= at;
+            let in_force = started && at < group.to;
+            match group.change {
+                Change::Base => conditions = group.elements.clone(),
+                Change::From if started => conditions = group.elements.clone(),
+                Change::Becoming if group.to <= at => merge(&mut conditions, &group.elements),
+                Change::Becoming if in_force => variations.push(label("BECMG", group)),
+                Change::Temporary if in_force => variations.push(label("TEMPO", group)),
+                Change::Probability(chance) if in_force => {
+                    variations.push(label(&format!("PROB{}", chance), group))
+                }
+                _ => {}
+            }
+        }
+        Forecast {
+            conditions,
+            variations,
+            coverage,
+        }
+    }
+}
+
+impl Forecast {
+    /// One line for an arrival at `eta`:
+    /// "At ETA 18:40Z: 20012KT 9999 BKN030, TEMPO 4000 SHRA".
+    pub fn summary(&self, eta: DateTime<Utc>, taf: &Taf) -> String {
+        const BOUND: &str = "%d %H:%MZ";
+        let caveat = match self.coverage {
+            Coverage::Within => String::new(),
+            Coverage::Before => {
+                format!(" (before the TAF starts {})", taf.valid_from.format(BOUND))
+            }
+            Coverage::After => {
+                format!(
+                    " (after the TAF ends {}, its last period)",
+                    taf.valid_to.format(BOUND)
+                )
+            }
+        };
+        let mut line = format!(
+            "At ETA {}{}: {}",
+            eta.format("%H:%MZ"),
+            caveat,
+            self.conditions.join(" ")
+        );
+        for variation in &self.variations {
+            line.push_str(&format!(", {}", variation));
+        }
+        line
+    }
+}
+
+fn label(name: &str, group: &Group) -> String {
+    format!("{} {}", name, group.elements.join(" "))
+}
+
+/// Replace the elements of the kinds `changes` mentions. CAVOK stands for
+/// visibility, weather and cloud together.
+fn merge(conditions: &mut Vec<String>, changes: &[String]) {
+    let replaced: Vec<Element> = changes.iter().flat_map(|e| kinds(e)).collect();
+    conditions.retain(|e| !kinds(e).iter().any(|k| replaced.contains(k)));
+    conditions.extend(changes.iter().cloned());
+    conditions.sort_by_key(|e| kinds(e).first().copied());
+}
+
+fn kinds(element: &str) -> Vec<Element> {
+    const CLOUDS: [&str; 9] = ["FEW", "SCT", "BKN", "OVC", "VV", "NSC", "SKC", "CLR", "NCD"];
+    let digits = element.chars().all(|c| c.is_ascii_digit());
+    if element == "CAVOK" {
+        vec![Element::Visibility, Element::Weather, Element::Cloud]
+    } else if element.ends_with("KT") || element.ends_with("MPS") || is_wind_range(element) {
+        vec![Element::Wind]
+    } else if element.ends_with("SM") || (digits && matches!(element.len(), 1 | 4)) {
+        vec![Element::Visibility]
+    } else if CLOUDS.iter().any(|c| element.starts_with(c)) {
+        vec![Element::Cloud]
+    } else {
+        vec![Element::Weather]
+    }
+}
+
+/// A variable wind direction range such as `180V240`.
+fn is_wind_range(element: &str) -> bool {
+    element.len() == 7
+        && element.as_bytes()[3] == b'V'
+        && element
+            .chars()
+            .enumerate()
+            .all(|(i, c)| i == 3 || c.is_ascii_digit())
+}
+
+/// A `DDHH/DDHH` period.
+fn period(token: &str, reference: DateTime<Utc>) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let (from, to) = token.split_once('/')?;
+    let from = day_time(from, reference)?;
+    let to = day_time(to, reference)?;
+    // A period that crosses the end of the month
+    let to = if to < from {
+        to.checked_add_months(Months::new(1))?
+    } else {
+        to
+    };
+    Some((from, to))
+}
+
+/// A `DDHH` or `DDHHMM` time in the month that puts it closest to
+/// `reference`. Hour 24 is midnight at the end of the day.
+fn day_time(token: &str, reference: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if !matches!(token.len(), 4 | 6) || !token.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let number = |range: std::ops::Range<usize>| token.get(range)?.parse::<u32>().ok();
+    let (day, hour) = (number(0..2)?, number(2..4)?);
+    let minute = if token.len() == 6 { number(4..6)? } else { 0 };
+    if hour > 24 || minute > 59 {
+        return None;
+    }
+
+    let this_month = NaiveDate::from_ymd_opt(reference.year(), reference.month(), 1)?;
+    [
+        this_month.checked_sub_months(Months::new(1)),
+        Some(this_month),
+        this_month.checked_add_months(Months::new(1)),
+    ]
+    .into_iter()
+    .flatten()
+    .filter_map(|month| month.with_day(day))
+    .filter_map(|date| {
+        let midnight = date.and_hms_opt(0, 0, 0)?.and_utc();
+        Some(midnight + Duration::hours(hour.into()) + Duration::minutes(minute.into()))
+    })
+    .min_by_key(|at| (*at - reference).num_seconds().abs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn utc(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 7, day, hour, minute, 0).unwrap()
+    }
+
+    const JFK: &str = "TAF AMD KJFK 121130Z 1212/1318 20012KT P6SM BKN030
+          FM121800 22015G25KT P6SM SCT040
+            TEMPO 1220/1224 3SM -SHRA BKN020
+          FM130200 VRB03KT 5SM BR OVC008
+            BECMG 1306/1308 27010KT P6SM NSW SCT015
+            PROB30 TEMPO 1310/1314 2SM TSRA OVC010CB=";
+
+    fn jfk() -> Taf {
+        Taf::parse(JFK, utc(12, 12, 0)).unwrap()
+    }
+
+    #[test]
+    fn test_parse_groups() {
+        let taf = jfk();
+        assert_eq!(taf.station, "KJFK");
+        assert_eq!(
+            (taf.valid_from, taf.valid_to),
+            (utc(12, 12, 0), utc(13, 18, 0))
+        );
+
+        let changes: Vec<Change> = taf.groups.iter().map(|g| g.change).collect();
+        assert_eq!(
+            changes,
+            [
+                Change::Base,
+                Change::From,
+                Change::Temporary,
+                Change::From,
+                Change::Becoming,
+                Change::Probability(30),
+            ]
+        );
+        // FM groups run until the next one, the last until the TAF ends
+        assert_eq!(
+            (taf.groups[0].from, taf.groups[0].to),
+            (utc(12, 12, 0), utc(12, 18, 0))
+        );
+        assert_eq!(
+            (taf.groups[1].from, taf.groups[1].to),
+            (utc(12, 18, 0), utc(13, 2, 0))
+        );
+        assert_eq!(taf.groups[3].to, utc(13, 18, 0));
+        // Hour 24 is the following midnight
+        assert_eq!(taf.groups[2].to, utc(13, 0, 0));
+        assert_eq!(taf.groups[5].elements, ["2SM", "TSRA", "OVC010CB"]);
+    }
+
+    #[test]
+    fn test_forecast_follows_fm_groups() {
+        let taf = jfk();
+        let forecast = taf.forecast_at(utc(12, 14, 0));
+        assert_eq!(forecast.conditions, ["20012KT", "P6SM", "BKN030"]);
+        assert!(forecast.variations.is_empty());
+
+        // Exactly at FM time the new conditions apply
+        assert_eq!(taf.forecast_at(utc(12, 18, 0)).conditions[0], "22015G25KT");
+    }
+
+    #[test]
+    fn test_forecast_lists_tempo_and_prob() {
+        let taf = jfk();
+        let eta = utc(12, 21, 15);
+        let forecast = taf.forecast_at(eta);
+        assert_eq!(forecast.variations, ["TEMPO 3SM -SHRA BKN020"]);
+        assert_eq!(
+            forecast.summary(eta, &taf),
+            "At ETA 21:15Z: 22015G25KT P6SM SCT040, TEMPO 3SM -SHRA BKN020"
+        );
+
+        let forecast = taf.forecast_at(utc(13, 11, 0));
+        assert_eq!(forecast.variations, ["PROB30 2SM TSRA OVC010CB"]);
+    }
+
+    #[test]
+    fn test_becmg_replaces_what_it_mentions_once_complete() {
+        let taf = jfk();
+        // During the change both are possible
+        let during = taf.forecast_at(utc(13, 7, 0));
+        assert_eq!(during.conditions, ["VRB03KT", "5SM", "BR", "OVC008"]);
+        assert_eq!(during.variations, ["BECMG 27010KT P6SM NSW SCT015"]);
+
+        let after = taf.forecast_at(utc(13, 9, 0));
+        assert_eq!(after.conditions, ["27010KT", "P6SM", "NSW", "SCT015"]);
+    }
+
+    #[test]
+    fn test_becmg_cavok_and_partial_changes() {
+        let now = utc(14, 5, 0);
+        let taf = Taf::parse(
+            "TAF EGLL 140500Z 1406/1512 24010KT 6000 -RA BKN012 \
+             BECMG 1408/1410 CAVOK BECMG 1412/1414 BKN040 BECMG 1416/1418 30015KT TX22/1414Z",
+            now,
+        )
+        .unwrap();
+
+        assert_eq!(
+            taf.forecast_at(utc(14, 11, 0)).conditions,
+            ["24010KT", "CAVOK"]
+        );
+        // New cloud ends CAVOK; the wind stays until it is changed
+        assert_eq!(
+            taf.forecast_at(utc(14, 15, 0)).conditions,
+            ["24010KT", "BKN040"]
+        );
+        assert_eq!(
+            taf.forecast_at(utc(14, 19, 0)).conditions,
+            ["30015KT", "BKN040"]
+        );
+    }
+
+    #[test]
+    fn test_eta_outside_validity_uses_nearest_period() {
+        let taf = jfk();
+        let late = utc(13, 20, 30);
+        let forecast = taf.forecast_at(late);
+        assert_eq!(forecast.coverage, Coverage::After);
+        assert_eq!(
+            forecast.summary(late, &taf),
+            "At ETA 20:30Z (after the TAF ends 13 18:00Z, its last period): \
+             27010KT P6SM NSW SCT015"
+        );
+
+        let early = utc(12, 9, 0);
+        let forecast = taf.forecast_at(early);
+        assert_eq!(forecast.coverage, Coverage::Before);
+        assert_eq!(forecast.conditions, ["20012KT", "P6SM", "BKN030"]);
+        assert!(forecast
+            .summary(early, &taf)
+            .contains("(before the TAF starts 12 12:00Z)"));
+    }
+
+    #[test]
+    fn test_periods_across_month_end() {
+        // Issued on the 31st, running into the 1st of the next month
+        let now = Utc.with_ymd_and_hms(2024, 7, 31, 17, 0, 0).unwrap();
+        let taf = Taf::parse("EDDF 311700Z 3118/0124 18005KT 9999 FEW035", now).unwrap();
+        assert_eq!(
+            taf.valid_from,
+            Utc.with_ymd_and_hms(2024, 7, 31, 18, 0, 0).unwrap()
+        );
+        assert_eq!(
+            taf.valid_to,
+            Utc.with_ymd_and_hms(2024, 8, 2, 0, 0, 0).unwrap()
+        );
+
+        // Fetched just after midnight on the 1st, a TAF from the 31st
+        let now = Utc.with_ymd_and_hms(2024, 8, 1, 0, 30, 0).unwrap();
+        let taf = Taf::parse("EDDF 311700Z 3118/0124 18005KT 9999 FEW035", now).unwrap();
+        assert_eq!(taf.valid_from.month(), 7);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let now = utc(12, 12, 0);
+        assert!(Taf::parse("", now).is_err());
+        assert!(Taf::parse("TAF KJFK 121130Z NIL=", now).is_err());
+        assert!(Taf::parse("TAF KJFK 121130Z 1212/1318 CNL", now).is_err());
+        assert!(Taf::parse("TAF KJFK 121130Z 20012KT P6SM", now).is_err());
+        // Remarks are dropped
+        let taf = Taf::parse(
+            "TAF KJFK 1212/1318 20012KT P6SM SKC RMK NXT FCST BY 18Z",
+            now,
+        );
+        assert_eq!(taf.unwrap().groups[0].elements, ["20012KT", "P6SM", "SKC"]);
+    }
+}
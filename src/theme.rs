@@ -0,0 +1,220 @@
+//! Colors that stay readable on both dark and light terminal backgrounds.
+//!
+//! Most of the UI uses the terminal's own palette. The colors that only
+//! work against one kind of background, dim text, the selected row and
+//! bright text, come from a [`Theme`]. With `background = "auto"` the
+//! terminal is asked for its background color (OSC 11) before the UI
+//! starts, then `COLORFGBG` is consulted, then dark is assumed. The query
+//! gives up after [`QUERY_TIMEOUT`], so a terminal that never answers only
+//! delays startup by that much.
+
+use std::time::Duration;
+
+use flight_tracker_tui::config::Background;
+use ratatui::style::Color;
+
+/// How long to wait for the terminal to report its background.
+const QUERY_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Colors that depend on the terminal background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// Secondary text: hints, labels, separators.
+    pub dim: Color,
+    /// Background of the selected list row.
+    pub selection: Color,
+    /// Text that should stand out, like flight numbers.
+    pub bright: Color,
+}
+
+impl Theme {
+    pub const DARK: Theme = Theme {
+        dim: Color::DarkGray,
+        selection: Color::DarkGray,
+        bright: Color::White,
+    };
+
+    /// From the 256-color grays, as the named grays are all too close to
+    /// white or black in common palettes.
+    pub const LIGHT: Theme = Theme {
+        dim: Color::Indexed(243),
+        selection: Color::Indexed(252),
+        bright: Color::Black,
+    };
+
+    /// The theme for the configured background, asking the terminal when
+    /// it is `auto`. Must run before the terminal is put in raw mode.
+    pub fn load(background: Background) -> Self {
+        let light = match background {
+            Background::Dark => false,
+            Background::Light => true,
+            Background::Auto => detect_light(),
+        };
+        if light {
+            Self::LIGHT
+        } else {
+            Self::DARK
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::DARK
+    }
+}
+
+/// Whether the terminal background looks light, dark when unsure.
+fn detect_light() -> bool {
+    let light = query_background(QUERY_TIMEOUT)
+        .and_then(|reply| parse_osc11(&reply))
+        .map(|rgb| luminance(rgb) > 0.5)
+        .or_else(|| {
+            let value = std::env::var("COLORFGBG").ok()?;
+            colorfgbg_is_light(&value)
+        });
+    tracing::debug!(?light, "Detected terminal background");
+    light.unwrap_or(false)
+}
+
+/// Ask the terminal for its background color and return whatever it
+/// answers within `timeout`. A Primary Device Attributes request follows
+/// the query; nearly every terminal answers that one, and in order, so a
+/// terminal that ignores OSC 11 is found out without sitting out the
+/// timeout.
+#[cfg(unix)]
+fn query_background(timeout: Duration) -> Option<Vec<u8>> {
+    use std::fs::OpenOptions;
+    use std::io::{Read, Write};
+    use std::os::fd::AsRawFd;
+    use std::time::Instant;
+
+    let mut tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    // Without raw mode the reply would be echoed and line-buffered
+    crossterm::terminal::enable_raw_mode().ok()?;
+    let mut reply = Vec::new();
+    if tty
+        .write_all(b"\x1b]11;?\x1b\\\x1b[c")
+        .and_then(|_| tty.flush())
+        .is_ok()
+    {
+        let deadline = Instant::now() + timeout;
+        let mut pollfd = libc::pollfd {
+            fd: tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        while !has_device_attributes(&reply) {
+            let left = deadline.saturating_duration_since(Instant::now());
+            // SAFETY: a single pollfd for a descriptor open throughout
+            let ready = unsafe { libc::poll(&mut pollfd, 1, left.as_millis() as libc::c_int) };
+            if ready <= 0 {
+                break;
+            }
+            let mut buf = [0u8; 64];
+            match tty.read(&mut buf) {
+                Ok(n) if n > 0 => reply.extend_from_slice(&buf[..n]),
+                _ => break,
+            }
+        }
+    }
+    let _ = crossterm::terminal::disable_raw_mode();
+    Some(reply)
+}
+
+#[cfg(not(unix))]
+fn query_background(_timeout: Duration) -> Option<Vec<u8>> {
+    None
+}
+
+/// Whether the reply so far ends in the answer to the device attributes
+/// request, `ESC [ ? ... c`.
+fn has_device_attributes(reply: &[u8]) -> bool {
+    reply
+        .windows(3)
+        .position(|w| w == b"\x1b[?")
+        .is_some_and(|start| reply[start..].contains(&b'c'))
+}
+
+/// The color in an OSC 11 reply (`ESC ] 11 ; rgb:RRRR/GGGG/BBBB`, ended by
+/// BEL or ST), each channel scaled to 0..=1.
+fn parse_osc11(reply: &[u8]) -> Option<(f64, f64, f64)> {
+    const PREFIX: &str = "]11;rgb:";
+    let reply = String::from_utf8_lossy(reply);
+    let spec = &reply[reply.find(PREFIX)? + PREFIX.len()..];
+    let spec = &spec[..spec.find(['\x07', '\x1b'])?];
+    let mut channels = spec.split('/').map(channel);
+    let rgb = (channels.next()??, channels.next()??, channels.next()??);
+    channels.next().is_none().then_some(rgb)
+}
+
+/// A channel of one to four hex digits, scaled to 0..=1.
+fn channel(hex: &str) -> Option<f64> {
+    if hex.is_empty() || hex.len() > 4 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let max = (1u32 << (4 * hex.len())) - 1;
+    Some(f64::from(u32::from_str_radix(hex, 16).ok()?) / f64::from(max))
+}
+
+/// Perceived brightness of a color, from 0 (black) to 1 (white).
+fn luminance((r, g, b): (f64, f64, f64)) -> f64 {
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+/// Whether `COLORFGBG` (`fg;bg` as ANSI color numbers, set by rxvt,
+/// Konsole and others) names a light background.
+fn colorfgbg_is_light(value: &str) -> Option<bool> {
+    let background: u8 = value.rsplit(';').next()?.trim().parse().ok()?;
+    Some(matches!(background, 7 | 9..=15))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_osc11() {
+        // xterm ends with ST, others with BEL; the device attributes follow
+        let white = parse_osc11(b"\x1b]11;rgb:ffff/ffff/ffff\x1b\\\x1b[?62;22c").unwrap();
+        assert_eq!(white, (1.0, 1.0, 1.0));
+        let (r, g, b) = parse_osc11(b"\x1b]11;rgb:28/2c/34\x07").unwrap();
+        assert!((r - 40.0 / 255.0).abs() < 1e-9 && g > r && b > g);
+        assert!(luminance((r, g, b)) < 0.5);
+        assert!(luminance(parse_osc11(b"\x1b]11;rgb:fdf6/f6e3/e3e3\x07").unwrap()) > 0.5);
+
+        // Only the device attributes: OSC 11 isn't supported
+        assert_eq!(parse_osc11(b"\x1b[?1;2c"), None);
+        assert_eq!(parse_osc11(b"\x1b]11;rgb:ffff/ffff\x07"), None);
+        assert_eq!(parse_osc11(b"\x1b]11;rgb:ffff/ffff/ffff"), None);
+        assert_eq!(parse_osc11(b"\x1b]11;rgb:fffff/0/0\x07"), None);
+        assert_eq!(parse_osc11(b"\x1b]11;rgb:+f/0/0\x07"), None);
+    }
+
+    #[test]
+    fn test_device_attributes_end_the_reply() {
+        assert!(!has_device_attributes(b""));
+        assert!(!has_device_attributes(b"\x1b]11;rgb:cccc/cccc/cccc\x1b\\"));
+        assert!(!has_device_attributes(b"\x1b]11;rgb:0/0/0\x07\x1b[?62;"));
+        assert!(has_device_attributes(b"\x1b]11;rgb:0/0/0\x07\x1b[?62;22c"));
+    }
+
+    #[test]
+    fn test_colorfgbg() {
+        assert_eq!(colorfgbg_is_light("15;0"), Some(false));
+        assert_eq!(colorfgbg_is_light("0;15"), Some(true));
+        assert_eq!(colorfgbg_is_light("0;default;7"), Some(true));
+        assert_eq!(colorfgbg_is_light("15;8"), Some(false));
+        assert_eq!(colorfgbg_is_light("default;default"), None);
+    }
+
+    #[test]
+    fn test_explicit_background_skips_the_query() {
+        assert_eq!(Theme::load(Background::Light), Theme::LIGHT);
+        assert_eq!(Theme::load(Background::Dark), Theme::DARK);
+    }
+}
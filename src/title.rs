@@ -0,0 +1,153 @@
+//! The terminal window title, kept on a one-line summary of the followed
+//! flight so it can be read from a tab or taskbar while the terminal is
+//! buried.
+
+use std::time::{Duration, Instant};
+
+use crossterm::terminal::SetTitle;
+
+use flight_tracker_tui::config::TimeFormat;
+use flight_tracker_tui::flight::Flight;
+use flight_tracker_tui::glyphs;
+
+/// What every title starts with, so the window can be told apart.
+const PREFIX: &str = "FT";
+/// Longest title written; tabs and taskbars show little more.
+pub const MAX_CHARS: usize = 40;
+/// Shortest time between two writes, however often the flight changes.
+const MIN_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The title for `flight`, e.g. "FT: UA123 SFO→JFK ✈ ETA 18:42", at most
+/// `max_chars` long. The flight's summary drops its least important facts
+/// to fit, and with `ascii_only` each glyph becomes one ASCII character so
+/// the length holds. Without a flight it is just the prefix.
+pub fn format_title(
+    flight: Option<&Flight>,
+    clock: TimeFormat,
+    ascii_only: bool,
+    max_chars: usize,
+) -> String {
+    let Some(flight) = flight else {
+        return PREFIX.to_string();
+    };
+    let width = max_chars.saturating_sub(PREFIX.len() + 2);
+    let title = format!("{}: {}", PREFIX, flight.summary(clock, width));
+    // Nothing from the providers may end the escape sequence early
+    let title = title.chars().filter(|c| !c.is_control());
+    if ascii_only {
+        title.map(glyphs::cell).collect()
+    } else {
+        title.collect()
+    }
+}
+
+/// Writes the title when it changes, at most once per [`MIN_INTERVAL`].
+#[derive(Debug, Default)]
+pub struct TerminalTitle {
+    /// Title on the terminal and when it was written, if any
+    written: Option<(String, Instant)>,
+}
+
+impl TerminalTitle {
+    /// Whether `title` should be written at `now`: it differs from the one
+    /// on the terminal, and the last write was long enough ago.
+    fn due(&self, title: &str, now: Instant) -> bool {
+        match &self.written {
+            Some((current, _)) if current == title => false,
+            Some((_, at)) => now.duration_since(*at) >= MIN_INTERVAL,
+            None => true,
+        }
+    }
+
+    /// Show `title`, unless it is unchanged or the last write was too
+    /// recent; a change held back goes out on a later call.
+    pub fn update(&mut self, title: String, now: Instant) {
+        if !self.due(&title, now) {
+            return;
+        }
+        // Through crossterm, so the escape goes out whole between frames
+        let _ = crossterm::execute!(std::io::stdout(), SetTitle(&title));
+        self.written = Some((title, now));
+    }
+
+    /// Blank the title again, if one was written.
+    pub fn clear(&mut self) {
+        if self.written.take().is_some() {
+            let _ = crossterm::execute!(std::io::stdout(), SetTitle(""));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use flight_tracker_tui::flight::FlightStatus;
+
+    fn cruising() -> Flight {
+        Flight {
+            arrival_scheduled: chrono::DateTime::parse_from_rfc3339("2099-07-14T18:42:00-04:00")
+                .ok(),
+            ..fixtures::cruising()
+        }
+    }
+
+    #[test]
+    fn test_format_title() {
+        let flight = cruising();
+        let clock = TimeFormat::TwentyFourHour;
+        let percent = flight.route_progress().unwrap().percent();
+
+        assert_eq!(
+            format_title(Some(&flight), clock, false, 80),
+            format!("FT: UA123 SFO→JFK ✈ FL360 480kt {}% ETA 18:42", percent)
+        );
+        // Shorter titles drop the summary's least important facts first
+        assert_eq!(
+            format_title(Some(&flight), clock, false, 30),
+            "FT: UA123 SFO→JFK ✈ ETA 18:42"
+        );
+        assert_eq!(
+            format_title(Some(&flight), clock, false, 20),
+            "FT: UA123 ETA 18:42"
+        );
+        assert_eq!(format_title(Some(&flight), clock, false, 8), "FT: UA1…");
+        for max in [0, 5, 12, 40] {
+            let title = format_title(Some(&flight), clock, false, max);
+            assert!(
+                title.chars().count() <= max.max(PREFIX.len() + 3),
+                "{title}"
+            );
+        }
+
+        assert_eq!(
+            format_title(Some(&flight), clock, true, 30),
+            "FT: UA123 SFO>JFK * ETA 18:42"
+        );
+        assert_eq!(format_title(None, clock, false, MAX_CHARS), "FT");
+    }
+
+    #[test]
+    fn test_format_title_drops_control_characters() {
+        let flight = Flight {
+            flight_number: "UA1\x07\x1b]0;x".to_string(),
+            status: FlightStatus::Scheduled,
+            ..Default::default()
+        };
+        let title = format_title(Some(&flight), TimeFormat::TwentyFourHour, false, MAX_CHARS);
+        assert_eq!(title, "FT: UA1]0;x Scheduled");
+    }
+
+    #[test]
+    fn test_writes_are_rate_limited() {
+        let start = Instant::now();
+        let mut title = TerminalTitle::default();
+        assert!(title.due("FT: UA123", start));
+        title.written = Some(("FT: UA123".to_string(), start));
+
+        // Unchanged never goes out; a change waits out the interval
+        assert!(!title.due("FT: UA123", start + MIN_INTERVAL * 2));
+        assert!(!title.due("FT: UA123 62%", start + Duration::from_secs(1)));
+        assert!(title.due("FT: UA123 62%", start + MIN_INTERVAL));
+    }
+}
@@ -0,0 +1,250 @@
+//! Keeping the JSONL track recordings from filling the disk.
+//!
+//! Each flight's recording is a file in the `tracks` directory under the
+//! data directory. Once it grows past [`Retention::max_file_mb`],
+//! [`rotate_if_needed`] moves it aside to `<file>.1`, replacing the previous
+//! rotation, so a flight keeps at most two files. [`enforce_cap`] holds the
+//! whole directory under [`Retention::max_total_mb`] by deleting the least
+//! recently written files first, and [`sweep`] does both at startup.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::Deserialize;
+
+use crate::paths::data_dir;
+
+/// Directory under the data directory holding the recordings.
+pub const TRACKS_DIR: &str = "tracks";
+
+const MB: u64 = 1024 * 1024;
+
+/// How much recorded track is kept, from `[track_retention]` in the config.
+///
+/// ```
+/// use flight_tracker_tui::config::Config;
+///
+/// let config = Config::parse("[track_retention]\nmax_total_mb = 50").unwrap();
+/// assert_eq!(config.track_retention.max_total_mb, 50);
+/// assert_eq!(config.track_retention.max_file_mb, 10);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Retention {
+    /// Size at which a flight's file is rotated, in MiB.
+    pub max_file_mb: u64,
+    /// Size of the whole tracks directory, in MiB; 0 means no cap.
+    pub max_total_mb: u64,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            max_file_mb: 10,
+            max_total_mb: 200,
+        }
+    }
+}
+
+impl Retention {
+    pub fn max_file_bytes(&self) -> u64 {
+        self.max_file_mb * MB
+    }
+
+    pub fn max_total_bytes(&self) -> u64 {
+        self.max_total_mb * MB
+    }
+}
+
+/// The tracks directory, if there is a data directory.
+pub fn tracks_dir() -> Option<PathBuf> {
+    data_dir().map(|p| p.join(TRACKS_DIR))
+}
+
+/// Where a recording goes when rotated: `UA123.jsonl` becomes
+/// `UA123.jsonl.1`.
+pub fn rotated_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".1");
+    path.with_file_name(name)
+}
+
+/// Move the recording at `path` aside if it has reached `max_bytes`,
+/// replacing any earlier rotation. Returns the bytes that rotation dropped.
+/// A missing file, or a `max_bytes` of 0, is left alone.
+pub fn rotate_if_needed(path: &Path, max_bytes: u64) -> io::Result<u64> {
+    let size = match fs::metadata(path) {
+        Ok(meta) => meta.len(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    if max_bytes == 0 || size < max_bytes {
+        return Ok(0);
+    }
+    let rotated = rotated_path(path);
+    let dropped = fs::metadata(&rotated).map_or(0, |meta| meta.len());
+    fs::rename(path, &rotated)?;
+    Ok(dropped)
+}
+
+/// Delete the least recently written files in `dir` until what's left fits
+/// in `max_bytes`. Returns the bytes deleted; a missing directory, or a
+/// `max_bytes` of 0, deletes nothing.
+pub fn enforce_cap(dir: &Path, max_bytes: u64) -> io::Result<u64> {
+    if max_bytes == 0 {
+        return Ok(0);
+    }
+    let mut files = recordings(dir)?;
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    // Oldest first; rotated files sort before their current file on a tie
+    files.sort_by(|a, b| a.2.cmp(&b.2).then_with(|| b.0.cmp(&a.0)));
+    let mut reclaimed = 0;
+    for (path, size, _) in files {
+        if total <= max_bytes {
+            break;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => {
+                total -= size;
+                reclaimed += size;
+            }
+            Err(e) => tracing::warn!(path = %path.display(), error = %e, "Could not delete track"),
+        }
+    }
+    Ok(reclaimed)
+}
+
+/// Rotate every oversized recording in `dir`, then enforce the total cap.
+/// Returns the bytes reclaimed.
+pub fn sweep(dir: &Path, retention: &Retention) -> io::Result<u64> {
+    let mut reclaimed = 0;
+    for (path, _, _) in recordings(dir)? {
+        if path.extension().is_some_and(|ext| ext == "jsonl") {
+            reclaimed += rotate_if_needed(&path, retention.max_file_bytes())?;
+        }
+    }
+    Ok(reclaimed + enforce_cap(dir, retention.max_total_bytes())?)
+}
+
+/// Files in `dir` with their size and modification time. A missing
+/// directory has none.
+fn recordings(dir: &Path) -> io::Result<Vec<(PathBuf, u64, SystemTime)>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut files = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let meta = entry.metadata()?;
+        if meta.is_file() {
+            let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            files.push((entry.path(), meta.len(), modified));
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::Duration;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tracks-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A `size`-byte file written `age_secs` seconds ago.
+    fn fixture(dir: &Path, name: &str, size: usize, age_secs: u64) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, vec![b'x'; size]).unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(age_secs);
+        File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        path
+    }
+
+    fn names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_rotation_keeps_one_previous_file() {
+        let dir = temp_dir("rotate");
+        let path = fixture(&dir, "UA123.jsonl", 50, 0);
+        assert_eq!(rotated_path(&path), dir.join("UA123.jsonl.1"));
+
+        // Under the limit, nothing moves
+        assert_eq!(rotate_if_needed(&path, 100).unwrap(), 0);
+        assert_eq!(names(&dir), ["UA123.jsonl"]);
+
+        assert_eq!(rotate_if_needed(&path, 50).unwrap(), 0);
+        assert_eq!(names(&dir), ["UA123.jsonl.1"]);
+
+        // A second rotation drops the first
+        fixture(&dir, "UA123.jsonl", 80, 0);
+        assert_eq!(rotate_if_needed(&path, 50).unwrap(), 50);
+        assert_eq!(names(&dir), ["UA123.jsonl.1"]);
+        assert_eq!(fs::metadata(rotated_path(&path)).unwrap().len(), 80);
+
+        assert_eq!(rotate_if_needed(&dir.join("BA285.jsonl"), 50).unwrap(), 0);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_cap_deletes_oldest_first() {
+        let dir = temp_dir("cap");
+        fixture(&dir, "UA123.jsonl.1", 400, 300);
+        fixture(&dir, "BA285.jsonl", 300, 200);
+        fixture(&dir, "UA123.jsonl", 200, 100);
+        fixture(&dir, "LH400.jsonl", 100, 0);
+
+        assert_eq!(enforce_cap(&dir, 1000).unwrap(), 0);
+        assert_eq!(enforce_cap(&dir, 0).unwrap(), 0);
+        assert_eq!(names(&dir).len(), 4);
+
+        // 1000 bytes down to 350 takes the two oldest
+        assert_eq!(enforce_cap(&dir, 350).unwrap(), 700);
+        assert_eq!(names(&dir), ["LH400.jsonl", "UA123.jsonl"]);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_sweep_rotates_then_caps() {
+        let dir = temp_dir("sweep");
+        let retention = Retention {
+            max_file_mb: 1,
+            max_total_mb: 2,
+        };
+        let mb = MB as usize;
+        fixture(&dir, "UA123.jsonl.1", mb / 2, 300);
+        fixture(&dir, "UA123.jsonl", mb + 1, 200);
+        fixture(&dir, "BA285.jsonl", mb / 2, 100);
+        fixture(&dir, "LH400.jsonl", mb / 2, 0);
+
+        // Rotating UA123 drops its old rotation, then the cap takes the
+        // oldest remaining file, the one just rotated
+        let reclaimed = sweep(&dir, &retention).unwrap();
+        assert_eq!(reclaimed, (mb / 2 + mb + 1) as u64);
+        assert_eq!(names(&dir), ["BA285.jsonl", "LH400.jsonl"]);
+
+        assert_eq!(sweep(&dir.join("missing"), &retention).unwrap(), 0);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
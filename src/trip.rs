@@ -0,0 +1,179 @@
+//! Trips: named groups of flights flown one after another, such as the legs
+//! of one booking.
+//!
+//! A [`Trip`] only holds flight numbers. The flights themselves stay in the
+//! tracked list, and the trip is worked out against it each time, so a leg
+//! that is removed or not found yet simply drops out of the group.
+
+use serde::{Deserialize, Serialize};
+
+use crate::flight::{Flight, FlightStatus};
+use crate::import::normalize_flight_number;
+
+/// An ordered group of flight numbers shown together in the list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Trip {
+    pub name: String,
+    /// Flight numbers in the order they are flown
+    pub legs: Vec<String>,
+}
+
+impl Trip {
+    pub fn new(name: impl Into<String>, legs: Vec<String>) -> Self {
+        Self {
+            name: name.into(),
+            legs,
+        }
+    }
+
+    /// The name as shown, with a dash between two places read as a route.
+    ///
+    /// ```
+    /// use flight_tracker_tui::trip::Trip;
+    ///
+    /// assert_eq!(Trip::new("NYC-TYO", vec![]).label(), "NYC→TYO");
+    /// assert_eq!(Trip::new("Honeymoon", vec![]).label(), "Honeymoon");
+    /// ```
+    pub fn label(&self) -> String {
+        match self.name.split_once('-') {
+            Some((from, to)) if !from.is_empty() && !to.is_empty() => format!("{}→{}", from, to),
+            _ => self.name.clone(),
+        }
+    }
+
+    /// Whether `flight_number` is one of the legs, however many leading
+    /// zeros either was written with.
+    pub fn contains(&self, flight_number: &str) -> bool {
+        let flight_number = normalize_flight_number(flight_number);
+        self.legs
+            .iter()
+            .any(|leg| normalize_flight_number(leg) == flight_number)
+    }
+
+    /// Indices into `flights` of the legs being tracked, in leg order.
+    pub fn tracked_legs(&self, flights: &[Flight]) -> Vec<usize> {
+        self.legs
+            .iter()
+            .map(|leg| normalize_flight_number(leg))
+            .filter_map(|leg| {
+                flights
+                    .iter()
+                    .position(|f| normalize_flight_number(&f.flight_number) == leg)
+            })
+            .collect()
+    }
+
+    /// Index into `flights` of the leg under way or next: the first tracked
+    /// one that hasn't landed. `None` once every leg has.
+    pub fn current_leg(&self, flights: &[Flight]) -> Option<usize> {
+        self.tracked_legs(flights)
+            .into_iter()
+            .find(|&i| !has_landed(&flights[i]))
+    }
+
+    /// How many tracked legs have landed.
+    pub fn legs_done(&self, flights: &[Flight]) -> usize {
+        self.tracked_legs(flights)
+            .into_iter()
+            .filter(|&i| has_landed(&flights[i]))
+            .count()
+    }
+
+    /// The group's summary line after its name, driven by the current leg,
+    /// e.g. "leg 2/3 NH6 En Route, 1 done".
+    pub fn summary(&self, flights: &[Flight]) -> String {
+        let legs = self.tracked_legs(flights);
+        let done = self.legs_done(flights);
+        let Some(current) = self.current_leg(flights) else {
+            return match legs.len() {
+                0 => "no legs tracked".to_string(),
+                n => format!("all {} legs landed", n),
+            };
+        };
+        let flight = &flights[current];
+        let position = legs.iter().position(|&i| i == current).unwrap_or_default();
+        let mut summary = format!(
+            "leg {}/{} {} {}",
+            position + 1,
+            legs.len(),
+            flight.flight_number,
+            flight.status
+        );
+        if done > 0 {
+            summary.push_str(&format!(", {} done", done));
+        }
+        summary
+    }
+}
+
+/// Whether a leg is over, as far as the trip is concerned.
+fn has_landed(flight: &Flight) -> bool {
+    flight.status == FlightStatus::Landed || flight.arrived_at.is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leg(flight_number: &str, status: FlightStatus) -> Flight {
+        Flight {
+            flight_number: flight_number.to_string(),
+            status,
+            ..Default::default()
+        }
+    }
+
+    fn nyc_tyo() -> Trip {
+        let legs = ["UA79", "NH6", "NH2175"].map(String::from).to_vec();
+        Trip::new("NYC-TYO", legs)
+    }
+
+    #[test]
+    fn test_current_leg_is_the_first_not_landed() {
+        let trip = nyc_tyo();
+        // Tracked in a different order than flown, with an unrelated flight
+        let mut flights = vec![
+            leg("NH6", FlightStatus::Scheduled),
+            leg("BA285", FlightStatus::EnRoute),
+            leg("NH2175", FlightStatus::Scheduled),
+            leg("UA79", FlightStatus::EnRoute),
+        ];
+        assert_eq!(trip.tracked_legs(&flights), [3, 0, 2]);
+        assert_eq!(trip.current_leg(&flights), Some(3));
+        assert_eq!(trip.summary(&flights), "leg 1/3 UA79 En Route");
+
+        flights[3].status = FlightStatus::Landed;
+        flights[0].status = FlightStatus::EnRoute;
+        assert_eq!(trip.current_leg(&flights), Some(0));
+        assert_eq!(trip.summary(&flights), "leg 2/3 NH6 En Route, 1 done");
+
+        // Arrived counts as landed whatever the last status was
+        flights[0].arrived_at = Some(chrono::Utc::now());
+        flights[2].status = FlightStatus::Landed;
+        assert_eq!(trip.current_leg(&flights), None);
+        assert_eq!(trip.summary(&flights), "all 3 legs landed");
+    }
+
+    #[test]
+    fn test_untracked_legs_drop_out() {
+        let trip = nyc_tyo();
+        let flights = vec![leg("NH2175", FlightStatus::Scheduled)];
+        assert_eq!(trip.summary(&flights), "leg 1/1 NH2175 Scheduled");
+        assert_eq!(trip.summary(&[]), "no legs tracked");
+        assert!(trip.contains("NH6") && !trip.contains("NH"));
+    }
+
+    #[test]
+    fn test_legs_match_however_they_were_written() {
+        let trip = Trip::new("NYC-TYO", vec!["UA79".to_string(), "NH006".to_string()]);
+        assert!(trip.contains("NH6") && trip.contains("nh0006") && trip.contains("NH006"));
+        assert!(!trip.contains("NH60"));
+
+        let flights = vec![
+            leg("NH6", FlightStatus::Scheduled),
+            leg("ua079", FlightStatus::Landed),
+        ];
+        assert_eq!(trip.tracked_legs(&flights), [1, 0]);
+        assert_eq!(trip.summary(&flights), "leg 2/2 NH6 Scheduled, 1 done");
+    }
+}
@@ -1,15 +1,81 @@
+use std::time::Instant;
+
+use chrono::{DateTime, Utc};
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
+    symbols::Marker,
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+    widgets::{
+        canvas::{Canvas, Map, MapResolution, Points},
+        Axis, Block, Borders, Chart, Clear, Dataset, GraphType, List, ListItem, Paragraph, Row,
+        Table, Tabs, Wrap,
+    },
     Frame,
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::app::{
+    App, AppMode, BulkRemoval, Diagnostics, FlightCounts, ListFilter, ProviderHealth,
+    RestoreSummary, RightPanel, ENV_FILE_HINT,
+};
+use crate::budget::BudgetPlan;
+use crate::config::ScreenLayout;
+use crate::flight::{
+    self, format_duration, Airport, EtaBand, Flight, FlightPhase, FlightStatus, JourneyProgress,
+    PositionSource, Reading, RouteProgress, ScheduleField, TrackPoint,
+};
+use crate::geo::{self, BoundingBox};
+use crate::glyphs;
+use crate::onboarding;
+use crate::palette;
+use crate::report::Column;
+use crate::settings;
+use crate::squawk::{self, SquawkKind};
+use crate::taf::{Change, Taf};
+use crate::theme::Theme;
+use crate::trip::Trip;
 
-use crate::app::{App, AppMode};
-use crate::flight::{Flight, FlightStatus};
+/// Smallest terminal the full layout is drawn in; anything smaller gets a
+/// message asking for more room.
+const MIN_WIDTH: u16 = 60;
+const MIN_HEIGHT: u16 = 16;
 
 pub fn draw(frame: &mut Frame, app: &App) {
+    draw_screen(frame, app);
+    if app.config.ascii_only {
+        to_ascii_cells(frame.buffer_mut());
+    }
+}
+
+/// Swap every non-ASCII character on screen for the ASCII one standing in
+/// for it, cell for cell, so columns stay where they were.
+fn to_ascii_cells(buffer: &mut Buffer) {
+    for cell in &mut buffer.content {
+        if !cell.symbol().is_ascii() {
+            let c = cell.symbol().chars().next().map_or(' ', glyphs::cell);
+            cell.set_char(c);
+        }
+    }
+}
+
+fn draw_screen(frame: &mut Frame, app: &App) {
+    if app.accessible {
+        draw_accessible(frame, app);
+        return;
+    }
+    if app.config.layout == ScreenLayout::Ticker {
+        draw_ticker(frame, app);
+        draw_overlays(frame, app);
+        return;
+    }
+    let area = frame.area();
+    if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+        draw_too_small(frame, area);
+        return;
+    }
+
     let main_chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -27,437 +93,3262 @@ pub fn draw(frame: &mut Frame, app: &App) {
         .split(main_chunks[1]);
 
     draw_flight_list(frame, content_chunks[0], app);
-    draw_flight_details(frame, content_chunks[1], app);
+    let mut right = content_chunks[1];
+    if app.show_profile {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+            .split(right);
+        right = chunks[0];
+        draw_altitude_chart(frame, chunks[1], app);
+    }
+    let panel_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(right);
+    draw_panel_tabs(frame, panel_chunks[0], app);
+    let right = panel_chunks[1];
+    match (app.right_panel, app.pinned_flight()) {
+        (RightPanel::Map, _) => draw_map_overview(frame, right, app),
+        (RightPanel::Log, Some(flight)) => draw_flight_log(frame, right, app, flight),
+        (RightPanel::Weather, Some(flight)) => draw_weather(frame, right, app, flight),
+        (RightPanel::Raw, Some(flight)) => {
+            draw_raw_payloads(frame, right, app, flight, app.panel_scroll);
+        }
+        // With no flight to show, the details pane says how to add one
+        _ => draw_flight_details(frame, right, app),
+    }
     draw_status_bar(frame, main_chunks[2], app);
+
+    draw_overlays(frame, app);
+    if app.mode == AppMode::Command {
+        draw_command_completions(frame, main_chunks[1], app);
+    }
 }
 
-fn draw_input(frame: &mut Frame, area: Rect, app: &App) {
-    let style = if app.mode == AppMode::Input {
-        Style::default().fg(Color::Yellow)
-    } else {
-        Style::default().fg(Color::DarkGray)
-    };
+/// The diagnostics and settings overlays, whichever are open.
+fn draw_overlays(frame: &mut Frame, app: &App) {
+    if let Some(diagnostics) = &app.diagnostics {
+        draw_diagnostics(frame, app, diagnostics);
+    }
+    if app.mode == AppMode::Settings {
+        draw_settings(frame, app);
+    }
+}
 
-    let title = if app.mode == AppMode::Input {
-        if app.history_index.is_some() {
-            " History (↑/↓ to browse) "
-        } else if !app.history.is_empty() {
-            " Enter Flight Number (↑ for history) "
-        } else {
-            " Enter Flight Number (e.g. UA123) "
-        }
+/// The ticker layout, for small panes: a line per flight and a status
+/// line, without borders. The input box only appears while something is
+/// being typed.
+fn draw_ticker(frame: &mut Frame, app: &App) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(area);
+    // While typing, the input box takes the top rows and the flights move
+    // under it, the selected one still in view
+    let editing = is_editing(app);
+    let mut list = chunks[0];
+    if editing {
+        let input = Rect::new(area.x, area.y, area.width, area.height.min(3));
+        list.y = input.bottom().min(list.bottom());
+        list.height = chunks[0].bottom() - list.y;
+        draw_input(frame, input, app);
+    }
+
+    let rows = ticker_rows(app);
+    let lines: Vec<Line> = if rows.is_empty() {
+        vec![Line::from(Span::styled(
+            "No flights tracked, / to add one",
+            Style::default().fg(app.theme.dim),
+        ))]
     } else {
-        " Press '/' to add flight "
+        rows.iter()
+            .map(|&i| ticker_line(app, i, usize::from(list.width)))
+            .collect()
     };
+    // Scrolled just enough to keep the selected flight on screen
+    let selected = rows.iter().position(|&i| Some(i) == app.selected_index);
+    let skip = selected.map_or(0, |row| (row + 1).saturating_sub(usize::from(list.height)));
+    frame.render_widget(Paragraph::new(lines).scroll((skip as u16, 0)), list);
+    frame.render_widget(Paragraph::new(status_line(app)), chunks[1]);
+    if editing && app.mode == AppMode::Command {
+        draw_command_completions(frame, list, app);
+    }
+}
 
-    let input = Paragraph::new(app.input_buffer.as_str())
-        .style(style)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(title)
-                .border_style(style),
-        );
-
-    frame.render_widget(input, area);
+/// The flights in the ticker, in list order: arrived ones last, and only
+/// while the Arrived section is open.
+fn ticker_rows(app: &App) -> Vec<usize> {
+    let arrived = app.arrived_indices();
+    let mut rows: Vec<usize> = app
+        .visible_indices()
+        .into_iter()
+        .filter(|i| !arrived.contains(i))
+        .collect();
+    if app.show_arrived {
+        rows.extend(arrived);
+    }
+    rows
+}
 
-    if app.mode == AppMode::Input {
-        frame.set_cursor_position((area.x + app.cursor_position as u16 + 1, area.y + 1));
+/// A flight's [`Flight::summary`] as a ticker row `width` columns wide.
+fn ticker_line(app: &App, i: usize, width: usize) -> Line<'static> {
+    let flight = &app.tracked_flights[i];
+    let mut style = if Some(i) == app.selected_index {
+        Style::default()
+            .bg(app.theme.selection)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    };
+    if flight.status == FlightStatus::NotFound {
+        style = style.add_modifier(Modifier::DIM);
     }
+    Line::from(flight.summary(app.config.time_format, width)).style(style)
 }
 
-fn draw_flight_list(frame: &mut Frame, area: Rect, app: &App) {
-    let items: Vec<ListItem> = app
-        .tracked_flights
+/// The `--accessible` layout: plain lines top to bottom, without borders,
+/// colors or a cursor, for screen readers and braille displays. The newest
+/// announcements stay on the bottom rows, up to a third of the screen.
+fn draw_accessible(frame: &mut Frame, app: &App) {
+    let area = frame.area();
+    let rows = app.announcements.len().min(usize::from(area.height / 3));
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(rows as u16)])
+        .split(area);
+
+    let body = Paragraph::new(accessible_lines(app)).wrap(Wrap { trim: true });
+    frame.render_widget(body, chunks[0]);
+    let announcements: Vec<Line> = app.announcements[app.announcements.len() - rows..]
         .iter()
-        .enumerate()
-        .map(|(i, flight)| {
-            let is_selected = Some(i) == app.selected_index;
+        .map(|a| Line::from(a.as_str()))
+        .collect();
+    frame.render_widget(Paragraph::new(announcements), chunks[1]);
+}
 
-            let status_color = status_to_color(&flight.status);
-            let prefix = if is_selected { "> " } else { "  " };
-
-            // Build route string
-            let route = match (&flight.origin, &flight.destination) {
-                (Some(orig), Some(dest)) => {
-                    let orig_code = orig.iata.as_deref().or(orig.icao.as_deref()).unwrap_or("???");
-                    let dest_code = dest.iata.as_deref().or(dest.icao.as_deref()).unwrap_or("???");
-                    format!(" {}→{}", orig_code, dest_code)
-                }
-                _ => String::new(),
+/// Everything in the accessible layout above the announcements, unstyled:
+/// the input line, the status, the flights, then the details pane or the
+/// overlay standing in for it.
+fn accessible_lines(app: &App) -> Vec<Line<'static>> {
+    let typed = if app.mode == AppMode::Viewing {
+        &app.input_draft
+    } else {
+        &app.input_buffer
+    };
+    let prompt = if typed.is_empty() {
+        input_title(app)
+    } else {
+        format!("{}: {}", input_title(app), typed)
+    };
+    let mut lines = vec![
+        Line::from(prompt),
+        status_line(app),
+        // Without the countdown, which would change every second
+        Line::from(dashboard_spans(
+            &app.theme,
+            app.flight_counts(),
+            None,
+            &app.provider_health,
+            app.config.aviationstack_monthly_limit,
+            usize::MAX,
+        )),
+        Line::from(""),
+    ];
+
+    for trip in &app.trips {
+        let rows = app.trip_rows(trip);
+        if !rows.is_empty() {
+            let summary = trip.summary(&app.tracked_flights);
+            let hidden = if app.is_trip_expanded(trip) {
+                ""
+            } else {
+                ", other legs hidden"
             };
+            lines.push(Line::from(format!(
+                "Trip {}: {}{}",
+                trip.label(),
+                summary,
+                hidden
+            )));
+            lines.extend(rows.into_iter().map(|i| accessible_flight_line(app, i)));
+        }
+    }
+    let arrived = app.arrived_indices();
+    for i in ungrouped_indices(app)
+        .into_iter()
+        .filter(|i| !arrived.contains(i))
+    {
+        lines.push(accessible_flight_line(app, i));
+    }
+    if !arrived.is_empty() {
+        let hidden = if app.show_arrived { "" } else { ", hidden" };
+        lines.push(Line::from(format!("Arrived: {}{}", arrived.len(), hidden)));
+        if app.show_arrived {
+            lines.extend(arrived.into_iter().map(|i| accessible_flight_line(app, i)));
+        }
+    }
+
+    lines.push(Line::from(""));
+    if let Some(diagnostics) = &app.diagnostics {
+        lines.extend(diagnostics_lines(app, diagnostics));
+    } else if app.mode == AppMode::Settings {
+        lines.extend(settings_lines(app));
+    } else if app.mode == AppMode::Command {
+        lines.extend(
+            command_completions(app)
+                .into_iter()
+                .take(MAX_COMPLETIONS)
+                .map(|c| Line::from(format!("{}: {}", command_usage(c), c.help))),
+        );
+    } else {
+        let scroll = usize::from(app.panel_scroll);
+        match (app.right_panel, app.pinned_flight()) {
+            (RightPanel::Log, Some(flight)) => {
+                lines.extend(log_lines(app, flight).into_iter().skip(scroll));
+            }
+            (RightPanel::Weather, Some(flight)) => lines.extend(weather_lines(app, flight)),
+            (RightPanel::Raw, Some(flight)) => {
+                let raw = raw_payload_lines(flight, &app.theme)
+                    .into_iter()
+                    .skip(scroll);
+                lines.extend(raw.map(|line| Line::from(line.to_string())));
+            }
+            // The map isn't drawn here, and screen readers get full names
+            _ => lines.extend(details_content(app, usize::MAX)),
+        }
+    }
+    lines
+        .into_iter()
+        .map(|line| Line::from(line.to_string()))
+        .collect()
+}
 
-            let line = Line::from(vec![
-                Span::raw(prefix),
-                Span::styled(&flight.flight_number, Style::default().fg(Color::White)),
-                Span::styled(route, Style::default().fg(Color::Cyan)),
-                Span::raw(" "),
-                Span::styled(format!("{}", flight.status), Style::default().fg(status_color)),
-            ]);
+/// A flight as one line of comma-separated facts, e.g. "UA123 SFO→LHR,
+/// En Route, 35000 ft, 15 min late, selected".
+fn accessible_flight_line(app: &App, i: usize) -> Line<'static> {
+    let flight = &app.tracked_flights[i];
+    let mut parts = vec![match flight.route_label() {
+        Some(route) => format!("{} {}", flight.flight_number, route),
+        None => flight.flight_number.clone(),
+    }];
+    parts.push(match flight.ground_airport() {
+        Some(airport) => format!("{} at {}", flight.status, airport.icao),
+        None => flight.status.to_string(),
+    });
+    if let Some(alt) = flight
+        .altitude_ft
+        .filter(|_| flight.status == FlightStatus::EnRoute)
+    {
+        parts.push(format!("{:.0} ft", alt));
+    }
+    if let Some(progress) = en_route_progress(flight) {
+        parts.push(format!("{}% flown", progress.percent()));
+    }
+    if let Some(delay) = flight.current_delay().filter(|d| *d > 0) {
+        parts.push(format!("{} min late", delay));
+    }
+    if flight.possible_diversion().is_some() {
+        parts.push("possible diversion".to_string());
+    }
+    if flight.alerts.iter().any(|a| a.armed) {
+        parts.push("alert armed".to_string());
+    }
+    if let Some(mute) = flight.muted_until.filter(|_| flight.is_muted(Utc::now())) {
+        parts.push(mute.to_string());
+    }
+    if app.following.as_deref() == Some(flight.flight_number.as_str()) {
+        parts.push("following".to_string());
+    }
+    if Some(i) == app.selected_index {
+        parts.push("selected".to_string());
+    }
+    Line::from(parts.join(", "))
+}
+
+/// Most completions listed under the palette's input line.
+const MAX_COMPLETIONS: usize = 6;
 
-            let style = if is_selected {
+/// The palette's best matches for what has been typed, best first, over the
+/// top left of the content area.
+fn draw_command_completions(frame: &mut Frame, content: Rect, app: &App) {
+    let matches = command_completions(app);
+    if matches.is_empty() {
+        return;
+    }
+    let items: Vec<ListItem> = matches
+        .iter()
+        .take(MAX_COMPLETIONS)
+        .enumerate()
+        .map(|(i, command)| {
+            let style = if i == 0 {
                 Style::default()
-                    .bg(Color::DarkGray)
+                    .fg(Color::Yellow)
                     .add_modifier(Modifier::BOLD)
             } else {
                 Style::default()
             };
-
-            ListItem::new(line).style(style)
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<32}", command_usage(command)), style),
+                Span::styled(command.help, Style::default().fg(app.theme.dim)),
+            ]))
         })
         .collect();
 
-    let list = List::new(items).block(
-        Block::default()
-            .borders(Borders::ALL)
-            .title(" Tracked Flights "),
+    let height = (items.len() as u16 + 2).min(content.height);
+    let area = Rect::new(content.x, content.y, content.width.min(80), height);
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        List::new(items).block(Block::default().borders(Borders::ALL)),
+        area,
     );
+}
 
-    frame.render_widget(list, area);
+/// Palette commands matching what has been typed, best first.
+fn command_completions(app: &App) -> Vec<&'static palette::Command> {
+    // Once an argument is being typed, keep showing the command it is for
+    let typed = app.input_buffer.trim_start();
+    let query = match typed.rsplit_once(' ') {
+        Some((head, _)) if palette::complete(typed).is_empty() => head,
+        _ => typed,
+    };
+    palette::complete(query)
 }
 
-fn status_to_color(status: &FlightStatus) -> Color {
-    match status {
-        FlightStatus::EnRoute => Color::Green,
-        FlightStatus::Scheduled => Color::Cyan,
-        FlightStatus::Landed => Color::Blue,
-        FlightStatus::OnGround => Color::Blue,
-        FlightStatus::Delayed => Color::Yellow,
-        FlightStatus::Cancelled => Color::Red,
-        FlightStatus::NotFound => Color::Red,
-        FlightStatus::Unknown => Color::DarkGray,
+/// A command's name with its argument placeholder, e.g. "alert <rule>".
+fn command_usage(command: &palette::Command) -> String {
+    match command.arg {
+        Some(arg) => format!("{} <{}>", command.name, arg),
+        None => command.name.to_string(),
     }
 }
 
-fn draw_flight_details(frame: &mut Frame, area: Rect, app: &App) {
-    let flight = app
-        .selected_index
-        .and_then(|i| app.tracked_flights.get(i));
+/// Centered note standing in for the layout until the terminal is resized.
+fn draw_too_small(frame: &mut Frame, area: Rect) {
+    let message = format!(
+        "terminal too small — need {}×{}, have {}×{}",
+        MIN_WIDTH, MIN_HEIGHT, area.width, area.height
+    );
+    // Wrapped lines, roughly, to center the message vertically
+    let lines = (message.chars().count() as u16).div_ceil(area.width.max(1));
+    let height = lines.min(area.height);
+    let message_area = Rect::new(
+        area.x,
+        area.y + (area.height - height) / 2,
+        area.width,
+        height,
+    );
+    let paragraph = Paragraph::new(message)
+        .style(Style::default().fg(Color::Yellow))
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+    frame.render_widget(paragraph, message_area);
+}
 
-    let content = match flight {
-        Some(f) => format_flight_details(f),
-        None => format_empty_state(app),
-    };
+/// Overlay answering "what is each provider doing": credentials, request
+/// counts, last success and failure, cache sizes, and the paths in use.
+fn draw_diagnostics(frame: &mut Frame, app: &App, diagnostics: &Diagnostics) {
+    let lines = diagnostics_lines(app, diagnostics);
+    let screen = frame.area();
+    let width = screen.width.saturating_sub(4).min(80);
+    let height = (lines.len() as u16 + 2).min(screen.height);
+    let area = Rect::new(
+        screen.x + (screen.width - width) / 2,
+        screen.y + (screen.height - height) / 2,
+        width,
+        height,
+    );
 
-    let details = Paragraph::new(content)
+    let overlay = Paragraph::new(lines)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .title(" Flight Details "),
+                .title(" Diagnostics "),
         )
-        .wrap(Wrap { trim: true });
-
-    frame.render_widget(details, area);
+        .wrap(Wrap { trim: false });
+    frame.render_widget(Clear, area);
+    frame.render_widget(overlay, area);
 }
 
-fn format_flight_details(flight: &Flight) -> Vec<Line<'static>> {
-    let mut lines = vec![];
+/// Overlay listing the settings that can be changed without editing
+/// `config.toml`, with the cursor's row highlighted.
+fn draw_settings(frame: &mut Frame, app: &App) {
+    let lines = settings_lines(app);
+    let screen = frame.area();
+    let width = screen.width.saturating_sub(4).min(64);
+    let height = (lines.len() as u16 + 2).min(screen.height);
+    let area = Rect::new(
+        screen.x + (screen.width - width) / 2,
+        screen.y + (screen.height - height) / 2,
+        width,
+        height,
+    );
+
+    let overlay =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(" Settings "));
+    frame.render_widget(Clear, area);
+    frame.render_widget(overlay, area);
+}
 
+fn settings_lines(app: &App) -> Vec<Line<'static>> {
+    let width = settings::SETTINGS
+        .iter()
+        .map(|s| s.label.len())
+        .max()
+        .unwrap_or(0);
+    let mut lines: Vec<Line<'static>> = settings::SETTINGS
+        .iter()
+        .enumerate()
+        .map(|(row, setting)| {
+            let selected = row == app.settings_cursor;
+            let marker = if selected { "›" } else { " " };
+            let line = format!(
+                "{} {:width$}  {}",
+                marker,
+                setting.label,
+                setting.current(app),
+                width = width
+            );
+            if selected {
+                Line::from(Span::styled(line, Style::default().bg(app.theme.selection)))
+            } else {
+                Line::from(line)
+            }
+        })
+        .collect();
     lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "↑/↓ choose · ←/→ or Enter change · Esc saves to config.toml",
+        Style::default().fg(app.theme.dim),
+    )));
+    lines
+}
 
-    // Flight number and callsign
-    let mut flight_line = vec![
-        Span::styled("Flight:  ", Style::default().add_modifier(Modifier::BOLD)),
-        Span::raw(flight.flight_number.clone()),
-    ];
-    if !flight.callsign.is_empty() {
-        flight_line.push(Span::styled(
-            format!(" ({})", flight.callsign),
-            Style::default().fg(Color::DarkGray),
-        ));
-    }
-    lines.push(Line::from(flight_line));
+fn diagnostics_lines(app: &App, diagnostics: &Diagnostics) -> Vec<Line<'static>> {
+    let clock = app.config.time_format;
+    let heading = Style::default()
+        .add_modifier(Modifier::BOLD)
+        .add_modifier(Modifier::UNDERLINED);
+    let dim = Style::default().fg(app.theme.dim);
 
-    // Airline
-    if let Some(airline) = &flight.airline {
+    let mut lines = vec![];
+    for (role, provider) in &diagnostics.providers {
+        // Snapshots and their readings only come from the position provider
+        let position = *role == "position";
+        let stats = &provider.stats;
         lines.push(Line::from(vec![
-            Span::styled("Airline: ", Style::default().add_modifier(Modifier::BOLD)),
-            Span::raw(airline.clone()),
+            Span::styled(provider.name, heading),
+            Span::raw(format!("  {}", provider.auth)),
         ]));
-    }
-
-    // Status
-    let status_color = status_to_color(&flight.status);
-    let mut status_line = vec![
-        Span::styled("Status:  ", Style::default().add_modifier(Modifier::BOLD)),
-        Span::styled(format!("{}", flight.status), Style::default().fg(status_color)),
-    ];
-    if let Some(delay) = flight.departure_delay.or(flight.arrival_delay) {
-        if delay > 0 {
-            status_line.push(Span::styled(
-                format!(" (+{}min)", delay),
+        lines.push(Line::from(format!(
+            "  Requests:     {} this session",
+            stats.calls
+        )));
+        lines.push(Line::from(match stats.last_success {
+            Some(at) => format!("  Last success: {} UTC", clock.format(at, true)),
+            None => "  Last success: never".to_string(),
+        }));
+        match &stats.last_failure {
+            Some((at, message)) => lines.push(Line::from(Span::styled(
+                format!(
+                    "  Last failure: {} UTC, {}",
+                    clock.format(*at, true),
+                    message
+                ),
+                Style::default().fg(Color::Red),
+            ))),
+            None => lines.push(Line::from("  Last failure: none")),
+        }
+        if provider.breaker.is_degraded() {
+            lines.push(Line::from(Span::styled(
+                format!("  Breaker:      {}", provider.breaker.describe()),
                 Style::default().fg(Color::Yellow),
-            ));
+            )));
         }
-    }
-    lines.push(Line::from(status_line));
-
-    // Route section
-    if flight.origin.is_some() || flight.destination.is_some() {
-        lines.push(Line::from(""));
-        lines.push(Line::from(Span::styled(
-            "Route",
-            Style::default()
-                .add_modifier(Modifier::BOLD)
-                .add_modifier(Modifier::UNDERLINED),
+        if let Some(resolution) = &provider.resolution {
+            let lag = app
+                .feed_time
+                .filter(|_| position)
+                .map(|f| {
+                    format!(
+                        ", latest snapshot {} old on arrival",
+                        format_lag(f.lag_secs)
+                    )
+                })
+                .unwrap_or_default();
+            lines.push(Line::from(Span::styled(
+                format!("  Resolution:   {}{}", resolution, lag),
+                dim,
+            )));
+        }
+        let cache = &provider.cache;
+        lines.push(Line::from(format!(
+            "  Cache:        {} entries, {} hits, {} misses, {} stored",
+            cache.entries, cache.hits, cache.misses, cache.inserts
         )));
-
-        if let Some(orig) = &flight.origin {
-            let code = orig.iata.as_deref().or(orig.icao.as_deref()).unwrap_or("???");
-            let name = orig.name.as_deref().unwrap_or("");
-            lines.push(Line::from(format!("  From: {} {}", code, name)));
+        let rejected: Vec<String> = Reading::ALL
+            .iter()
+            .filter(|_| position)
+            .filter_map(|r| app.rejected_readings.get(r).map(|n| format!("{} {}", r, n)))
+            .collect();
+        if !rejected.is_empty() {
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "  Rejected:     {} (implausible readings)",
+                    rejected.join(", ")
+                ),
+                Style::default().fg(Color::Yellow),
+            )));
         }
+        lines.push(Line::from(""));
+    }
 
-        if let Some(dest) = &flight.destination {
-            let code = dest.iata.as_deref().or(dest.icao.as_deref()).unwrap_or("???");
-            let name = dest.name.as_deref().unwrap_or("");
-            lines.push(Line::from(format!("  To:   {} {}", code, name)));
-        }
+    let path = |path: &Option<std::path::PathBuf>| {
+        path.as_ref()
+            .map_or("unavailable".to_string(), |p| p.display().to_string())
+    };
+    lines.push(Line::from(Span::styled("Paths", heading)));
+    lines.push(Line::from(format!(
+        "  Data:   {}",
+        path(&diagnostics.data_dir)
+    )));
+    lines.push(Line::from(format!(
+        "  Config: {}",
+        path(&diagnostics.config_path)
+    )));
+    lines.push(Line::from(format!(
+        "  Log:    {}",
+        path(&diagnostics.log_path)
+    )));
+    lines.push(Line::from(""));
+    match app.pending_cache_clear {
+        Some(kind) => lines.push(Line::from(Span::styled(
+            format!(
+                "Clear the {} cache? y to confirm, any other key cancels",
+                kind.label()
+            ),
+            Style::default().fg(Color::Yellow),
+        ))),
+        None => lines.push(Line::from(Span::styled(
+            "p/s clear the position/schedule cache, D or Esc to close",
+            dim,
+        ))),
     }
+    lines
+}
 
-    // Schedule section
-    let has_schedule = flight.departure_scheduled.is_some() || flight.arrival_scheduled.is_some();
-    if has_schedule {
-        lines.push(Line::from(""));
-        lines.push(Line::from(Span::styled(
-            "Schedule",
-            Style::default()
-                .add_modifier(Modifier::BOLD)
-                .add_modifier(Modifier::UNDERLINED),
-        )));
+/// Whether something is being typed into the input box.
+fn is_editing(app: &App) -> bool {
+    matches!(
+        app.mode,
+        AppMode::Input
+            | AppMode::AlertInput
+            | AppMode::ImportInput
+            | AppMode::ListSearch
+            | AppMode::Command
+    )
+}
 
-        if let Some(dep) = &flight.departure_scheduled {
-            let time = format_time(dep);
-            let mut dep_line = format!("  Departure:  {}", time);
-            if let Some(actual) = &flight.departure_actual {
-                dep_line.push_str(&format!(" (actual: {})", format_time(actual)));
-            } else if let Some(est) = &flight.departure_estimated {
-                dep_line.push_str(&format!(" (est: {})", format_time(est)));
-            }
-            lines.push(Line::from(dep_line));
-        }
+fn draw_input(frame: &mut Frame, area: Rect, app: &App) {
+    let editing = is_editing(app);
+    let already_tracked = app.mode == AppMode::Input && app.input_match.is_some();
+    let style = if already_tracked {
+        Style::default().fg(Color::Cyan)
+    } else if editing {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default().fg(app.theme.dim)
+    };
+    let title = format!(" {} ", input_title(app));
 
-        if let Some(arr) = &flight.arrival_scheduled {
-            let time = format_time(arr);
-            let mut arr_line = format!("  Arrival:    {}", time);
-            if let Some(actual) = &flight.arrival_actual {
-                arr_line.push_str(&format!(" (actual: {})", format_time(actual)));
-            } else if let Some(est) = &flight.arrival_estimated {
-                arr_line.push_str(&format!(" (est: {})", format_time(est)));
+    // An unsubmitted flight number stays visible, dimmed, outside Input mode
+    let text = if app.mode == AppMode::Viewing && !app.input_draft.is_empty() {
+        Line::from(Span::styled(
+            app.input_draft.as_str(),
+            Style::default().add_modifier(Modifier::DIM),
+        ))
+    } else {
+        Line::from(app.input_buffer.as_str())
+    };
+    let input = Paragraph::new(text).style(style).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(style),
+    );
+
+    frame.render_widget(input, area);
+
+    if editing {
+        frame.set_cursor_position((area.x + app.cursor_position as u16 + 1, area.y + 1));
+    }
+}
+
+/// What the input line is for in the current mode, with the keys that
+/// matter there.
+fn input_title(app: &App) -> String {
+    match app.mode {
+        AppMode::Input => {
+            if let Some(flight_number) = &app.input_match {
+                format!("{} already tracking — Enter jumps to it", flight_number)
+            } else if let Some(prompt) = &app.return_prompt {
+                let other = if prompt.guesses.len() > 1 {
+                    ", Tab tries another"
+                } else {
+                    ""
+                };
+                format!(
+                    "Return of {} ({}→{})? Enter searches{}",
+                    prompt.of, prompt.from, prompt.to, other
+                )
+            } else if app.history_index.is_some() {
+                "History (↑/↓ to browse)".to_string()
+            } else if !app.history.is_empty() {
+                "Enter Flight Number (↑ for history)".to_string()
+            } else {
+                "Enter Flight Number(s) (e.g. UA123, BA285)".to_string()
             }
-            lines.push(Line::from(arr_line));
         }
+        AppMode::AlertInput => {
+            let flight_number = app
+                .selected_flight()
+                .map(|f| f.flight_number.as_str())
+                .unwrap_or_default();
+            format!(
+                "Alert for {} (150km, 3000ft, landed; empty clears)",
+                flight_number
+            )
+        }
+        AppMode::ImportInput => "Import flights from file (CSV or JSON path)".to_string(),
+        AppMode::ListSearch => format!(
+            "Filter list: {} of {} shown (Enter keeps, Esc clears)",
+            app.visible_indices().len(),
+            app.tracked_flights.len()
+        ),
+        AppMode::Command => "Command (Tab completes, Enter runs, Esc cancels)".to_string(),
+        AppMode::Settings => "Settings (←/→ or Enter changes, Esc saves)".to_string(),
+        AppMode::Viewing => "Press '/' to add flight".to_string(),
+        AppMode::Onboarding => "Welcome".to_string(),
     }
+}
 
-    // Position section (only if we have live data)
-    if flight.latitude.is_some() || flight.altitude_ft.is_some() {
-        lines.push(Line::from(""));
-        lines.push(Line::from(Span::styled(
-            "Live Position",
-            Style::default()
-                .add_modifier(Modifier::BOLD)
-                .add_modifier(Modifier::UNDERLINED),
-        )));
+/// One row of the flight list: a header over a group, or a flight.
+enum ListRow<'a> {
+    Header(Line<'a>),
+    Flight(usize),
+}
 
-        if let (Some(lat), Some(lon)) = (flight.latitude, flight.longitude) {
-            let lat_dir = if lat >= 0.0 { "N" } else { "S" };
-            let lon_dir = if lon >= 0.0 { "E" } else { "W" };
-            lines.push(Line::from(format!(
-                "  Position:  {:.4}°{}, {:.4}°{}",
-                lat.abs(), lat_dir, lon.abs(), lon_dir
-            )));
+fn draw_flight_list(frame: &mut Frame, area: Rect, app: &App) {
+    let mut rows = Vec::new();
+    for trip in &app.trips {
+        let legs = app.trip_rows(trip);
+        if !legs.is_empty() {
+            rows.push(ListRow::Header(trip_header(app, trip)));
+            rows.extend(legs.into_iter().map(ListRow::Flight));
         }
+    }
+    let arrived = app.arrived_indices();
+    rows.extend(
+        ungrouped_indices(app)
+            .into_iter()
+            .filter(|i| !arrived.contains(i))
+            .map(ListRow::Flight),
+    );
 
-        if let Some(alt) = flight.altitude_ft {
-            lines.push(Line::from(format!("  Altitude:  {:.0} ft", alt)));
+    if !arrived.is_empty() {
+        let marker = if app.show_arrived { "▾" } else { "▸" };
+        rows.push(ListRow::Header(Line::from(Span::styled(
+            format!("{} Arrived ({})", marker, arrived.len()),
+            Style::default()
+                .fg(app.theme.dim)
+                .add_modifier(Modifier::BOLD),
+        ))));
+        if app.show_arrived {
+            rows.extend(arrived.into_iter().map(ListRow::Flight));
         }
+    }
 
-        if let Some(hdg) = flight.heading {
-            lines.push(Line::from(format!("  Heading:   {:.0}°", hdg)));
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Tracked Flights ");
+    let inner = block.inner(area);
+    frame.render_widget(flight_table(app, &rows, inner.width).block(block), area);
+
+    // A header spans every column, so it is drawn over its empty row
+    for (y, row) in (inner.y + 1..inner.bottom()).zip(&rows) {
+        if let ListRow::Header(line) = row {
+            frame.render_widget(line.clone(), Rect::new(inner.x, y, inner.width, 1));
         }
+    }
+}
 
-        if let Some(gs) = flight.ground_speed_kts {
-            lines.push(Line::from(format!("  Speed:     {:.0} kts", gs)));
+/// The list's rows as a table of the `[table]` columns under their titles,
+/// with the selection marker before them and the flight's flags after.
+/// Columns that don't fit in `width` are left off from the right, though
+/// the first is always kept.
+fn flight_table<'a>(app: &'a App, rows: &[ListRow<'a>], width: u16) -> Table<'a> {
+    let columns = app.config.table.resolve();
+    let mut header: Vec<Line> = vec![Line::default()];
+    header.extend(columns.iter().map(|c| Line::from(c.title)));
+    header.push(Line::default());
+    let cells: Vec<Option<Vec<Line>>> = rows
+        .iter()
+        .map(|row| match row {
+            ListRow::Flight(i) => Some(flight_cells(app, *i, &columns)),
+            ListRow::Header(_) => None,
+        })
+        .collect();
+
+    let mut widths: Vec<usize> = vec![0; header.len()];
+    for (k, column) in columns.iter().enumerate() {
+        widths[k + 1] = column.width;
+    }
+    for line_cells in std::iter::once(&header).chain(cells.iter().flatten()) {
+        for (width, cell) in widths.iter_mut().zip(line_cells) {
+            *width = (*width).max(cell.width());
         }
+    }
 
-        if let Some(vr) = flight.vertical_rate {
-            let vr_str = if vr >= 0.0 {
-                format!("+{:.0}", vr)
-            } else {
-                format!("{:.0}", vr)
-            };
-            lines.push(Line::from(format!("  Climb:     {} ft/min", vr_str)));
+    // The marker and flags columns always show; each column after the
+    // marker adds its width and a space before it
+    let flags = widths.len() - 1;
+    let mut used = widths[0] + widths[flags] + 1;
+    let mut shown = vec![0];
+    for (k, column_width) in widths.iter().enumerate().take(flags).skip(1) {
+        used += column_width + 1;
+        if k > 1 && used > usize::from(width) {
+            break;
         }
+        shown.push(k);
     }
+    shown.push(flags);
 
-    // Aircraft info
-    if flight.aircraft_type.is_some() || flight.registration.is_some() || !flight.icao24.is_empty() {
-        lines.push(Line::from(""));
-        lines.push(Line::from(Span::styled(
-            "Aircraft",
+    let pick = |line_cells: Vec<Line<'a>>| -> Vec<Line<'a>> {
+        line_cells
+            .into_iter()
+            .enumerate()
+            .filter(|(k, _)| shown.contains(k))
+            .map(|(_, cell)| cell)
+            .collect()
+    };
+    let table_rows: Vec<Row> = rows
+        .iter()
+        .zip(cells)
+        .map(|(row, line_cells)| match (row, line_cells) {
+            (ListRow::Flight(i), Some(line_cells)) => {
+                Row::new(pick(line_cells)).style(flight_row_style(app, *i))
+            }
+            // Left empty for the header drawn over it
+            _ => Row::new(Vec::<Line>::new()),
+        })
+        .collect();
+    let constraints = shown.iter().map(|&k| Constraint::Length(widths[k] as u16));
+
+    let header_style = Style::default()
+        .fg(app.theme.dim)
+        .add_modifier(Modifier::BOLD);
+    Table::new(table_rows, constraints).header(Row::new(pick(header)).style(header_style))
+}
+
+/// The flights the list shows outside any trip, arrived ones included.
+fn ungrouped_indices(app: &App) -> Vec<usize> {
+    app.visible_indices()
+        .into_iter()
+        .filter(|&i| app.trip_of(&app.tracked_flights[i].flight_number).is_none())
+        .collect()
+}
+
+/// A trip's line above its legs, e.g. "▾ Trip: NYC→TYO  leg 2/3 NH6 En
+/// Route, 1 done". The marker shows whether every leg is listed.
+fn trip_header<'a>(app: &App, trip: &Trip) -> Line<'a> {
+    let marker = if app.is_trip_expanded(trip) {
+        "▾"
+    } else {
+        "▸"
+    };
+    Line::from(vec![
+        Span::styled(
+            format!("{} Trip: {}", marker, trip.label()),
             Style::default()
-                .add_modifier(Modifier::BOLD)
-                .add_modifier(Modifier::UNDERLINED),
-        )));
+                .fg(app.theme.bright)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            format!("  {}", trip.summary(&app.tracked_flights)),
+            Style::default().fg(app.theme.dim),
+        ),
+    ])
+}
 
-        if let Some(aircraft) = &flight.aircraft_type {
-            lines.push(Line::from(format!("  Type:      {}", aircraft)));
-        }
+/// A flight's cells in the list: the selection marker, one per column and
+/// then its flags.
+fn flight_cells<'a>(app: &'a App, i: usize, columns: &[&Column]) -> Vec<Line<'a>> {
+    let flight = &app.tracked_flights[i];
+    // Legs sit under their trip's header
+    let indent = if app.trip_of(&flight.flight_number).is_some() {
+        "  "
+    } else {
+        ""
+    };
+    let prefix = if Some(i) == app.selected_index {
+        ">"
+    } else if app.following.as_deref() == Some(flight.flight_number.as_str()) {
+        "◎"
+    } else {
+        " "
+    };
+    let mut marker = vec![Span::raw(indent), Span::raw(prefix)];
+    if let Some(band) = flight.eta_band(Utc::now()) {
+        marker.push(Span::styled(" ●", Style::default().fg(eta_color(band))));
+    }
 
-        if let Some(reg) = &flight.registration {
-            lines.push(Line::from(format!("  Reg:       {}", reg)));
-        }
+    let mut cells = vec![Line::from(marker)];
+    cells.extend(
+        columns
+            .iter()
+            .map(|column| flight_cell(app, flight, column)),
+    );
+    cells.push(flight_flags(app, flight));
+    cells
+}
 
-        if !flight.icao24.is_empty() {
-            lines.push(Line::from(format!("  ICAO24:    {}", flight.icao24)));
+/// One column's cell for `flight`. The flight, route and status are drawn
+/// in color, the route in its short form; the rest read like the report.
+fn flight_cell<'a>(app: &'a App, flight: &'a Flight, column: &Column) -> Line<'a> {
+    match column.id {
+        "flight" => Line::from(Span::styled(
+            flight.flight_number.as_str(),
+            Style::default().fg(app.theme.bright),
+        )),
+        "route" => Line::from(Span::styled(
+            flight.route_label().unwrap_or_default(),
+            Style::default().fg(Color::Cyan),
+        )),
+        "status" => {
+            let status_color = status_to_color(&flight.status);
+            let mut spans = vec![Span::styled(
+                flight.status.to_string(),
+                Style::default().fg(status_color),
+            )];
+            if let Some(progress) = en_route_progress(flight) {
+                spans.push(Span::styled(
+                    format!(" {}%", progress.percent()),
+                    Style::default().fg(app.theme.dim),
+                ));
+            }
+            if let Some(airport) = flight.ground_airport() {
+                spans.push(Span::styled(
+                    format!(" at {}", airport.icao),
+                    Style::default().fg(status_color),
+                ));
+            }
+            Line::from(spans)
         }
+        _ => Line::from((column.value)(flight, app.config.time_format)),
+    }
+}
 
-        if let Some(squawk) = &flight.squawk {
-            lines.push(Line::from(format!("  Squawk:    {}", squawk)));
+/// Markers after a flight's columns: a possible diversion, armed alerts,
+/// recurring, from the watchlist and muted.
+fn flight_flags<'a>(app: &App, flight: &Flight) -> Line<'a> {
+    let mut flags = vec![];
+    if flight.possible_diversion().is_some() {
+        flags.push(Span::styled("⚠ diverted?", diversion_style()));
+    }
+    if flight.alerts.iter().any(|a| a.armed) {
+        flags.push(Span::styled("[w]", Style::default().fg(Color::Magenta)));
+    }
+    if flight.recurring {
+        flags.push(Span::styled("↻", Style::default().fg(Color::Cyan)));
+    }
+    if flight.from_watchlist {
+        flags.push(Span::styled("≡", Style::default().fg(app.theme.dim)));
+    }
+    if flight.is_muted(Utc::now()) {
+        flags.push(Span::styled("🔕", Style::default().fg(app.theme.dim)));
+    }
+    let mut spans = vec![];
+    for (n, flag) in flags.into_iter().enumerate() {
+        if n > 0 {
+            spans.push(Span::raw(" "));
         }
+        spans.push(flag);
     }
+    Line::from(spans)
+}
 
-    // Not found message
-    if flight.status == FlightStatus::NotFound && flight.origin.is_none() {
-        lines.push(Line::from(""));
-        lines.push(Line::from(Span::styled(
-            "No data available for this flight.",
-            Style::default().fg(Color::Yellow),
-        )));
-        lines.push(Line::from("The flight may not be active or"));
-        lines.push(Line::from("the flight number may be incorrect."));
+fn flight_row_style(app: &App, i: usize) -> Style {
+    let mut style = if Some(i) == app.selected_index {
+        Style::default()
+            .bg(app.theme.selection)
+            .add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    };
+    // Likely typos, kept out of the way
+    if app.tracked_flights[i].status == FlightStatus::NotFound {
+        style = style.add_modifier(Modifier::DIM);
     }
+    style
+}
 
-    // Last updated
-    if let Some(updated) = flight.last_updated {
-        lines.push(Line::from(""));
-        lines.push(Line::from(Span::styled(
-            format!("Updated: {}", updated.format("%H:%M:%S UTC")),
-            Style::default().fg(Color::DarkGray),
-        )));
+/// How much of its route an airborne flight has covered, for the list.
+fn en_route_progress(flight: &Flight) -> Option<RouteProgress> {
+    flight
+        .route_progress()
+        .filter(|_| flight.status == FlightStatus::EnRoute)
+}
+
+/// Badge color for the time left until a flight's ETA.
+fn eta_color(band: EtaBand) -> Color {
+    match band {
+        EtaBand::Later => Color::Green,
+        EtaBand::WithinHour => Color::Yellow,
+        EtaBand::Imminent => Color::Red,
+    }
+}
+
+/// A landing away from the scheduled route stands out in red.
+fn diversion_style() -> Style {
+    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+}
+
+fn status_to_color(status: &FlightStatus) -> Color {
+    match status {
+        FlightStatus::EnRoute => Color::Green,
+        FlightStatus::Scheduled => Color::Cyan,
+        FlightStatus::Landed => Color::Blue,
+        FlightStatus::OnGround => Color::Blue,
+        FlightStatus::Delayed => Color::Yellow,
+        FlightStatus::Cancelled => Color::Red,
+        FlightStatus::NotFound => Color::Red,
+        FlightStatus::Unknown => Color::DarkGray,
+    }
+}
+
+/// The strip of numbered tabs over the right-hand panel, the active one
+/// highlighted.
+fn draw_panel_tabs(frame: &mut Frame, area: Rect, app: &App) {
+    let titles = RightPanel::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, panel)| format!("{} {}", i + 1, panel.title()));
+    let selected = RightPanel::ALL.iter().position(|&p| p == app.right_panel);
+    let tabs = Tabs::new(titles)
+        .select(selected)
+        .style(Style::default().fg(app.theme.dim))
+        .highlight_style(
+            Style::default()
+                .fg(app.theme.bright)
+                .add_modifier(Modifier::BOLD | Modifier::REVERSED),
+        );
+    frame.render_widget(tabs, area);
+}
+
+/// What the pinned flight has done so far, one event a line.
+fn draw_flight_log(frame: &mut Frame, area: Rect, app: &App, flight: &Flight) {
+    let title = format!(" Log: {} (PgUp/PgDn scroll) ", flight.flight_number);
+    let log = Paragraph::new(log_lines(app, flight))
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .scroll((app.panel_scroll, 0));
+    frame.render_widget(log, area);
+}
+
+fn log_lines(app: &App, flight: &Flight) -> Vec<Line<'static>> {
+    let log = flight.log();
+    if log.is_empty() {
+        return vec![Line::from(Span::styled(
+            "Nothing logged yet: departures, delays and landings show up here",
+            Style::default().fg(app.theme.dim),
+        ))];
     }
+    log.into_iter()
+        .map(|entry| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{} UTC  ", app.config.time_format.format(entry.at, false)),
+                    Style::default().fg(app.theme.dim),
+                ),
+                Span::raw(entry.event),
+            ])
+        })
+        .collect()
+}
+
+/// The destination's weather in full: the METAR, every TAF group and the
+/// forecast for the ETA.
+fn draw_weather(frame: &mut Frame, area: Rect, app: &App, flight: &Flight) {
+    let title = format!(" Weather: {} ", flight.flight_number);
+    let weather = Paragraph::new(weather_lines(app, flight))
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .wrap(Wrap { trim: true });
+    frame.render_widget(weather, area);
+}
 
+fn weather_lines(app: &App, flight: &Flight) -> Vec<Line<'static>> {
+    let dim = Style::default().fg(app.theme.dim);
+    let Some(dest) = &flight.destination else {
+        return vec![Line::from(Span::styled("No destination known yet", dim))];
+    };
+    let heading = Line::from(Span::styled(
+        format!("Destination: {}", dest.code()),
+        Style::default().add_modifier(Modifier::BOLD),
+    ));
+    let Some(weather) = dest.icao_code().and_then(|icao| app.weather.get(icao)) else {
+        return vec![
+            heading,
+            Line::from(Span::styled("No weather received yet", dim)),
+        ];
+    };
+    let mut lines = vec![heading, Line::from("")];
+    match &weather.metar {
+        Some(metar) => lines.push(Line::from(format!("METAR: {}", metar))),
+        None => lines.push(Line::from(Span::styled("No METAR", dim))),
+    }
+    let Some(taf) = &weather.taf else {
+        lines.push(Line::from(Span::styled("No TAF", dim)));
+        return lines;
+    };
+    lines.push(Line::from(format!(
+        "TAF {} to {}",
+        taf.valid_from.format("%d %H:%MZ"),
+        taf.valid_to.format("%d %H:%MZ")
+    )));
+    lines.extend(taf_group_lines(taf));
+    if let Some(eta) = flight.eta() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(taf.forecast_at(eta).summary(eta, taf)));
+    }
     lines
 }
 
-fn format_empty_state(app: &App) -> Vec<Line<'static>> {
+/// One line per TAF group, e.g. "  TEMPO 14:00Z-18:00Z 4000 SHRA".
+fn taf_group_lines(taf: &Taf) -> Vec<Line<'static>> {
+    taf.groups
+        .iter()
+        .map(|group| {
+            let change = match group.change {
+                Change::Base => String::new(),
+                Change::From => "FM ".to_string(),
+                Change::Becoming => "BECMG ".to_string(),
+                Change::Temporary => "TEMPO ".to_string(),
+                Change::Probability(percent) => format!("PROB{} ", percent),
+            };
+            Line::from(format!(
+                "  {}{}-{} {}",
+                change,
+                group.from.format("%H:%MZ"),
+                group.to.format("%H:%MZ"),
+                group.elements.join(" ")
+            ))
+        })
+        .collect()
+}
+
+fn draw_flight_details(frame: &mut Frame, area: Rect, app: &App) {
+    let inner_width = usize::from(area.width.saturating_sub(2));
+    let details = Paragraph::new(details_content(app, inner_width))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Flight Details "),
+        )
+        .wrap(Wrap { trim: true });
+
+    frame.render_widget(details, area);
+}
+
+/// The walkthrough, the pinned flight, the restored session or the empty
+/// state, whichever the details pane is showing, `width` columns wide.
+fn details_content(app: &App, width: usize) -> Vec<Line<'static>> {
+    match (app.pinned_flight(), &app.restore) {
+        _ if app.mode == AppMode::Onboarding => format_onboarding(app),
+        (Some(f), _) => format_flight_details(app, f, width),
+        (None, Some(restore)) => format_restore_summary(restore, &app.theme),
+        (None, None) => format_empty_state(app),
+    }
+}
+
+/// The selected flight's last payload from each provider, as received.
+fn draw_raw_payloads(frame: &mut Frame, area: Rect, app: &App, flight: &Flight, scroll: u16) {
+    let title = format!(
+        " Raw payloads: {} (PgUp/PgDn scroll, Ctrl+D closes) ",
+        flight.flight_number
+    );
+    let payloads = Paragraph::new(raw_payload_lines(flight, &app.theme))
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .scroll((scroll, 0));
+    frame.render_widget(payloads, area);
+}
+
+/// Both payloads under a heading each, unwrapped so scrolling goes by line.
+fn raw_payload_lines<'a>(flight: &'a Flight, theme: &Theme) -> Vec<Line<'a>> {
+    let heading = Style::default().add_modifier(Modifier::BOLD);
     let mut lines = vec![];
+    for (name, raw) in [
+        ("OpenSky state vector", &flight.raw_state),
+        ("AviationStack flight", &flight.raw_schedule),
+    ] {
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+        lines.push(Line::from(Span::styled(name, heading)));
+        match raw {
+            Some(raw) => lines.extend(raw.lines().map(Line::from)),
+            None => lines.push(Line::from(Span::styled(
+                "Nothing received yet",
+                Style::default().fg(theme.dim),
+            ))),
+        }
+    }
+    lines
+}
 
-    lines.push(Line::from(""));
+/// Plot every tracked flight with a position on a world map, labelled by
+/// flight number and trailed by its track, with the selected flight
+/// highlighted.
+fn draw_map_overview(frame: &mut Frame, area: Rect, app: &App) {
+    let view = app.map_viewport.unwrap_or(BoundingBox::WORLD);
+    let unplaced: Vec<&str> = app
+        .tracked_flights
+        .iter()
+        .filter(|f| f.latitude.is_none() || f.longitude.is_none())
+        .map(|f| f.flight_number.as_str())
+        .collect();
 
-    // Show history if available
-    if !app.history.is_empty() {
-        lines.push(Line::from(Span::styled(
-            "Recent Flights",
-            Style::default()
-                .add_modifier(Modifier::BOLD)
-                .add_modifier(Modifier::UNDERLINED),
-        )));
-        lines.push(Line::from(""));
+    let canvas = Canvas::default()
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" Map Overview (M to close) "),
+        )
+        .marker(Marker::Braille)
+        .x_bounds([view.min_lon, view.max_lon])
+        .y_bounds([view.min_lat, view.max_lat])
+        .paint(|ctx| {
+            ctx.draw(&Map {
+                resolution: MapResolution::High,
+                color: app.theme.dim,
+            });
+            ctx.layer();
 
-        for (i, entry) in app.history.entries().take(8).enumerate() {
-            let route_str = entry
-                .route
-                .as_ref()
-                .map(|r| format!(" {}", r))
-                .unwrap_or_default();
+            let now = Utc::now();
+            for (i, flight) in app.tracked_flights.iter().enumerate() {
+                let Some((lat, lon, estimated)) = displayed_position(app, flight, now) else {
+                    continue;
+                };
+                let mut style = if Some(i) == app.pinned_index() {
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(status_to_color(&flight.status))
+                };
+                let trail: Vec<(f64, f64)> = flight
+                    .track
+                    .iter()
+                    .map(|p| (p.longitude, p.latitude))
+                    .collect();
+                ctx.draw(&Points {
+                    coords: &trail,
+                    color: style.fg.unwrap_or(app.theme.bright),
+                });
+                if estimated {
+                    style = style.add_modifier(Modifier::DIM);
+                }
+                ctx.draw(&Points {
+                    coords: &[(lon, lat)],
+                    color: style.fg.unwrap_or(app.theme.bright),
+                });
+                let marker = if estimated { "†" } else { "" };
+                ctx.print(
+                    lon,
+                    lat,
+                    Span::styled(format!(" {}{}", flight.flight_number, marker), style),
+                );
+            }
 
-            let style = if app.history_index == Some(i) {
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD)
+            // Legend for flights that can't be plotted, top-left corner
+            let line_height = (view.max_lat - view.min_lat) / f64::from(area.height.max(3) - 2);
+            for (row, flight_number) in unplaced.iter().enumerate() {
+                ctx.print(
+                    view.min_lon,
+                    view.max_lat - line_height * row as f64,
+                    Span::styled(
+                        format!("{} no position", flight_number),
+                        Style::default().fg(app.theme.dim),
+                    ),
+                );
+            }
+        });
+
+    frame.render_widget(canvas, area);
+}
+
+/// Chart the selected flight's altitude against time since its first
+/// recorded position, breaking the line at coverage gaps and marking the
+/// top of climb and top of descent.
+fn draw_altitude_chart(frame: &mut Frame, area: Rect, app: &App) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Altitude Profile (P to close) ");
+    let track = app.pinned_flight().map_or(&[][..], |f| f.track.as_slice());
+    let peak = track.iter().filter_map(|p| p.altitude_ft).reduce(f64::max);
+    let (Some(first), Some(last), Some(peak)) = (track.first(), track.last(), peak) else {
+        let empty = Paragraph::new(Line::styled(
+            "  No altitude reports yet",
+            Style::default().fg(app.theme.dim),
+        ));
+        frame.render_widget(empty.block(block), area);
+        return;
+    };
+
+    let minutes = |p: &TrackPoint| (p.at - first.at).num_seconds() as f64 / 60.0;
+    let to_xy = |p: &TrackPoint| Some((minutes(p), p.altitude_ft?));
+    let span_mins = minutes(last).max(1.0);
+    let top_ft = (peak / ALTITUDE_STEP_FT).ceil().max(1.0) * ALTITUDE_STEP_FT;
+
+    let segments: Vec<Vec<(f64, f64)>> = flight::split_at_gaps(track, PROFILE_GAP)
+        .into_iter()
+        .map(|segment| segment.iter().filter_map(to_xy).collect())
+        .collect();
+    let markers = flight::cruise_markers(track);
+    let top_of_climb: Vec<(f64, f64)> = markers.top_of_climb.iter().filter_map(to_xy).collect();
+    let top_of_descent: Vec<(f64, f64)> = markers.top_of_descent.iter().filter_map(to_xy).collect();
+
+    let mut datasets: Vec<Dataset> = segments
+        .iter()
+        .map(|points| {
+            // A lone point between gaps has no line to draw
+            let graph_type = if points.len() == 1 {
+                GraphType::Scatter
             } else {
-                Style::default().fg(Color::Cyan)
+                GraphType::Line
             };
+            Dataset::default()
+                .marker(Marker::Braille)
+                .graph_type(graph_type)
+                .style(Style::default().fg(Color::Cyan))
+                .data(points)
+        })
+        .collect();
+    for (name, points, color) in [
+        ("top of climb", &top_of_climb, Color::Green),
+        ("top of descent", &top_of_descent, Color::Magenta),
+    ] {
+        if !points.is_empty() {
+            datasets.push(
+                Dataset::default()
+                    .name(name)
+                    .marker(Marker::Dot)
+                    .graph_type(GraphType::Scatter)
+                    .style(Style::default().fg(color))
+                    .data(points),
+            );
+        }
+    }
 
-            lines.push(Line::from(vec![
-                Span::raw("  "),
-                Span::styled(entry.flight_number.clone(), style),
-                Span::styled(route_str, Style::default().fg(Color::DarkGray)),
-            ]));
+    let dim = Style::default().fg(app.theme.dim);
+    let chart = Chart::new(datasets)
+        .block(block)
+        .x_axis(
+            Axis::default()
+                .style(dim)
+                .bounds([0.0, span_mins])
+                .labels(elapsed_labels(span_mins.round() as i64)),
+        )
+        .y_axis(
+            Axis::default()
+                .style(dim)
+                .bounds([0.0, top_ft])
+                .labels(altitude_labels(top_ft)),
+        );
+    frame.render_widget(chart, area);
+}
+
+/// Coverage gap after which the altitude chart breaks its line.
+const PROFILE_GAP: chrono::Duration = chrono::Duration::minutes(15);
+/// The altitude chart's scale is rounded up to a multiple of this.
+const ALTITUDE_STEP_FT: f64 = 5_000.0;
+
+/// Time axis labels for a chart spanning `span_mins` minutes: start, middle
+/// and end, in minutes for flights under two hours and hours beyond.
+fn elapsed_labels(span_mins: i64) -> Vec<String> {
+    [0, span_mins / 2, span_mins]
+        .into_iter()
+        .map(|mins| {
+            if span_mins < 120 {
+                format!("{}m", mins)
+            } else {
+                format!("{}h{:02}", mins / 60, mins % 60)
+            }
+        })
+        .collect()
+}
+
+/// Altitude axis labels from the ground to `top_ft`, in thousands of feet.
+fn altitude_labels(top_ft: f64) -> Vec<String> {
+    let thousands = (top_ft / 1_000.0).round() as i64;
+    vec![
+        "0".to_string(),
+        format!("{}k", thousands / 2),
+        format!("{}k ft", thousands),
+    ]
+}
+
+/// Where to draw a flight: its dead-reckoned position when that is enabled
+/// and possible, otherwise the last fix. The flag marks an estimate.
+fn displayed_position(app: &App, flight: &Flight, now: DateTime<Utc>) -> Option<(f64, f64, bool)> {
+    if app.config.dead_reckoning {
+        if let Some((lat, lon)) = flight.estimated_position(now) {
+            return Some((lat, lon, true));
+        }
+    }
+    Some((flight.latitude?, flight.longitude?, false))
+}
+
+/// Which provider the position came from, flagging AviationStack's
+/// minutes-old `live` reports so they aren't taken for live tracking.
+fn position_source_line(app: &App, flight: &Flight) -> Option<Line<'static>> {
+    match flight.position_source? {
+        PositionSource::OpenSky => Some(Line::from("  Source:    OpenSky")),
+        PositionSource::AviationStack => {
+            let as_of = flight
+                .position_time
+                .map(|at| format!(", as of {} UTC", app.config.time_format.format(at, false)))
+                .unwrap_or_default();
+            Some(Line::from(vec![
+                Span::raw("  Source:    "),
+                Span::styled(
+                    format!("AviationStack{} (less fresh)", as_of),
+                    Style::default().fg(Color::Yellow),
+                ),
+            ]))
         }
+    }
+}
 
-        lines.push(Line::from(""));
+/// How old the latest fix is, and how much older than the OpenSky snapshot
+/// it came in, which anonymous queries only get every 10 seconds.
+fn fix_age_line(flight: &Flight, theme: &Theme, now: DateTime<Utc>) -> Option<Line<'static>> {
+    let fixed = flight.position_time?;
+    let mut age = format!(
+        "  Fix age:   {}",
+        format_lag((now - fixed).num_seconds().max(0))
+    );
+    let behind = flight
+        .snapshot_time
+        .map_or(0, |s| (s - fixed).num_seconds());
+    if behind > 0 {
+        age.push_str(&format!(", {} older than its snapshot", format_lag(behind)));
+    }
+    Some(Line::from(Span::styled(
+        age,
+        Style::default().fg(theme.dim),
+    )))
+}
+
+/// The destination's METAR and, while the flight has an ETA, the TAF's
+/// forecast for it.
+fn destination_weather(app: &App, flight: &Flight, dest: &Airport) -> Vec<Line<'static>> {
+    let Some(weather) = dest.icao_code().and_then(|icao| app.weather.get(icao)) else {
+        return Vec::new();
+    };
+    let mut lines = Vec::new();
+    if let Some(metar) = &weather.metar {
         lines.push(Line::from(Span::styled(
-            "Press ↑ in input to cycle through history",
-            Style::default().fg(Color::DarkGray),
+            format!("  METAR: {}", metar),
+            Style::default().add_modifier(Modifier::DIM),
         )));
-    } else {
-        lines.push(Line::from(Span::styled(
-            "No flight selected",
-            Style::default().fg(Color::DarkGray),
+    }
+    if let (Some(taf), Some(eta)) = (&weather.taf, flight.eta()) {
+        lines.push(Line::from(format!(
+            "  {}",
+            taf.forecast_at(eta).summary(eta, taf)
         )));
-        lines.push(Line::from(""));
-        lines.push(Line::from("Enter a flight number above to start tracking."));
     }
-
-    lines.push(Line::from(""));
-    lines.push(Line::from(Span::styled(
-        "Controls:",
-        Style::default().add_modifier(Modifier::BOLD),
-    )));
-    lines.push(Line::from("  /     - Add a new flight"));
-    lines.push(Line::from("  ↑/↓   - Browse history (in input)"));
-    lines.push(Line::from("  j/k   - Navigate flights"));
-    lines.push(Line::from("  d     - Remove selected flight"));
-    lines.push(Line::from("  r     - Force refresh"));
-    lines.push(Line::from("  q     - Quit"));
-
     lines
 }
 
-fn format_time(time_str: &str) -> String {
-    // Parse ISO 8601 time and format nicely
-    // Input: "2024-01-15T14:30:00+00:00"
-    // Output: "14:30"
-    if let Some(t_pos) = time_str.find('T') {
-        let time_part = &time_str[t_pos + 1..];
-        if time_part.len() >= 5 {
-            return time_part[..5].to_string();
+/// Words of an airport's name that say nothing about where it is, dropped
+/// first when the name doesn't fit.
+const GENERIC_AIRPORT_WORDS: [&str; 4] = ["International", "Intl", "Airport", "Aerodrome"];
+
+/// `text` cut to at most `width` terminal columns, ending in `…` if
+/// anything was dropped. Wide characters count twice and combining marks
+/// not at all, and no character is split.
+fn truncate_to_width(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut cut = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let columns = c.width().unwrap_or(0);
+        if used + columns > width.saturating_sub(1) {
+            break;
         }
+        used += columns;
+        cut.push(c);
+    }
+    let mut cut = cut.trim_end().to_string();
+    if width > 0 {
+        cut.push('…');
     }
-    time_str.to_string()
+    cut
 }
 
-fn draw_status_bar(frame: &mut Frame, area: Rect, app: &App) {
-    let status = if let Some(err) = &app.last_error {
-        Line::from(Span::styled(
-            format!("Error: {}", err),
-            Style::default().fg(Color::Red),
-        ))
-    } else if app.loading {
-        Line::from(Span::styled(
-            "Loading...",
-            Style::default().fg(Color::Yellow),
-        ))
-    } else if let Some(msg) = &app.status_message {
-        Line::from(Span::styled(msg.clone(), Style::default().fg(Color::Cyan)))
-    } else {
-        let update_info = if let Some(secs) = app.seconds_until_update() {
-            format!(" | Next update in {}s", secs)
-        } else {
-            String::new()
-        };
+/// An airport's code and name in at most `width` columns. The code always
+/// stays; the name first loses its generic words, then its end, so the
+/// city it usually starts with stays readable.
+fn airport_label(code: &str, name: &str, width: usize) -> String {
+    let full = format!("{} {}", code, name).trim_end().to_string();
+    if full.width() <= width {
+        return full;
+    }
+    let name = name
+        .split_whitespace()
+        .filter(|word| !GENERIC_AIRPORT_WORDS.contains(word))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let room = width.saturating_sub(code.width() + 1);
+    if name.is_empty() || room == 0 {
+        return code.to_string();
+    }
+    format!("{} {}", code, truncate_to_width(&name, room))
+}
+
+/// The details of `flight`, with lines that would wrap awkwardly fitted to
+/// `width` columns.
+fn format_flight_details(app: &App, flight: &Flight, width: usize) -> Vec<Line<'static>> {
+    let mut lines = vec![];
+
+    lines.push(Line::from(""));
+
+    // Flight number and callsign
+    let mut flight_line = vec![
+        Span::styled("Flight:  ", Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(flight.flight_number.clone()),
+    ];
+    if !flight.callsign.is_empty() {
+        flight_line.push(Span::styled(
+            format!(" ({})", flight.callsign),
+            Style::default().fg(app.theme.dim),
+        ));
+    }
+    lines.push(Line::from(flight_line));
+
+    // Airline
+    if let Some(airline) = &flight.airline {
+        lines.push(Line::from(vec![
+            Span::styled("Airline: ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(match flight.operating_airline() {
+                Some(operator) => format!("{} (operated by {})", airline, operator),
+                None => airline.clone(),
+            }),
+        ]));
+    }
+
+    // Status
+    let status_color = status_to_color(&flight.status);
+    let mut status_line = vec![
+        Span::styled("Status:  ", Style::default().add_modifier(Modifier::BOLD)),
+        Span::styled(
+            format!("{}", flight.status),
+            Style::default().fg(status_color),
+        ),
+    ];
+    if let Some(airport) = flight.ground_airport() {
+        status_line.push(Span::styled(
+            format!(" at {} {}", airport.icao, airport.name),
+            Style::default().fg(status_color),
+        ));
+    }
+    if let Some(delay) = flight.departure_delay.or(flight.arrival_delay) {
+        if delay > 0 {
+            status_line.push(Span::styled(
+                format!(" (+{}min)", delay),
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+    }
+    lines.push(Line::from(status_line));
+    if let (Some(_), Some(destination)) = (flight.possible_diversion(), &flight.destination) {
+        lines.push(Line::from(Span::styled(
+            format!(
+                "         ⚠ Possible diversion, scheduled for {}",
+                destination.code()
+            ),
+            diversion_style(),
+        )));
+    }
+
+    // Route section
+    if flight.origin.is_some() || flight.destination.is_some() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "Route",
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .add_modifier(Modifier::UNDERLINED),
+        )));
+
+        const LABEL: &str = "  From: ";
+        let room = width.saturating_sub(LABEL.width());
+        if let Some(orig) = &flight.origin {
+            let name = orig.name.as_deref().unwrap_or("");
+            lines.push(Line::from(format!(
+                "  From: {}",
+                airport_label(orig.code(), name, room)
+            )));
+        }
+
+        if let Some(dest) = &flight.destination {
+            let name = dest.name.as_deref().unwrap_or("");
+            lines.push(Line::from(format!(
+                "  To:   {}",
+                airport_label(dest.code(), name, room)
+            )));
+            lines.extend(destination_weather(app, flight, dest));
+        }
+    }
+
+    // Schedule section
+    let scheduled = |field| flight.format_schedule_time(field, app.config.time_format);
+    let departure = scheduled(ScheduleField::DepartureScheduled);
+    let arrival = scheduled(ScheduleField::ArrivalScheduled);
+    if departure.is_some() || arrival.is_some() {
+        lines.push(Line::from(""));
+        let mut header = match flight.flight_date {
+            Some(date) => format!("Schedule ({})", date.format("%Y-%m-%d")),
+            None => "Schedule".to_string(),
+        };
+        if flight.recurring {
+            header.push_str(" ↻ daily");
+        }
+        lines.push(Line::from(Span::styled(
+            header,
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .add_modifier(Modifier::UNDERLINED),
+        )));
+
+        if let Some(time) = departure {
+            let mut dep_line = format!("  Departure:  {}", time);
+            if let Some(actual) = scheduled(ScheduleField::DepartureActual) {
+                dep_line.push_str(&format!(" (actual: {})", actual));
+            } else if let Some(est) = scheduled(ScheduleField::DepartureEstimated) {
+                dep_line.push_str(&format!(" (est: {})", est));
+            }
+            lines.push(Line::from(dep_line));
+        }
+
+        if let Some(time) = arrival {
+            let mut arr_line = format!("  Arrival:    {}", time);
+            if let Some(actual) = scheduled(ScheduleField::ArrivalActual) {
+                arr_line.push_str(&format!(" (actual: {})", actual));
+            } else if let Some(est) = scheduled(ScheduleField::ArrivalEstimated) {
+                arr_line.push_str(&format!(" (est: {})", est));
+            }
+            lines.push(Line::from(arr_line));
+        }
+
+        if let Some(comparison) = flight.eta_comparison() {
+            let style = if comparison.diverges() {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "  ETAs:       {}",
+                    comparison.summary(app.config.time_format)
+                ),
+                style,
+            )));
+        }
+        if let Some(trend) = flight.delay_trend(Utc::now()) {
+            lines.push(Line::from(format!("  Delay:      {}", trend)));
+        }
+        if let Some(block) = flight.block_time() {
+            lines.push(Line::from(format!(
+                "  Block time: {}",
+                format_duration(block)
+            )));
+        }
+        if let Some((elapsed, remaining)) = flight.time_en_route(Utc::now()) {
+            lines.push(Line::from(format!(
+                "  En route:   {} elapsed, {} remaining",
+                format_duration(elapsed),
+                format_duration(remaining)
+            )));
+        }
+
+        if let Some(progress) = flight
+            .journey_progress(Utc::now())
+            .filter(|_| !app.accessible)
+        {
+            lines.push(Line::from(""));
+            lines.push(timeline_bar(progress, &app.theme));
+        }
+    }
+
+    // Position section (only if we have live data)
+    if flight.latitude.is_some() || flight.altitude_ft.is_some() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "Live Position",
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .add_modifier(Modifier::UNDERLINED),
+        )));
+
+        if let Some((lat, lon, estimated)) = displayed_position(app, flight, Utc::now()) {
+            let lat_dir = if lat >= 0.0 { "N" } else { "S" };
+            let lon_dir = if lon >= 0.0 { "E" } else { "W" };
+            let position = format!(
+                "  Position:  {:.4}°{}, {:.4}°{}",
+                lat.abs(),
+                lat_dir,
+                lon.abs(),
+                lon_dir
+            );
+            lines.push(if estimated {
+                Line::from(vec![
+                    Span::styled(position, Style::default().add_modifier(Modifier::DIM)),
+                    Span::styled(" † estimated", Style::default().fg(app.theme.dim)),
+                ])
+            } else {
+                Line::from(position)
+            });
+        }
+
+        if let Some(source) = position_source_line(app, flight) {
+            lines.push(source);
+        }
+        if let Some(age) = fix_age_line(flight, &app.theme, Utc::now()) {
+            lines.push(age);
+        }
+
+        if let Some(alt) = flight.altitude_ft {
+            lines.push(Line::from(format!("  Altitude:  {:.0} ft", alt)));
+        }
+
+        if let Some(hdg) = flight.heading {
+            lines.push(Line::from(format!("  Heading:   {:.0}°", hdg)));
+        }
+
+        if let Some(gs) = flight.ground_speed_kts {
+            lines.push(Line::from(format!("  Speed:     {:.0} kts", gs)));
+        }
+
+        if let Some(vr) = flight.smoothed_vertical_rate() {
+            let vr_str = if vr.abs() < f64::from(app.config.level_below_fpm) {
+                "level".to_string()
+            } else if vr >= 0.0 {
+                format!("+{:.0} ft/min", vr)
+            } else {
+                format!("{:.0} ft/min", vr)
+            };
+            lines.push(Line::from(format!("  Climb:     {}", vr_str)));
+        }
+
+        if let Some(profile) =
+            altitude_profile(&flight.track, PROFILE_WIDTH).filter(|_| !app.accessible)
+        {
+            lines.push(Line::from(format!("  Profile:   {}", profile)));
+        }
+
+        if let Some(km) = flight.distance_to_destination_km() {
+            let route = flight.route_progress().map_or(String::new(), |progress| {
+                format!(
+                    " of {:.0} km, {}% flown",
+                    progress.total_km,
+                    progress.percent()
+                )
+            });
+            lines.push(Line::from(format!("  To dest:   {:.0} km{}", km, route)));
+        }
+
+        if let Some(view) = app
+            .config
+            .observer
+            .and_then(|obs| flight.seen_from(obs.latitude, obs.longitude))
+        {
+            lines.push(Line::from(format!(
+                "  From you:  {:.0} km away, bearing {:.0}° {}",
+                view.distance_km,
+                view.bearing,
+                geo::compass_point(view.bearing)
+            )));
+            if let Some(elevation) = view.elevation {
+                let elevation = if elevation < 0.0 {
+                    "below horizon".to_string()
+                } else {
+                    format!("{:.0}° above horizon", elevation)
+                };
+                lines.push(Line::from(format!("  Elevation: {}", elevation)));
+            }
+        }
+    }
+
+    // Alert rules, and the arriving-soon notice
+    let arrival_notice = app.arrival_notice(flight);
+    if !flight.alerts.is_empty() || arrival_notice.is_some() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "Alerts",
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .add_modifier(Modifier::UNDERLINED),
+        )));
+
+        for rule in &flight.alerts {
+            let (marker, color) = if rule.armed {
+                ("armed", Color::Magenta)
+            } else {
+                ("fired", app.theme.dim)
+            };
+            lines.push(Line::from(vec![
+                Span::raw(format!("  {} ", rule.condition)),
+                Span::styled(format!("[{}]", marker), Style::default().fg(color)),
+            ]));
+        }
+        if let Some((due, sent)) = arrival_notice {
+            let (marker, color) = if sent {
+                ("fired", app.theme.dim)
+            } else {
+                ("armed", Color::Magenta)
+            };
+            lines.push(Line::from(vec![
+                Span::raw(format!(
+                    "  arriving soon at {} UTC ({}m before ETA) ",
+                    app.config.time_format.format(due, false),
+                    app.config.arrival_notice_mins
+                )),
+                Span::styled(format!("[{}]", marker), Style::default().fg(color)),
+            ]));
+        }
+    }
+
+    // Aircraft info
+    if flight.aircraft_type.is_some()
+        || flight.registration.is_some()
+        || flight.category.is_some()
+        || !flight.icao24.is_empty()
+    {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "Aircraft",
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .add_modifier(Modifier::UNDERLINED),
+        )));
+
+        if let Some(aircraft) = &flight.aircraft_type {
+            lines.push(Line::from(format!(
+                "  Type:      {}",
+                app.aircraft_types.describe(aircraft)
+            )));
+        }
+
+        if let Some(category) = flight.category {
+            lines.push(Line::from(format!("  Category:  {}", category)));
+        }
+
+        if let Some(reg) = &flight.registration {
+            lines.push(Line::from(format!("  Reg:       {}", reg)));
+        }
+
+        if !flight.icao24.is_empty() {
+            lines.push(Line::from(format!("  ICAO24:    {}", flight.icao24)));
+        }
+
+        if let Some(code) = &flight.squawk {
+            let style = if SquawkKind::classify(code).is_emergency() {
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            lines.push(Line::from(vec![
+                Span::raw("  Squawk:    "),
+                Span::styled(squawk::describe(code), style),
+            ]));
+        }
+    }
+
+    // Not found message
+    if flight.status == FlightStatus::NotFound && flight.origin.is_none() {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "No data available for this flight.",
+            Style::default().fg(Color::Yellow),
+        )));
+        lines.push(Line::from("The flight may not be active or"));
+        lines.push(Line::from("the flight number may be incorrect."));
+        if let Some(near) = app.history.near_match(&flight.flight_number) {
+            lines.push(Line::from(vec![
+                Span::raw("Did you mean "),
+                Span::styled(
+                    near.to_string(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::raw("?"),
+            ]));
+        }
+        lines.push(Line::from(Span::styled(
+            "Enter or R searches again.",
+            Style::default().fg(app.theme.dim),
+        )));
+    }
+
+    // Last updated
+    if let Some(updated) = flight.last_updated {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            format!(
+                "Updated: {} UTC",
+                app.config.time_format.format(updated, true)
+            ),
+            Style::default().fg(app.theme.dim),
+        )));
+    }
+
+    // Position refresh cadence
+    if !flight.icao24.is_empty() {
+        let phase = flight.phase();
+        let cadence = match app.flight_refresh_interval(flight) {
+            _ if phase == FlightPhase::Finished => format!("stopped ({})", phase),
+            Some(secs) => format!("every {}s ({})", secs, phase),
+            None if app.budget == Some(BudgetPlan::Exhausted) => {
+                "manual (daily credits used up)".to_string()
+            }
+            None => "paused".to_string(),
+        };
+        lines.push(Line::from(Span::styled(
+            format!("Refresh: {}", cadence),
+            Style::default().fg(app.theme.dim),
+        )));
+    }
+
+    lines
+}
+
+/// What the previous session held, shown until it is refreshed or pruned.
+fn format_restore_summary(restore: &RestoreSummary, theme: &Theme) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            restore.message(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    if !restore.likely_landed.is_empty() {
+        lines.push(Line::from(vec![
+            Span::raw("Likely landed: "),
+            Span::styled(
+                restore.likely_landed.join(", "),
+                Style::default().fg(Color::Blue),
+            ),
+        ]));
+        lines.push(Line::from(""));
+    }
+    let wait = restore.resume_at.saturating_duration_since(Instant::now());
+    lines.push(Line::from(Span::styled(
+        format!("Refreshing automatically in {}s", wait.as_secs()),
+        Style::default().fg(theme.dim),
+    )));
+    lines
+}
+
+/// The first-run walkthrough: what the app does, where its files go, and
+/// a flight to try.
+fn format_onboarding(app: &App) -> Vec<Line<'static>> {
+    let heading = Style::default()
+        .add_modifier(Modifier::BOLD)
+        .add_modifier(Modifier::UNDERLINED);
+    let key = Style::default().fg(Color::Yellow);
+    let dim = Style::default().fg(app.theme.dim);
+    let dir = app
+        .onboarding_dir
+        .as_ref()
+        .map_or("the config directory".to_string(), |d| {
+            d.display().to_string()
+        });
+
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled("Welcome to Flight Tracker", heading)),
+        Line::from(""),
+        Line::from("Track flights by number: live positions from OpenSky, routes and"),
+        Line::from("delays from AviationStack, alerts when they get close or land."),
+        Line::from(""),
+        Line::from("Positions work without any setup. Keys for schedules and higher"),
+        Line::from("OpenSky limits go in a .env file, preferences in config.toml."),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  c     ", key),
+            Span::raw(format!("Create commented config.toml and .env in {}", dir)),
+        ]),
+        Line::from(vec![
+            Span::styled("  Enter ", key),
+            Span::raw(format!(
+                "Start with a demo search for {}",
+                onboarding::SUGGESTED_FLIGHT
+            )),
+        ]),
+        Line::from(vec![Span::styled("  Esc   ", key), Span::raw("Skip")]),
+    ];
+    let warnings = app.capabilities.warnings();
+    if !warnings.is_empty() {
+        lines.push(Line::from(""));
+        for warning in warnings {
+            lines.push(Line::from(Span::styled(warning, dim)));
+        }
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "This is only shown on the first run.",
+        dim,
+    )));
+    lines
+}
+
+fn format_empty_state(app: &App) -> Vec<Line<'static>> {
+    let mut lines = vec![];
+
+    lines.push(Line::from(""));
+
+    // Show history if available
+    if !app.history.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "Recent Flights",
+            Style::default()
+                .add_modifier(Modifier::BOLD)
+                .add_modifier(Modifier::UNDERLINED),
+        )));
+        lines.push(Line::from(""));
+
+        for (i, entry) in app.history.entries().take(8).enumerate() {
+            let route_str = entry
+                .route
+                .as_ref()
+                .map(|r| format!(" {}", r))
+                .unwrap_or_default();
+
+            let style = if app.history_index == Some(i) {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::Cyan)
+            };
+
+            lines.push(Line::from(vec![
+                Span::raw("  "),
+                Span::styled(entry.flight_number.clone(), style),
+                Span::styled(route_str, Style::default().fg(app.theme.dim)),
+            ]));
+        }
+
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "Press ↑ in input to cycle through history",
+            Style::default().fg(app.theme.dim),
+        )));
+    } else {
+        lines.push(Line::from(Span::styled(
+            "No flight selected",
+            Style::default().fg(app.theme.dim),
+        )));
+        lines.push(Line::from(""));
+        lines.push(Line::from("Enter a flight number above to start tracking."));
+    }
+
+    let warnings = app.capabilities.warnings();
+    if !warnings.is_empty() {
+        lines.push(Line::from(""));
+        for warning in warnings {
+            lines.push(Line::from(Span::styled(
+                warning,
+                Style::default().fg(Color::Yellow),
+            )));
+        }
+        lines.push(Line::from(Span::styled(
+            ENV_FILE_HINT,
+            Style::default().fg(app.theme.dim),
+        )));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Controls:",
+        Style::default().add_modifier(Modifier::BOLD),
+    )));
+    lines.push(Line::from("  /     - Add a new flight"));
+    lines.push(Line::from("  ↑/↓   - Browse history (in input)"));
+    lines.push(Line::from("  Enter - Edit and re-track selected flight"));
+    lines.push(Line::from("  .     - Re-track most recent flight"));
+    lines.push(Line::from("  j/k   - Navigate flights"));
+    lines.push(Line::from("  d     - Remove selected flight"));
+    lines.push(Line::from("  f     - Filter: all / active / problems"));
+    lines.push(Line::from(
+        "  ^F    - Filter by flight, callsign, airline or airport",
+    ));
+    lines.push(Line::from("  A     - Expand/collapse arrived flights"));
+    lines.push(Line::from(
+        "  1-5   - Details / Map / Log / Weather / Raw tab",
+    ));
+    lines.push(Line::from("  w     - Set alert on selected flight"));
+    lines.push(Line::from("  i     - Import flights from file"));
+    lines.push(Line::from("  M     - Toggle map overview"));
+    lines.push(Line::from("  P     - Toggle altitude profile chart"));
+    lines.push(Line::from("  t     - Toggle 12/24-hour times"));
+    lines.push(Line::from("  R     - Roll selected flight over daily"));
+    lines.push(Line::from("  E     - Export report (Markdown)"));
+    lines.push(Line::from("  D     - Diagnostics"));
+    lines.push(Line::from("  r     - Force refresh"));
+    lines.push(Line::from(
+        "  :     - Command palette (export, clear cache, ...)",
+    ));
+    lines.push(Line::from("  q     - Quit"));
+
+    lines
+}
+
+/// Journey bar from departure to arrival: time spent waiting past the
+/// scheduled departure in yellow, time flown in green, the rest dimmed, and
+/// a plane at the current time.
+fn timeline_bar(progress: JourneyProgress, theme: &Theme) -> Line<'static> {
+    const WIDTH: usize = 30;
+
+    let cell = |fraction: f64| (fraction * (WIDTH - 1) as f64).round() as usize;
+    let now = cell(progress.now);
+    let departed = progress.departed.map(cell);
+
+    let mut spans = vec![Span::raw("  ")];
+    for i in 0..WIDTH {
+        let span = if i == now {
+            Span::styled("✈", Style::default().add_modifier(Modifier::BOLD))
+        } else if i > now {
+            Span::styled("─", Style::default().fg(theme.dim))
+        } else if departed.is_none_or(|d| i < d) {
+            Span::styled("━", Style::default().fg(Color::Yellow))
+        } else {
+            Span::styled("━", Style::default().fg(Color::Green))
+        };
+        spans.push(span);
+    }
+    Line::from(spans)
+}
+
+/// "45s", or "3m 05s" once the feed is minutes behind.
+fn format_lag(secs: i64) -> String {
+    if secs < 60 {
+        format!("{}s", secs)
+    } else {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    }
+}
+
+/// Spinner glyph for the current tenth of a second.
+fn spinner_frame(now: DateTime<Utc>, ascii_only: bool) -> &'static str {
+    let frames: &[&str] = if ascii_only {
+        &glyphs::ASCII_SPINNER
+    } else {
+        &glyphs::SPINNER
+    };
+    frames[(now.timestamp_millis() / 100).rem_euclid(frames.len() as i64) as usize]
+}
 
-        Line::from(vec![
-            Span::raw(format!(
-                "Tracking {} flight(s){}",
-                app.tracked_flights.len(),
-                update_info
-            )),
-            Span::raw(" | "),
+/// Columns in the details pane's altitude profile.
+const PROFILE_WIDTH: usize = 32;
+
+/// Sparkline of altitude along a track, in `width` columns spanning its
+/// first to last report. Each column shows the highest altitude reported
+/// in its stretch of time, scaled from the ground to the track's peak; a
+/// column without reports repeats the one before. `None` until the track
+/// has two altitudes at different times.
+fn altitude_profile(track: &[TrackPoint], width: usize) -> Option<String> {
+    const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let points: Vec<(i64, f64)> = track
+        .iter()
+        .filter_map(|p| Some((p.at.timestamp(), p.altitude_ft?.max(0.0))))
+        .collect();
+    let (first, last) = (points.first()?.0, points.last()?.0);
+    if last <= first || width == 0 {
+        return None;
+    }
+    let peak = points.iter().map(|p| p.1).fold(0.0, f64::max);
+
+    let mut columns: Vec<Option<f64>> = vec![None; width];
+    for (time, altitude) in points {
+        let fraction = (time - first) as f64 / (last - first) as f64;
+        let column = &mut columns[(fraction * (width - 1) as f64).round() as usize];
+        *column = Some(column.map_or(altitude, |a| a.max(altitude)));
+    }
+
+    let mut previous = 0.0;
+    let profile = columns
+        .into_iter()
+        .map(|altitude| {
+            previous = altitude.unwrap_or(previous);
+            let level = if peak > 0.0 {
+                (previous / peak * (LEVELS.len() - 1) as f64).round() as usize
+            } else {
+                0
+            };
+            LEVELS[level]
+        })
+        .collect();
+    Some(profile)
+}
+
+/// Between segments of the dashboard line.
+const SEGMENT_SEPARATOR: &str = " · ";
+
+/// The dashboard line, e.g. "5 tracked · 3 en route · next update 12s ·
+/// OpenSky ok · AviationStack 37/100". Segments are dropped from the right
+/// until the line fits in `width` columns, but the tracked count always
+/// stays. States no flight is in are left out.
+fn dashboard_spans(
+    theme: &Theme,
+    counts: FlightCounts,
+    next_update: Option<u64>,
+    providers: &[ProviderHealth],
+    monthly_limit: u32,
+    width: usize,
+) -> Vec<Span<'static>> {
+    let mut segments = vec![Span::raw(format!("{} tracked", counts.tracked))];
+    for (count, label, color) in [
+        (counts.en_route, "en route", Color::Blue),
+        (counts.delayed, "delayed", Color::Yellow),
+        (counts.landed, "landed", Color::Green),
+    ] {
+        if count > 0 {
+            segments.push(Span::styled(
+                format!("{} {}", count, label),
+                Style::default().fg(color),
+            ));
+        }
+    }
+    if let Some(secs) = next_update {
+        segments.push(Span::raw(format!("next update {}s", secs)));
+    }
+    for provider in providers {
+        let (state, color) = match provider.requests_this_month {
+            _ if provider.degraded => ("degraded".to_string(), Color::Red),
+            _ if provider.stats.is_failing() => ("failing".to_string(), Color::Red),
+            Some(used) if used >= monthly_limit => {
+                (format!("{}/{}", used, monthly_limit), Color::Yellow)
+            }
+            Some(used) => (format!("{}/{}", used, monthly_limit), Color::Green),
+            None if provider.stats.last_success.is_some() => ("ok".to_string(), Color::Green),
+            None => ("idle".to_string(), theme.dim),
+        };
+        segments.push(Span::styled(
+            format!("{} {}", provider.name, state),
+            Style::default().fg(color),
+        ));
+    }
+
+    let separators = |n: usize| n.saturating_sub(1) * SEGMENT_SEPARATOR.chars().count();
+    let mut used = segments.iter().map(Span::width).sum::<usize>() + separators(segments.len());
+    while used > width && segments.len() > 1 {
+        let dropped = segments.pop().map_or(0, |s| s.width());
+        used -= dropped + SEGMENT_SEPARATOR.chars().count();
+    }
+
+    let mut spans = Vec::with_capacity(segments.len() * 2);
+    for (i, segment) in segments.into_iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::styled(
+                SEGMENT_SEPARATOR,
+                Style::default().fg(theme.dim),
+            ));
+        }
+        spans.push(segment);
+    }
+    spans
+}
+
+fn draw_status_bar(frame: &mut Frame, area: Rect, app: &App) {
+    let mut dashboard = dashboard_spans(
+        &app.theme,
+        app.flight_counts(),
+        app.seconds_until_update(),
+        &app.provider_health,
+        app.config.aviationstack_monthly_limit,
+        area.width.saturating_sub(4) as usize,
+    );
+    dashboard.insert(0, Span::raw(" "));
+    dashboard.push(Span::raw(" "));
+    let status_bar = Paragraph::new(status_line(app)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(Line::from(dashboard)),
+    );
+
+    frame.render_widget(status_bar, area);
+}
+
+/// The status bar's text: a confirmation, alert, error or message when
+/// there is one, otherwise the view's state and the main keys.
+fn status_line(app: &App) -> Line<'static> {
+    if let Some(kind) = app.pending_removal {
+        let what = match kind {
+            BulkRemoval::Finished => "landed, cancelled and unfound",
+            BulkRemoval::All => "tracked",
+        };
+        Line::from(Span::styled(
+            format!(
+                "Remove all {} {} flight(s)? y to confirm, any other key cancels",
+                app.pending_removal_count(),
+                what
+            ),
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        ))
+    } else if let Some(alert) = &app.alert_message {
+        Line::from(Span::styled(
+            format!("ALERT: {}", alert),
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        ))
+    } else if let Some(err) = &app.last_error {
+        Line::from(Span::styled(
+            format!("Error: {}", err),
+            Style::default().fg(Color::Red),
+        ))
+    } else if app.loading {
+        // A spinning glyph would be read out on every frame
+        let text = if app.accessible {
+            "Loading...".to_string()
+        } else {
+            format!(
+                "{} Loading...",
+                spinner_frame(Utc::now(), app.config.ascii_only)
+            )
+        };
+        Line::from(Span::styled(text, Style::default().fg(Color::Yellow)))
+    } else if let Some(msg) = &app.status_message {
+        Line::from(Span::styled(msg.clone(), Style::default().fg(Color::Cyan)))
+    } else {
+        let mut update_info = vec![];
+        let mut filters = vec![];
+        if app.filter != ListFilter::All {
+            filters.push(format!("filter: {}", app.filter));
+        }
+        if !app.list_query.is_empty() {
+            filters.push(format!("search: \"{}\"", app.list_query));
+        }
+        if !filters.is_empty() {
+            update_info.push(format!(
+                "{} ({}/{})",
+                filters.join(", "),
+                app.visible_indices().len(),
+                app.tracked_flights.len()
+            ));
+        }
+        if !app.focused {
+            if app.background_multiplier == 0 {
+                update_info.push("Background (paused)".to_string());
+            } else {
+                update_info.push(format!(
+                    "Background (x{} interval)",
+                    app.background_multiplier
+                ));
+            }
+        }
+        if let Some(BudgetPlan::Interval(secs)) = app.budget {
+            if secs > app.config.min_refresh_secs {
+                update_info.push(format!("Budget: every {}s", secs));
+            }
+        }
+
+        // Counts and the next update are in the dashboard line above
+        let mut spans = vec![];
+        if let Some(flight_number) = &app.following {
+            spans.push(Span::styled(
+                format!("FOLLOW {}", flight_number),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        if !update_info.is_empty() {
+            if !spans.is_empty() {
+                spans.push(Span::raw(" | "));
+            }
+            spans.push(Span::raw(update_info.join(" | ")));
+        }
+        if let Some(feed) = app.lagging_feed() {
+            spans.push(Span::raw(" | "));
+            spans.push(Span::styled(
+                format!(
+                    "feed time {} UTC ({} behind)",
+                    app.config.time_format.format(feed.snapshot, true),
+                    format_lag(feed.lag_secs)
+                ),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        if app.budget == Some(BudgetPlan::Exhausted) {
+            spans.push(Span::raw(" | "));
+            spans.push(Span::styled(
+                "daily OpenSky credits used up, press r to refresh",
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        }
+        if !spans.is_empty() {
+            spans.push(Span::raw(" | "));
+        }
+        spans.extend([
             Span::styled("q", Style::default().fg(Color::Yellow)),
             Span::raw(" quit  "),
             Span::styled("/", Style::default().fg(Color::Yellow)),
             Span::raw(" add  "),
             Span::styled("d", Style::default().fg(Color::Yellow)),
             Span::raw(" delete  "),
+            Span::styled("w", Style::default().fg(Color::Yellow)),
+            Span::raw(" alert  "),
             Span::styled("r", Style::default().fg(Color::Yellow)),
             Span::raw(" refresh"),
-        ])
-    };
+        ]);
+        Line::from(spans)
+    }
+}
 
-    let status_bar = Paragraph::new(status).block(Block::default().borders(Borders::ALL));
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::ProviderStats;
+    use crate::fixtures;
 
-    frame.render_widget(status_bar, area);
+    fn text(spans: &[Span]) -> String {
+        spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    fn providers(opensky_ok: bool, used: u32) -> Vec<ProviderHealth> {
+        let now = Utc::now();
+        vec![
+            ProviderHealth {
+                name: "OpenSky",
+                role: "position",
+                stats: ProviderStats {
+                    calls: 3,
+                    last_success: opensky_ok.then_some(now),
+                    last_failure: Some((now - chrono::Duration::minutes(5), "timeout".into())),
+                },
+                degraded: false,
+                requests_this_month: None,
+                auth: None,
+            },
+            ProviderHealth {
+                name: "AviationStack",
+                role: "schedule",
+                stats: ProviderStats::default(),
+                degraded: false,
+                requests_this_month: Some(used),
+                auth: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_dashboard_fits_width() {
+        let counts = FlightCounts {
+            tracked: 5,
+            en_route: 3,
+            delayed: 1,
+            landed: 1,
+        };
+        let providers = providers(true, 37);
+        let dark = &Theme::DARK;
+        let full = "5 tracked · 3 en route · 1 delayed · 1 landed · next update 12s \
+                    · OpenSky ok · AviationStack 37/100";
+
+        let spans = dashboard_spans(dark, counts, Some(12), &providers, 100, 200);
+        assert_eq!(text(&spans), full);
+        let exact = full.chars().count();
+        assert_eq!(
+            text(&dashboard_spans(
+                dark,
+                counts,
+                Some(12),
+                &providers,
+                100,
+                exact
+            )),
+            full
+        );
+
+        // Segments go from the right, whole
+        let spans = dashboard_spans(dark, counts, Some(12), &providers, 100, exact - 1);
+        assert_eq!(
+            text(&spans),
+            "5 tracked · 3 en route · 1 delayed · 1 landed · next update 12s · OpenSky ok"
+        );
+        let spans = dashboard_spans(dark, counts, Some(12), &providers, 100, 30);
+        assert_eq!(text(&spans), "5 tracked · 3 en route");
+        // The tracked count stays however narrow
+        let spans = dashboard_spans(dark, counts, Some(12), &providers, 100, 3);
+        assert_eq!(text(&spans), "5 tracked");
+    }
+
+    #[test]
+    fn test_dashboard_segments() {
+        let counts = FlightCounts {
+            tracked: 2,
+            en_route: 2,
+            ..Default::default()
+        };
+        let dark = &Theme::DARK;
+        // Empty states, an unknown update time and providers are left out
+        assert_eq!(
+            text(&dashboard_spans(dark, counts, None, &[], 100, 80)),
+            "2 tracked · 2 en route"
+        );
+
+        let spans = dashboard_spans(dark, counts, None, &providers(false, 100), 100, 80);
+        assert_eq!(
+            text(&spans),
+            "2 tracked · 2 en route · OpenSky failing · AviationStack 100/100"
+        );
+        let colors: Vec<_> = spans.iter().map(|s| s.style.fg).collect();
+        assert_eq!(colors[4], Some(Color::Red));
+        assert_eq!(colors[6], Some(Color::Yellow));
+
+        let idle = [ProviderHealth {
+            name: "OpenSky",
+            ..Default::default()
+        }];
+        let spans = dashboard_spans(dark, counts, None, &idle, 100, 80);
+        assert_eq!(text(&spans), "2 tracked · 2 en route · OpenSky idle");
+        // Idle providers and separators are dimmed to suit the background
+        let spans = dashboard_spans(&Theme::LIGHT, counts, None, &idle, 100, 80);
+        assert_eq!(spans[3].style.fg, Some(Theme::LIGHT.dim));
+        assert_eq!(spans[4].style.fg, Some(Theme::LIGHT.dim));
+    }
+
+    fn track(points: &[(i64, Option<f64>)]) -> Vec<TrackPoint> {
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        points
+            .iter()
+            .map(|&(mins, altitude_ft)| TrackPoint {
+                at: start + chrono::Duration::minutes(mins),
+                latitude: 51.0,
+                longitude: -1.0,
+                altitude_ft,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_altitude_profile() {
+        // Climb, cruise, descent over an hour
+        let flight = track(&[
+            (0, Some(0.0)),
+            (10, Some(20_000.0)),
+            (20, Some(36_000.0)),
+            (40, Some(36_000.0)),
+            (50, Some(18_000.0)),
+            (60, Some(0.0)),
+        ]);
+        assert_eq!(altitude_profile(&flight, 7).as_deref(), Some("▁▅███▅▁"));
+
+        // Columns without reports carry the last altitude across the gap
+        let gap = track(&[(0, Some(0.0)), (10, Some(35_000.0)), (60, Some(35_000.0))]);
+        assert_eq!(altitude_profile(&gap, 7).as_deref(), Some("▁██████"));
+    }
+
+    #[test]
+    fn test_chart_labels() {
+        assert_eq!(elapsed_labels(50), vec!["0m", "25m", "50m"]);
+        assert_eq!(elapsed_labels(119), vec!["0m", "59m", "119m"]);
+        assert_eq!(elapsed_labels(660), vec!["0h00", "5h30", "11h00"]);
+        assert_eq!(altitude_labels(40_000.0), vec!["0", "20k", "40k ft"]);
+        assert_eq!(altitude_labels(5_000.0), vec!["0", "2k", "5k ft"]);
+    }
+
+    #[test]
+    fn test_altitude_chart_marks_cruise() {
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let mut app = App::default();
+        app.add_flight("BA285".to_string(), None, None);
+        app.tracked_flights[0].track = track(&[
+            (0, Some(0.0)),
+            (20, Some(35_000.0)),
+            (300, Some(36_000.0)),
+            (340, Some(10_000.0)),
+            (360, Some(0.0)),
+        ]);
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal
+            .draw(|frame| draw_altitude_chart(frame, frame.area(), &app))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        let screen: String = buffer.content.iter().map(|cell| cell.symbol()).collect();
+        for text in ["top of climb", "top of descent", "6h00", "40k ft"] {
+            assert!(screen.contains(text), "{text} missing");
+        }
+    }
+
+    #[test]
+    fn test_ascii_only_draws_nothing_else() {
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let render = |app: &App| {
+            let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+            terminal.draw(|frame| draw(frame, app)).unwrap();
+            let buffer = terminal.backend().buffer();
+            buffer
+                .content
+                .iter()
+                .map(|cell| cell.symbol())
+                .collect::<String>()
+        };
+        let mut app = App::default();
+        app.mode = AppMode::Viewing;
+        app.config.ascii_only = true;
+        // Saved with the Unicode arrow before ASCII mode was turned on
+        app.history
+            .add("UA900".to_string(), Some("SFO→FRA".to_string()));
+        let screen = render(&app);
+        assert!(screen.is_ascii(), "{screen}");
+        assert!(
+            screen.contains("UA900 SFO>FRA") && screen.contains("+----"),
+            "{screen}"
+        );
+        app.add_flight("BA285".to_string(), None, None);
+        app.tracked_flights[0] = Flight {
+            status: FlightStatus::EnRoute,
+            origin: Some(fixtures::airport("LHR")),
+            destination: Some(fixtures::airport("SFO")),
+            latitude: Some(60.0),
+            longitude: Some(-40.0),
+            heading: Some(270.0),
+            altitude_ft: Some(36_000.0),
+            track: track(&[(0, Some(0.0)), (20, Some(35_000.0)), (300, Some(36_000.0))]),
+            ..app.tracked_flights[0].clone()
+        };
+        app.selected_index = Some(0);
+        app.loading = true;
+        let screen = render(&app);
+        assert!(screen.is_ascii(), "{screen}");
+        assert!(screen.contains("> BA285  LHR>SFO En Route"), "{screen}");
+
+        app.show_profile = true;
+        app.right_panel = RightPanel::Map;
+        assert!(render(&app).is_ascii());
+        app.mode = AppMode::Settings;
+        assert!(render(&app).is_ascii());
+        app.mode = AppMode::Viewing;
+        app.config.layout = ScreenLayout::Ticker;
+        assert!(render(&app).is_ascii());
+
+        // The same screens still draw Unicode with the flag off
+        app.config.ascii_only = false;
+        assert!(!render(&app).is_ascii());
+    }
+
+    #[test]
+    fn test_tiny_terminal_shows_a_message() {
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let mut app = App::default();
+        app.add_flight("BA285".to_string(), None, None);
+        app.tracked_flights[0].track = track(&[(0, Some(0.0)), (20, Some(35_000.0))]);
+        app.show_profile = true;
+
+        let mut terminal = Terminal::new(TestBackend::new(48, 12)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let screen: String = buffer.content.iter().map(|cell| cell.symbol()).collect();
+        assert!(
+            screen.contains("terminal too small — need 60×16, have 48×12"),
+            "{screen}"
+        );
+
+        // Down to nothing at all, and the smallest full layout, don't panic
+        for (width, height) in [(1, 1), (0, 0), (200, 3), (MIN_WIDTH, MIN_HEIGHT)] {
+            let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+            terminal.draw(|frame| draw(frame, &app)).unwrap();
+        }
+        app.right_panel = RightPanel::Map;
+        let mut terminal = Terminal::new(TestBackend::new(MIN_WIDTH, MIN_HEIGHT)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let screen: String = buffer.content.iter().map(|cell| cell.symbol()).collect();
+        assert!(!screen.contains("too small"));
+    }
+
+    #[test]
+    fn test_position_source_line() {
+        let app = App::default();
+        let text =
+            |flight: &Flight| position_source_line(&app, flight).map(|line| line.to_string());
+        let mut flight = Flight::default();
+        assert_eq!(text(&flight), None);
+
+        flight.position_source = Some(PositionSource::OpenSky);
+        assert_eq!(text(&flight).as_deref(), Some("  Source:    OpenSky"));
+
+        flight.position_source = Some(PositionSource::AviationStack);
+        flight.position_time = DateTime::from_timestamp(1_721_000_520, 0);
+        assert_eq!(
+            text(&flight).as_deref(),
+            Some("  Source:    AviationStack, as of 23:42 UTC (less fresh)")
+        );
+    }
+
+    #[test]
+    fn test_fix_age_line() {
+        let now = DateTime::from_timestamp(1_721_000_100, 0).unwrap();
+        let text =
+            |flight: &Flight| fix_age_line(flight, &Theme::DARK, now).map(|line| line.to_string());
+        let mut flight = Flight::default();
+        assert_eq!(text(&flight), None);
+
+        flight.position_time = DateTime::from_timestamp(1_721_000_086, 0);
+        assert_eq!(text(&flight).as_deref(), Some("  Fix age:   14s"));
+        flight.snapshot_time = DateTime::from_timestamp(1_721_000_095, 0);
+        assert_eq!(
+            text(&flight).as_deref(),
+            Some("  Fix age:   14s, 9s older than its snapshot")
+        );
+        flight.position_time = DateTime::from_timestamp(1_721_000_000, 0);
+        flight.snapshot_time = flight.position_time;
+        assert_eq!(text(&flight).as_deref(), Some("  Fix age:   1m 40s"));
+    }
+
+    #[test]
+    fn test_diagnostics_show_data_resolution() {
+        let provider = |name, resolution: Option<&str>| crate::api::ProviderDiagnostics {
+            name,
+            auth: "anonymous".to_string(),
+            stats: ProviderStats::default(),
+            cache: Default::default(),
+            breaker: Default::default(),
+            resolution: resolution.map(str::to_string),
+        };
+        let diagnostics = Diagnostics {
+            providers: vec![
+                ("position", provider("OpenSky", Some("10s (anonymous)"))),
+                ("schedule", provider("AviationStack", None)),
+            ],
+            data_dir: None,
+            config_path: None,
+            log_path: None,
+        };
+        let mut app = App::default();
+        let resolution = |app: &App| -> Vec<String> {
+            diagnostics_lines(app, &diagnostics)
+                .iter()
+                .map(Line::to_string)
+                .filter(|line| line.contains("Resolution"))
+                .collect()
+        };
+        assert_eq!(resolution(&app), ["  Resolution:   10s (anonymous)"]);
+
+        app.feed_time = Some(crate::app::FeedTime {
+            snapshot: Utc::now(),
+            lag_secs: 12,
+        });
+        assert_eq!(
+            resolution(&app),
+            ["  Resolution:   10s (anonymous), latest snapshot 12s old on arrival"]
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_count_rejected_readings() {
+        // The demo serves both roles under one name
+        let demo = crate::api::ProviderDiagnostics {
+            name: "Demo",
+            auth: "offline".to_string(),
+            stats: ProviderStats::default(),
+            cache: Default::default(),
+            breaker: Default::default(),
+            resolution: None,
+        };
+        let diagnostics = Diagnostics {
+            providers: vec![("position", demo.clone()), ("schedule", demo)],
+            data_dir: None,
+            config_path: None,
+            log_path: None,
+        };
+        let mut app = App::default();
+        let rejected = |app: &App| -> Vec<String> {
+            diagnostics_lines(app, &diagnostics)
+                .iter()
+                .map(Line::to_string)
+                .filter(|line| line.contains("Rejected"))
+                .collect()
+        };
+        assert!(rejected(&app).is_empty());
+
+        app.rejected_readings.insert(Reading::GroundSpeed, 3);
+        app.rejected_readings.insert(Reading::Altitude, 1);
+        assert_eq!(
+            rejected(&app),
+            ["  Rejected:     altitude 1, ground speed 3 (implausible readings)"]
+        );
+    }
+
+    #[test]
+    fn test_raw_payload_lines() {
+        let mut flight = Flight {
+            raw_schedule: Some("{\n  \"flight_status\": \"active\"\n}".to_string()),
+            ..Default::default()
+        };
+        let text = |flight: &Flight| -> Vec<String> {
+            raw_payload_lines(flight, &Theme::DARK)
+                .iter()
+                .map(Line::to_string)
+                .collect()
+        };
+        assert_eq!(
+            text(&flight),
+            [
+                "OpenSky state vector",
+                "Nothing received yet",
+                "",
+                "AviationStack flight",
+                "{",
+                "  \"flight_status\": \"active\"",
+                "}",
+            ]
+        );
+        flight.raw_state = Some("[]".to_string());
+        assert_eq!(text(&flight)[1], "[]");
+    }
+
+    #[test]
+    fn test_follow_marks_list_and_status_bar() {
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let mut app = App::default();
+        app.mode = AppMode::Viewing;
+        app.add_flight("UA123".to_string(), None, None);
+        app.add_flight("BA285".to_string(), None, None);
+        app.selected_index = Some(0);
+        app.toggle_follow();
+        app.selected_index = Some(1);
+        app.status_message = None;
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let screen: String = buffer.content.iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("FOLLOW UA123"), "{screen}");
+        assert!(screen.contains("◎ UA123"));
+        assert!(screen.contains("> BA285"));
+        assert!(screen.contains("Flight:  UA123"));
+    }
+
+    #[test]
+    fn test_ticker_layout() {
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let mut app = App::default();
+        app.mode = AppMode::Viewing;
+        app.toggle_layout();
+        for number in ["UA123", "BA285", "LH400"] {
+            app.add_flight(number.to_string(), None, None);
+        }
+        app.tracked_flights[0].status = FlightStatus::EnRoute;
+        app.tracked_flights[0].altitude_ft = Some(36_000.0);
+        app.tracked_flights[0].ground_speed_kts = Some(480.0);
+        app.selected_index = Some(0);
+        app.status_message = Some("Ticker".to_string());
+
+        let render = |app: &App, width: u16, height: u16| {
+            let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+            terminal.draw(|frame| draw(frame, app)).unwrap();
+            terminal
+                .backend()
+                .buffer()
+                .content
+                .chunks(usize::from(width))
+                .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+                .map(|row| row.trim_end().to_string())
+                .collect::<Vec<_>>()
+        };
+        // Far below the full layout's minimum, without borders
+        let rows = render(&app, 24, 5);
+        let expected = [
+            "UA123 ✈ FL360 480kt",
+            "BA285 Not Found",
+            "LH400 Not Found",
+            "",
+            "Ticker",
+        ];
+        assert_eq!(rows, expected);
+
+        // Narrower drops facts, and the list scrolls to keep the selection
+        app.selected_index = Some(2);
+        assert_eq!(render(&app, 12, 3), ["BA285", "LH400", "Ticker"]);
+
+        // Typing opens the input box at the top, above the selection
+        app.apply(crate::action::Action::BeginInput);
+        let rows = render(&app, 24, 5);
+        assert!(
+            rows[0].starts_with('┌') && rows[2].starts_with('└'),
+            "{rows:?}"
+        );
+        assert_eq!(rows[3], "LH400 Not Found");
+        app.apply(crate::action::Action::CancelInput);
+        assert_eq!(render(&app, 24, 5)[0], "UA123 ✈ FL360 480kt");
+    }
+
+    #[test]
+    fn test_accessible_layout_is_plain_lines() {
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let mut app = App::default();
+        app.accessible = true;
+        app.mode = AppMode::Viewing;
+        app.add_flight("UA123".to_string(), None, None);
+        app.add_flight("BA285".to_string(), None, None);
+        app.tracked_flights[0].status = FlightStatus::EnRoute;
+        app.tracked_flights[0].altitude_ft = Some(35_000.0);
+        app.tracked_flights[0].departure_delay = Some(15);
+        app.selected_index = Some(0);
+        app.announcements
+            .push("UA123 now En Route at FL350".to_string());
+
+        // Smaller than the full layout allows, which doesn't matter here
+        let mut terminal = Terminal::new(TestBackend::new(50, 12)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let rows: Vec<String> = buffer
+            .content
+            .chunks(50)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect();
+        assert_eq!(rows[0].trim_end(), "Press '/' to add flight");
+        let selected = "UA123, En Route, 35000 ft, 15 min late, selected";
+        assert!(rows.contains(&format!("{:<50}", selected)));
+        assert!(
+            rows.contains(&format!("{:<50}", "BA285, Not Found")),
+            "{rows:?}"
+        );
+        // The newest announcements at the bottom, oldest first
+        assert_eq!(rows[10].trim_end(), "BA285 added, Not Found");
+        assert_eq!(rows[11].trim_end(), "UA123 now En Route at FL350");
+        let screen = rows.concat();
+        assert!(!screen.contains(['│', '─', '┌']) && !screen.contains("too small"));
+        assert!(buffer.content.iter().all(|cell| cell.fg == Color::Reset));
+    }
+
+    #[test]
+    fn test_trip_in_list() {
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let mut app = App::default();
+        app.mode = AppMode::Viewing;
+        for number in ["BA285", "UA79", "NH6"] {
+            app.add_flight(number.to_string(), None, None);
+        }
+        app.tracked_flights[1].status = FlightStatus::Landed;
+        app.tracked_flights[2].status = FlightStatus::EnRoute;
+        app.trips.push(Trip::new(
+            "NYC-TYO",
+            vec!["UA79".to_string(), "NH6".to_string()],
+        ));
+        app.selected_index = Some(2);
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+        let rows: Vec<String> = terminal
+            .backend()
+            .buffer()
+            .content
+            .chunks(100)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect();
+        let list: Vec<&str> = rows[4..8]
+            .iter()
+            .map(|row| row.split('│').nth(1).unwrap())
+            .collect();
+        assert!(list[0].starts_with("    Flight Route Status"), "{list:?}");
+        assert!(
+            list[1].starts_with("▸ Trip: NYC→TYO  leg 2/2 NH6 En R"),
+            "{list:?}"
+        );
+        // Legs are indented under the trip, shifting every flight over
+        assert!(list[2].starts_with("  > NH6          En Route"), "{list:?}");
+        assert!(
+            list[3].starts_with("    BA285        Not Found"),
+            "{list:?}"
+        );
+
+        app.accessible = true;
+        let lines: Vec<String> = accessible_lines(&app).iter().map(Line::to_string).collect();
+        let trip = lines.iter().position(|l| l.starts_with("Trip")).unwrap();
+        assert_eq!(
+            lines[trip..trip + 3],
+            [
+                "Trip NYC→TYO: leg 2/2 NH6 En Route, 1 done, other legs hidden",
+                "NH6, En Route, selected",
+                "BA285, Not Found",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_flight_list_columns() {
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let mut app = App::default();
+        app.mode = AppMode::Viewing;
+        app.add_flight("UA123".to_string(), None, None);
+        app.tracked_flights[0].squawk = Some("7700".to_string());
+        app.tracked_flights[0].altitude_ft = Some(36_000.0);
+        app.selected_index = Some(0);
+        let list = |app: &App| {
+            let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+            terminal.draw(|frame| draw(frame, app)).unwrap();
+            let rows: Vec<String> = terminal
+                .backend()
+                .buffer()
+                .content
+                .chunks(100)
+                .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+                .collect();
+            rows[4..6]
+                .iter()
+                .map(|row| row.split('│').nth(1).unwrap().trim_end().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // The default columns from departure on don't fit the pane
+        assert_eq!(
+            list(&app),
+            ["  Flight Route Status", "> UA123        Not Found"]
+        );
+
+        app.config.table.columns = vec!["flight".into(), "squawk".into(), "altitude".into()];
+        assert_eq!(
+            list(&app),
+            ["  Flight Squawk Altitude", "> UA123  7700   36000 ft"]
+        );
+
+        app.config.table.columns = vec!["status".into(), "flight".into()];
+        assert_eq!(list(&app), ["  Status    Flight", "> Not Found UA123"]);
+    }
+
+    #[test]
+    fn test_route_progress_in_list_and_details() {
+        let mut app = App::default();
+        // Without an altitude, which the accessible line would read out
+        app.tracked_flights.push(Flight {
+            altitude_ft: None,
+            ..fixtures::cruising()
+        });
+        let percent = app.tracked_flights[0].route_progress().unwrap().percent();
+        assert_eq!(
+            accessible_flight_line(&app, 0).to_string(),
+            format!("UA123 SFO→JFK, En Route, {}% flown", percent)
+        );
+        let details: Vec<String> = format_flight_details(&app, &app.tracked_flights[0], usize::MAX)
+            .iter()
+            .map(Line::to_string)
+            .collect();
+        let to_dest = details
+            .iter()
+            .find(|l| l.starts_with("  To dest:"))
+            .unwrap();
+        assert!(
+            to_dest.ends_with(&format!(" of 4152 km, {}% flown", percent)),
+            "{to_dest}"
+        );
+
+        // Only while airborne, and only with both airports and a fix
+        app.tracked_flights[0].status = FlightStatus::Landed;
+        assert!(en_route_progress(&app.tracked_flights[0]).is_none());
+        app.tracked_flights[0].status = FlightStatus::EnRoute;
+        app.tracked_flights[0].origin = None;
+        assert_eq!(
+            accessible_flight_line(&app, 0).to_string(),
+            "UA123, En Route"
+        );
+    }
+
+    #[test]
+    fn test_eta_comparison_in_details() {
+        let app = App::default();
+        let at = |time: &str| Some(DateTime::parse_from_rfc3339(time).unwrap());
+        let mut flight = Flight {
+            latitude: Some(40.64),
+            longitude: Some(-73.78),
+            ground_speed_kts: Some(300.0),
+            position_time: at("2099-07-14T22:31:00Z").map(|t| t.to_utc()),
+            arrival_scheduled: at("2099-07-14T18:40:00-04:00"),
+            arrival_estimated: at("2099-07-14T18:55:00-04:00"),
+            ..fixtures::cruising()
+        };
+        let etas = |flight: &Flight| {
+            format_flight_details(&app, flight, usize::MAX)
+                .into_iter()
+                .find(|l| l.to_string().starts_with("  ETAs:"))
+        };
+
+        // Over the airport: in on the fix, 24 minutes before the estimate
+        let line = etas(&flight).unwrap();
+        assert_eq!(
+            line.to_string(),
+            "  ETAs:       airline estimate 18:55, track-based 18:31 — \
+             possible hold or early arrival"
+        );
+        assert_eq!(line.spans[0].style.fg, Some(Color::Yellow));
+
+        flight.arrival_estimated = at("2099-07-14T18:40:00-04:00");
+        let line = etas(&flight).unwrap();
+        assert_eq!(
+            line.to_string(),
+            "  ETAs:       airline estimate 18:40, track-based 18:31"
+        );
+        assert_eq!(line.spans[0].style.fg, None);
+
+        flight.ground_speed_kts = None;
+        assert!(etas(&flight).is_none());
+    }
+
+    #[test]
+    fn test_destination_weather_in_details() {
+        let mut app = App::default();
+        let now = Utc::now();
+        let taf = format!(
+            "TAF KJFK {} {}/{} 20012KT P6SM BKN030",
+            now.format("%d%H%MZ"),
+            now.format("%d%H"),
+            (now + chrono::Duration::hours(24)).format("%d%H"),
+        );
+        app.weather.insert(
+            "KJFK".to_string(),
+            crate::app::Weather {
+                metar: Some("KJFK 121151Z 19010KT 10SM FEW250 27/18 A3002".to_string()),
+                taf: Some(crate::taf::Taf::parse(&taf, now).unwrap()),
+            },
+        );
+        let eta = now + chrono::Duration::hours(2);
+        let flight = Flight {
+            flight_number: "BA117".to_string(),
+            status: FlightStatus::EnRoute,
+            destination: Some(fixtures::airport("JFK")),
+            arrival_scheduled: Some(eta.fixed_offset()),
+            ..Default::default()
+        };
+        let lines: Vec<String> = format_flight_details(&app, &flight, usize::MAX)
+            .iter()
+            .map(Line::to_string)
+            .collect();
+        let to = lines.iter().position(|l| l.starts_with("  To:")).unwrap();
+        assert_eq!(
+            lines[to + 1],
+            "  METAR: KJFK 121151Z 19010KT 10SM FEW250 27/18 A3002"
+        );
+        assert_eq!(
+            lines[to + 2],
+            format!("  At ETA {}: 20012KT P6SM BKN030", eta.format("%H:%MZ"))
+        );
+    }
+
+    #[test]
+    fn test_truncate_to_width() {
+        assert_eq!(truncate_to_width("Heathrow", 8), "Heathrow");
+        assert_eq!(truncate_to_width("Heathrow", 5), "Heat…");
+        assert_eq!(truncate_to_width("Heathrow", 1), "…");
+        assert_eq!(truncate_to_width("Heathrow", 0), "");
+        // No space left dangling before the ellipsis
+        assert_eq!(truncate_to_width("San Francisco", 5), "San…");
+        // Multibyte: ß and ü are one column each
+        assert_eq!(
+            truncate_to_width("Franz Josef Strauß", 18),
+            "Franz Josef Strauß"
+        );
+        assert_eq!(truncate_to_width("München Franz Josef", 4), "Mün…");
+        // Wide characters take two columns and are never split
+        assert_eq!(truncate_to_width("東京国際空港", 6), "東京…");
+        assert_eq!(truncate_to_width("東京国際空港", 5), "東京…");
+        // Thai combining marks take none and stay with their consonant
+        let thai = "ท่าอากาศยานสุวรรณภูมิ";
+        let cut = truncate_to_width(thai, 4);
+        assert_eq!(cut, "ท่าอ…");
+        assert!(cut.width() <= 4);
+    }
+
+    #[test]
+    fn test_airport_label_fits_width() {
+        let name = "San Francisco International Airport";
+        assert_eq!(
+            airport_label("SFO", name, 80),
+            "SFO San Francisco International Airport"
+        );
+        // Generic words go before the city does
+        assert_eq!(airport_label("SFO", name, 30), "SFO San Francisco");
+        assert_eq!(airport_label("SFO", name, 12), "SFO San Fra…");
+        // The code stays however narrow
+        assert_eq!(airport_label("SFO", name, 3), "SFO");
+        assert_eq!(airport_label("SFO", name, 1), "SFO");
+        assert_eq!(airport_label("SFO", "", 2), "SFO");
+
+        let munich = "Flughafen München Franz Josef Strauß";
+        assert_eq!(airport_label("MUC", munich, 22), "MUC Flughafen München…");
+        for width in 0..50 {
+            let label = airport_label("BKK", "ท่าอากาศยานสุวรรณภูมิ", width);
+            assert!(label.starts_with("BKK"), "{label}");
+            assert!(label.width() <= width.max(3), "{width}: {label}");
+        }
+    }
+
+    #[test]
+    fn test_route_lines_fit_pane_width() {
+        let app = App::default();
+        let flight = Flight {
+            flight_number: "TG910".to_string(),
+            origin: Some(flight::Airport {
+                iata: Some("BKK".to_string()),
+                name: Some("ท่าอากาศยานสุวรรณภูมิ Suvarnabhumi Airport".to_string()),
+                ..Default::default()
+            }),
+            destination: Some(flight::Airport {
+                iata: Some("MUC".to_string()),
+                name: Some("Flughafen München Franz Josef Strauß".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        for width in [20, 30, 44, 60] {
+            let lines: Vec<String> = format_flight_details(&app, &flight, width)
+                .iter()
+                .map(Line::to_string)
+                .collect();
+            let from = lines.iter().find(|l| l.starts_with("  From: BKK")).unwrap();
+            let to = lines.iter().find(|l| l.starts_with("  To:   MUC")).unwrap();
+            assert!(from.width() <= width, "{width}: {from}");
+            assert!(to.width() <= width, "{width}: {to}");
+        }
+        let lines: Vec<String> = format_flight_details(&app, &flight, 60)
+            .iter()
+            .map(Line::to_string)
+            .collect();
+        assert!(lines.contains(&"  To:   MUC Flughafen München Franz Josef Strauß".to_string()));
+    }
+
+    #[test]
+    fn test_settings_lines() {
+        let mut app = App::default();
+        app.settings_cursor = 1;
+        let lines: Vec<String> = settings_lines(&app).iter().map(Line::to_string).collect();
+        assert_eq!(lines.len(), settings::SETTINGS.len() + 2);
+        assert_eq!(lines[0], "  Refresh interval      at most every 10s");
+        assert_eq!(lines[1], "› Schedule refresh      every 30 minutes");
+        assert!(lines
+            .iter()
+            .any(|l| l == "  Theme                 ask the terminal at startup"));
+        assert!(lines.last().unwrap().contains("Esc saves to config.toml"));
+    }
+
+    #[test]
+    fn test_ground_airport_in_details() {
+        let app = App::default();
+        let mut flight = Flight {
+            flight_number: "BA113".to_string(),
+            status: FlightStatus::OnGround,
+            on_ground: true,
+            latitude: Some(48.94),
+            longitude: Some(-54.57),
+            destination: Some(fixtures::airport("JFK")),
+            ..Default::default()
+        };
+        let text = |flight: &Flight| -> Vec<String> {
+            format_flight_details(&app, flight, usize::MAX)
+                .iter()
+                .map(Line::to_string)
+                .collect()
+        };
+        let lines = text(&flight);
+        assert!(
+            lines.contains(&"Status:  On Ground at CYQX Gander Intl".to_string()),
+            "{lines:?}"
+        );
+        assert!(lines
+            .iter()
+            .any(|l| l.contains("Possible diversion, scheduled for JFK")));
+
+        // Where it was meant to be, there is nothing to flag
+        flight.destination = Some(fixtures::airport("YQX"));
+        let lines = text(&flight);
+        assert!(lines.contains(&"Status:  On Ground at CYQX Gander Intl".to_string()));
+        assert!(!lines.iter().any(|l| l.contains("diversion")));
+    }
+
+    #[test]
+    fn test_altitude_profile_needs_two_altitudes() {
+        assert_eq!(altitude_profile(&[], PROFILE_WIDTH), None);
+        assert_eq!(
+            altitude_profile(&track(&[(0, Some(1_000.0))]), PROFILE_WIDTH),
+            None
+        );
+        // Reports without an altitude don't count
+        let sparse = track(&[(0, Some(1_000.0)), (5, None)]);
+        assert_eq!(altitude_profile(&sparse, PROFILE_WIDTH), None);
+        let ground = track(&[(0, Some(0.0)), (5, Some(0.0))]);
+        assert_eq!(altitude_profile(&ground, 3).as_deref(), Some("▁▁▁"));
+    }
+
+    #[test]
+    fn test_panel_tabs() {
+        use ratatui::{backend::TestBackend, Terminal};
+
+        let render = |app: &App| {
+            let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+            terminal.draw(|frame| draw(frame, app)).unwrap();
+            let buffer = terminal.backend().buffer();
+            buffer
+                .content
+                .iter()
+                .map(|cell| cell.symbol())
+                .collect::<String>()
+        };
+        let mut app = App::default();
+        app.mode = AppMode::Viewing;
+        app.add_flight("BA285".to_string(), None, None);
+        app.tracked_flights[0].destination = Some(fixtures::airport("SFO"));
+        app.tracked_flights[0].taxiing_since = DateTime::from_timestamp(1_721_000_520, 0);
+        app.selected_index = Some(0);
+
+        let screen = render(&app);
+        assert!(
+            screen.contains("1 Details │ 2 Map │ 3 Log │ 4 Weather │ 5 Raw"),
+            "{screen}"
+        );
+        assert!(screen.contains("Flight Details"), "{screen}");
+
+        app.show_panel(RightPanel::Log);
+        let screen = render(&app);
+        assert!(screen.contains("Log: BA285"), "{screen}");
+        assert!(screen.contains("23:42 UTC  Taxiing"), "{screen}");
+
+        app.show_panel(RightPanel::Weather);
+        assert!(render(&app).contains("No weather received yet"));
+
+        // Without a flight, every tab but the map falls back to the details
+        app.tracked_flights.clear();
+        app.selected_index = None;
+        assert!(render(&app).contains("Flight Details"));
+    }
 }
@@ -0,0 +1,134 @@
+//! View preferences that survive restarts, kept apart from the session.
+//!
+//! What the keys change about the view (the clock, the list filter, which
+//! panels are open) is saved to `ui_state.json` in the data directory a
+//! moment after it last changed, and read back at startup. `config.toml`
+//! gives the defaults: a setting it also has, like `time_format`, is only
+//! saved once a key has changed it, and from then on the saved choice wins
+//! until the file is deleted.
+//!
+//! Every field has a serde default and unknown fields are ignored, so files
+//! written by older or newer versions still load.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::app::ListFilter;
+use crate::config::{ScreenLayout, TimeFormat};
+use crate::paths::data_dir;
+use crate::persist;
+
+pub const UI_STATE_FILE: &str = "ui_state.json";
+
+/// How long the view has to stay unchanged before it is saved, so cycling
+/// through a toggle writes once.
+pub const SAVE_DELAY: Duration = Duration::from_secs(2);
+
+/// The saved view preferences.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiState {
+    /// Clock chosen with `t`, over the config's `time_format`
+    pub time_format: Option<TimeFormat>,
+    /// Layout chosen with Ctrl+T, over the config's `layout`
+    pub layout: Option<ScreenLayout>,
+    pub filter: ListFilter,
+    pub show_arrived: bool,
+    pub show_map: bool,
+    pub show_profile: bool,
+}
+
+impl UiState {
+    /// Load the saved preferences, or the defaults if there are none.
+    pub fn load() -> Self {
+        Self::path()
+            .map(|path| Self::load_from(&path))
+            .unwrap_or_default()
+    }
+
+    /// Load preferences from `path`. A missing file gives the defaults, as
+    /// does one that doesn't parse, with a warning.
+    pub fn load_from(path: &Path) -> Self {
+        let Ok(contents) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&contents).unwrap_or_else(|e| {
+            warn!(path = %path.display(), error = %e, "Ignoring unreadable UI state");
+            Self::default()
+        })
+    }
+
+    /// Save the preferences in the background.
+    pub fn save(&self) {
+        if let Some(path) = Self::path() {
+            if let Ok(contents) = serde_json::to_string_pretty(self) {
+                persist::write(path, contents);
+            }
+        }
+    }
+
+    fn path() -> Option<PathBuf> {
+        data_dir().map(|p| p.join(UI_STATE_FILE))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let state = UiState {
+            time_format: Some(TimeFormat::TwelveHour),
+            layout: Some(ScreenLayout::Ticker),
+            filter: ListFilter::Problems,
+            show_arrived: true,
+            show_map: true,
+            show_profile: false,
+        };
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(serde_json::from_str::<UiState>(&json).unwrap(), state);
+        assert!(json.contains("\"time_format\":\"12h\""), "{json}");
+        assert!(json.contains("\"filter\":\"problems\""), "{json}");
+        assert!(json.contains("\"layout\":\"ticker\""), "{json}");
+
+        let json = serde_json::to_string(&UiState::default()).unwrap();
+        assert_eq!(
+            serde_json::from_str::<UiState>(&json).unwrap(),
+            UiState::default()
+        );
+    }
+
+    #[test]
+    fn test_old_and_new_files_load() {
+        // Written before the map was remembered
+        let old: UiState = serde_json::from_str(r#"{"filter": "active"}"#).unwrap();
+        assert_eq!(old.filter, ListFilter::Active);
+        assert_eq!(old.time_format, None);
+        assert!(!old.show_map);
+
+        // Written by a version that remembers more
+        let newer: UiState =
+            serde_json::from_str(r#"{"show_map": true, "units": "metric"}"#).unwrap();
+        assert!(newer.show_map);
+    }
+
+    #[test]
+    fn test_unreadable_file_gives_defaults() {
+        let dir = std::env::temp_dir().join(format!("ui-state-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(UI_STATE_FILE);
+        assert_eq!(UiState::load_from(&path), UiState::default());
+
+        fs::write(&path, "{\"show_map\": tru").unwrap();
+        assert_eq!(UiState::load_from(&path), UiState::default());
+
+        fs::write(&path, "{\"show_profile\": true}").unwrap();
+        assert!(UiState::load_from(&path).show_profile);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,235 @@
+//! A shared watchlist file whose flights are tracked while they are listed.
+//!
+//! The file holds one flight per line, in any form the import accepts, with
+//! blank lines and `#` comments ignored. [`Watchlist`] notices when it
+//! changes and [`reconcile`] works out what to add and remove so the
+//! tracked list mirrors it, leaving flights added by hand alone.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::import::{self, FlightQuery};
+
+/// Ticks between checks of the file's modification time.
+pub const POLL_EVERY_TICKS: u32 = 2;
+
+/// The flights listed in a watchlist file's contents. Anything after a `#`
+/// is a comment, so entries can be annotated; lines that aren't a flight
+/// are skipped.
+///
+/// ```
+/// use flight_tracker_tui::watchlist::parse;
+///
+/// let entries = parse("# Morning bank\nBA285  # LHR shuttle\n\nua123 2025-06-01\n");
+/// let numbers: Vec<_> = entries.iter().map(|q| q.flight_number.as_str()).collect();
+/// assert_eq!(numbers, ["BA285", "UA123"]);
+/// ```
+pub fn parse(contents: &str) -> Vec<FlightQuery> {
+    let stripped: String = contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut seen = HashSet::new();
+    import::parse_csv(&stripped)
+        .entries
+        .into_iter()
+        .filter(|q| seen.insert(q.flight_number.clone()))
+        .collect()
+}
+
+/// What to change so the tracked flights mirror the watchlist.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Changes {
+    /// Listed flights that aren't tracked yet
+    pub add: Vec<FlightQuery>,
+    /// Flights added from the watchlist that are no longer listed
+    pub remove: Vec<String>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.add.is_empty() && self.remove.is_empty()
+    }
+}
+
+/// Compare the `listed` flights with the `tracked` ones, given as flight
+/// number and whether it came from the watchlist. A flight tracked by hand
+/// is never removed, and a listed one already tracked by hand isn't added
+/// again.
+///
+/// ```
+/// use flight_tracker_tui::watchlist::{parse, reconcile};
+///
+/// let listed = parse("BA285\nUA123\n");
+/// let changes = reconcile(&listed, [("BA285", true), ("AF7", true), ("LH400", false)]);
+/// assert_eq!(changes.add[0].flight_number, "UA123");
+/// assert_eq!(changes.remove, ["AF7"]);
+/// ```
+pub fn reconcile<'a>(
+    listed: &[FlightQuery],
+    tracked: impl IntoIterator<Item = (&'a str, bool)>,
+) -> Changes {
+    let listed_numbers: HashSet<&str> = listed.iter().map(|q| q.flight_number.as_str()).collect();
+    let mut tracked_numbers = HashSet::new();
+    let mut remove = Vec::new();
+    for (flight_number, from_watchlist) in tracked {
+        tracked_numbers.insert(flight_number);
+        if from_watchlist && !listed_numbers.contains(flight_number) {
+            remove.push(flight_number.to_string());
+        }
+    }
+    let add = listed
+        .iter()
+        .filter(|q| !tracked_numbers.contains(q.flight_number.as_str()))
+        .cloned()
+        .collect();
+    Changes { add, remove }
+}
+
+/// Polls a watchlist file, reading it again whenever it changes.
+#[derive(Debug)]
+pub struct Watchlist {
+    path: PathBuf,
+    /// Modification time and length as last read
+    last_seen: Option<(SystemTime, u64)>,
+    ticks: u32,
+    /// Whether the last check failed, so a missing file is reported once
+    failing: bool,
+}
+
+impl Watchlist {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            last_seen: None,
+            ticks: 0,
+            failing: false,
+        }
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Call once a tick. True every [`POLL_EVERY_TICKS`] ticks, starting
+    /// with the first, when it is time to [`check`](Self::check) the file.
+    pub fn tick(&mut self) -> bool {
+        let due = self.ticks == 0;
+        self.ticks = (self.ticks + 1) % POLL_EVERY_TICKS;
+        due
+    }
+
+    /// The file's flights, if it changed since it was last read. An error
+    /// is returned once, when the file first can't be read; until it can
+    /// again, the flights it listed last stay as they are. This reads the
+    /// file, so an async caller should run it on a blocking thread.
+    pub fn check(&mut self) -> Option<io::Result<Vec<FlightQuery>>> {
+        match self.read_if_changed() {
+            Ok(entries) => {
+                self.failing = false;
+                entries.map(Ok)
+            }
+            Err(e) if !self.failing => {
+                self.failing = true;
+                Some(Err(e))
+            }
+            Err(_) => None,
+        }
+    }
+
+    fn read_if_changed(&mut self) -> io::Result<Option<Vec<FlightQuery>>> {
+        let metadata = fs::metadata(&self.path)?;
+        let seen = (metadata.modified()?, metadata.len());
+        if self.last_seen == Some(seen) {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(&self.path)?;
+        self.last_seen = Some(seen);
+        Ok(Some(parse(&contents)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbers(queries: &[FlightQuery]) -> Vec<&str> {
+        queries.iter().map(|q| q.flight_number.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse_skips_comments_and_junk() {
+        let contents =
+            "\u{feff}flight\r\n# ops\r\nBA285\r\n  \r\nnot a flight\r\nAF7 # late\r\nBA285\r\n";
+        assert_eq!(numbers(&parse(contents)), ["BA285", "AF7"]);
+        assert!(parse("").is_empty());
+        assert!(parse("# nothing yet\n\n").is_empty());
+    }
+
+    #[test]
+    fn test_reconcile_mirrors_the_file() {
+        let listed = parse("BA285\nUA123\nLH400\n");
+        let tracked = [
+            ("BA285", true),
+            ("AF7", true),
+            ("LH400", false),
+            ("DL1", false),
+        ];
+        let changes = reconcile(&listed, tracked);
+
+        assert_eq!(numbers(&changes.add), ["UA123"]);
+        // Manual flights stay, listed or not
+        assert_eq!(changes.remove, ["AF7"]);
+    }
+
+    #[test]
+    fn test_reconcile_unchanged_and_emptied() {
+        let listed = parse("BA285\nUA123\n");
+        assert!(reconcile(&listed, [("BA285", true), ("UA123", true)]).is_empty());
+
+        let changes = reconcile(&[], [("BA285", true), ("UA123", false)]);
+        assert!(changes.add.is_empty());
+        assert_eq!(changes.remove, ["BA285"]);
+    }
+
+    /// What a caller does each tick.
+    fn poll(watchlist: &mut Watchlist) -> Option<io::Result<Vec<FlightQuery>>> {
+        watchlist.tick().then(|| watchlist.check()).flatten()
+    }
+
+    #[test]
+    fn test_poll_reads_changes_and_reports_errors_once() {
+        let path = std::env::temp_dir().join(format!("watchlist-{}.txt", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut watchlist = Watchlist::new(path.clone());
+
+        assert!(matches!(poll(&mut watchlist), Some(Err(_))));
+        for _ in 1..POLL_EVERY_TICKS {
+            assert!(poll(&mut watchlist).is_none());
+        }
+        // Still missing: already reported
+        assert!(poll(&mut watchlist).is_none());
+
+        fs::write(&path, "BA285\n").unwrap();
+        let mut polls = (0..POLL_EVERY_TICKS).map(|_| poll(&mut watchlist));
+        let entries = polls.find_map(|p| p).unwrap().unwrap();
+        assert_eq!(numbers(&entries), ["BA285"]);
+
+        // Unchanged since: nothing new
+        for _ in 0..POLL_EVERY_TICKS * 2 {
+            assert!(poll(&mut watchlist).is_none());
+        }
+
+        // A different length counts as a change even within the mtime's
+        // resolution
+        fs::write(&path, "BA285\nUA123\n").unwrap();
+        let mut polls = (0..POLL_EVERY_TICKS).map(|_| poll(&mut watchlist));
+        let entries = polls.find_map(|p| p).unwrap().unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(numbers(&entries), ["BA285", "UA123"]);
+    }
+}
@@ -0,0 +1,310 @@
+//! Posting flight status changes to a webhook, for Slack, Discord, ntfy
+//! and the like.
+//!
+//! A [`StatusChange`] is posted as JSON with its fields plus a one-line
+//! summary under `text` (Slack) and `content` (Discord). [`Debouncer`]
+//! holds changes back so a flight whose status flaps posts at most once per
+//! [`MIN_INTERVAL`], and the channel gets at most [`MAX_PER_WINDOW`] posts
+//! per [`WINDOW`]. [`Webhook::send`] makes one post with a short timeout
+//! and a couple of retries.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use tracing::debug;
+
+use crate::error::AppError;
+use crate::flight::{Flight, FlightStatus};
+
+/// Shortest gap between two posts about one flight.
+pub const MIN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Most posts for all flights together within [`WINDOW`].
+pub const MAX_PER_WINDOW: usize = 10;
+pub const WINDOW: Duration = Duration::from_secs(60);
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// Tries per post, the first included.
+const ATTEMPTS: u32 = 3;
+/// Wait before the first retry, doubled before each one after.
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// A flight's status moving from one value to another.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatusChange {
+    pub flight_number: String,
+    pub old_status: String,
+    pub new_status: String,
+    /// "SFO→LHR", once both airports are known.
+    pub route: Option<String>,
+    /// Minutes late, from the schedule.
+    pub delay_minutes: Option<i32>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl StatusChange {
+    /// `flight`'s move from `old` to the status it has now.
+    pub fn new(flight: &Flight, old: &FlightStatus, at: DateTime<Utc>) -> Self {
+        Self {
+            flight_number: flight.flight_number.clone(),
+            old_status: old.to_string(),
+            new_status: flight.status.to_string(),
+            route: flight.route_label(),
+            delay_minutes: flight.current_delay(),
+            timestamp: at,
+        }
+    }
+
+    /// One line for a chat message.
+    ///
+    /// ```
+    /// use chrono::Utc;
+    /// use flight_tracker_tui::webhook::StatusChange;
+    ///
+    /// let change = StatusChange {
+    ///     flight_number: "UA123".to_string(),
+    ///     old_status: "En Route".to_string(),
+    ///     new_status: "Landed".to_string(),
+    ///     route: Some("SFO→LHR".to_string()),
+    ///     delay_minutes: Some(15),
+    ///     timestamp: Utc::now(),
+    /// };
+    /// assert_eq!(change.summary(), "UA123 SFO→LHR: En Route → Landed, 15 min late");
+    /// ```
+    pub fn summary(&self) -> String {
+        let mut line = self.flight_number.clone();
+        if let Some(route) = &self.route {
+            line.push_str(&format!(" {}", route));
+        }
+        line.push_str(&format!(": {} → {}", self.old_status, self.new_status));
+        if let Some(delay) = self.delay_minutes.filter(|d| *d > 0) {
+            line.push_str(&format!(", {} min late", delay));
+        }
+        line
+    }
+
+    /// The JSON body posted: every field, plus the summary as `text` and
+    /// `content` so Slack and Discord show it as the message.
+    pub fn payload(&self) -> Value {
+        let mut body = serde_json::to_value(self).unwrap_or_default();
+        let summary = Value::String(self.summary());
+        if let Value::Object(fields) = &mut body {
+            fields.insert("text".to_string(), summary.clone());
+            fields.insert("content".to_string(), summary);
+        }
+        body
+    }
+}
+
+/// Changes waiting to be posted, limited per flight and overall.
+#[derive(Debug, Default)]
+pub struct Debouncer {
+    /// At most one per flight, from its first unposted status to its latest.
+    pending: Vec<StatusChange>,
+    last_sent: HashMap<String, Instant>,
+    /// When each post in the current window went out.
+    recent: VecDeque<Instant>,
+}
+
+impl Debouncer {
+    /// Queue a change, merging it with one still waiting for the flight.
+    pub fn push(&mut self, change: StatusChange) {
+        match self
+            .pending
+            .iter_mut()
+            .find(|p| p.flight_number == change.flight_number)
+        {
+            Some(waiting) => {
+                *waiting = StatusChange {
+                    old_status: std::mem::take(&mut waiting.old_status),
+                    ..change
+                }
+            }
+            None => self.pending.push(change),
+        }
+    }
+
+    /// The changes to post at `now`, oldest first. A flight that posted less
+    /// than [`MIN_INTERVAL`] ago keeps waiting, and one whose status has
+    /// gone back to where it started is dropped.
+    pub fn due(&mut self, now: Instant) -> Vec<StatusChange> {
+        while self
+            .recent
+            .front()
+            .is_some_and(|sent| now.duration_since(*sent) >= WINDOW)
+        {
+            self.recent.pop_front();
+        }
+        self.pending.retain(|c| c.old_status != c.new_status);
+
+        let mut due = Vec::new();
+        let mut i = 0;
+        while i < self.pending.len() && self.recent.len() < MAX_PER_WINDOW {
+            let rested = self
+                .last_sent
+                .get(&self.pending[i].flight_number)
+                .is_none_or(|sent| now.duration_since(*sent) >= MIN_INTERVAL);
+            if !rested {
+                i += 1;
+                continue;
+            }
+            let change = self.pending.remove(i);
+            self.last_sent.insert(change.flight_number.clone(), now);
+            self.recent.push_back(now);
+            due.push(change);
+        }
+        due
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// Where status changes are posted. Clones share one connection pool.
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl Webhook {
+    /// A webhook at `url`, giving up on each post after a few seconds.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client: reqwest::Client::builder()
+                .timeout(TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// POST `change`, retrying a failed post twice.
+    pub async fn send(&self, change: &StatusChange) -> Result<(), AppError> {
+        let body = change.payload();
+        let mut delay = RETRY_DELAY;
+        for attempt in 1.. {
+            let result = self
+                .client
+                .post(&self.url)
+                .json(&body)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            match result {
+                Ok(_) => break,
+                // The URL is often the secret, so it stays out of errors
+                Err(e) if attempt >= ATTEMPTS => return Err(AppError::Network(e.without_url())),
+                Err(e) => {
+                    debug!(
+                        flight = %change.flight_number,
+                        attempt,
+                        error = %e.without_url(),
+                        "Webhook post failed, retrying"
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay *= 2;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(flight: &str, old: &str, new: &str) -> StatusChange {
+        StatusChange {
+            flight_number: flight.to_string(),
+            old_status: old.to_string(),
+            new_status: new.to_string(),
+            route: None,
+            delay_minutes: None,
+            timestamp: DateTime::from_timestamp(1_721_000_000, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_payload() {
+        let flight = Flight {
+            flight_number: "UA123".to_string(),
+            status: FlightStatus::Landed,
+            departure_delay: Some(5),
+            arrival_delay: Some(20),
+            ..Default::default()
+        };
+        let at = DateTime::from_timestamp(1_721_000_000, 0).unwrap();
+        let change = StatusChange::new(&flight, &FlightStatus::EnRoute, at);
+
+        assert_eq!(
+            change.payload(),
+            serde_json::json!({
+                "flight_number": "UA123",
+                "old_status": "En Route",
+                "new_status": "Landed",
+                "route": null,
+                "delay_minutes": 20,
+                "timestamp": "2024-07-14T23:33:20Z",
+                "text": "UA123: En Route → Landed, 20 min late",
+                "content": "UA123: En Route → Landed, 20 min late",
+            })
+        );
+    }
+
+    #[test]
+    fn test_flapping_status_posts_once_then_waits() {
+        let mut debouncer = Debouncer::default();
+        let start = Instant::now();
+
+        debouncer.push(change("UA123", "En Route", "On Ground"));
+        assert_eq!(
+            debouncer.due(start),
+            [change("UA123", "En Route", "On Ground")]
+        );
+
+        // Back and forth within the minute, then settling on Landed
+        debouncer.push(change("UA123", "On Ground", "En Route"));
+        debouncer.push(change("UA123", "En Route", "Landed"));
+        assert!(debouncer.due(start + Duration::from_secs(30)).is_empty());
+        assert_eq!(
+            debouncer.due(start + MIN_INTERVAL),
+            [change("UA123", "On Ground", "Landed")]
+        );
+        assert!(debouncer.is_empty());
+    }
+
+    #[test]
+    fn test_change_undone_before_posting_is_dropped() {
+        let mut debouncer = Debouncer::default();
+        let start = Instant::now();
+        debouncer.push(change("UA123", "Scheduled", "En Route"));
+        debouncer.due(start);
+
+        debouncer.push(change("UA123", "En Route", "On Ground"));
+        debouncer.push(change("UA123", "On Ground", "En Route"));
+        assert!(debouncer.due(start + MIN_INTERVAL).is_empty());
+        assert!(debouncer.is_empty());
+    }
+
+    #[test]
+    fn test_channel_rate_limit() {
+        let mut debouncer = Debouncer::default();
+        let start = Instant::now();
+        for i in 0..MAX_PER_WINDOW + 2 {
+            debouncer.push(change(&format!("FL{}", i), "Scheduled", "En Route"));
+        }
+
+        assert_eq!(debouncer.due(start).len(), MAX_PER_WINDOW);
+        assert!(debouncer.due(start + Duration::from_secs(59)).is_empty());
+        // The rest go out once the window has moved on, oldest first
+        let later = debouncer.due(start + WINDOW);
+        let flights: Vec<_> = later.iter().map(|c| c.flight_number.as_str()).collect();
+        assert_eq!(flights, ["FL10", "FL11"]);
+    }
+}
@@ -0,0 +1,141 @@
+//! Exercises the provider traits end to end using the scripted demo provider.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use flight_tracker_tui::api::{DemoProvider, PositionProvider, ScheduleProvider};
+use flight_tracker_tui::flight::{Airport, Flight, FlightStatus};
+
+fn providers() -> (Arc<dyn PositionProvider>, Arc<dyn ScheduleProvider>) {
+    let demo = DemoProvider::new();
+    (Arc::new(demo.clone()), Arc::new(demo))
+}
+
+#[tokio::test]
+async fn airborne_flight_advances_towards_destination() {
+    let (position, _) = providers();
+
+    let first = position
+        .search_flight("BA178")
+        .await
+        .unwrap()
+        .state
+        .unwrap();
+    let second = position
+        .get_state(&first.icao24)
+        .await
+        .unwrap()
+        .state
+        .unwrap();
+
+    let distance_to_jfk = |lat: Option<f64>, lon: Option<f64>| {
+        Flight {
+            latitude: lat,
+            longitude: lon,
+            destination: Some(Airport {
+                iata: Some("JFK".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+        .distance_to_destination_km()
+        .unwrap()
+    };
+
+    assert_eq!(first.callsign.as_deref(), Some("BAW178"));
+    assert!(
+        distance_to_jfk(second.latitude, second.longitude)
+            < distance_to_jfk(first.latitude, first.longitude)
+    );
+}
+
+#[tokio::test]
+async fn airborne_flight_eventually_lands() {
+    let (position, _) = providers();
+
+    let mut state = position
+        .search_flight("BA178")
+        .await
+        .unwrap()
+        .state
+        .unwrap();
+    for _ in 0..50 {
+        if state.on_ground {
+            break;
+        }
+        state = position
+            .get_state(&state.icao24)
+            .await
+            .unwrap()
+            .state
+            .unwrap();
+    }
+
+    assert!(state.on_ground);
+    assert_eq!(state.baro_altitude, Some(0.0));
+}
+
+#[tokio::test]
+async fn scripted_statuses() {
+    let (position, schedule) = providers();
+    assert!(schedule.is_enabled());
+
+    let status = |data: Option<flight_tracker_tui::api::FlightData>| {
+        FlightStatus::from_api_status(&data.unwrap().flight_status.unwrap())
+    };
+
+    assert_eq!(
+        status(schedule.get_flight("UA900", None).await.unwrap()),
+        FlightStatus::EnRoute
+    );
+    assert_eq!(
+        status(schedule.get_flight("AF007", None).await.unwrap()),
+        FlightStatus::Delayed
+    );
+    assert_eq!(
+        status(schedule.get_flight("LH400", None).await.unwrap()),
+        FlightStatus::Landed
+    );
+
+    // Ground-bound flights have no live position
+    assert!(position
+        .search_flight("AF007")
+        .await
+        .unwrap()
+        .state
+        .is_none());
+    assert!(position
+        .search_flight("LH400")
+        .await
+        .unwrap()
+        .state
+        .is_none());
+}
+
+#[tokio::test]
+async fn unknown_flight_is_not_found() {
+    let (position, schedule) = providers();
+
+    assert!(DemoProvider::flight_numbers().any(|n| n == "ZZ404"));
+    assert!(position
+        .search_flight("ZZ404")
+        .await
+        .unwrap()
+        .state
+        .is_none());
+    assert!(schedule.get_flight("ZZ404", None).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn schedule_refresh_is_unmetered_without_a_quota() {
+    let (_, schedule) = providers();
+
+    let refreshed = schedule
+        .refresh_flight("AF007", None, Duration::from_secs(1800))
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(refreshed.departure.unwrap().delay, Some(45));
+    assert_eq!(schedule.requests_this_month(), None);
+}
@@ -0,0 +1,84 @@
+//! Records provider responses to a file and reads them back as the
+//! responses `--replay` feeds to the app.
+
+use flight_tracker_tui::api::{DemoProvider, PositionProvider, ScheduleProvider};
+use flight_tracker_tui::import::FlightQuery;
+use flight_tracker_tui::recording::{self, Entry, RecordedError, Recorder, Response, REDACTED};
+use flight_tracker_tui::AppError;
+
+const API_KEY: &str = "f3a9c0de5b7741e2";
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("{}-{}.jsonl", name, std::process::id()))
+}
+
+#[tokio::test]
+async fn recorded_responses_read_back_unchanged() {
+    let path = temp_path("recording-round-trip");
+    let _ = std::fs::remove_file(&path);
+    let demo = DemoProvider::new();
+    let snapshot = demo.search_flight("BA178").await.unwrap();
+    let schedule = demo.get_flight("BA178", None).await.unwrap();
+    assert!(snapshot.state.is_some() && schedule.is_some());
+    let search = Response::FlightSearch {
+        query: FlightQuery::from("BA178".to_string()),
+        position: Ok(Some(snapshot.clone())),
+        schedule: schedule.map(Box::new),
+    };
+    let update = Response::FlightUpdate("BA178".to_string(), Ok(snapshot));
+    let url = format!("GET /v1/flights?access_key={}&flight_iata=BA2", API_KEY);
+    let failure = Response::ScheduleUpdate(
+        "BA2".to_string(),
+        Err(RecordedError::from(&AppError::Api(url))),
+    );
+
+    let mut recorder = Recorder::create(&path, vec![API_KEY.to_string()]).unwrap();
+    for response in [&search, &update, &failure] {
+        recorder.record(response).unwrap();
+    }
+    drop(recorder);
+
+    let text = std::fs::read_to_string(&path).unwrap();
+    let entries: Vec<Entry<Response>> = recording::read(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert!(!text.contains(API_KEY), "{text}");
+    assert_eq!(entries.len(), 3);
+    assert!(entries
+        .windows(2)
+        .all(|pair| pair[0].at_ms <= pair[1].at_ms));
+
+    // Everything but the credential reads back as it was recorded
+    let json = |response: &Response| serde_json::to_value(response).unwrap();
+    assert_eq!(json(&entries[0].event), json(&search));
+    assert_eq!(json(&entries[1].event), json(&update));
+
+    let Response::ScheduleUpdate(flight, Err(error)) = &entries[2].event else {
+        panic!("{:?}", entries[2]);
+    };
+    assert_eq!(flight, "BA2");
+    let error = AppError::from(error.clone());
+    assert_eq!(
+        error.user_message(),
+        format!(
+            "Schedule lookup failed: GET /v1/flights?access_key={}&flight_iata=BA2",
+            REDACTED
+        )
+    );
+}
+
+#[test]
+fn bad_line_is_reported_by_number() {
+    let path = temp_path("recording-bad-line");
+    let snapshot = r#"{"Ok":{"time":0,"state":null,"candidates":[]}}"#;
+    let line = format!(
+        r#"{{"at_ms":0,"event":{{"flight_update":["BA2",{}]}}}}"#,
+        snapshot
+    );
+    std::fs::write(&path, format!("{}\n\nnot json\n", line)).unwrap();
+    let result = recording::read::<Response>(&path);
+    let _ = std::fs::remove_file(&path);
+
+    let error = result.unwrap_err().to_string();
+    assert!(error.contains("line 3:"), "{error}");
+}